@@ -1,10 +1,12 @@
 use std::io::{self, Read, Write};
 
 use bytes::{Buf, BufMut};
-use prost::Message;
+use prost::{Message, ReflectMessage};
+use prost_types::{json, text_format, TypeRegistry};
 
 use protobuf::conformance::{
-    conformance_request, conformance_response, ConformanceRequest, ConformanceResponse, WireFormat,
+    conformance_request, conformance_response, ConformanceRequest, ConformanceResponse,
+    TestCategory, WireFormat,
 };
 use protobuf::test_messages::proto2::TestAllTypesProto2;
 use protobuf::test_messages::proto3::TestAllTypesProto3;
@@ -49,68 +51,111 @@ fn main() -> io::Result<()> {
 }
 
 fn handle_request(request: ConformanceRequest) -> conformance_response::Result {
-    match request.requested_output_format() {
+    let output = request.requested_output_format();
+    match output {
         WireFormat::Unspecified => {
             return conformance_response::Result::ParseError(
                 "output format unspecified".to_string(),
             );
         }
-        WireFormat::Json => {
-            return conformance_response::Result::Skipped(
-                "JSON output is not supported".to_string(),
-            );
-        }
         WireFormat::Jspb => {
             return conformance_response::Result::Skipped(
                 "JSPB output is not supported".to_string(),
             );
         }
-        WireFormat::TextFormat => {
-            return conformance_response::Result::Skipped(
-                "TEXT_FORMAT output is not supported".to_string(),
-            );
-        }
-        WireFormat::Protobuf => (),
+        WireFormat::Protobuf | WireFormat::Json | WireFormat::TextFormat => (),
     };
 
-    let buf = match request.payload {
-        None => return conformance_response::Result::ParseError("no payload".to_string()),
-        Some(conformance_request::Payload::JsonPayload(_)) => {
-            return conformance_response::Result::Skipped(
-                "JSON input is not supported".to_string(),
-            );
+    match &*request.message_type {
+        "protobuf_test_messages.proto2.TestAllTypesProto2" => {
+            handle_typed::<TestAllTypesProto2>(&request, output)
+        }
+        "protobuf_test_messages.proto3.TestAllTypesProto3" => {
+            handle_typed::<TestAllTypesProto3>(&request, output)
         }
+        _ => conformance_response::Result::ParseError(format!(
+            "unknown message type: {}",
+            request.message_type
+        )),
+    }
+}
+
+fn handle_typed<M>(request: &ConformanceRequest, output: WireFormat) -> conformance_response::Result
+where
+    M: ReflectMessage + Default,
+{
+    let message = match request.payload {
+        None => return conformance_response::Result::ParseError("no payload".to_string()),
         Some(conformance_request::Payload::JspbPayload(_)) => {
             return conformance_response::Result::Skipped(
-                "JSON input is not supported".to_string(),
+                "JSPB input is not supported".to_string(),
             );
         }
-        Some(conformance_request::Payload::TextPayload(_)) => {
-            return conformance_response::Result::Skipped(
-                "JSON input is not supported".to_string(),
-            );
+        Some(conformance_request::Payload::TextPayload(ref text)) => {
+            let options = text_format::ParseOptions::new()
+                .message_type(TestAllTypesProto2::type_descriptor())
+                .message_type(TestAllTypesProto3::type_descriptor());
+            match text_format::from_str_with_options::<M>(text, &options) {
+                Ok(message) => message,
+                Err(error) => return conformance_response::Result::ParseError(error.to_string()),
+            }
         }
-        Some(conformance_request::Payload::ProtobufPayload(buf)) => buf,
-    };
-
-    let roundtrip = match &*request.message_type {
-        "protobuf_test_messages.proto2.TestAllTypesProto2" => roundtrip::<TestAllTypesProto2>(&buf),
-        "protobuf_test_messages.proto3.TestAllTypesProto3" => roundtrip::<TestAllTypesProto3>(&buf),
-        _ => {
-            return conformance_response::Result::ParseError(format!(
-                "unknown message type: {}",
-                request.message_type
-            ));
+        Some(conformance_request::Payload::ProtobufPayload(ref buf)) => {
+            if output == WireFormat::Protobuf {
+                return match roundtrip::<M>(buf) {
+                    RoundtripResult::Ok(buf) => conformance_response::Result::ProtobufPayload(buf),
+                    RoundtripResult::DecodeError(error) => {
+                        conformance_response::Result::ParseError(error.to_string())
+                    }
+                    RoundtripResult::Error(error) => {
+                        conformance_response::Result::RuntimeError(error.to_string())
+                    }
+                };
+            }
+            match M::decode(&**buf) {
+                Ok(message) => message,
+                Err(error) => return conformance_response::Result::ParseError(error.to_string()),
+            }
+        }
+        Some(conformance_request::Payload::JsonPayload(ref json)) => {
+            let options = json::ParseOptions::new()
+                .ignore_unknown_fields(
+                    request.test_category() == TestCategory::JsonIgnoreUnknownParsingTest,
+                )
+                .type_registry(&type_registry());
+            match json::from_str_with_options::<M>(json, &options) {
+                Ok(message) => message,
+                Err(error) => return conformance_response::Result::ParseError(error.to_string()),
+            }
         }
     };
 
-    match roundtrip {
-        RoundtripResult::Ok(buf) => conformance_response::Result::ProtobufPayload(buf),
-        RoundtripResult::DecodeError(error) => {
-            conformance_response::Result::ParseError(error.to_string())
+    match output {
+        WireFormat::Json => {
+            let options = json::SerializeOptions::new().type_registry(&type_registry());
+            match json::to_string_with_options(&message, &options) {
+                Ok(json) => conformance_response::Result::JsonPayload(json),
+                Err(error) => conformance_response::Result::SerializeError(error.to_string()),
+            }
         }
-        RoundtripResult::Error(error) => {
-            conformance_response::Result::RuntimeError(error.to_string())
+        WireFormat::TextFormat => {
+            let options = text_format::PrintOptions::new()
+                .message_type(TestAllTypesProto2::type_descriptor())
+                .message_type(TestAllTypesProto3::type_descriptor());
+            conformance_response::Result::TextPayload(text_format::to_string_with_options(
+                &message, &options,
+            ))
         }
+        _ => conformance_response::Result::ProtobufPayload(message.encode_to_vec()),
     }
 }
+
+/// Returns a registry of the message types which `Any` values in the test messages may contain.
+fn type_registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::new();
+    registry
+        .register_well_known_types()
+        .register_descriptor(TestAllTypesProto2::type_descriptor())
+        .register_descriptor(TestAllTypesProto3::type_descriptor());
+    registry
+}
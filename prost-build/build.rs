@@ -10,6 +10,9 @@
 //!         bundled source code.
 //!     3. Otherwise, it will attempt to execute from the `PATH` and fail if it does not exist.
 //!
+//! If `PROTOC_NO_VENDOR` is enabled and `protoc` can't be found, `protoc` is assumed to be in the
+//! `PATH` when it's invoked, so that crates which only use the native parser can still be built.
+//!
 //! The following locations are checked for the Protobuf include directory in decreasing priority:
 //!
 //!     1. The `PROTOC_INCLUDE` environment variable.
@@ -100,13 +103,15 @@ fn protoc() -> Option<PathBuf> {
 }
 
 fn main() {
-    let protoc = protoc().expect(
-        "Failed to find or build the protoc binary. The PROTOC environment \
+    let protoc = protoc()
+        .or_else(|| env::var_os("PROTOC_NO_VENDOR").map(|_| PathBuf::from("protoc")))
+        .expect(
+            "Failed to find or build the protoc binary. The PROTOC environment \
     is not set, `protoc` is not in PATH or you are missing the requirements to compile protobuf \
     from source. \n \
     Check out the `prost-build` README for instructions on the requirements: \
     https://github.com/tokio-rs/prost#generated-code",
-    );
+        );
 
     let protoc_include = env_protoc_include().unwrap_or_else(bundled_protoc_include);
 
@@ -6,17 +6,18 @@ use std::iter;
 use itertools::{Either, Itertools};
 use log::debug;
 use multimap::MultiMap;
+use prost_types::descriptor_proto::ExtensionRange;
 use prost_types::field_descriptor_proto::{Label, Type};
 use prost_types::source_code_info::Location;
 use prost_types::{
-    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FieldOptions, FileDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto,
-    SourceCodeInfo,
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    FieldDescriptorProto, FieldOptions, FileDescriptorProto, MessageOptions, OneofDescriptorProto,
+    ServiceDescriptorProto, SourceCodeInfo,
 };
 
 use crate::ast::{Comments, Method, Service};
 use crate::extern_paths::ExternPaths;
-use crate::ident::{to_snake, to_upper_camel};
+use crate::ident::{to_json_name, to_snake, to_upper_camel};
 use crate::message_graph::MessageGraph;
 use crate::{BytesType, Config, MapType};
 
@@ -29,6 +30,8 @@ enum Syntax {
 pub struct CodeGenerator<'a> {
     config: &'a mut Config,
     package: String,
+    /// The package of the file, which unlike `package` does not include enclosing messages.
+    file_package: String,
     source_info: SourceCodeInfo,
     syntax: Syntax,
     message_graph: &'a MessageGraph,
@@ -36,6 +39,8 @@ pub struct CodeGenerator<'a> {
     depth: u8,
     path: Vec<i32>,
     buf: &'a mut String,
+    /// The paths of the messages to register with the type registry, relative to the module.
+    registered_types: Vec<String>,
 }
 
 fn push_indent(buf: &mut String, depth: u8) {
@@ -44,13 +49,17 @@ fn push_indent(buf: &mut String, depth: u8) {
     }
 }
 impl<'a> CodeGenerator<'a> {
+    /// Generates the code for `file`, appending it to `buf`.
+    ///
+    /// Returns the paths of the messages which should be registered with the type registry,
+    /// relative to the module.
     pub fn generate(
         config: &mut Config,
         message_graph: &MessageGraph,
         extern_paths: &ExternPaths,
         file: FileDescriptorProto,
         buf: &mut String,
-    ) {
+    ) -> Vec<String> {
         let mut source_info = file
             .source_code_info
             .expect("no source code info in request");
@@ -68,9 +77,11 @@ impl<'a> CodeGenerator<'a> {
             Some(s) => panic!("unknown syntax: {}", s),
         };
 
+        let package = file.package.unwrap_or_default();
         let mut code_gen = CodeGenerator {
             config,
-            package: file.package.unwrap_or_default(),
+            file_package: package.clone(),
+            package,
             source_info,
             syntax,
             message_graph,
@@ -78,6 +89,7 @@ impl<'a> CodeGenerator<'a> {
             depth: 0,
             path: Vec::new(),
             buf,
+            registered_types: Vec::new(),
         };
 
         debug!(
@@ -102,6 +114,14 @@ impl<'a> CodeGenerator<'a> {
         }
         code_gen.path.pop();
 
+        code_gen.path.push(7);
+        for (idx, field) in file.extension.into_iter().enumerate() {
+            code_gen.path.push(idx as i32);
+            code_gen.append_extension(field);
+            code_gen.path.pop();
+        }
+        code_gen.path.pop();
+
         if code_gen.config.service_generator.is_some() {
             code_gen.path.push(6);
             for (idx, service) in file.service.into_iter().enumerate() {
@@ -116,6 +136,8 @@ impl<'a> CodeGenerator<'a> {
 
             code_gen.path.pop();
         }
+
+        code_gen.registered_types
     }
 
     fn append_message(&mut self, message: DescriptorProto) {
@@ -182,12 +204,30 @@ impl<'a> CodeGenerator<'a> {
 
         self.append_doc(&fq_message_name, None);
         self.append_type_attributes(&fq_message_name);
-        self.push_indent();
-        self.buf
-            .push_str("#[derive(Clone, PartialEq, ::prost::Message)]\n");
+        if let Some(generator) = self.config.attribute_generator.as_mut() {
+            let attributes = generator.message_attributes(
+                &fq_message_name,
+                message
+                    .options
+                    .as_ref()
+                    .unwrap_or(&MessageOptions::default()),
+            );
+            self.append_attributes(attributes);
+        }
+        self.push_derive(&fq_message_name, "::prost::Message");
+        if self.reflect(&fq_message_name) {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "#[prost(full_name=\"{}\")]\n",
+                &fq_message_name[1..]
+            ));
+        }
         self.push_indent();
         self.buf.push_str("pub struct ");
         self.buf.push_str(&to_upper_camel(&message_name));
+        if self.borrowed(&fq_message_name) {
+            self.buf.push_str("<'a>");
+        }
         self.buf.push_str(" {\n");
 
         self.depth += 1;
@@ -223,11 +263,49 @@ impl<'a> CodeGenerator<'a> {
         }
         self.path.pop();
 
+        if !message.extension_range.is_empty() {
+            self.append_extension_set(&message.extension_range);
+        }
+
+        if self
+            .config
+            .unknown_fields
+            .get(&fq_message_name)
+            .next()
+            .is_some()
+        {
+            self.append_unknown_fields();
+        }
+
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n");
 
-        if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
+        if !self.borrowed(&fq_message_name) {
+            self.append_name_impl(&message_name, &fq_message_name);
+
+            if self
+                .config
+                .type_registry
+                .get(&fq_message_name)
+                .next()
+                .is_some()
+            {
+                let mut path = self.package[self.file_package.len()..]
+                    .split('.')
+                    .filter(|module| !module.is_empty())
+                    .map(to_snake)
+                    .collect::<Vec<_>>();
+                path.push(to_upper_camel(&message_name));
+                self.registered_types.push(path.join("::"));
+            }
+        }
+
+        if !message.enum_type.is_empty()
+            || !nested_types.is_empty()
+            || !oneof_fields.is_empty()
+            || !message.extension.is_empty()
+        {
             self.push_mod(&message_name);
             self.path.push(3);
             for (nested_type, idx) in nested_types {
@@ -255,10 +333,45 @@ impl<'a> CodeGenerator<'a> {
                 self.append_oneof(&fq_message_name, oneof, idx, fields);
             }
 
+            self.path.push(6);
+            for (idx, field) in message.extension.into_iter().enumerate() {
+                self.path.push(idx as i32);
+                self.append_extension(field);
+                self.path.pop();
+            }
+            self.path.pop();
+
             self.pop_mod();
         }
     }
 
+    fn append_name_impl(&mut self, message_name: &str, fq_message_name: &str) {
+        // Nested messages of files without a package have names starting with `..`.
+        let name = fq_message_name.trim_start_matches('.');
+        let name = if self.file_package.is_empty() {
+            name
+        } else {
+            &name[self.file_package.len() + 1..]
+        };
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::prost::Name for {} {{\n",
+            to_upper_camel(message_name)
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("const NAME: &'static str = \"{}\";\n", name));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "const PACKAGE: &'static str = \"{}\";\n",
+            self.file_package
+        ));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
     fn append_type_attributes(&mut self, fq_message_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self.config.type_attributes.get(fq_message_name) {
@@ -268,6 +381,30 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
+    /// Appends the attributes returned by the attribute generator for a message field.
+    fn append_generated_field_attributes(
+        &mut self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+    ) {
+        if let Some(generator) = self.config.attribute_generator.as_mut() {
+            let attributes = generator.field_attributes(
+                fq_message_name,
+                field.name(),
+                field.options.as_ref().unwrap_or(&FieldOptions::default()),
+            );
+            self.append_attributes(attributes);
+        }
+    }
+
+    fn append_attributes(&mut self, attributes: Vec<String>) {
+        for attribute in attributes {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str(&attribute);
+            self.buf.push('\n');
+        }
+    }
+
     fn append_field_attributes(&mut self, fq_message_name: &str, field_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self
@@ -286,9 +423,51 @@ impl<'a> CodeGenerator<'a> {
         let repeated = field.label == Some(Label::Repeated as i32);
         let deprecated = self.deprecated(&field);
         let optional = self.optional(&field);
-        let ty = self.resolve_type(&field, fq_message_name);
+        let borrowed = self.borrowed(fq_message_name);
+        let borrowed_type = self.borrowed_type(&field);
+        if borrowed_type && (!borrowed || type_ == Type::Group) {
+            panic!(
+                "field {}.{} has borrowed message type {}, which may only be used by non-group \
+                 fields of borrowed messages",
+                fq_message_name,
+                field.name(),
+                field.type_name()
+            );
+        }
+        let custom_type = self.custom_type(fq_message_name, &field);
+        let field_type = self
+            .config
+            .field_types
+            .get_first_field(fq_message_name, field.name())
+            .cloned();
+        let lazy = type_ == Type::Message
+            && !borrowed_type
+            && field_type.is_none()
+            && self
+                .config
+                .lazy
+                .get_first_field(fq_message_name, field.name())
+                .is_some();
+        let ty = match type_ {
+            _ if field_type.is_some() => field_type.as_ref().unwrap().0.clone(),
+            Type::Message if lazy => format!(
+                "::prost::Lazy<{}>",
+                self.resolve_type(&field, fq_message_name)
+            ),
+            Type::String if borrowed => String::from("&'a str"),
+            Type::Bytes if borrowed => String::from("&'a [u8]"),
+            Type::Message if borrowed_type => {
+                format!("{}<'a>", self.resolve_type(&field, fq_message_name))
+            }
+            _ => custom_type
+                .clone()
+                .unwrap_or_else(|| self.resolve_type(&field, fq_message_name)),
+        };
 
+        // Lazy fields box the decoded message themselves.
         let boxed = !repeated
+            && field_type.is_none()
+            && !lazy
             && (type_ == Type::Message || type_ == Type::Group)
             && self
                 .message_graph
@@ -313,7 +492,13 @@ impl<'a> CodeGenerator<'a> {
         let type_tag = self.field_type_tag(&field);
         self.buf.push_str(&type_tag);
 
-        if type_ == Type::Bytes {
+        if type_ == Type::String && borrowed {
+            self.buf.push_str("=\"str\"");
+        } else if type_ == Type::Bytes && borrowed {
+            self.buf.push_str("=\"slice\"");
+        } else if let Some(custom_type) = custom_type {
+            self.buf.push_str(&format!("={:?}", custom_type));
+        } else if type_ == Type::Bytes {
             let bytes_type = self
                 .config
                 .bytes_type
@@ -347,6 +532,12 @@ impl<'a> CodeGenerator<'a> {
         if boxed {
             self.buf.push_str(", boxed");
         }
+        if borrowed_type {
+            self.buf.push_str(", borrowed");
+        }
+        if lazy {
+            self.buf.push_str(", lazy");
+        }
         self.buf.push_str(", tag=\"");
         self.buf.push_str(&field.number().to_string());
 
@@ -380,8 +571,15 @@ impl<'a> CodeGenerator<'a> {
             }
         }
 
-        self.buf.push_str("\")]\n");
+        self.buf.push('"');
+        if let Some((_, ref conversion)) = field_type {
+            self.buf.push_str(&format!(", convert={:?}", conversion));
+        }
+        let name = self.field_name_attrs(fq_message_name, &field);
+        self.buf.push_str(&name);
+        self.buf.push_str(")]\n");
         self.append_field_attributes(fq_message_name, field.name());
+        self.append_generated_field_attributes(fq_message_name, &field);
         self.push_indent();
         self.buf.push_str("pub ");
         self.buf.push_str(&to_snake(field.name()));
@@ -411,6 +609,14 @@ impl<'a> CodeGenerator<'a> {
         key: &FieldDescriptorProto,
         value: &FieldDescriptorProto,
     ) {
+        if self.borrowed_type(value) {
+            panic!(
+                "map field {}.{} has borrowed message value type {}, which is not supported",
+                fq_message_name,
+                field.name(),
+                value.type_name()
+            );
+        }
         let key_ty = self.resolve_type(key, fq_message_name);
         let value_ty = self.resolve_type(value, fq_message_name);
 
@@ -434,13 +640,15 @@ impl<'a> CodeGenerator<'a> {
         let value_tag = self.map_value_type_tag(value);
 
         self.buf.push_str(&format!(
-            "#[prost({}=\"{}, {}\", tag=\"{}\")]\n",
+            "#[prost({}=\"{}, {}\", tag=\"{}\"{})]\n",
             map_type.annotation(),
             key_tag,
             value_tag,
-            field.number()
+            field.number(),
+            self.field_name_attrs(fq_message_name, &field),
         ));
         self.append_field_attributes(fq_message_name, field.name());
+        self.append_generated_field_attributes(fq_message_name, &field);
         self.push_indent();
         self.buf.push_str(&format!(
             "pub {}: {}<{}, {}>,\n",
@@ -466,12 +674,13 @@ impl<'a> CodeGenerator<'a> {
         self.append_doc(fq_message_name, None);
         self.push_indent();
         self.buf.push_str(&format!(
-            "#[prost(oneof=\"{}\", tags=\"{}\")]\n",
+            "#[prost(oneof=\"{}\", tags=\"{}\"{})]\n",
             name,
             fields
                 .iter()
                 .map(|&(ref field, _)| field.number())
-                .join(", ")
+                .join(", "),
+            self.reflect_name(fq_message_name, oneof.name()),
         ));
         self.append_field_attributes(fq_message_name, oneof.name());
         self.push_indent();
@@ -482,6 +691,99 @@ impl<'a> CodeGenerator<'a> {
         ));
     }
 
+    fn append_unknown_fields(&mut self) {
+        self.push_indent();
+        self.buf.push_str("#[prost(unknown_fields)]\n");
+        self.push_indent();
+        self.buf
+            .push_str("pub _unknown_fields: ::prost::UnknownFields,\n");
+    }
+
+    fn append_extension_set(&mut self, ranges: &[ExtensionRange]) {
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "#[prost(extensions=\"{}\")]\n",
+            ranges
+                .iter()
+                .map(|range| format!("{}..{}", range.start(), range.end()))
+                .join(", ")
+        ));
+        self.push_indent();
+        self.buf
+            .push_str("pub _extensions: ::prost::ExtensionSet,\n");
+    }
+
+    fn append_extension(&mut self, field: FieldDescriptorProto) {
+        let fq_name = format!(
+            "{}{}.{}",
+            if self.package.is_empty() { "" } else { "." },
+            self.package,
+            field.name()
+        );
+        debug!("  extension: {:?}", fq_name);
+
+        let extension_type = match field.r#type() {
+            Type::Float => Cow::Borrowed("Float"),
+            Type::Double => Cow::Borrowed("Double"),
+            Type::Int32 => Cow::Borrowed("Int32"),
+            Type::Int64 => Cow::Borrowed("Int64"),
+            Type::Uint32 => Cow::Borrowed("Uint32"),
+            Type::Uint64 => Cow::Borrowed("Uint64"),
+            Type::Sint32 => Cow::Borrowed("Sint32"),
+            Type::Sint64 => Cow::Borrowed("Sint64"),
+            Type::Fixed32 => Cow::Borrowed("Fixed32"),
+            Type::Fixed64 => Cow::Borrowed("Fixed64"),
+            Type::Sfixed32 => Cow::Borrowed("Sfixed32"),
+            Type::Sfixed64 => Cow::Borrowed("Sfixed64"),
+            Type::Bool => Cow::Borrowed("Bool"),
+            Type::String => Cow::Borrowed("String"),
+            Type::Bytes => Cow::Borrowed("Bytes"),
+            Type::Enum => Cow::Borrowed("Enumeration"),
+            Type::Message => Cow::Owned(format!(
+                "Message<{}>",
+                self.resolve_ident(field.type_name())
+            )),
+            Type::Group => Cow::Owned(format!("Group<{}>", self.resolve_ident(field.type_name()))),
+        };
+        let extension_type = if field.label() != Label::Repeated {
+            format!("::prost::extension::{}", extension_type)
+        } else if can_pack(&field)
+            && field
+                .options
+                .as_ref()
+                .map_or(self.syntax == Syntax::Proto3, |options| options.packed())
+        {
+            format!(
+                "::prost::extension::Packed<::prost::extension::{}>",
+                extension_type
+            )
+        } else {
+            format!(
+                "::prost::extension::Repeated<::prost::extension::{}>",
+                extension_type
+            )
+        };
+
+        self.append_doc(&fq_name, None);
+        if self.deprecated(&field) {
+            self.push_indent();
+            self.buf.push_str("#[deprecated]\n");
+        }
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const {}: ::prost::Extension<{}, {}> =\n",
+            to_snake(field.name()).to_uppercase(),
+            self.resolve_ident(field.extendee()),
+            extension_type,
+        ));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "    ::prost::Extension::new(\"{}\", {});\n",
+            &fq_name[1..],
+            field.number()
+        ));
+    }
+
     fn append_oneof(
         &mut self,
         fq_message_name: &str,
@@ -497,9 +799,7 @@ impl<'a> CodeGenerator<'a> {
 
         let oneof_name = format!("{}.{}", fq_message_name, oneof.name());
         self.append_type_attributes(&oneof_name);
-        self.push_indent();
-        self.buf
-            .push_str("#[derive(Clone, PartialEq, ::prost::Oneof)]\n");
+        self.push_derive(fq_message_name, "::prost::Oneof");
         self.push_indent();
         self.buf.push_str("pub enum ");
         self.buf.push_str(&to_upper_camel(oneof.name()));
@@ -509,22 +809,38 @@ impl<'a> CodeGenerator<'a> {
         self.depth += 1;
         for (field, idx) in fields {
             let type_ = field.r#type();
+            if self.borrowed_type(&field) {
+                panic!(
+                    "oneof field {}.{} has borrowed message type {}, which is not supported",
+                    oneof_name,
+                    field.name(),
+                    field.type_name()
+                );
+            }
 
             self.path.push(idx as i32);
             self.append_doc(fq_message_name, Some(field.name()));
             self.path.pop();
 
             self.push_indent();
-            let ty_tag = self.field_type_tag(&field);
+            let custom_type = self.custom_type(fq_message_name, &field);
+            let ty_tag = match custom_type {
+                Some(ref custom_type) => {
+                    Cow::Owned(format!("{}={:?}", self.field_type_tag(&field), custom_type))
+                }
+                None => self.field_type_tag(&field),
+            };
             self.buf.push_str(&format!(
-                "#[prost({}, tag=\"{}\")]\n",
+                "#[prost({}, tag=\"{}\"{})]\n",
                 ty_tag,
-                field.number()
+                field.number(),
+                self.field_name_attrs(fq_message_name, &field),
             ));
             self.append_field_attributes(&oneof_name, field.name());
+            self.append_generated_field_attributes(fq_message_name, &field);
 
             self.push_indent();
-            let ty = self.resolve_type(&field, fq_message_name);
+            let ty = custom_type.unwrap_or_else(|| self.resolve_type(&field, fq_message_name));
 
             let boxed = (type_ == Type::Message || type_ == Type::Group)
                 && self
@@ -603,12 +919,27 @@ impl<'a> CodeGenerator<'a> {
 
         self.append_doc(&fq_proto_enum_name, None);
         self.append_type_attributes(&fq_proto_enum_name);
+        if let Some(generator) = self.config.attribute_generator.as_mut() {
+            let attributes = generator.enum_attributes(
+                &fq_proto_enum_name,
+                desc.options.as_ref().unwrap_or(&EnumOptions::default()),
+            );
+            self.append_attributes(attributes);
+        }
         self.push_indent();
         self.buf.push_str(
             "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]\n",
         );
         self.push_indent();
         self.buf.push_str("#[repr(i32)]\n");
+        let reflect = self.reflect(&fq_proto_enum_name);
+        if reflect {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "#[prost(reflect, full_name=\"{}\")]\n",
+                fq_proto_enum_name.trim_start_matches('.')
+            ));
+        }
         self.push_indent();
         self.buf.push_str("pub enum ");
         self.buf.push_str(&enum_name);
@@ -624,6 +955,20 @@ impl<'a> CodeGenerator<'a> {
 
             self.append_doc(&fq_proto_enum_name, Some(variant.proto_name));
             self.append_field_attributes(&fq_proto_enum_name, variant.proto_name);
+            if let Some(generator) = self.config.attribute_generator.as_mut() {
+                let options = enum_values[variant.path_idx].options.as_ref();
+                let attributes = generator.enum_value_attributes(
+                    &fq_proto_enum_name,
+                    variant.proto_name,
+                    options.unwrap_or(&EnumValueOptions::default()),
+                );
+                self.append_attributes(attributes);
+            }
+            if reflect {
+                self.push_indent();
+                self.buf
+                    .push_str(&format!("#[prost(name=\"{}\")]\n", variant.proto_name));
+            }
             self.push_indent();
             self.buf.push_str(&variant.generated_variant_name);
             self.buf.push_str(" = ");
@@ -779,6 +1124,80 @@ impl<'a> CodeGenerator<'a> {
         self.buf.push_str("}\n");
     }
 
+    /// Returns `true` if the message borrows from the buffer it is decoded from.
+    fn borrowed(&self, fq_message_name: &str) -> bool {
+        self.message_graph.is_borrowed(fq_message_name)
+    }
+
+    /// Returns `true` if the message should implement `ReflectMessage`.
+    fn reflect(&self, fq_message_name: &str) -> bool {
+        self.config.reflect.get(fq_message_name).next().is_some() && !self.borrowed(fq_message_name)
+    }
+
+    /// Returns the `name` field attribute used by `ReflectMessage`, if the message implements it.
+    fn reflect_name(&self, fq_message_name: &str, field_name: &str) -> String {
+        if self.reflect(fq_message_name) {
+            format!(", name=\"{}\"", field_name)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns `true` if the message should implement `DiffMessage`.
+    fn diff(&self, fq_message_name: &str) -> bool {
+        self.config.diff.get(fq_message_name).next().is_some() && !self.borrowed(fq_message_name)
+    }
+
+    /// Pushes the derive attribute of a message or oneof, which derives `derive` along with
+    /// `ReflectMessage` and `DiffMessage` if the message implements them.
+    fn push_derive(&mut self, fq_message_name: &str, derive: &str) {
+        let mut derives = format!("Clone, PartialEq, {}", derive);
+        if self.reflect(fq_message_name) {
+            derives.push_str(", ::prost::ReflectMessage");
+        }
+        if self.diff(fq_message_name) {
+            derives.push_str(", ::prost::DiffMessage");
+        }
+        self.push_indent();
+        self.buf.push_str(&format!("#[derive({})]\n", derives));
+    }
+
+    /// Returns the `name` field attribute used by `ReflectMessage` and `DiffMessage`, if the
+    /// message implements either, and the `json_name` attribute used by `ReflectMessage` if the
+    /// message implements it and the JSON name differs from the default.
+    fn field_name_attrs(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> String {
+        if !self.reflect(fq_message_name) && !self.diff(fq_message_name) {
+            return String::new();
+        }
+        let mut name = format!(", name=\"{}\"", field.name());
+        if self.reflect(fq_message_name) {
+            if let Some(json_name) = field.json_name.as_deref() {
+                if json_name != to_json_name(field.name()) {
+                    name.push_str(&format!(", json_name=\"{}\"", json_name));
+                }
+            }
+        }
+        name
+    }
+
+    /// Returns `true` if the field's type is a message which borrows from the buffer it is
+    /// decoded from.
+    fn borrowed_type(&self, field: &FieldDescriptorProto) -> bool {
+        matches!(field.r#type(), Type::Message | Type::Group) && self.borrowed(field.type_name())
+    }
+
+    /// Returns the custom Rust type configured for a `string` or `bytes` field, if any.
+    fn custom_type(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> Option<String> {
+        let types = match field.r#type() {
+            Type::String => &self.config.custom_string_type,
+            Type::Bytes => &self.config.custom_bytes_type,
+            _ => return None,
+        };
+        types
+            .get_first_field(fq_message_name, field.name())
+            .cloned()
+    }
+
     fn resolve_type(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> String {
         match field.r#type() {
             Type::Float => String::from("f32"),
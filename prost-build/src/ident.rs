@@ -40,6 +40,23 @@ pub fn to_upper_camel(s: &str) -> String {
     ident
 }
 
+/// Converts a `.proto` field name to its default JSON name, following `protoc`.
+pub fn to_json_name(s: &str) -> String {
+    let mut json_name = String::with_capacity(s.len());
+    let mut capitalize_next = false;
+    for c in s.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -159,4 +176,15 @@ mod tests {
         assert_eq!("FuzzBuster", &to_upper_camel("FuzzBuster"));
         assert_eq!("Self_", &to_upper_camel("self"));
     }
+
+    #[test]
+    fn test_to_json_name() {
+        assert_eq!("fooBar", &to_json_name("foo_bar"));
+        assert_eq!("fooBar", &to_json_name("fooBar"));
+        assert_eq!("FooBar", &to_json_name("FooBar"));
+        assert_eq!("fooBar2", &to_json_name("foo_bar_2"));
+        assert_eq!("fooBar", &to_json_name("foo__bar"));
+        assert_eq!("fooBar", &to_json_name("foo_bar_"));
+        assert_eq!("FooBar", &to_json_name("_foo_bar"));
+    }
 }
@@ -124,6 +124,15 @@
 //! If `PROTOC_INCLUDE` is not found in the environment, then the Protobuf include directory
 //! bundled in the prost-build crate is be used.
 //!
+//! ### Parsing without `protoc`
+//!
+//! Alternatively, [`Config::native_parser`] configures `prost-build` to parse `.proto` files with
+//! its own parser, so that `protoc` is not invoked at all. Imports are resolved against the
+//! include directories passed to `compile_protos` followed by the Protobuf include directory, and
+//! comments are carried through to the generated code in the same way as with `protoc`. If
+//! `PROTOC_NO_VENDOR` is set, `prost-build` builds even if `protoc` can't be found, so the native
+//! parser can be used in environments without `protoc`.
+//!
 //! ### Compiling `protoc` from source
 //!
 //! Compiling `protoc` from source requires a few external dependencies. Currently,
@@ -137,6 +146,7 @@ mod code_generator;
 mod extern_paths;
 mod ident;
 mod message_graph;
+mod parser;
 mod path;
 
 use std::collections::HashMap;
@@ -152,7 +162,10 @@ use std::process::Command;
 
 use log::trace;
 use prost::Message;
-use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use prost_types::{
+    EnumOptions, EnumValueOptions, FieldOptions, FileDescriptorProto, FileDescriptorSet,
+    MessageOptions,
+};
 
 pub use crate::ast::{Comments, Method, Service};
 use crate::code_generator::CodeGenerator;
@@ -206,6 +219,82 @@ pub trait ServiceGenerator {
     fn finalize_package(&mut self, _package: &str, _buf: &mut String) {}
 }
 
+/// An attribute generator adds attributes to generated types, fields and enum variants based on
+/// the options of their Protobuf definitions.
+///
+/// Attribute generators are registered with a code generator using the
+/// `Config::attribute_generator` method.
+///
+/// Custom options are kept in the `_extensions` field of the options messages, and can be read
+/// with `prost::ExtendableMessage::get_extension`, given an `Extension` describing the option:
+///
+/// ```rust
+/// use prost::{ExtendableMessage, Extension};
+/// use prost_build::AttributeGenerator;
+/// use prost_types::FieldOptions;
+///
+/// /// `extend google.protobuf.FieldOptions { optional bool redact = 50000; }`
+/// const REDACT: Extension<FieldOptions, prost::extension::Bool> = Extension::new("redact", 50000);
+///
+/// struct Redact;
+///
+/// impl AttributeGenerator for Redact {
+///     fn field_attributes(
+///         &mut self,
+///         _fq_message_name: &str,
+///         _field_name: &str,
+///         options: &FieldOptions,
+///     ) -> Vec<String> {
+///         match options.get_extension(&REDACT) {
+///             Ok(Some(true)) => vec!["#[serde(skip)]".to_string()],
+///             _ => Vec::new(),
+///         }
+///     }
+/// }
+/// ```
+///
+/// Each method is passed the fully qualified Protobuf name of the enclosing message or enum, with
+/// a leading `.`, and returns the attributes to add, one per line. Definitions without options are
+/// passed the default options. The default implementations add no attributes.
+pub trait AttributeGenerator {
+    /// Returns the attributes of the struct generated for a message.
+    fn message_attributes(
+        &mut self,
+        _fq_message_name: &str,
+        _options: &MessageOptions,
+    ) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the attributes of a message field.
+    ///
+    /// This is called for fields in a `oneof` too, whose attributes are added to the variant of the
+    /// generated `enum`. `fq_message_name` is the name of the message containing the `oneof`.
+    fn field_attributes(
+        &mut self,
+        _fq_message_name: &str,
+        _field_name: &str,
+        _options: &FieldOptions,
+    ) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the attributes of the `enum` generated for an enum.
+    fn enum_attributes(&mut self, _fq_enum_name: &str, _options: &EnumOptions) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the attributes of an enum variant.
+    fn enum_value_attributes(
+        &mut self,
+        _fq_enum_name: &str,
+        _value_name: &str,
+        _options: &EnumValueOptions,
+    ) -> Vec<String> {
+        Vec::new()
+    }
+}
+
 /// The map collection type to output for Protobuf `map` fields.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -244,8 +333,13 @@ impl Default for BytesType {
 pub struct Config {
     file_descriptor_set_path: Option<PathBuf>,
     service_generator: Option<Box<dyn ServiceGenerator>>,
+    attribute_generator: Option<Box<dyn AttributeGenerator>>,
     map_type: PathMap<MapType>,
     bytes_type: PathMap<BytesType>,
+    custom_bytes_type: PathMap<String>,
+    custom_string_type: PathMap<String>,
+    field_types: PathMap<(String, String)>,
+    lazy: PathMap<()>,
     type_attributes: PathMap<String>,
     field_attributes: PathMap<String>,
     prost_types: bool,
@@ -255,7 +349,13 @@ pub struct Config {
     default_package_filename: String,
     protoc_args: Vec<OsString>,
     disable_comments: PathMap<()>,
+    unknown_fields: PathMap<()>,
+    borrowed: PathMap<()>,
+    reflect: PathMap<()>,
+    diff: PathMap<()>,
+    type_registry: PathMap<()>,
     skip_protoc_run: bool,
+    native_parser: bool,
     include_file: Option<PathBuf>,
 }
 
@@ -387,6 +487,158 @@ impl Config {
         self
     }
 
+    /// Configure the code generator to generate fields of a custom Rust type for Protobuf
+    /// `bytes` type fields.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. For details about matching fields see
+    /// [`bytes`](#method.bytes). Map fields are not affected.
+    ///
+    /// **`rust_type`** - the fully qualified path of the Rust type, which must implement
+    /// [`prost::BytesAdapter`][1]. The type takes precedence over types configured with
+    /// [`bytes`](#method.bytes).
+    ///
+    /// The calls to this method are cumulative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.bytes_type(".my_messages.MyMessageType.my_bytes_field", "::my_crate::SharedBytes");
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/trait.BytesAdapter.html
+    pub fn bytes_type<P, T>(&mut self, path: P, rust_type: T) -> &mut Self
+    where
+        P: AsRef<str>,
+        T: AsRef<str>,
+    {
+        self.custom_bytes_type
+            .insert(path.as_ref().to_string(), rust_type.as_ref().to_string());
+        self
+    }
+
+    /// Configure the code generator to generate fields of a custom Rust type for Protobuf
+    /// `string` type fields.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. For details about matching fields see
+    /// [`bytes`](#method.bytes). Map fields are not affected.
+    ///
+    /// **`rust_type`** - the fully qualified path of the Rust type, which must implement
+    /// [`prost::StringAdapter`][1].
+    ///
+    /// The calls to this method are cumulative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Use `Box<str>` for all string fields in a package.
+    /// config.string_type(".my_messages", "::prost::alloc::boxed::Box<str>");
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/trait.StringAdapter.html
+    pub fn string_type<P, T>(&mut self, path: P, rust_type: T) -> &mut Self
+    where
+        P: AsRef<str>,
+        T: AsRef<str>,
+    {
+        self.custom_string_type
+            .insert(path.as_ref().to_string(), rust_type.as_ref().to_string());
+        self
+    }
+
+    /// Configure the code generator to generate fields of a custom Rust type, which is converted
+    /// to and from the Protobuf type of the field when the message is encoded and decoded.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. For details about matching fields see
+    /// [`bytes`](#method.bytes). Map and oneof fields are not affected.
+    ///
+    /// **`rust_type`** - the fully qualified path of the Rust type. Optional and repeated fields
+    /// are generated as an `Option` or a `Vec` of the type.
+    ///
+    /// **`conversion`** - the fully qualified path of a type implementing
+    /// [`prost::Conversion`][1], whose `Value` is the Rust type, and whose `Wire` is the type
+    /// the field would otherwise have.
+    ///
+    /// Messages with converted fields can't be used with [`reflect`](#method.reflect).
+    ///
+    /// The calls to this method are cumulative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.field_type(
+    ///     ".my_messages.User.created_at",
+    ///     "::chrono::DateTime<::chrono::Utc>",
+    ///     "crate::conversions::Timestamp",
+    /// );
+    /// config.field_type(".my_messages.User.id", "crate::UserId", "crate::UserId");
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/trait.Conversion.html
+    pub fn field_type<P, T, C>(&mut self, path: P, rust_type: T, conversion: C) -> &mut Self
+    where
+        P: AsRef<str>,
+        T: AsRef<str>,
+        C: AsRef<str>,
+    {
+        self.field_types.insert(
+            path.as_ref().to_string(),
+            (
+                rust_type.as_ref().to_string(),
+                conversion.as_ref().to_string(),
+            ),
+        );
+        self
+    }
+
+    /// Configure the code generator to generate [`prost::Lazy`][1] fields for Protobuf message
+    /// fields, which are decoded when they're first accessed rather than with the containing
+    /// message.
+    ///
+    /// Until a lazy field is accessed, it holds the encoded bytes of the message, which are
+    /// encoded again unchanged. This avoids the cost of decoding nested messages which are
+    /// rarely used.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use lazy
+    /// message fields. For details about matching fields see [`btree_map`](#method.btree_map).
+    /// Group, map, oneof and borrowed fields are not affected.
+    ///
+    /// Messages with lazy fields can't be used with [`reflect`](#method.reflect).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Decode a specific field lazily.
+    /// config.lazy(&[".my_messages.Envelope.payload"]);
+    ///
+    /// // Decode all message fields of a message type lazily.
+    /// config.lazy(&[".my_messages.Envelope"]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/struct.Lazy.html
+    pub fn lazy<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.lazy.clear();
+        for matcher in paths {
+            self.lazy.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
     /// Add additional attribute to matched fields.
     ///
     /// # Arguments
@@ -475,6 +727,18 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to use the provided attribute generator.
+    ///
+    /// The attributes it returns are added after those configured with `type_attribute` and
+    /// `field_attribute`.
+    pub fn attribute_generator(
+        &mut self,
+        attribute_generator: Box<dyn AttributeGenerator>,
+    ) -> &mut Self {
+        self.attribute_generator = Some(attribute_generator);
+        self
+    }
+
     /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
     /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
     pub fn compile_well_known_types(&mut self) -> &mut Self {
@@ -515,6 +779,208 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to preserve unknown fields in generated messages.
+    ///
+    /// By default, fields with tags which are not part of a message's schema are skipped during
+    /// decoding, and are lost if the message is re-encoded. Messages matching one of the paths
+    /// instead get an additional `_unknown_fields` member of type [`prost::UnknownFields`], which
+    /// captures the tag, wire type and payload of each unrecognized field and re-emits them when
+    /// the message is encoded. This allows proxies built against an older schema to forward
+    /// fields added by newer producers.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should preserve unknown fields.
+    /// For details about matching see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Preserve unknown fields in all messages.
+    /// config.preserve_unknown_fields(&["."]);
+    ///
+    /// // Preserve unknown fields in a specific message type.
+    /// config.preserve_unknown_fields(&[".my_messages.MyMessageType"]);
+    /// ```
+    pub fn preserve_unknown_fields<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.unknown_fields.clear();
+        for matcher in paths {
+            self.unknown_fields.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to generate message types which borrow from the buffer they
+    /// are decoded from.
+    ///
+    /// Borrowed messages are generated with a lifetime parameter `'a`, and represent Protobuf
+    /// `string` and `bytes` fields as `&'a str` and `&'a [u8]`. Fields of other borrowed message
+    /// types are borrowed as well. Borrowed messages implement [`prost::BorrowedMessage`] instead
+    /// of [`prost::Message`], and are decoded without copying with
+    /// [`BorrowedMessage::decode_borrowed`][1].
+    ///
+    /// Only messages which have a `string` or `bytes` field, or a field of another borrowed
+    /// message type, get a lifetime parameter. Oneof and map fields are not borrowed, and may not
+    /// have borrowed message types.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should be borrowed. For details
+    /// about matching see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Borrow all messages in a package.
+    /// config.borrowed(&[".my_messages"]);
+    ///
+    /// // Borrow a specific message type.
+    /// config.borrowed(&[".my_messages.MyMessageType"]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/trait.BorrowedMessage.html#method.decode_borrowed
+    pub fn borrowed<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.borrowed.clear();
+        for matcher in paths {
+            self.borrowed.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to implement runtime reflection for generated messages.
+    ///
+    /// Messages matching one of the paths derive [`prost::ReflectMessage`], which exposes the
+    /// message's fully qualified name and field descriptors, and allows fields to be read, set and
+    /// cleared by number or name through the dynamically typed `prost::reflect::Value`. The names
+    /// are taken from the `.proto` definitions. Enums matching one of the paths are annotated with
+    /// `#[prost(reflect)]` and their `.proto` value names, which are exposed through
+    /// `prost::reflect::ReflectEnum` and used by the JSON mapping in `prost-types`.
+    ///
+    /// Message, group and enum fields of reflected messages must have types which implement
+    /// `ReflectMessage` or `ReflectEnum` as well, so the paths should normally cover every message
+    /// and enum that reflected messages depend on. The well-known types in `prost-types` implement
+    /// `ReflectMessage`. Borrowed messages (see [`borrowed`](#method.borrowed)) do not support
+    /// reflection, and are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should implement reflection.
+    /// For details about matching see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Implement reflection for all messages.
+    /// config.reflect(&["."]);
+    ///
+    /// // Implement reflection for all messages in a package.
+    /// config.reflect(&[".my_messages"]);
+    /// ```
+    pub fn reflect<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.reflect.clear();
+        for matcher in paths {
+            self.reflect.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to implement field-level diffs for generated messages.
+    ///
+    /// Messages matching one of the paths derive [`prost::DiffMessage`], which reports each field
+    /// of two instances of the message under its `.proto` name, so that they can be compared with
+    /// `prost_types::diff::diff`. Unlike [`reflect`](#method.reflect), this doesn't need
+    /// descriptors.
+    ///
+    /// Message and group fields of diffed messages must have types which implement `DiffMessage`
+    /// as well, so the paths should normally cover every message that diffed messages depend on.
+    /// The well-known types in `prost-types` implement `DiffMessage`. Borrowed messages (see
+    /// [`borrowed`](#method.borrowed)) can't be diffed, and are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should implement diffs.
+    /// For details about matching see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Implement diffs for all messages.
+    /// config.diff(&["."]);
+    ///
+    /// // Implement diffs for all messages in a package.
+    /// config.diff(&[".my_messages"]);
+    /// ```
+    pub fn diff<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.diff.clear();
+        for matcher in paths {
+            self.diff.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to emit functions which register messages with a
+    /// `prost_types::TypeRegistry`, so that `google.protobuf.Any` values containing them can be
+    /// decoded at runtime.
+    ///
+    /// A `register_types` function is generated in the module of every package containing
+    /// messages which match one of the paths, and registers those messages. The generated code
+    /// depends on the `prost-types` crate. Borrowed messages (see [`borrowed`](#method.borrowed))
+    /// are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should be registered. For
+    /// details about matching see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Register all messages.
+    /// config.type_registry(&["."]);
+    ///
+    /// // Register the messages of a package.
+    /// config.type_registry(&[".my_messages"]);
+    /// ```
+    ///
+    /// The generated functions are then called to populate a registry:
+    ///
+    /// ```rust,ignore
+    /// let mut registry = prost_types::TypeRegistry::new();
+    /// my_messages::register_types(&mut registry);
+    /// ```
+    pub fn type_registry<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.type_registry.clear();
+        for matcher in paths {
+            self.type_registry.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
     /// Declare an externally provided Protobuf package or type.
     ///
     /// `extern_path` allows `prost` types in external crates to be referenced in generated code.
@@ -679,6 +1145,30 @@ impl Config {
         self
     }
 
+    /// Parses `.proto` files with the parser built into `prost-build`, rather than by invoking
+    /// `protoc`.
+    ///
+    /// The native parser resolves imports against the `includes` passed to `compile_protos`,
+    /// followed by the Protobuf include directory, in the same way as `protoc`. Any arguments
+    /// added with `protoc_arg` are ignored. If `file_descriptor_set_path` is set, the parsed file
+    /// descriptor set is written to it.
+    ///
+    /// Custom options are not interpreted by the native parser, so they appear in the file
+    /// descriptor set as `uninterpreted_option`s, and are not available to the service and
+    /// attribute generators.
+    ///
+    /// In `build.rs`:
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.native_parser()
+    ///     .compile_protos(&["src/items.proto"], &["src/"]);
+    /// ```
+    pub fn native_parser(&mut self) -> &mut Self {
+        self.native_parser = true;
+        self
+    }
+
     /// Configures the code generator to not strip the enum name from variant names.
     ///
     /// Protobuf enum definitions commonly include the enum name as a prefix of every variant name.
@@ -809,6 +1299,74 @@ impl Config {
         // this figured out.
         // [1]: http://doc.crates.io/build-script.html#outputs-of-the-build-script
 
+        let file_descriptor_set = if self.native_parser && !self.skip_protoc_run {
+            let file_descriptor_set = parser::parse_files(protos, includes)?;
+            if let Some(path) = &self.file_descriptor_set_path {
+                fs::write(path, file_descriptor_set.encode_to_vec())?;
+            }
+            file_descriptor_set
+        } else {
+            self.run_protoc(protos, includes)?
+        };
+
+        let requests = file_descriptor_set
+            .file
+            .into_iter()
+            .map(|descriptor| {
+                (
+                    Module::from_protobuf_package_name(descriptor.package()),
+                    descriptor,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let file_names = requests
+            .iter()
+            .map(|req| {
+                (
+                    req.0.clone(),
+                    req.0.to_file_name_or(&self.default_package_filename),
+                )
+            })
+            .collect::<HashMap<Module, String>>();
+
+        let modules = self.generate(requests)?;
+        for (module, content) in &modules {
+            let file_name = file_names
+                .get(module)
+                .expect("every module should have a filename");
+            let output_path = target.join(file_name);
+
+            let previous_content = fs::read(&output_path);
+
+            if previous_content
+                .map(|previous_content| previous_content == content.as_bytes())
+                .unwrap_or(false)
+            {
+                trace!("unchanged: {:?}", file_name);
+            } else {
+                trace!("writing: {:?}", file_name);
+                fs::write(output_path, content)?;
+            }
+        }
+
+        if let Some(ref include_file) = self.include_file {
+            trace!("Writing include file: {:?}", target.join(include_file));
+            let mut file = fs::File::create(target.join(include_file))?;
+            self.write_includes(modules.keys().collect(), &mut file, 0, !target_is_env)?;
+            file.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Invokes `protoc` to parse `protos`, unless `skip_protoc_run` is set, and returns the file
+    /// descriptor set it writes.
+    fn run_protoc(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<FileDescriptorSet> {
         let tmp;
         let file_descriptor_set_path = if let Some(path) = &self.file_descriptor_set_path {
             path.clone()
@@ -869,69 +1427,39 @@ impl Config {
             )
         })?;
 
-        let requests = file_descriptor_set
-            .file
-            .into_iter()
-            .map(|descriptor| {
-                (
-                    Module::from_protobuf_package_name(descriptor.package()),
-                    descriptor,
-                )
-            })
-            .collect::<Vec<_>>();
+        Ok(file_descriptor_set)
+    }
 
-        let file_names = requests
+    /// Writes an include file for `modules` to `outfile`, which includes the file generated for
+    /// each module by its path relative to the include file.
+    ///
+    /// This is the include file written by `compile_protos` when both `include_file` and `out_dir`
+    /// are set. It's generally used along with [`generate()`](Config::generate), when the
+    /// generated files are written by the caller.
+    pub fn write_include_file<W>(&self, modules: &[&Module], outfile: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut modules: Vec<&Module> = modules
             .iter()
-            .map(|req| {
-                (
-                    req.0.clone(),
-                    req.0.to_file_name_or(&self.default_package_filename),
-                )
-            })
-            .collect::<HashMap<Module, String>>();
-
-        let modules = self.generate(requests)?;
-        for (module, content) in &modules {
-            let file_name = file_names
-                .get(module)
-                .expect("every module should have a filename");
-            let output_path = target.join(file_name);
-
-            let previous_content = fs::read(&output_path);
-
-            if previous_content
-                .map(|previous_content| previous_content == content.as_bytes())
-                .unwrap_or(false)
-            {
-                trace!("unchanged: {:?}", file_name);
-            } else {
-                trace!("writing: {:?}", file_name);
-                fs::write(output_path, content)?;
-            }
-        }
-
-        if let Some(ref include_file) = self.include_file {
-            trace!("Writing include file: {:?}", target.join(include_file));
-            let mut file = fs::File::create(target.join(include_file))?;
-            self.write_includes(
-                modules.keys().collect(),
-                &mut file,
-                0,
-                if target_is_env { None } else { Some(&target) },
-            )?;
-            file.flush()?;
-        }
-
+            .copied()
+            .filter(|module| !module.is_empty())
+            .collect();
+        modules.sort();
+        self.write_includes(modules, outfile, 0, true)?;
         Ok(())
     }
 
-    fn write_includes(
+    fn write_includes<W>(
         &self,
         mut entries: Vec<&Module>,
-        outfile: &mut fs::File,
+        outfile: &mut W,
         depth: usize,
-        basepath: Option<&PathBuf>,
-    ) -> Result<usize> {
+        relative: bool,
+    ) -> Result<usize>
+    where
+        W: Write,
+    {
         let mut written = 0;
         while !entries.is_empty() {
             let modident = entries[0].part(depth);
@@ -957,12 +1485,12 @@ impl Config {
                     .collect(),
                 outfile,
                 depth + 1,
-                basepath,
+                relative,
             )?;
             written += subwritten;
             if subwritten != matching.len() {
                 let modname = matching[0].to_partial_file_name(..=depth);
-                if basepath.is_some() {
+                if relative {
                     self.write_line(
                         outfile,
                         depth + 1,
@@ -983,7 +1511,10 @@ impl Config {
         Ok(written)
     }
 
-    fn write_line(&self, outfile: &mut fs::File, depth: usize, line: &str) -> Result<()> {
+    fn write_line<W>(&self, outfile: &mut W, depth: usize, line: &str) -> Result<()>
+    where
+        W: Write,
+    {
         outfile.write_all(format!("{}{}\n", ("    ").to_owned().repeat(depth), line).as_bytes())
     }
 
@@ -999,11 +1530,16 @@ impl Config {
     ) -> Result<HashMap<Module, String>> {
         let mut modules = HashMap::new();
         let mut packages = HashMap::new();
+        let mut registered_types = HashMap::new();
 
-        let message_graph = MessageGraph::new(requests.iter().map(|x| &x.1))
+        let mut message_graph = MessageGraph::new(requests.iter().map(|x| &x.1))
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
         let extern_paths = ExternPaths::new(&self.extern_paths, self.prost_types)
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        message_graph.resolve_borrowed(|msg_name| {
+            extern_paths.resolve_ident(msg_name).is_none()
+                && self.borrowed.get(msg_name).next().is_some()
+        });
 
         for request in requests {
             // Only record packages that have services
@@ -1011,8 +1547,27 @@ impl Config {
                 packages.insert(request.0.clone(), request.1.package().to_string());
             }
 
-            let buf = modules.entry(request.0).or_insert_with(String::new);
-            CodeGenerator::generate(self, &message_graph, &extern_paths, request.1, buf);
+            let buf = modules.entry(request.0.clone()).or_insert_with(String::new);
+            let types =
+                CodeGenerator::generate(self, &message_graph, &extern_paths, request.1, buf);
+            if !types.is_empty() {
+                registered_types
+                    .entry(request.0)
+                    .or_insert_with(Vec::new)
+                    .extend(types);
+            }
+        }
+
+        for (module, types) in registered_types {
+            let buf = modules.get_mut(&module).unwrap();
+            buf.push_str(
+                "/// Registers the message types of this package with a type registry.\n\
+                 pub fn register_types(registry: &mut ::prost_types::TypeRegistry) {\n",
+            );
+            for ty in types {
+                buf.push_str(&format!("    registry.register::<{}>();\n", ty));
+            }
+            buf.push_str("}\n");
         }
 
         if let Some(ref mut service_generator) = self.service_generator {
@@ -1031,8 +1586,13 @@ impl default::Default for Config {
         Config {
             file_descriptor_set_path: None,
             service_generator: None,
+            attribute_generator: None,
             map_type: PathMap::default(),
             bytes_type: PathMap::default(),
+            custom_bytes_type: PathMap::default(),
+            custom_string_type: PathMap::default(),
+            field_types: PathMap::default(),
+            lazy: PathMap::default(),
             type_attributes: PathMap::default(),
             field_attributes: PathMap::default(),
             prost_types: true,
@@ -1042,7 +1602,13 @@ impl default::Default for Config {
             default_package_filename: "_".to_string(),
             protoc_args: Vec::new(),
             disable_comments: PathMap::default(),
+            unknown_fields: PathMap::default(),
+            borrowed: PathMap::default(),
+            reflect: PathMap::default(),
+            diff: PathMap::default(),
+            type_registry: PathMap::default(),
             skip_protoc_run: false,
+            native_parser: false,
             include_file: None,
         }
     }
@@ -1053,8 +1619,13 @@ impl fmt::Debug for Config {
         fmt.debug_struct("Config")
             .field("file_descriptor_set_path", &self.file_descriptor_set_path)
             .field("service_generator", &self.service_generator.is_some())
+            .field("attribute_generator", &self.attribute_generator.is_some())
             .field("map_type", &self.map_type)
             .field("bytes_type", &self.bytes_type)
+            .field("custom_bytes_type", &self.custom_bytes_type)
+            .field("custom_string_type", &self.custom_string_type)
+            .field("field_types", &self.field_types)
+            .field("lazy", &self.lazy)
             .field("type_attributes", &self.type_attributes)
             .field("field_attributes", &self.field_attributes)
             .field("prost_types", &self.prost_types)
@@ -1064,6 +1635,12 @@ impl fmt::Debug for Config {
             .field("default_package_filename", &self.default_package_filename)
             .field("protoc_args", &self.protoc_args)
             .field("disable_comments", &self.disable_comments)
+            .field("unknown_fields", &self.unknown_fields)
+            .field("borrowed", &self.borrowed)
+            .field("reflect", &self.reflect)
+            .field("diff", &self.diff)
+            .field("type_registry", &self.type_registry)
+            .field("native_parser", &self.native_parser)
             .finish()
     }
 }
@@ -1221,6 +1798,9 @@ mod tests {
     use std::cell::RefCell;
     use std::rc::Rc;
 
+    use prost::{extension, ExtendableMessage, Extension};
+    use prost_types::MethodOptions;
+
     /// An example service generator that generates a trait with methods corresponding to the
     /// service methods.
     struct ServiceTraitGenerator;
@@ -1284,6 +1864,86 @@ mod tests {
         }
     }
 
+    const TABLE: Extension<MessageOptions, extension::String> =
+        Extension::new("custom_options.table", 50000);
+    const REDACT: Extension<FieldOptions, extension::Bool> =
+        Extension::new("custom_options.redact", 50000);
+    const CLOSED: Extension<EnumOptions, extension::Bool> =
+        Extension::new("custom_options.closed", 50000);
+    const LABEL: Extension<EnumValueOptions, extension::String> =
+        Extension::new("custom_options.label", 50000);
+    const SCOPES: Extension<MethodOptions, extension::Repeated<extension::String>> =
+        Extension::new("custom_options.scopes", 50000);
+
+    /// Adds doc attributes describing the custom options in `custom_options.proto`.
+    struct CustomOptionsGenerator;
+
+    impl AttributeGenerator for CustomOptionsGenerator {
+        fn message_attributes(
+            &mut self,
+            fq_message_name: &str,
+            options: &MessageOptions,
+        ) -> Vec<String> {
+            match options.get_extension(&TABLE).unwrap() {
+                Some(table) => vec![format!("#[doc = \"{} table: {}\"]", fq_message_name, table)],
+                None => Vec::new(),
+            }
+        }
+
+        fn field_attributes(
+            &mut self,
+            fq_message_name: &str,
+            field_name: &str,
+            options: &FieldOptions,
+        ) -> Vec<String> {
+            match options.get_extension(&REDACT).unwrap() {
+                Some(true) => vec![format!(
+                    "#[doc = \"{}.{} redacted\"]",
+                    fq_message_name, field_name
+                )],
+                _ => Vec::new(),
+            }
+        }
+
+        fn enum_attributes(&mut self, fq_enum_name: &str, options: &EnumOptions) -> Vec<String> {
+            match options.get_extension(&CLOSED).unwrap() {
+                Some(true) => vec![format!("#[doc = \"{} closed\"]", fq_enum_name)],
+                _ => Vec::new(),
+            }
+        }
+
+        fn enum_value_attributes(
+            &mut self,
+            fq_enum_name: &str,
+            value_name: &str,
+            options: &EnumValueOptions,
+        ) -> Vec<String> {
+            match options.get_extension(&LABEL).unwrap() {
+                Some(label) => vec![format!(
+                    "#[doc = \"{}.{} label: {}\"]",
+                    fq_enum_name, value_name, label
+                )],
+                None => Vec::new(),
+            }
+        }
+    }
+
+    /// Lists the scopes of each method, from the `scopes` method option.
+    struct ScopesGenerator;
+
+    impl ServiceGenerator for ScopesGenerator {
+        fn generate(&mut self, service: Service, buf: &mut String) {
+            for method in service.methods {
+                let scopes = method.options.get_extension(&SCOPES).unwrap();
+                buf.push_str(&format!(
+                    "// {}: {:?}\n",
+                    method.proto_name,
+                    scopes.unwrap_or_default()
+                ));
+            }
+        }
+    }
+
     #[test]
     fn smoke_test() {
         let _ = env_logger::try_init();
@@ -1311,4 +1971,37 @@ mod tests {
         assert_eq!(&state.package_names, &["helloworld"]);
         assert_eq!(state.finalized, 3);
     }
+
+    #[test]
+    fn custom_options() {
+        let _ = env_logger::try_init();
+        let tmp = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tmp.path())
+            .attribute_generator(Box::new(CustomOptionsGenerator))
+            .service_generator(Box::new(ScopesGenerator))
+            .compile_protos(&["src/custom_options.proto"], &["src"])
+            .unwrap();
+
+        let code = fs::read_to_string(tmp.path().join("custom_options.rs")).unwrap();
+        for expected in [
+            "#[doc = \".custom_options.User table: users\"]\n#[derive(",
+            "    #[doc = \".custom_options.User.password redacted\"]\n    pub password:",
+            "        #[doc = \".custom_options.User.email redacted\"]\n        Email(",
+            "#[doc = \".custom_options.Role closed\"]\n#[derive(",
+            "    #[doc = \".custom_options.Role.ROLE_ADMIN label: Administrator\"]\n    Admin = 1,",
+            "// Get: [\"users.read\"]\n",
+            "// Delete: [\"users.read\", \"users.write\"]\n",
+        ] {
+            assert!(
+                code.contains(expected),
+                "{} not found in:\n{}",
+                expected,
+                code
+            );
+        }
+        assert!(!code.contains("name redacted"));
+        assert!(!code.contains("phone redacted"));
+    }
 }
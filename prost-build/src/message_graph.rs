@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use petgraph::algo::has_path_connecting;
 use petgraph::graph::NodeIndex;
@@ -12,6 +12,20 @@ use prost_types::{field_descriptor_proto, DescriptorProto, FileDescriptorProto};
 pub struct MessageGraph {
     index: HashMap<String, NodeIndex>,
     graph: Graph<String, ()>,
+    /// The fields of each message which may borrow from the decoded buffer.
+    borrowable_fields: HashMap<String, BorrowableFields>,
+    borrowed: HashSet<String>,
+}
+
+/// The fields of a message which may borrow from the decoded buffer.
+///
+/// Oneof and map fields are never borrowed.
+#[derive(Default)]
+struct BorrowableFields {
+    /// Whether the message has a `string` or `bytes` field.
+    has_scalar: bool,
+    /// The types of the message's message fields.
+    message_types: Vec<String>,
 }
 
 impl MessageGraph {
@@ -21,6 +35,8 @@ impl MessageGraph {
         let mut msg_graph = MessageGraph {
             index: HashMap::new(),
             graph: Graph::new(),
+            borrowable_fields: HashMap::new(),
+            borrowed: HashSet::new(),
         };
 
         for file in files {
@@ -41,6 +57,7 @@ impl MessageGraph {
         let MessageGraph {
             ref mut index,
             ref mut graph,
+            ..
         } = *self;
         assert_eq!(b'.', msg_name.as_bytes()[0]);
         *index
@@ -66,6 +83,37 @@ impl MessageGraph {
             }
         }
 
+        let map_entries = msg
+            .nested_type
+            .iter()
+            .filter(|nested| {
+                nested
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.map_entry)
+                    .unwrap_or(false)
+            })
+            .map(|nested| format!("{}.{}", msg_name, nested.name()))
+            .collect::<HashSet<_>>();
+        let mut borrowable = BorrowableFields::default();
+        for field in &msg.field {
+            if field.oneof_index.is_some() && !field.proto3_optional() {
+                continue;
+            }
+            match field.r#type() {
+                field_descriptor_proto::Type::String | field_descriptor_proto::Type::Bytes => {
+                    borrowable.has_scalar = true
+                }
+                field_descriptor_proto::Type::Message
+                    if !map_entries.contains(field.type_name()) =>
+                {
+                    borrowable.message_types.push(field.type_name().to_string())
+                }
+                _ => (),
+            }
+        }
+        self.borrowable_fields.insert(msg_name.clone(), borrowable);
+
         for msg in &msg.nested_type {
             self.add_message(&msg_name, msg);
         }
@@ -84,4 +132,39 @@ impl MessageGraph {
 
         has_path_connecting(&self.graph, outer, inner, None)
     }
+
+    /// Determines which messages borrow from the buffer they are decoded from.
+    ///
+    /// A message is borrowed if `matches` returns `true` for its fully-qualified name, and it has
+    /// a `string` or `bytes` field, or a field of another borrowed message type.
+    pub fn resolve_borrowed<F>(&mut self, matches: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let candidates = self
+            .borrowable_fields
+            .iter()
+            .filter(|(msg_name, _)| matches(msg_name))
+            .collect::<Vec<_>>();
+
+        let mut borrowed = HashSet::new();
+        loop {
+            let len = borrowed.len();
+            for &(msg_name, fields) in &candidates {
+                if fields.has_scalar || fields.message_types.iter().any(|ty| borrowed.contains(ty))
+                {
+                    borrowed.insert(msg_name.clone());
+                }
+            }
+            if borrowed.len() == len {
+                break;
+            }
+        }
+        self.borrowed = borrowed;
+    }
+
+    /// Returns true if message type `msg` borrows from the buffer it is decoded from.
+    pub fn is_borrowed(&self, msg: &str) -> bool {
+        self.borrowed.contains(msg)
+    }
 }
@@ -0,0 +1,1553 @@
+//! Parses the tokens of a `.proto` file into a `FileDescriptorProto`.
+//!
+//! Type names are recorded as written, and are resolved once the imported files have been parsed.
+
+use std::fmt::Write;
+
+use prost::reflect::{Kind, Value};
+use prost::ReflectMessage;
+use prost_types::descriptor_proto::{ExtensionRange, ReservedRange};
+use prost_types::enum_descriptor_proto::EnumReservedRange;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::source_code_info::Location;
+use prost_types::uninterpreted_option::NamePart;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    ExtensionRangeOptions, FieldDescriptorProto, FieldOptions, FileDescriptorProto, FileOptions,
+    MessageOptions, MethodDescriptorProto, MethodOptions, OneofDescriptorProto, OneofOptions,
+    ServiceDescriptorProto, ServiceOptions, SourceCodeInfo, UninterpretedOption,
+};
+
+use super::lexer::{tokenize, unescape, Token, TokenKind};
+
+/// The largest field number.
+pub(super) const MAX_FIELD_NUMBER: i32 = 536_870_911;
+
+pub(super) fn parse(name: &str, source: &str) -> Result<FileDescriptorProto> {
+    let tokens = tokenize(source).map_err(|(line, column, message)| {
+        format!("{}:{}:{}: {}", name, line + 1, column + 1, message)
+    })?;
+    let mut parser = Parser {
+        file_name: name,
+        tokens,
+        pos: 0,
+        proto3: false,
+        locations: Vec::new(),
+    };
+    let mut file = parser.parse_file()?;
+    file.name = Some(name.to_string());
+    Ok(file)
+}
+
+/// Returns the name of the synthesized message type holding the entries of a map field.
+fn map_entry_name(field_name: &str) -> String {
+    let mut name = String::new();
+    let mut capitalize_next = true;
+    for c in field_name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name.push_str("Entry");
+    name
+}
+
+/// Returns the default JSON name of a field.
+pub(super) fn json_name(field_name: &str) -> String {
+    let mut name = String::new();
+    let mut capitalize_next = false;
+    for c in field_name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn scalar_type(name: &str) -> Option<Type> {
+    let ty = match name {
+        "double" => Type::Double,
+        "float" => Type::Float,
+        "int64" => Type::Int64,
+        "uint64" => Type::Uint64,
+        "int32" => Type::Int32,
+        "fixed64" => Type::Fixed64,
+        "fixed32" => Type::Fixed32,
+        "bool" => Type::Bool,
+        "string" => Type::String,
+        "bytes" => Type::Bytes,
+        "uint32" => Type::Uint32,
+        "sfixed32" => Type::Sfixed32,
+        "sfixed64" => Type::Sfixed64,
+        "sint32" => Type::Sint32,
+        "sint64" => Type::Sint64,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+fn child_path(path: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field);
+    path.push(index as i32);
+    path
+}
+
+fn field_path(path: &[i32], field: i32) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field);
+    path
+}
+
+/// Escapes bytes the same way as the default values of `bytes` fields written by `protoc`.
+fn c_escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &b in bytes {
+        match b {
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            b'"' => escaped.push_str("\\\""),
+            b'\'' => escaped.push_str("\\'"),
+            b'\\' => escaped.push_str("\\\\"),
+            0x20..=0x7E => escaped.push(char::from(b)),
+            _ => write!(escaped, "\\{:03o}", b).unwrap(),
+        }
+    }
+    escaped
+}
+
+/// Formats a floating point default value the same way as `protoc`, which uses the shorter of
+/// `%.6g` and `%.9g` for floats, or of `%.15g` and `%.17g` for doubles, that round trips.
+fn format_float(value: f64, is_float: bool) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    } else if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if is_float {
+        let value = value as f32;
+        let short = format_g(value.into(), 6);
+        if short.parse::<f32>() == Ok(value) {
+            short
+        } else {
+            format_g(value.into(), 9)
+        }
+    } else {
+        let short = format_g(value, 15);
+        if short.parse::<f64>() == Ok(value) {
+            short
+        } else {
+            format_g(value, 17)
+        }
+    }
+}
+
+/// Formats a finite number like C's `%.<precision>g`.
+fn format_g(value: f64, precision: usize) -> String {
+    let scientific = format!("{:.*e}", precision - 1, value);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    let trim = |s: &str| -> String {
+        if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            s.to_string()
+        }
+    };
+    if exponent < -4 || exponent >= precision as i32 {
+        format!(
+            "{}e{}{:02}",
+            trim(mantissa),
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        )
+    } else {
+        let decimals = (precision as i32 - 1 - exponent) as usize;
+        trim(&format!("{:.*}", decimals, value))
+    }
+}
+
+/// The value of an option, before it's interpreted according to the option's type.
+enum OptionValue {
+    Identifier(String),
+    PositiveInt(u64),
+    NegativeInt(i64),
+    Double(f64),
+    String(Vec<u8>),
+    Aggregate(String),
+}
+
+/// The options messages of the descriptors.
+trait Options: ReflectMessage + Default {
+    fn uninterpreted_option(&mut self) -> &mut Vec<UninterpretedOption>;
+}
+
+macro_rules! impl_options {
+    ($($ty:ty),*) => {
+        $(
+            impl Options for $ty {
+                fn uninterpreted_option(&mut self) -> &mut Vec<UninterpretedOption> {
+                    &mut self.uninterpreted_option
+                }
+            }
+        )*
+    };
+}
+
+impl_options!(
+    FileOptions,
+    MessageOptions,
+    FieldOptions,
+    OneofOptions,
+    EnumOptions,
+    EnumValueOptions,
+    ServiceOptions,
+    MethodOptions,
+    ExtensionRangeOptions
+);
+
+/// Where a field is being declared.
+struct FieldContext<'a> {
+    /// The path of the field's location.
+    path: Vec<i32>,
+    /// The nested types of the containing message or file, which map entry and group types are
+    /// added to, and the path of their location.
+    nested_types: &'a mut Vec<DescriptorProto>,
+    nested_types_path: Vec<i32>,
+    oneof_index: Option<i32>,
+    extendee: Option<&'a str>,
+}
+
+struct Parser<'a> {
+    file_name: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    proto3: bool,
+    locations: Vec<Location>,
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        let token = self.peek();
+        token.kind != TokenKind::String && token.text == text
+    }
+
+    fn at_end(&self) -> bool {
+        self.peek().kind == TokenKind::End
+    }
+
+    /// Consumes the current token, returning its index.
+    fn next(&mut self) -> usize {
+        let index = self.pos;
+        if !self.at_end() {
+            self.pos += 1;
+        }
+        index
+    }
+
+    fn try_consume(&mut self, text: &str) -> bool {
+        if self.looking_at(text) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<usize> {
+        if self.looking_at(text) {
+            Ok(self.next())
+        } else {
+            Err(self.error(&format!("Expected \"{}\".", text)))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> Result<String> {
+        if self.peek().kind == TokenKind::Ident {
+            let index = self.next();
+            Ok(self.tokens[index].text.clone())
+        } else {
+            Err(self.error(&format!("Expected {}.", what)))
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, index: usize, message: &str) -> String {
+        let token = &self.tokens[index];
+        format!(
+            "{}:{}:{}: {}",
+            self.file_name,
+            token.line + 1,
+            token.column + 1,
+            message
+        )
+    }
+
+    /// Adds a location for `path`, which is completed by `end_location`.
+    fn start_location(&mut self, path: Vec<i32>) -> usize {
+        self.locations.push(Location {
+            path,
+            ..Location::default()
+        });
+        self.locations.len() - 1
+    }
+
+    /// Completes a location spanning the tokens from `start` to `end`. If `declaration_end` is
+    /// given, the comments before `start` and after `declaration_end` are attached to it.
+    fn end_location(
+        &mut self,
+        location: usize,
+        start: usize,
+        end: usize,
+        declaration_end: Option<usize>,
+    ) {
+        let tokens = &self.tokens;
+        let (start, end, declaration_end) = (
+            &tokens[start],
+            &tokens[end],
+            declaration_end.map(|index| &tokens[index]),
+        );
+        let location = &mut self.locations[location];
+        location.span = if start.line == end.line {
+            vec![start.line, start.column, end.end_column]
+        } else {
+            vec![start.line, start.column, end.line, end.end_column]
+        };
+        if let Some(declaration_end) = declaration_end {
+            location.leading_comments = start.leading_comments.clone();
+            location.leading_detached_comments = start.detached_comments.clone();
+            location.trailing_comments = declaration_end.trailing_comments.clone();
+        }
+    }
+
+    fn add_location(&mut self, path: Vec<i32>, start: usize, end: usize, comments: bool) {
+        let location = self.start_location(path);
+        self.end_location(
+            location,
+            start,
+            end,
+            if comments { Some(end) } else { None },
+        );
+    }
+
+    /// The index of the last consumed token.
+    fn previous(&self) -> usize {
+        self.pos - 1
+    }
+
+    fn parse_file(&mut self) -> Result<FileDescriptorProto> {
+        let mut file = FileDescriptorProto::default();
+        let root = self.start_location(Vec::new());
+
+        if self.looking_at("syntax") {
+            let start = self.next();
+            self.expect("=")?;
+            let syntax_index = self.pos;
+            let syntax = self.parse_string("syntax identifier")?;
+            match &*syntax {
+                b"proto2" => (),
+                b"proto3" => {
+                    self.proto3 = true;
+                    file.syntax = Some("proto3".to_string());
+                }
+                _ => {
+                    return Err(self.error_at(
+                        syntax_index,
+                        &format!(
+                            "Unrecognized syntax identifier \"{}\".  This parser only \
+                             recognizes \"proto2\" and \"proto3\".",
+                            String::from_utf8_lossy(&syntax)
+                        ),
+                    ))
+                }
+            }
+            let end = self.expect(";")?;
+            self.add_location(vec![12], start, end, true);
+        }
+
+        while !self.at_end() {
+            if self.try_consume(";") {
+                continue;
+            }
+            let start = self.pos;
+            match &*self.peek().text {
+                "message" => {
+                    let path = vec![4, file.message_type.len() as i32];
+                    let message = self.parse_message(path)?;
+                    file.message_type.push(message);
+                }
+                "enum" => {
+                    let path = vec![5, file.enum_type.len() as i32];
+                    let desc = self.parse_enum(path)?;
+                    file.enum_type.push(desc);
+                }
+                "service" => {
+                    let path = vec![6, file.service.len() as i32];
+                    let service = self.parse_service(path)?;
+                    file.service.push(service);
+                }
+                "extend" => {
+                    self.parse_extend(
+                        &mut file.extension,
+                        vec![7],
+                        &mut file.message_type,
+                        vec![4],
+                    )?;
+                }
+                "import" => {
+                    self.next();
+                    let index = file.dependency.len() as i32;
+                    if self.looking_at("public") {
+                        let public = self.next();
+                        let path = vec![10, file.public_dependency.len() as i32];
+                        self.add_location(path, public, public, false);
+                        file.public_dependency.push(index);
+                    } else if self.looking_at("weak") {
+                        let weak = self.next();
+                        let path = vec![11, file.weak_dependency.len() as i32];
+                        self.add_location(path, weak, weak, false);
+                        file.weak_dependency.push(index);
+                    }
+                    let import = self.parse_string("a string naming the file to import")?;
+                    let end = self.expect(";")?;
+                    self.add_location(vec![3, index], start, end, true);
+                    file.dependency
+                        .push(String::from_utf8_lossy(&import).into_owned());
+                }
+                "package" => {
+                    if file.package.is_some() {
+                        return Err(self.error("Multiple package definitions."));
+                    }
+                    self.next();
+                    let package = self.parse_full_ident("package name")?;
+                    let end = self.expect(";")?;
+                    self.add_location(vec![2], start, end, true);
+                    file.package = Some(package);
+                }
+                "option" => {
+                    self.parse_option_statement(&mut file.options, vec![8])?;
+                }
+                _ => {
+                    return Err(self.error("Expected top-level statement (e.g. \"message\")."));
+                }
+            }
+        }
+
+        if self.pos > 0 {
+            self.end_location(root, 0, self.previous(), None);
+        } else {
+            self.locations.remove(root);
+        }
+        file.source_code_info = Some(SourceCodeInfo {
+            location: std::mem::take(&mut self.locations),
+        });
+        Ok(file)
+    }
+
+    /// Parses a dot-separated identifier.
+    fn parse_full_ident(&mut self, what: &str) -> Result<String> {
+        let mut ident = self.expect_ident(what)?;
+        while self.try_consume(".") {
+            ident.push('.');
+            ident.push_str(&self.expect_ident("identifier")?);
+        }
+        Ok(ident)
+    }
+
+    /// Parses a type name, which may be fully qualified with a leading `.`.
+    fn parse_type_name(&mut self) -> Result<String> {
+        let mut name = String::new();
+        if self.try_consume(".") {
+            name.push('.');
+        }
+        name.push_str(&self.parse_full_ident("type name")?);
+        Ok(name)
+    }
+
+    /// Parses one or more adjacent string literals.
+    fn parse_string(&mut self, what: &str) -> Result<Vec<u8>> {
+        if self.peek().kind != TokenKind::String {
+            return Err(self.error(&format!("Expected {}.", what)));
+        }
+        let mut value = Vec::new();
+        while self.peek().kind == TokenKind::String {
+            let index = self.next();
+            let text = &self.tokens[index].text;
+            value.extend(unescape(text).map_err(|message| self.error_at(index, &message))?);
+        }
+        Ok(value)
+    }
+
+    /// Parses an unsigned integer literal no larger than `max`.
+    fn parse_integer(&mut self, max: u64, what: &str) -> Result<u64> {
+        if self.peek().kind != TokenKind::Int {
+            return Err(self.error(&format!("Expected {}.", what)));
+        }
+        let index = self.next();
+        match parse_int(&self.tokens[index].text) {
+            Some(value) if value <= max => Ok(value),
+            _ => Err(self.error_at(index, "Integer out of range.")),
+        }
+    }
+
+    /// Parses an optionally negative `int32` literal.
+    fn parse_int32(&mut self, what: &str) -> Result<i32> {
+        let negative = self.try_consume("-");
+        let max = i32::MAX as u64 + u64::from(negative);
+        let value = self.parse_integer(max, what)? as i64;
+        Ok(if negative { -value } else { value } as i32)
+    }
+
+    fn parse_message(&mut self, path: Vec<i32>) -> Result<DescriptorProto> {
+        let location = self.start_location(path.clone());
+        let start = self.expect("message")?;
+        let name = self.expect_ident("message name")?;
+        let mut message = DescriptorProto {
+            name: Some(name),
+            ..DescriptorProto::default()
+        };
+        let open = self.expect("{")?;
+        self.parse_message_body(&mut message, &path)?;
+        self.end_location(location, start, self.previous(), Some(open));
+        Ok(message)
+    }
+
+    /// Parses the statements of a message, after the opening brace.
+    fn parse_message_body(&mut self, message: &mut DescriptorProto, path: &[i32]) -> Result<()> {
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in message definition (missing '}')."));
+            }
+            if self.try_consume("}") {
+                break;
+            }
+            if self.try_consume(";") {
+                continue;
+            }
+            match &*self.peek().text {
+                "message" => {
+                    let nested_path = child_path(path, 3, message.nested_type.len());
+                    let nested = self.parse_message(nested_path)?;
+                    message.nested_type.push(nested);
+                }
+                "enum" => {
+                    let enum_path = child_path(path, 4, message.enum_type.len());
+                    let desc = self.parse_enum(enum_path)?;
+                    message.enum_type.push(desc);
+                }
+                "extensions" => self.parse_extensions(message, path)?,
+                "reserved" => self.parse_reserved(message, path)?,
+                "extend" => {
+                    self.parse_extend(
+                        &mut message.extension,
+                        field_path(path, 6),
+                        &mut message.nested_type,
+                        field_path(path, 3),
+                    )?;
+                }
+                "option" => {
+                    self.parse_option_statement(&mut message.options, field_path(path, 7))?;
+                }
+                "oneof" => self.parse_oneof(message, path)?,
+                _ => {
+                    let field_path = child_path(path, 2, message.field.len());
+                    let field = self.parse_field(FieldContext {
+                        path: field_path,
+                        nested_types: &mut message.nested_type,
+                        nested_types_path: self::field_path(path, 3),
+                        oneof_index: None,
+                        extendee: None,
+                    })?;
+                    message.field.push(field);
+                }
+            }
+        }
+
+        // Ranges ending at `max` end at the largest field number, unless the message uses the
+        // message set wire format.
+        let message_set =
+            matches!(&message.options, Some(options) if options.message_set_wire_format());
+        let max = if message_set {
+            i32::MAX
+        } else {
+            MAX_FIELD_NUMBER + 1
+        };
+        for range in &mut message.extension_range {
+            if range.end == Some(i32::MAX) {
+                range.end = Some(max);
+            }
+        }
+        for range in &mut message.reserved_range {
+            if range.end == Some(i32::MAX) {
+                range.end = Some(max);
+            }
+        }
+
+        self.add_synthetic_oneofs(message);
+        Ok(())
+    }
+
+    /// Adds a oneof for each proto3 `optional` field, after the declared oneofs.
+    fn add_synthetic_oneofs(&mut self, message: &mut DescriptorProto) {
+        let mut names: Vec<String> = message
+            .field
+            .iter()
+            .map(|field| field.name().to_string())
+            .chain(
+                message
+                    .oneof_decl
+                    .iter()
+                    .map(|oneof| oneof.name().to_string()),
+            )
+            .collect();
+        for field in &mut message.field {
+            if !field.proto3_optional() {
+                continue;
+            }
+            let mut name = field.name().to_string();
+            if !name.starts_with('_') {
+                name.insert(0, '_');
+            }
+            while names.contains(&name) {
+                name.insert(0, 'X');
+            }
+            names.push(name.clone());
+            field.oneof_index = Some(message.oneof_decl.len() as i32);
+            message.oneof_decl.push(OneofDescriptorProto {
+                name: Some(name),
+                options: None,
+            });
+        }
+    }
+
+    fn parse_oneof(&mut self, message: &mut DescriptorProto, path: &[i32]) -> Result<()> {
+        let oneof_index = message.oneof_decl.len();
+        let oneof_path = child_path(path, 8, oneof_index);
+        let location = self.start_location(oneof_path.clone());
+        let start = self.expect("oneof")?;
+        let name = self.expect_ident("oneof name")?;
+        let mut oneof = OneofDescriptorProto {
+            name: Some(name),
+            options: None,
+        };
+        let open = self.expect("{")?;
+        let mut has_fields = false;
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in oneof definition (missing '}')."));
+            }
+            if self.try_consume("}") {
+                break;
+            }
+            if self.try_consume(";") {
+                continue;
+            }
+            if self.looking_at("option") {
+                self.parse_option_statement(&mut oneof.options, field_path(&oneof_path, 2))?;
+                continue;
+            }
+            if matches!(&*self.peek().text, "required" | "optional" | "repeated") {
+                return Err(self.error(
+                    "Fields in oneofs must not have labels (required / optional / repeated).",
+                ));
+            }
+            let field_path = child_path(path, 2, message.field.len());
+            let field = self.parse_field(FieldContext {
+                path: field_path,
+                nested_types: &mut message.nested_type,
+                nested_types_path: self::field_path(path, 3),
+                oneof_index: Some(oneof_index as i32),
+                extendee: None,
+            })?;
+            message.field.push(field);
+            has_fields = true;
+        }
+        if !has_fields {
+            return Err(self.error_at(start, "Oneof must have at least one field."));
+        }
+        self.end_location(location, start, self.previous(), Some(open));
+        message.oneof_decl.push(oneof);
+        Ok(())
+    }
+
+    fn parse_field(&mut self, context: FieldContext<'_>) -> Result<FieldDescriptorProto> {
+        let location = self.start_location(context.path.clone());
+        let start = self.pos;
+        let mut field = FieldDescriptorProto {
+            oneof_index: context.oneof_index,
+            extendee: context.extendee.map(str::to_string),
+            ..FieldDescriptorProto::default()
+        };
+
+        let label = match &*self.peek().text {
+            "optional" => Some(Label::Optional),
+            "required" => Some(Label::Required),
+            "repeated" => Some(Label::Repeated),
+            _ => None,
+        };
+        if label.is_some() {
+            self.next();
+        }
+        if label == Some(Label::Required) && self.proto3 {
+            return Err(self.error_at(start, "Required fields are not allowed in proto3."));
+        }
+        if label == Some(Label::Optional) && self.proto3 {
+            field.proto3_optional = Some(true);
+        }
+
+        let is_map = self.looking_at("map") && self.peek_nth(1).text == "<";
+        if is_map {
+            if label.is_some() {
+                return Err(self.error_at(
+                    start,
+                    "Field labels (required/optional/repeated) are not allowed on map fields.",
+                ));
+            }
+            if context.oneof_index.is_some() {
+                return Err(self.error("Map fields are not allowed in oneofs."));
+            }
+            if context.extendee.is_some() {
+                return Err(self.error("Map fields are not allowed to be extensions."));
+            }
+        } else if label.is_none()
+            && !self.proto3
+            && context.oneof_index.is_none()
+            && !self.looking_at("map")
+        {
+            return Err(self.error("Expected \"required\", \"optional\", or \"repeated\"."));
+        }
+        field.set_label(label.unwrap_or(if is_map {
+            Label::Repeated
+        } else {
+            Label::Optional
+        }));
+
+        let mut map_types = None;
+        let mut is_group = false;
+        if is_map {
+            self.next();
+            self.expect("<")?;
+            let key = self.parse_field_type()?;
+            self.expect(",")?;
+            let value = self.parse_field_type()?;
+            self.expect(">")?;
+            if let (Some(key), _) = &key {
+                if matches!(
+                    key,
+                    Type::Double | Type::Float | Type::Bytes | Type::Message | Type::Group
+                ) {
+                    return Err(self.error_at(
+                        start,
+                        "Key in map fields cannot be float/double, bytes or message types.",
+                    ));
+                }
+            }
+            field.set_type(Type::Message);
+            map_types = Some((key, value));
+        } else if self.looking_at("group") {
+            if self.proto3 {
+                return Err(self.error("Groups are not supported in proto3 syntax."));
+            }
+            self.next();
+            field.set_type(Type::Group);
+            is_group = true;
+        } else {
+            match self.parse_field_type()? {
+                (Some(ty), _) => field.set_type(ty),
+                (None, type_name) => field.type_name = type_name,
+            }
+        }
+
+        let name_index = self.pos;
+        let name = self.expect_ident("field name")?;
+        if is_group {
+            if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return Err(
+                    self.error_at(name_index, "Group names must start with a capital letter.")
+                );
+            }
+            field.type_name = Some(name.clone());
+            field.name = Some(name.to_ascii_lowercase());
+        } else {
+            field.name = Some(name);
+        }
+
+        self.expect("=")?;
+        let number = self.parse_integer(i32::MAX as u64, "field number")?;
+        field.number = Some(number as i32);
+
+        if self.looking_at("[") {
+            self.parse_field_options(&mut field, is_group || is_map)?;
+        }
+        if field.json_name.is_none() {
+            field.json_name = Some(json_name(field.name()));
+        }
+
+        if let Some((key, value)) = map_types {
+            let entry_name = map_entry_name(field.name());
+            let entry_field = |name: &str, number: i32, (ty, type_name): (Option<Type>, _)| {
+                let mut entry_field = FieldDescriptorProto {
+                    name: Some(name.to_string()),
+                    number: Some(number),
+                    type_name,
+                    json_name: Some(name.to_string()),
+                    ..FieldDescriptorProto::default()
+                };
+                entry_field.set_label(Label::Optional);
+                if let Some(ty) = ty {
+                    entry_field.set_type(ty);
+                }
+                entry_field
+            };
+            context.nested_types.push(DescriptorProto {
+                name: Some(entry_name.clone()),
+                field: vec![entry_field("key", 1, key), entry_field("value", 2, value)],
+                options: Some(MessageOptions {
+                    map_entry: Some(true),
+                    ..MessageOptions::default()
+                }),
+                ..DescriptorProto::default()
+            });
+            field.type_name = Some(entry_name);
+        }
+
+        if is_group {
+            let mut group_path = context.nested_types_path.clone();
+            group_path.push(context.nested_types.len() as i32);
+            let group_location = self.start_location(group_path.clone());
+            let open = self.expect("{")?;
+            let mut group = DescriptorProto {
+                name: field.type_name.clone(),
+                ..DescriptorProto::default()
+            };
+            self.parse_message_body(&mut group, &group_path)?;
+            self.end_location(group_location, start, self.previous(), Some(open));
+            context.nested_types.push(group);
+            self.end_location(location, start, self.previous(), None);
+        } else {
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        }
+        Ok(field)
+    }
+
+    /// Parses the type of a field, returning either a scalar type or a type name.
+    fn parse_field_type(&mut self) -> Result<(Option<Type>, Option<String>)> {
+        if let Some(ty) = scalar_type(&self.peek().text) {
+            if self.peek().kind == TokenKind::Ident {
+                self.next();
+                return Ok((Some(ty), None));
+            }
+        }
+        if self.looking_at("group") || self.looking_at("map") {
+            return Err(self.error("Expected type name."));
+        }
+        Ok((None, Some(self.parse_type_name()?)))
+    }
+
+    fn parse_field_options(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        is_message: bool,
+    ) -> Result<()> {
+        self.expect("[")?;
+        loop {
+            let index = self.pos;
+            if self.looking_at("default") && self.peek_nth(1).text == "=" {
+                self.next();
+                self.next();
+                if field.default_value.is_some() {
+                    return Err(self.error_at(index, "Already set option \"default\"."));
+                }
+                if field.label() == Label::Repeated {
+                    return Err(self.error_at(index, "Repeated fields can't have default values."));
+                }
+                if is_message {
+                    return Err(self.error_at(index, "Messages can't have default values."));
+                }
+                field.default_value = Some(self.parse_default_value(field)?);
+            } else if self.looking_at("json_name") && self.peek_nth(1).text == "=" {
+                self.next();
+                self.next();
+                if field.extendee.is_some() {
+                    return Err(self.error_at(
+                        index,
+                        "option json_name is not allowed on extension fields.",
+                    ));
+                }
+                if field.json_name.is_some() {
+                    return Err(self.error_at(index, "Already set option \"json_name\"."));
+                }
+                let json_name = self.parse_string("string")?;
+                field.json_name = Some(String::from_utf8_lossy(&json_name).into_owned());
+            } else {
+                self.parse_option(&mut field.options)?;
+            }
+            if self.try_consume("]") {
+                return Ok(());
+            }
+            self.expect(",")?;
+        }
+    }
+
+    /// Parses the default value of a field, formatting it the same way as `protoc`.
+    fn parse_default_value(&mut self, field: &FieldDescriptorProto) -> Result<String> {
+        if field.r#type.is_none() {
+            // The type of the field is not known until it's resolved, so the value is checked
+            // then.
+            let index = self.next();
+            return Ok(self.tokens[index].text.clone());
+        }
+
+        let index = self.pos;
+        let value = match field.r#type() {
+            Type::Int32 | Type::Sint32 | Type::Sfixed32 => self.parse_int32("integer")?.to_string(),
+            Type::Int64 | Type::Sint64 | Type::Sfixed64 => {
+                let negative = self.try_consume("-");
+                let max = i64::MAX as u64 + u64::from(negative);
+                let value = self.parse_integer(max, "integer")?;
+                if negative {
+                    format!("-{}", value)
+                } else {
+                    value.to_string()
+                }
+            }
+            Type::Uint32 | Type::Fixed32 => {
+                self.parse_integer(u32::MAX.into(), "integer")?.to_string()
+            }
+            Type::Uint64 | Type::Fixed64 => self.parse_integer(u64::MAX, "integer")?.to_string(),
+            Type::Float | Type::Double => {
+                let negative = self.try_consume("-");
+                let value = self.parse_number()?;
+                format!(
+                    "{}{}",
+                    if negative { "-" } else { "" },
+                    format_float(value, field.r#type() == Type::Float)
+                )
+            }
+            Type::Bool => match &*self.peek().text {
+                "true" | "false" => {
+                    let index = self.next();
+                    self.tokens[index].text.clone()
+                }
+                _ => return Err(self.error("Expected \"true\" or \"false\".")),
+            },
+            Type::String => String::from_utf8_lossy(&self.parse_string("string")?).into_owned(),
+            Type::Bytes => c_escape(&self.parse_string("string")?),
+            Type::Enum => self.expect_ident("enum identifier")?,
+            Type::Message | Type::Group => {
+                return Err(self.error_at(index, "Messages can't have default values."))
+            }
+        };
+        Ok(value)
+    }
+
+    /// Parses an unsigned number, which may be an integer, a float, `inf` or `nan`.
+    fn parse_number(&mut self) -> Result<f64> {
+        let index = self.pos;
+        let token = &self.tokens[index];
+        let value = match token.kind {
+            TokenKind::Int => parse_int(&token.text).map(|value| value as f64),
+            TokenKind::Float => token.text.parse().ok(),
+            TokenKind::Ident if token.text == "inf" => Some(f64::INFINITY),
+            TokenKind::Ident if token.text == "nan" => Some(f64::NAN),
+            _ => return Err(self.error("Expected number.")),
+        };
+        self.next();
+        value.ok_or_else(|| self.error_at(index, "Integer out of range."))
+    }
+
+    fn parse_enum(&mut self, path: Vec<i32>) -> Result<EnumDescriptorProto> {
+        let location = self.start_location(path.clone());
+        let start = self.expect("enum")?;
+        let name = self.expect_ident("enum name")?;
+        let mut desc = EnumDescriptorProto {
+            name: Some(name),
+            ..EnumDescriptorProto::default()
+        };
+        let open = self.expect("{")?;
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in enum definition (missing '}')."));
+            }
+            if self.try_consume("}") {
+                break;
+            }
+            if self.try_consume(";") {
+                continue;
+            }
+            match &*self.peek().text {
+                "option" => self.parse_option_statement(&mut desc.options, field_path(&path, 3))?,
+                "reserved" => self.parse_enum_reserved(&mut desc, &path)?,
+                _ => {
+                    let value_path = child_path(&path, 2, desc.value.len());
+                    let value_location = self.start_location(value_path);
+                    let value_start = self.pos;
+                    let name = self.expect_ident("enum constant name")?;
+                    self.expect("=")?;
+                    let number = self.parse_int32("integer")?;
+                    let mut value = EnumValueDescriptorProto {
+                        name: Some(name),
+                        number: Some(number),
+                        options: None,
+                    };
+                    if self.try_consume("[") {
+                        loop {
+                            self.parse_option(&mut value.options)?;
+                            if self.try_consume("]") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+                    let end = self.expect(";")?;
+                    self.end_location(value_location, value_start, end, Some(end));
+                    desc.value.push(value);
+                }
+            }
+        }
+        self.end_location(location, start, self.previous(), Some(open));
+        Ok(desc)
+    }
+
+    fn parse_enum_reserved(&mut self, desc: &mut EnumDescriptorProto, path: &[i32]) -> Result<()> {
+        let start = self.expect("reserved")?;
+        if self.peek().kind == TokenKind::String {
+            let location = self.start_location(field_path(path, 5));
+            loop {
+                let index = self.pos;
+                let name = String::from_utf8_lossy(&self.parse_string("string")?).into_owned();
+                self.add_location(
+                    child_path(path, 5, desc.reserved_name.len()),
+                    index,
+                    index,
+                    false,
+                );
+                desc.reserved_name.push(name);
+                if !self.try_consume(",") {
+                    break;
+                }
+            }
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        } else {
+            let location = self.start_location(field_path(path, 4));
+            loop {
+                let range_start = self.pos;
+                let start_number = self.parse_int32("enum number range start")?;
+                let end_number = if self.try_consume("to") {
+                    if self.try_consume("max") {
+                        i32::MAX
+                    } else {
+                        self.parse_int32("enum number range end")?
+                    }
+                } else {
+                    start_number
+                };
+                let path = child_path(path, 4, desc.reserved_range.len());
+                self.add_location(path, range_start, self.previous(), false);
+                desc.reserved_range.push(EnumReservedRange {
+                    start: Some(start_number),
+                    end: Some(end_number),
+                });
+                if !self.try_consume(",") {
+                    break;
+                }
+            }
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        }
+        Ok(())
+    }
+
+    /// Parses a list of field number ranges, where the end of each range is exclusive.
+    fn parse_ranges(&mut self, path: &[i32], what: &str) -> Result<Vec<(i32, i32)>> {
+        let mut ranges = Vec::new();
+        loop {
+            let start = self.pos;
+            let start_number =
+                self.parse_integer(i32::MAX as u64, &format!("{} range start", what))?;
+            let end_number = if self.try_consume("to") {
+                if self.try_consume("max") {
+                    // The end of the range depends on the message's options, so it's adjusted
+                    // once the message has been parsed.
+                    i32::MAX as u64
+                } else {
+                    self.parse_integer(i32::MAX as u64, &format!("{} range end", what))?
+                }
+            } else {
+                start_number
+            };
+            let mut range_path = path.to_vec();
+            range_path.push(ranges.len() as i32);
+            self.add_location(range_path, start, self.previous(), false);
+            ranges.push((start_number as i32, (end_number as i32).saturating_add(1)));
+            if !self.try_consume(",") {
+                return Ok(ranges);
+            }
+        }
+    }
+
+    fn parse_extensions(&mut self, message: &mut DescriptorProto, path: &[i32]) -> Result<()> {
+        let location = self.start_location(field_path(path, 5));
+        let start = self.expect("extensions")?;
+        let first = message.extension_range.len();
+        let ranges = self.parse_ranges_from(&field_path(path, 5), first, "extension number")?;
+        let mut options = None;
+        if self.try_consume("[") {
+            loop {
+                self.parse_option(&mut options)?;
+                if self.try_consume("]") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        for (start, end) in ranges {
+            message.extension_range.push(ExtensionRange {
+                start: Some(start),
+                end: Some(end),
+                options: options.clone(),
+            });
+        }
+        let end = self.expect(";")?;
+        self.end_location(location, start, end, Some(end));
+        Ok(())
+    }
+
+    /// Like `parse_ranges`, but numbers the locations of the ranges from `first`.
+    fn parse_ranges_from(
+        &mut self,
+        path: &[i32],
+        first: usize,
+        what: &str,
+    ) -> Result<Vec<(i32, i32)>> {
+        let locations = self.locations.len();
+        let ranges = self.parse_ranges(path, what)?;
+        for location in &mut self.locations[locations..] {
+            if let Some(index) = location.path.last_mut() {
+                *index += first as i32;
+            }
+        }
+        Ok(ranges)
+    }
+
+    fn parse_reserved(&mut self, message: &mut DescriptorProto, path: &[i32]) -> Result<()> {
+        let start = self.expect("reserved")?;
+        if self.peek().kind == TokenKind::String {
+            let location = self.start_location(field_path(path, 10));
+            loop {
+                let index = self.pos;
+                let name = String::from_utf8_lossy(&self.parse_string("string")?).into_owned();
+                let name_path = child_path(path, 10, message.reserved_name.len());
+                self.add_location(name_path, index, index, false);
+                message.reserved_name.push(name);
+                if !self.try_consume(",") {
+                    break;
+                }
+            }
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        } else {
+            let location = self.start_location(field_path(path, 9));
+            let first = message.reserved_range.len();
+            let ranges = self.parse_ranges_from(&field_path(path, 9), first, "field number")?;
+            for (start, end) in ranges {
+                message.reserved_range.push(ReservedRange {
+                    start: Some(start),
+                    end: Some(end),
+                });
+            }
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        }
+        Ok(())
+    }
+
+    fn parse_extend(
+        &mut self,
+        extensions: &mut Vec<FieldDescriptorProto>,
+        extensions_path: Vec<i32>,
+        nested_types: &mut Vec<DescriptorProto>,
+        nested_types_path: Vec<i32>,
+    ) -> Result<()> {
+        let location = self.start_location(extensions_path.clone());
+        let start = self.expect("extend")?;
+        let extendee = self.parse_type_name()?;
+        let open = self.expect("{")?;
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in extend definition (missing '}')."));
+            }
+            if self.try_consume("}") {
+                break;
+            }
+            if self.try_consume(";") {
+                continue;
+            }
+            let mut path = extensions_path.clone();
+            path.push(extensions.len() as i32);
+            let field = self.parse_field(FieldContext {
+                path,
+                nested_types,
+                nested_types_path: nested_types_path.clone(),
+                oneof_index: None,
+                extendee: Some(&extendee),
+            })?;
+            extensions.push(field);
+        }
+        self.end_location(location, start, self.previous(), Some(open));
+        Ok(())
+    }
+
+    fn parse_service(&mut self, path: Vec<i32>) -> Result<ServiceDescriptorProto> {
+        let location = self.start_location(path.clone());
+        let start = self.expect("service")?;
+        let name = self.expect_ident("service name")?;
+        let mut service = ServiceDescriptorProto {
+            name: Some(name),
+            ..ServiceDescriptorProto::default()
+        };
+        let open = self.expect("{")?;
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in service definition (missing '}')."));
+            }
+            if self.try_consume("}") {
+                break;
+            }
+            if self.try_consume(";") {
+                continue;
+            }
+            if self.looking_at("option") {
+                self.parse_option_statement(&mut service.options, field_path(&path, 3))?;
+                continue;
+            }
+            let method_path = child_path(&path, 2, service.method.len());
+            let method = self.parse_method(method_path)?;
+            service.method.push(method);
+        }
+        self.end_location(location, start, self.previous(), Some(open));
+        Ok(service)
+    }
+
+    fn parse_method(&mut self, path: Vec<i32>) -> Result<MethodDescriptorProto> {
+        let location = self.start_location(path.clone());
+        let start = self.expect("rpc")?;
+        let name = self.expect_ident("method name")?;
+        let mut method = MethodDescriptorProto {
+            name: Some(name),
+            ..MethodDescriptorProto::default()
+        };
+
+        self.expect("(")?;
+        if self.try_consume("stream") {
+            method.client_streaming = Some(true);
+        }
+        method.input_type = Some(self.parse_type_name()?);
+        self.expect(")")?;
+        self.expect("returns")?;
+        self.expect("(")?;
+        if self.try_consume("stream") {
+            method.server_streaming = Some(true);
+        }
+        method.output_type = Some(self.parse_type_name()?);
+        self.expect(")")?;
+
+        if self.looking_at("{") {
+            let open = self.next();
+            method.options.get_or_insert_with(MethodOptions::default);
+            loop {
+                if self.at_end() {
+                    return Err(self.error("Reached end of input in method options (missing '}')."));
+                }
+                if self.try_consume("}") {
+                    break;
+                }
+                if self.try_consume(";") {
+                    continue;
+                }
+                self.parse_option_statement(&mut method.options, field_path(&path, 4))?;
+            }
+            self.end_location(location, start, self.previous(), Some(open));
+        } else {
+            let end = self.expect(";")?;
+            self.end_location(location, start, end, Some(end));
+        }
+        Ok(method)
+    }
+
+    /// Parses an `option name = value;` statement.
+    fn parse_option_statement<O>(&mut self, options: &mut Option<O>, path: Vec<i32>) -> Result<()>
+    where
+        O: Options,
+    {
+        let location = self.start_location(path.clone());
+        let start = self.expect("option")?;
+        let number = self.parse_option(options)?;
+        let end = self.expect(";")?;
+        self.end_location(location, start, end, None);
+        // The comments are attached to the location of the option's field, which is only known
+        // for standard options.
+        if let Some(number) = number {
+            let location = self.start_location(field_path(&path, number));
+            self.end_location(location, start, end, Some(end));
+        }
+        Ok(())
+    }
+
+    /// Parses a `name = value` option assignment, returning the field number of a standard
+    /// option.
+    fn parse_option<O>(&mut self, options: &mut Option<O>) -> Result<Option<i32>>
+    where
+        O: Options,
+    {
+        let start = self.pos;
+        let mut name = Vec::new();
+        loop {
+            if self.try_consume("(") {
+                let mut part = String::new();
+                if self.try_consume(".") {
+                    part.push('.');
+                }
+                part.push_str(&self.parse_full_ident("identifier")?);
+                self.expect(")")?;
+                name.push(NamePart {
+                    name_part: part,
+                    is_extension: true,
+                });
+            } else {
+                name.push(NamePart {
+                    name_part: self.expect_ident("identifier")?,
+                    is_extension: false,
+                });
+            }
+            if !self.try_consume(".") {
+                break;
+            }
+        }
+        self.expect("=")?;
+        let value = self.parse_option_value()?;
+
+        let options = options.get_or_insert_with(O::default);
+        if name.len() == 1 && !name[0].is_extension {
+            let number = set_option(options, &name[0].name_part, &value)
+                .map_err(|message| self.error_at(start, &message))?;
+            Ok(Some(number))
+        } else {
+            let mut option = UninterpretedOption {
+                name,
+                ..UninterpretedOption::default()
+            };
+            match value {
+                OptionValue::Identifier(value) => option.identifier_value = Some(value),
+                OptionValue::PositiveInt(value) => option.positive_int_value = Some(value),
+                OptionValue::NegativeInt(value) => option.negative_int_value = Some(value),
+                OptionValue::Double(value) => option.double_value = Some(value),
+                OptionValue::String(value) => option.string_value = Some(value),
+                OptionValue::Aggregate(value) => option.aggregate_value = Some(value),
+            }
+            options.uninterpreted_option().push(option);
+            Ok(None)
+        }
+    }
+
+    fn parse_option_value(&mut self) -> Result<OptionValue> {
+        let negative = self.try_consume("-");
+        let index = self.pos;
+        let token = &self.tokens[index];
+        let value = match token.kind {
+            TokenKind::Ident if negative && (token.text == "inf" || token.text == "nan") => {
+                OptionValue::Double(-self.parse_number()?)
+            }
+            TokenKind::Ident if !negative => {
+                self.next();
+                OptionValue::Identifier(self.tokens[index].text.clone())
+            }
+            TokenKind::Int if negative => {
+                let value = self.parse_integer(i64::MAX as u64 + 1, "integer")?;
+                OptionValue::NegativeInt((value as i64).wrapping_neg())
+            }
+            TokenKind::Int => OptionValue::PositiveInt(self.parse_integer(u64::MAX, "integer")?),
+            TokenKind::Float => {
+                let value = self.parse_number()?;
+                OptionValue::Double(if negative { -value } else { value })
+            }
+            TokenKind::String if !negative => OptionValue::String(self.parse_string("string")?),
+            TokenKind::Symbol if token.text == "{" && !negative => {
+                self.next();
+                let mut aggregate = String::new();
+                let mut depth = 1;
+                loop {
+                    if self.at_end() {
+                        return Err(
+                            self.error("Unexpected end of stream while parsing aggregate value.")
+                        );
+                    }
+                    if self.looking_at("{") {
+                        depth += 1;
+                    } else if self.looking_at("}") {
+                        depth -= 1;
+                        if depth == 0 {
+                            self.next();
+                            break;
+                        }
+                    }
+                    if !aggregate.is_empty() {
+                        aggregate.push(' ');
+                    }
+                    let index = self.next();
+                    aggregate.push_str(&self.tokens[index].text);
+                }
+                OptionValue::Aggregate(aggregate)
+            }
+            _ if negative => return Err(self.error("Expected number.")),
+            _ => return Err(self.error("Expected option value.")),
+        };
+        Ok(value)
+    }
+}
+
+/// Parses a decimal, hexadecimal or octal integer literal.
+fn parse_int(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if text.len() > 1 && text.starts_with('0') {
+        u64::from_str_radix(&text[1..], 8).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Sets a standard option, which is a field of the options message, returning its number.
+fn set_option(options: &mut dyn ReflectMessage, name: &str, value: &OptionValue) -> Result<i32> {
+    let field = options
+        .descriptor()
+        .field_by_name(name)
+        .filter(|field| !field.is_list())
+        .ok_or_else(|| format!("Option \"{}\" unknown.", name))?;
+    if options.has_field(field.number()) {
+        return Err(format!("Option \"{}\" was already set.", name));
+    }
+
+    let int_value = |min: i64, max: u64| match *value {
+        OptionValue::PositiveInt(value) if value <= max => Some(value as i64),
+        OptionValue::NegativeInt(value) if value >= min => Some(value),
+        _ => None,
+    };
+    let value = match field.kind() {
+        Kind::Bool => match value {
+            OptionValue::Identifier(value) if value == "true" => Some(Value::Bool(true)),
+            OptionValue::Identifier(value) if value == "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Kind::Enum => match value {
+            OptionValue::Identifier(value) => {
+                let desc = field.enum_type().expect("enum field without an enum type");
+                let number = desc.value_number(value).ok_or_else(|| {
+                    format!(
+                        "Enum type \"{}\" has no value named \"{}\" for option \"{}\".",
+                        desc.full_name(),
+                        value,
+                        name
+                    )
+                })?;
+                Some(Value::EnumNumber(number))
+            }
+            _ => None,
+        },
+        Kind::String => match value {
+            OptionValue::String(value) => {
+                Some(Value::String(String::from_utf8_lossy(value).into_owned()))
+            }
+            _ => None,
+        },
+        Kind::Bytes => match value {
+            OptionValue::String(value) => Some(Value::Bytes(value.clone())),
+            _ => None,
+        },
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            int_value(i32::MIN.into(), i32::MAX as u64).map(|value| Value::I32(value as i32))
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            int_value(i64::MIN, i64::MAX as u64).map(Value::I64)
+        }
+        Kind::Uint32 | Kind::Fixed32 => {
+            int_value(0, u32::MAX.into()).map(|value| Value::U32(value as u32))
+        }
+        Kind::Uint64 | Kind::Fixed64 => match *value {
+            OptionValue::PositiveInt(value) => Some(Value::U64(value)),
+            _ => None,
+        },
+        Kind::Float | Kind::Double => {
+            let value = match *value {
+                OptionValue::PositiveInt(value) => Some(value as f64),
+                OptionValue::NegativeInt(value) => Some(value as f64),
+                OptionValue::Double(value) => Some(value),
+                OptionValue::Identifier(ref value) if value == "inf" => Some(f64::INFINITY),
+                OptionValue::Identifier(ref value) if value == "nan" => Some(f64::NAN),
+                _ => None,
+            };
+            if field.kind() == Kind::Float {
+                value.map(|value| Value::F32(value as f32))
+            } else {
+                value.map(Value::F64)
+            }
+        }
+        Kind::Message | Kind::Group | Kind::Map(..) => {
+            return Err(format!("Option \"{}\" can't be set here.", name));
+        }
+    };
+    let value = value.ok_or_else(|| {
+        let full_name = format!("{}.{}", options.descriptor().full_name(), name);
+        match field.kind() {
+            Kind::Bool => format!(
+                "Value must be \"true\" or \"false\" for boolean option \"{}\".",
+                full_name
+            ),
+            Kind::Enum => format!(
+                "Value must be identifier for enum-valued option \"{}\".",
+                full_name
+            ),
+            Kind::String | Kind::Bytes => format!(
+                "Value must be quoted string for string option \"{}\".",
+                full_name
+            ),
+            Kind::Float | Kind::Double => {
+                format!("Value must be number for option \"{}\".", full_name)
+            }
+            _ => format!(
+                "Value must be integer in range for option \"{}\".",
+                full_name
+            ),
+        }
+    })?;
+    options
+        .set_field(field.number(), value)
+        .map_err(|error| error.to_string())?;
+    Ok(field.number() as i32)
+}
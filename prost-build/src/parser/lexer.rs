@@ -0,0 +1,526 @@
+//! Tokenizes `.proto` files.
+//!
+//! Comments are attached to tokens following the same rules as `protoc`, so that the leading,
+//! trailing and detached comments recorded in `SourceCodeInfo` match those it would produce.
+
+use std::mem;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum TokenKind {
+    Ident,
+    Int,
+    Float,
+    /// A string literal, whose text includes the quotes and escapes.
+    String,
+    Symbol,
+    End,
+}
+
+#[derive(Debug)]
+pub(super) struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    /// The zero-based line of the token.
+    pub line: i32,
+    /// The zero-based column of the token, with tabs advancing to the next multiple of 8.
+    pub column: i32,
+    /// The column following the end of the token. Tokens never span lines.
+    pub end_column: i32,
+    /// The comment attached to the start of the token.
+    pub leading_comments: Option<String>,
+    /// The comment attached to the end of the token.
+    pub trailing_comments: Option<String>,
+    /// The comments preceding the token which are not attached to it or to the previous token.
+    pub detached_comments: Vec<String>,
+}
+
+/// A tokenizer error, with its zero-based line and column.
+pub(super) type LexError = (i32, i32, String);
+
+pub(super) fn tokenize(source: &str) -> Result<Vec<Token>, LexError> {
+    let mut lexer = Lexer {
+        source: source.as_bytes(),
+        pos: 0,
+        line: 0,
+        column: 0,
+    };
+    let mut tokens: Vec<Token> = Vec::new();
+
+    loop {
+        let mut collector = CommentCollector::default();
+        if tokens.is_empty() {
+            lexer.skip_byte_order_mark();
+            collector.detached_from_previous = true;
+            lexer.collect_comments(&mut collector)?;
+        } else if lexer.collect_same_line_comment(&mut collector)? {
+            lexer.collect_comments(&mut collector)?;
+        } else {
+            lexer.skip_whitespace_and_comments()?;
+        }
+
+        let mut token = lexer.next_token()?;
+        if token.kind == TokenKind::End
+            || (token.kind == TokenKind::Symbol && matches!(&*token.text, "}" | "]" | ")"))
+        {
+            // At the end of a scope, comments can't be attached to the following token.
+            collector.flush();
+        }
+        if collector.has_comment {
+            token.leading_comments = Some(collector.buffer);
+        }
+        token.detached_comments = collector.detached;
+        if let Some(previous) = tokens.last_mut() {
+            previous.trailing_comments = collector.trailing;
+        }
+
+        let end = token.kind == TokenKind::End;
+        tokens.push(token);
+        if end {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Sorts the comments found between two tokens.
+#[derive(Default)]
+struct CommentCollector {
+    buffer: String,
+    has_comment: bool,
+    is_line_comment: bool,
+    /// Whether the buffered comment may still become the trailing comment of the previous token.
+    detached_from_previous: bool,
+    trailing: Option<String>,
+    detached: Vec<String>,
+}
+
+impl CommentCollector {
+    fn start_line_comment(&mut self) {
+        // Consecutive line comments are combined, but not line and block comments.
+        if self.has_comment && !self.is_line_comment {
+            self.flush();
+        }
+        self.has_comment = true;
+        self.is_line_comment = true;
+    }
+
+    fn start_block_comment(&mut self) {
+        self.flush();
+        self.has_comment = true;
+        self.is_line_comment = false;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.has_comment = false;
+    }
+
+    /// Completes the buffered comment, which is known not to be attached to the next token.
+    fn flush(&mut self) {
+        if !self.has_comment {
+            return;
+        }
+        let comment = mem::take(&mut self.buffer);
+        if self.detached_from_previous {
+            self.detached.push(comment);
+        } else {
+            self.trailing = Some(comment);
+            self.detached_from_previous = true;
+        }
+        self.has_comment = false;
+    }
+}
+
+enum CommentStart {
+    Line,
+    Block,
+    None,
+}
+
+struct Lexer<'a> {
+    source: &'a [u8],
+    pos: usize,
+    line: i32,
+    column: i32,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.pos).copied()
+    }
+
+    fn peek_next(&self) -> Option<u8> {
+        self.source.get(self.pos + 1).copied()
+    }
+
+    fn advance(&mut self) {
+        match self.source[self.pos] {
+            b'\n' => {
+                self.line += 1;
+                self.column = 0;
+            }
+            b'\t' => self.column += 8 - self.column % 8,
+            _ => self.column += 1,
+        }
+        self.pos += 1;
+    }
+
+    fn try_consume(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, LexError> {
+        Err((self.line, self.column, message.to_string()))
+    }
+
+    fn skip_byte_order_mark(&mut self) {
+        if self.source.starts_with(b"\xEF\xBB\xBF") {
+            self.pos += 3;
+        }
+    }
+
+    fn skip_whitespace_no_newline(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\x0B' | b'\x0C')) {
+            self.advance();
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), LexError> {
+        loop {
+            self.skip_whitespace_no_newline();
+            if self.try_consume(b'\n') {
+                continue;
+            }
+            match self.comment_start() {
+                CommentStart::Line => self.read_line_comment(None),
+                CommentStart::Block => self.read_block_comment(None)?,
+                CommentStart::None => return Ok(()),
+            }
+        }
+    }
+
+    fn comment_start(&mut self) -> CommentStart {
+        if self.peek() != Some(b'/') {
+            return CommentStart::None;
+        }
+        match self.peek_next() {
+            Some(b'/') => {
+                self.advance();
+                self.advance();
+                CommentStart::Line
+            }
+            Some(b'*') => {
+                self.advance();
+                self.advance();
+                CommentStart::Block
+            }
+            _ => CommentStart::None,
+        }
+    }
+
+    /// Reads the rest of a line comment, including the newline.
+    fn read_line_comment(&mut self, content: Option<&mut String>) {
+        let start = self.pos;
+        while !matches!(self.peek(), None | Some(b'\n')) {
+            self.advance();
+        }
+        self.try_consume(b'\n');
+        if let Some(content) = content {
+            content.push_str(&String::from_utf8_lossy(&self.source[start..self.pos]));
+        }
+    }
+
+    /// Reads the rest of a block comment, stripping the leading `*` of each line.
+    fn read_block_comment(&mut self, mut content: Option<&mut String>) -> Result<(), LexError> {
+        let mut start = self.pos;
+        loop {
+            while !matches!(self.peek(), None | Some(b'*' | b'/' | b'\n')) {
+                self.advance();
+            }
+            if self.try_consume(b'\n') {
+                if let Some(content) = content.as_mut() {
+                    content.push_str(&String::from_utf8_lossy(&self.source[start..self.pos]));
+                }
+                self.skip_whitespace_no_newline();
+                if self.try_consume(b'*') && self.try_consume(b'/') {
+                    return Ok(());
+                }
+                start = self.pos;
+            } else if self.peek() == Some(b'*') && self.peek_next() == Some(b'/') {
+                if let Some(content) = content.as_mut() {
+                    content.push_str(&String::from_utf8_lossy(&self.source[start..self.pos]));
+                }
+                self.advance();
+                self.advance();
+                return Ok(());
+            } else if self.peek() == Some(b'/') && self.peek_next() == Some(b'*') {
+                return self.error("\"/*\" inside block comment. Block comments cannot be nested.");
+            } else if self.peek().is_none() {
+                return self.error("End-of-file inside block comment.");
+            } else {
+                self.advance();
+            }
+        }
+    }
+
+    /// Collects a comment on the same line as the previous token, returning whether the next
+    /// token is on a later line.
+    fn collect_same_line_comment(
+        &mut self,
+        collector: &mut CommentCollector,
+    ) -> Result<bool, LexError> {
+        self.skip_whitespace_no_newline();
+        match self.comment_start() {
+            CommentStart::Line => {
+                collector.start_line_comment();
+                self.read_line_comment(Some(&mut collector.buffer));
+                collector.flush();
+                Ok(true)
+            }
+            CommentStart::Block => {
+                collector.start_block_comment();
+                self.read_block_comment(Some(&mut collector.buffer))?;
+                self.skip_whitespace_no_newline();
+                if self.try_consume(b'\n') {
+                    collector.flush();
+                    Ok(true)
+                } else {
+                    // The next token is on the same line, so it's unclear which token the comment
+                    // belongs to.
+                    collector.clear();
+                    Ok(false)
+                }
+            }
+            CommentStart::None => Ok(self.try_consume(b'\n')),
+        }
+    }
+
+    /// Collects the comments on the lines preceding the next token.
+    fn collect_comments(&mut self, collector: &mut CommentCollector) -> Result<(), LexError> {
+        loop {
+            self.skip_whitespace_no_newline();
+            match self.comment_start() {
+                CommentStart::Line => {
+                    collector.start_line_comment();
+                    self.read_line_comment(Some(&mut collector.buffer));
+                }
+                CommentStart::Block => {
+                    collector.start_block_comment();
+                    self.read_block_comment(Some(&mut collector.buffer))?;
+                    self.skip_whitespace_no_newline();
+                    self.try_consume(b'\n');
+                }
+                CommentStart::None => {
+                    if self.try_consume(b'\n') {
+                        // A blank line detaches the comments before it.
+                        collector.flush();
+                        collector.detached_from_previous = true;
+                    } else {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, LexError> {
+        let start = self.pos;
+        let (line, column) = (self.line, self.column);
+        let c = match self.peek() {
+            Some(c) => c,
+            None => {
+                return Ok(Token {
+                    kind: TokenKind::End,
+                    text: String::new(),
+                    line,
+                    column,
+                    end_column: column,
+                    leading_comments: None,
+                    trailing_comments: None,
+                    detached_comments: Vec::new(),
+                })
+            }
+        };
+
+        let kind = if c.is_ascii_alphabetic() || c == b'_' {
+            while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'_') {
+                self.advance();
+            }
+            TokenKind::Ident
+        } else if c.is_ascii_digit()
+            || (c == b'.' && matches!(self.peek_next(), Some(c) if c.is_ascii_digit()))
+        {
+            self.read_number()?
+        } else if c == b'"' || c == b'\'' {
+            self.read_string(c)?;
+            TokenKind::String
+        } else if c.is_ascii_graphic() {
+            self.advance();
+            TokenKind::Symbol
+        } else {
+            return self.error("Invalid control characters encountered in text.");
+        };
+
+        Ok(Token {
+            kind,
+            text: String::from_utf8_lossy(&self.source[start..self.pos]).into_owned(),
+            line,
+            column,
+            end_column: self.column,
+            leading_comments: None,
+            trailing_comments: None,
+            detached_comments: Vec::new(),
+        })
+    }
+
+    fn read_number(&mut self) -> Result<TokenKind, LexError> {
+        let mut kind = TokenKind::Int;
+        if self.peek() == Some(b'0') && matches!(self.peek_next(), Some(b'x' | b'X')) {
+            self.advance();
+            self.advance();
+            if !matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+                return self.error("\"0x\" must be followed by hex digits.");
+            }
+            while matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+                self.advance();
+            }
+        } else if self.peek() == Some(b'0') && matches!(self.peek_next(), Some(b'0'..=b'9')) {
+            while matches!(self.peek(), Some(b'0'..=b'7')) {
+                self.advance();
+            }
+            if matches!(self.peek(), Some(b'8' | b'9')) {
+                return self.error("Numbers starting with leading zero must be in octal.");
+            }
+        } else {
+            while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                self.advance();
+            }
+            if self.try_consume(b'.') {
+                kind = TokenKind::Float;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                    self.advance();
+                }
+            }
+            if matches!(self.peek(), Some(b'e' | b'E')) {
+                kind = TokenKind::Float;
+                self.advance();
+                if !self.try_consume(b'-') {
+                    self.try_consume(b'+');
+                }
+                if !matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                    return self.error("\"e\" must be followed by exponent.");
+                }
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                    self.advance();
+                }
+            }
+        }
+
+        if matches!(self.peek(), Some(c) if c.is_ascii_alphabetic() || c == b'_' || c == b'.') {
+            return self.error("Need space between number and identifier.");
+        }
+        Ok(kind)
+    }
+
+    fn read_string(&mut self, quote: u8) -> Result<(), LexError> {
+        self.advance();
+        loop {
+            match self.peek() {
+                None => return self.error("Unexpected end of string."),
+                Some(b'\n') => return self.error("String literals cannot cross line boundaries."),
+                Some(b'\\') => {
+                    self.advance();
+                    if self.peek().is_some() {
+                        self.advance();
+                    }
+                }
+                Some(c) => {
+                    self.advance();
+                    if c == quote {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolves the escapes in the text of a string literal token, including its quotes.
+pub(super) fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let bytes = &text.as_bytes()[1..text.len() - 1];
+    let mut value = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        pos += 1;
+        if c != b'\\' {
+            value.push(c);
+            continue;
+        }
+
+        let escape = bytes[pos];
+        pos += 1;
+        match escape {
+            b'a' => value.push(0x07),
+            b'b' => value.push(0x08),
+            b'f' => value.push(0x0C),
+            b'n' => value.push(b'\n'),
+            b'r' => value.push(b'\r'),
+            b't' => value.push(b'\t'),
+            b'v' => value.push(0x0B),
+            b'\\' | b'?' | b'\'' | b'"' => value.push(escape),
+            b'0'..=b'7' => {
+                let mut code = u32::from(escape - b'0');
+                let mut digits = 1;
+                while digits < 3 && matches!(bytes.get(pos), Some(b'0'..=b'7')) {
+                    code = code * 8 + u32::from(bytes[pos] - b'0');
+                    pos += 1;
+                    digits += 1;
+                }
+                // Like protoc, octal escapes are truncated to a byte.
+                value.push(code as u8);
+            }
+            b'x' | b'X' => {
+                let (code, len) = parse_hex(&bytes[pos..], 2);
+                if len == 0 {
+                    return Err("Expected hex digits for escape sequence.".to_string());
+                }
+                pos += len;
+                value.push(code as u8);
+            }
+            b'u' | b'U' => {
+                let digits = if escape == b'u' { 4 } else { 8 };
+                let (code, len) = parse_hex(&bytes[pos..], digits);
+                let c = match char::from_u32(code) {
+                    Some(c) if len == digits => c,
+                    _ => return Err("Expected a valid unicode escape sequence.".to_string()),
+                };
+                pos += len;
+                let mut buf = [0; 4];
+                value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => return Err("Invalid escape sequence in string literal.".to_string()),
+        }
+    }
+    Ok(value)
+}
+
+/// Parses up to `max` hex digits, returning the value and the number of digits.
+fn parse_hex(bytes: &[u8], max: usize) -> (u32, usize) {
+    let mut code = 0;
+    let mut len = 0;
+    while len < max {
+        match bytes.get(len).and_then(|&c| char::from(c).to_digit(16)) {
+            Some(digit) => {
+                code = code * 16 + digit;
+                len += 1;
+            }
+            None => break,
+        }
+    }
+    (code, len)
+}
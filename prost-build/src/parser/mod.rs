@@ -0,0 +1,458 @@
+//! A native parser for `.proto` files, which can be used instead of `protoc`.
+//!
+//! The files are parsed into the same `FileDescriptorSet` that `protoc --include_imports
+//! --include_source_info` writes, with the exception that custom options are left
+//! uninterpreted.
+
+mod grammar;
+mod lexer;
+mod resolve;
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+
+/// Parses `protos` and the files they import, which are searched for in `includes`.
+///
+/// The returned files are ordered such that every file follows its imports.
+pub(crate) fn parse_files(
+    protos: &[impl AsRef<Path>],
+    includes: &[impl AsRef<Path>],
+) -> Result<FileDescriptorSet> {
+    let mut loader = Loader {
+        includes: includes
+            .iter()
+            .map(|path| path.as_ref().to_owned())
+            .collect(),
+        files: Vec::new(),
+        stack: Vec::new(),
+    };
+    // Set the protoc include after the user includes in case the user wants to override one of
+    // the built-in .protos.
+    loader.includes.push(crate::protoc_include());
+
+    let result = protos.iter().try_for_each(|proto| {
+        let name = loader.virtual_path(proto.as_ref())?;
+        loader.load(&name, None)
+    });
+    result
+        .and_then(|()| resolve::resolve(&mut loader.files))
+        .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+
+    Ok(FileDescriptorSet { file: loader.files })
+}
+
+struct Loader {
+    includes: Vec<PathBuf>,
+    files: Vec<FileDescriptorProto>,
+    /// The names of the files being loaded, which are used to detect recursive imports.
+    stack: Vec<String>,
+}
+
+/// Returns the path relative to `base` with `/` separators, if `path` is within `base`.
+fn relative_path(path: &Path, base: &Path) -> Option<String> {
+    let components = path
+        .strip_prefix(base)
+        .ok()?
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<&str>>>()?;
+    Some(components.join("/"))
+}
+
+impl Loader {
+    /// Returns the name of a file passed on the command line, which is its path relative to the
+    /// include directory which contains it.
+    fn virtual_path(&self, proto: &Path) -> std::result::Result<String, String> {
+        if let Ok(path) = proto.canonicalize() {
+            for include in &self.includes {
+                if let Ok(include) = include.canonicalize() {
+                    if let Some(name) = relative_path(&path, &include) {
+                        return Ok(name);
+                    }
+                }
+            }
+            Err(format!(
+                "{}: File does not reside within any path specified using --proto_path (or \
+                 -I).  You must specify a --proto_path which encompasses this file.",
+                proto.display()
+            ))
+        } else {
+            // The path may already be relative to an include directory.
+            match relative_path(proto, Path::new("")) {
+                Some(name) if self.find(&name).is_some() => Ok(name),
+                _ => Err(format!("{}: No such file or directory", proto.display())),
+            }
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        self.includes
+            .iter()
+            .map(|include| include.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the file named `name` after the files it imports. `import` is the file and position
+    /// of the import statement, if the file is being loaded as an import.
+    fn load(
+        &mut self,
+        name: &str,
+        import: Option<(&str, (i32, i32))>,
+    ) -> std::result::Result<(), String> {
+        if self.files.iter().any(|file| file.name() == name) {
+            return Ok(());
+        }
+        if let Some(start) = self.stack.iter().position(|loading| loading == name) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(name.to_string());
+            let (file, (line, column)) = import.unwrap_or((name, (0, 0)));
+            return Err(format!(
+                "{}:{}:{}: File recursively imports itself: {}",
+                file,
+                line + 1,
+                column + 1,
+                cycle.join(" -> ")
+            ));
+        }
+
+        let path = match self.find(name) {
+            Some(path) => path,
+            None => {
+                return Err(match import {
+                    Some((file, (line, column))) => format!(
+                        "{}: File not found.\n{}:{}:{}: Import \"{}\" was not found or had \
+                         errors.",
+                        name,
+                        file,
+                        line + 1,
+                        column + 1,
+                        name
+                    ),
+                    None => format!("{}: File not found.", name),
+                });
+            }
+        };
+        let source =
+            fs::read_to_string(&path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let file = grammar::parse(name, &source)?;
+
+        self.stack.push(name.to_string());
+        for (index, dependency) in file.dependency.iter().enumerate() {
+            let position = file
+                .source_code_info
+                .iter()
+                .flat_map(|info| &info.location)
+                .find(|location| location.path == [3, index as i32])
+                .map_or((0, 0), |location| (location.span[0], location.span[1]));
+            self.load(dependency, Some((name, position)))?;
+        }
+        self.stack.pop();
+
+        self.files.push(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::process::Command;
+
+    use prost::Message;
+    use prost_types::source_code_info::Location;
+    use prost_types::DescriptorProto;
+
+    use super::*;
+    use crate::Config;
+
+    const INCLUDES: &[&str] = &["src", "../tests/src"];
+
+    fn protoc(protos: &[&str], includes: &[&str]) -> FileDescriptorSet {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("descriptor-set");
+        let mut cmd = Command::new(crate::protoc());
+        cmd.arg("--include_imports")
+            .arg("--include_source_info")
+            .arg("-o")
+            .arg(&path);
+        for include in includes {
+            cmd.arg("-I").arg(include);
+        }
+        cmd.arg("-I").arg(crate::protoc_include()).args(protos);
+        let output = cmd.output().unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        FileDescriptorSet::decode(&*fs::read(path).unwrap()).unwrap()
+    }
+
+    /// Clears the custom options, which `protoc` interprets as extensions of the options messages,
+    /// and the native parser leaves uninterpreted.
+    fn clear_uninterpreted_options(file: &mut FileDescriptorProto) {
+        macro_rules! clear {
+            ($options:expr) => {
+                if let Some(options) = $options.as_mut() {
+                    options.uninterpreted_option.clear();
+                    options._extensions.clear();
+                }
+            };
+        }
+        fn clear_message(message: &mut DescriptorProto) {
+            clear!(message.options);
+            message
+                .field
+                .iter_mut()
+                .for_each(|field| clear!(field.options));
+            message
+                .extension
+                .iter_mut()
+                .for_each(|field| clear!(field.options));
+            message
+                .oneof_decl
+                .iter_mut()
+                .for_each(|oneof| clear!(oneof.options));
+            for range in &mut message.extension_range {
+                clear!(range.options);
+            }
+            for desc in &mut message.enum_type {
+                clear!(desc.options);
+                desc.value
+                    .iter_mut()
+                    .for_each(|value| clear!(value.options));
+            }
+            message.nested_type.iter_mut().for_each(clear_message);
+        }
+
+        clear!(file.options);
+        file.message_type.iter_mut().for_each(clear_message);
+        file.extension
+            .iter_mut()
+            .for_each(|field| clear!(field.options));
+        for desc in &mut file.enum_type {
+            clear!(desc.options);
+            desc.value
+                .iter_mut()
+                .for_each(|value| clear!(value.options));
+        }
+        for service in &mut file.service {
+            clear!(service.options);
+            service
+                .method
+                .iter_mut()
+                .for_each(|method| clear!(method.options));
+        }
+    }
+
+    /// Checks that the native parser produces the same descriptors as `protoc`, and that every
+    /// location it records matches a location recorded by `protoc`.
+    fn check(protos: &[&str], includes: &[&str]) {
+        let mut expected = protoc(protos, includes);
+        let mut actual = parse_files(protos, includes).unwrap();
+
+        let names = |set: &FileDescriptorSet| {
+            set.file
+                .iter()
+                .map(|file| file.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&actual), names(&expected));
+
+        for (actual, expected) in actual.file.iter_mut().zip(&mut expected.file) {
+            let expected_locations = expected.source_code_info.take().unwrap().location;
+            let actual_locations = actual.source_code_info.take().unwrap().location;
+            for location in &actual_locations {
+                assert!(
+                    expected_locations.contains(location),
+                    "{}: unexpected location {:?}\nexpected one of {:#?}",
+                    actual.name(),
+                    location,
+                    expected_locations
+                        .iter()
+                        .filter(|expected| expected.path == location.path)
+                        .collect::<Vec<&Location>>()
+                );
+            }
+            clear_uninterpreted_options(actual);
+            clear_uninterpreted_options(expected);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    /// Generates code for `protos` with either `protoc` or the native parser.
+    fn generate(protos: &[&str], native: bool) -> BTreeMap<String, String> {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::new();
+        config.out_dir(tmp.path());
+        if native {
+            config.native_parser();
+        }
+        config.compile_protos(protos, INCLUDES).unwrap();
+        fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let name = entry.file_name().into_string().unwrap();
+                (name, fs::read_to_string(entry.path()).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_protoc() {
+        let mut protos = vec![
+            "src/hello.proto",
+            "src/goodbye.proto",
+            "src/smoke_test.proto",
+            "src/types.proto",
+            "src/custom_options.proto",
+        ];
+        let tests: Vec<String> = fs::read_dir("../tests/src")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension() == Some("proto".as_ref()))
+            .map(|path| path.to_str().unwrap().to_string())
+            .collect();
+        protos.extend(tests.iter().map(String::as_str));
+        for proto in &protos {
+            check(&[proto], INCLUDES);
+        }
+
+        for packages in ["../tests/src/packages", "../tests/src/no_root_packages"] {
+            check(&["widget_factory.proto"], &[packages]);
+        }
+        for proto in ["any", "api", "descriptor", "struct", "compiler/plugin"] {
+            check(&[&format!("google/protobuf/{}.proto", proto)], INCLUDES);
+        }
+    }
+
+    #[test]
+    fn generates_same_code_as_protoc() {
+        let protos = &["src/smoke_test.proto", "../tests/src/json.proto"];
+        assert_eq!(generate(protos, true), generate(protos, false));
+    }
+
+    #[test]
+    fn comments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let proto = tmp.path().join("comments.proto");
+        fs::write(
+            &proto,
+            r#"// Detached.
+
+// Syntax.
+syntax = "proto3"; // Trailing syntax.
+
+/* Block
+ * comment. */
+message Foo { // Trailing Foo.
+  // Leading bar.
+  int32 bar = 1;
+  /// Doc.
+  map<string, Foo> baz = 2; // Trailing baz.
+
+  // Detached before oneof.
+
+  oneof qux {
+    string a = 3;
+  }
+  optional bool b = 4;
+}
+
+enum Kind { UNKNOWN = 0; }
+"#,
+        )
+        .unwrap();
+        let includes = &[tmp.path()];
+        let mut expected = {
+            let set_path = tmp.path().join("set");
+            let output = Command::new(crate::protoc())
+                .arg("--include_source_info")
+                .arg("-o")
+                .arg(&set_path)
+                .arg("-I")
+                .arg(tmp.path())
+                .arg(&proto)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            FileDescriptorSet::decode(&*fs::read(set_path).unwrap()).unwrap()
+        };
+        let mut actual = parse_files(&[&proto], includes).unwrap();
+        let locations = |set: &mut FileDescriptorSet| {
+            let mut locations = set.file[0].source_code_info.take().unwrap().location;
+            locations.retain(|location| location.path.len() % 2 == 0);
+            locations.sort_by(|a, b| a.path.cmp(&b.path));
+            locations
+        };
+        assert_eq!(locations(&mut actual), locations(&mut expected));
+        assert_eq!(actual, expected);
+    }
+
+    fn error(source: &str) -> String {
+        let tmp = tempfile::tempdir().unwrap();
+        let proto = tmp.path().join("error.proto");
+        fs::write(&proto, source).unwrap();
+        parse_files(&[&proto], &[tmp.path()])
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo {\n  int32 a = 1\n}\n"),
+            "error.proto:4:1: Expected \";\"."
+        );
+        assert_eq!(
+            error("syntax = \"proto4\";"),
+            "error.proto:1:10: Unrecognized syntax identifier \"proto4\".  This parser only \
+             recognizes \"proto2\" and \"proto3\"."
+        );
+        assert_eq!(
+            error("message Foo { int32 a = 1; }"),
+            "error.proto:1:15: Expected \"required\", \"optional\", or \"repeated\"."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { Bar a = 1; }"),
+            "error.proto:2:15: \"Bar\" is not defined."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { int32 a = 1; int32 b = 1; }"),
+            "error.proto:2:28: Field number 1 has already been used in \"Foo\" by field \"a\"."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { int32 a = 1; string a = 2; }"),
+            "error.proto:2:28: \"a\" is already defined in \"Foo\"."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nenum E { A = 1; }"),
+            "error.proto:2:10: The first enum value must be zero in proto3."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nimport \"missing.proto\";"),
+            "missing.proto: File not found.\nerror.proto:2:1: Import \"missing.proto\" was not \
+             found or had errors."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { int32 a = 1 [default = 1]; }"),
+            "error.proto:2:15: Explicit default values are not allowed in proto3."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { int32 a = 1 [deprecated = yes]; }"),
+            "error.proto:2:28: Value must be \"true\" or \"false\" for boolean option \
+             \"google.protobuf.FieldOptions.deprecated\"."
+        );
+        assert_eq!(
+            error("syntax = \"proto3\";\nmessage Foo { string s = 1; /* unterminated"),
+            "error.proto:2:44: End-of-file inside block comment."
+        );
+    }
+}
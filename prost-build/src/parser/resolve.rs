@@ -0,0 +1,815 @@
+//! Resolves the type names of parsed files, and checks the rules which span more than a single
+//! declaration.
+
+use std::collections::{HashMap, HashSet};
+
+use prost_types::field_descriptor_proto::Type;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+
+use super::grammar::MAX_FIELD_NUMBER;
+
+type Result<T> = std::result::Result<T, String>;
+
+enum SymbolKind {
+    Package,
+    Message {
+        extension_ranges: Vec<(i32, i32)>,
+    },
+    Enum {
+        values: Vec<String>,
+        closed: bool,
+    },
+    Service,
+    /// Fields, oneofs, enum values and methods.
+    Other,
+}
+
+struct Symbol {
+    kind: SymbolKind,
+    /// The files defining the symbol. Only packages are defined by more than one file.
+    files: Vec<usize>,
+}
+
+impl Symbol {
+    fn is_type(&self) -> bool {
+        matches!(
+            self.kind,
+            SymbolKind::Message { .. } | SymbolKind::Enum { .. }
+        )
+    }
+
+    fn is_aggregate(&self) -> bool {
+        !matches!(self.kind, SymbolKind::Other)
+    }
+}
+
+enum Lookup<'a> {
+    Found(&'a Symbol),
+    /// The symbol is defined in a file which isn't imported.
+    NotImported(usize),
+    Missing,
+}
+
+/// Resolves the type names of `files`, which must be ordered such that every file follows its
+/// imports.
+pub(super) fn resolve(files: &mut [FileDescriptorProto]) -> Result<()> {
+    let mut resolver = Resolver::new(files);
+    for (index, file) in files.iter().enumerate() {
+        resolver.add_file(index, file)?;
+    }
+    for (index, file) in files.iter_mut().enumerate() {
+        resolver.resolve_file(index, file)?;
+    }
+    let mut extensions = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        resolver.check_file(index, file, &mut extensions)?;
+    }
+    Ok(())
+}
+
+fn full_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn child_path(path: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field);
+    path.push(index as i32);
+    path
+}
+
+struct Resolver {
+    names: Vec<String>,
+    /// The start of the span of each location, by file and path.
+    positions: Vec<HashMap<Vec<i32>, (i32, i32)>>,
+    /// The files visible from each file: itself, its imports, and their public imports.
+    visible: Vec<HashSet<usize>>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl Resolver {
+    fn new(files: &[FileDescriptorProto]) -> Resolver {
+        let names: Vec<String> = files.iter().map(|file| file.name().to_string()).collect();
+        let indices: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (&**name, index))
+            .collect();
+
+        let positions = files
+            .iter()
+            .map(|file| {
+                file.source_code_info
+                    .iter()
+                    .flat_map(|info| &info.location)
+                    .filter(|location| location.span.len() >= 3)
+                    .map(|location| (location.path.clone(), (location.span[0], location.span[1])))
+                    .collect()
+            })
+            .collect();
+
+        fn add_public(
+            files: &[FileDescriptorProto],
+            indices: &HashMap<&str, usize>,
+            index: usize,
+            visible: &mut HashSet<usize>,
+        ) {
+            for &public in &files[index].public_dependency {
+                let dependency = &files[index].dependency[public as usize];
+                if let Some(&dependency) = indices.get(&**dependency) {
+                    if visible.insert(dependency) {
+                        add_public(files, indices, dependency, visible);
+                    }
+                }
+            }
+        }
+        let visible = files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let mut visible = HashSet::new();
+                visible.insert(index);
+                for dependency in &file.dependency {
+                    if let Some(&dependency) = indices.get(&**dependency) {
+                        visible.insert(dependency);
+                        add_public(files, &indices, dependency, &mut visible);
+                    }
+                }
+                visible
+            })
+            .collect();
+
+        Resolver {
+            names,
+            positions,
+            visible,
+            symbols: HashMap::new(),
+        }
+    }
+
+    fn error(&self, file: usize, path: &[i32], message: &str) -> String {
+        match self.positions[file].get(path) {
+            Some((line, column)) => {
+                format!(
+                    "{}:{}:{}: {}",
+                    self.names[file],
+                    line + 1,
+                    column + 1,
+                    message
+                )
+            }
+            None => format!("{}: {}", self.names[file], message),
+        }
+    }
+
+    fn add_symbol(
+        &mut self,
+        file: usize,
+        name: String,
+        kind: SymbolKind,
+        path: &[i32],
+    ) -> Result<()> {
+        if let Some(existing) = self.symbols.get(&name) {
+            let message = if existing.files[0] != file {
+                format!(
+                    "\"{}\" is already defined in file \"{}\".",
+                    name, self.names[existing.files[0]]
+                )
+            } else {
+                match name.rfind('.') {
+                    Some(dot) => format!(
+                        "\"{}\" is already defined in \"{}\".",
+                        &name[dot + 1..],
+                        &name[..dot]
+                    ),
+                    None => format!("\"{}\" is already defined.", name),
+                }
+            };
+            return Err(self.error(file, path, &message));
+        }
+        self.symbols.insert(
+            name,
+            Symbol {
+                kind,
+                files: vec![file],
+            },
+        );
+        Ok(())
+    }
+
+    fn add_file(&mut self, index: usize, file: &FileDescriptorProto) -> Result<()> {
+        let package = file.package();
+        if !package.is_empty() {
+            for (end, _) in package
+                .match_indices('.')
+                .chain(std::iter::once((package.len(), "")))
+            {
+                let name = &package[..end];
+                match self.symbols.get_mut(name) {
+                    Some(Symbol {
+                        kind: SymbolKind::Package,
+                        files,
+                    }) => {
+                        if !files.contains(&index) {
+                            files.push(index);
+                        }
+                    }
+                    Some(symbol) => {
+                        let message = format!(
+                            "\"{}\" is already defined (as something other than a package) in \
+                             file \"{}\".",
+                            name, self.names[symbol.files[0]]
+                        );
+                        return Err(self.error(index, &[2], &message));
+                    }
+                    None => self.add_symbol(index, name.to_string(), SymbolKind::Package, &[2])?,
+                }
+            }
+        }
+
+        let proto3 = file.syntax() == "proto3";
+        for (i, message) in file.message_type.iter().enumerate() {
+            self.add_message(index, package, message, &[4, i as i32], proto3)?;
+        }
+        for (i, desc) in file.enum_type.iter().enumerate() {
+            self.add_enum(index, package, desc, &[5, i as i32], proto3)?;
+        }
+        for (i, extension) in file.extension.iter().enumerate() {
+            let name = full_name(package, extension.name());
+            self.add_symbol(index, name, SymbolKind::Other, &[7, i as i32])?;
+        }
+        for (i, service) in file.service.iter().enumerate() {
+            let path = [6, i as i32];
+            let name = full_name(package, service.name());
+            for (j, method) in service.method.iter().enumerate() {
+                let method_name = full_name(&name, method.name());
+                let method_path = child_path(&path, 2, j);
+                self.add_symbol(index, method_name, SymbolKind::Other, &method_path)?;
+            }
+            self.add_symbol(index, name, SymbolKind::Service, &path)?;
+        }
+        Ok(())
+    }
+
+    fn add_message(
+        &mut self,
+        file: usize,
+        scope: &str,
+        message: &DescriptorProto,
+        path: &[i32],
+        proto3: bool,
+    ) -> Result<()> {
+        let name = full_name(scope, message.name());
+        let extension_ranges = message
+            .extension_range
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect();
+        self.add_symbol(
+            file,
+            name.clone(),
+            SymbolKind::Message { extension_ranges },
+            path,
+        )?;
+        for (i, field) in message.field.iter().enumerate() {
+            let field_name = full_name(&name, field.name());
+            self.add_symbol(file, field_name, SymbolKind::Other, &child_path(path, 2, i))?;
+        }
+        for (i, oneof) in message.oneof_decl.iter().enumerate() {
+            let oneof_name = full_name(&name, oneof.name());
+            self.add_symbol(file, oneof_name, SymbolKind::Other, &child_path(path, 8, i))?;
+        }
+        for (i, nested) in message.nested_type.iter().enumerate() {
+            self.add_message(file, &name, nested, &child_path(path, 3, i), proto3)?;
+        }
+        for (i, desc) in message.enum_type.iter().enumerate() {
+            self.add_enum(file, &name, desc, &child_path(path, 4, i), proto3)?;
+        }
+        for (i, extension) in message.extension.iter().enumerate() {
+            let extension_name = full_name(&name, extension.name());
+            self.add_symbol(
+                file,
+                extension_name,
+                SymbolKind::Other,
+                &child_path(path, 6, i),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn add_enum(
+        &mut self,
+        file: usize,
+        scope: &str,
+        desc: &EnumDescriptorProto,
+        path: &[i32],
+        proto3: bool,
+    ) -> Result<()> {
+        let name = full_name(scope, desc.name());
+        let values = desc
+            .value
+            .iter()
+            .map(|value| value.name().to_string())
+            .collect();
+        self.add_symbol(
+            file,
+            name.clone(),
+            SymbolKind::Enum {
+                values,
+                closed: !proto3,
+            },
+            path,
+        )?;
+        // Enum values are siblings of their enum, rather than children of it.
+        for (i, value) in desc.value.iter().enumerate() {
+            let value_name = full_name(scope, value.name());
+            self.add_symbol(file, value_name, SymbolKind::Other, &child_path(path, 2, i))
+                .map_err(|error| {
+                    format!(
+                        "{}  Note that enum values use C++ scoping rules, meaning that enum \
+                         values are siblings of their type, not children of it.  Therefore, \
+                         \"{}\" must be unique within \"{}\", not just within \"{}\".",
+                        error,
+                        value.name(),
+                        scope,
+                        desc.name()
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    fn find(&self, file: usize, name: &str) -> Lookup<'_> {
+        match self.symbols.get(name) {
+            Some(symbol) if symbol.files.iter().any(|f| self.visible[file].contains(f)) => {
+                Lookup::Found(symbol)
+            }
+            Some(symbol) => Lookup::NotImported(symbol.files[0]),
+            None => Lookup::Missing,
+        }
+    }
+
+    /// Looks up `name` relative to the element named `relative_to`, searching the innermost scope
+    /// first, and returns the symbol's full name.
+    fn lookup(
+        &self,
+        file: usize,
+        name: &str,
+        relative_to: &str,
+        types_only: bool,
+    ) -> Result<(String, &Symbol)> {
+        let not_found = |not_imported: Option<usize>| match not_imported {
+            Some(defined_in) => format!(
+                "\"{}\" seems to be defined in \"{}\", which is not imported by \"{}\".  To use \
+                 it here, please add the necessary import.",
+                name, self.names[defined_in], self.names[file]
+            ),
+            None => format!("\"{}\" is not defined.", name),
+        };
+
+        if let Some(stripped) = name.strip_prefix('.') {
+            return match self.find(file, stripped) {
+                Lookup::Found(symbol) => Ok((stripped.to_string(), symbol)),
+                Lookup::NotImported(defined_in) => Err(not_found(Some(defined_in))),
+                Lookup::Missing => Err(not_found(None)),
+            };
+        }
+
+        let first = name.split('.').next().unwrap_or(name);
+        let mut scope = relative_to;
+        let mut not_imported = None;
+        while let Some(dot) = scope.rfind('.') {
+            scope = &scope[..dot];
+            let candidate = format!("{}.{}", scope, first);
+            match self.find(file, &candidate) {
+                Lookup::Found(symbol) if first.len() < name.len() => {
+                    if symbol.is_aggregate() {
+                        // Only the first part of a compound name was found, so the rest must be
+                        // defined within it.
+                        let resolved = format!("{}.{}", scope, name);
+                        return match self.find(file, &resolved) {
+                            Lookup::Found(symbol) => Ok((resolved, symbol)),
+                            _ => Err(format!(
+                                "\"{}\" is resolved to \"{}\", which is not defined. The \
+                                 innermost scope is searched first in name resolution. Consider \
+                                 using a leading '.'(i.e., \".{}\") to start from the outermost \
+                                 scope.",
+                                name, resolved, name
+                            )),
+                        };
+                    }
+                }
+                Lookup::Found(symbol) => {
+                    if !types_only || symbol.is_type() {
+                        return Ok((candidate, symbol));
+                    }
+                }
+                Lookup::NotImported(defined_in) => not_imported = Some(defined_in),
+                Lookup::Missing => (),
+            }
+        }
+        match self.find(file, name) {
+            Lookup::Found(symbol) => Ok((name.to_string(), symbol)),
+            Lookup::NotImported(defined_in) => Err(not_found(Some(defined_in))),
+            Lookup::Missing => Err(not_found(not_imported)),
+        }
+    }
+
+    /// Looks up the name of a message type.
+    fn lookup_message(
+        &self,
+        file: usize,
+        name: &str,
+        relative_to: &str,
+        path: &[i32],
+    ) -> Result<String> {
+        match self.lookup(file, name, relative_to, false) {
+            Ok((full_name, symbol)) => match symbol.kind {
+                SymbolKind::Message { .. } => Ok(format!(".{}", full_name)),
+                _ => Err(self.error(file, path, &format!("\"{}\" is not a message type.", name))),
+            },
+            Err(message) => Err(self.error(file, path, &message)),
+        }
+    }
+
+    fn resolve_file(&self, index: usize, file: &mut FileDescriptorProto) -> Result<()> {
+        let package = file.package().to_string();
+        for (i, message) in file.message_type.iter_mut().enumerate() {
+            self.resolve_message(index, &package, message, &[4, i as i32])?;
+        }
+        for (i, extension) in file.extension.iter_mut().enumerate() {
+            let name = full_name(&package, extension.name());
+            self.resolve_field(index, &name, extension, &[7, i as i32])?;
+        }
+        for (i, service) in file.service.iter_mut().enumerate() {
+            let service_name = full_name(&package, service.name());
+            for (j, method) in service.method.iter_mut().enumerate() {
+                let name = full_name(&service_name, method.name());
+                let path = [6, i as i32, 2, j as i32];
+                let input_type = self.lookup_message(index, method.input_type(), &name, &path)?;
+                let output_type = self.lookup_message(index, method.output_type(), &name, &path)?;
+                method.input_type = Some(input_type);
+                method.output_type = Some(output_type);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_message(
+        &self,
+        file: usize,
+        scope: &str,
+        message: &mut DescriptorProto,
+        path: &[i32],
+    ) -> Result<()> {
+        let name = full_name(scope, message.name());
+        for (i, field) in message.field.iter_mut().enumerate() {
+            let field_name = full_name(&name, field.name());
+            self.resolve_field(file, &field_name, field, &child_path(path, 2, i))?;
+        }
+        for (i, extension) in message.extension.iter_mut().enumerate() {
+            let extension_name = full_name(&name, extension.name());
+            self.resolve_field(file, &extension_name, extension, &child_path(path, 6, i))?;
+        }
+        for (i, nested) in message.nested_type.iter_mut().enumerate() {
+            self.resolve_message(file, &name, nested, &child_path(path, 3, i))?;
+        }
+        Ok(())
+    }
+
+    fn resolve_field(
+        &self,
+        file: usize,
+        name: &str,
+        field: &mut FieldDescriptorProto,
+        path: &[i32],
+    ) -> Result<()> {
+        if let Some(type_name) = field.type_name.take() {
+            let (full_name, symbol) = self
+                .lookup(file, &type_name, name, true)
+                .map_err(|message| self.error(file, path, &message))?;
+            match symbol.kind {
+                SymbolKind::Message { .. } => {
+                    if field.r#type.is_none() {
+                        field.set_type(Type::Message);
+                    }
+                }
+                SymbolKind::Enum { .. } if field.r#type.is_none() => field.set_type(Type::Enum),
+                SymbolKind::Enum { .. } => {
+                    let message = format!("\"{}\" is not a message type.", type_name);
+                    return Err(self.error(file, path, &message));
+                }
+                _ => {
+                    let message = format!("\"{}\" is not a type.", type_name);
+                    return Err(self.error(file, path, &message));
+                }
+            }
+            field.type_name = Some(format!(".{}", full_name));
+        }
+        if let Some(extendee) = field.extendee.take() {
+            field.extendee = Some(self.lookup_message(file, &extendee, name, path)?);
+        }
+        Ok(())
+    }
+
+    fn check_file(
+        &self,
+        index: usize,
+        file: &FileDescriptorProto,
+        extensions: &mut HashMap<(String, i32), (String, usize)>,
+    ) -> Result<()> {
+        let proto3 = file.syntax() == "proto3";
+        let package = file.package();
+        for (i, message) in file.message_type.iter().enumerate() {
+            self.check_message(index, proto3, package, message, &[4, i as i32], extensions)?;
+        }
+        for (i, desc) in file.enum_type.iter().enumerate() {
+            self.check_enum(index, proto3, package, desc, &[5, i as i32])?;
+        }
+        for (i, extension) in file.extension.iter().enumerate() {
+            let path = [7, i as i32];
+            let name = full_name(package, extension.name());
+            self.check_field(index, proto3, None, extension, &path)?;
+            self.check_extension(index, &name, extension, &path, extensions)?;
+        }
+        Ok(())
+    }
+
+    fn check_message(
+        &self,
+        file: usize,
+        proto3: bool,
+        scope: &str,
+        message: &DescriptorProto,
+        path: &[i32],
+        extensions: &mut HashMap<(String, i32), (String, usize)>,
+    ) -> Result<()> {
+        let name = full_name(scope, message.name());
+        if proto3 && !message.extension_range.is_empty() {
+            let message = "Extension ranges are not allowed in proto3.";
+            return Err(self.error(file, &child_path(path, 5, 0), message));
+        }
+
+        let mut numbers = HashMap::new();
+        for (i, field) in message.field.iter().enumerate() {
+            let field_path = child_path(path, 2, i);
+            let error = |message: String| self.error(file, &field_path, &message);
+            self.check_field(file, proto3, Some(&name), field, &field_path)?;
+
+            let number = field.number();
+            if let Some(other) = numbers.insert(number, field.name()) {
+                return Err(error(format!(
+                    "Field number {} has already been used in \"{}\" by field \"{}\".",
+                    number, name, other
+                )));
+            }
+            if message
+                .reserved_range
+                .iter()
+                .any(|range| range.start() <= number && number < range.end())
+            {
+                return Err(error(format!(
+                    "Field \"{}\" uses reserved number {}.",
+                    field.name(),
+                    number
+                )));
+            }
+            if message
+                .reserved_name
+                .iter()
+                .any(|name| name == field.name())
+            {
+                return Err(error(format!(
+                    "Field name \"{}\" is reserved.",
+                    field.name()
+                )));
+            }
+            if let Some(range) = message
+                .extension_range
+                .iter()
+                .find(|range| range.start() <= number && number < range.end())
+            {
+                return Err(error(format!(
+                    "Extension range {} to {} includes field \"{}\" ({}).",
+                    range.start(),
+                    range.end() - 1,
+                    field.name(),
+                    number
+                )));
+            }
+
+            let entry = message.nested_type.iter().find(|nested| {
+                nested.options.as_ref().map(|options| options.map_entry()) == Some(true)
+                    && field.type_name() == format!(".{}.{}", name, nested.name())
+            });
+            if let Some(entry) = entry {
+                if entry.field[0].r#type() == Type::Enum {
+                    return Err(error("Key in map fields cannot be enum types.".to_string()));
+                }
+            }
+        }
+
+        let message_set =
+            matches!(&message.options, Some(options) if options.message_set_wire_format());
+        for (i, range) in message.extension_range.iter().enumerate() {
+            if range.start() <= 0 || (range.end() > MAX_FIELD_NUMBER + 1 && !message_set) {
+                let message = format!(
+                    "Extension numbers must be between 1 and {}.",
+                    MAX_FIELD_NUMBER
+                );
+                return Err(self.error(file, &child_path(path, 5, i), &message));
+            }
+        }
+
+        for (i, nested) in message.nested_type.iter().enumerate() {
+            let nested_path = child_path(path, 3, i);
+            self.check_message(file, proto3, &name, nested, &nested_path, extensions)?;
+        }
+        for (i, desc) in message.enum_type.iter().enumerate() {
+            self.check_enum(file, proto3, &name, desc, &child_path(path, 4, i))?;
+        }
+        for (i, extension) in message.extension.iter().enumerate() {
+            let extension_path = child_path(path, 6, i);
+            let extension_name = full_name(&name, extension.name());
+            self.check_field(file, proto3, None, extension, &extension_path)?;
+            self.check_extension(
+                file,
+                &extension_name,
+                extension,
+                &extension_path,
+                extensions,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks a field, where `message` is the name of the containing message unless the field
+    /// is an extension.
+    fn check_field(
+        &self,
+        file: usize,
+        proto3: bool,
+        message: Option<&str>,
+        field: &FieldDescriptorProto,
+        path: &[i32],
+    ) -> Result<()> {
+        let error = |message: String| self.error(file, path, &message);
+
+        let number = field.number();
+        if number <= 0 {
+            return Err(error(
+                "Field numbers must be positive integers.".to_string(),
+            ));
+        }
+        if number > MAX_FIELD_NUMBER {
+            return Err(error(format!(
+                "Field numbers cannot be greater than {}.",
+                MAX_FIELD_NUMBER
+            )));
+        }
+        if (19000..=19999).contains(&number) {
+            return Err(error(format!(
+                "Field numbers 19000 through 19999 are reserved for the protocol buffer library \
+                 implementation. (Field \"{}\" uses {}.)",
+                field.name(),
+                number
+            )));
+        }
+        if proto3 && field.default_value.is_some() {
+            return Err(error(
+                "Explicit default values are not allowed in proto3.".to_string(),
+            ));
+        }
+
+        match field.r#type() {
+            Type::Enum => {
+                let enum_name = &field.type_name()[1..];
+                if let Some(Symbol {
+                    kind: SymbolKind::Enum { values, closed },
+                    ..
+                }) = self.symbols.get(enum_name)
+                {
+                    if let (true, true, Some(message)) = (proto3, *closed, message) {
+                        return Err(error(format!(
+                            "Enum type \"{}\" is not a proto3 enum, but is used in \"{}\" which \
+                             is a proto3 message type.",
+                            enum_name, message
+                        )));
+                    }
+                    if let Some(default) = &field.default_value {
+                        if !values.contains(default) {
+                            return Err(error(format!(
+                                "Enum type \"{}\" has no value named \"{}\".",
+                                enum_name, default
+                            )));
+                        }
+                    }
+                }
+            }
+            Type::Message | Type::Group if field.default_value.is_some() => {
+                return Err(error("Messages can't have default values.".to_string()));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn check_extension(
+        &self,
+        file: usize,
+        name: &str,
+        extension: &FieldDescriptorProto,
+        path: &[i32],
+        extensions: &mut HashMap<(String, i32), (String, usize)>,
+    ) -> Result<()> {
+        let extendee = &extension.extendee()[1..];
+        let number = extension.number();
+        if let Some(Symbol {
+            kind: SymbolKind::Message { extension_ranges },
+            ..
+        }) = self.symbols.get(extendee)
+        {
+            if !extension_ranges
+                .iter()
+                .any(|&(start, end)| start <= number && number < end)
+            {
+                let message = format!(
+                    "\"{}\" does not declare {} as an extension number.",
+                    extendee, number
+                );
+                return Err(self.error(file, path, &message));
+            }
+        }
+        if let Some((other, other_file)) =
+            extensions.insert((extendee.to_string(), number), (name.to_string(), file))
+        {
+            let message = format!(
+                "Extension number {} has already been used in \"{}\" by extension \"{}\" \
+                 defined in \"{}\".",
+                number, extendee, other, self.names[other_file]
+            );
+            return Err(self.error(file, path, &message));
+        }
+        Ok(())
+    }
+
+    fn check_enum(
+        &self,
+        file: usize,
+        proto3: bool,
+        scope: &str,
+        desc: &EnumDescriptorProto,
+        path: &[i32],
+    ) -> Result<()> {
+        if desc.value.is_empty() {
+            let message = "Enums must contain at least one value.";
+            return Err(self.error(file, path, message));
+        }
+        if proto3 && desc.value[0].number() != 0 {
+            let message = "The first enum value must be zero in proto3.";
+            return Err(self.error(file, &child_path(path, 2, 0), message));
+        }
+
+        let allow_alias = matches!(&desc.options, Some(options) if options.allow_alias());
+        let mut numbers = HashMap::new();
+        for (i, value) in desc.value.iter().enumerate() {
+            let value_path = child_path(path, 2, i);
+            let error = |message: String| self.error(file, &value_path, &message);
+            let number = value.number();
+            if let Some(other) = numbers.insert(number, value.name()) {
+                if !allow_alias {
+                    return Err(error(format!(
+                        "\"{}\" uses the same enum value as \"{}\". If this is intended, set \
+                         'option allow_alias = true;' to the enum definition.",
+                        full_name(scope, value.name()),
+                        full_name(scope, other)
+                    )));
+                }
+            }
+            if desc
+                .reserved_range
+                .iter()
+                .any(|range| range.start() <= number && number <= range.end())
+            {
+                return Err(error(format!(
+                    "Enum value \"{}\" uses reserved number {}.",
+                    value.name(),
+                    number
+                )));
+            }
+            if desc.reserved_name.iter().any(|name| name == value.name()) {
+                return Err(error(format!(
+                    "Enum value \"{}\" is reserved.",
+                    value.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
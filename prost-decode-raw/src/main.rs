@@ -0,0 +1,142 @@
+//! Prints the structure of an encoded Protobuf message without its schema, like
+//! `protoc --decode_raw`.
+//!
+//! The message is read from the file given as an argument, or from stdin if there's no argument.
+//! With `--descriptor-set <file> --type <name>`, fields are labelled with the names declared by
+//! the message type in the `FileDescriptorSet`.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use prost_types::dynamic::DescriptorPool;
+use prost_types::raw::{decode_raw, decode_raw_with_descriptor};
+
+const USAGE: &str = "usage: prost-decode-raw [--descriptor-set <file> --type <name>] [<file>]";
+
+fn main() {
+    if let Err(error) = run(env::args().skip(1)) {
+        eprintln!("prost-decode-raw: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run<I>(args: I) -> Result<(), String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args = Args::parse(args)?;
+
+    let buf = match args.input {
+        Some(ref path) => fs::read(path).map_err(|error| format!("{}: {}", path, error))?,
+        None => {
+            let mut buf = Vec::new();
+            io::stdin()
+                .read_to_end(&mut buf)
+                .map_err(|error| format!("failed to read stdin: {}", error))?;
+            buf
+        }
+    };
+
+    let message = match args.descriptor_set {
+        Some(ref path) => {
+            let set = fs::read(path).map_err(|error| format!("{}: {}", path, error))?;
+            let pool = DescriptorPool::decode(&*set).map_err(|error| error.to_string())?;
+            let name = args.message_type.as_deref().unwrap_or_default();
+            let desc = pool
+                .get_message_by_name(name.trim_start_matches('.'))
+                .ok_or_else(|| format!("message type {} not found in {}", name, path))?;
+            decode_raw_with_descriptor(&buf, &desc)
+        }
+        None => decode_raw(&buf),
+    }
+    .map_err(|error| format!("failed to decode message: {}", error))?;
+
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", message)
+        .and_then(|()| stdout.flush())
+        .map_err(|error| error.to_string())
+}
+
+/// The command line arguments.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    descriptor_set: Option<String>,
+    message_type: Option<String>,
+    input: Option<String>,
+}
+
+impl Args {
+    fn parse<I>(args: I) -> Result<Args, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let option = match &*arg {
+                "--descriptor-set" => &mut parsed.descriptor_set,
+                "--type" => &mut parsed.message_type,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("unknown option: {}\n{}", arg, USAGE))
+                }
+                _ if parsed.input.is_none() => {
+                    // `-` reads from stdin, as if no file was given.
+                    if arg != "-" {
+                        parsed.input = Some(arg);
+                    }
+                    continue;
+                }
+                _ => return Err(format!("unexpected argument: {}\n{}", arg, USAGE)),
+            };
+            match args.next() {
+                Some(value) => *option = Some(value),
+                None => return Err(format!("option {} requires a value\n{}", arg, USAGE)),
+            }
+        }
+        if parsed.descriptor_set.is_some() != parsed.message_type.is_some() {
+            return Err(format!(
+                "--descriptor-set and --type must be used together\n{}",
+                USAGE
+            ));
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(parse(&[]).unwrap(), Args::default());
+        assert_eq!(parse(&["-"]).unwrap(), Args::default());
+        assert_eq!(
+            parse(&[
+                "--type",
+                ".pkg.Msg",
+                "--descriptor-set",
+                "set.bin",
+                "msg.bin"
+            ])
+            .unwrap(),
+            Args {
+                descriptor_set: Some("set.bin".to_string()),
+                message_type: Some(".pkg.Msg".to_string()),
+                input: Some("msg.bin".to_string()),
+            }
+        );
+
+        assert!(parse(&["--type"]).is_err());
+        assert!(parse(&["--type", "pkg.Msg"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["a.bin", "b.bin"]).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Code generation for the `DiffMessage` derive.
+
+use anyhow::{bail, Error};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Data, DataEnum, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Ident, Variant,
+};
+
+use crate::field::{self, map, scalar, Field, Label};
+use crate::reflect::to_snake;
+
+pub fn try_diff_message(input: DeriveInput) -> Result<TokenStream, Error> {
+    let ident = input.ident;
+    if input.generics.lifetimes().next().is_some() {
+        bail!(
+            "DiffMessage can not be derived for borrowed message {}",
+            ident
+        );
+    }
+    match input.data {
+        Data::Struct(variant_data) => diff_struct(ident, variant_data),
+        Data::Enum(DataEnum { variants, .. }) => diff_oneof(ident, variants),
+        Data::Union(..) => bail!("DiffMessage can not be derived for a union"),
+    }
+}
+
+fn diff_struct(ident: Ident, variant_data: DataStruct) -> Result<TokenStream, Error> {
+    let fields = match variant_data.fields {
+        Fields::Named(FieldsNamed { named: fields, .. })
+        | Fields::Unnamed(FieldsUnnamed {
+            unnamed: fields, ..
+        }) => fields.into_iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let mut next_tag: u32 = 1;
+    let mut diffs = Vec::new();
+    for (idx, field) in fields.into_iter().enumerate() {
+        let field_ident = field
+            .ident
+            .unwrap_or_else(|| Ident::new(&idx.to_string(), Span::call_site()));
+        if field::unknown_fields_attr(&field.attrs)?
+            || field::extensions_attr(&field.attrs)?.is_some()
+        {
+            continue;
+        }
+        let name = match field::name_attr(&field.attrs)? {
+            Some(name) => name,
+            None => field_ident.to_string().trim_start_matches("r#").to_owned(),
+        };
+        let field = match Field::new(field.attrs, Some(next_tag))? {
+            Some(field) => field,
+            None => continue,
+        };
+        next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
+
+        let diff = match field {
+            Field::Oneof(ref oneof) => {
+                let ty = &oneof.ty;
+                quote! {
+                    <#ty as ::prost::diff::DiffOneof>::diff_oneof(
+                        self.#field_ident.as_ref(),
+                        other.#field_ident.as_ref(),
+                        differ,
+                    );
+                }
+            }
+            // Converted fields are compared by their wire representation.
+            Field::Converted(ref converted) => {
+                let old = converted.to_diff_wire(quote!(self.#field_ident));
+                let new = converted.to_diff_wire(quote!(other.#field_ident));
+                let old_ref = field_to_value_ref(&converted.inner, quote!(old), true);
+                let new_ref = field_to_value_ref(&converted.inner, quote!(new), true);
+                quote! {
+                    {
+                        let old = #old;
+                        let new = #new;
+                        differ.diff_field(#name, #old_ref, #new_ref);
+                    }
+                }
+            }
+            _ => {
+                let old_ref = field_to_value_ref(&field, quote!(self.#field_ident), false);
+                let new_ref = field_to_value_ref(&field, quote!(other.#field_ident), false);
+                quote!(differ.diff_field(#name, #old_ref, #new_ref);)
+            }
+        };
+        diffs.push(diff);
+    }
+
+    Ok(quote! {
+        impl ::prost::diff::DiffMessage for #ident {
+            #[allow(unused_variables)]
+            fn diff_fields(
+                &self,
+                other: &dyn ::prost::diff::DiffMessage,
+                differ: &mut dyn ::prost::diff::Differ,
+            ) {
+                let other = match other.downcast_ref::<Self>() {
+                    ::core::option::Option::Some(other) => other,
+                    ::core::option::Option::None => return,
+                };
+                #(#diffs)*
+            }
+
+            fn clone_boxed(&self) -> ::prost::alloc::boxed::Box<dyn ::prost::diff::DiffMessage> {
+                ::prost::alloc::boxed::Box::new(::core::clone::Clone::clone(self))
+            }
+
+            fn eq_dyn(&self, other: &dyn ::prost::diff::DiffMessage) -> bool {
+                match other.downcast_ref::<Self>() {
+                    ::core::option::Option::Some(other) => self == other,
+                    ::core::option::Option::None => false,
+                }
+            }
+
+            fn as_any(&self) -> &dyn ::core::any::Any {
+                self
+            }
+        }
+    })
+}
+
+fn diff_oneof(
+    ident: Ident,
+    variants: syn::punctuated::Punctuated<Variant, syn::token::Comma>,
+) -> Result<TokenStream, Error> {
+    let mut diffs = Vec::new();
+    for Variant {
+        attrs,
+        ident: variant_ident,
+        fields: variant_fields,
+        ..
+    } in variants
+    {
+        let name = match field::name_attr(&attrs)? {
+            Some(name) => name,
+            None => to_snake(&variant_ident.to_string()),
+        };
+        match variant_fields {
+            Fields::Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => (),
+            _ => bail!("Oneof variants must have a single unnamed field"),
+        }
+        let to_value_ref = match Field::new_oneof(attrs)? {
+            Some(Field::Scalar(ref scalar)) => scalar_to_value_ref(&scalar.ty),
+            Some(_) => message_to_value_ref(),
+            None => bail!("invalid oneof variant: oneof variants may not be ignored"),
+        };
+        let value_ref = |oneof: TokenStream| {
+            quote! {
+                match #oneof {
+                    ::core::option::Option::Some(#ident::#variant_ident(value)) => {
+                        ::core::option::Option::Some(#to_value_ref)
+                    }
+                    _ => ::core::option::Option::None,
+                }
+            }
+        };
+        let old_ref = value_ref(quote!(old));
+        let new_ref = value_ref(quote!(new));
+        diffs.push(quote!(differ.diff_field(#name, #old_ref, #new_ref);));
+    }
+
+    Ok(quote! {
+        impl ::prost::diff::DiffOneof for #ident {
+            fn diff_oneof(
+                old: ::core::option::Option<&Self>,
+                new: ::core::option::Option<&Self>,
+                differ: &mut dyn ::prost::diff::Differ,
+            ) {
+                #(#diffs)*
+            }
+        }
+    })
+}
+
+/// Returns the `ValueRef` variant which holds values of the scalar type.
+fn value_ref_variant(ty: &scalar::Ty) -> TokenStream {
+    let variant = match *ty {
+        scalar::Ty::Double => quote!(F64),
+        scalar::Ty::Float => quote!(F32),
+        scalar::Ty::Int32 | scalar::Ty::Sint32 | scalar::Ty::Sfixed32 => quote!(I32),
+        scalar::Ty::Int64 | scalar::Ty::Sint64 | scalar::Ty::Sfixed64 => quote!(I64),
+        scalar::Ty::Uint32 | scalar::Ty::Fixed32 => quote!(U32),
+        scalar::Ty::Uint64 | scalar::Ty::Fixed64 => quote!(U64),
+        scalar::Ty::Bool => quote!(Bool),
+        scalar::Ty::String(..) => quote!(String),
+        scalar::Ty::Bytes(..) => quote!(Bytes),
+        scalar::Ty::Enumeration(..) => quote!(EnumNumber),
+    };
+    quote!(::prost::diff::ValueRef::#variant)
+}
+
+/// Returns an expression which converts a reference to a scalar, bound to `value`, to a
+/// `ValueRef`.
+fn scalar_to_value_ref(ty: &scalar::Ty) -> TokenStream {
+    let variant = value_ref_variant(ty);
+    match *ty {
+        scalar::Ty::String(..) => quote!(#variant(::core::convert::AsRef::<str>::as_ref(value))),
+        scalar::Ty::Bytes(..) => quote!(#variant(::core::convert::AsRef::<[u8]>::as_ref(value))),
+        _ => quote!(#variant(*value)),
+    }
+}
+
+/// Returns an expression which converts a reference to a message, bound to `value`, to a
+/// `ValueRef`.
+fn message_to_value_ref() -> TokenStream {
+    quote!(::prost::diff::ValueRef::Message(value))
+}
+
+/// Returns an expression which evaluates to the `ValueRef` of the field `member` if the field is
+/// set, and to `None` otherwise.
+///
+/// If `wire` is `true`, `member` holds the wire representation of a converted field as
+/// `::prost::diff::Wire` values, which are dereferenced to the values of the field.
+fn field_to_value_ref(field: &Field, member: TokenStream, wire: bool) -> TokenStream {
+    let single = if wire {
+        quote!((*#member))
+    } else {
+        member.clone()
+    };
+    let (to_value_ref, label) = match *field {
+        Field::Scalar(ref scalar) => {
+            let to_value_ref = scalar_to_value_ref(&scalar.ty);
+            let label = match scalar.kind {
+                // Fields without presence are set if they differ from their default value.
+                scalar::Kind::Plain(ref default) => {
+                    let default = default.typed();
+                    let value = scalar.ty.as_ref(single.clone());
+                    return quote! {
+                        if #value != #default {
+                            let value = &#single;
+                            ::core::option::Option::Some(#to_value_ref)
+                        } else {
+                            ::core::option::Option::None
+                        }
+                    };
+                }
+                scalar::Kind::Required(..) => Label::Required,
+                scalar::Kind::Optional(..) => Label::Optional,
+                scalar::Kind::Repeated | scalar::Kind::Packed => Label::Repeated,
+            };
+            (to_value_ref, label)
+        }
+        Field::Message(ref message) => (message_to_value_ref(), message.label),
+        Field::Group(ref group) => (message_to_value_ref(), group.label),
+        Field::Map(ref map) => {
+            let key_to_value_ref = scalar_to_value_ref(&map.key_ty);
+            let value_to_value_ref = match map.value_ty {
+                map::ValueTy::Scalar(ref ty) => scalar_to_value_ref(ty),
+                map::ValueTy::Message => message_to_value_ref(),
+            };
+            return quote! {
+                if #member.is_empty() {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(::prost::diff::ValueRef::Map(
+                        #member
+                            .iter()
+                            .map(|(key, value)| {
+                                let key_value_ref = {
+                                    let value = key;
+                                    #key_to_value_ref
+                                };
+                                (key_value_ref, #value_to_value_ref)
+                            })
+                            .collect(),
+                    ))
+                }
+            };
+        }
+        Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+    };
+    let element_to_value_ref = if wire {
+        quote!({
+            let value = &**value;
+            #to_value_ref
+        })
+    } else {
+        to_value_ref.clone()
+    };
+    match label {
+        Label::Optional => quote!(#member.as_ref().map(|value| #element_to_value_ref)),
+        Label::Required => quote! {{
+            let value = &#single;
+            ::core::option::Option::Some(#to_value_ref)
+        }},
+        Label::Repeated => quote! {
+            if #member.is_empty() {
+                ::core::option::Option::None
+            } else {
+                ::core::option::Option::Some(::prost::diff::ValueRef::List(
+                    #member.iter().map(|value| #element_to_value_ref).collect(),
+                ))
+            }
+        },
+    }
+}
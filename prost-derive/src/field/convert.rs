@@ -0,0 +1,312 @@
+use anyhow::{bail, Error};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_str, Lit, Meta, MetaNameValue, Path};
+
+use crate::field::{scalar, set_option, Field as InnerField, Label};
+
+/// A scalar, message or group field whose Rust type is converted to and from the type of its wire
+/// representation with a `::prost::Conversion`.
+#[derive(Clone)]
+pub struct Field {
+    /// The field, as it would be declared with the wire representation type.
+    pub inner: Box<InnerField>,
+    /// The path of the type implementing `::prost::Conversion`.
+    pub conversion: Path,
+}
+
+/// The shape of a converted field, i.e. how many values of the Rust type it holds.
+enum Shape {
+    Single,
+    Optional,
+    Repeated,
+    /// A packed repeated scalar field, which is encoded as a whole.
+    Packed,
+}
+
+impl Field {
+    /// Removes the `convert` attribute from the field attributes, returning the path of the
+    /// conversion type if the attribute is present.
+    pub fn conversion_attr(attrs: &mut Vec<Meta>) -> Result<Option<Path>, Error> {
+        let mut conversion = None;
+        let mut error = None;
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("convert") {
+                return true;
+            }
+            let result = match *attr {
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(ref lit),
+                    ..
+                }) => parse_str::<Path>(&lit.value())
+                    .map_err(Error::from)
+                    .and_then(|path| {
+                        set_option(&mut conversion, path, "duplicate convert attributes")
+                    }),
+                _ => Err(anyhow::anyhow!("invalid convert attribute: {:?}", attr)),
+            };
+            if let Err(e) = result {
+                error.get_or_insert(e);
+            }
+            false
+        });
+        match error {
+            Some(error) => Err(error),
+            None => Ok(conversion),
+        }
+    }
+
+    pub fn new(inner: InnerField, conversion: Path) -> Result<Field, Error> {
+        match inner {
+            InnerField::Scalar(ref scalar) if scalar.ty.is_borrowed() => {
+                bail!("convert attribute may not be applied to borrowed fields")
+            }
+            InnerField::Message(ref message) if message.borrowed => {
+                bail!("convert attribute may not be applied to borrowed fields")
+            }
+            InnerField::Scalar(..) | InnerField::Message(..) | InnerField::Group(..) => (),
+            InnerField::Map(..) | InnerField::Oneof(..) | InnerField::Converted(..) => {
+                bail!("convert attribute may only be applied to scalar, message and group fields")
+            }
+        }
+        Ok(Field {
+            inner: Box::new(inner),
+            conversion,
+        })
+    }
+
+    fn shape(&self) -> Shape {
+        let label = match *self.inner {
+            InnerField::Scalar(ref scalar) => match scalar.kind {
+                scalar::Kind::Plain(..) | scalar::Kind::Required(..) => return Shape::Single,
+                scalar::Kind::Optional(..) => return Shape::Optional,
+                scalar::Kind::Repeated => return Shape::Repeated,
+                scalar::Kind::Packed => return Shape::Packed,
+            },
+            InnerField::Message(ref message) => message.label,
+            InnerField::Group(ref group) => group.label,
+            _ => unreachable!(),
+        };
+        match label {
+            Label::Required => Shape::Single,
+            Label::Optional => Shape::Optional,
+            Label::Repeated => Shape::Repeated,
+        }
+    }
+
+    /// Returns an expression which converts the field `ident` to its wire representation.
+    fn to_wire(&self, ident: TokenStream) -> TokenStream {
+        let conversion = &self.conversion;
+        let to_wire = quote!(<#conversion as ::prost::Conversion>::to_wire);
+        match self.shape() {
+            Shape::Single => quote!(#to_wire(&#ident)),
+            Shape::Optional => quote!(#ident.as_ref().map(#to_wire)),
+            Shape::Repeated | Shape::Packed => quote! {
+                #ident.iter().map(#to_wire).collect::<::prost::alloc::vec::Vec<_>>()
+            },
+        }
+    }
+
+    /// Returns an expression which evaluates to the wire representation of the field `ident` as
+    /// `::prost::diff::Wire` values, in an `Option` or a `Vec` for optional and repeated fields.
+    pub fn to_diff_wire(&self, ident: TokenStream) -> TokenStream {
+        let conversion = &self.conversion;
+        let to_wire = quote!(::prost::diff::Wire::new::<#conversion>);
+        match self.shape() {
+            Shape::Single => quote!(#to_wire(&#ident)),
+            Shape::Optional => quote!(#ident.as_ref().map(#to_wire)),
+            Shape::Repeated | Shape::Packed => quote! {
+                #ident.iter().map(#to_wire).collect::<::prost::alloc::vec::Vec<_>>()
+            },
+        }
+    }
+
+    /// Returns the field which encodes a single value of an optional or repeated field, which is
+    /// always encoded.
+    fn element(&self) -> InnerField {
+        let mut element = (*self.inner).clone();
+        match element {
+            InnerField::Scalar(ref mut scalar) => {
+                scalar.kind = scalar::Kind::Required(scalar::DefaultValue::new(&scalar.ty));
+            }
+            InnerField::Message(ref mut message) => message.label = Label::Required,
+            InnerField::Group(ref mut group) => group.label = Label::Required,
+            _ => unreachable!(),
+        }
+        element
+    }
+
+    /// Returns a block which evaluates `tokens` for the wire representation of `value`, an
+    /// expression of type `&Value`.
+    ///
+    /// The wire representation is borrowed from the value if the conversion supports it, and is
+    /// converted otherwise. `tokens` is called with the expression to use for the representation.
+    fn with_wire<F>(&self, value: TokenStream, tokens: F) -> TokenStream
+    where
+        F: Fn(TokenStream) -> TokenStream,
+    {
+        let conversion = &self.conversion;
+        let borrowed = tokens(quote!((*wire)));
+        let converted = tokens(quote!(wire));
+        quote! {
+            match <#conversion as ::prost::Conversion>::to_wire_ref(#value) {
+                ::core::option::Option::Some(wire) => { #borrowed }
+                ::core::option::Option::None => {
+                    let wire = <#conversion as ::prost::Conversion>::to_wire(#value);
+                    #converted
+                }
+            }
+        }
+    }
+
+    /// Returns a statement which encodes the field through its wire representation, using
+    /// `encode` to encode the wire representation of the field or of each of its values.
+    fn encode_with<F>(&self, ident: TokenStream, encode: F) -> TokenStream
+    where
+        F: Fn(&InnerField, TokenStream) -> TokenStream,
+    {
+        match self.shape() {
+            Shape::Single => self.with_wire(quote!(&#ident), |wire| encode(&self.inner, wire)),
+            Shape::Optional => {
+                let element = self.element();
+                let encode = self.with_wire(quote!(value), |wire| encode(&element, wire));
+                quote! {
+                    if let ::core::option::Option::Some(ref value) = #ident {
+                        #encode
+                    }
+                }
+            }
+            Shape::Repeated => {
+                let element = self.element();
+                let encode = self.with_wire(quote!(value), |wire| encode(&element, wire));
+                quote! {
+                    for value in &#ident {
+                        #encode
+                    }
+                }
+            }
+            // Packed fields are encoded with a length prefix, so all the values are converted
+            // up front.
+            Shape::Packed => {
+                let to_wire = self.to_wire(ident);
+                let encode = encode(&self.inner, quote!(wire));
+                quote! {
+                    {
+                        let wire = #to_wire;
+                        #encode
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, |inner, wire| inner.encode(wire))
+    }
+
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, |inner, wire| inner.encode_deterministic(wire))
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded value into the
+    /// field.
+    ///
+    /// The existing value of a message or group field is converted to its wire representation
+    /// before merging, so that messages are merged as usual, and the result is converted back.
+    /// Decoded scalar values replace the existing value, which isn't converted.
+    pub fn merge(&self, ident: TokenStream) -> TokenStream {
+        let conversion = &self.conversion;
+        let from_wire = quote!(<#conversion as ::prost::Conversion>::from_wire);
+        let merge = self.inner.merge(quote!(wire_value));
+        let scalar = matches!(*self.inner, InnerField::Scalar(..));
+        let (wire, assign) = match self.shape() {
+            Shape::Single if scalar => (
+                quote!(::core::default::Default::default()),
+                quote!(#from_wire(wire).map(|converted| *#ident = converted)),
+            ),
+            Shape::Single => (
+                self.to_wire(quote!(*#ident)),
+                quote!(#from_wire(wire).map(|converted| *#ident = converted)),
+            ),
+            Shape::Optional => (
+                if scalar {
+                    quote!(::core::option::Option::None)
+                } else {
+                    self.to_wire(ident.clone())
+                },
+                quote! {
+                    match wire {
+                        ::core::option::Option::Some(wire) => #from_wire(wire)
+                            .map(|converted| *#ident = ::core::option::Option::Some(converted)),
+                        ::core::option::Option::None => ::core::result::Result::Ok(()),
+                    }
+                },
+            ),
+            Shape::Repeated | Shape::Packed => (
+                quote!(::prost::alloc::vec::Vec::new()),
+                quote! {
+                    wire.into_iter().try_for_each(|wire| {
+                        #from_wire(wire).map(|converted| #ident.push(converted))
+                    })
+                },
+            ),
+        };
+        quote! {
+            {
+                let mut wire = #wire;
+                let merged = {
+                    let wire_value = &mut wire;
+                    #merge
+                };
+                match merged {
+                    ::core::result::Result::Ok(()) => #assign,
+                    ::core::result::Result::Err(error) => ::core::result::Result::Err(error),
+                }
+            }
+        }
+    }
+
+    pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
+        match self.shape() {
+            Shape::Single => self.with_wire(quote!(&#ident), |wire| self.inner.encoded_len(wire)),
+            Shape::Optional => {
+                let element = self.element();
+                let encoded_len = self.with_wire(quote!(value), |wire| element.encoded_len(wire));
+                quote!(#ident.as_ref().map_or(0, |value| #encoded_len))
+            }
+            Shape::Repeated => {
+                let element = self.element();
+                let encoded_len = self.with_wire(quote!(value), |wire| element.encoded_len(wire));
+                quote!(#ident.iter().map(|value| #encoded_len).sum::<usize>())
+            }
+            Shape::Packed => {
+                let to_wire = self.to_wire(ident);
+                let encoded_len = self.inner.encoded_len(quote!(wire));
+                quote! {
+                    {
+                        let wire = #to_wire;
+                        #encoded_len
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking that a required field is
+    /// present, or `None` if the field isn't required.
+    pub fn check_required(&self) -> Option<TokenStream> {
+        if !self.inner.is_required() {
+            return None;
+        }
+        let tag = self.inner.tags()[0];
+        Some(quote!(::prost::encoding::FieldPresence::require(presence, #tag)))
+    }
+
+    pub fn clear(&self, ident: TokenStream) -> TokenStream {
+        match self.shape() {
+            Shape::Single => quote!(#ident = ::core::default::Default::default()),
+            Shape::Optional => quote!(#ident = ::core::option::Option::None),
+            Shape::Repeated | Shape::Packed => quote!(#ident.clear()),
+        }
+    }
+}
@@ -71,19 +71,27 @@ impl Field {
     }
 
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(encode))
+    }
+
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(encode_deterministic))
+    }
+
+    fn encode_with(&self, ident: TokenStream, encode_fn: TokenStream) -> TokenStream {
         let tag = self.tag;
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
-                    ::prost::encoding::group::encode(#tag, msg, buf);
+                    ::prost::encoding::group::#encode_fn(#tag, msg, buf);
                 }
             },
             Label::Required => quote! {
-                ::prost::encoding::group::encode(#tag, &#ident, buf);
+                ::prost::encoding::group::#encode_fn(#tag, &#ident, buf);
             },
             Label::Repeated => quote! {
                 for msg in &#ident {
-                    ::prost::encoding::group::encode(#tag, msg, buf);
+                    ::prost::encoding::group::#encode_fn(#tag, msg, buf);
                 }
             },
         }
@@ -109,6 +117,82 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the result of merging a decoded group into a
+    /// singular field, while recording its decoded fields in `presence`, or `None` if the field
+    /// is merged untracked.
+    pub fn merge_tracked(&self, ident: TokenStream) -> Option<TokenStream> {
+        match self.label {
+            Label::Optional => {
+                let merge = self.merge_tracked_value(
+                    quote!(#ident.get_or_insert_with(::core::default::Default::default)),
+                    quote!(set),
+                );
+                Some(quote! {
+                    {
+                        let set = #ident.is_some();
+                        #merge
+                    }
+                })
+            }
+            Label::Required => Some(self.merge_tracked_value(ident, quote!(true))),
+            Label::Repeated => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded group into
+    /// `value`, which held a message before the decode if `set` is true.
+    pub fn merge_tracked_value(&self, value: TokenStream, set: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        quote! {
+            ::prost::encoding::group::merge_tracked(
+                tag,
+                wire_type,
+                #value,
+                buf,
+                ctx,
+                presence.nested_mut(#tag, #set),
+            )
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking that the field is present
+    /// if it's required, and the required fields of its group, or `None` if there's nothing to
+    /// check.
+    pub fn check_required(&self, ident: TokenStream) -> Option<TokenStream> {
+        let tag = self.tag;
+        let check = self.check_required_value(quote!(msg));
+        match self.label {
+            Label::Optional => Some(quote! {
+                match #ident {
+                    ::core::option::Option::Some(ref msg) => #check,
+                    ::core::option::Option::None => ::core::result::Result::Ok(()),
+                }
+            }),
+            Label::Required => Some(quote! {
+                ::prost::encoding::FieldPresence::require(presence, #tag).and_then(|()| {
+                    let msg = &#ident;
+                    #check
+                })
+            }),
+            // Repeated groups are checked as they are decoded.
+            Label::Repeated => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking the required fields of the
+    /// group `value` in the field, if it was decoded.
+    pub fn check_required_value(&self, value: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        quote! {
+            match ::prost::encoding::FieldPresence::nested(presence, #tag) {
+                ::core::option::Option::Some(presence) => {
+                    ::prost::Message::check_required_fields(#value, presence)
+                }
+                ::core::option::Option::None => ::core::result::Result::Ok(()),
+            }
+        }
+    }
+
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
         let tag = self.tag;
         match self.label {
@@ -129,16 +129,30 @@ impl Field {
 
     /// Returns a statement which encodes the map field.
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, false)
+    }
+
+    /// Returns a statement which encodes the map field, with its entries in key order.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, true)
+    }
+
+    fn encode_with(&self, ident: TokenStream, deterministic: bool) -> TokenStream {
         let tag = self.tag;
         let key_mod = self.key_ty.module();
         let ke = quote!(::prost::encoding::#key_mod::encode);
         let kl = quote!(::prost::encoding::#key_mod::encoded_len);
         let module = self.map_ty.module();
+        let (encode_fn, encode_with_default_fn) = if deterministic {
+            (quote!(encode_sorted), quote!(encode_sorted_with_default))
+        } else {
+            (quote!(encode), quote!(encode_with_default))
+        };
         match &self.value_ty {
             ValueTy::Scalar(scalar::Ty::Enumeration(ty)) => {
                 let default = quote!(#ty::default() as i32);
                 quote! {
-                    ::prost::encoding::#module::encode_with_default(
+                    ::prost::encoding::#module::#encode_with_default_fn(
                         #ke,
                         #kl,
                         ::prost::encoding::int32::encode,
@@ -155,7 +169,7 @@ impl Field {
                 let ve = quote!(::prost::encoding::#val_mod::encode);
                 let vl = quote!(::prost::encoding::#val_mod::encoded_len);
                 quote! {
-                    ::prost::encoding::#module::encode(
+                    ::prost::encoding::#module::#encode_fn(
                         #ke,
                         #kl,
                         #ve,
@@ -166,17 +180,24 @@ impl Field {
                     );
                 }
             }
-            ValueTy::Message => quote! {
-                ::prost::encoding::#module::encode(
-                    #ke,
-                    #kl,
-                    ::prost::encoding::message::encode,
-                    ::prost::encoding::message::encoded_len,
-                    #tag,
-                    &#ident,
-                    buf,
-                );
-            },
+            ValueTy::Message => {
+                let ve = if deterministic {
+                    quote!(::prost::encoding::message::encode_deterministic)
+                } else {
+                    quote!(::prost::encoding::message::encode)
+                };
+                quote! {
+                    ::prost::encoding::#module::#encode_fn(
+                        #ke,
+                        #kl,
+                        #ve,
+                        ::prost::encoding::message::encoded_len,
+                        #tag,
+                        &#ident,
+                        buf,
+                    );
+                }
+            }
         }
     }
 
@@ -365,7 +386,7 @@ fn key_ty_from_str(s: &str) -> Result<scalar::Ty, Error> {
         | scalar::Ty::Sfixed32
         | scalar::Ty::Sfixed64
         | scalar::Ty::Bool
-        | scalar::Ty::String => Ok(ty),
+        | scalar::Ty::String(..) => Ok(ty),
         _ => bail!("invalid map key type: {}", s),
     }
 }
@@ -9,6 +9,10 @@ use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
 pub struct Field {
     pub label: Label,
     pub tag: u32,
+    /// Whether the message type borrows from the decoded buffer.
+    pub borrowed: bool,
+    /// Whether the field holds a `::prost::Lazy`, which is decoded on first access.
+    pub lazy: bool,
 }
 
 impl Field {
@@ -17,6 +21,8 @@ impl Field {
         let mut label = None;
         let mut tag = None;
         let mut boxed = false;
+        let mut borrowed = false;
+        let mut lazy = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -25,6 +31,10 @@ impl Field {
                 set_bool(&mut message, "duplicate message attribute")?;
             } else if word_attr("boxed", attr) {
                 set_bool(&mut boxed, "duplicate boxed attribute")?;
+            } else if word_attr("borrowed", attr) {
+                set_bool(&mut borrowed, "duplicate borrowed attribute")?;
+            } else if word_attr("lazy", attr) {
+                set_bool(&mut lazy, "duplicate lazy attribute")?;
             } else if let Some(t) = tag_attr(attr)? {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
             } else if let Some(l) = Label::from_attr(attr) {
@@ -47,6 +57,10 @@ impl Field {
             _ => bail!("unknown attributes for message field: {:?}", unknown_attrs),
         }
 
+        if lazy && borrowed {
+            bail!("message field may not be both lazy and borrowed");
+        }
+
         let tag = match tag.or(inferred_tag) {
             Some(tag) => tag,
             None => bail!("message field is missing a tag attribute"),
@@ -55,6 +69,8 @@ impl Field {
         Ok(Some(Field {
             label: label.unwrap_or(Label::Optional),
             tag,
+            borrowed,
+            lazy,
         }))
     }
 
@@ -73,53 +89,195 @@ impl Field {
         }
     }
 
+    /// Returns the path of the encoding module for the message type.
+    fn module(&self) -> TokenStream {
+        if self.borrowed {
+            quote!(::prost::encoding::borrowed::message)
+        } else if self.lazy {
+            quote!(::prost::encoding::lazy)
+        } else {
+            quote!(::prost::encoding::message)
+        }
+    }
+
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(encode))
+    }
+
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        // Borrowed messages don't have a deterministic encoding mode.
+        if self.borrowed {
+            return self.encode(ident);
+        }
+        self.encode_with(ident, quote!(encode_deterministic))
+    }
+
+    fn encode_with(&self, ident: TokenStream, encode_fn: TokenStream) -> TokenStream {
+        let module = self.module();
         let tag = self.tag;
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
-                    ::prost::encoding::message::encode(#tag, msg, buf);
+                    #module::#encode_fn(#tag, msg, buf);
                 }
             },
             Label::Required => quote! {
-                ::prost::encoding::message::encode(#tag, &#ident, buf);
+                #module::#encode_fn(#tag, &#ident, buf);
             },
             Label::Repeated => quote! {
                 for msg in &#ident {
-                    ::prost::encoding::message::encode(#tag, msg, buf);
+                    #module::#encode_fn(#tag, msg, buf);
                 }
             },
         }
     }
 
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
+        let module = self.module();
         match self.label {
             Label::Optional => quote! {
-                ::prost::encoding::message::merge(wire_type,
+                #module::merge(wire_type,
                                                  #ident.get_or_insert_with(::core::default::Default::default),
                                                  buf,
                                                  ctx)
             },
             Label::Required => quote! {
-                ::prost::encoding::message::merge(wire_type, #ident, buf, ctx)
+                #module::merge(wire_type, #ident, buf, ctx)
+            },
+            Label::Repeated => quote! {
+                #module::merge_repeated(wire_type, #ident, buf, ctx)
+            },
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded message into a
+    /// singular field, while recording its decoded fields in `presence`, or `None` if the field
+    /// is merged untracked.
+    pub fn merge_tracked(&self, ident: TokenStream) -> Option<TokenStream> {
+        match self.label {
+            Label::Optional => {
+                let merge = self.merge_tracked_value(
+                    quote!(#ident.get_or_insert_with(::core::default::Default::default)),
+                    quote!(set),
+                )?;
+                Some(quote! {
+                    {
+                        let set = #ident.is_some();
+                        #merge
+                    }
+                })
+            }
+            Label::Required => self.merge_tracked_value(ident, quote!(true)),
+            Label::Repeated => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded message into
+    /// `value`, which held a message before the decode if `set` is true.
+    pub fn merge_tracked_value(&self, value: TokenStream, set: TokenStream) -> Option<TokenStream> {
+        // Lazy messages are decoded on first access, and are not checked.
+        if self.lazy {
+            return None;
+        }
+        let module = self.module();
+        let tag = self.tag;
+        Some(quote! {
+            #module::merge_tracked(wire_type, #value, buf, ctx, presence.nested_mut(#tag, #set))
+        })
+    }
+
+    pub fn merge_projected(&self, ident: TokenStream) -> Option<TokenStream> {
+        // Borrowed and lazy messages are decoded in full.
+        if self.borrowed || self.lazy {
+            return None;
+        }
+        let module = quote!(::prost::encoding::message);
+        Some(match self.label {
+            Label::Optional => quote! {
+                #module::merge_projected(
+                    wire_type,
+                    #ident.get_or_insert_with(::core::default::Default::default),
+                    buf,
+                    ctx,
+                    projection,
+                )
+            },
+            Label::Required => quote! {
+                #module::merge_projected(wire_type, #ident, buf, ctx, projection)
             },
             Label::Repeated => quote! {
-                ::prost::encoding::message::merge_repeated(wire_type, #ident, buf, ctx)
+                #module::merge_repeated_projected(wire_type, #ident, buf, ctx, projection)
             },
+        })
+    }
+
+    /// Returns an expression which evaluates to the result of checking that the field is present
+    /// if it's required, and the required fields of its message, or `None` if there's nothing to
+    /// check.
+    pub fn check_required(&self, ident: TokenStream) -> Option<TokenStream> {
+        let tag = self.tag;
+        let require = quote!(::prost::encoding::FieldPresence::require(presence, #tag));
+        if self.lazy {
+            return match self.label {
+                Label::Required => Some(require),
+                Label::Optional | Label::Repeated => None,
+            };
+        }
+        let check = self.check_required_value(quote!(msg));
+        match self.label {
+            Label::Optional => Some(quote! {
+                match #ident {
+                    ::core::option::Option::Some(ref msg) => #check,
+                    ::core::option::Option::None => ::core::result::Result::Ok(()),
+                }
+            }),
+            Label::Required => Some(quote! {
+                #require.and_then(|()| {
+                    let msg = &#ident;
+                    #check
+                })
+            }),
+            // Repeated messages are checked as they are decoded.
+            Label::Repeated => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking the required fields of the
+    /// message `value` in the field, if it was decoded.
+    pub fn check_required_value(&self, value: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        let message_trait = self.message_trait();
+        quote! {
+            match ::prost::encoding::FieldPresence::nested(presence, #tag) {
+                ::core::option::Option::Some(presence) => {
+                    #message_trait::check_required_fields(#value, presence)
+                }
+                ::core::option::Option::None => ::core::result::Result::Ok(()),
+            }
+        }
+    }
+
+    /// Returns the path of the trait implemented by the message type.
+    fn message_trait(&self) -> TokenStream {
+        if self.borrowed {
+            quote!(::prost::BorrowedMessage)
+        } else {
+            quote!(::prost::Message)
         }
     }
 
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
+        let module = self.module();
         let tag = self.tag;
         match self.label {
             Label::Optional => quote! {
-                #ident.as_ref().map_or(0, |msg| ::prost::encoding::message::encoded_len(#tag, msg))
+                #ident.as_ref().map_or(0, |msg| #module::encoded_len(#tag, msg))
             },
             Label::Required => quote! {
-                ::prost::encoding::message::encoded_len(#tag, &#ident)
+                #module::encoded_len(#tag, &#ident)
             },
             Label::Repeated => quote! {
-                ::prost::encoding::message::encoded_len_repeated(#tag, &#ident)
+                #module::encoded_len_repeated(#tag, &#ident)
             },
         }
     }
@@ -127,6 +285,7 @@ impl Field {
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
+            Label::Required if self.lazy => quote!(#ident = ::core::default::Default::default()),
             Label::Required => quote!(#ident.clear()),
             Label::Repeated => quote!(#ident.clear()),
         }
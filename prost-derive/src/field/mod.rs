@@ -1,8 +1,9 @@
+mod convert;
 mod group;
-mod map;
+pub mod map;
 mod message;
 mod oneof;
-mod scalar;
+pub mod scalar;
 
 use std::fmt;
 use std::slice;
@@ -24,6 +25,8 @@ pub enum Field {
     Oneof(oneof::Field),
     /// A group field.
     Group(group::Field),
+    /// A field converted to and from its wire representation.
+    Converted(convert::Field),
 }
 
 impl Field {
@@ -32,7 +35,8 @@ impl Field {
     /// If the meta items are invalid, an error will be returned.
     /// If the field should be ignored, `None` is returned.
     pub fn new(attrs: Vec<Attribute>, inferred_tag: Option<u32>) -> Result<Option<Field>, Error> {
-        let attrs = prost_attrs(attrs);
+        let mut attrs = field_attrs(attrs);
+        let conversion = convert::Field::conversion_attr(&mut attrs)?;
 
         // TODO: check for ignore attribute.
 
@@ -50,7 +54,12 @@ impl Field {
             bail!("no type attribute");
         };
 
-        Ok(Some(field))
+        match conversion {
+            Some(conversion) => Ok(Some(Field::Converted(convert::Field::new(
+                field, conversion,
+            )?))),
+            None => Ok(Some(field)),
+        }
     }
 
     /// Creates a new oneof `Field` from an iterator of field attributes.
@@ -58,7 +67,10 @@ impl Field {
     /// If the meta items are invalid, an error will be returned.
     /// If the field should be ignored, `None` is returned.
     pub fn new_oneof(attrs: Vec<Attribute>) -> Result<Option<Field>, Error> {
-        let attrs = prost_attrs(attrs);
+        let mut attrs = field_attrs(attrs);
+        if convert::Field::conversion_attr(&mut attrs)?.is_some() {
+            bail!("convert attribute may not be applied to oneof fields");
+        }
 
         // TODO: check for ignore attribute.
 
@@ -84,6 +96,29 @@ impl Field {
             Field::Map(ref map) => vec![map.tag],
             Field::Oneof(ref oneof) => oneof.tags.clone(),
             Field::Group(ref group) => vec![group.tag],
+            Field::Converted(ref converted) => converted.inner.tags(),
+        }
+    }
+
+    /// Returns `true` if the field is a proto2 `required` field.
+    pub fn is_required(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => matches!(scalar.kind, scalar::Kind::Required(..)),
+            Field::Message(ref message) => message.label == Label::Required,
+            Field::Group(ref group) => group.label == Label::Required,
+            Field::Map(..) | Field::Oneof(..) => false,
+            Field::Converted(ref converted) => converted.inner.is_required(),
+        }
+    }
+
+    /// Returns `true` if the deterministic encoding of the field may differ from its encoding,
+    /// which is the case for maps and for fields holding messages.
+    pub fn has_deterministic_encoding(&self) -> bool {
+        match *self {
+            Field::Scalar(..) => false,
+            Field::Message(ref message) => !message.borrowed,
+            Field::Map(..) | Field::Oneof(..) | Field::Group(..) => true,
+            Field::Converted(ref converted) => converted.inner.has_deterministic_encoding(),
         }
     }
 
@@ -95,6 +130,19 @@ impl Field {
             Field::Map(ref map) => map.encode(ident),
             Field::Oneof(ref oneof) => oneof.encode(ident),
             Field::Group(ref group) => group.encode(ident),
+            Field::Converted(ref converted) => converted.encode(ident),
+        }
+    }
+
+    /// Returns a statement which encodes the field, with the entries of maps in key order.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.encode(ident),
+            Field::Message(ref message) => message.encode_deterministic(ident),
+            Field::Map(ref map) => map.encode_deterministic(ident),
+            Field::Oneof(ref oneof) => oneof.encode_deterministic(ident),
+            Field::Group(ref group) => group.encode_deterministic(ident),
+            Field::Converted(ref converted) => converted.encode_deterministic(ident),
         }
     }
 
@@ -107,6 +155,67 @@ impl Field {
             Field::Map(ref map) => map.merge(ident),
             Field::Oneof(ref oneof) => oneof.merge(ident),
             Field::Group(ref group) => group.merge(ident),
+            Field::Converted(ref converted) => converted.merge(ident),
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging the fields of a decoded
+    /// message selected by the nested projection `projection` into the field, or `None` if the
+    /// field is always decoded in full.
+    pub fn merge_projected(&self, ident: TokenStream) -> Option<TokenStream> {
+        match *self {
+            Field::Message(ref message) => message.merge_projected(ident),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded value into the
+    /// field, while recording the decoded fields of its singular messages in `presence`, or `None`
+    /// if the field is merged untracked.
+    pub fn merge_tracked(&self, ident: TokenStream) -> Option<TokenStream> {
+        match *self {
+            Field::Message(ref message) => message.merge_tracked(ident),
+            Field::Group(ref group) => group.merge_tracked(ident),
+            Field::Oneof(ref oneof) => Some(oneof.merge_tracked(ident)),
+            Field::Scalar(..) | Field::Map(..) | Field::Converted(..) => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded value into
+    /// `value`, the value of a oneof variant, while recording the decoded fields of its message
+    /// in `presence`, or `None` if the variant doesn't hold a message.
+    pub fn merge_tracked_value(&self, value: TokenStream, set: TokenStream) -> Option<TokenStream> {
+        match *self {
+            Field::Message(ref message) => message.merge_tracked_value(value, set),
+            Field::Group(ref group) => Some(group.merge_tracked_value(value, set)),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking that the field is present
+    /// if it's required, and the required fields of its messages, or `None` if there's nothing to
+    /// check.
+    pub fn check_required(&self, ident: TokenStream) -> Option<TokenStream> {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.check_required(),
+            Field::Message(ref message) => message.check_required(ident),
+            // Map values are checked as they are decoded.
+            Field::Map(..) => None,
+            Field::Oneof(ref oneof) => Some(oneof.check_required(ident)),
+            Field::Group(ref group) => group.check_required(ident),
+            Field::Converted(ref converted) => converted.check_required(),
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking the required fields of
+    /// `value`, the value of a oneof variant, or `None` if the variant doesn't hold a message.
+    pub fn check_required_value(&self, value: TokenStream) -> Option<TokenStream> {
+        match *self {
+            Field::Message(ref message) if !message.lazy => {
+                Some(message.check_required_value(value))
+            }
+            Field::Group(ref group) => Some(group.check_required_value(value)),
+            _ => None,
         }
     }
 
@@ -118,6 +227,7 @@ impl Field {
             Field::Message(ref msg) => msg.encoded_len(ident),
             Field::Oneof(ref oneof) => oneof.encoded_len(ident),
             Field::Group(ref group) => group.encoded_len(ident),
+            Field::Converted(ref converted) => converted.encoded_len(ident),
         }
     }
 
@@ -129,6 +239,7 @@ impl Field {
             Field::Map(ref map) => map.clear(ident),
             Field::Oneof(ref oneof) => oneof.clear(ident),
             Field::Group(ref group) => group.clear(ident),
+            Field::Converted(ref converted) => converted.clear(ident),
         }
     }
 
@@ -173,6 +284,81 @@ impl Field {
     }
 }
 
+/// Returns `true` if the field attributes mark the field as the message's unknown field store,
+/// i.e. `#[prost(unknown_fields)]`.
+pub fn unknown_fields_attr(attrs: &[Attribute]) -> Result<bool, Error> {
+    let attrs = prost_attrs(attrs.to_vec());
+    if !attrs.iter().any(|attr| word_attr("unknown_fields", attr)) {
+        return Ok(false);
+    }
+    if attrs.len() > 1 {
+        bail!("unknown_fields attribute may not be combined with other attributes");
+    }
+    Ok(true)
+}
+
+/// Returns the extension ranges if the field attributes mark the field as the message's extension
+/// set, i.e. `#[prost(extensions = "100..200, 1000..2000")]`. The end of each range is exclusive.
+pub fn extensions_attr(attrs: &[Attribute]) -> Result<Option<Vec<(u32, u32)>>, Error> {
+    let ranges = match string_attr("extensions", attrs)? {
+        Some(ranges) => ranges,
+        None => return Ok(None),
+    };
+    if prost_attrs(attrs.to_vec()).len() > 1 {
+        bail!("extensions attribute may not be combined with other attributes");
+    }
+    ranges
+        .split(',')
+        .map(|range| {
+            let (start, end) = match range.trim().split_once("..") {
+                Some(range) => range,
+                None => bail!("invalid extension range: {:?}", range),
+            };
+            let (start, end) = (start.parse::<u32>()?, end.parse::<u32>()?);
+            if start >= end {
+                bail!("empty extension range: {:?}", range);
+            }
+            Ok((start, end))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Returns the `.proto` name of the field from a `#[prost(name = "...")]` attribute, if present.
+///
+/// The name is only used for reflection, so it is ignored when building the field itself.
+pub fn name_attr(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    string_attr("name", attrs)
+}
+
+/// Returns the JSON name of the field from a `#[prost(json_name = "...")]` attribute, if present.
+///
+/// Like `name`, the JSON name is only used for reflection.
+pub fn json_name_attr(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    string_attr("json_name", attrs)
+}
+
+fn string_attr(key: &str, attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    let mut value = None;
+    for attr in prost_attrs(attrs.to_vec()) {
+        if !attr.path().is_ident(key) {
+            continue;
+        }
+        match attr {
+            Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(ref lit),
+                ..
+            }) => set_option(
+                &mut value,
+                lit.value(),
+                &format!("duplicate {} attributes", key),
+            )?,
+            _ => bail!("invalid {} attribute: {:?}", key, attr),
+        }
+    }
+    Ok(value)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Label {
     /// An optional field.
@@ -247,6 +433,15 @@ fn prost_attrs(attrs: Vec<Attribute>) -> Vec<Meta> {
         .collect()
 }
 
+/// Get the 'prost' attribute items which describe the field encoding, i.e. everything except the
+/// reflection-only `name` attribute.
+fn field_attrs(attrs: Vec<Attribute>) -> Vec<Meta> {
+    prost_attrs(attrs)
+        .into_iter()
+        .filter(|attr| !attr.path().is_ident("name") && !attr.path().is_ident("json_name"))
+        .collect()
+}
+
 pub fn set_option<T>(option: &mut Option<T>, value: T, message: &str) -> Result<(), Error>
 where
     T: fmt::Debug,
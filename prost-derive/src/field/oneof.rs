@@ -77,6 +77,15 @@ impl Field {
         }
     }
 
+    /// Returns a statement which encodes the oneof field, with the entries of maps in key order.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let Some(ref oneof) = #ident {
+                oneof.encode_deterministic(buf)
+            }
+        }
+    }
+
     /// Returns an expression which evaluates to the result of decoding the oneof field.
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
         let ty = &self.ty;
@@ -85,6 +94,26 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the result of decoding the oneof field, while
+    /// recording the decoded fields of its message in `presence`.
+    pub fn merge_tracked(&self, ident: TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        quote! {
+            #ty::merge_tracked(#ident, tag, wire_type, buf, ctx, presence)
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of checking the required fields of the
+    /// message in the oneof field.
+    pub fn check_required(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            match #ident {
+                ::core::option::Option::Some(ref oneof) => oneof.check_required_fields(presence),
+                ::core::option::Option::None => ::core::result::Result::Ok(()),
+            }
+        }
+    }
+
     /// Returns an expression which evaluates to the encoded length of the oneof field.
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
         let ty = &self.ty;
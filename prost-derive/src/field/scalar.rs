@@ -4,7 +4,9 @@ use std::fmt;
 use anyhow::{anyhow, bail, Error};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_str, Ident, Lit, LitByteStr, Meta, MetaList, MetaNameValue, NestedMeta, Path};
+use syn::{
+    parse_str, Ident, Lit, LitByteStr, Meta, MetaList, MetaNameValue, NestedMeta, Path, Type,
+};
 
 use crate::field::{bool_attr, set_option, tag_attr, Label};
 
@@ -118,8 +120,9 @@ impl Field {
         match self.kind {
             Kind::Plain(ref default) => {
                 let default = default.typed();
+                let value = self.ty.as_ref(ident.clone());
                 quote! {
-                    if #ident != #default {
+                    if #value != #default {
                         #encode_fn(#tag, &#ident, buf);
                     }
                 }
@@ -158,6 +161,18 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the result of checking that a required field is
+    /// present, or `None` if the field isn't required.
+    pub fn check_required(&self) -> Option<TokenStream> {
+        match self.kind {
+            Kind::Required(..) => {
+                let tag = self.tag;
+                Some(quote!(::prost::encoding::FieldPresence::require(presence, #tag)))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns an expression which evaluates to the encoded length of the field.
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
         let module = self.ty.module();
@@ -172,8 +187,9 @@ impl Field {
         match self.kind {
             Kind::Plain(ref default) => {
                 let default = default.typed();
+                let value = self.ty.as_ref(ident.clone());
                 quote! {
-                    if #ident != #default {
+                    if #value != #default {
                         #encoded_len_fn(#tag, &#ident)
                     } else {
                         0
@@ -194,7 +210,13 @@ impl Field {
             Kind::Plain(ref default) | Kind::Required(ref default) => {
                 let default = default.typed();
                 match self.ty {
-                    Ty::String | Ty::Bytes(..) => quote!(#ident.clear()),
+                    Ty::String(StringTy::String) | Ty::Bytes(BytesTy::Vec | BytesTy::Bytes) => {
+                        quote!(#ident.clear())
+                    }
+                    _ if self.ty.is_custom() => {
+                        let default = self.default();
+                        quote!(#ident = #default)
+                    }
                     _ => quote!(#ident = #default),
                 }
             }
@@ -206,6 +228,21 @@ impl Field {
     /// Returns an expression which evaluates to the default value of the field.
     pub fn default(&self) -> TokenStream {
         match self.kind {
+            Kind::Plain(ref value) | Kind::Required(ref value) if self.ty.is_borrowed() => {
+                value.typed()
+            }
+            Kind::Plain(ref value) | Kind::Required(ref value) if self.ty.is_custom() => {
+                match *value {
+                    DefaultValue::String(ref value) if !value.is_empty() => {
+                        self.ty.adapt(quote!(#value))
+                    }
+                    DefaultValue::Bytes(ref value) if !value.is_empty() => {
+                        let lit = LitByteStr::new(value, Span::call_site());
+                        self.ty.adapt(quote!(&#lit[..]))
+                    }
+                    _ => quote!(::core::default::Default::default()),
+                }
+            }
             Kind::Plain(ref value) | Kind::Required(ref value) => value.owned(),
             Kind::Optional(_) => quote!(::core::option::Option::None),
             Kind::Repeated | Kind::Packed => quote!(::prost::alloc::vec::Vec::new()),
@@ -340,6 +377,9 @@ impl Field {
 
             let match_some = if self.ty.is_numeric() {
                 quote!(::core::option::Option::Some(val) => val,)
+            } else if self.ty.is_custom() {
+                let val = self.ty.as_ref(quote!(*val));
+                quote!(::core::option::Option::Some(ref val) => #val,)
             } else {
                 quote!(::core::option::Option::Some(ref val) => &val[..],)
             };
@@ -380,15 +420,49 @@ pub enum Ty {
     Sfixed32,
     Sfixed64,
     Bool,
-    String,
+    String(StringTy),
     Bytes(BytesTy),
     Enumeration(Path),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StringTy {
+    String,
+    /// A `&str` borrowed from the decoded buffer.
+    Str,
+    /// A custom type implementing `StringAdapter`.
+    Custom(String),
+}
+
+impl StringTy {
+    fn try_from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "string" => Ok(StringTy::String),
+            "str" => Ok(StringTy::Str),
+            _ => match parse_str::<Type>(s) {
+                Ok(_) => Ok(StringTy::Custom(s.to_owned())),
+                Err(_) => bail!("Invalid string type: {}", s),
+            },
+        }
+    }
+
+    fn rust_type(&self) -> TokenStream {
+        match self {
+            StringTy::String => quote! { ::prost::alloc::string::String },
+            StringTy::Str => quote! { &'a str },
+            StringTy::Custom(ty) => parse_str::<Type>(ty).unwrap().into_token_stream(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BytesTy {
     Vec,
     Bytes,
+    /// A `&[u8]` borrowed from the decoded buffer.
+    Slice,
+    /// A custom type implementing `BytesAdapter`.
+    Custom(String),
 }
 
 impl BytesTy {
@@ -396,7 +470,11 @@ impl BytesTy {
         match s {
             "vec" => Ok(BytesTy::Vec),
             "bytes" => Ok(BytesTy::Bytes),
-            _ => bail!("Invalid bytes type: {}", s),
+            "slice" => Ok(BytesTy::Slice),
+            _ => match parse_str::<Type>(s) {
+                Ok(_) => Ok(BytesTy::Custom(s.to_owned())),
+                Err(_) => bail!("Invalid bytes type: {}", s),
+            },
         }
     }
 
@@ -404,6 +482,8 @@ impl BytesTy {
         match self {
             BytesTy::Vec => quote! { ::prost::alloc::vec::Vec<u8> },
             BytesTy::Bytes => quote! { ::prost::bytes::Bytes },
+            BytesTy::Slice => quote! { &'a [u8] },
+            BytesTy::Custom(ty) => parse_str::<Type>(ty).unwrap().into_token_stream(),
         }
     }
 }
@@ -424,8 +504,13 @@ impl Ty {
             Meta::Path(ref name) if name.is_ident("sfixed32") => Ty::Sfixed32,
             Meta::Path(ref name) if name.is_ident("sfixed64") => Ty::Sfixed64,
             Meta::Path(ref name) if name.is_ident("bool") => Ty::Bool,
-            Meta::Path(ref name) if name.is_ident("string") => Ty::String,
+            Meta::Path(ref name) if name.is_ident("string") => Ty::String(StringTy::String),
             Meta::Path(ref name) if name.is_ident("bytes") => Ty::Bytes(BytesTy::Vec),
+            Meta::NameValue(MetaNameValue {
+                ref path,
+                lit: Lit::Str(ref l),
+                ..
+            }) if path.is_ident("string") => Ty::String(StringTy::try_from_str(&l.value())?),
             Meta::NameValue(MetaNameValue {
                 ref path,
                 lit: Lit::Str(ref l),
@@ -474,7 +559,7 @@ impl Ty {
             "sfixed32" => Ty::Sfixed32,
             "sfixed64" => Ty::Sfixed64,
             "bool" => Ty::Bool,
-            "string" => Ty::String,
+            "string" => Ty::String(StringTy::String),
             "bytes" => Ty::Bytes(BytesTy::Vec),
             s if s.len() > enumeration_len && &s[..enumeration_len] == "enumeration" => {
                 let s = &s[enumeration_len..].trim();
@@ -510,7 +595,7 @@ impl Ty {
             Ty::Sfixed32 => "sfixed32",
             Ty::Sfixed64 => "sfixed64",
             Ty::Bool => "bool",
-            Ty::String => "string",
+            Ty::String(..) => "string",
             Ty::Bytes(..) => "bytes",
             Ty::Enumeration(..) => "enum",
         }
@@ -519,7 +604,7 @@ impl Ty {
     // TODO: rename to 'owned_type'.
     pub fn rust_type(&self) -> TokenStream {
         match self {
-            Ty::String => quote!(::prost::alloc::string::String),
+            Ty::String(ty) => ty.rust_type(),
             Ty::Bytes(ty) => ty.rust_type(),
             _ => self.rust_ref_type(),
         }
@@ -541,22 +626,71 @@ impl Ty {
             Ty::Sfixed32 => quote!(i32),
             Ty::Sfixed64 => quote!(i64),
             Ty::Bool => quote!(bool),
-            Ty::String => quote!(&str),
+            Ty::String(..) => quote!(&str),
             Ty::Bytes(..) => quote!(&[u8]),
             Ty::Enumeration(..) => quote!(i32),
         }
     }
 
-    pub fn module(&self) -> Ident {
+    /// Returns the path of the encoding module for the type, relative to `::prost::encoding`.
+    pub fn module(&self) -> TokenStream {
+        match *self {
+            Ty::Enumeration(..) => quote!(int32),
+            Ty::String(StringTy::Str) => quote!(borrowed::string),
+            Ty::Bytes(BytesTy::Slice) => quote!(borrowed::bytes),
+            _ => {
+                let module = Ident::new(self.as_str(), Span::call_site());
+                quote!(#module)
+            }
+        }
+    }
+
+    /// Returns true if the scalar type is a custom string or bytes type.
+    pub fn is_custom(&self) -> bool {
+        matches!(
+            self,
+            Ty::String(StringTy::Custom(..)) | Ty::Bytes(BytesTy::Custom(..))
+        )
+    }
+
+    /// Returns an expression which borrows the value of `expr` as a `&str` or `&[u8]` if the
+    /// scalar type is a custom string or bytes type, so that it can be compared with a default
+    /// value.
+    pub fn as_ref(&self, expr: TokenStream) -> TokenStream {
         match *self {
-            Ty::Enumeration(..) => Ident::new("int32", Span::call_site()),
-            _ => Ident::new(self.as_str(), Span::call_site()),
+            Ty::String(StringTy::Custom(..)) => {
+                quote!(::core::convert::AsRef::<str>::as_ref(&#expr))
+            }
+            Ty::Bytes(BytesTy::Custom(..)) => {
+                quote!(::core::convert::AsRef::<[u8]>::as_ref(&#expr))
+            }
+            _ => expr,
         }
     }
 
+    /// Returns an expression which creates a value of the string or bytes type from `value`, a
+    /// `&str` or a `&[u8]`.
+    pub fn adapt(&self, value: TokenStream) -> TokenStream {
+        let ty = self.rust_type();
+        let adapter = match *self {
+            Ty::String(..) => quote!(::prost::StringAdapter),
+            _ => quote!(::prost::BytesAdapter),
+        };
+        quote! {{
+            let mut adapted: #ty = ::core::default::Default::default();
+            #adapter::replace_with(&mut adapted, #value);
+            adapted
+        }}
+    }
+
+    /// Returns true if the scalar type borrows from the decoded buffer.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, Ty::String(StringTy::Str) | Ty::Bytes(BytesTy::Slice))
+    }
+
     /// Returns false if the scalar type is length delimited (i.e., `string` or `bytes`).
     pub fn is_numeric(&self) -> bool {
-        !matches!(self, Ty::String | Ty::Bytes(..))
+        !matches!(self, Ty::String(..) | Ty::Bytes(..))
     }
 }
 
@@ -648,10 +782,8 @@ impl DefaultValue {
             Lit::Int(ref lit) if *ty == Ty::Double => DefaultValue::F64(lit.base10_parse()?),
 
             Lit::Bool(ref lit) if *ty == Ty::Bool => DefaultValue::Bool(lit.value),
-            Lit::Str(ref lit) if *ty == Ty::String => DefaultValue::String(lit.value()),
-            Lit::ByteStr(ref lit)
-                if *ty == Ty::Bytes(BytesTy::Bytes) || *ty == Ty::Bytes(BytesTy::Vec) =>
-            {
+            Lit::Str(ref lit) if matches!(ty, Ty::String(..)) => DefaultValue::String(lit.value()),
+            Lit::ByteStr(ref lit) if matches!(ty, Ty::Bytes(..)) => {
                 DefaultValue::Bytes(lit.value())
             }
 
@@ -757,7 +889,7 @@ impl DefaultValue {
             Ty::Uint64 | Ty::Fixed64 => DefaultValue::U64(0),
 
             Ty::Bool => DefaultValue::Bool(false),
-            Ty::String => DefaultValue::String(String::new()),
+            Ty::String(..) => DefaultValue::String(String::new()),
             Ty::Bytes(..) => DefaultValue::Bytes(Vec::new()),
             Ty::Enumeration(ref path) => DefaultValue::Enumeration(quote!(#path::default())),
         }
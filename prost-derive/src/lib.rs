@@ -5,7 +5,7 @@
 extern crate alloc;
 extern crate proc_macro;
 
-use anyhow::{bail, Error};
+use anyhow::{anyhow, bail, Error};
 use itertools::Itertools;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
@@ -15,7 +15,9 @@ use syn::{
     FieldsUnnamed, Ident, Variant,
 };
 
+mod diff;
 mod field;
+mod reflect;
 use crate::field::Field;
 
 fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
@@ -32,6 +34,13 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    // Messages with a lifetime parameter borrow from the buffer they are decoded from.
+    let mut lifetimes = generics.lifetimes().map(|def| &def.lifetime);
+    let lifetime = lifetimes.next();
+    if lifetimes.next().is_some() {
+        bail!("message {} has more than one lifetime parameter", ident);
+    }
+
     let fields = match variant_data {
         DataStruct {
             fields: Fields::Named(FieldsNamed { named: fields, .. }),
@@ -51,6 +60,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     };
 
     let mut next_tag: u32 = 1;
+    let mut unknown_fields: Option<Ident> = None;
+    let mut extensions: Option<(Ident, Vec<(u32, u32)>)> = None;
     let mut fields = fields
         .into_iter()
         .enumerate()
@@ -58,6 +69,44 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             let field_ident = field
                 .ident
                 .unwrap_or_else(|| Ident::new(&idx.to_string(), Span::call_site()));
+            match field::extensions_attr(&field.attrs) {
+                Ok(Some(_)) if extensions.is_some() => {
+                    return Some(Err(anyhow!(
+                        "message {} has multiple extensions members",
+                        ident
+                    )));
+                }
+                Ok(Some(ranges)) => {
+                    extensions = Some((field_ident, ranges));
+                    return None;
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    return Some(Err(err.context(format!(
+                        "invalid message field {}.{}",
+                        ident, field_ident
+                    ))));
+                }
+            }
+            match field::unknown_fields_attr(&field.attrs) {
+                Ok(true) if unknown_fields.is_some() => {
+                    return Some(Err(anyhow!(
+                        "message {} has multiple unknown_fields members",
+                        ident
+                    )));
+                }
+                Ok(true) => {
+                    unknown_fields = Some(field_ident);
+                    return None;
+                }
+                Ok(false) => (),
+                Err(err) => {
+                    return Some(Err(err.context(format!(
+                        "invalid message field {}.{}",
+                        ident, field_ident
+                    ))));
+                }
+            }
             match Field::new(field.attrs, Some(next_tag)) {
                 Ok(Some(field)) => {
                     next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
@@ -91,6 +140,18 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     if tags.len() != num_tags {
         bail!("message {} has fields with duplicate tags", ident);
     }
+    if let Some((_, ref ranges)) = extensions {
+        if tags.iter().any(|tag| {
+            ranges
+                .iter()
+                .any(|&(start, end)| start <= *tag && *tag < end)
+        }) {
+            bail!(
+                "message {} has fields with tags in its extension ranges",
+                ident
+            );
+        }
+    }
 
     let encoded_len = fields
         .iter()
@@ -116,6 +177,101 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         }
     });
 
+    // Fields with unrecognized tags are either retained in the unknown field store, or skipped.
+    let merge_unknown = match unknown_fields {
+        Some(ref field_ident) => quote! {
+            self.#field_ident.merge_field(tag, wire_type, buf, ctx)
+        },
+        None => quote!(::prost::encoding::skip_field(wire_type, tag, buf, ctx)),
+    };
+    // Fields with tags in the extension ranges are retained in the extension set.
+    let merge_extensions = extensions.iter().map(|&(ref field_ident, ref ranges)| {
+        let ranges = ranges.iter().map(|&(start, end)| {
+            let end = end - 1;
+            quote!(#start..=#end)
+        });
+        let ranges = Itertools::intersperse(ranges, quote!(|));
+        quote! {
+            #(#ranges)* => self.#field_ident.merge_field(tag, wire_type, buf, ctx),
+        }
+    });
+    let extension_fields = extensions.iter().map(|(field_ident, _)| field_ident);
+    let encode_extensions = extension_fields
+        .clone()
+        .map(|field_ident| quote!(self.#field_ident.encode_raw(buf);))
+        .collect::<Vec<_>>();
+    let encoded_len_extensions = extension_fields
+        .clone()
+        .map(|field_ident| quote!(self.#field_ident.encoded_len()));
+    let clear_extensions = extension_fields
+        .clone()
+        .map(|field_ident| quote!(self.#field_ident.clear()));
+    let default_extensions = extension_fields
+        .clone()
+        .map(|field_ident| quote!(#field_ident: ::core::default::Default::default(),));
+    let debug_extensions = extension_fields.map(|field_ident| {
+        quote! {
+            let builder = builder.field(stringify!(#field_ident), &self.#field_ident);
+        }
+    });
+    let extendable = extensions.iter().map(|&(ref field_ident, ref ranges)| {
+        let ranges = ranges.iter().map(|&(start, end)| quote!((#start, #end)));
+        quote! {
+            impl #impl_generics ::prost::ExtendableMessage for #ident #ty_generics #where_clause {
+                const EXTENSION_RANGES: &'static [(u32, u32)] = &[#(#ranges),*];
+
+                fn extensions(&self) -> &::prost::ExtensionSet {
+                    &self.#field_ident
+                }
+
+                fn extensions_mut(&mut self) -> &mut ::prost::ExtensionSet {
+                    &mut self.#field_ident
+                }
+            }
+        }
+    });
+
+    let encode_unknown = unknown_fields
+        .iter()
+        .map(|field_ident| quote!(self.#field_ident.encode_raw(buf);))
+        .collect::<Vec<_>>();
+    let encoded_len_unknown = unknown_fields
+        .iter()
+        .map(|field_ident| quote!(self.#field_ident.encoded_len()));
+    let clear_unknown = unknown_fields
+        .iter()
+        .map(|field_ident| quote!(self.#field_ident.clear()));
+    let default_unknown = unknown_fields
+        .iter()
+        .map(|field_ident| quote!(#field_ident: ::core::default::Default::default(),));
+    let debug_unknown = unknown_fields.iter().map(|field_ident| {
+        quote! {
+            let builder = builder.field(stringify!(#field_ident), &self.#field_ident);
+        }
+    });
+
+    // The deterministic encoding only differs for maps and fields holding messages, so other
+    // messages keep the default, which encodes as usual.
+    let encode_raw_deterministic = if fields
+        .iter()
+        .any(|&(_, ref field)| field.has_deterministic_encoding())
+    {
+        let encode_deterministic = fields.iter().map(|&(ref field_ident, ref field)| {
+            field.encode_deterministic(quote!(self.#field_ident))
+        });
+        quote! {
+            #[allow(unused_variables)]
+            fn encode_raw_deterministic<B>(&self, buf: &mut B)
+            where B: ::prost::bytes::BufMut {
+                #(#encode_deterministic)*
+                #(#encode_extensions)*
+                #(#encode_unknown)*
+            }
+        }
+    } else {
+        quote!()
+    };
+
     let struct_name = if fields.is_empty() {
         quote!()
     } else {
@@ -171,36 +327,213 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(f.debug_tuple(stringify!(#ident)))
     };
 
-    let expanded = quote! {
-        impl #impl_generics ::prost::Message for #ident #ty_generics #where_clause {
-            #[allow(unused_variables)]
-            fn encode_raw<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
-                #(#encode)*
+    // Singular message fields record the fields decoded for their messages across all of their
+    // occurrences, so that the required fields are checked once the whole message is decoded.
+    let merge_tracked = fields
+        .iter()
+        .filter_map(|&(ref field_ident, ref field)| {
+            let merge = field.merge_tracked(quote!(value))?;
+            let tags = field.tags().into_iter().map(|tag| quote!(#tag));
+            let tags = Itertools::intersperse(tags, quote!(|));
+            Some(quote! {
+                #(#tags)* => {
+                    let value = &mut self.#field_ident;
+                    let result = #merge;
+                    result.map_err(|mut error| {
+                        error.push(STRUCT_NAME, stringify!(#field_ident));
+                        error
+                    })
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let merge_field_tracked = if merge_tracked.is_empty() {
+        quote!()
+    } else {
+        let signature = match lifetime {
+            Some(lifetime) => quote! {
+                fn merge_field_tracked(
+                    &mut self,
+                    tag: u32,
+                    wire_type: ::prost::encoding::WireType,
+                    buf: &mut &#lifetime [u8],
+                    ctx: ::prost::encoding::DecodeContext,
+                    presence: &mut ::prost::encoding::FieldPresence,
+                ) -> ::core::result::Result<(), ::prost::DecodeError>
+            },
+            None => quote! {
+                fn merge_field_tracked<B>(
+                    &mut self,
+                    tag: u32,
+                    wire_type: ::prost::encoding::WireType,
+                    buf: &mut B,
+                    ctx: ::prost::encoding::DecodeContext,
+                    presence: &mut ::prost::encoding::FieldPresence,
+                ) -> ::core::result::Result<(), ::prost::DecodeError>
+                where B: ::prost::bytes::Buf
+            },
+        };
+        quote! {
+            #signature {
+                #struct_name
+                match tag {
+                    #(#merge_tracked)*
+                    _ => self.merge_field(tag, wire_type, buf, ctx),
+                }
             }
+        }
+    };
 
-            #[allow(unused_variables)]
-            fn merge_field<B>(
+    let check_required = fields
+        .iter()
+        .filter_map(|&(ref field_ident, ref field)| {
+            let check = field.check_required(quote!(self.#field_ident))?;
+            Some(quote! {
+                if let ::core::result::Result::Err(mut error) = #check {
+                    error.push(STRUCT_NAME, stringify!(#field_ident));
+                    return ::core::result::Result::Err(error);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let check_required_fields = if check_required.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn check_required_fields(
+                &self,
+                presence: &::prost::encoding::FieldPresence,
+            ) -> ::core::result::Result<(), ::prost::DecodeError> {
+                #struct_name
+                #(#check_required)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+
+    // Message fields with a nested projection only merge the selected fields of the message.
+    // Other selected fields are merged in full.
+    let merge_projected = fields
+        .iter()
+        .filter_map(|&(ref field_ident, ref field)| {
+            let merge = field.merge_projected(quote!(value))?;
+            let tag = field.tags()[0];
+            Some(quote! {
+                #tag => {
+                    let value = &mut self.#field_ident;
+                    #merge.map_err(|mut error| {
+                        error.push(STRUCT_NAME, stringify!(#field_ident));
+                        error
+                    })
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let merge_field_projected = if merge_projected.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn merge_field_projected<B>(
                 &mut self,
                 tag: u32,
                 wire_type: ::prost::encoding::WireType,
                 buf: &mut B,
                 ctx: ::prost::encoding::DecodeContext,
+                projection: &::prost::Projection,
             ) -> ::core::result::Result<(), ::prost::DecodeError>
             where B: ::prost::bytes::Buf {
+                #struct_name
+                match projection.get(tag) {
+                    ::core::option::Option::None => {
+                        ::prost::encoding::skip_field(wire_type, tag, buf, ctx)
+                    }
+                    ::core::option::Option::Some(
+                        ::prost::projection::Selection::Nested(projection),
+                    ) => match tag {
+                        #(#merge_projected)*
+                        _ => self.merge_field(tag, wire_type, buf, ctx),
+                    },
+                    ::core::option::Option::Some(::prost::projection::Selection::Field) => {
+                        self.merge_field(tag, wire_type, buf, ctx)
+                    }
+                }
+            }
+        }
+    };
+
+    // Borrowed messages don't have a deterministic encoding mode or projected decoding.
+    let (message_trait, merge_field, message_methods) = match lifetime {
+        Some(lifetime) => (
+            quote!(::prost::BorrowedMessage<#lifetime>),
+            quote! {
+                fn merge_field(
+                    &mut self,
+                    tag: u32,
+                    wire_type: ::prost::encoding::WireType,
+                    buf: &mut &#lifetime [u8],
+                    ctx: ::prost::encoding::DecodeContext,
+                ) -> ::core::result::Result<(), ::prost::DecodeError>
+            },
+            quote! {
+                #merge_field_tracked
+
+                #check_required_fields
+            },
+        ),
+        None => (
+            quote!(::prost::Message),
+            quote! {
+                fn merge_field<B>(
+                    &mut self,
+                    tag: u32,
+                    wire_type: ::prost::encoding::WireType,
+                    buf: &mut B,
+                    ctx: ::prost::encoding::DecodeContext,
+                ) -> ::core::result::Result<(), ::prost::DecodeError>
+                where B: ::prost::bytes::Buf
+            },
+            quote! {
+                #encode_raw_deterministic
+
+                #merge_field_tracked
+
+                #check_required_fields
+
+                #merge_field_projected
+            },
+        ),
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #message_trait for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode_raw<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
+                #(#encode)*
+                #(#encode_extensions)*
+                #(#encode_unknown)*
+            }
+
+            #message_methods
+
+            #[allow(unused_variables)]
+            #merge_field {
                 #struct_name
                 match tag {
                     #(#merge)*
-                    _ => ::prost::encoding::skip_field(wire_type, tag, buf, ctx),
+                    #(#merge_extensions)*
+                    _ => #merge_unknown,
                 }
             }
 
             #[inline]
             fn encoded_len(&self) -> usize {
-                0 #(+ #encoded_len)*
+                0 #(+ #encoded_len)* #(+ #encoded_len_extensions)* #(+ #encoded_len_unknown)*
             }
 
             fn clear(&mut self) {
                 #(#clear;)*
+                #(#clear_extensions;)*
+                #(#clear_unknown;)*
             }
         }
 
@@ -208,6 +541,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             fn default() -> Self {
                 #ident {
                     #(#default)*
+                    #(#default_extensions)*
+                    #(#default_unknown)*
                 }
             }
         }
@@ -216,10 +551,14 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 let mut builder = #debug_builder;
                 #(#debugs;)*
+                #(#debug_extensions)*
+                #(#debug_unknown)*
                 builder.finish()
             }
         }
 
+        #(#extendable)*
+
         #methods
     };
 
@@ -244,13 +583,17 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
         Data::Union(..) => bail!("Enumeration can not be derived for a union"),
     };
 
+    let reflect = reflect::reflect_attr(&input.attrs)?;
+    let full_name = reflect::full_name_attr(&input.attrs)?.unwrap_or_else(|| ident.to_string());
+
     // Map the variants into 'fields'.
     let mut variants: Vec<(Ident, Expr)> = Vec::new();
+    let mut names = Vec::new();
     for Variant {
+        attrs,
         ident,
         fields,
         discriminant,
-        ..
     } in punctuated_variants
     {
         match fields {
@@ -260,6 +603,10 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
             }
         }
 
+        names.push(match field::name_attr(&attrs)? {
+            Some(name) => name,
+            None => ident.to_string(),
+        });
         match discriminant {
             Some((_, expr)) => variants.push((ident, expr)),
             None => bail!("Enumeration variants must have a disriminant"),
@@ -279,6 +626,26 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
         |&(ref variant, ref value)| quote!(#value => ::core::option::Option::Some(#ident::#variant)),
     );
 
+    let reflect_enum = if reflect {
+        let values = names
+            .iter()
+            .zip(&variants)
+            .map(|(name, &(_, ref value))| quote!((#name, #value)));
+        quote! {
+            impl #impl_generics ::prost::reflect::ReflectEnum
+                for #ident #ty_generics #where_clause
+            {
+                fn enum_descriptor() -> &'static ::prost::reflect::EnumDescriptor {
+                    static DESCRIPTOR: ::prost::reflect::EnumDescriptor =
+                        ::prost::reflect::EnumDescriptor::new(#full_name, &[#(#values),*]);
+                    &DESCRIPTOR
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
     let is_valid_doc = format!("Returns `true` if `value` is a variant of `{}`.", ident);
     let from_i32_doc = format!(
         "Converts an `i32` to a `{}`, or `None` if `value` is not a valid variant.",
@@ -310,6 +677,8 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
             }
         }
 
+        #reflect_enum
+
         impl #impl_generics ::core::convert::From::<#ident> for i32 #ty_generics #where_clause {
             fn from(value: #ident) -> i32 {
                 value as i32
@@ -388,6 +757,11 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(#ident::#variant_ident(ref value) => { #encode })
     });
 
+    let encode_deterministic = fields.iter().map(|&(ref variant_ident, ref field)| {
+        let encode = field.encode_deterministic(quote!(*value));
+        quote!(#ident::#variant_ident(ref value) => { #encode })
+    });
+
     let merge = fields.iter().map(|&(ref variant_ident, ref field)| {
         let tag = field.tags()[0];
         let merge = field.merge(quote!(value));
@@ -407,6 +781,35 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         }
     });
 
+    let merge_tracked = fields.iter().filter_map(|&(ref variant_ident, ref field)| {
+        let tag = field.tags()[0];
+        let merge = field.merge_tracked_value(quote!(value), quote!(true))?;
+        let merge_owned = field.merge_tracked_value(quote!(value), quote!(false))?;
+        Some(quote! {
+            #tag => {
+                match field {
+                    ::core::option::Option::Some(#ident::#variant_ident(ref mut value)) => {
+                        #merge
+                    },
+                    _ => {
+                        let mut owned_value = ::core::default::Default::default();
+                        let value = &mut owned_value;
+                        #merge_owned.map(|_| {
+                            *field = ::core::option::Option::Some(#ident::#variant_ident(owned_value))
+                        })
+                    },
+                }
+            }
+        })
+    });
+
+    let check_required = fields.iter().map(|&(ref variant_ident, ref field)| {
+        let check = field
+            .check_required_value(quote!(value))
+            .unwrap_or_else(|| quote!(::core::result::Result::Ok(())));
+        quote!(#ident::#variant_ident(ref value) => #check)
+    });
+
     let encoded_len = fields.iter().map(|&(ref variant_ident, ref field)| {
         let encoded_len = field.encoded_len(quote!(*value));
         quote!(#ident::#variant_ident(ref value) => #encoded_len)
@@ -431,6 +834,13 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
+            /// Encodes the message to a buffer, with the entries of maps in key order.
+            pub fn encode_deterministic<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
+                match *self {
+                    #(#encode_deterministic,)*
+                }
+            }
+
             /// Decodes an instance of the message from a buffer, and merges it into self.
             pub fn merge<B>(
                 field: &mut ::core::option::Option<#ident #ty_generics>,
@@ -446,6 +856,36 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
+            /// Decodes an instance of the message from a buffer, and merges it into self, recording
+            /// the decoded fields of a singular message in `presence`.
+            #[allow(unused_variables)]
+            pub fn merge_tracked<B>(
+                field: &mut ::core::option::Option<#ident #ty_generics>,
+                tag: u32,
+                wire_type: ::prost::encoding::WireType,
+                buf: &mut B,
+                ctx: ::prost::encoding::DecodeContext,
+                presence: &mut ::prost::encoding::FieldPresence,
+            ) -> ::core::result::Result<(), ::prost::DecodeError>
+            where B: ::prost::bytes::Buf {
+                match tag {
+                    #(#merge_tracked,)*
+                    _ => #ident::merge(field, tag, wire_type, buf, ctx),
+                }
+            }
+
+            /// Checks the required fields of the message held by the oneof, with the presence of
+            /// the fields of the enclosing message.
+            #[allow(unused_variables)]
+            pub fn check_required_fields(
+                &self,
+                presence: &::prost::encoding::FieldPresence,
+            ) -> ::core::result::Result<(), ::prost::DecodeError> {
+                match *self {
+                    #(#check_required,)*
+                }
+            }
+
             /// Returns the encoded length of the message without a length delimiter.
             #[inline]
             pub fn encoded_len(&self) -> usize {
@@ -471,3 +911,21 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
 pub fn oneof(input: TokenStream) -> TokenStream {
     try_oneof(input).unwrap()
 }
+
+#[proc_macro_derive(ReflectMessage, attributes(prost))]
+pub fn reflect_message(input: TokenStream) -> TokenStream {
+    syn::parse(input)
+        .map_err(Error::from)
+        .and_then(reflect::try_reflect_message)
+        .unwrap()
+        .into()
+}
+
+#[proc_macro_derive(DiffMessage, attributes(prost))]
+pub fn diff_message(input: TokenStream) -> TokenStream {
+    syn::parse(input)
+        .map_err(Error::from)
+        .and_then(diff::try_diff_message)
+        .unwrap()
+        .into()
+}
@@ -0,0 +1,886 @@
+//! Code generation for the `ReflectMessage` derive.
+
+use anyhow::{bail, Error};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Attribute, Data, DataEnum, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Ident,
+    Lit, Meta, MetaList, MetaNameValue, NestedMeta, Variant,
+};
+
+use crate::field::{self, map, scalar, Field, Label};
+
+pub fn try_reflect_message(input: DeriveInput) -> Result<TokenStream, Error> {
+    let ident = input.ident;
+    if input.generics.lifetimes().next().is_some() {
+        bail!(
+            "ReflectMessage can not be derived for borrowed message {}",
+            ident
+        );
+    }
+    match input.data {
+        Data::Struct(variant_data) => reflect_struct(ident, &input.attrs, variant_data),
+        Data::Enum(DataEnum { variants, .. }) => reflect_oneof(ident, variants),
+        Data::Union(..) => bail!("ReflectMessage can not be derived for a union"),
+    }
+}
+
+fn reflect_struct(
+    ident: Ident,
+    attrs: &[Attribute],
+    variant_data: DataStruct,
+) -> Result<TokenStream, Error> {
+    let full_name = full_name_attr(attrs)?.unwrap_or_else(|| ident.to_string());
+
+    let fields = match variant_data.fields {
+        Fields::Named(FieldsNamed { named: fields, .. })
+        | Fields::Unnamed(FieldsUnnamed {
+            unnamed: fields, ..
+        }) => fields.into_iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let mut next_tag: u32 = 1;
+    let mut reflected = Vec::new();
+    for (idx, field) in fields.into_iter().enumerate() {
+        let field_ident = field
+            .ident
+            .unwrap_or_else(|| Ident::new(&idx.to_string(), Span::call_site()));
+        if field::unknown_fields_attr(&field.attrs)?
+            || field::extensions_attr(&field.attrs)?.is_some()
+        {
+            continue;
+        }
+        let name = match field::name_attr(&field.attrs)? {
+            Some(name) => name,
+            None => field_ident.to_string().trim_start_matches("r#").to_owned(),
+        };
+        let json_name = match field::json_name_attr(&field.attrs)? {
+            Some(json_name) => json_name,
+            None => to_json_name(&name),
+        };
+        let ty = field.ty;
+        if let Some(field) = Field::new(field.attrs, Some(next_tag))? {
+            next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
+            reflected.push((field_ident, name, json_name, ty, field));
+        }
+    }
+
+    let mut descriptors = Vec::new();
+    let mut oneofs = Vec::new();
+    let mut get = Vec::new();
+    let mut get_ref = Vec::new();
+    let mut get_message_mut = Vec::new();
+    let mut has = Vec::new();
+    let mut set = Vec::new();
+    let mut clear = Vec::new();
+
+    for (field_ident, name, json_name, ty, field) in &reflected {
+        let member = quote!(self.#field_ident);
+
+        if let Field::Converted(..) = *field {
+            bail!(
+                "field {} has a convert attribute, which is not supported by reflection",
+                field_ident
+            );
+        }
+
+        if let Field::Message(ref message) = *field {
+            if message.lazy {
+                bail!(
+                    "field {} is lazy, which is not supported by reflection",
+                    field_ident
+                );
+            }
+        }
+
+        if let Field::Oneof(ref oneof) = *field {
+            let ty = &oneof.ty;
+            let tags = oneof.tags.iter().map(|tag| quote!(#tag));
+            let tags = quote!(#(#tags)|*);
+            oneofs.push(quote! {
+                ::prost::reflect::OneofDescriptor::new(
+                    #name,
+                    <#ty as ::prost::reflect::ReflectOneof>::FIELDS,
+                )
+            });
+            let is_set = quote! {
+                match #member {
+                    ::core::option::Option::Some(ref oneof) => {
+                        ::prost::reflect::ReflectOneof::number(oneof) == number
+                    }
+                    ::core::option::Option::None => false,
+                }
+            };
+            let oneof_get = |get_fn: TokenStream| {
+                quote! {
+                    #tags => match #member {
+                        ::core::option::Option::Some(ref oneof)
+                            if ::prost::reflect::ReflectOneof::number(oneof) == number =>
+                        {
+                            ::core::option::Option::Some(
+                                ::prost::reflect::ReflectOneof::#get_fn(oneof),
+                            )
+                        }
+                        _ => ::core::option::Option::None,
+                    },
+                }
+            };
+            get.push(oneof_get(quote!(get)));
+            get_ref.push(oneof_get(quote!(get_ref)));
+            get_message_mut.push(quote! {
+                #tags => {
+                    if !#is_set {
+                        let message = <Self as ::prost::ReflectMessage>::type_descriptor()
+                            .field(number)?
+                            .message_type()?
+                            .new_message();
+                        #member = ::core::option::Option::Some(
+                            ::prost::reflect::ReflectOneof::from_value(
+                                number,
+                                ::prost::reflect::Value::Message(message),
+                            )
+                            .ok()?,
+                        );
+                    }
+                    match #member {
+                        ::core::option::Option::Some(ref mut oneof) => {
+                            ::prost::reflect::ReflectOneof::get_message_mut(oneof)
+                        }
+                        ::core::option::Option::None => ::core::option::Option::None,
+                    }
+                }
+            });
+            has.push(quote!(#tags => #is_set,));
+            set.push(quote! {
+                #tags => {
+                    #member = ::core::option::Option::Some(
+                        ::prost::reflect::ReflectOneof::from_value(number, value)?,
+                    );
+                }
+            });
+            clear.push(quote! {
+                #tags => {
+                    if #is_set {
+                        #member = ::core::option::Option::None;
+                    }
+                }
+            });
+            continue;
+        }
+
+        let tag = field.tags()[0];
+        let kind = kind(field);
+        let cardinality = cardinality(field);
+        let message_ty = quote!(<#ty as ::prost::reflect::MessageField>::Message);
+        let types = field_types(field, &message_ty);
+        descriptors.push(quote! {
+            ::prost::reflect::FieldDescriptor::new(#name, #json_name, #tag, #kind, #cardinality)
+                #types
+        });
+
+        let (has_expr, from_value) = match *field {
+            Field::Scalar(ref scalar) => {
+                let from_value = scalar_from_value(&scalar.ty);
+                match scalar.kind {
+                    scalar::Kind::Plain(ref default) => {
+                        let default = default.typed();
+                        let value = scalar.ty.as_ref(member.clone());
+                        (quote!(#value != #default), quote!(#from_value?))
+                    }
+                    scalar::Kind::Required(..) => (quote!(true), quote!(#from_value?)),
+                    scalar::Kind::Optional(..) => (
+                        quote!(#member.is_some()),
+                        quote!(::core::option::Option::Some(#from_value?)),
+                    ),
+                    scalar::Kind::Repeated | scalar::Kind::Packed => {
+                        (quote!(!#member.is_empty()), list_from_value(&from_value))
+                    }
+                }
+            }
+            Field::Message(ref message) => singular_message(&member, message.label),
+            Field::Group(ref group) => singular_message(&member, group.label),
+            Field::Map(ref map) => {
+                let key_from_value = scalar_from_value(&map.key_ty);
+                let value_from_value = match map.value_ty {
+                    map::ValueTy::Scalar(ref ty) => scalar_from_value(ty),
+                    map::ValueTy::Message => message_from_value(),
+                };
+                (
+                    quote!(!#member.is_empty()),
+                    quote! {
+                        match value {
+                            ::prost::reflect::Value::Map(entries) => entries
+                                .into_iter()
+                                .map(|(key, value)| {
+                                    let key = {
+                                        let value = key;
+                                        #key_from_value?
+                                    };
+                                    let value = #value_from_value?;
+                                    ::core::result::Result::<_, ::prost::reflect::ReflectError>::Ok(
+                                        (key, value),
+                                    )
+                                })
+                                .collect::<::core::result::Result<_, _>>()?,
+                            _ => return ::core::result::Result::Err(error),
+                        }
+                    },
+                )
+            }
+            Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+        };
+
+        let get_expr = field_to_value(field, &member, false);
+        let get_ref_expr = field_to_value(field, &member, true);
+        get.push(quote!(#tag => { #get_expr },));
+        get_ref.push(quote!(#tag => { #get_ref_expr },));
+        if let Some(message_mut) = message_mut(field, &member) {
+            get_message_mut.push(quote!(#tag => #message_mut,));
+        }
+        has.push(quote!(#tag => #has_expr,));
+        set.push(quote! {
+            #tag => {
+                let error = ::prost::reflect::ReflectError::TypeMismatch {
+                    field: #name,
+                    expected: #kind,
+                };
+                #member = #from_value;
+            }
+        });
+        let clear_field = field.clear(member.clone());
+        clear.push(quote!(#tag => { #clear_field; }));
+    }
+
+    let common = common_methods();
+    Ok(quote! {
+        impl ::prost::ReflectMessage for #ident {
+            fn descriptor(&self) -> &'static ::prost::reflect::MessageDescriptor {
+                <Self as ::prost::ReflectMessage>::type_descriptor()
+            }
+
+            fn type_descriptor() -> &'static ::prost::reflect::MessageDescriptor {
+                const FIELDS: &[::prost::reflect::FieldDescriptor] = &[#(#descriptors),*];
+                const ONEOFS: &[::prost::reflect::OneofDescriptor] = &[#(#oneofs),*];
+                fn new_message() -> ::prost::alloc::boxed::Box<dyn ::prost::ReflectMessage> {
+                    ::prost::alloc::boxed::Box::new(<#ident as ::core::default::Default>::default())
+                }
+                static DESCRIPTOR: ::prost::reflect::MessageDescriptor =
+                    ::prost::reflect::MessageDescriptor::new(
+                        #full_name,
+                        FIELDS,
+                        ONEOFS,
+                        new_message,
+                    );
+                &DESCRIPTOR
+            }
+
+            #[allow(unused_variables)]
+            fn get_field(&self, number: u32) -> ::core::option::Option<::prost::reflect::Value> {
+                match number {
+                    #(#get)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn get_field_ref(
+                &self,
+                number: u32,
+            ) -> ::core::option::Option<::prost::reflect::ValueRef<'_>> {
+                match number {
+                    #(#get_ref)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn get_message_mut(
+                &mut self,
+                number: u32,
+            ) -> ::core::option::Option<&mut dyn ::prost::ReflectMessage> {
+                match number {
+                    #(#get_message_mut)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn has_field(&self, number: u32) -> bool {
+                match number {
+                    #(#has)*
+                    _ => false,
+                }
+            }
+
+            #[allow(unused_variables, unreachable_code)]
+            fn set_field(
+                &mut self,
+                number: u32,
+                value: ::prost::reflect::Value,
+            ) -> ::core::result::Result<(), ::prost::reflect::ReflectError> {
+                match number {
+                    #(#set)*
+                    _ => return ::core::result::Result::Err(
+                        ::prost::reflect::ReflectError::UnknownField(number),
+                    ),
+                }
+                ::core::result::Result::Ok(())
+            }
+
+            #[allow(unreachable_code)]
+            fn clear_field(&mut self, number: u32) -> bool {
+                match number {
+                    #(#clear)*
+                    _ => return false,
+                }
+                true
+            }
+
+            #common
+        }
+    })
+}
+
+fn reflect_oneof(
+    ident: Ident,
+    variants: syn::punctuated::Punctuated<Variant, syn::token::Comma>,
+) -> Result<TokenStream, Error> {
+    let mut fields = Vec::new();
+    for Variant {
+        attrs,
+        ident: variant_ident,
+        fields: variant_fields,
+        ..
+    } in variants
+    {
+        let name = match field::name_attr(&attrs)? {
+            Some(name) => name,
+            None => to_snake(&variant_ident.to_string()),
+        };
+        let json_name = match field::json_name_attr(&attrs)? {
+            Some(json_name) => json_name,
+            None => to_json_name(&name),
+        };
+        let ty = match variant_fields {
+            Fields::Unnamed(FieldsUnnamed { unnamed, .. }) if unnamed.len() == 1 => {
+                unnamed.into_iter().next().unwrap().ty
+            }
+            _ => bail!("Oneof variants must have a single unnamed field"),
+        };
+        match Field::new_oneof(attrs)? {
+            Some(Field::Message(ref message)) if message.lazy => bail!(
+                "oneof variant {} is lazy, which is not supported by reflection",
+                variant_ident
+            ),
+            Some(field) => fields.push((variant_ident, name, json_name, ty, field)),
+            None => bail!("invalid oneof variant: oneof variants may not be ignored"),
+        }
+    }
+
+    let descriptors = fields.iter().map(|(_, name, json_name, ty, field)| {
+        let tag = field.tags()[0];
+        let kind = kind(field);
+        let types = field_types(field, &quote!(#ty));
+        quote! {
+            ::prost::reflect::FieldDescriptor::new(
+                #name,
+                #json_name,
+                #tag,
+                #kind,
+                ::prost::reflect::Cardinality::Optional,
+            )
+            #types
+        }
+    });
+
+    let numbers = fields.iter().map(|(variant_ident, _, _, _, field)| {
+        let tag = field.tags()[0];
+        quote!(#ident::#variant_ident(..) => #tag,)
+    });
+
+    let get = |by_ref: bool| {
+        fields
+            .iter()
+            .map(|(variant_ident, _, _, _, field)| {
+                let to_value = match *field {
+                    Field::Scalar(ref scalar) => scalar_to_value(&scalar.ty, by_ref),
+                    _ => message_to_value(by_ref),
+                };
+                quote!(#ident::#variant_ident(ref value) => #to_value,)
+            })
+            .collect::<Vec<_>>()
+    };
+    let get_ref = get(true);
+    let get = get(false);
+
+    let get_message_mut = fields
+        .iter()
+        .map(|(variant_ident, _, _, _, field)| match *field {
+            Field::Message(..) | Field::Group(..) => quote! {
+                #ident::#variant_ident(ref mut value) => ::core::option::Option::Some(value),
+            },
+            _ => quote!(#ident::#variant_ident(..) => ::core::option::Option::None,),
+        });
+
+    let from_value = fields.iter().map(|(variant_ident, name, _, _, field)| {
+        let tag = field.tags()[0];
+        let kind = kind(field);
+        let from_value = match *field {
+            Field::Scalar(ref scalar) => scalar_from_value(&scalar.ty),
+            _ => message_from_value(),
+        };
+        quote! {
+            #tag => {
+                let error = ::prost::reflect::ReflectError::TypeMismatch {
+                    field: #name,
+                    expected: #kind,
+                };
+                ::core::result::Result::Ok(#ident::#variant_ident(#from_value?))
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::prost::reflect::ReflectOneof for #ident {
+            const FIELDS: &'static [::prost::reflect::FieldDescriptor] = &[#(#descriptors),*];
+
+            fn number(&self) -> u32 {
+                match *self {
+                    #(#numbers)*
+                }
+            }
+
+            fn get(&self) -> ::prost::reflect::Value {
+                match *self {
+                    #(#get)*
+                }
+            }
+
+            fn get_ref(&self) -> ::prost::reflect::ValueRef<'_> {
+                match *self {
+                    #(#get_ref)*
+                }
+            }
+
+            fn get_message_mut(
+                &mut self,
+            ) -> ::core::option::Option<&mut dyn ::prost::ReflectMessage> {
+                match *self {
+                    #(#get_message_mut)*
+                }
+            }
+
+            fn from_value(
+                number: u32,
+                value: ::prost::reflect::Value,
+            ) -> ::core::result::Result<Self, ::prost::reflect::ReflectError> {
+                match number {
+                    #(#from_value)*
+                    _ => ::core::result::Result::Err(
+                        ::prost::reflect::ReflectError::UnknownField(number),
+                    ),
+                }
+            }
+        }
+    })
+}
+
+/// Returns `true` if an enumeration opts into reflection with `#[prost(reflect)]`.
+pub fn reflect_attr(attrs: &[Attribute]) -> Result<bool, Error> {
+    let mut reflect = false;
+    for attr in attrs.iter().flat_map(Attribute::parse_meta) {
+        let nested = match attr {
+            Meta::List(MetaList { path, nested, .. }) if path.is_ident("prost") => nested,
+            _ => continue,
+        };
+        for meta in nested {
+            match meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("reflect") => {
+                    if reflect {
+                        bail!("duplicate reflect attributes");
+                    }
+                    reflect = true;
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(reflect)
+}
+
+/// Returns the full type name from a `#[prost(full_name = "...")]` struct or enum attribute.
+pub fn full_name_attr(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    let mut full_name = None;
+    for attr in attrs.iter().flat_map(Attribute::parse_meta) {
+        let nested = match attr {
+            Meta::List(MetaList { path, nested, .. }) if path.is_ident("prost") => nested,
+            _ => continue,
+        };
+        for meta in nested {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(lit),
+                    ..
+                })) if path.is_ident("full_name") => field::set_option(
+                    &mut full_name,
+                    lit.value(),
+                    "duplicate full_name attributes",
+                )?,
+                _ => (),
+            }
+        }
+    }
+    Ok(full_name)
+}
+
+/// Converts a oneof variant name to the default `.proto` field name.
+pub fn to_snake(s: &str) -> String {
+    let mut snake = String::with_capacity(s.len());
+    for (i, c) in s.trim_start_matches("r#").char_indices() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Converts a `.proto` field name to its default JSON name, following `protoc`.
+fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// Returns the `FieldDescriptor` builder calls which link the field to its message or enum type.
+/// `message_ty` is the Rust type of the field's messages.
+fn field_types(field: &Field, message_ty: &TokenStream) -> TokenStream {
+    let message_type = quote! {
+        .with_message_type(<#message_ty as ::prost::ReflectMessage>::type_descriptor)
+    };
+    let enum_type = |ty: &scalar::Ty| match *ty {
+        scalar::Ty::Enumeration(ref path) => quote! {
+            .with_enum_type(<#path as ::prost::reflect::ReflectEnum>::enum_descriptor)
+        },
+        _ => TokenStream::new(),
+    };
+    match *field {
+        Field::Scalar(ref scalar) => enum_type(&scalar.ty),
+        Field::Message(..) | Field::Group(..) => message_type,
+        Field::Map(ref map) => match map.value_ty {
+            map::ValueTy::Scalar(ref ty) => enum_type(ty),
+            map::ValueTy::Message => message_type,
+        },
+        Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+    }
+}
+
+/// Returns an expression which evaluates to the `Kind` of the field.
+fn kind(field: &Field) -> TokenStream {
+    match *field {
+        Field::Scalar(ref scalar) => scalar_kind(&scalar.ty),
+        Field::Message(..) => quote!(::prost::reflect::Kind::Message),
+        Field::Group(..) => quote!(::prost::reflect::Kind::Group),
+        Field::Map(ref map) => {
+            let key = scalar_kind(&map.key_ty);
+            let value = match map.value_ty {
+                map::ValueTy::Scalar(ref ty) => scalar_kind(ty),
+                map::ValueTy::Message => quote!(::prost::reflect::Kind::Message),
+            };
+            quote!(::prost::reflect::Kind::Map(&#key, &#value))
+        }
+        Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+    }
+}
+
+fn scalar_kind(ty: &scalar::Ty) -> TokenStream {
+    let kind = match *ty {
+        scalar::Ty::Double => quote!(Double),
+        scalar::Ty::Float => quote!(Float),
+        scalar::Ty::Int32 => quote!(Int32),
+        scalar::Ty::Int64 => quote!(Int64),
+        scalar::Ty::Uint32 => quote!(Uint32),
+        scalar::Ty::Uint64 => quote!(Uint64),
+        scalar::Ty::Sint32 => quote!(Sint32),
+        scalar::Ty::Sint64 => quote!(Sint64),
+        scalar::Ty::Fixed32 => quote!(Fixed32),
+        scalar::Ty::Fixed64 => quote!(Fixed64),
+        scalar::Ty::Sfixed32 => quote!(Sfixed32),
+        scalar::Ty::Sfixed64 => quote!(Sfixed64),
+        scalar::Ty::Bool => quote!(Bool),
+        scalar::Ty::String(..) => quote!(String),
+        scalar::Ty::Bytes(..) => quote!(Bytes),
+        scalar::Ty::Enumeration(..) => quote!(Enum),
+    };
+    quote!(::prost::reflect::Kind::#kind)
+}
+
+/// Returns an expression which evaluates to the `Cardinality` of the field.
+fn cardinality(field: &Field) -> TokenStream {
+    let label = match *field {
+        Field::Scalar(ref scalar) => match scalar.kind {
+            scalar::Kind::Plain(..) => return quote!(::prost::reflect::Cardinality::Singular),
+            scalar::Kind::Optional(..) => Label::Optional,
+            scalar::Kind::Required(..) => Label::Required,
+            scalar::Kind::Repeated | scalar::Kind::Packed => Label::Repeated,
+        },
+        Field::Message(ref message) => message.label,
+        Field::Group(ref group) => group.label,
+        Field::Map(..) => Label::Repeated,
+        Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+    };
+    match label {
+        Label::Optional => quote!(::prost::reflect::Cardinality::Optional),
+        Label::Required => quote!(::prost::reflect::Cardinality::Required),
+        Label::Repeated => quote!(::prost::reflect::Cardinality::Repeated),
+    }
+}
+
+/// Returns the `Value` variant which holds values of the scalar type, or the `ValueRef` variant
+/// if `by_ref` is `true`.
+fn value_variant(ty: &scalar::Ty, by_ref: bool) -> TokenStream {
+    let variant = match *ty {
+        scalar::Ty::Double => quote!(F64),
+        scalar::Ty::Float => quote!(F32),
+        scalar::Ty::Int32 | scalar::Ty::Sint32 | scalar::Ty::Sfixed32 => quote!(I32),
+        scalar::Ty::Int64 | scalar::Ty::Sint64 | scalar::Ty::Sfixed64 => quote!(I64),
+        scalar::Ty::Uint32 | scalar::Ty::Fixed32 => quote!(U32),
+        scalar::Ty::Uint64 | scalar::Ty::Fixed64 => quote!(U64),
+        scalar::Ty::Bool => quote!(Bool),
+        scalar::Ty::String(..) => quote!(String),
+        scalar::Ty::Bytes(..) => quote!(Bytes),
+        scalar::Ty::Enumeration(..) => quote!(EnumNumber),
+    };
+    let value = value_type(by_ref);
+    quote!(#value::#variant)
+}
+
+/// Returns the path of `Value`, or of `ValueRef` if `by_ref` is `true`.
+fn value_type(by_ref: bool) -> TokenStream {
+    if by_ref {
+        quote!(::prost::reflect::ValueRef)
+    } else {
+        quote!(::prost::reflect::Value)
+    }
+}
+
+/// Returns an expression which converts a reference to a scalar, bound to `value`, to a `Value`,
+/// or to a `ValueRef` if `by_ref` is `true`.
+fn scalar_to_value(ty: &scalar::Ty, by_ref: bool) -> TokenStream {
+    let variant = value_variant(ty, by_ref);
+    match *ty {
+        scalar::Ty::String(..) if by_ref => {
+            quote!(#variant(::core::convert::AsRef::<str>::as_ref(value)))
+        }
+        scalar::Ty::Bytes(..) if by_ref => {
+            quote!(#variant(::core::convert::AsRef::<[u8]>::as_ref(value)))
+        }
+        scalar::Ty::String(..) => quote!(#variant(::prost::alloc::string::String::from(
+            ::core::convert::AsRef::<str>::as_ref(value)
+        ))),
+        scalar::Ty::Bytes(..) => quote!(#variant(
+            ::core::convert::AsRef::<[u8]>::as_ref(value).to_vec()
+        )),
+        _ => quote!(#variant(*value)),
+    }
+}
+
+/// Returns an expression which converts the `Value` bound to `value` to a scalar, or evaluates to
+/// a clone of `error` if the value has a different type.
+fn scalar_from_value(ty: &scalar::Ty) -> TokenStream {
+    let variant = value_variant(ty, false);
+    let convert = match *ty {
+        scalar::Ty::String(..) | scalar::Ty::Bytes(..) if ty.is_custom() => {
+            ty.adapt(quote!(&value[..]))
+        }
+        scalar::Ty::Bytes(ref bytes) => {
+            let rust_ty = scalar::Ty::Bytes(bytes.clone()).rust_type();
+            quote!(<#rust_ty as ::core::convert::From<::prost::alloc::vec::Vec<u8>>>::from(value))
+        }
+        _ => quote!(value),
+    };
+    quote! {
+        match value {
+            #variant(value) => ::core::result::Result::Ok(#convert),
+            _ => ::core::result::Result::Err(::core::clone::Clone::clone(&error)),
+        }
+    }
+}
+
+/// Returns an expression which converts a reference to a message, bound to `value`, to a `Value`,
+/// or to a `ValueRef` if `by_ref` is `true`.
+fn message_to_value(by_ref: bool) -> TokenStream {
+    if by_ref {
+        quote!(::prost::reflect::ValueRef::Message(value))
+    } else {
+        quote!(::prost::reflect::Value::Message(
+            ::prost::ReflectMessage::clone_boxed(value)
+        ))
+    }
+}
+
+/// Returns an expression which converts the `Value` bound to `value` to a message.
+fn message_from_value() -> TokenStream {
+    quote! {
+        match value {
+            ::prost::reflect::Value::Message(value) => ::prost::ReflectMessage::from_boxed(value)
+                .map_err(|_| ::core::clone::Clone::clone(&error)),
+            _ => ::core::result::Result::Err(::core::clone::Clone::clone(&error)),
+        }
+    }
+}
+
+fn list_to_value(member: &TokenStream, to_value: &TokenStream, by_ref: bool) -> TokenStream {
+    let value = value_type(by_ref);
+    quote! {
+        ::core::option::Option::Some(#value::List(
+            #member.iter().map(|value| #to_value).collect(),
+        ))
+    }
+}
+
+fn list_from_value(from_value: &TokenStream) -> TokenStream {
+    quote! {
+        match value {
+            ::prost::reflect::Value::List(values) => values
+                .into_iter()
+                .map(|value| #from_value)
+                .collect::<::core::result::Result<_, _>>()?,
+            _ => return ::core::result::Result::Err(error),
+        }
+    }
+}
+
+/// Returns an expression which evaluates to the optional `Value` of the field `member`, or to its
+/// `ValueRef` if `by_ref` is `true`.
+fn field_to_value(field: &Field, member: &TokenStream, by_ref: bool) -> TokenStream {
+    let (to_value, label) = match *field {
+        Field::Scalar(ref scalar) => {
+            let to_value = scalar_to_value(&scalar.ty, by_ref);
+            let label = match scalar.kind {
+                scalar::Kind::Plain(..) | scalar::Kind::Required(..) => Label::Required,
+                scalar::Kind::Optional(..) => Label::Optional,
+                scalar::Kind::Repeated | scalar::Kind::Packed => Label::Repeated,
+            };
+            (to_value, label)
+        }
+        Field::Message(ref message) => (message_to_value(by_ref), message.label),
+        Field::Group(ref group) => (message_to_value(by_ref), group.label),
+        Field::Map(ref map) => {
+            let key_to_value = scalar_to_value(&map.key_ty, by_ref);
+            let value_to_value = match map.value_ty {
+                map::ValueTy::Scalar(ref ty) => scalar_to_value(ty, by_ref),
+                map::ValueTy::Message => message_to_value(by_ref),
+            };
+            let value = value_type(by_ref);
+            return quote! {
+                ::core::option::Option::Some(#value::Map(
+                    #member
+                        .iter()
+                        .map(|(key, value)| {
+                            let key_value = {
+                                let value = key;
+                                #key_to_value
+                            };
+                            (key_value, #value_to_value)
+                        })
+                        .collect(),
+                ))
+            };
+        }
+        Field::Oneof(..) | Field::Converted(..) => unreachable!(),
+    };
+    match label {
+        Label::Optional => quote!(#member.as_ref().map(|value| #to_value)),
+        Label::Required => quote! {
+            let value = &#member;
+            ::core::option::Option::Some(#to_value)
+        },
+        Label::Repeated => list_to_value(member, &to_value, by_ref),
+    }
+}
+
+/// Returns an expression which evaluates to a mutable reference to the field `member`, setting it
+/// to an empty message if it isn't set, if it's a singular message or group field.
+fn message_mut(field: &Field, member: &TokenStream) -> Option<TokenStream> {
+    let label = match *field {
+        Field::Message(ref message) => message.label,
+        Field::Group(ref group) => group.label,
+        _ => return None,
+    };
+    match label {
+        Label::Optional => Some(quote! {
+            ::core::option::Option::Some(
+                #member.get_or_insert_with(::core::default::Default::default),
+            )
+        }),
+        Label::Required => Some(quote!(::core::option::Option::Some(&mut #member))),
+        Label::Repeated => None,
+    }
+}
+
+/// Returns the has and set expressions for a message or group field.
+fn singular_message(member: &TokenStream, label: Label) -> (TokenStream, TokenStream) {
+    let from_value = message_from_value();
+    match label {
+        Label::Optional => (
+            quote!(#member.is_some()),
+            quote!(::core::option::Option::Some(#from_value?)),
+        ),
+        Label::Required => (quote!(true), quote!(#from_value?)),
+        Label::Repeated => (quote!(!#member.is_empty()), list_from_value(&from_value)),
+    }
+}
+
+/// Returns the `ReflectMessage` methods which do not depend on the message's fields.
+fn common_methods() -> TokenStream {
+    quote! {
+        fn clone_boxed(&self) -> ::prost::alloc::boxed::Box<dyn ::prost::ReflectMessage> {
+            ::prost::alloc::boxed::Box::new(::core::clone::Clone::clone(self))
+        }
+
+        fn eq_dyn(&self, other: &dyn ::prost::ReflectMessage) -> bool {
+            match other.as_any().downcast_ref::<Self>() {
+                ::core::option::Option::Some(other) => self == other,
+                ::core::option::Option::None => false,
+            }
+        }
+
+        fn encode_dyn(&self, buf: &mut ::prost::alloc::vec::Vec<u8>) {
+            ::prost::Message::encode_raw(self, buf)
+        }
+
+        fn merge_dyn(
+            &mut self,
+            buf: &[u8],
+        ) -> ::core::result::Result<(), ::prost::DecodeError> {
+            ::prost::Message::merge(self, buf)
+        }
+
+        fn as_any(&self) -> &dyn ::core::any::Any {
+            self
+        }
+
+        fn into_any(
+            self: ::prost::alloc::boxed::Box<Self>,
+        ) -> ::prost::alloc::boxed::Box<dyn ::core::any::Any> {
+            self
+        }
+    }
+}
@@ -1,27 +1,33 @@
 /// The version number of protocol compiler.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.compiler.Version")]
 pub struct Version {
-    #[prost(int32, optional, tag="1")]
+    #[prost(int32, optional, tag="1", name="major")]
     pub major: ::core::option::Option<i32>,
-    #[prost(int32, optional, tag="2")]
+    #[prost(int32, optional, tag="2", name="minor")]
     pub minor: ::core::option::Option<i32>,
-    #[prost(int32, optional, tag="3")]
+    #[prost(int32, optional, tag="3", name="patch")]
     pub patch: ::core::option::Option<i32>,
     /// A suffix for alpha, beta or rc release, e.g., "alpha-1", "rc2". It should
     /// be empty for mainline stable releases.
-    #[prost(string, optional, tag="4")]
+    #[prost(string, optional, tag="4", name="suffix")]
     pub suffix: ::core::option::Option<::prost::alloc::string::String>,
 }
+impl ::prost::Name for Version {
+    const NAME: &'static str = "Version";
+    const PACKAGE: &'static str = "google.protobuf.compiler";
+}
 /// An encoded CodeGeneratorRequest is written to the plugin's stdin.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.compiler.CodeGeneratorRequest")]
 pub struct CodeGeneratorRequest {
     /// The .proto files that were explicitly listed on the command-line.  The
     /// code generator should generate code only for these files.  Each file's
     /// descriptor will be included in proto_file, below.
-    #[prost(string, repeated, tag="1")]
+    #[prost(string, repeated, tag="1", name="file_to_generate")]
     pub file_to_generate: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     /// The generator parameter passed on the command-line.
-    #[prost(string, optional, tag="2")]
+    #[prost(string, optional, tag="2", name="parameter")]
     pub parameter: ::core::option::Option<::prost::alloc::string::String>,
     /// FileDescriptorProtos for all files in files_to_generate and everything
     /// they import.  The files will appear in topological order, so each file
@@ -37,14 +43,19 @@ pub struct CodeGeneratorRequest {
     ///
     /// Type names of fields and extensions in the FileDescriptorProto are always
     /// fully qualified.
-    #[prost(message, repeated, tag="15")]
+    #[prost(message, repeated, tag="15", name="proto_file")]
     pub proto_file: ::prost::alloc::vec::Vec<super::FileDescriptorProto>,
     /// The version number of protocol compiler.
-    #[prost(message, optional, tag="3")]
+    #[prost(message, optional, tag="3", name="compiler_version")]
     pub compiler_version: ::core::option::Option<Version>,
 }
+impl ::prost::Name for CodeGeneratorRequest {
+    const NAME: &'static str = "CodeGeneratorRequest";
+    const PACKAGE: &'static str = "google.protobuf.compiler";
+}
 /// The plugin writes an encoded CodeGeneratorResponse to stdout.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.compiler.CodeGeneratorResponse")]
 pub struct CodeGeneratorResponse {
     /// Error message.  If non-empty, code generation failed.  The plugin process
     /// should exit with status code zero even if it reports an error in this way.
@@ -54,19 +65,24 @@ pub struct CodeGeneratorResponse {
     /// problem in protoc itself -- such as the input CodeGeneratorRequest being
     /// unparseable -- should be reported by writing a message to stderr and
     /// exiting with a non-zero status code.
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="error")]
     pub error: ::core::option::Option<::prost::alloc::string::String>,
     /// A bitmask of supported features that the code generator supports.
     /// This is a bitwise "or" of values from the Feature enum.
-    #[prost(uint64, optional, tag="2")]
+    #[prost(uint64, optional, tag="2", name="supported_features")]
     pub supported_features: ::core::option::Option<u64>,
-    #[prost(message, repeated, tag="15")]
+    #[prost(message, repeated, tag="15", name="file")]
     pub file: ::prost::alloc::vec::Vec<code_generator_response::File>,
 }
+impl ::prost::Name for CodeGeneratorResponse {
+    const NAME: &'static str = "CodeGeneratorResponse";
+    const PACKAGE: &'static str = "google.protobuf.compiler";
+}
 /// Nested message and enum types in `CodeGeneratorResponse`.
 pub mod code_generator_response {
     /// Represents a single generated file.
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.compiler.CodeGeneratorResponse.File")]
     pub struct File {
         /// The file name, relative to the output directory.  The name must not
         /// contain "." or ".." components and must be relative, not be absolute (so,
@@ -79,7 +95,7 @@ pub mod code_generator_response {
         /// files need not reside completely in memory at one time.  Note that as of
         /// this writing protoc does not optimize for this -- it will read the entire
         /// CodeGeneratorResponse before writing files to disk.
-        #[prost(string, optional, tag="1")]
+        #[prost(string, optional, tag="1", name="name")]
         pub name: ::core::option::Option<::prost::alloc::string::String>,
         /// If non-empty, indicates that the named file should already exist, and the
         /// content here is to be inserted into that file at a defined insertion
@@ -118,22 +134,29 @@ pub mod code_generator_response {
         /// command line.
         ///
         /// If |insertion_point| is present, |name| must also be present.
-        #[prost(string, optional, tag="2")]
+        #[prost(string, optional, tag="2", name="insertion_point")]
         pub insertion_point: ::core::option::Option<::prost::alloc::string::String>,
         /// The file contents.
-        #[prost(string, optional, tag="15")]
+        #[prost(string, optional, tag="15", name="content")]
         pub content: ::core::option::Option<::prost::alloc::string::String>,
         /// Information describing the file content being inserted. If an insertion
         /// point is used, this information will be appropriately offset and inserted
         /// into the code generation metadata for the generated files.
-        #[prost(message, optional, tag="16")]
+        #[prost(message, optional, tag="16", name="generated_code_info")]
         pub generated_code_info: ::core::option::Option<super::super::GeneratedCodeInfo>,
     }
+    impl ::prost::Name for File {
+        const NAME: &'static str = "CodeGeneratorResponse.File";
+        const PACKAGE: &'static str = "google.protobuf.compiler";
+    }
     /// Sync with code_generator.h.
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.compiler.CodeGeneratorResponse.Feature")]
     pub enum Feature {
+        #[prost(name="FEATURE_NONE")]
         None = 0,
+        #[prost(name="FEATURE_PROTO3_OPTIONAL")]
         Proto3Optional = 1,
     }
     impl Feature {
@@ -0,0 +1,330 @@
+//! Field-level differences between two messages.
+//!
+//! [`diff`] walks two instances of a message type which implements [`DiffMessage`], which
+//! `prost-build` derives for the messages selected with `Config::diff`, and reports the fields
+//! which were added, removed or changed, each with its path from the root message:
+//!
+//! * A field is added or removed if it's set in only one of the messages. Fields without presence
+//!   are set if they differ from their default value, and repeated and map fields are set if
+//!   they're not empty.
+//! * Nested messages which are set in both messages are compared field by field, which includes
+//!   the message fields of a `oneof`. Setting a different field of a `oneof` removes the old
+//!   field and adds the new one.
+//! * Repeated fields are compared element by element, by index.
+//! * Map fields are compared entry by entry, by key. Changes are reported in the order of the
+//!   keys, so the result doesn't depend on the iteration order of the map.
+//!
+//! [`Diff`] is printed with one change per line:
+//!
+//! ```text
+//! ~ address.city
+//! + contacts["work"]
+//! - scores[2]
+//! ```
+//!
+//! Field masks can't address the elements of repeated and map fields, so
+//! [`Diff::field_mask`] covers the whole field for changes to elements or entries.
+//!
+//! `DiffMessage` doesn't need reflection, so messages can be diffed whether or not they implement
+//! `ReflectMessage`. The walk borrows the fields of both messages, so nested messages aren't
+//! copied: only the values of the reported changes are.
+
+use core::cmp::Ordering;
+use core::fmt::{self, Write};
+
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::diff::{DiffMessage, Value, ValueRef};
+
+use crate::FieldMask;
+
+/// Returns the differences from `old` to `new`.
+pub fn diff<M>(old: &M, new: &M) -> Diff
+where
+    M: DiffMessage,
+{
+    let mut differ = Differ::default();
+    old.diff_fields(new, &mut differ);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+/// The differences between two messages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    /// The changed fields, in the order of the fields of the messages.
+    pub changes: Vec<FieldChange>,
+}
+
+/// A change to a field, or to an element or entry of a repeated or map field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// The path to the changed value from the root message.
+    pub path: Vec<PathSegment>,
+    /// The change to the value.
+    pub change: Change,
+}
+
+/// A segment of the path of a [`FieldChange`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    /// A field of a message, by name.
+    Field(&'static str),
+    /// An element of a repeated field, by index.
+    Index(usize),
+    /// An entry of a map field, by key.
+    Key(Value),
+}
+
+/// How a value changed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// The value is only set in the new message.
+    Added(Value),
+    /// The value is only set in the old message.
+    Removed(Value),
+    /// The value is set to different values in both messages, with the old and new value.
+    Changed(Value, Value),
+}
+
+impl Diff {
+    /// Returns `true` if the messages are equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the canonical form of a mask which covers the changed fields.
+    ///
+    /// Copying the fields of the mask from the new message into the old message with
+    /// [`FieldMask::copy`] makes the messages equal.
+    pub fn field_mask(&self) -> FieldMask {
+        let mut mask = FieldMask::new(self.changes.iter().map(FieldChange::field_path));
+        mask.normalize();
+        mask
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            let sign = match change.change {
+                Change::Added(..) => '+',
+                Change::Removed(..) => '-',
+                Change::Changed(..) => '~',
+            };
+            writeln!(f, "{} {}", sign, change)?;
+        }
+        Ok(())
+    }
+}
+
+impl FieldChange {
+    /// Returns the path of the changed field as dotted field names, such as `"address.city"`.
+    ///
+    /// For changes to elements or entries of repeated and map fields, this is the path of the
+    /// repeated or map field.
+    pub fn field_path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match *segment {
+                PathSegment::Field(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                PathSegment::Index(..) | PathSegment::Key(..) => break,
+            }
+        }
+        path
+    }
+}
+
+/// Formats the path of the change, such as `contacts["home"].city` or `scores[2]`.
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.path.iter().enumerate() {
+            match *segment {
+                PathSegment::Field(name) => {
+                    if i > 0 {
+                        f.write_char('.')?;
+                    }
+                    f.write_str(name)?;
+                }
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+                PathSegment::Key(ref key) => {
+                    f.write_char('[')?;
+                    match *key {
+                        Value::Bool(key) => write!(f, "{}", key)?,
+                        Value::I32(key) => write!(f, "{}", key)?,
+                        Value::I64(key) => write!(f, "{}", key)?,
+                        Value::U32(key) => write!(f, "{}", key)?,
+                        Value::U64(key) => write!(f, "{}", key)?,
+                        Value::String(ref key) => write!(f, "{:?}", key)?,
+                        ref key => write!(f, "{:?}", key)?,
+                    }
+                    f.write_char(']')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Differ {
+    /// The path to the values being compared.
+    path: Vec<PathSegment>,
+    changes: Vec<FieldChange>,
+}
+
+impl Differ {
+    fn push(&mut self, change: Change) {
+        self.changes.push(FieldChange {
+            path: self.path.clone(),
+            change,
+        });
+    }
+
+    fn diff_values(&mut self, old: ValueRef<'_>, new: ValueRef<'_>) {
+        match (old, new) {
+            (ValueRef::Message(old), ValueRef::Message(new)) => old.diff_fields(new, self),
+            (ValueRef::List(old), ValueRef::List(new)) => self.diff_lists(old, new),
+            (ValueRef::Map(old), ValueRef::Map(new)) => self.diff_maps(old, new),
+            (old, new) => {
+                if old != new {
+                    self.push(Change::Changed(old.to_value(), new.to_value()));
+                }
+            }
+        }
+    }
+
+    fn diff_lists(&mut self, old: Vec<ValueRef<'_>>, new: Vec<ValueRef<'_>>) {
+        let mut old = old.into_iter();
+        let mut new = new.into_iter();
+        for index in 0.. {
+            self.path.push(PathSegment::Index(index));
+            match (old.next(), new.next()) {
+                (None, None) => {
+                    self.path.pop();
+                    break;
+                }
+                (None, Some(new)) => self.push(Change::Added(new.to_value())),
+                (Some(old), None) => self.push(Change::Removed(old.to_value())),
+                (Some(old), Some(new)) => self.diff_values(old, new),
+            }
+            self.path.pop();
+        }
+    }
+
+    fn diff_maps(
+        &mut self,
+        mut old: Vec<(ValueRef<'_>, ValueRef<'_>)>,
+        mut new: Vec<(ValueRef<'_>, ValueRef<'_>)>,
+    ) {
+        old.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+        new.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+        let mut old = old.into_iter().peekable();
+        let mut new = new.into_iter().peekable();
+        loop {
+            let ordering = match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(old), Some(new)) => cmp_keys(&old.0, &new.0),
+            };
+            match ordering {
+                Ordering::Less => {
+                    let (key, old) = old.next().unwrap();
+                    self.path.push(PathSegment::Key(key.to_value()));
+                    self.push(Change::Removed(old.to_value()));
+                }
+                Ordering::Greater => {
+                    let (key, new) = new.next().unwrap();
+                    self.path.push(PathSegment::Key(key.to_value()));
+                    self.push(Change::Added(new.to_value()));
+                }
+                Ordering::Equal => {
+                    let (key, old) = old.next().unwrap();
+                    let (_, new) = new.next().unwrap();
+                    self.path.push(PathSegment::Key(key.to_value()));
+                    self.diff_values(old, new);
+                }
+            }
+            self.path.pop();
+        }
+    }
+}
+
+impl prost::diff::Differ for Differ {
+    fn diff_field(
+        &mut self,
+        name: &'static str,
+        old: Option<ValueRef<'_>>,
+        new: Option<ValueRef<'_>>,
+    ) {
+        self.path.push(PathSegment::Field(name));
+        match (old, new) {
+            (None, None) => (),
+            (None, Some(new)) => self.push(Change::Added(new.to_value())),
+            (Some(old), None) => self.push(Change::Removed(old.to_value())),
+            (Some(old), Some(new)) => self.diff_values(old, new),
+        }
+        self.path.pop();
+    }
+}
+
+/// Compares map keys, which are integers, booleans or strings.
+fn cmp_keys(a: &ValueRef<'_>, b: &ValueRef<'_>) -> Ordering {
+    match (a, b) {
+        (ValueRef::Bool(a), ValueRef::Bool(b)) => a.cmp(b),
+        (ValueRef::I32(a), ValueRef::I32(b)) => a.cmp(b),
+        (ValueRef::I64(a), ValueRef::I64(b)) => a.cmp(b),
+        (ValueRef::U32(a), ValueRef::U32(b)) => a.cmp(b),
+        (ValueRef::U64(a), ValueRef::U64(b)) => a.cmp(b),
+        (ValueRef::String(a), ValueRef::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::string::ToString;
+
+    use prost::{DiffMessage, Message};
+
+    use super::*;
+
+    #[derive(Clone, PartialEq, Message, DiffMessage)]
+    struct Counters {
+        #[prost(hash_map = "string, int32", tag = "1")]
+        counts: HashMap<String, i32>,
+        #[prost(hash_map = "int64, message", tag = "2")]
+        children: HashMap<i64, Counters>,
+    }
+
+    #[test]
+    fn hash_map() {
+        let mut old = Counters::default();
+        let mut new = Counters::default();
+        for i in 0..20 {
+            old.counts.insert(i.to_string(), i);
+            new.counts.insert(i.to_string(), i * 2);
+        }
+        old.children.insert(-1, old.clone());
+        new.children.insert(-1, Counters::default());
+        new.children.insert(5, Counters::default());
+
+        // Entries are reported in the order of the keys, whatever the iteration order of the maps.
+        let diff = diff(&old, &new);
+        let paths: Vec<String> = diff.changes.iter().map(ToString::to_string).collect();
+        let mut expected: Vec<String> = (1..20).map(|i| format!("counts[\"{}\"]", i)).collect();
+        expected.sort();
+        expected.push("children[-1].counts".to_string());
+        expected.push("children[5]".to_string());
+        assert_eq!(paths, expected);
+        assert_eq!(diff.field_mask().paths, ["children", "counts"]);
+    }
+}
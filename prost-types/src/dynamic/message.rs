@@ -0,0 +1,765 @@
+use core::fmt;
+
+use prost::alloc::borrow::Cow;
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::bytes::{Buf, BufMut};
+use prost::encoding::{
+    bool, bytes, double, encode_key, encode_varint, encoded_len_varint, fixed32, fixed64, float,
+    group, int32, int64, key_len, message, sfixed32, sfixed64, sint32, sint64, string, uint32,
+    uint64, DecodeContext, FieldPresence, WireType,
+};
+use prost::{DecodeError, DecodeErrorKind, Message, UnknownFields};
+
+use super::{FieldDescriptor, Kind, MapKey, MessageDescriptor, Value};
+use crate::field_descriptor_proto::Label;
+
+/// A Protobuf message whose type is described by a [`MessageDescriptor`] at runtime.
+///
+/// Field values are stored as [`Value`]s, keyed by field number. Fields which are not defined by
+/// the message type are kept as unknown fields, and are written back out when the message is
+/// re-encoded.
+#[derive(Clone)]
+pub struct DynamicMessage {
+    desc: MessageDescriptor,
+    fields: BTreeMap<u32, Value>,
+    unknown_fields: UnknownFields,
+}
+
+/// An error returned when setting a field of a [`DynamicMessage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetFieldError {
+    /// The message type has no field with the given number.
+    UnknownField(u32),
+    /// The message type has no field with the given name.
+    UnknownFieldName(String),
+    /// The value does not have the type of the named field.
+    InvalidValue { field: String },
+}
+
+impl fmt::Display for SetFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetFieldError::UnknownField(number) => write!(f, "unknown field number {}", number),
+            SetFieldError::UnknownFieldName(name) => write!(f, "unknown field {}", name),
+            SetFieldError::InvalidValue { field } => {
+                write!(f, "invalid value for field {}", field)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetFieldError {}
+
+impl DynamicMessage {
+    /// Creates a message of the given type with all fields unset.
+    pub fn new(desc: MessageDescriptor) -> DynamicMessage {
+        DynamicMessage {
+            desc,
+            fields: BTreeMap::new(),
+            unknown_fields: UnknownFields::new(),
+        }
+    }
+
+    /// Decodes a message of the given type from a buffer.
+    ///
+    /// The entire buffer will be consumed.
+    pub fn decode<B>(desc: MessageDescriptor, buf: B) -> Result<DynamicMessage, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut message = DynamicMessage::new(desc);
+        message.merge(buf)?;
+        Ok(message)
+    }
+
+    /// Returns the type of the message.
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.desc
+    }
+
+    /// Returns `true` if the field with the given number is set.
+    ///
+    /// Fields without presence are only considered set if they hold a non-default value, and
+    /// repeated fields if they are non-empty.
+    pub fn has_field(&self, number: u32) -> bool {
+        match (self.desc.get_field(number), self.fields.get(&number)) {
+            (Some(field), Some(value)) => !is_default(&field, value),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the field with the given name is set.
+    pub fn has_field_by_name(&self, name: &str) -> bool {
+        match self.desc.get_field_by_name(name) {
+            Some(field) => self.has_field(field.number()),
+            None => false,
+        }
+    }
+
+    /// Returns the value of the field with the given number, or `None` if the message type has no
+    /// such field. Unset fields return their default value.
+    pub fn get_field(&self, number: u32) -> Option<Cow<'_, Value>> {
+        let field = self.desc.get_field(number)?;
+        Some(match self.fields.get(&number) {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(Value::default_for_field(&field)),
+        })
+    }
+
+    /// Returns the value of the field with the given name. See [`DynamicMessage::get_field`].
+    pub fn get_field_by_name(&self, name: &str) -> Option<Cow<'_, Value>> {
+        let field = self.desc.get_field_by_name(name)?;
+        self.get_field(field.number())
+    }
+
+    /// Returns a mutable reference to the message in the singular message field with the given
+    /// number, setting it to an empty message if it is unset, and clearing the other fields of its
+    /// oneof.
+    ///
+    /// Returns `None` if the message type has no such field, or if it doesn't hold a single
+    /// message.
+    pub fn get_message_mut(&mut self, number: u32) -> Option<&mut DynamicMessage> {
+        let field = self.desc.get_field(number)?;
+        if field.is_list() || field.is_map() {
+            return None;
+        }
+        self.field_mut(&field).as_message_mut()
+    }
+
+    /// Returns a mutable reference to the message in the field with the given name. See
+    /// [`DynamicMessage::get_message_mut`].
+    pub fn get_message_mut_by_name(&mut self, name: &str) -> Option<&mut DynamicMessage> {
+        let field = self.desc.get_field_by_name(name)?;
+        self.get_message_mut(field.number())
+    }
+
+    /// Appends a value to the repeated field with the given number.
+    pub fn push_field(&mut self, number: u32, value: Value) -> Result<(), SetFieldError> {
+        let field = self
+            .desc
+            .get_field(number)
+            .ok_or(SetFieldError::UnknownField(number))?;
+        self.push(&field, value)
+    }
+
+    /// Appends a value to the repeated field with the given name.
+    pub fn push_field_by_name(&mut self, name: &str, value: Value) -> Result<(), SetFieldError> {
+        let field = self
+            .desc
+            .get_field_by_name(name)
+            .ok_or_else(|| SetFieldError::UnknownFieldName(name.into()))?;
+        self.push(&field, value)
+    }
+
+    fn push(&mut self, field: &FieldDescriptor, value: Value) -> Result<(), SetFieldError> {
+        if !field.is_list() || !value.is_valid_for_kind(&field.kind()) {
+            return Err(SetFieldError::InvalidValue {
+                field: field.name().into(),
+            });
+        }
+        self.field_mut(field)
+            .as_list_mut()
+            .expect("invalid value for field")
+            .push(value);
+        Ok(())
+    }
+
+    /// Inserts an entry into the map field with the given number, returning the value previously
+    /// stored under the key.
+    pub fn insert_map_entry(
+        &mut self,
+        number: u32,
+        key: MapKey,
+        value: Value,
+    ) -> Result<Option<Value>, SetFieldError> {
+        let field = self
+            .desc
+            .get_field(number)
+            .ok_or(SetFieldError::UnknownField(number))?;
+        self.insert(&field, key, value)
+    }
+
+    /// Inserts an entry into the map field with the given name. See
+    /// [`DynamicMessage::insert_map_entry`].
+    pub fn insert_map_entry_by_name(
+        &mut self,
+        name: &str,
+        key: MapKey,
+        value: Value,
+    ) -> Result<Option<Value>, SetFieldError> {
+        let field = self
+            .desc
+            .get_field_by_name(name)
+            .ok_or_else(|| SetFieldError::UnknownFieldName(name.into()))?;
+        self.insert(&field, key, value)
+    }
+
+    fn insert(
+        &mut self,
+        field: &FieldDescriptor,
+        key: MapKey,
+        value: Value,
+    ) -> Result<Option<Value>, SetFieldError> {
+        let valid = field.is_map() && {
+            let (key_field, value_field) = map_entry_fields(&field.kind());
+            Value::from(key.clone()).is_valid_for_kind(&key_field.kind())
+                && value.is_valid_for_kind(&value_field.kind())
+        };
+        if !valid {
+            return Err(SetFieldError::InvalidValue {
+                field: field.name().into(),
+            });
+        }
+        Ok(self
+            .field_mut(field)
+            .as_map_mut()
+            .expect("invalid value for field")
+            .insert(key, value))
+    }
+
+    /// Sets the field with the given number, clearing the other fields of its oneof.
+    pub fn set_field(&mut self, number: u32, value: Value) -> Result<(), SetFieldError> {
+        let field = self
+            .desc
+            .get_field(number)
+            .ok_or(SetFieldError::UnknownField(number))?;
+        self.set(&field, value)
+    }
+
+    /// Sets the field with the given name, clearing the other fields of its oneof.
+    pub fn set_field_by_name(&mut self, name: &str, value: Value) -> Result<(), SetFieldError> {
+        let field = self
+            .desc
+            .get_field_by_name(name)
+            .ok_or_else(|| SetFieldError::UnknownFieldName(name.into()))?;
+        self.set(&field, value)
+    }
+
+    fn set(&mut self, field: &FieldDescriptor, value: Value) -> Result<(), SetFieldError> {
+        if !value.is_valid_for_field(field) {
+            return Err(SetFieldError::InvalidValue {
+                field: field.name().into(),
+            });
+        }
+        self.clear_oneof_siblings(field);
+        self.fields.insert(field.number(), value);
+        Ok(())
+    }
+
+    /// Clears the field with the given number, returning `false` if the message type has no such
+    /// field.
+    pub fn clear_field(&mut self, number: u32) -> bool {
+        if self.desc.get_field(number).is_none() {
+            return false;
+        }
+        self.fields.remove(&number);
+        true
+    }
+
+    /// Clears the field with the given name. See [`DynamicMessage::clear_field`].
+    pub fn clear_field_by_name(&mut self, name: &str) -> bool {
+        match self.desc.get_field_by_name(name) {
+            Some(field) => self.clear_field(field.number()),
+            None => false,
+        }
+    }
+
+    /// Returns an iterator over the fields which are set, in field number order.
+    pub fn fields(&self) -> impl Iterator<Item = (FieldDescriptor, &Value)> + '_ {
+        self.fields.iter().filter_map(move |(&number, value)| {
+            let field = self.desc.get_field(number)?;
+            if is_default(&field, value) {
+                None
+            } else {
+                Some((field, value))
+            }
+        })
+    }
+
+    /// Returns the fields which were not recognized while decoding the message.
+    pub fn unknown_fields(&self) -> &UnknownFields {
+        &self.unknown_fields
+    }
+
+    /// Returns a mutable reference to the value of the field, setting it to its default value if
+    /// it is unset, and clearing the other fields of its oneof.
+    ///
+    /// The value must not be replaced with a value of a different type, since encoding relies on
+    /// the values matching their fields.
+    fn field_mut(&mut self, field: &FieldDescriptor) -> &mut Value {
+        self.clear_oneof_siblings(field);
+        self.fields
+            .entry(field.number())
+            .or_insert_with(|| Value::default_for_field(field))
+    }
+
+    fn clear_oneof_siblings(&mut self, field: &FieldDescriptor) {
+        let oneof = match field.oneof_index() {
+            Some(oneof) => oneof,
+            None => return,
+        };
+        for sibling in self.desc.fields() {
+            if sibling.oneof_index() == Some(oneof) && sibling.number() != field.number() {
+                self.fields.remove(&sibling.number());
+            }
+        }
+    }
+}
+
+/// Returns `true` if the value is not considered set, and is not encoded.
+fn is_default(field: &FieldDescriptor, value: &Value) -> bool {
+    match value {
+        Value::List(values) => values.is_empty(),
+        Value::Map(entries) => entries.is_empty(),
+        value => !field.has_presence() && *value == Value::default_for_field(field),
+    }
+}
+
+/// Returns the key and value fields of a map field's entry type. The pool checks that every map
+/// entry type has both when its file is added.
+fn map_entry_fields(kind: &Kind) -> (FieldDescriptor, FieldDescriptor) {
+    match kind {
+        Kind::Message(entry) => match (entry.get_field(1), entry.get_field(2)) {
+            (Some(key), Some(value)) => (key, value),
+            _ => unreachable!("unchecked map entry type {}", entry.full_name()),
+        },
+        _ => unreachable!("map field type is not a message"),
+    }
+}
+
+/// Calls an `encoding` function for a list of numeric values, which are collected into a `Vec`
+/// of the corresponding Rust type first.
+macro_rules! packed {
+    ($kind:expr, $list:expr, |$module:ident, $values:ident| $body:expr) => {
+        match $kind {
+            Kind::Double => packed!(@ $list, double, F64, $module, $values, $body),
+            Kind::Float => packed!(@ $list, float, F32, $module, $values, $body),
+            Kind::Int32 => packed!(@ $list, int32, I32, $module, $values, $body),
+            Kind::Int64 => packed!(@ $list, int64, I64, $module, $values, $body),
+            Kind::Uint32 => packed!(@ $list, uint32, U32, $module, $values, $body),
+            Kind::Uint64 => packed!(@ $list, uint64, U64, $module, $values, $body),
+            Kind::Sint32 => packed!(@ $list, sint32, I32, $module, $values, $body),
+            Kind::Sint64 => packed!(@ $list, sint64, I64, $module, $values, $body),
+            Kind::Fixed32 => packed!(@ $list, fixed32, U32, $module, $values, $body),
+            Kind::Fixed64 => packed!(@ $list, fixed64, U64, $module, $values, $body),
+            Kind::Sfixed32 => packed!(@ $list, sfixed32, I32, $module, $values, $body),
+            Kind::Sfixed64 => packed!(@ $list, sfixed64, I64, $module, $values, $body),
+            Kind::Bool => packed!(@ $list, bool, Bool, $module, $values, $body),
+            Kind::Enum(_) => packed!(@ $list, int32, EnumNumber, $module, $values, $body),
+            _ => panic!("invalid packed field type"),
+        }
+    };
+    (@ $list:expr, $m:ident, $variant:ident, $module:ident, $values:ident, $body:expr) => {{
+        use prost::encoding::$m as $module;
+        let $values = $list
+            .iter()
+            .map(|value| match *value {
+                Value::$variant(value) => value,
+                _ => panic!("invalid value for field"),
+            })
+            .collect::<Vec<_>>();
+        $body
+    }};
+}
+
+/// Encodes a singular value. Message values are encoded as groups if `is_group` is set.
+///
+/// Values are checked against the field type when they are set, and decoded values always match
+/// it, so a mismatched value is a bug.
+fn encode_value<B>(number: u32, kind: &Kind, is_group: bool, value: &Value, buf: &mut B)
+where
+    B: BufMut,
+{
+    match (kind, value) {
+        (Kind::Double, Value::F64(value)) => double::encode(number, value, buf),
+        (Kind::Float, Value::F32(value)) => float::encode(number, value, buf),
+        (Kind::Int32, Value::I32(value)) => int32::encode(number, value, buf),
+        (Kind::Int64, Value::I64(value)) => int64::encode(number, value, buf),
+        (Kind::Uint32, Value::U32(value)) => uint32::encode(number, value, buf),
+        (Kind::Uint64, Value::U64(value)) => uint64::encode(number, value, buf),
+        (Kind::Sint32, Value::I32(value)) => sint32::encode(number, value, buf),
+        (Kind::Sint64, Value::I64(value)) => sint64::encode(number, value, buf),
+        (Kind::Fixed32, Value::U32(value)) => fixed32::encode(number, value, buf),
+        (Kind::Fixed64, Value::U64(value)) => fixed64::encode(number, value, buf),
+        (Kind::Sfixed32, Value::I32(value)) => sfixed32::encode(number, value, buf),
+        (Kind::Sfixed64, Value::I64(value)) => sfixed64::encode(number, value, buf),
+        (Kind::Bool, Value::Bool(value)) => bool::encode(number, value, buf),
+        (Kind::String, Value::String(value)) => string::encode(number, value, buf),
+        (Kind::Bytes, Value::Bytes(value)) => bytes::encode(number, value, buf),
+        (Kind::Enum(_), Value::EnumNumber(value)) => int32::encode(number, value, buf),
+        (Kind::Message(_), Value::Message(value)) if is_group => group::encode(number, value, buf),
+        (Kind::Message(_), Value::Message(value)) => message::encode(number, value, buf),
+        _ => panic!("invalid value for field"),
+    }
+}
+
+fn encoded_len_value(number: u32, kind: &Kind, is_group: bool, value: &Value) -> usize {
+    match (kind, value) {
+        (Kind::Double, Value::F64(value)) => double::encoded_len(number, value),
+        (Kind::Float, Value::F32(value)) => float::encoded_len(number, value),
+        (Kind::Int32, Value::I32(value)) => int32::encoded_len(number, value),
+        (Kind::Int64, Value::I64(value)) => int64::encoded_len(number, value),
+        (Kind::Uint32, Value::U32(value)) => uint32::encoded_len(number, value),
+        (Kind::Uint64, Value::U64(value)) => uint64::encoded_len(number, value),
+        (Kind::Sint32, Value::I32(value)) => sint32::encoded_len(number, value),
+        (Kind::Sint64, Value::I64(value)) => sint64::encoded_len(number, value),
+        (Kind::Fixed32, Value::U32(value)) => fixed32::encoded_len(number, value),
+        (Kind::Fixed64, Value::U64(value)) => fixed64::encoded_len(number, value),
+        (Kind::Sfixed32, Value::I32(value)) => sfixed32::encoded_len(number, value),
+        (Kind::Sfixed64, Value::I64(value)) => sfixed64::encoded_len(number, value),
+        (Kind::Bool, Value::Bool(value)) => bool::encoded_len(number, value),
+        (Kind::String, Value::String(value)) => string::encoded_len(number, value),
+        (Kind::Bytes, Value::Bytes(value)) => bytes::encoded_len(number, value),
+        (Kind::Enum(_), Value::EnumNumber(value)) => int32::encoded_len(number, value),
+        (Kind::Message(_), Value::Message(value)) if is_group => group::encoded_len(number, value),
+        (Kind::Message(_), Value::Message(value)) => message::encoded_len(number, value),
+        _ => panic!("invalid value for field"),
+    }
+}
+
+/// Decodes a singular value of a type other than a message.
+fn decode_scalar<B>(
+    kind: &Kind,
+    wire_type: WireType,
+    buf: &mut B,
+    ctx: DecodeContext,
+) -> Result<Value, DecodeError>
+where
+    B: Buf,
+{
+    macro_rules! decode {
+        ($module:ident, $variant:ident) => {{
+            let mut value = Default::default();
+            $module::merge(wire_type, &mut value, buf, ctx)?;
+            Value::$variant(value)
+        }};
+    }
+
+    Ok(match kind {
+        Kind::Double => decode!(double, F64),
+        Kind::Float => decode!(float, F32),
+        Kind::Int32 => decode!(int32, I32),
+        Kind::Int64 => decode!(int64, I64),
+        Kind::Uint32 => decode!(uint32, U32),
+        Kind::Uint64 => decode!(uint64, U64),
+        Kind::Sint32 => decode!(sint32, I32),
+        Kind::Sint64 => decode!(sint64, I64),
+        Kind::Fixed32 => decode!(fixed32, U32),
+        Kind::Fixed64 => decode!(fixed64, U64),
+        Kind::Sfixed32 => decode!(sfixed32, I32),
+        Kind::Sfixed64 => decode!(sfixed64, I64),
+        Kind::Bool => decode!(bool, Bool),
+        Kind::String => decode!(string, String),
+        Kind::Bytes => {
+            let mut value: Vec<u8> = Vec::new();
+            bytes::merge(wire_type, &mut value, buf, ctx)?;
+            Value::Bytes(value)
+        }
+        Kind::Enum(_) => decode!(int32, EnumNumber),
+        Kind::Message(_) => unreachable!(),
+    })
+}
+
+/// Encodes a map key as field number 1 of a map entry. The pool only accepts map entry types
+/// with a key type that `MapKey` can hold.
+fn encode_map_key<B>(kind: &Kind, key: &MapKey, buf: &mut B)
+where
+    B: BufMut,
+{
+    match (kind, key) {
+        (Kind::Int32, MapKey::I32(key)) => int32::encode(1, key, buf),
+        (Kind::Int64, MapKey::I64(key)) => int64::encode(1, key, buf),
+        (Kind::Uint32, MapKey::U32(key)) => uint32::encode(1, key, buf),
+        (Kind::Uint64, MapKey::U64(key)) => uint64::encode(1, key, buf),
+        (Kind::Sint32, MapKey::I32(key)) => sint32::encode(1, key, buf),
+        (Kind::Sint64, MapKey::I64(key)) => sint64::encode(1, key, buf),
+        (Kind::Fixed32, MapKey::U32(key)) => fixed32::encode(1, key, buf),
+        (Kind::Fixed64, MapKey::U64(key)) => fixed64::encode(1, key, buf),
+        (Kind::Sfixed32, MapKey::I32(key)) => sfixed32::encode(1, key, buf),
+        (Kind::Sfixed64, MapKey::I64(key)) => sfixed64::encode(1, key, buf),
+        (Kind::Bool, MapKey::Bool(key)) => bool::encode(1, key, buf),
+        (Kind::String, MapKey::String(key)) => string::encode(1, key, buf),
+        _ => panic!("invalid map key for field"),
+    }
+}
+
+fn encoded_len_map_key(kind: &Kind, key: &MapKey) -> usize {
+    match (kind, key) {
+        (Kind::Int32, MapKey::I32(key)) => int32::encoded_len(1, key),
+        (Kind::Int64, MapKey::I64(key)) => int64::encoded_len(1, key),
+        (Kind::Uint32, MapKey::U32(key)) => uint32::encoded_len(1, key),
+        (Kind::Uint64, MapKey::U64(key)) => uint64::encoded_len(1, key),
+        (Kind::Sint32, MapKey::I32(key)) => sint32::encoded_len(1, key),
+        (Kind::Sint64, MapKey::I64(key)) => sint64::encoded_len(1, key),
+        (Kind::Fixed32, MapKey::U32(key)) => fixed32::encoded_len(1, key),
+        (Kind::Fixed64, MapKey::U64(key)) => fixed64::encoded_len(1, key),
+        (Kind::Sfixed32, MapKey::I32(key)) => sfixed32::encoded_len(1, key),
+        (Kind::Sfixed64, MapKey::I64(key)) => sfixed64::encoded_len(1, key),
+        (Kind::Bool, MapKey::Bool(key)) => bool::encoded_len(1, key),
+        (Kind::String, MapKey::String(key)) => string::encoded_len(1, key),
+        _ => panic!("invalid map key for field"),
+    }
+}
+
+fn encode_field<B>(field: &FieldDescriptor, value: &Value, buf: &mut B)
+where
+    B: BufMut,
+{
+    let number = field.number();
+    let kind = field.kind();
+    match value {
+        Value::Map(entries) => {
+            let (key_field, value_field) = map_entry_fields(&kind);
+            let (key_kind, value_kind) = (key_field.kind(), value_field.kind());
+            for (key, value) in entries {
+                let len = encoded_len_map_key(&key_kind, key)
+                    + encoded_len_value(2, &value_kind, false, value);
+                encode_key(number, WireType::LengthDelimited, buf);
+                encode_varint(len as u64, buf);
+                encode_map_key(&key_kind, key, buf);
+                encode_value(2, &value_kind, false, value, buf);
+            }
+        }
+        Value::List(values) if field.is_packed() => {
+            packed!(&kind, values, |module, values| module::encode_packed(
+                number, &values, buf
+            ))
+        }
+        Value::List(values) => {
+            for value in values {
+                encode_value(number, &kind, field.is_group(), value, buf);
+            }
+        }
+        value => encode_value(number, &kind, field.is_group(), value, buf),
+    }
+}
+
+fn encoded_len_field(field: &FieldDescriptor, value: &Value) -> usize {
+    let number = field.number();
+    let kind = field.kind();
+    match value {
+        Value::Map(entries) => {
+            let (key_field, value_field) = map_entry_fields(&kind);
+            let (key_kind, value_kind) = (key_field.kind(), value_field.kind());
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let len = encoded_len_map_key(&key_kind, key)
+                        + encoded_len_value(2, &value_kind, false, value);
+                    key_len(number) + encoded_len_varint(len as u64) + len
+                })
+                .sum()
+        }
+        Value::List(values) if field.is_packed() => {
+            packed!(&kind, values, |module, values| module::encoded_len_packed(
+                number, &values
+            ))
+        }
+        Value::List(values) => values
+            .iter()
+            .map(|value| encoded_len_value(number, &kind, field.is_group(), value))
+            .sum(),
+        value => encoded_len_value(number, &kind, field.is_group(), value),
+    }
+}
+
+impl Message for DynamicMessage {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for (field, value) in self.fields() {
+            encode_field(&field, value, buf);
+        }
+        self.unknown_fields.encode_raw(buf);
+    }
+
+    fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        let field = match self.desc.get_field(tag) {
+            Some(field) => field,
+            None => return self.unknown_fields.merge_field(tag, wire_type, buf, ctx),
+        };
+        let kind = field.kind();
+
+        if field.is_map() {
+            let (key_field, value_field) = map_entry_fields(&kind);
+            let mut entry = match kind {
+                Kind::Message(desc) => DynamicMessage::new(desc),
+                _ => unreachable!(),
+            };
+            message::merge(wire_type, &mut entry, buf, ctx)?;
+            let key = entry
+                .fields
+                .remove(&1)
+                .unwrap_or_else(|| Value::default_for_field(&key_field));
+            let key = MapKey::from_value(key).ok_or_else(|| DecodeError::new("invalid map key"))?;
+            let value = entry
+                .fields
+                .remove(&2)
+                .unwrap_or_else(|| Value::default_for_field(&value_field));
+            self.field_mut(&field)
+                .as_map_mut()
+                .expect("invalid value for field")
+                .insert(key, value);
+        } else if field.is_list() {
+            let list = self
+                .field_mut(&field)
+                .as_list_mut()
+                .expect("invalid value for field");
+
+            macro_rules! merge_repeated {
+                ($module:ident, $variant:ident) => {{
+                    let mut values = Vec::new();
+                    $module::merge_repeated(wire_type, &mut values, buf, ctx)?;
+                    list.extend(values.into_iter().map(Value::$variant));
+                }};
+            }
+
+            match kind {
+                Kind::Double => merge_repeated!(double, F64),
+                Kind::Float => merge_repeated!(float, F32),
+                Kind::Int32 => merge_repeated!(int32, I32),
+                Kind::Int64 => merge_repeated!(int64, I64),
+                Kind::Uint32 => merge_repeated!(uint32, U32),
+                Kind::Uint64 => merge_repeated!(uint64, U64),
+                Kind::Sint32 => merge_repeated!(sint32, I32),
+                Kind::Sint64 => merge_repeated!(sint64, I64),
+                Kind::Fixed32 => merge_repeated!(fixed32, U32),
+                Kind::Fixed64 => merge_repeated!(fixed64, U64),
+                Kind::Sfixed32 => merge_repeated!(sfixed32, I32),
+                Kind::Sfixed64 => merge_repeated!(sfixed64, I64),
+                Kind::Bool => merge_repeated!(bool, Bool),
+                Kind::Enum(_) => merge_repeated!(int32, EnumNumber),
+                Kind::String | Kind::Bytes => {
+                    list.push(decode_scalar(&kind, wire_type, buf, ctx)?);
+                }
+                Kind::Message(desc) => {
+                    let mut value = DynamicMessage::new(desc);
+                    if field.is_group() {
+                        group::merge(tag, wire_type, &mut value, buf, ctx)?;
+                    } else {
+                        message::merge(wire_type, &mut value, buf, ctx)?;
+                    }
+                    list.push(Value::Message(value));
+                }
+            }
+        } else if let Kind::Message(..) = kind {
+            let value = self
+                .field_mut(&field)
+                .as_message_mut()
+                .expect("invalid value for field");
+            if field.is_group() {
+                group::merge(tag, wire_type, value, buf, ctx)?;
+            } else {
+                message::merge(wire_type, value, buf, ctx)?;
+            }
+        } else {
+            let value = decode_scalar(&kind, wire_type, buf, ctx)?;
+            self.clear_oneof_siblings(&field);
+            self.fields.insert(tag, value);
+        }
+        Ok(())
+    }
+
+    fn merge_field_tracked<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        let field = match self.desc.get_field(tag) {
+            Some(field) if !field.is_list() && !field.is_map() => field,
+            _ => return self.merge_field(tag, wire_type, buf, ctx),
+        };
+        if let Kind::Message(..) = field.kind() {
+            let presence = presence.nested_mut(tag, self.fields.contains_key(&tag));
+            let value = self
+                .field_mut(&field)
+                .as_message_mut()
+                .expect("invalid value for field");
+            if field.is_group() {
+                group::merge_tracked(tag, wire_type, value, buf, ctx, presence)
+            } else {
+                message::merge_tracked(wire_type, value, buf, ctx, presence)
+            }
+        } else {
+            self.merge_field(tag, wire_type, buf, ctx)
+        }
+    }
+
+    fn check_required_fields(&self, presence: &FieldPresence) -> Result<(), DecodeError> {
+        // Fields are present while they hold a value, whether it was decoded or already set.
+        for field in self.desc.fields() {
+            let number = field.number();
+            match self.fields.get(&number) {
+                None if field.cardinality() == Label::Required => {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::MissingRequiredField,
+                        format!(
+                            "missing required field {}.{}",
+                            self.desc.full_name(),
+                            field.name()
+                        ),
+                    ));
+                }
+                Some(Value::Message(message)) => {
+                    if let Some(presence) = FieldPresence::nested(presence, number) {
+                        message.check_required_fields(presence)?;
+                    }
+                }
+                // Repeated and map values are checked as they are decoded.
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.fields()
+            .map(|(field, value)| encoded_len_field(&field, value))
+            .sum::<usize>()
+            + self.unknown_fields.encoded_len()
+    }
+
+    fn clear(&mut self) {
+        self.fields.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl PartialEq for DynamicMessage {
+    fn eq(&self, other: &DynamicMessage) -> bool {
+        self.desc == other.desc
+            && self.unknown_fields == other.unknown_fields
+            && self.fields().eq(other.fields())
+    }
+}
+
+impl fmt::Debug for DynamicMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct(self.desc.full_name());
+        for (field, value) in self.fields() {
+            s.field(field.name(), value);
+        }
+        s.finish()
+    }
+}
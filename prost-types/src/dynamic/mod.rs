@@ -0,0 +1,48 @@
+//! Messages whose types are only known at runtime.
+//!
+//! A [`DescriptorPool`] indexes the message and enum types defined by a set of file descriptors,
+//! such as the output of `protoc --descriptor_set_out` or a schema registry. Any message type in
+//! the pool can then be decoded, inspected, modified and re-encoded as a [`DynamicMessage`],
+//! without generated Rust code.
+//!
+//! ```rust,ignore
+//! let pool = DescriptorPool::decode(file_descriptor_set_bytes)?;
+//! let desc = pool.get_message_by_name("my.package.MyMessage").unwrap();
+//!
+//! let mut message = DynamicMessage::decode(desc, message_bytes)?;
+//! message.set_field_by_name("name", Value::String("example".to_owned()))?;
+//! let bytes = message.encode_to_vec();
+//! ```
+
+mod message;
+mod pool;
+mod value;
+
+use core::fmt;
+
+use prost::alloc::string::String;
+
+pub use self::message::{DynamicMessage, SetFieldError};
+pub use self::pool::{DescriptorPool, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor};
+pub use self::value::{MapKey, Value};
+
+/// An error returned when a set of file descriptors can't be added to a [`DescriptorPool`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptorError {
+    description: String,
+}
+
+impl DescriptorError {
+    fn new(description: String) -> DescriptorError {
+        DescriptorError { description }
+    }
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid descriptor: {}", self.description)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptorError {}
@@ -0,0 +1,793 @@
+use core::fmt;
+
+use prost::alloc::borrow::ToOwned;
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::sync::Arc;
+use prost::alloc::vec;
+use prost::alloc::vec::Vec;
+use prost::bytes::Buf;
+use prost::encoding::{MAX_TAG, MIN_TAG};
+use prost::Message;
+
+use super::{DescriptorError, Value};
+use crate::field_descriptor_proto::{Label, Type};
+use crate::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet,
+};
+
+/// A set of Protobuf file descriptors, indexed by the fully qualified names of the message and
+/// enum types they define.
+///
+/// Cloning a pool is cheap, since the descriptors are shared.
+#[derive(Clone, Default)]
+pub struct DescriptorPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Clone, Default)]
+struct PoolInner {
+    files: Vec<FileDescriptorProto>,
+    messages: Vec<MessageInner>,
+    enums: Vec<EnumInner>,
+    /// Type definitions by fully qualified name, without a leading period.
+    names: BTreeMap<String, Definition>,
+}
+
+#[derive(Clone, Copy)]
+enum Definition {
+    Message(usize),
+    Enum(usize),
+}
+
+#[derive(Clone)]
+struct MessageInner {
+    full_name: String,
+    proto: DescriptorProto,
+    fields: Vec<FieldInner>,
+    numbers: BTreeMap<u32, usize>,
+    names: BTreeMap<String, usize>,
+}
+
+#[derive(Clone)]
+struct FieldInner {
+    proto: FieldDescriptorProto,
+    kind: KindIndex,
+    /// The index of the containing oneof. Synthetic proto3 `optional` oneofs are not included.
+    oneof: Option<usize>,
+    presence: bool,
+    packed: bool,
+}
+
+#[derive(Clone, Copy)]
+enum KindIndex {
+    Scalar(Type),
+    Message(usize),
+    Enum(usize),
+}
+
+#[derive(Clone)]
+struct EnumInner {
+    full_name: String,
+    proto: EnumDescriptorProto,
+}
+
+impl DescriptorPool {
+    /// Creates an empty pool.
+    pub fn new() -> DescriptorPool {
+        DescriptorPool::default()
+    }
+
+    /// Decodes an encoded `FileDescriptorSet`, such as the output of `protoc --descriptor_set_out`,
+    /// and creates a pool containing its files.
+    pub fn decode<B>(buf: B) -> Result<DescriptorPool, DescriptorError>
+    where
+        B: Buf,
+    {
+        let set = FileDescriptorSet::decode(buf).map_err(|error| {
+            DescriptorError::new(format!("failed to decode file descriptor set: {}", error))
+        })?;
+        DescriptorPool::from_file_descriptor_set(set)
+    }
+
+    /// Creates a pool containing the files of a `FileDescriptorSet`.
+    pub fn from_file_descriptor_set(
+        set: FileDescriptorSet,
+    ) -> Result<DescriptorPool, DescriptorError> {
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_set(set)?;
+        Ok(pool)
+    }
+
+    /// Adds the files of a `FileDescriptorSet` to the pool.
+    ///
+    /// The files may be given in any order, but every file imported by one of them must either be
+    /// part of the set or already be in the pool. Files which are already in the pool are skipped.
+    /// If an error is returned, the pool is left unchanged.
+    pub fn add_file_descriptor_set(
+        &mut self,
+        set: FileDescriptorSet,
+    ) -> Result<(), DescriptorError> {
+        let mut inner = (*self.inner).clone();
+
+        let mut pending = set.file;
+        while !pending.is_empty() {
+            let pending_len = pending.len();
+            let mut blocked = Vec::new();
+            for file in pending {
+                if let Some(existing) = inner.files.iter().find(|f| f.name() == file.name()) {
+                    if *existing != file {
+                        return Err(DescriptorError::new(format!(
+                            "file {} is already in the pool with different contents",
+                            file.name()
+                        )));
+                    }
+                } else if file
+                    .dependency
+                    .iter()
+                    .all(|dependency| inner.files.iter().any(|f| f.name() == dependency))
+                {
+                    inner.add_file(file)?;
+                } else {
+                    blocked.push(file);
+                }
+            }
+
+            if blocked.len() == pending_len {
+                let file = &blocked[0];
+                let missing = file
+                    .dependency
+                    .iter()
+                    .find(|&dependency| {
+                        !inner.files.iter().any(|f| f.name() == dependency)
+                            && !blocked.iter().any(|f| f.name() == dependency)
+                    })
+                    .or_else(|| file.dependency.first())
+                    .map_or("", String::as_str);
+                return Err(DescriptorError::new(format!(
+                    "file {} imports {}, which is not in the pool",
+                    file.name(),
+                    missing
+                )));
+            }
+            pending = blocked;
+        }
+
+        self.inner = Arc::new(inner);
+        Ok(())
+    }
+
+    /// Adds a single file to the pool. See [`DescriptorPool::add_file_descriptor_set`].
+    pub fn add_file_descriptor_proto(
+        &mut self,
+        file: FileDescriptorProto,
+    ) -> Result<(), DescriptorError> {
+        self.add_file_descriptor_set(FileDescriptorSet { file: vec![file] })
+    }
+
+    /// Returns the files in the pool, in the order they were added.
+    pub fn files(&self) -> &[FileDescriptorProto] {
+        &self.inner.files
+    }
+
+    /// Returns an iterator over all message types in the pool, including nested and map entry
+    /// types.
+    pub fn messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor> + '_ {
+        (0..self.inner.messages.len()).map(move |index| MessageDescriptor {
+            pool: self.clone(),
+            index,
+        })
+    }
+
+    /// Returns the message type with the given fully qualified name, e.g. `my.package.MyMessage`.
+    /// A leading period is allowed.
+    pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor> {
+        match self.get_definition(name)? {
+            Definition::Message(index) => Some(MessageDescriptor {
+                pool: self.clone(),
+                index,
+            }),
+            Definition::Enum(..) => None,
+        }
+    }
+
+    /// Returns the enum type with the given fully qualified name, e.g. `my.package.MyEnum`. A
+    /// leading period is allowed.
+    pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor> {
+        match self.get_definition(name)? {
+            Definition::Enum(index) => Some(EnumDescriptor {
+                pool: self.clone(),
+                index,
+            }),
+            Definition::Message(..) => None,
+        }
+    }
+
+    fn get_definition(&self, name: &str) -> Option<Definition> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        self.inner.names.get(name).copied()
+    }
+}
+
+impl fmt::Debug for DescriptorPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DescriptorPool")
+            .field(
+                "files",
+                &self
+                    .inner
+                    .files
+                    .iter()
+                    .map(|f| f.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl PartialEq for DescriptorPool {
+    fn eq(&self, other: &DescriptorPool) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for DescriptorPool {}
+
+impl PoolInner {
+    fn add_file(&mut self, file: FileDescriptorProto) -> Result<(), DescriptorError> {
+        let proto3 = file.syntax() == "proto3";
+        let first_message = self.messages.len();
+        for message in &file.message_type {
+            self.add_message(file.package(), message)?;
+        }
+        for enum_type in &file.enum_type {
+            self.add_enum(file.package(), enum_type)?;
+        }
+        for index in first_message..self.messages.len() {
+            self.resolve_fields(index, proto3)?;
+        }
+        for index in first_message..self.messages.len() {
+            self.check_map_entry(index)?;
+        }
+        self.files.push(file);
+        Ok(())
+    }
+
+    fn add_name(&mut self, full_name: &str, definition: Definition) -> Result<(), DescriptorError> {
+        if self
+            .names
+            .insert(full_name.to_owned(), definition)
+            .is_some()
+        {
+            return Err(DescriptorError::new(format!(
+                "type {} is defined more than once",
+                full_name
+            )));
+        }
+        Ok(())
+    }
+
+    fn add_message(
+        &mut self,
+        scope: &str,
+        message: &DescriptorProto,
+    ) -> Result<(), DescriptorError> {
+        let full_name = join_name(scope, message.name());
+        self.add_name(&full_name, Definition::Message(self.messages.len()))?;
+        self.messages.push(MessageInner {
+            full_name: full_name.clone(),
+            proto: message.clone(),
+            fields: Vec::new(),
+            numbers: BTreeMap::new(),
+            names: BTreeMap::new(),
+        });
+        for nested in &message.nested_type {
+            self.add_message(&full_name, nested)?;
+        }
+        for enum_type in &message.enum_type {
+            self.add_enum(&full_name, enum_type)?;
+        }
+        Ok(())
+    }
+
+    fn add_enum(
+        &mut self,
+        scope: &str,
+        enum_type: &EnumDescriptorProto,
+    ) -> Result<(), DescriptorError> {
+        let full_name = join_name(scope, enum_type.name());
+        self.add_name(&full_name, Definition::Enum(self.enums.len()))?;
+        self.enums.push(EnumInner {
+            full_name,
+            proto: enum_type.clone(),
+        });
+        Ok(())
+    }
+
+    /// Resolves the types of a message's fields, which may refer to any type defined in the pool.
+    fn resolve_fields(&mut self, index: usize, proto3: bool) -> Result<(), DescriptorError> {
+        let message = &self.messages[index];
+        let mut fields = Vec::with_capacity(message.proto.field.len());
+        let mut numbers = BTreeMap::new();
+        let mut names = BTreeMap::new();
+
+        for (field_index, field) in message.proto.field.iter().enumerate() {
+            let ty = field.r#type.and_then(Type::from_i32);
+            let kind = match ty {
+                Some(ty) if !matches!(ty, Type::Message | Type::Group | Type::Enum) => {
+                    KindIndex::Scalar(ty)
+                }
+                _ => match self.resolve_type_name(&message.full_name, field.type_name()) {
+                    Some(Definition::Message(_)) if ty == Some(Type::Enum) => {
+                        return Err(DescriptorError::new(format!(
+                            "field {}.{} has enum type {}, which is a message type",
+                            message.full_name,
+                            field.name(),
+                            field.type_name()
+                        )))
+                    }
+                    Some(Definition::Enum(_))
+                        if matches!(ty, Some(Type::Message | Type::Group)) =>
+                    {
+                        return Err(DescriptorError::new(format!(
+                            "field {}.{} has message type {}, which is an enum type",
+                            message.full_name,
+                            field.name(),
+                            field.type_name()
+                        )))
+                    }
+                    Some(Definition::Message(index)) => KindIndex::Message(index),
+                    Some(Definition::Enum(index)) => KindIndex::Enum(index),
+                    None => {
+                        return Err(DescriptorError::new(format!(
+                            "field {}.{} has unknown type {}",
+                            message.full_name,
+                            field.name(),
+                            field.type_name()
+                        )))
+                    }
+                },
+            };
+
+            let repeated = field.label() == Label::Repeated;
+            let oneof = if field.proto3_optional() {
+                None
+            } else {
+                field.oneof_index.map(|index| index as usize)
+            };
+            let presence = !repeated
+                && (!proto3
+                    || field.proto3_optional()
+                    || oneof.is_some()
+                    || matches!(kind, KindIndex::Message(..)));
+            let packable = match kind {
+                KindIndex::Scalar(ty) => !matches!(ty, Type::String | Type::Bytes),
+                KindIndex::Enum(..) => true,
+                KindIndex::Message(..) => false,
+            };
+            let packed = repeated
+                && packable
+                && field
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.packed)
+                    .unwrap_or(proto3);
+
+            let number = field.number() as u32;
+            if !(MIN_TAG..=MAX_TAG).contains(&number) {
+                return Err(DescriptorError::new(format!(
+                    "field {}.{} has invalid number {}",
+                    message.full_name,
+                    field.name(),
+                    field.number()
+                )));
+            }
+            if numbers.insert(number, field_index).is_some() {
+                return Err(DescriptorError::new(format!(
+                    "message {} has more than one field with number {}",
+                    message.full_name, number
+                )));
+            }
+            names.insert(field.name().to_owned(), field_index);
+            fields.push(FieldInner {
+                proto: field.clone(),
+                kind,
+                oneof,
+                presence,
+                packed,
+            });
+        }
+
+        let message = &mut self.messages[index];
+        message.fields = fields;
+        message.numbers = numbers;
+        message.names = names;
+        Ok(())
+    }
+
+    /// Checks that a map entry type has a key field 1 of a valid map key type and a value field 2,
+    /// which the map field encoding relies on.
+    fn check_map_entry(&self, index: usize) -> Result<(), DescriptorError> {
+        let message = &self.messages[index];
+        let map_entry = message
+            .proto
+            .options
+            .as_ref()
+            .and_then(|options| options.map_entry)
+            .unwrap_or(false);
+        if !map_entry {
+            return Ok(());
+        }
+
+        let field = |number| message.numbers.get(&number).map(|&i| &message.fields[i]);
+        let (key, value) = match (field(1), field(2)) {
+            (Some(key), Some(value))
+                if message.fields.len() == 2
+                    && message
+                        .fields
+                        .iter()
+                        .all(|field| field.proto.label() != Label::Repeated) =>
+            {
+                (key, value)
+            }
+            _ => {
+                return Err(DescriptorError::new(format!(
+                    "map entry {} must have a singular key field 1 and value field 2",
+                    message.full_name
+                )))
+            }
+        };
+        let valid_key = match key.kind {
+            KindIndex::Scalar(ty) => !matches!(ty, Type::Double | Type::Float | Type::Bytes),
+            KindIndex::Message(..) | KindIndex::Enum(..) => false,
+        };
+        if !valid_key {
+            return Err(DescriptorError::new(format!(
+                "map entry {} has a key type that can't be used as a map key",
+                message.full_name
+            )));
+        }
+        if value.proto.r#type == Some(Type::Group as i32) {
+            return Err(DescriptorError::new(format!(
+                "map entry {} has a group value field",
+                message.full_name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolves a type name referenced from within `scope` using the Protobuf scoping rules:
+    /// fully qualified names start with a period, and other names are searched for from the
+    /// innermost scope outwards.
+    fn resolve_type_name(&self, scope: &str, type_name: &str) -> Option<Definition> {
+        if let Some(name) = type_name.strip_prefix('.') {
+            return self.names.get(name).copied();
+        }
+        let mut scope = scope;
+        loop {
+            let definition = if scope.is_empty() {
+                self.names.get(type_name)
+            } else {
+                self.names.get(&format!("{}.{}", scope, type_name))
+            };
+            if let Some(definition) = definition {
+                return Some(*definition);
+            }
+            if scope.is_empty() {
+                return None;
+            }
+            scope = scope.rfind('.').map_or("", |index| &scope[..index]);
+        }
+    }
+}
+
+fn join_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// A message type in a [`DescriptorPool`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct MessageDescriptor {
+    pool: DescriptorPool,
+    index: usize,
+}
+
+impl MessageDescriptor {
+    fn inner(&self) -> &MessageInner {
+        &self.pool.inner.messages[self.index]
+    }
+
+    /// Returns the pool containing the message type.
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Returns the fully qualified name of the message type, e.g. `my.package.MyMessage`.
+    pub fn full_name(&self) -> &str {
+        &self.inner().full_name
+    }
+
+    /// Returns the unqualified name of the message type, e.g. `MyMessage`.
+    pub fn name(&self) -> &str {
+        self.inner().proto.name()
+    }
+
+    /// Returns the raw descriptor of the message type.
+    pub fn descriptor_proto(&self) -> &DescriptorProto {
+        &self.inner().proto
+    }
+
+    /// Returns `true` if the message type is the synthetic entry type of a map field.
+    pub fn is_map_entry(&self) -> bool {
+        self.inner()
+            .proto
+            .options
+            .as_ref()
+            .and_then(|options| options.map_entry)
+            .unwrap_or(false)
+    }
+
+    /// Returns an iterator over the fields of the message type, in declaration order.
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor> + '_ {
+        (0..self.inner().fields.len()).map(move |index| FieldDescriptor {
+            message: self.clone(),
+            index,
+        })
+    }
+
+    /// Returns the field with the given number.
+    pub fn get_field(&self, number: u32) -> Option<FieldDescriptor> {
+        let index = *self.inner().numbers.get(&number)?;
+        Some(FieldDescriptor {
+            message: self.clone(),
+            index,
+        })
+    }
+
+    /// Returns the field with the given name, as declared in the `.proto` file.
+    pub fn get_field_by_name(&self, name: &str) -> Option<FieldDescriptor> {
+        let index = *self.inner().names.get(name)?;
+        Some(FieldDescriptor {
+            message: self.clone(),
+            index,
+        })
+    }
+}
+
+impl fmt::Debug for MessageDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageDescriptor")
+            .field(&self.full_name())
+            .finish()
+    }
+}
+
+/// A field of a message type in a [`DescriptorPool`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    message: MessageDescriptor,
+    index: usize,
+}
+
+impl FieldDescriptor {
+    fn inner(&self) -> &FieldInner {
+        &self.message.inner().fields[self.index]
+    }
+
+    /// Returns the message type containing the field.
+    pub fn containing_message(&self) -> &MessageDescriptor {
+        &self.message
+    }
+
+    /// Returns the name of the field, as declared in the `.proto` file.
+    pub fn name(&self) -> &str {
+        self.inner().proto.name()
+    }
+
+    /// Returns the field number.
+    pub fn number(&self) -> u32 {
+        self.inner().proto.number() as u32
+    }
+
+    /// Returns the raw descriptor of the field.
+    pub fn field_descriptor_proto(&self) -> &FieldDescriptorProto {
+        &self.inner().proto
+    }
+
+    /// Returns the type of the field. For map fields, this is the map entry message type.
+    pub fn kind(&self) -> Kind {
+        let pool = &self.message.pool;
+        match self.inner().kind {
+            KindIndex::Scalar(ty) => match ty {
+                Type::Double => Kind::Double,
+                Type::Float => Kind::Float,
+                Type::Int32 => Kind::Int32,
+                Type::Int64 => Kind::Int64,
+                Type::Uint32 => Kind::Uint32,
+                Type::Uint64 => Kind::Uint64,
+                Type::Sint32 => Kind::Sint32,
+                Type::Sint64 => Kind::Sint64,
+                Type::Fixed32 => Kind::Fixed32,
+                Type::Fixed64 => Kind::Fixed64,
+                Type::Sfixed32 => Kind::Sfixed32,
+                Type::Sfixed64 => Kind::Sfixed64,
+                Type::Bool => Kind::Bool,
+                Type::String => Kind::String,
+                Type::Bytes => Kind::Bytes,
+                Type::Message | Type::Group | Type::Enum => unreachable!(),
+            },
+            KindIndex::Message(index) => Kind::Message(MessageDescriptor {
+                pool: pool.clone(),
+                index,
+            }),
+            KindIndex::Enum(index) => Kind::Enum(EnumDescriptor {
+                pool: pool.clone(),
+                index,
+            }),
+        }
+    }
+
+    /// Returns the label of the field.
+    pub fn cardinality(&self) -> Label {
+        self.inner().proto.label()
+    }
+
+    /// Returns `true` if the field is a repeated field, other than a map field.
+    pub fn is_list(&self) -> bool {
+        self.cardinality() == Label::Repeated && !self.is_map()
+    }
+
+    /// Returns `true` if the field is a map field.
+    pub fn is_map(&self) -> bool {
+        match self.inner().kind {
+            KindIndex::Message(index) => {
+                self.cardinality() == Label::Repeated
+                    && MessageDescriptor {
+                        pool: self.message.pool.clone(),
+                        index,
+                    }
+                    .is_map_entry()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the field is encoded as a group.
+    pub fn is_group(&self) -> bool {
+        self.inner().proto.r#type() == Type::Group
+    }
+
+    /// Returns `true` if the repeated field uses the packed encoding.
+    pub fn is_packed(&self) -> bool {
+        self.inner().packed
+    }
+
+    /// Returns `true` if the field distinguishes between being unset and being set to its default
+    /// value.
+    pub fn has_presence(&self) -> bool {
+        self.inner().presence
+    }
+
+    /// Returns the name of the oneof containing the field, if any.
+    pub fn containing_oneof(&self) -> Option<&str> {
+        let index = self.inner().oneof?;
+        self.message
+            .inner()
+            .proto
+            .oneof_decl
+            .get(index)
+            .map(|oneof| oneof.name())
+    }
+
+    /// Returns the default value of the field.
+    pub fn default_value(&self) -> Value {
+        Value::default_for_field(self)
+    }
+
+    pub(super) fn oneof_index(&self) -> Option<usize> {
+        self.inner().oneof
+    }
+}
+
+impl fmt::Debug for FieldDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldDescriptor")
+            .field("message", &self.message.full_name())
+            .field("name", &self.name())
+            .field("number", &self.number())
+            .finish()
+    }
+}
+
+/// An enum type in a [`DescriptorPool`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct EnumDescriptor {
+    pool: DescriptorPool,
+    index: usize,
+}
+
+impl EnumDescriptor {
+    fn inner(&self) -> &EnumInner {
+        &self.pool.inner.enums[self.index]
+    }
+
+    /// Returns the fully qualified name of the enum type, e.g. `my.package.MyEnum`.
+    pub fn full_name(&self) -> &str {
+        &self.inner().full_name
+    }
+
+    /// Returns the unqualified name of the enum type, e.g. `MyEnum`.
+    pub fn name(&self) -> &str {
+        self.inner().proto.name()
+    }
+
+    /// Returns the raw descriptor of the enum type.
+    pub fn enum_descriptor_proto(&self) -> &EnumDescriptorProto {
+        &self.inner().proto
+    }
+
+    /// Returns the values of the enum type, in declaration order.
+    pub fn values(&self) -> &[EnumValueDescriptorProto] {
+        &self.inner().proto.value
+    }
+
+    /// Returns the number of the value with the given name.
+    pub fn get_value_by_name(&self, name: &str) -> Option<i32> {
+        self.values()
+            .iter()
+            .find(|value| value.name() == name)
+            .map(EnumValueDescriptorProto::number)
+    }
+
+    /// Returns the name of the value with the given number.
+    pub fn get_value_name(&self, number: i32) -> Option<&str> {
+        self.values()
+            .iter()
+            .find(|value| value.number() == number)
+            .map(EnumValueDescriptorProto::name)
+    }
+
+    /// Returns the default value of the enum type, which is its first value.
+    pub fn default_value(&self) -> i32 {
+        self.values()
+            .first()
+            .map_or(0, EnumValueDescriptorProto::number)
+    }
+}
+
+impl fmt::Debug for EnumDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EnumDescriptor")
+            .field(&self.full_name())
+            .finish()
+    }
+}
+
+/// The type of a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    /// A message or group type.
+    Message(MessageDescriptor),
+    Enum(EnumDescriptor),
+}
@@ -0,0 +1,344 @@
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+
+use super::{DynamicMessage, FieldDescriptor, Kind};
+
+/// The value of a field of a [`DynamicMessage`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// The number of an enum value. Numbers which are not defined by the enum type are allowed.
+    EnumNumber(i32),
+    Message(DynamicMessage),
+    /// The value of a repeated field.
+    List(Vec<Value>),
+    /// The value of a map field.
+    Map(BTreeMap<MapKey, Value>),
+}
+
+/// The key of a map field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MapKey {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    String(String),
+}
+
+impl Value {
+    /// Returns the default value of a field.
+    ///
+    /// This is an empty list or map for repeated fields, and otherwise the explicit default
+    /// declared in a proto2 file, falling back to the default value of the field's type.
+    pub fn default_for_field(field: &FieldDescriptor) -> Value {
+        if field.is_map() {
+            return Value::Map(BTreeMap::new());
+        } else if field.is_list() {
+            return Value::List(Vec::new());
+        }
+
+        let kind = field.kind();
+        field
+            .field_descriptor_proto()
+            .default_value
+            .as_deref()
+            .and_then(|default| parse_default(&kind, default))
+            .unwrap_or_else(|| Value::default_for_kind(&kind))
+    }
+
+    /// Returns the default value of a type.
+    pub fn default_for_kind(kind: &Kind) -> Value {
+        match kind {
+            Kind::Double => Value::F64(0.0),
+            Kind::Float => Value::F32(0.0),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(0),
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(0),
+            Kind::Uint32 | Kind::Fixed32 => Value::U32(0),
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(0),
+            Kind::Bool => Value::Bool(false),
+            Kind::String => Value::String(String::new()),
+            Kind::Bytes => Value::Bytes(Vec::new()),
+            Kind::Enum(desc) => Value::EnumNumber(desc.default_value()),
+            Kind::Message(desc) => Value::Message(DynamicMessage::new(desc.clone())),
+        }
+    }
+
+    /// Returns `true` if the value can be stored in the field.
+    pub fn is_valid_for_field(&self, field: &FieldDescriptor) -> bool {
+        match self {
+            Value::List(values) if field.is_list() => {
+                let kind = field.kind();
+                values.iter().all(|value| value.is_valid_for_kind(&kind))
+            }
+            Value::Map(entries) if field.is_map() => {
+                let entry = match field.kind() {
+                    Kind::Message(entry) => entry,
+                    _ => return false,
+                };
+                let (key, value) = match (entry.get_field(1), entry.get_field(2)) {
+                    (Some(key), Some(value)) => (key.kind(), value.kind()),
+                    _ => return false,
+                };
+                entries.iter().all(|(k, v)| {
+                    Value::from(k.clone()).is_valid_for_kind(&key) && v.is_valid_for_kind(&value)
+                })
+            }
+            _ if field.is_list() || field.is_map() => false,
+            value => value.is_valid_for_kind(&field.kind()),
+        }
+    }
+
+    /// Returns `true` if the value is a singular value of the given type.
+    pub fn is_valid_for_kind(&self, kind: &Kind) -> bool {
+        matches!(
+            (self, kind),
+            (Value::F64(_), Kind::Double)
+                | (Value::F32(_), Kind::Float)
+                | (Value::I32(_), Kind::Int32 | Kind::Sint32 | Kind::Sfixed32)
+                | (Value::I64(_), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64)
+                | (Value::U32(_), Kind::Uint32 | Kind::Fixed32)
+                | (Value::U64(_), Kind::Uint64 | Kind::Fixed64)
+                | (Value::Bool(_), Kind::Bool)
+                | (Value::String(_), Kind::String)
+                | (Value::Bytes(_), Kind::Bytes)
+                | (Value::EnumNumber(_), Kind::Enum(_))
+        ) || match (self, kind) {
+            (Value::Message(message), Kind::Message(desc)) => message.descriptor() == desc,
+            _ => false,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Value::I32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::I64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            Value::U32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::U64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Value::F32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_enum_number(&self) -> Option<i32> {
+        match *self {
+            Value::EnumNumber(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_message(&self) -> Option<&DynamicMessage> {
+        match self {
+            Value::Message(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_message_mut(&mut self) -> Option<&mut DynamicMessage> {
+        match self {
+            Value::Message(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::List(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<MapKey, Value>> {
+        match self {
+            Value::Map(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_map_mut(&mut self) -> Option<&mut BTreeMap<MapKey, Value>> {
+        match self {
+            Value::Map(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl MapKey {
+    /// Converts a value to a map key, returning `None` if the type can't be used as a map key.
+    pub fn from_value(value: Value) -> Option<MapKey> {
+        match value {
+            Value::Bool(value) => Some(MapKey::Bool(value)),
+            Value::I32(value) => Some(MapKey::I32(value)),
+            Value::I64(value) => Some(MapKey::I64(value)),
+            Value::U32(value) => Some(MapKey::U32(value)),
+            Value::U64(value) => Some(MapKey::U64(value)),
+            Value::String(value) => Some(MapKey::String(value)),
+            _ => None,
+        }
+    }
+}
+
+impl From<MapKey> for Value {
+    fn from(key: MapKey) -> Value {
+        match key {
+            MapKey::Bool(value) => Value::Bool(value),
+            MapKey::I32(value) => Value::I32(value),
+            MapKey::I64(value) => Value::I64(value),
+            MapKey::U32(value) => Value::U32(value),
+            MapKey::U64(value) => Value::U64(value),
+            MapKey::String(value) => Value::String(value),
+        }
+    }
+}
+
+/// Parses the textual default value of a proto2 field, as stored in `FieldDescriptorProto`.
+fn parse_default(kind: &Kind, default: &str) -> Option<Value> {
+    let value = match kind {
+        Kind::Double => Value::F64(parse_float(default)?),
+        Kind::Float => Value::F32(parse_float(default)? as f32),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(default.parse().ok()?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(default.parse().ok()?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(default.parse().ok()?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(default.parse().ok()?),
+        Kind::Bool => Value::Bool(default.parse().ok()?),
+        Kind::String => Value::String(default.into()),
+        Kind::Bytes => Value::Bytes(unescape_c_escape_string(default)?),
+        Kind::Enum(desc) => Value::EnumNumber(desc.get_value_by_name(default)?),
+        Kind::Message(_) => return None,
+    };
+    Some(value)
+}
+
+fn parse_float(default: &str) -> Option<f64> {
+    match default {
+        "inf" => Some(f64::INFINITY),
+        "-inf" => Some(f64::NEG_INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => default.parse().ok(),
+    }
+}
+
+/// Unescapes a C-style escaped string, as `protoc` uses for `bytes` default values.
+fn unescape_c_escape_string(s: &str) -> Option<Vec<u8>> {
+    let src = s.as_bytes();
+    let mut dst = Vec::with_capacity(src.len());
+    let mut p = 0;
+
+    while p < src.len() {
+        if src[p] != b'\\' {
+            dst.push(src[p]);
+            p += 1;
+            continue;
+        }
+
+        p += 1;
+        let c = *src.get(p)?;
+        p += 1;
+        match c {
+            b'a' => dst.push(0x07),
+            b'b' => dst.push(0x08),
+            b'f' => dst.push(0x0C),
+            b'n' => dst.push(0x0A),
+            b'r' => dst.push(0x0D),
+            b't' => dst.push(0x09),
+            b'v' => dst.push(0x0B),
+            b'\\' | b'\'' | b'"' | b'?' => dst.push(c),
+            b'0'..=b'7' => {
+                let mut octal = u32::from(c - b'0');
+                for _ in 0..2 {
+                    match src.get(p) {
+                        Some(&d @ b'0'..=b'7') => {
+                            octal = octal * 8 + u32::from(d - b'0');
+                            p += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                dst.push(octal as u8);
+            }
+            b'x' | b'X' => {
+                let mut hex = 0u32;
+                let start = p;
+                while p < src.len() && p < start + 2 && src[p].is_ascii_hexdigit() {
+                    hex = hex * 16 + char::from(src[p]).to_digit(16)?;
+                    p += 1;
+                }
+                if p == start {
+                    return None;
+                }
+                dst.push(hex as u8);
+            }
+            _ => return None,
+        }
+    }
+    Some(dst)
+}
@@ -0,0 +1,368 @@
+//! Helpers for `google.protobuf.FieldMask`.
+//!
+//! The path operations follow the semantics of the `FieldMaskUtil` helpers of the other Protobuf
+//! implementations. A path covers itself and the paths of the fields nested below it, so `a`
+//! covers `a.b`. The message operations resolve paths using the reflection descriptors of
+//! messages which implement `ReflectMessage`.
+
+use core::cmp::Ordering;
+
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+
+use prost::projection::{PathError, Projection, Selection};
+use prost::reflect::{MessageDescriptor, Value, ValueRef};
+use prost::ReflectMessage;
+
+use crate::FieldMask;
+
+impl FieldMask {
+    /// Creates a field mask with the given paths.
+    pub fn new<I, S>(paths: I) -> FieldMask
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        FieldMask {
+            paths: paths.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Converts the mask to its canonical form, in which the paths are sorted, and paths which
+    /// are empty or covered by other paths are removed.
+    pub fn normalize(&mut self) {
+        self.paths.retain(|path| !path.is_empty());
+        self.paths.sort_unstable();
+        self.paths.dedup();
+        // After sorting, the paths covered by a path immediately follow it.
+        let mut normalized: Vec<String> = Vec::with_capacity(self.paths.len());
+        for path in self.paths.drain(..) {
+            if !matches!(normalized.last(), Some(last) if covers(last, &path)) {
+                normalized.push(path);
+            }
+        }
+        self.paths = normalized;
+    }
+
+    /// Returns the canonical form of a mask which covers the paths covered by either mask.
+    pub fn union(&self, other: &FieldMask) -> FieldMask {
+        let mut union = FieldMask::new(self.paths.iter().chain(&other.paths).cloned());
+        union.normalize();
+        union
+    }
+
+    /// Returns the canonical form of a mask which covers the paths covered by both masks.
+    pub fn intersection(&self, other: &FieldMask) -> FieldMask {
+        let mut intersection = FieldMask::default();
+        for a in &self.paths {
+            for b in &other.paths {
+                if covers(a, b) {
+                    intersection.paths.push(b.clone());
+                } else if covers(b, a) {
+                    intersection.paths.push(a.clone());
+                }
+            }
+        }
+        intersection.normalize();
+        intersection
+    }
+
+    /// Returns `true` if `path` is covered by one of the paths of the mask.
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.iter().any(|prefix| covers(prefix, path))
+    }
+
+    /// Checks that the paths of the mask name fields of the message type.
+    ///
+    /// Each path must name a field of the message, optionally followed by the names of nested
+    /// fields of singular message fields. Repeated and map fields may only be the last field of a
+    /// path.
+    pub fn validate(&self, desc: &MessageDescriptor) -> Result<(), PathError> {
+        self.resolve(desc).map(|_| ())
+    }
+
+    /// Returns a projection which selects the fields of the mask, for decoding only those fields
+    /// with [`Message::decode_projected`][1].
+    ///
+    /// The paths of the mask are resolved using the reflection descriptor of the message type,
+    /// and an error is returned for the first path which doesn't match its fields.
+    ///
+    /// [1]: prost::Message::decode_projected
+    pub fn projection(&self, desc: &MessageDescriptor) -> Result<Projection, PathError> {
+        Projection::from_paths(desc, &self.paths)
+    }
+
+    /// Replaces the fields of `destination` covered by the mask with the fields of `source`.
+    ///
+    /// Covered fields which aren't set in `source` are cleared in `destination`, which matches
+    /// the semantics of update requests with a field mask. Nested messages of `destination` are
+    /// only created if the corresponding message in `source` is set. If a path is invalid,
+    /// `destination` is not modified.
+    pub fn copy<M>(&self, source: &M, destination: &mut M) -> Result<(), PathError>
+    where
+        M: ReflectMessage,
+    {
+        let desc = M::type_descriptor();
+        copy(&self.resolve(desc)?, desc, source, destination);
+        Ok(())
+    }
+
+    /// Merges the fields of `source` covered by the mask into `destination`.
+    ///
+    /// Covered fields are merged in the same way as when merging encoded messages: fields which
+    /// are set in `source` replace singular fields, are merged into message fields, and are
+    /// appended to repeated fields. If a path is invalid, `destination` is not modified.
+    pub fn merge<M>(&self, source: &M, destination: &mut M) -> Result<(), PathError>
+    where
+        M: ReflectMessage,
+    {
+        let desc = M::type_descriptor();
+        merge(&self.resolve(desc)?, desc, source, destination);
+        Ok(())
+    }
+
+    /// Clears the fields of `message` which aren't covered by the mask.
+    ///
+    /// If a path is invalid, `message` is not modified.
+    pub fn trim<M>(&self, message: &mut M) -> Result<(), PathError>
+    where
+        M: ReflectMessage,
+    {
+        let desc = M::type_descriptor();
+        trim(&self.resolve(desc)?, desc, message);
+        Ok(())
+    }
+
+    /// Resolves the paths of the mask to a projection which selects the covered fields.
+    fn resolve(&self, desc: &MessageDescriptor) -> Result<Projection, PathError> {
+        let mut projection = Projection::new();
+        for path in &self.paths {
+            projection.select_singular_path(desc, path)?;
+        }
+        Ok(projection)
+    }
+}
+
+/// Returns `true` if `prefix` covers `path`, i.e. the paths are equal or `path` names a field
+/// nested below `prefix`.
+fn covers(prefix: &str, path: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.'),
+        None => false,
+    }
+}
+
+/// Replaces the fields of `destination` selected by `projection` with the fields of `source`.
+fn copy(
+    projection: &Projection,
+    desc: &MessageDescriptor,
+    source: &dyn ReflectMessage,
+    destination: &mut dyn ReflectMessage,
+) {
+    for (number, selection) in projection.fields() {
+        let nested = match selection {
+            Selection::Field => {
+                match source.get_field(number) {
+                    Some(value) if source.has_field(number) => {
+                        set_field(destination, number, value)
+                    }
+                    _ => {
+                        destination.clear_field(number);
+                    }
+                }
+                continue;
+            }
+            Selection::Nested(nested) => nested,
+        };
+
+        let message_type = match nested_type(desc, number) {
+            Some(message_type) => message_type,
+            None => continue,
+        };
+        let source_nested = message_field(source, number);
+        if source_nested.is_none() && !destination.has_field(number) {
+            continue;
+        }
+        let destination_nested = match destination.get_message_mut(number) {
+            Some(message) => message,
+            None => continue,
+        };
+        let empty;
+        let source_nested = match source_nested {
+            Some(message) => message,
+            None => {
+                empty = message_type.new_message();
+                &*empty
+            }
+        };
+        copy(nested, message_type, source_nested, destination_nested);
+    }
+}
+
+/// Merges the fields of `source` selected by `projection` into `destination`.
+fn merge(
+    projection: &Projection,
+    desc: &MessageDescriptor,
+    source: &dyn ReflectMessage,
+    destination: &mut dyn ReflectMessage,
+) {
+    for (number, selection) in projection.fields() {
+        if !source.has_field(number) {
+            continue;
+        }
+
+        let nested = match selection {
+            Selection::Field => {
+                merge_field(number, source, destination);
+                continue;
+            }
+            Selection::Nested(nested) => nested,
+        };
+
+        let (message_type, source_nested) =
+            match (nested_type(desc, number), message_field(source, number)) {
+                (Some(message_type), Some(source_nested)) => (message_type, source_nested),
+                _ => continue,
+            };
+        if let Some(destination_nested) = destination.get_message_mut(number) {
+            merge(nested, message_type, source_nested, destination_nested);
+        }
+    }
+}
+
+/// Merges the field `number` of `source` into `destination` in the same way as when merging
+/// encoded messages: singular fields are replaced, message fields are merged, repeated fields are
+/// appended to and map entries are replaced by key.
+fn merge_field(number: u32, source: &dyn ReflectMessage, destination: &mut dyn ReflectMessage) {
+    let merged = match source.get_field_ref(number) {
+        Some(ValueRef::Message(source_nested)) => {
+            if let Some(destination_nested) = destination.get_message_mut(number) {
+                merge_message(source_nested, destination_nested);
+            }
+            return;
+        }
+        Some(ValueRef::List(values)) => {
+            let mut merged = match destination.get_field_ref(number) {
+                Some(ValueRef::List(existing)) => existing.iter().map(ValueRef::to_value).collect(),
+                _ => Vec::new(),
+            };
+            merged.extend(values.iter().map(ValueRef::to_value));
+            Value::List(merged)
+        }
+        Some(ValueRef::Map(mut entries)) => {
+            let mut existing = match destination.get_field_ref(number) {
+                Some(ValueRef::Map(existing)) => existing,
+                _ => Vec::new(),
+            };
+            // Both maps are sorted by key, so the entries can be merged in a single pass, with
+            // the entries of `source` replacing the entries of `destination` with the same key.
+            existing.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+            entries.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+            let mut merged = Vec::with_capacity(existing.len() + entries.len());
+            let mut existing = existing.into_iter().peekable();
+            for (key, value) in entries {
+                while let Some(entry) = existing.next_if(|entry| cmp_keys(&entry.0, &key).is_lt()) {
+                    merged.push((entry.0.to_value(), entry.1.to_value()));
+                }
+                existing.next_if(|entry| cmp_keys(&entry.0, &key).is_eq());
+                merged.push((key.to_value(), value.to_value()));
+            }
+            merged.extend(existing.map(|(key, value)| (key.to_value(), value.to_value())));
+            Value::Map(merged)
+        }
+        Some(value) => value.to_value(),
+        None => return,
+    };
+    set_field(destination, number, merged);
+}
+
+/// Merges all the fields of `source` into `destination`.
+fn merge_message(source: &dyn ReflectMessage, destination: &mut dyn ReflectMessage) {
+    for field in source.descriptor().fields() {
+        if source.has_field(field.number()) {
+            merge_field(field.number(), source, destination);
+        }
+    }
+}
+
+/// Clears the fields of `message` which aren't selected by `projection`.
+fn trim(projection: &Projection, desc: &MessageDescriptor, message: &mut dyn ReflectMessage) {
+    for field in desc.fields() {
+        let number = field.number();
+        match projection.get(number) {
+            None => {
+                message.clear_field(number);
+            }
+            Some(Selection::Field) => (),
+            Some(Selection::Nested(nested)) => {
+                let message_type = match field.message_type() {
+                    Some(message_type) if message.has_field(number) => message_type,
+                    _ => continue,
+                };
+                if let Some(message_nested) = message.get_message_mut(number) {
+                    trim(nested, message_type, message_nested);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the message type of the field `number`, if it's a message field.
+fn nested_type(desc: &MessageDescriptor, number: u32) -> Option<&'static MessageDescriptor> {
+    desc.field(number).and_then(|field| field.message_type())
+}
+
+/// Returns the singular message field `number`, if it's set.
+fn message_field(message: &dyn ReflectMessage, number: u32) -> Option<&dyn ReflectMessage> {
+    match message.get_field_ref(number) {
+        Some(ValueRef::Message(message)) => Some(message),
+        _ => None,
+    }
+}
+
+/// Sets a field to a value from a message of the same type, which always has the right type, so
+/// setting it can't fail.
+fn set_field(message: &mut dyn ReflectMessage, number: u32, value: Value) {
+    let _ = message.set_field(number, value);
+}
+
+/// Compares map keys, which are integers, booleans or strings.
+fn cmp_keys(a: &ValueRef<'_>, b: &ValueRef<'_>) -> Ordering {
+    match (a, b) {
+        (ValueRef::Bool(a), ValueRef::Bool(b)) => a.cmp(b),
+        (ValueRef::I32(a), ValueRef::I32(b)) => a.cmp(b),
+        (ValueRef::I64(a), ValueRef::I64(b)) => a.cmp(b),
+        (ValueRef::U32(a), ValueRef::U32(b)) => a.cmp(b),
+        (ValueRef::U64(a), ValueRef::U64(b)) => a.cmp(b),
+        (ValueRef::String(a), ValueRef::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(paths: &[&str]) -> FieldMask {
+        FieldMask::new(paths.iter().copied())
+    }
+
+    #[test]
+    fn normalize() {
+        let mut normalized = mask(&["b", "a.b", "", "a", "ab.c", "a.c.d", "b", "ab"]);
+        normalized.normalize();
+        assert_eq!(normalized, mask(&["a", "ab", "b"]));
+        assert!(normalized.contains("a.x.y"));
+        assert!(normalized.contains("ab"));
+        assert!(!normalized.contains("abc"));
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = mask(&["a.b", "c", "d.e"]);
+        let b = mask(&["a", "c.f", "g"]);
+        assert_eq!(a.union(&b), mask(&["a", "c", "d.e", "g"]));
+        assert_eq!(a.intersection(&b), mask(&["a.b", "c.f"]));
+        assert_eq!(a.intersection(&FieldMask::default()), FieldMask::default());
+    }
+}
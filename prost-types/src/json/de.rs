@@ -0,0 +1,613 @@
+//! Parsing of reflected messages from JSON.
+
+use core::convert::TryFrom;
+
+use prost::alloc::borrow::ToOwned;
+use prost::alloc::boxed::Box;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::reflect::{Cardinality, FieldDescriptor, Kind, MessageDescriptor, Value};
+use prost::ReflectMessage;
+
+use super::parser::{is_number, Json};
+use super::ser::{camel_to_snake, snake_to_camel};
+use super::{
+    has_special_form, is_wrapper, JsonError, ParseOptions, DURATION_SECONDS_MAX,
+    TIMESTAMP_SECONDS_MAX, TIMESTAMP_SECONDS_MIN,
+};
+use crate::type_registry::resolve_type_url;
+use crate::{Duration, Timestamp};
+
+pub(super) fn parse_message(
+    desc: &'static MessageDescriptor,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<Box<dyn ReflectMessage>, JsonError> {
+    let mut message = desc.new_message();
+    match desc.full_name() {
+        "google.protobuf.Timestamp" => {
+            let timestamp = parse_timestamp(json)?;
+            set(&mut *message, 1, Value::I64(timestamp.seconds))?;
+            set(&mut *message, 2, Value::I32(timestamp.nanos))?;
+        }
+        "google.protobuf.Duration" => {
+            let duration = parse_duration(json)?;
+            set(&mut *message, 1, Value::I64(duration.seconds))?;
+            set(&mut *message, 2, Value::I32(duration.nanos))?;
+        }
+        "google.protobuf.FieldMask" => {
+            let paths = parse_field_mask(json)?;
+            set(&mut *message, 1, Value::List(paths))?;
+        }
+        "google.protobuf.Value" => parse_struct_value(&mut *message, json, options)?,
+        "google.protobuf.Any" => parse_any(&mut *message, json, options)?,
+        name if is_wrapper(name)
+            || name == "google.protobuf.Struct"
+            || name == "google.protobuf.ListValue" =>
+        {
+            // Represented by their single field.
+            let field = field(desc, 1)?;
+            if let Some(value) = parse_field_value(field, json, options)? {
+                set(&mut *message, 1, value)?;
+            }
+        }
+        _ => parse_object(&mut *message, json, options)?,
+    }
+    Ok(message)
+}
+
+fn parse_object(
+    message: &mut dyn ReflectMessage,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<(), JsonError> {
+    let desc = message.descriptor();
+    match json {
+        Json::Object(members) => parse_members(message, members, options),
+        json => Err(expected("an object", desc.full_name(), json)),
+    }
+}
+
+/// Parses an `Any` from an object with an `@type` key, and either the fields of the contained
+/// message, or a `value` key with the special form of a well-known type.
+fn parse_any(
+    message: &mut dyn ReflectMessage,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<(), JsonError> {
+    let members = match json {
+        Json::Object(members) => members,
+        json => return Err(expected("an object", "google.protobuf.Any", json)),
+    };
+    if members.is_empty() {
+        return Ok(());
+    }
+
+    let type_url = match members.iter().find(|(key, _)| key == "@type") {
+        Some((_, Json::String(type_url))) => type_url,
+        Some((_, json)) => return Err(expected("a string", "@type", json)),
+        None => {
+            return Err(JsonError::new(
+                "missing @type in google.protobuf.Any".to_owned(),
+            ))
+        }
+    };
+    let desc = resolve_type_url(&options.message_types, type_url).ok_or_else(|| {
+        JsonError::new(format!(
+            "unknown message type {:?} in google.protobuf.Any",
+            type_url
+        ))
+    })?;
+
+    let mut contained = desc.new_message();
+    if has_special_form(desc.full_name()) {
+        for (key, json) in members {
+            match &**key {
+                "@type" => (),
+                "value" => contained = parse_message(desc, json, options)?,
+                _ if options.ignore_unknown_fields => (),
+                key => {
+                    return Err(JsonError::new(format!(
+                        "unknown field {:?} in google.protobuf.Any with type {}",
+                        key,
+                        desc.full_name()
+                    )))
+                }
+            }
+        }
+    } else {
+        let fields = members.iter().filter(|(key, _)| key != "@type");
+        parse_members(&mut *contained, fields, options)?;
+    }
+
+    let mut value = Vec::new();
+    contained.encode_dyn(&mut value);
+    set(message, 1, Value::String(type_url.clone()))?;
+    set(message, 2, Value::Bytes(value))
+}
+
+/// Parses the members of an object as the fields of a message.
+fn parse_members<'a, I>(
+    message: &mut dyn ReflectMessage,
+    members: I,
+    options: &ParseOptions,
+) -> Result<(), JsonError>
+where
+    I: IntoIterator<Item = &'a (String, Json)>,
+{
+    let desc = message.descriptor();
+    let mut seen_fields = Vec::new();
+    let mut seen_oneofs = Vec::new();
+    for (key, json) in members {
+        let field = match desc
+            .field_by_json_name(key)
+            .or_else(|| desc.field_by_name(key))
+        {
+            Some(field) => field,
+            None if options.ignore_unknown_fields => continue,
+            None => {
+                return Err(JsonError::new(format!(
+                    "unknown field {:?} in {}",
+                    key,
+                    desc.full_name()
+                )))
+            }
+        };
+
+        let number = field.number();
+        if seen_fields.contains(&number) {
+            return Err(JsonError::new(format!(
+                "duplicate field {} in {}",
+                field.name(),
+                desc.full_name()
+            )));
+        }
+        seen_fields.push(number);
+
+        let value = match parse_field_value(field, json, options)? {
+            Some(value) => value,
+            None => continue,
+        };
+        if let Some(oneof) = desc.oneof_of(number) {
+            if seen_oneofs.contains(&oneof.name()) {
+                return Err(JsonError::new(format!(
+                    "multiple fields of oneof {} in {}",
+                    oneof.name(),
+                    desc.full_name()
+                )));
+            }
+            seen_oneofs.push(oneof.name());
+        }
+        set(message, number, value)?;
+    }
+    Ok(())
+}
+
+/// Parses the value of a field. Returns `None` if the field should be left unset.
+fn parse_field_value(
+    field: &'static FieldDescriptor,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<Option<Value>, JsonError> {
+    match (field.kind(), json) {
+        (kind, Json::Null)
+            if field.cardinality() == Cardinality::Repeated || !accepts_null(field, kind) =>
+        {
+            Ok(None)
+        }
+        (Kind::Map(key_kind, value_kind), Json::Object(members)) => {
+            let mut entries = Vec::with_capacity(members.len());
+            for (key, json) in members {
+                let key = parse_map_key(field, *key_kind, key)?;
+                if entries.iter().any(|(k, _)| *k == key) {
+                    return Err(JsonError::new(format!(
+                        "duplicate map key in field {}",
+                        field.name()
+                    )));
+                }
+                check_element_null(field, *value_kind, json)?;
+                if let Some(value) = parse_value(field, *value_kind, json, options)? {
+                    entries.push((key, value));
+                }
+            }
+            Ok(Some(Value::Map(entries)))
+        }
+        (Kind::Map(..), json) => Err(expected("an object", field.name(), json)),
+        (kind, Json::Array(values)) if field.cardinality() == Cardinality::Repeated => {
+            let mut list = Vec::with_capacity(values.len());
+            for json in values {
+                check_element_null(field, kind, json)?;
+                if let Some(value) = parse_value(field, kind, json, options)? {
+                    list.push(value);
+                }
+            }
+            Ok(Some(Value::List(list)))
+        }
+        (_, json) if field.cardinality() == Cardinality::Repeated => {
+            Err(expected("an array", field.name(), json))
+        }
+        (kind, json) => parse_value(field, kind, json, options),
+    }
+}
+
+/// Parses a singular value, or an element of a repeated or map field. Returns `None` if the
+/// value should be skipped.
+fn parse_value(
+    field: &'static FieldDescriptor,
+    kind: Kind,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<Option<Value>, JsonError> {
+    let value = match kind {
+        Kind::Double => Value::F64(parse_f64(field, json)?),
+        Kind::Float => {
+            let value = parse_f64(field, json)?;
+            if value.is_finite() && !(-f64::from(f32::MAX)..=f64::from(f32::MAX)).contains(&value) {
+                return Err(out_of_range(field, json));
+            }
+            Value::F32(value as f32)
+        }
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            let value = parse_i64(field, json)?;
+            Value::I32(i32::try_from(value).map_err(|_| out_of_range(field, json))?)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(parse_i64(field, json)?),
+        Kind::Uint32 | Kind::Fixed32 => {
+            let value = parse_u64(field, json)?;
+            Value::U32(u32::try_from(value).map_err(|_| out_of_range(field, json))?)
+        }
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(parse_u64(field, json)?),
+        Kind::Bool => match json {
+            Json::Bool(value) => Value::Bool(*value),
+            json => return Err(expected("a boolean", field.name(), json)),
+        },
+        Kind::String => match json {
+            Json::String(value) => Value::String(value.clone()),
+            json => return Err(expected("a string", field.name(), json)),
+        },
+        Kind::Bytes => match json {
+            Json::String(value) => Value::Bytes(decode_base64(value).ok_or_else(|| {
+                JsonError::new(format!("invalid base64 for field {}", field.name()))
+            })?),
+            json => return Err(expected("a base64 string", field.name(), json)),
+        },
+        Kind::Enum => match json {
+            Json::Null => Value::EnumNumber(0),
+            Json::String(name) => {
+                match field.enum_type().and_then(|desc| desc.value_number(name)) {
+                    Some(number) => Value::EnumNumber(number),
+                    None if options.ignore_unknown_fields => return Ok(None),
+                    None => {
+                        return Err(JsonError::new(format!(
+                            "unknown enum value {:?} for field {}",
+                            name,
+                            field.name()
+                        )))
+                    }
+                }
+            }
+            Json::Number(_) => {
+                let value = parse_i64(field, json)?;
+                Value::EnumNumber(i32::try_from(value).map_err(|_| out_of_range(field, json))?)
+            }
+            json => return Err(expected("an enum value", field.name(), json)),
+        },
+        Kind::Message | Kind::Group => {
+            let desc = field.message_type().ok_or_else(|| {
+                JsonError::new(format!("unknown message type for field {}", field.name()))
+            })?;
+            Value::Message(parse_message(desc, json, options)?)
+        }
+        Kind::Map(..) => return Err(expected("a singular value", field.name(), json)),
+    };
+    Ok(Some(value))
+}
+
+fn parse_map_key(field: &FieldDescriptor, kind: Kind, key: &str) -> Result<Value, JsonError> {
+    let json = Json::String(key.to_owned());
+    let key = match kind {
+        Kind::String => Value::String(key.to_owned()),
+        Kind::Bool => match key {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => return Err(expected("a boolean map key", field.name(), &json)),
+        },
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            let value = parse_i64(field, &json)?;
+            Value::I32(i32::try_from(value).map_err(|_| out_of_range(field, &json))?)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(parse_i64(field, &json)?),
+        Kind::Uint32 | Kind::Fixed32 => {
+            let value = parse_u64(field, &json)?;
+            Value::U32(u32::try_from(value).map_err(|_| out_of_range(field, &json))?)
+        }
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(parse_u64(field, &json)?),
+        kind => {
+            return Err(JsonError::new(format!(
+                "invalid map key type {:?} for field {}",
+                kind,
+                field.name()
+            )))
+        }
+    };
+    Ok(key)
+}
+
+/// Returns `true` if JSON `null` is a value of the type, rather than meaning the field is unset.
+fn accepts_null(field: &FieldDescriptor, kind: Kind) -> bool {
+    match kind {
+        Kind::Enum => matches!(
+            field.enum_type(),
+            Some(desc) if desc.full_name() == "google.protobuf.NullValue"
+        ),
+        Kind::Message => matches!(
+            field.message_type(),
+            Some(desc) if desc.full_name() == "google.protobuf.Value"
+        ),
+        _ => false,
+    }
+}
+
+/// Elements of repeated and map fields can only be `null` if it's a value of their type.
+fn check_element_null(field: &FieldDescriptor, kind: Kind, json: &Json) -> Result<(), JsonError> {
+    if *json == Json::Null && !accepts_null(field, kind) {
+        return Err(JsonError::new(format!(
+            "null element in field {}",
+            field.name()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the text of a number, which may be quoted.
+fn number_text<'a>(field: &FieldDescriptor, json: &'a Json) -> Result<&'a str, JsonError> {
+    match json {
+        Json::Number(text) => Ok(text),
+        Json::String(text) if is_number(text) => Ok(text),
+        json => Err(expected("a number", field.name(), json)),
+    }
+}
+
+fn parse_i64(field: &FieldDescriptor, json: &Json) -> Result<i64, JsonError> {
+    let text = number_text(field, json)?;
+    if let Ok(value) = text.parse::<i64>() {
+        return Ok(value);
+    }
+    // Integers may be written with a fraction or exponent, as long as the value is integral.
+    let value = text
+        .parse::<f64>()
+        .map_err(|_| expected("an integer", field.name(), json))?;
+    if !(-9.223_372_036_854_776e18..9.223_372_036_854_776e18).contains(&value) {
+        return Err(out_of_range(field, json));
+    }
+    // In range, the conversion only drops the fraction. `f64::fract` isn't available without std.
+    let integer = value as i64;
+    if integer as f64 != value {
+        return Err(expected("an integer", field.name(), json));
+    }
+    Ok(integer)
+}
+
+fn parse_u64(field: &FieldDescriptor, json: &Json) -> Result<u64, JsonError> {
+    let text = number_text(field, json)?;
+    if let Ok(value) = text.parse::<u64>() {
+        return Ok(value);
+    }
+    let value = text
+        .parse::<f64>()
+        .map_err(|_| expected("an integer", field.name(), json))?;
+    if !(0.0..1.844_674_407_370_955_2e19).contains(&value) {
+        return Err(out_of_range(field, json));
+    }
+    let integer = value as u64;
+    if integer as f64 != value {
+        return Err(expected("an integer", field.name(), json));
+    }
+    Ok(integer)
+}
+
+fn parse_f64(field: &FieldDescriptor, json: &Json) -> Result<f64, JsonError> {
+    match json {
+        Json::String(text) if text == "NaN" => return Ok(f64::NAN),
+        Json::String(text) if text == "Infinity" => return Ok(f64::INFINITY),
+        Json::String(text) if text == "-Infinity" => return Ok(f64::NEG_INFINITY),
+        _ => (),
+    }
+    let value = number_text(field, json)?
+        .parse::<f64>()
+        .map_err(|_| expected("a number", field.name(), json))?;
+    if value.is_infinite() {
+        return Err(out_of_range(field, json));
+    }
+    Ok(value)
+}
+
+fn parse_timestamp(json: &Json) -> Result<Timestamp, JsonError> {
+    let text = match json {
+        Json::String(text) => text,
+        json => return Err(expected("a string", "google.protobuf.Timestamp", json)),
+    };
+    let invalid = || JsonError::new(format!("invalid timestamp {:?}", text));
+    if !is_rfc3339(text) {
+        return Err(invalid());
+    }
+    let timestamp = text.parse::<Timestamp>().map_err(|_| invalid())?;
+    if timestamp.seconds < TIMESTAMP_SECONDS_MIN || timestamp.seconds > TIMESTAMP_SECONDS_MAX {
+        return Err(invalid());
+    }
+    Ok(timestamp)
+}
+
+/// Checks that a timestamp has the strict form required by the JSON mapping,
+/// `YYYY-MM-DDTHH:MM:SS[.fraction](Z|+HH:MM|-HH:MM)`. The datetime parser also accepts a number of
+/// more lenient forms.
+fn is_rfc3339(s: &str) -> bool {
+    fn digits(s: &[u8]) -> bool {
+        !s.is_empty() && s.iter().all(u8::is_ascii_digit)
+    }
+
+    let s = s.as_bytes();
+    if s.len() < 20
+        || !digits(&s[0..4])
+        || s[4] != b'-'
+        || !digits(&s[5..7])
+        || s[7] != b'-'
+        || !digits(&s[8..10])
+        || s[10] != b'T'
+        || !digits(&s[11..13])
+        || s[13] != b':'
+        || !digits(&s[14..16])
+        || s[16] != b':'
+        || !digits(&s[17..19])
+    {
+        return false;
+    }
+
+    let mut rest = &s[19..];
+    if rest[0] == b'.' {
+        let len = rest[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 || len > 9 {
+            return false;
+        }
+        rest = &rest[1 + len..];
+    }
+    match rest {
+        b"Z" => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => digits(&[*h1, *h2, *m1, *m2]),
+        _ => false,
+    }
+}
+
+fn parse_duration(json: &Json) -> Result<Duration, JsonError> {
+    let text = match json {
+        Json::String(text) => text,
+        json => return Err(expected("a string", "google.protobuf.Duration", json)),
+    };
+    let invalid = || JsonError::new(format!("invalid duration {:?}", text));
+    let duration = text.parse::<Duration>().map_err(|_| invalid())?;
+    if duration.seconds.abs() > DURATION_SECONDS_MAX {
+        return Err(invalid());
+    }
+    Ok(duration)
+}
+
+fn parse_field_mask(json: &Json) -> Result<Vec<Value>, JsonError> {
+    let text = match json {
+        Json::String(text) => text,
+        json => return Err(expected("a string", "google.protobuf.FieldMask", json)),
+    };
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(',')
+        .map(|path| {
+            let snake = camel_to_snake(path);
+            if path.contains('_') || snake_to_camel(&snake) != path {
+                return Err(JsonError::new(format!(
+                    "invalid field mask path {:?}",
+                    path
+                )));
+            }
+            Ok(Value::String(snake))
+        })
+        .collect()
+}
+
+fn parse_struct_value(
+    message: &mut dyn ReflectMessage,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<(), JsonError> {
+    let desc = message.descriptor();
+    let (number, value) = match json {
+        Json::Null => (1, Value::EnumNumber(0)),
+        Json::Number(text) => match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => (2, Value::F64(value)),
+            _ => return Err(out_of_range(field(desc, 2)?, json)),
+        },
+        Json::String(value) => (3, Value::String(value.clone())),
+        Json::Bool(value) => (4, Value::Bool(*value)),
+        Json::Object(_) => {
+            let struct_desc = field(desc, 5)?.message_type();
+            (5, Value::Message(parse_nested(struct_desc, json, options)?))
+        }
+        Json::Array(_) => {
+            let list_desc = field(desc, 6)?.message_type();
+            (6, Value::Message(parse_nested(list_desc, json, options)?))
+        }
+    };
+    set(message, number, value)
+}
+
+fn parse_nested(
+    desc: Option<&'static MessageDescriptor>,
+    json: &Json,
+    options: &ParseOptions,
+) -> Result<Box<dyn ReflectMessage>, JsonError> {
+    let desc = desc.ok_or_else(|| JsonError::new("unknown message type".to_owned()))?;
+    parse_message(desc, json, options)
+}
+
+fn field(
+    desc: &'static MessageDescriptor,
+    number: u32,
+) -> Result<&'static FieldDescriptor, JsonError> {
+    desc.field(number)
+        .ok_or_else(|| JsonError::new(format!("{} has no field {}", desc.full_name(), number)))
+}
+
+fn set(message: &mut dyn ReflectMessage, number: u32, value: Value) -> Result<(), JsonError> {
+    message
+        .set_field(number, value)
+        .map_err(|error| JsonError::new(format!("{}", error)))
+}
+
+fn expected(what: &str, name: &str, json: &Json) -> JsonError {
+    JsonError::new(format!(
+        "expected {} for {}, found {}",
+        what,
+        name,
+        json.type_name()
+    ))
+}
+
+fn out_of_range(field: &FieldDescriptor, json: &Json) -> JsonError {
+    JsonError::new(format!(
+        "value out of range for field {}: {:?}",
+        field.name(),
+        json
+    ))
+}
+
+/// Decodes standard or URL-safe base64, with or without padding.
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let unpadded = s.trim_end_matches('=');
+    let padding = s.len() - unpadded.len();
+    let remainder = s.len() % 4;
+    if unpadded.len() % 4 == 1 || padding > 2 || (padding > 0 && remainder != 0) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &c in unpadded.as_bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(digit);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
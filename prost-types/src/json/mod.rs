@@ -0,0 +1,222 @@
+//! The canonical [Protobuf JSON mapping][1] for reflected messages.
+//!
+//! Any message implementing [`ReflectMessage`] can be serialized to and parsed from JSON. Fields
+//! are keyed by their lowerCamelCase JSON names, 64-bit integers are written as strings, enums by
+//! the name of their value and bytes as base64. The well-known types use their special forms:
+//! `Timestamp` and `Duration` as strings, `FieldMask` as a comma-separated list of paths, `Struct`,
+//! `Value` and `ListValue` as arbitrary JSON, and the wrapper types as their bare value.
+//!
+//! `google.protobuf.Any` is written as an object with an `@type` key holding its type URL, along
+//! with the fields of the contained message, or with a `value` key holding the JSON form of a
+//! well-known type which has a special form. Resolving the type URL requires the descriptor of
+//! the contained message type, which must be registered with the `message_type` or
+//! `type_registry` options, e.g. with [`TypeRegistry::register_reflect`] or
+//! [`TypeRegistry::register_well_known_types`].
+//!
+//! ```rust,ignore
+//! let json = prost_types::json::to_string(&message)?;
+//! let message: MyMessage = prost_types::json::from_str(&json)?;
+//! ```
+//!
+//! [1]: https://developers.google.com/protocol-buffers/docs/proto3#json
+
+mod de;
+mod parser;
+mod ser;
+
+pub(crate) use self::ser::write_finite_float;
+
+use core::fmt;
+
+use prost::alloc::boxed::Box;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::reflect::MessageDescriptor;
+use prost::ReflectMessage;
+
+use crate::TypeRegistry;
+
+/// Serializes a message to JSON.
+pub fn to_string(message: &dyn ReflectMessage) -> Result<String, JsonError> {
+    to_string_with_options(message, &SerializeOptions::default())
+}
+
+/// Serializes a message to JSON, using the given options.
+pub fn to_string_with_options(
+    message: &dyn ReflectMessage,
+    options: &SerializeOptions,
+) -> Result<String, JsonError> {
+    ser::serialize(message, options)
+}
+
+/// Parses a message of type `M` from JSON.
+pub fn from_str<M>(s: &str) -> Result<M, JsonError>
+where
+    M: ReflectMessage,
+{
+    from_str_with_options(s, &ParseOptions::default())
+}
+
+/// Parses a message of type `M` from JSON, using the given options.
+pub fn from_str_with_options<M>(s: &str, options: &ParseOptions) -> Result<M, JsonError>
+where
+    M: ReflectMessage,
+{
+    let message = from_str_with_descriptor(M::type_descriptor(), s, options)?;
+    M::from_boxed(message).map_err(|message| {
+        JsonError::new(format!(
+            "parsed a {} rather than a {}",
+            message.descriptor().full_name(),
+            M::type_descriptor().full_name()
+        ))
+    })
+}
+
+/// Parses a message of the type described by `desc` from JSON.
+pub fn from_str_with_descriptor(
+    desc: &'static MessageDescriptor,
+    s: &str,
+    options: &ParseOptions,
+) -> Result<Box<dyn ReflectMessage>, JsonError> {
+    let json = parser::parse(s)?;
+    de::parse_message(desc, &json, options)
+}
+
+/// Options for serializing messages to JSON.
+#[derive(Clone, Debug, Default)]
+pub struct SerializeOptions {
+    use_proto_field_name: bool,
+    emit_unpopulated_fields: bool,
+    message_types: Vec<&'static MessageDescriptor>,
+}
+
+impl SerializeOptions {
+    /// Creates the default serialize options.
+    pub fn new() -> SerializeOptions {
+        SerializeOptions::default()
+    }
+
+    /// Keys fields by their name in the `.proto` file, rather than by their lowerCamelCase JSON
+    /// name.
+    pub fn use_proto_field_name(mut self, enabled: bool) -> SerializeOptions {
+        self.use_proto_field_name = enabled;
+        self
+    }
+
+    /// Emits fields without presence, including repeated and map fields, even when they have
+    /// their default value.
+    pub fn emit_unpopulated_fields(mut self, enabled: bool) -> SerializeOptions {
+        self.emit_unpopulated_fields = enabled;
+        self
+    }
+
+    /// Registers a message type which `google.protobuf.Any` values may contain.
+    pub fn message_type(mut self, desc: &'static MessageDescriptor) -> SerializeOptions {
+        self.message_types.push(desc);
+        self
+    }
+
+    /// Registers the message types with descriptors in the registry, which
+    /// `google.protobuf.Any` values may contain.
+    pub fn type_registry(mut self, registry: &TypeRegistry) -> SerializeOptions {
+        self.message_types.extend(registry.descriptors());
+        self
+    }
+}
+
+/// Options for parsing messages from JSON.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    ignore_unknown_fields: bool,
+    message_types: Vec<&'static MessageDescriptor>,
+}
+
+impl ParseOptions {
+    /// Creates the default parse options.
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Skips object keys which don't name a field, and enum value names which aren't defined by
+    /// the enum, rather than failing.
+    pub fn ignore_unknown_fields(mut self, enabled: bool) -> ParseOptions {
+        self.ignore_unknown_fields = enabled;
+        self
+    }
+
+    /// Registers a message type which `google.protobuf.Any` values may contain.
+    pub fn message_type(mut self, desc: &'static MessageDescriptor) -> ParseOptions {
+        self.message_types.push(desc);
+        self
+    }
+
+    /// Registers the message types with descriptors in the registry, which
+    /// `google.protobuf.Any` values may contain.
+    pub fn type_registry(mut self, registry: &TypeRegistry) -> ParseOptions {
+        self.message_types.extend(registry.descriptors());
+        self
+    }
+}
+
+/// An error returned when a message can't be serialized to or parsed from JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    description: String,
+}
+
+impl JsonError {
+    fn new(description: String) -> JsonError {
+        JsonError { description }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSON: {}", self.description)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {}
+
+/// Returns `true` for the full names of the well-known wrapper types, which are represented in
+/// JSON by their `value` field.
+fn is_wrapper(full_name: &str) -> bool {
+    matches!(
+        full_name,
+        "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue"
+    )
+}
+
+/// Returns `true` for the full names of the well-known types with a special JSON form, which are
+/// represented in an `Any` by a `value` key.
+fn has_special_form(full_name: &str) -> bool {
+    is_wrapper(full_name)
+        || matches!(
+            full_name,
+            "google.protobuf.Any"
+                | "google.protobuf.Duration"
+                | "google.protobuf.FieldMask"
+                | "google.protobuf.ListValue"
+                | "google.protobuf.Struct"
+                | "google.protobuf.Timestamp"
+                | "google.protobuf.Value"
+        )
+}
+
+/// The range of seconds representable as an RFC 3339 timestamp, 0001-01-01T00:00:00Z to
+/// 9999-12-31T23:59:59Z.
+const TIMESTAMP_SECONDS_MIN: i64 = -62_135_596_800;
+const TIMESTAMP_SECONDS_MAX: i64 = 253_402_300_799;
+
+/// The range of seconds allowed in a duration, roughly 10,000 years.
+const DURATION_SECONDS_MAX: i64 = 315_576_000_000;
@@ -0,0 +1,282 @@
+//! A strict parser for JSON text, producing a tree of [`Json`] values.
+
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+
+use super::JsonError;
+
+/// The maximum depth of nested arrays and objects.
+const RECURSION_LIMIT: u32 = 100;
+
+/// A parsed JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Json {
+    Null,
+    Bool(bool),
+    /// A number, kept in its textual form so that 64-bit integers don't lose precision.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// The members of an object, in the order they appear. Duplicate keys are preserved.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Returns a short description of the value's type, for error messages.
+    pub(super) fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+/// Parses a single JSON value, which may be surrounded by whitespace.
+pub(super) fn parse(s: &str) -> Result<Json, JsonError> {
+    let mut parser = Parser {
+        input: s.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.parse_value(RECURSION_LIMIT)?;
+    parser.skip_whitespace();
+    if parser.pos != parser.input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Returns `true` if `s` is a number according to the JSON grammar.
+pub(super) fn is_number(s: &str) -> bool {
+    let mut parser = Parser {
+        input: s.as_bytes(),
+        pos: 0,
+    };
+    parser.parse_number().is_ok() && parser.pos == s.len()
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, description: &str) -> JsonError {
+        JsonError::new(format!("{} at offset {}", description, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> Result<(), JsonError> {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn parse_value(&mut self, depth: u32) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.expect_literal("null").map(|()| Json::Null),
+            Some(b't') => self.expect_literal("true").map(|()| Json::Bool(true)),
+            Some(b'f') => self.expect_literal("false").map(|()| Json::Bool(false)),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.parse_number().map(Json::Number),
+            Some(b'[') => {
+                let depth = self.enter(depth)?;
+                self.parse_array(depth)
+            }
+            Some(b'{') => {
+                let depth = self.enter(depth)?;
+                self.parse_object(depth)
+            }
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn enter(&self, depth: u32) -> Result<u32, JsonError> {
+        depth
+            .checked_sub(1)
+            .ok_or_else(|| self.error("recursion limit reached"))
+    }
+
+    fn parse_array(&mut self, depth: u32) -> Result<Json, JsonError> {
+        self.pos += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.parse_value(depth)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: u32) -> Result<Json, JsonError> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected an object key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            self.pos += 1;
+            members.push((key, self.parse_value(depth)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<String, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+        // The input is valid UTF-8 and the number is ASCII.
+        Ok(String::from_utf8_lossy(&self.input[start..self.pos]).into_owned())
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = self.peek();
+                    self.pos += 1;
+                    let c = match escape {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(c) if c < 0x20 => return Err(self.error("control character in string")),
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Parses the hex digits of a `\u` escape, including a following low surrogate escape if the
+    /// first is a high surrogate. Leaves the position after the last hex digit.
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.input[self.pos..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let mut code = 0;
+        for &digit in digits {
+            code = code * 16
+                + char::from(digit)
+                    .to_digit(16)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+        }
+        self.pos += 4;
+        Ok(code)
+    }
+}
@@ -0,0 +1,481 @@
+//! Serialization of reflected messages to JSON.
+
+use core::fmt::{self, Write};
+
+use prost::alloc::borrow::ToOwned;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::reflect::{Cardinality, FieldDescriptor, Kind, Value};
+use prost::ReflectMessage;
+
+use super::{
+    has_special_form, is_wrapper, JsonError, SerializeOptions, DURATION_SECONDS_MAX,
+    TIMESTAMP_SECONDS_MAX, TIMESTAMP_SECONDS_MIN,
+};
+use crate::type_registry::resolve_type_url;
+use crate::{Duration, Timestamp};
+
+pub(super) fn serialize(
+    message: &dyn ReflectMessage,
+    options: &SerializeOptions,
+) -> Result<String, JsonError> {
+    let mut serializer = Serializer {
+        out: String::new(),
+        options,
+    };
+    serializer.write_message(message)?;
+    Ok(serializer.out)
+}
+
+struct Serializer<'a> {
+    out: String,
+    options: &'a SerializeOptions,
+}
+
+impl<'a> Serializer<'a> {
+    fn write_message(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let desc = message.descriptor();
+        match desc.full_name() {
+            "google.protobuf.Timestamp" => self.write_timestamp(message),
+            "google.protobuf.Duration" => self.write_duration(message),
+            "google.protobuf.FieldMask" => self.write_field_mask(message),
+            "google.protobuf.Struct" | "google.protobuf.ListValue" => {
+                // Both are represented by their single map or repeated field.
+                let field = field(message, 1)?;
+                self.write_field_value(field, &get(message, 1)?)
+            }
+            "google.protobuf.Value" => self.write_struct_value(message),
+            "google.protobuf.Any" => self.write_any(message),
+            name if is_wrapper(name) => {
+                let field = field(message, 1)?;
+                self.write_field_value(field, &get(message, 1)?)
+            }
+            _ => self.write_object(message, None),
+        }
+    }
+
+    /// Writes the fields of a message as an object, after an `@type` key if `type_url` is set.
+    fn write_object(
+        &mut self,
+        message: &dyn ReflectMessage,
+        type_url: Option<&str>,
+    ) -> Result<(), JsonError> {
+        let desc = message.descriptor();
+        self.out.push('{');
+        let mut first = true;
+        if let Some(type_url) = type_url {
+            self.write_string("@type");
+            self.out.push(':');
+            self.write_string(type_url);
+            first = false;
+        }
+        for field in desc.fields() {
+            let number = field.number();
+            let emit_default = self.options.emit_unpopulated_fields
+                && matches!(
+                    field.cardinality(),
+                    Cardinality::Singular | Cardinality::Repeated
+                )
+                && desc.oneof_of(number).is_none();
+            if !message.has_field(number) && !emit_default {
+                continue;
+            }
+            let value = get(message, number)?;
+
+            if !first {
+                self.out.push(',');
+            }
+            first = false;
+            let key = if self.options.use_proto_field_name {
+                field.name()
+            } else {
+                field.json_name()
+            };
+            self.write_string(key);
+            self.out.push(':');
+            self.write_field_value(field, &value)?;
+        }
+        self.out.push('}');
+        Ok(())
+    }
+
+    /// Writes an `Any` with the fields of the contained message, or with its special form in a
+    /// `value` key.
+    fn write_any(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let type_url = match get(message, 1)? {
+            Value::String(type_url) => type_url,
+            _ => return Err(JsonError::new("invalid google.protobuf.Any".to_owned())),
+        };
+        let value = match get(message, 2)? {
+            Value::Bytes(value) => value,
+            _ => return Err(JsonError::new("invalid google.protobuf.Any".to_owned())),
+        };
+        if type_url.is_empty() && value.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+
+        let desc = resolve_type_url(&self.options.message_types, &type_url).ok_or_else(|| {
+            JsonError::new(format!(
+                "unknown message type {:?} in google.protobuf.Any",
+                type_url
+            ))
+        })?;
+        let mut contained = desc.new_message();
+        contained.merge_dyn(&value).map_err(|error| {
+            JsonError::new(format!(
+                "invalid value of google.protobuf.Any with type {:?}: {}",
+                type_url, error
+            ))
+        })?;
+
+        if has_special_form(desc.full_name()) {
+            self.out.push('{');
+            self.write_string("@type");
+            self.out.push(':');
+            self.write_string(&type_url);
+            self.out.push_str(",\"value\":");
+            self.write_message(&*contained)?;
+            self.out.push('}');
+            Ok(())
+        } else {
+            self.write_object(&*contained, Some(&type_url))
+        }
+    }
+
+    fn write_field_value(
+        &mut self,
+        field: &FieldDescriptor,
+        value: &Value,
+    ) -> Result<(), JsonError> {
+        match (field.kind(), value) {
+            (Kind::Map(key_kind, value_kind), Value::Map(entries)) => {
+                self.out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        self.out.push(',');
+                    }
+                    self.write_map_key(*key_kind, key)?;
+                    self.out.push(':');
+                    self.write_value(field, *value_kind, value)?;
+                }
+                self.out.push('}');
+                Ok(())
+            }
+            (kind, Value::List(values)) => {
+                self.out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        self.out.push(',');
+                    }
+                    self.write_value(field, kind, value)?;
+                }
+                self.out.push(']');
+                Ok(())
+            }
+            (kind, value) => self.write_value(field, kind, value),
+        }
+    }
+
+    fn write_map_key(&mut self, kind: Kind, key: &Value) -> Result<(), JsonError> {
+        match key {
+            Value::String(key) => self.write_string(key),
+            Value::Bool(key) => self.write_string(if *key { "true" } else { "false" }),
+            Value::I32(key) => self.write_string(&format!("{}", key)),
+            Value::I64(key) => self.write_string(&format!("{}", key)),
+            Value::U32(key) => self.write_string(&format!("{}", key)),
+            Value::U64(key) => self.write_string(&format!("{}", key)),
+            _ => {
+                return Err(JsonError::new(format!(
+                    "invalid map key for type {:?}",
+                    kind
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a singular value of a field, or an element of a repeated or map field.
+    fn write_value(
+        &mut self,
+        field: &FieldDescriptor,
+        kind: Kind,
+        value: &Value,
+    ) -> Result<(), JsonError> {
+        match value {
+            Value::Bool(value) => self.out.push_str(if *value { "true" } else { "false" }),
+            Value::I32(value) => write!(self.out, "{}", value).unwrap(),
+            Value::U32(value) => write!(self.out, "{}", value).unwrap(),
+            Value::I64(value) => write!(self.out, "\"{}\"", value).unwrap(),
+            Value::U64(value) => write!(self.out, "\"{}\"", value).unwrap(),
+            Value::F32(value) => self.write_float(*value, f64::from(*value)),
+            Value::F64(value) => self.write_float(*value, *value),
+            Value::String(value) => self.write_string(value),
+            Value::Bytes(value) => {
+                self.out.push('"');
+                encode_base64(value, &mut self.out);
+                self.out.push('"');
+            }
+            Value::EnumNumber(number) => {
+                let enum_type = field.enum_type();
+                match enum_type {
+                    Some(enum_type) if enum_type.full_name() == "google.protobuf.NullValue" => {
+                        self.out.push_str("null")
+                    }
+                    Some(enum_type) => match enum_type.value_name(*number) {
+                        Some(name) => self.write_string(name),
+                        None => write!(self.out, "{}", number).unwrap(),
+                    },
+                    None => write!(self.out, "{}", number).unwrap(),
+                }
+            }
+            Value::Message(message) => self.write_message(&**message)?,
+            Value::List(_) | Value::Map(_) => {
+                return Err(JsonError::new(format!(
+                    "invalid value for field {} of type {:?}",
+                    field.name(),
+                    kind
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a floating point number, using strings for the non-finite values.
+    ///
+    /// `wide` is the value converted to `f64`, which is used to classify it.
+    fn write_float<F>(&mut self, value: F, wide: f64)
+    where
+        F: fmt::Display + fmt::LowerExp,
+    {
+        if wide.is_nan() {
+            self.out.push_str("\"NaN\"");
+        } else if wide == f64::INFINITY {
+            self.out.push_str("\"Infinity\"");
+        } else if wide == f64::NEG_INFINITY {
+            self.out.push_str("\"-Infinity\"");
+        } else {
+            write_finite_float(&mut self.out, value, wide);
+        }
+    }
+
+    fn write_string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                c if (c as u32) < 0x20 => write!(self.out, "\\u{:04x}", c as u32).unwrap(),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn write_timestamp(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let timestamp = Timestamp {
+            seconds: get_i64(message, 1)?,
+            nanos: get_i32(message, 2)?,
+        };
+        if timestamp.seconds < TIMESTAMP_SECONDS_MIN
+            || timestamp.seconds > TIMESTAMP_SECONDS_MAX
+            || timestamp.nanos < 0
+            || timestamp.nanos > 999_999_999
+        {
+            return Err(JsonError::new(format!(
+                "timestamp out of range: {:?}",
+                timestamp
+            )));
+        }
+        self.write_string(&format!("{}", timestamp));
+        Ok(())
+    }
+
+    fn write_duration(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let duration = Duration {
+            seconds: get_i64(message, 1)?,
+            nanos: get_i32(message, 2)?,
+        };
+        if duration.seconds.abs() > DURATION_SECONDS_MAX
+            || duration.nanos.abs() > 999_999_999
+            || (duration.seconds < 0 && duration.nanos > 0)
+            || (duration.seconds > 0 && duration.nanos < 0)
+        {
+            return Err(JsonError::new(format!(
+                "duration out of range: {:?}",
+                duration
+            )));
+        }
+        self.write_string(&format!("{}", duration));
+        Ok(())
+    }
+
+    fn write_field_mask(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let paths = match get(message, 1)? {
+            Value::List(paths) => paths,
+            _ => {
+                return Err(JsonError::new(
+                    "invalid google.protobuf.FieldMask".to_owned(),
+                ))
+            }
+        };
+        let mut joined = String::new();
+        for (i, path) in paths.iter().enumerate() {
+            let path = match path {
+                Value::String(path) => path,
+                _ => {
+                    return Err(JsonError::new(
+                        "invalid google.protobuf.FieldMask".to_owned(),
+                    ))
+                }
+            };
+            if i != 0 {
+                joined.push(',');
+            }
+            let camel = snake_to_camel(path);
+            if camel_to_snake(&camel) != *path {
+                return Err(JsonError::new(format!(
+                    "field mask path {:?} can't be represented in JSON",
+                    path
+                )));
+            }
+            joined.push_str(&camel);
+        }
+        self.write_string(&joined);
+        Ok(())
+    }
+
+    fn write_struct_value(&mut self, message: &dyn ReflectMessage) -> Result<(), JsonError> {
+        let desc = message.descriptor();
+        for field in desc.fields() {
+            if !message.has_field(field.number()) {
+                continue;
+            }
+            let value = get(message, field.number())?;
+            if let Value::F64(number) = value {
+                if !number.is_finite() {
+                    return Err(JsonError::new(format!(
+                        "google.protobuf.Value can't represent {}",
+                        number
+                    )));
+                }
+            }
+            return self.write_field_value(field, &value);
+        }
+        // A value without a kind set is treated as null.
+        self.out.push_str("null");
+        Ok(())
+    }
+}
+
+fn field(message: &dyn ReflectMessage, number: u32) -> Result<&'static FieldDescriptor, JsonError> {
+    message.descriptor().field(number).ok_or_else(|| {
+        JsonError::new(format!(
+            "{} has no field {}",
+            message.descriptor().full_name(),
+            number
+        ))
+    })
+}
+
+fn get(message: &dyn ReflectMessage, number: u32) -> Result<Value, JsonError> {
+    message.get_field(number).ok_or_else(|| {
+        JsonError::new(format!(
+            "{} has no field {}",
+            message.descriptor().full_name(),
+            number
+        ))
+    })
+}
+
+fn get_i64(message: &dyn ReflectMessage, number: u32) -> Result<i64, JsonError> {
+    match get(message, number)? {
+        Value::I64(value) => Ok(value),
+        _ => Err(JsonError::new(format!(
+            "invalid {}",
+            message.descriptor().full_name()
+        ))),
+    }
+}
+
+fn get_i32(message: &dyn ReflectMessage, number: u32) -> Result<i32, JsonError> {
+    match get(message, number)? {
+        Value::I32(value) => Ok(value),
+        _ => Err(JsonError::new(format!(
+            "invalid {}",
+            message.descriptor().full_name()
+        ))),
+    }
+}
+
+/// Converts a `snake_case` field path to `lowerCamelCase`.
+pub(super) fn snake_to_camel(path: &str) -> String {
+    let mut camel = String::with_capacity(path.len());
+    let mut uppercase_next = false;
+    for c in path.chars() {
+        if c == '_' {
+            uppercase_next = true;
+        } else if uppercase_next {
+            camel.push(c.to_ascii_uppercase());
+            uppercase_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// Converts a `lowerCamelCase` field path to `snake_case`.
+pub(super) fn camel_to_snake(path: &str) -> String {
+    let mut snake = String::with_capacity(path.len() + 4);
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn encode_base64(bytes: &[u8], out: &mut String) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// Writes a finite floating point number, switching to exponent notation for magnitudes outside
+/// `1e-7..1e21` rather than writing out hundreds of digits.
+///
+/// `wide` is the value converted to `f64`, which is used to classify it.
+pub(crate) fn write_finite_float<F>(out: &mut String, value: F, wide: f64)
+where
+    F: fmt::Display + fmt::LowerExp,
+{
+    // `f64::abs` isn't available without std.
+    let magnitude = if wide < 0.0 { -wide } else { wide };
+    if magnitude != 0.0 && !(1e-7..1e21).contains(&magnitude) {
+        write!(out, "{:e}", value).unwrap();
+    } else {
+        write!(out, "{}", value).unwrap();
+    }
+}
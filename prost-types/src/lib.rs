@@ -18,12 +18,24 @@ use core::i64;
 use core::str::FromStr;
 use core::time;
 
+use prost::alloc::string::String;
+use prost::{DecodeError, Name};
+
 include!("protobuf.rs");
 pub mod compiler {
     include!("compiler.rs");
 }
 
 mod datetime;
+pub mod diff;
+pub mod dynamic;
+mod field_mask;
+pub mod json;
+pub mod raw;
+pub mod text_format;
+mod type_registry;
+
+pub use crate::type_registry::{DecodeFn, TypeRegistry};
 
 // The Protobuf `Duration` and `Timestamp` types can't delegate to the standard library equivalents
 // because the Protobuf versions are signed. To make them easier to work with, `From` conversions
@@ -122,7 +134,7 @@ impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut d = self.clone();
         d.normalize();
-        if self.seconds < 0 && self.nanos < 0 {
+        if d.seconds < 0 || d.nanos < 0 {
             write!(f, "-")?;
         }
         write!(f, "{}", d.seconds.abs())?;
@@ -394,6 +406,88 @@ impl fmt::Display for Timestamp {
     }
 }
 
+impl Any {
+    /// Packs a message into an `Any`, with the type URL of the message type.
+    pub fn from_msg<M>(message: &M) -> Any
+    where
+        M: Name,
+    {
+        Any {
+            type_url: M::type_url(),
+            value: message.encode_to_vec(),
+        }
+    }
+
+    /// Unpacks the message contained in the `Any`.
+    ///
+    /// An error is returned if the type URL does not name the message type `M`, or if the value
+    /// can't be decoded. Only the type name following the last `/` of the type URL is compared,
+    /// so type URLs with prefixes other than `type.googleapis.com/` are accepted.
+    pub fn to_msg<M>(&self) -> Result<M, AnyError>
+    where
+        M: Name + Default,
+    {
+        let expected = M::full_name();
+        if self.type_name() != expected {
+            return Err(AnyError::TypeMismatch {
+                expected,
+                actual: self.type_url.clone(),
+            });
+        }
+        M::decode(&*self.value).map_err(AnyError::Decode)
+    }
+
+    /// Returns the fully qualified name of the message type contained in the `Any`, which is the
+    /// part of the type URL following the last `/`.
+    pub fn type_name(&self) -> &str {
+        match self.type_url.rfind('/') {
+            Some(slash) => &self.type_url[slash + 1..],
+            None => &self.type_url,
+        }
+    }
+}
+
+/// An error unpacking an [`Any`].
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum AnyError {
+    /// Indicates that the type URL of the `Any` does not name the requested message type.
+    TypeMismatch {
+        /// The fully qualified name of the requested message type.
+        expected: String,
+        /// The type URL of the `Any`.
+        actual: String,
+    },
+
+    /// Indicates failure to decode the value of the `Any`.
+    Decode(DecodeError),
+
+    /// Indicates that the message type of the `Any` is not registered with the [`TypeRegistry`].
+    /// The included string is the type URL of the `Any`.
+    UnknownType(String),
+}
+
+impl fmt::Display for AnyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyError::TypeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "expected an Any containing {}, found {}",
+                    expected, actual
+                )
+            }
+            AnyError::Decode(error) => write!(f, "failed to decode Any value: {}", error),
+            AnyError::UnknownType(type_url) => {
+                write!(f, "unknown message type in Any: {}", type_url)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AnyError {}
+
 #[cfg(test)]
 mod tests {
     use std::time::{self, SystemTime, UNIX_EPOCH};
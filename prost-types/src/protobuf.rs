@@ -1,151 +1,184 @@
 /// The protocol compiler can output a FileDescriptorSet containing the .proto
 /// files it parses.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FileDescriptorSet")]
 pub struct FileDescriptorSet {
-    #[prost(message, repeated, tag="1")]
+    #[prost(message, repeated, tag="1", name="file")]
     pub file: ::prost::alloc::vec::Vec<FileDescriptorProto>,
 }
+impl ::prost::Name for FileDescriptorSet {
+    const NAME: &'static str = "FileDescriptorSet";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Describes a complete .proto file.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FileDescriptorProto")]
 pub struct FileDescriptorProto {
     /// file name, relative to root of source tree
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
     /// e.g. "foo", "foo.bar", etc.
-    #[prost(string, optional, tag="2")]
+    #[prost(string, optional, tag="2", name="package")]
     pub package: ::core::option::Option<::prost::alloc::string::String>,
     /// Names of files imported by this file.
-    #[prost(string, repeated, tag="3")]
+    #[prost(string, repeated, tag="3", name="dependency")]
     pub dependency: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     /// Indexes of the public imported files in the dependency list above.
-    #[prost(int32, repeated, packed="false", tag="10")]
+    #[prost(int32, repeated, packed="false", tag="10", name="public_dependency")]
     pub public_dependency: ::prost::alloc::vec::Vec<i32>,
     /// Indexes of the weak imported files in the dependency list.
     /// For Google-internal migration only. Do not use.
-    #[prost(int32, repeated, packed="false", tag="11")]
+    #[prost(int32, repeated, packed="false", tag="11", name="weak_dependency")]
     pub weak_dependency: ::prost::alloc::vec::Vec<i32>,
     /// All top-level definitions in this file.
-    #[prost(message, repeated, tag="4")]
+    #[prost(message, repeated, tag="4", name="message_type")]
     pub message_type: ::prost::alloc::vec::Vec<DescriptorProto>,
-    #[prost(message, repeated, tag="5")]
+    #[prost(message, repeated, tag="5", name="enum_type")]
     pub enum_type: ::prost::alloc::vec::Vec<EnumDescriptorProto>,
-    #[prost(message, repeated, tag="6")]
+    #[prost(message, repeated, tag="6", name="service")]
     pub service: ::prost::alloc::vec::Vec<ServiceDescriptorProto>,
-    #[prost(message, repeated, tag="7")]
+    #[prost(message, repeated, tag="7", name="extension")]
     pub extension: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, optional, tag="8")]
+    #[prost(message, optional, tag="8", name="options")]
     pub options: ::core::option::Option<FileOptions>,
     /// This field contains optional information about the original source code.
     /// You may safely remove this entire field without harming runtime
     /// functionality of the descriptors -- the information is needed only by
     /// development tools.
-    #[prost(message, optional, tag="9")]
+    #[prost(message, optional, tag="9", name="source_code_info")]
     pub source_code_info: ::core::option::Option<SourceCodeInfo>,
     /// The syntax of the proto file.
     /// The supported values are "proto2" and "proto3".
-    #[prost(string, optional, tag="12")]
+    #[prost(string, optional, tag="12", name="syntax")]
     pub syntax: ::core::option::Option<::prost::alloc::string::String>,
 }
+impl ::prost::Name for FileDescriptorProto {
+    const NAME: &'static str = "FileDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Describes a message type.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.DescriptorProto")]
 pub struct DescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="field")]
     pub field: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, repeated, tag="6")]
+    #[prost(message, repeated, tag="6", name="extension")]
     pub extension: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, repeated, tag="3")]
+    #[prost(message, repeated, tag="3", name="nested_type")]
     pub nested_type: ::prost::alloc::vec::Vec<DescriptorProto>,
-    #[prost(message, repeated, tag="4")]
+    #[prost(message, repeated, tag="4", name="enum_type")]
     pub enum_type: ::prost::alloc::vec::Vec<EnumDescriptorProto>,
-    #[prost(message, repeated, tag="5")]
+    #[prost(message, repeated, tag="5", name="extension_range")]
     pub extension_range: ::prost::alloc::vec::Vec<descriptor_proto::ExtensionRange>,
-    #[prost(message, repeated, tag="8")]
+    #[prost(message, repeated, tag="8", name="oneof_decl")]
     pub oneof_decl: ::prost::alloc::vec::Vec<OneofDescriptorProto>,
-    #[prost(message, optional, tag="7")]
+    #[prost(message, optional, tag="7", name="options")]
     pub options: ::core::option::Option<MessageOptions>,
-    #[prost(message, repeated, tag="9")]
+    #[prost(message, repeated, tag="9", name="reserved_range")]
     pub reserved_range: ::prost::alloc::vec::Vec<descriptor_proto::ReservedRange>,
     /// Reserved field names, which may not be used by fields in the same message.
     /// A given name may only be reserved once.
-    #[prost(string, repeated, tag="10")]
+    #[prost(string, repeated, tag="10", name="reserved_name")]
     pub reserved_name: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
+impl ::prost::Name for DescriptorProto {
+    const NAME: &'static str = "DescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `DescriptorProto`.
 pub mod descriptor_proto {
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.DescriptorProto.ExtensionRange")]
     pub struct ExtensionRange {
         /// Inclusive.
-        #[prost(int32, optional, tag="1")]
+        #[prost(int32, optional, tag="1", name="start")]
         pub start: ::core::option::Option<i32>,
         /// Exclusive.
-        #[prost(int32, optional, tag="2")]
+        #[prost(int32, optional, tag="2", name="end")]
         pub end: ::core::option::Option<i32>,
-        #[prost(message, optional, tag="3")]
+        #[prost(message, optional, tag="3", name="options")]
         pub options: ::core::option::Option<super::ExtensionRangeOptions>,
     }
+    impl ::prost::Name for ExtensionRange {
+        const NAME: &'static str = "DescriptorProto.ExtensionRange";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
     /// Range of reserved tag numbers. Reserved tag numbers may not be used by
     /// fields or extension ranges in the same message. Reserved ranges may
     /// not overlap.
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.DescriptorProto.ReservedRange")]
     pub struct ReservedRange {
         /// Inclusive.
-        #[prost(int32, optional, tag="1")]
+        #[prost(int32, optional, tag="1", name="start")]
         pub start: ::core::option::Option<i32>,
         /// Exclusive.
-        #[prost(int32, optional, tag="2")]
+        #[prost(int32, optional, tag="2", name="end")]
         pub end: ::core::option::Option<i32>,
     }
+    impl ::prost::Name for ReservedRange {
+        const NAME: &'static str = "DescriptorProto.ReservedRange";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.ExtensionRangeOptions")]
 pub struct ExtensionRangeOptions {
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for ExtensionRangeOptions {
+    const NAME: &'static str = "ExtensionRangeOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
 /// Describes a field within a message.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FieldDescriptorProto")]
 pub struct FieldDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(int32, optional, tag="3")]
+    #[prost(int32, optional, tag="3", name="number")]
     pub number: ::core::option::Option<i32>,
-    #[prost(enumeration="field_descriptor_proto::Label", optional, tag="4")]
+    #[prost(enumeration="field_descriptor_proto::Label", optional, tag="4", name="label")]
     pub label: ::core::option::Option<i32>,
     /// If type_name is set, this need not be set.  If both this and type_name
     /// are set, this must be one of TYPE_ENUM, TYPE_MESSAGE or TYPE_GROUP.
-    #[prost(enumeration="field_descriptor_proto::Type", optional, tag="5")]
+    #[prost(enumeration="field_descriptor_proto::Type", optional, tag="5", name="type")]
     pub r#type: ::core::option::Option<i32>,
     /// For message and enum types, this is the name of the type.  If the name
     /// starts with a '.', it is fully-qualified.  Otherwise, C++-like scoping
     /// rules are used to find the type (i.e. first the nested types within this
     /// message are searched, then within the parent, on up to the root
     /// namespace).
-    #[prost(string, optional, tag="6")]
+    #[prost(string, optional, tag="6", name="type_name")]
     pub type_name: ::core::option::Option<::prost::alloc::string::String>,
     /// For extensions, this is the name of the type being extended.  It is
     /// resolved in the same manner as type_name.
-    #[prost(string, optional, tag="2")]
+    #[prost(string, optional, tag="2", name="extendee")]
     pub extendee: ::core::option::Option<::prost::alloc::string::String>,
     /// For numeric types, contains the original text representation of the value.
     /// For booleans, "true" or "false".
     /// For strings, contains the default text contents (not escaped in any way).
     /// For bytes, contains the C escaped value.  All bytes >= 128 are escaped.
     /// TODO(kenton):  Base-64 encode?
-    #[prost(string, optional, tag="7")]
+    #[prost(string, optional, tag="7", name="default_value")]
     pub default_value: ::core::option::Option<::prost::alloc::string::String>,
     /// If set, gives the index of a oneof in the containing type's oneof_decl
     /// list.  This field is a member of that oneof.
-    #[prost(int32, optional, tag="9")]
+    #[prost(int32, optional, tag="9", name="oneof_index")]
     pub oneof_index: ::core::option::Option<i32>,
     /// JSON name of this field. The value is set by protocol compiler. If the
     /// user has set a "json_name" option on this field, that option's value
     /// will be used. Otherwise, it's deduced from the field's name by converting
     /// it to camelCase.
-    #[prost(string, optional, tag="10")]
+    #[prost(string, optional, tag="10", name="json_name")]
     pub json_name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="8")]
+    #[prost(message, optional, tag="8", name="options")]
     pub options: ::core::option::Option<FieldOptions>,
     /// If true, this is a proto3 "optional". When a proto3 field is optional, it
     /// tracks presence regardless of field type.
@@ -168,45 +201,68 @@ pub struct FieldDescriptorProto {
     ///
     /// Proto2 optional fields do not set this flag, because they already indicate
     /// optional with `LABEL_OPTIONAL`.
-    #[prost(bool, optional, tag="17")]
+    #[prost(bool, optional, tag="17", name="proto3_optional")]
     pub proto3_optional: ::core::option::Option<bool>,
 }
+impl ::prost::Name for FieldDescriptorProto {
+    const NAME: &'static str = "FieldDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `FieldDescriptorProto`.
 pub mod field_descriptor_proto {
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.FieldDescriptorProto.Type")]
     pub enum Type {
         /// 0 is reserved for errors.
         /// Order is weird for historical reasons.
+        #[prost(name="TYPE_DOUBLE")]
         Double = 1,
+        #[prost(name="TYPE_FLOAT")]
         Float = 2,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT64 if
         /// negative values are likely.
+        #[prost(name="TYPE_INT64")]
         Int64 = 3,
+        #[prost(name="TYPE_UINT64")]
         Uint64 = 4,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT32 if
         /// negative values are likely.
+        #[prost(name="TYPE_INT32")]
         Int32 = 5,
+        #[prost(name="TYPE_FIXED64")]
         Fixed64 = 6,
+        #[prost(name="TYPE_FIXED32")]
         Fixed32 = 7,
+        #[prost(name="TYPE_BOOL")]
         Bool = 8,
+        #[prost(name="TYPE_STRING")]
         String = 9,
         /// Tag-delimited aggregate.
         /// Group type is deprecated and not supported in proto3. However, Proto3
         /// implementations should still be able to parse the group wire format and
         /// treat group fields as unknown fields.
+        #[prost(name="TYPE_GROUP")]
         Group = 10,
         /// Length-delimited aggregate.
+        #[prost(name="TYPE_MESSAGE")]
         Message = 11,
         /// New in version 2.
+        #[prost(name="TYPE_BYTES")]
         Bytes = 12,
+        #[prost(name="TYPE_UINT32")]
         Uint32 = 13,
+        #[prost(name="TYPE_ENUM")]
         Enum = 14,
+        #[prost(name="TYPE_SFIXED32")]
         Sfixed32 = 15,
+        #[prost(name="TYPE_SFIXED64")]
         Sfixed64 = 16,
         /// Uses ZigZag encoding.
+        #[prost(name="TYPE_SINT32")]
         Sint32 = 17,
         /// Uses ZigZag encoding.
+        #[prost(name="TYPE_SINT64")]
         Sint64 = 18,
     }
     impl Type {
@@ -239,10 +295,14 @@ pub mod field_descriptor_proto {
     }
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.FieldDescriptorProto.Label")]
     pub enum Label {
         /// 0 is reserved for errors
+        #[prost(name="LABEL_OPTIONAL")]
         Optional = 1,
+        #[prost(name="LABEL_REQUIRED")]
         Required = 2,
+        #[prost(name="LABEL_REPEATED")]
         Repeated = 3,
     }
     impl Label {
@@ -260,32 +320,42 @@ pub mod field_descriptor_proto {
     }
 }
 /// Describes a oneof.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.OneofDescriptorProto")]
 pub struct OneofDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="2")]
+    #[prost(message, optional, tag="2", name="options")]
     pub options: ::core::option::Option<OneofOptions>,
 }
+impl ::prost::Name for OneofDescriptorProto {
+    const NAME: &'static str = "OneofDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Describes an enum type.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.EnumDescriptorProto")]
 pub struct EnumDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="value")]
     pub value: ::prost::alloc::vec::Vec<EnumValueDescriptorProto>,
-    #[prost(message, optional, tag="3")]
+    #[prost(message, optional, tag="3", name="options")]
     pub options: ::core::option::Option<EnumOptions>,
     /// Range of reserved numeric values. Reserved numeric values may not be used
     /// by enum values in the same enum declaration. Reserved ranges may not
     /// overlap.
-    #[prost(message, repeated, tag="4")]
+    #[prost(message, repeated, tag="4", name="reserved_range")]
     pub reserved_range: ::prost::alloc::vec::Vec<enum_descriptor_proto::EnumReservedRange>,
     /// Reserved enum value names, which may not be reused. A given name may only
     /// be reserved once.
-    #[prost(string, repeated, tag="5")]
+    #[prost(string, repeated, tag="5", name="reserved_name")]
     pub reserved_name: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
+impl ::prost::Name for EnumDescriptorProto {
+    const NAME: &'static str = "EnumDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `EnumDescriptorProto`.
 pub mod enum_descriptor_proto {
     /// Range of reserved numeric values. Reserved values may not be used by
@@ -294,56 +364,76 @@ pub mod enum_descriptor_proto {
     /// Note that this is distinct from DescriptorProto.ReservedRange in that it
     /// is inclusive such that it can appropriately represent the entire int32
     /// domain.
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.EnumDescriptorProto.EnumReservedRange")]
     pub struct EnumReservedRange {
         /// Inclusive.
-        #[prost(int32, optional, tag="1")]
+        #[prost(int32, optional, tag="1", name="start")]
         pub start: ::core::option::Option<i32>,
         /// Inclusive.
-        #[prost(int32, optional, tag="2")]
+        #[prost(int32, optional, tag="2", name="end")]
         pub end: ::core::option::Option<i32>,
     }
+    impl ::prost::Name for EnumReservedRange {
+        const NAME: &'static str = "EnumDescriptorProto.EnumReservedRange";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
 }
 /// Describes a value within an enum.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.EnumValueDescriptorProto")]
 pub struct EnumValueDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(int32, optional, tag="2")]
+    #[prost(int32, optional, tag="2", name="number")]
     pub number: ::core::option::Option<i32>,
-    #[prost(message, optional, tag="3")]
+    #[prost(message, optional, tag="3", name="options")]
     pub options: ::core::option::Option<EnumValueOptions>,
 }
+impl ::prost::Name for EnumValueDescriptorProto {
+    const NAME: &'static str = "EnumValueDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Describes a service.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.ServiceDescriptorProto")]
 pub struct ServiceDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="method")]
     pub method: ::prost::alloc::vec::Vec<MethodDescriptorProto>,
-    #[prost(message, optional, tag="3")]
+    #[prost(message, optional, tag="3", name="options")]
     pub options: ::core::option::Option<ServiceOptions>,
 }
+impl ::prost::Name for ServiceDescriptorProto {
+    const NAME: &'static str = "ServiceDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Describes a method of a service.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.MethodDescriptorProto")]
 pub struct MethodDescriptorProto {
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="name")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
     /// Input and output type names.  These are resolved in the same way as
     /// FieldDescriptorProto.type_name, but must refer to a message type.
-    #[prost(string, optional, tag="2")]
+    #[prost(string, optional, tag="2", name="input_type")]
     pub input_type: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(string, optional, tag="3")]
+    #[prost(string, optional, tag="3", name="output_type")]
     pub output_type: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="4")]
+    #[prost(message, optional, tag="4", name="options")]
     pub options: ::core::option::Option<MethodOptions>,
     /// Identifies if client streams multiple client messages
-    #[prost(bool, optional, tag="5", default="false")]
+    #[prost(bool, optional, tag="5", default="false", name="client_streaming")]
     pub client_streaming: ::core::option::Option<bool>,
     /// Identifies if server streams multiple server messages
-    #[prost(bool, optional, tag="6", default="false")]
+    #[prost(bool, optional, tag="6", default="false", name="server_streaming")]
     pub server_streaming: ::core::option::Option<bool>,
 }
+impl ::prost::Name for MethodDescriptorProto {
+    const NAME: &'static str = "MethodDescriptorProto";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 // ===================================================================
 // Options
 
@@ -377,20 +467,21 @@ pub struct MethodDescriptorProto {
 //   If this turns out to be popular, a web service will be set up
 //   to automatically assign option numbers.
 
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FileOptions")]
 pub struct FileOptions {
     /// Sets the Java package where classes generated from this .proto will be
     /// placed.  By default, the proto package is used, but this is often
     /// inappropriate because proto packages do not normally start with backwards
     /// domain names.
-    #[prost(string, optional, tag="1")]
+    #[prost(string, optional, tag="1", name="java_package")]
     pub java_package: ::core::option::Option<::prost::alloc::string::String>,
     /// Controls the name of the wrapper Java class generated for the .proto file.
     /// That class will always contain the .proto file's getDescriptor() method as
     /// well as any top-level extensions defined in the .proto file.
     /// If java_multiple_files is disabled, then all the other classes from the
     /// .proto file will be nested inside the single wrapper outer class.
-    #[prost(string, optional, tag="8")]
+    #[prost(string, optional, tag="8", name="java_outer_classname")]
     pub java_outer_classname: ::core::option::Option<::prost::alloc::string::String>,
     /// If enabled, then the Java code generator will generate a separate .java
     /// file for each top-level message, enum, and service defined in the .proto
@@ -398,11 +489,11 @@ pub struct FileOptions {
     /// named by java_outer_classname.  However, the wrapper class will still be
     /// generated to contain the file's getDescriptor() method as well as any
     /// top-level extensions defined in the file.
-    #[prost(bool, optional, tag="10", default="false")]
+    #[prost(bool, optional, tag="10", default="false", name="java_multiple_files")]
     pub java_multiple_files: ::core::option::Option<bool>,
     /// This option does nothing.
     #[deprecated]
-    #[prost(bool, optional, tag="20")]
+    #[prost(bool, optional, tag="20", name="java_generate_equals_and_hash")]
     pub java_generate_equals_and_hash: ::core::option::Option<bool>,
     /// If set true, then the Java2 code generator will generate code that
     /// throws an exception whenever an attempt is made to assign a non-UTF-8
@@ -410,9 +501,9 @@ pub struct FileOptions {
     /// Message reflection will do the same.
     /// However, an extension field still accepts non-UTF-8 byte sequences.
     /// This option has no effect on when used with the lite runtime.
-    #[prost(bool, optional, tag="27", default="false")]
+    #[prost(bool, optional, tag="27", default="false", name="java_string_check_utf8")]
     pub java_string_check_utf8: ::core::option::Option<bool>,
-    #[prost(enumeration="file_options::OptimizeMode", optional, tag="9", default="Speed")]
+    #[prost(enumeration="file_options::OptimizeMode", optional, tag="9", default="Speed", name="optimize_for")]
     pub optimize_for: ::core::option::Option<i32>,
     /// Sets the Go package where structs generated from this .proto will be
     /// placed. If omitted, the Go package will be derived from the following:
@@ -420,7 +511,7 @@ pub struct FileOptions {
     /// * The basename of the package import path, if provided.
     /// * Otherwise, the package statement in the .proto file, if present.
     /// * Otherwise, the basename of the .proto file, without extension.
-    #[prost(string, optional, tag="11")]
+    #[prost(string, optional, tag="11", name="go_package")]
     pub go_package: ::core::option::Option<::prost::alloc::string::String>,
     /// Should generic services be generated in each language?  "Generic" services
     /// are not specific to any particular RPC system.  They are generated by the
@@ -432,74 +523,84 @@ pub struct FileOptions {
     /// that generate code specific to your particular RPC system.  Therefore,
     /// these default to false.  Old code which depends on generic services should
     /// explicitly set them to true.
-    #[prost(bool, optional, tag="16", default="false")]
+    #[prost(bool, optional, tag="16", default="false", name="cc_generic_services")]
     pub cc_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="17", default="false")]
+    #[prost(bool, optional, tag="17", default="false", name="java_generic_services")]
     pub java_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="18", default="false")]
+    #[prost(bool, optional, tag="18", default="false", name="py_generic_services")]
     pub py_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="42", default="false")]
+    #[prost(bool, optional, tag="42", default="false", name="php_generic_services")]
     pub php_generic_services: ::core::option::Option<bool>,
     /// Is this file deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for everything in the file, or it will be completely ignored; in the very
     /// least, this is a formalization for deprecating files.
-    #[prost(bool, optional, tag="23", default="false")]
+    #[prost(bool, optional, tag="23", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// Enables the use of arenas for the proto messages in this file. This applies
     /// only to generated classes for C++.
-    #[prost(bool, optional, tag="31", default="true")]
+    #[prost(bool, optional, tag="31", default="true", name="cc_enable_arenas")]
     pub cc_enable_arenas: ::core::option::Option<bool>,
     /// Sets the objective c class prefix which is prepended to all objective c
     /// generated classes from this .proto. There is no default.
-    #[prost(string, optional, tag="36")]
+    #[prost(string, optional, tag="36", name="objc_class_prefix")]
     pub objc_class_prefix: ::core::option::Option<::prost::alloc::string::String>,
     /// Namespace for generated classes; defaults to the package.
-    #[prost(string, optional, tag="37")]
+    #[prost(string, optional, tag="37", name="csharp_namespace")]
     pub csharp_namespace: ::core::option::Option<::prost::alloc::string::String>,
     /// By default Swift generators will take the proto package and CamelCase it
     /// replacing '.' with underscore and use that to prefix the types/symbols
     /// defined. When this options is provided, they will use this value instead
     /// to prefix the types/symbols defined.
-    #[prost(string, optional, tag="39")]
+    #[prost(string, optional, tag="39", name="swift_prefix")]
     pub swift_prefix: ::core::option::Option<::prost::alloc::string::String>,
     /// Sets the php class prefix which is prepended to all php generated classes
     /// from this .proto. Default is empty.
-    #[prost(string, optional, tag="40")]
+    #[prost(string, optional, tag="40", name="php_class_prefix")]
     pub php_class_prefix: ::core::option::Option<::prost::alloc::string::String>,
     /// Use this option to change the namespace of php generated classes. Default
     /// is empty. When this option is empty, the package name will be used for
     /// determining the namespace.
-    #[prost(string, optional, tag="41")]
+    #[prost(string, optional, tag="41", name="php_namespace")]
     pub php_namespace: ::core::option::Option<::prost::alloc::string::String>,
     /// Use this option to change the namespace of php generated metadata classes.
     /// Default is empty. When this option is empty, the proto file name will be
     /// used for determining the namespace.
-    #[prost(string, optional, tag="44")]
+    #[prost(string, optional, tag="44", name="php_metadata_namespace")]
     pub php_metadata_namespace: ::core::option::Option<::prost::alloc::string::String>,
     /// Use this option to change the package of ruby generated classes. Default
     /// is empty. When this option is not set, the package name will be used for
     /// determining the ruby package.
-    #[prost(string, optional, tag="45")]
+    #[prost(string, optional, tag="45", name="ruby_package")]
     pub ruby_package: ::core::option::Option<::prost::alloc::string::String>,
     /// The parser stores options it doesn't recognize here.
     /// See the documentation for the "Options" section above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for FileOptions {
+    const NAME: &'static str = "FileOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
 /// Nested message and enum types in `FileOptions`.
 pub mod file_options {
     /// Generated classes can be optimized for speed or code size.
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.FileOptions.OptimizeMode")]
     pub enum OptimizeMode {
         /// Generate complete code for parsing, serialization,
+        #[prost(name="SPEED")]
         Speed = 1,
         /// etc.
         ///
         /// Use ReflectionOps to implement these methods.
+        #[prost(name="CODE_SIZE")]
         CodeSize = 2,
         /// Generate code using MessageLite and the lite runtime.
+        #[prost(name="LITE_RUNTIME")]
         LiteRuntime = 3,
     }
     impl OptimizeMode {
@@ -516,7 +617,8 @@ pub mod file_options {
         }
     }
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.MessageOptions")]
 pub struct MessageOptions {
     /// Set true to use the old proto1 MessageSet wire format for extensions.
     /// This is provided for backwards-compatibility with the MessageSet wire
@@ -536,18 +638,18 @@ pub struct MessageOptions {
     ///
     /// Because this is an option, the above two restrictions are not enforced by
     /// the protocol compiler.
-    #[prost(bool, optional, tag="1", default="false")]
+    #[prost(bool, optional, tag="1", default="false", name="message_set_wire_format")]
     pub message_set_wire_format: ::core::option::Option<bool>,
     /// Disables the generation of the standard "descriptor()" accessor, which can
     /// conflict with a field of the same name.  This is meant to make migration
     /// from proto1 easier; new code should avoid fields named "descriptor".
-    #[prost(bool, optional, tag="2", default="false")]
+    #[prost(bool, optional, tag="2", default="false", name="no_standard_descriptor_accessor")]
     pub no_standard_descriptor_accessor: ::core::option::Option<bool>,
     /// Is this message deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for the message, or it will be completely ignored; in the very least,
     /// this is a formalization for deprecating messages.
-    #[prost(bool, optional, tag="3", default="false")]
+    #[prost(bool, optional, tag="3", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// Whether the message is an automatically generated map entry type for the
     /// maps field.
@@ -570,26 +672,33 @@ pub struct MessageOptions {
     /// NOTE: Do not set the option in .proto files. Always use the maps syntax
     /// instead. The option should only be implicitly set by the proto compiler
     /// parser.
-    #[prost(bool, optional, tag="7")]
+    #[prost(bool, optional, tag="7", name="map_entry")]
     pub map_entry: ::core::option::Option<bool>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+impl ::prost::Name for MessageOptions {
+    const NAME: &'static str = "MessageOptions";
+    const PACKAGE: &'static str = "google.protobuf";
+}
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FieldOptions")]
 pub struct FieldOptions {
     /// The ctype option instructs the C++ code generator to use a different
     /// representation of the field than it normally would.  See the specific
     /// options below.  This option is not yet implemented in the open source
     /// release -- sorry, we'll try to include it in a future version!
-    #[prost(enumeration="field_options::CType", optional, tag="1", default="String")]
+    #[prost(enumeration="field_options::CType", optional, tag="1", default="String", name="ctype")]
     pub ctype: ::core::option::Option<i32>,
     /// The packed option can be enabled for repeated primitive fields to enable
     /// a more efficient representation on the wire. Rather than repeatedly
     /// writing the tag and type for each element, the entire array is encoded as
     /// a single length-delimited blob. In proto3, only explicit setting it to
     /// false will avoid using packed encoding.
-    #[prost(bool, optional, tag="2")]
+    #[prost(bool, optional, tag="2", name="packed")]
     pub packed: ::core::option::Option<bool>,
     /// The jstype option determines the JavaScript type used for values of the
     /// field.  The option is permitted only for 64 bit integral and fixed types
@@ -602,7 +711,7 @@ pub struct FieldOptions {
     ///
     /// This option is an enum to permit additional types to be added, e.g.
     /// goog.math.Integer.
-    #[prost(enumeration="field_options::JsType", optional, tag="6", default="JsNormal")]
+    #[prost(enumeration="field_options::JsType", optional, tag="6", default="JsNormal", name="jstype")]
     pub jstype: ::core::option::Option<i32>,
     /// Should this field be parsed lazily?  Lazy applies only to message-type
     /// fields.  It means that when the outer message is initially parsed, the
@@ -631,29 +740,39 @@ pub struct FieldOptions {
     /// implementation must either *always* check its required fields, or *never*
     /// check its required fields, regardless of whether or not the message has
     /// been parsed.
-    #[prost(bool, optional, tag="5", default="false")]
+    #[prost(bool, optional, tag="5", default="false", name="lazy")]
     pub lazy: ::core::option::Option<bool>,
     /// Is this field deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for accessors, or it will be completely ignored; in the very least, this
     /// is a formalization for deprecating fields.
-    #[prost(bool, optional, tag="3", default="false")]
+    #[prost(bool, optional, tag="3", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// For Google-internal migration only. Do not use.
-    #[prost(bool, optional, tag="10", default="false")]
+    #[prost(bool, optional, tag="10", default="false", name="weak")]
     pub weak: ::core::option::Option<bool>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for FieldOptions {
+    const NAME: &'static str = "FieldOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
 /// Nested message and enum types in `FieldOptions`.
 pub mod field_options {
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.FieldOptions.CType")]
     pub enum CType {
         /// Default mode.
+        #[prost(name="STRING")]
         String = 0,
+        #[prost(name="CORD")]
         Cord = 1,
+        #[prost(name="STRING_PIECE")]
         StringPiece = 2,
     }
     impl CType {
@@ -671,12 +790,16 @@ pub mod field_options {
     }
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.FieldOptions.JSType")]
     pub enum JsType {
         /// Use the default type.
+        #[prost(name="JS_NORMAL")]
         JsNormal = 0,
         /// Use JavaScript strings.
+        #[prost(name="JS_STRING")]
         JsString = 1,
         /// Use JavaScript numbers.
+        #[prost(name="JS_NUMBER")]
         JsNumber = 2,
     }
     impl JsType {
@@ -693,41 +816,63 @@ pub mod field_options {
         }
     }
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.OneofOptions")]
 pub struct OneofOptions {
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for OneofOptions {
+    const NAME: &'static str = "OneofOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.EnumOptions")]
 pub struct EnumOptions {
     /// Set this option to true to allow mapping different tag names to the same
     /// value.
-    #[prost(bool, optional, tag="2")]
+    #[prost(bool, optional, tag="2", name="allow_alias")]
     pub allow_alias: ::core::option::Option<bool>,
     /// Is this enum deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for the enum, or it will be completely ignored; in the very least, this
     /// is a formalization for deprecating enums.
-    #[prost(bool, optional, tag="3", default="false")]
+    #[prost(bool, optional, tag="3", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+impl ::prost::Name for EnumOptions {
+    const NAME: &'static str = "EnumOptions";
+    const PACKAGE: &'static str = "google.protobuf";
+}
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.EnumValueOptions")]
 pub struct EnumValueOptions {
     /// Is this enum value deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for the enum value, or it will be completely ignored; in the very least,
     /// this is a formalization for deprecating enum values.
-    #[prost(bool, optional, tag="1", default="false")]
+    #[prost(bool, optional, tag="1", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for EnumValueOptions {
+    const NAME: &'static str = "EnumValueOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.ServiceOptions")]
 pub struct ServiceOptions {
     // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
     // framework.  We apologize for hoarding these numbers to ourselves, but
@@ -738,13 +883,20 @@ pub struct ServiceOptions {
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for the service, or it will be completely ignored; in the very least,
     /// this is a formalization for deprecating services.
-    #[prost(bool, optional, tag="33", default="false")]
+    #[prost(bool, optional, tag="33", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for ServiceOptions {
+    const NAME: &'static str = "ServiceOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.MethodOptions")]
 pub struct MethodOptions {
     // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
     // framework.  We apologize for hoarding these numbers to ourselves, but
@@ -755,13 +907,19 @@ pub struct MethodOptions {
     /// Depending on the target platform, this can emit Deprecated annotations
     /// for the method, or it will be completely ignored; in the very least,
     /// this is a formalization for deprecating methods.
-    #[prost(bool, optional, tag="33", default="false")]
+    #[prost(bool, optional, tag="33", default="false", name="deprecated")]
     pub deprecated: ::core::option::Option<bool>,
-    #[prost(enumeration="method_options::IdempotencyLevel", optional, tag="34", default="IdempotencyUnknown")]
+    #[prost(enumeration="method_options::IdempotencyLevel", optional, tag="34", default="IdempotencyUnknown", name="idempotency_level")]
     pub idempotency_level: ::core::option::Option<i32>,
     /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
+    #[prost(message, repeated, tag="999", name="uninterpreted_option")]
     pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
+    #[prost(extensions="1000..536870912")]
+    pub _extensions: ::prost::ExtensionSet,
+}
+impl ::prost::Name for MethodOptions {
+    const NAME: &'static str = "MethodOptions";
+    const PACKAGE: &'static str = "google.protobuf";
 }
 /// Nested message and enum types in `MethodOptions`.
 pub mod method_options {
@@ -770,11 +928,15 @@ pub mod method_options {
     /// methods, and PUT verb for idempotent methods instead of the default POST.
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.MethodOptions.IdempotencyLevel")]
     pub enum IdempotencyLevel {
+        #[prost(name="IDEMPOTENCY_UNKNOWN")]
         IdempotencyUnknown = 0,
         /// implies idempotent
+        #[prost(name="NO_SIDE_EFFECTS")]
         NoSideEffects = 1,
         /// idempotent, but may have side effects
+        #[prost(name="IDEMPOTENT")]
         Idempotent = 2,
     }
     impl IdempotencyLevel {
@@ -797,25 +959,30 @@ pub mod method_options {
 /// options protos in descriptor objects (e.g. returned by Descriptor::options(),
 /// or produced by Descriptor::CopyTo()) will never have UninterpretedOptions
 /// in them.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.UninterpretedOption")]
 pub struct UninterpretedOption {
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="name")]
     pub name: ::prost::alloc::vec::Vec<uninterpreted_option::NamePart>,
     /// The value of the uninterpreted option, in whatever type the tokenizer
     /// identified it as during parsing. Exactly one of these should be set.
-    #[prost(string, optional, tag="3")]
+    #[prost(string, optional, tag="3", name="identifier_value")]
     pub identifier_value: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(uint64, optional, tag="4")]
+    #[prost(uint64, optional, tag="4", name="positive_int_value")]
     pub positive_int_value: ::core::option::Option<u64>,
-    #[prost(int64, optional, tag="5")]
+    #[prost(int64, optional, tag="5", name="negative_int_value")]
     pub negative_int_value: ::core::option::Option<i64>,
-    #[prost(double, optional, tag="6")]
+    #[prost(double, optional, tag="6", name="double_value")]
     pub double_value: ::core::option::Option<f64>,
-    #[prost(bytes="vec", optional, tag="7")]
+    #[prost(bytes="vec", optional, tag="7", name="string_value")]
     pub string_value: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
-    #[prost(string, optional, tag="8")]
+    #[prost(string, optional, tag="8", name="aggregate_value")]
     pub aggregate_value: ::core::option::Option<::prost::alloc::string::String>,
 }
+impl ::prost::Name for UninterpretedOption {
+    const NAME: &'static str = "UninterpretedOption";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `UninterpretedOption`.
 pub mod uninterpreted_option {
     /// The name of the uninterpreted option.  Each string represents a segment in
@@ -823,20 +990,26 @@ pub mod uninterpreted_option {
     /// extension (denoted with parentheses in options specs in .proto files).
     /// E.g.,{ \["foo", false\], \["bar.baz", true\], \["qux", false\] } represents
     /// "foo.(bar.baz).qux".
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.UninterpretedOption.NamePart")]
     pub struct NamePart {
-        #[prost(string, required, tag="1")]
+        #[prost(string, required, tag="1", name="name_part")]
         pub name_part: ::prost::alloc::string::String,
-        #[prost(bool, required, tag="2")]
+        #[prost(bool, required, tag="2", name="is_extension")]
         pub is_extension: bool,
     }
+    impl ::prost::Name for NamePart {
+        const NAME: &'static str = "UninterpretedOption.NamePart";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
 }
 // ===================================================================
 // Optional source code info
 
 /// Encapsulates information about the original source file from which a
 /// FileDescriptorProto was generated.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.SourceCodeInfo")]
 pub struct SourceCodeInfo {
     /// A Location identifies a piece of source code in a .proto file which
     /// corresponds to a particular definition.  This information is intended
@@ -882,12 +1055,17 @@ pub struct SourceCodeInfo {
     /// * Code which tries to interpret locations should probably be designed to
     ///   ignore those that it doesn't understand, as more types of locations could
     ///   be recorded in the future.
-    #[prost(message, repeated, tag="1")]
+    #[prost(message, repeated, tag="1", name="location")]
     pub location: ::prost::alloc::vec::Vec<source_code_info::Location>,
 }
+impl ::prost::Name for SourceCodeInfo {
+    const NAME: &'static str = "SourceCodeInfo";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `SourceCodeInfo`.
 pub mod source_code_info {
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.SourceCodeInfo.Location")]
     pub struct Location {
         /// Identifies which part of the FileDescriptorProto was defined at this
         /// location.
@@ -912,14 +1090,14 @@ pub mod source_code_info {
         /// \[ 4, 3, 2, 7 \]
         /// this path refers to the whole field declaration (from the beginning
         /// of the label to the terminating semicolon).
-        #[prost(int32, repeated, tag="1")]
+        #[prost(int32, repeated, tag="1", name="path")]
         pub path: ::prost::alloc::vec::Vec<i32>,
         /// Always has exactly three or four elements: start line, start column,
         /// end line (optional, otherwise assumed same as start line), end column.
         /// These are packed into a single field for efficiency.  Note that line
         /// and column numbers are zero-based -- typically you will want to add
         /// 1 to each before displaying to a user.
-        #[prost(int32, repeated, tag="2")]
+        #[prost(int32, repeated, tag="2", name="span")]
         pub span: ::prost::alloc::vec::Vec<i32>,
         /// If this SourceCodeInfo represents a complete declaration, these are any
         /// comments appearing before and after the declaration which appear to be
@@ -968,45 +1146,59 @@ pub mod source_code_info {
         /// optional int32 grault = 6;
         ///
         /// // ignored detached comments.
-        #[prost(string, optional, tag="3")]
+        #[prost(string, optional, tag="3", name="leading_comments")]
         pub leading_comments: ::core::option::Option<::prost::alloc::string::String>,
-        #[prost(string, optional, tag="4")]
+        #[prost(string, optional, tag="4", name="trailing_comments")]
         pub trailing_comments: ::core::option::Option<::prost::alloc::string::String>,
-        #[prost(string, repeated, tag="6")]
+        #[prost(string, repeated, tag="6", name="leading_detached_comments")]
         pub leading_detached_comments: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     }
+    impl ::prost::Name for Location {
+        const NAME: &'static str = "SourceCodeInfo.Location";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
 }
 /// Describes the relationship between generated code and its original source
 /// file. A GeneratedCodeInfo message is associated with only one generated
 /// source file, but may contain references to different source .proto files.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.GeneratedCodeInfo")]
 pub struct GeneratedCodeInfo {
     /// An Annotation connects some span of text in generated code to an element
     /// of its generating .proto file.
-    #[prost(message, repeated, tag="1")]
+    #[prost(message, repeated, tag="1", name="annotation")]
     pub annotation: ::prost::alloc::vec::Vec<generated_code_info::Annotation>,
 }
+impl ::prost::Name for GeneratedCodeInfo {
+    const NAME: &'static str = "GeneratedCodeInfo";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `GeneratedCodeInfo`.
 pub mod generated_code_info {
-    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+    #[prost(full_name="google.protobuf.GeneratedCodeInfo.Annotation")]
     pub struct Annotation {
         /// Identifies the element in the original source .proto file. This field
         /// is formatted the same as SourceCodeInfo.Location.path.
-        #[prost(int32, repeated, tag="1")]
+        #[prost(int32, repeated, tag="1", name="path")]
         pub path: ::prost::alloc::vec::Vec<i32>,
         /// Identifies the filesystem path to the original source .proto.
-        #[prost(string, optional, tag="2")]
+        #[prost(string, optional, tag="2", name="source_file")]
         pub source_file: ::core::option::Option<::prost::alloc::string::String>,
         /// Identifies the starting offset in bytes in the generated code
         /// that relates to the identified object.
-        #[prost(int32, optional, tag="3")]
+        #[prost(int32, optional, tag="3", name="begin")]
         pub begin: ::core::option::Option<i32>,
         /// Identifies the ending offset in bytes in the generated code that
         /// relates to the identified offset. The end offset should be one past
         /// the last relevant byte (so the length of the text = end - begin).
-        #[prost(int32, optional, tag="4")]
+        #[prost(int32, optional, tag="4", name="end")]
         pub end: ::core::option::Option<i32>,
     }
+    impl ::prost::Name for Annotation {
+        const NAME: &'static str = "GeneratedCodeInfo.Annotation";
+        const PACKAGE: &'static str = "google.protobuf";
+    }
 }
 /// `Any` contains an arbitrary serialized protocol buffer message along with a
 /// URL that describes the type of the serialized message.
@@ -1101,7 +1293,8 @@ pub mod generated_code_info {
 ///    "value": "1.212s"
 ///  }
 /// ```
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Any")]
 pub struct Any {
     /// A URL/resource name that uniquely identifies the type of the serialized
     /// protocol buffer message. This string must contain at least
@@ -1130,122 +1323,161 @@ pub struct Any {
     ///
     /// Schemes other than `http`, `https` (or the empty scheme) might be
     /// used with implementation specific semantics.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="type_url")]
     pub type_url: ::prost::alloc::string::String,
     /// Must be a valid serialized protocol buffer of the above specified type.
-    #[prost(bytes="vec", tag="2")]
+    #[prost(bytes="vec", tag="2", name="value")]
     pub value: ::prost::alloc::vec::Vec<u8>,
 }
+impl ::prost::Name for Any {
+    const NAME: &'static str = "Any";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// `SourceContext` represents information about the source of a
 /// protobuf element, like the file in which it is defined.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.SourceContext")]
 pub struct SourceContext {
     /// The path-qualified name of the .proto file that contained the associated
     /// protobuf element.  For example: `"google/protobuf/source_context.proto"`.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="file_name")]
     pub file_name: ::prost::alloc::string::String,
 }
+impl ::prost::Name for SourceContext {
+    const NAME: &'static str = "SourceContext";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// A protocol buffer message type.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Type")]
 pub struct Type {
     /// The fully qualified message name.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// The list of fields.
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="fields")]
     pub fields: ::prost::alloc::vec::Vec<Field>,
     /// The list of types appearing in `oneof` definitions in this type.
-    #[prost(string, repeated, tag="3")]
+    #[prost(string, repeated, tag="3", name="oneofs")]
     pub oneofs: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     /// The protocol buffer options.
-    #[prost(message, repeated, tag="4")]
+    #[prost(message, repeated, tag="4", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
     /// The source context.
-    #[prost(message, optional, tag="5")]
+    #[prost(message, optional, tag="5", name="source_context")]
     pub source_context: ::core::option::Option<SourceContext>,
     /// The source syntax.
-    #[prost(enumeration="Syntax", tag="6")]
+    #[prost(enumeration="Syntax", tag="6", name="syntax")]
     pub syntax: i32,
 }
+impl ::prost::Name for Type {
+    const NAME: &'static str = "Type";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// A single field of a message type.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Field")]
 pub struct Field {
     /// The field type.
-    #[prost(enumeration="field::Kind", tag="1")]
+    #[prost(enumeration="field::Kind", tag="1", name="kind")]
     pub kind: i32,
     /// The field cardinality.
-    #[prost(enumeration="field::Cardinality", tag="2")]
+    #[prost(enumeration="field::Cardinality", tag="2", name="cardinality")]
     pub cardinality: i32,
     /// The field number.
-    #[prost(int32, tag="3")]
+    #[prost(int32, tag="3", name="number")]
     pub number: i32,
     /// The field name.
-    #[prost(string, tag="4")]
+    #[prost(string, tag="4", name="name")]
     pub name: ::prost::alloc::string::String,
     /// The field type URL, without the scheme, for message or enumeration
     /// types. Example: `"type.googleapis.com/google.protobuf.Timestamp"`.
-    #[prost(string, tag="6")]
+    #[prost(string, tag="6", name="type_url")]
     pub type_url: ::prost::alloc::string::String,
     /// The index of the field type in `Type.oneofs`, for message or enumeration
     /// types. The first type has index 1; zero means the type is not in the list.
-    #[prost(int32, tag="7")]
+    #[prost(int32, tag="7", name="oneof_index")]
     pub oneof_index: i32,
     /// Whether to use alternative packed wire representation.
-    #[prost(bool, tag="8")]
+    #[prost(bool, tag="8", name="packed")]
     pub packed: bool,
     /// The protocol buffer options.
-    #[prost(message, repeated, tag="9")]
+    #[prost(message, repeated, tag="9", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
     /// The field JSON name.
-    #[prost(string, tag="10")]
+    #[prost(string, tag="10", name="json_name")]
     pub json_name: ::prost::alloc::string::String,
     /// The string value of the default value of this field. Proto2 syntax only.
-    #[prost(string, tag="11")]
+    #[prost(string, tag="11", name="default_value")]
     pub default_value: ::prost::alloc::string::String,
 }
+impl ::prost::Name for Field {
+    const NAME: &'static str = "Field";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `Field`.
 pub mod field {
     /// Basic field types.
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.Field.Kind")]
     pub enum Kind {
         /// Field type unknown.
+        #[prost(name="TYPE_UNKNOWN")]
         TypeUnknown = 0,
         /// Field type double.
+        #[prost(name="TYPE_DOUBLE")]
         TypeDouble = 1,
         /// Field type float.
+        #[prost(name="TYPE_FLOAT")]
         TypeFloat = 2,
         /// Field type int64.
+        #[prost(name="TYPE_INT64")]
         TypeInt64 = 3,
         /// Field type uint64.
+        #[prost(name="TYPE_UINT64")]
         TypeUint64 = 4,
         /// Field type int32.
+        #[prost(name="TYPE_INT32")]
         TypeInt32 = 5,
         /// Field type fixed64.
+        #[prost(name="TYPE_FIXED64")]
         TypeFixed64 = 6,
         /// Field type fixed32.
+        #[prost(name="TYPE_FIXED32")]
         TypeFixed32 = 7,
         /// Field type bool.
+        #[prost(name="TYPE_BOOL")]
         TypeBool = 8,
         /// Field type string.
+        #[prost(name="TYPE_STRING")]
         TypeString = 9,
         /// Field type group. Proto2 syntax only, and deprecated.
+        #[prost(name="TYPE_GROUP")]
         TypeGroup = 10,
         /// Field type message.
+        #[prost(name="TYPE_MESSAGE")]
         TypeMessage = 11,
         /// Field type bytes.
+        #[prost(name="TYPE_BYTES")]
         TypeBytes = 12,
         /// Field type uint32.
+        #[prost(name="TYPE_UINT32")]
         TypeUint32 = 13,
         /// Field type enum.
+        #[prost(name="TYPE_ENUM")]
         TypeEnum = 14,
         /// Field type sfixed32.
+        #[prost(name="TYPE_SFIXED32")]
         TypeSfixed32 = 15,
         /// Field type sfixed64.
+        #[prost(name="TYPE_SFIXED64")]
         TypeSfixed64 = 16,
         /// Field type sint32.
+        #[prost(name="TYPE_SINT32")]
         TypeSint32 = 17,
         /// Field type sint64.
+        #[prost(name="TYPE_SINT64")]
         TypeSint64 = 18,
     }
     impl Kind {
@@ -1280,14 +1512,19 @@ pub mod field {
     /// Whether a field is optional, required, or repeated.
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
     #[repr(i32)]
+    #[prost(reflect, full_name="google.protobuf.Field.Cardinality")]
     pub enum Cardinality {
         /// For fields with unknown cardinality.
+        #[prost(name="CARDINALITY_UNKNOWN")]
         Unknown = 0,
         /// For optional fields.
+        #[prost(name="CARDINALITY_OPTIONAL")]
         Optional = 1,
         /// For required fields. Proto2 syntax only.
+        #[prost(name="CARDINALITY_REQUIRED")]
         Required = 2,
         /// For repeated fields.
+        #[prost(name="CARDINALITY_REPEATED")]
         Repeated = 3,
     }
     impl Cardinality {
@@ -1306,61 +1543,79 @@ pub mod field {
     }
 }
 /// Enum type definition.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Enum")]
 pub struct Enum {
     /// Enum type name.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// Enum value definitions.
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="enumvalue")]
     pub enumvalue: ::prost::alloc::vec::Vec<EnumValue>,
     /// Protocol buffer options.
-    #[prost(message, repeated, tag="3")]
+    #[prost(message, repeated, tag="3", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
     /// The source context.
-    #[prost(message, optional, tag="4")]
+    #[prost(message, optional, tag="4", name="source_context")]
     pub source_context: ::core::option::Option<SourceContext>,
     /// The source syntax.
-    #[prost(enumeration="Syntax", tag="5")]
+    #[prost(enumeration="Syntax", tag="5", name="syntax")]
     pub syntax: i32,
 }
+impl ::prost::Name for Enum {
+    const NAME: &'static str = "Enum";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Enum value definition.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.EnumValue")]
 pub struct EnumValue {
     /// Enum value name.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// Enum value number.
-    #[prost(int32, tag="2")]
+    #[prost(int32, tag="2", name="number")]
     pub number: i32,
     /// Protocol buffer options.
-    #[prost(message, repeated, tag="3")]
+    #[prost(message, repeated, tag="3", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
 }
+impl ::prost::Name for EnumValue {
+    const NAME: &'static str = "EnumValue";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// A protocol buffer option, which can be attached to a message, field,
 /// enumeration, etc.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Option")]
 pub struct Option {
     /// The option's name. For protobuf built-in options (options defined in
     /// descriptor.proto), this is the short name. For example, `"map_entry"`.
     /// For custom options, it should be the fully-qualified name. For example,
     /// `"google.api.http"`.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// The option's value packed in an Any message. If the value is a primitive,
     /// the corresponding wrapper type defined in google/protobuf/wrappers.proto
     /// should be used. If the value is an enum, it should be stored as an int32
     /// value using the google.protobuf.Int32Value type.
-    #[prost(message, optional, tag="2")]
+    #[prost(message, optional, tag="2", name="value")]
     pub value: ::core::option::Option<Any>,
 }
+impl ::prost::Name for Option {
+    const NAME: &'static str = "Option";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// The syntax in which a protocol buffer element is defined.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+#[prost(reflect, full_name="google.protobuf.Syntax")]
 pub enum Syntax {
     /// Syntax `proto2`.
+    #[prost(name="SYNTAX_PROTO2")]
     Proto2 = 0,
     /// Syntax `proto3`.
+    #[prost(name="SYNTAX_PROTO3")]
     Proto3 = 1,
 }
 impl Syntax {
@@ -1384,17 +1639,18 @@ impl Syntax {
 /// sometimes simply referred to as "APIs" in other contexts, such as the name of
 /// this message itself. See <https://cloud.google.com/apis/design/glossary> for
 /// detailed terminology.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Api")]
 pub struct Api {
     /// The fully qualified name of this interface, including package name
     /// followed by the interface's simple name.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// The methods of this interface, in unspecified order.
-    #[prost(message, repeated, tag="2")]
+    #[prost(message, repeated, tag="2", name="methods")]
     pub methods: ::prost::alloc::vec::Vec<Method>,
     /// Any metadata attached to the interface.
-    #[prost(message, repeated, tag="3")]
+    #[prost(message, repeated, tag="3", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
     /// A version string for this interface. If specified, must have the form
     /// `major-version.minor-version`, as in `1.10`. If the minor version is
@@ -1415,44 +1671,53 @@ pub struct Api {
     /// `google.feature.v1`. For major versions 0 and 1, the suffix can
     /// be omitted. Zero major versions must only be used for
     /// experimental, non-GA interfaces.
-    #[prost(string, tag="4")]
+    #[prost(string, tag="4", name="version")]
     pub version: ::prost::alloc::string::String,
     /// Source context for the protocol buffer service represented by this
     /// message.
-    #[prost(message, optional, tag="5")]
+    #[prost(message, optional, tag="5", name="source_context")]
     pub source_context: ::core::option::Option<SourceContext>,
     /// Included interfaces. See \\[Mixin\]\[\\].
-    #[prost(message, repeated, tag="6")]
+    #[prost(message, repeated, tag="6", name="mixins")]
     pub mixins: ::prost::alloc::vec::Vec<Mixin>,
     /// The source syntax of the service.
-    #[prost(enumeration="Syntax", tag="7")]
+    #[prost(enumeration="Syntax", tag="7", name="syntax")]
     pub syntax: i32,
 }
+impl ::prost::Name for Api {
+    const NAME: &'static str = "Api";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Method represents a method of an API interface.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Method")]
 pub struct Method {
     /// The simple name of this method.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// A URL of the input message type.
-    #[prost(string, tag="2")]
+    #[prost(string, tag="2", name="request_type_url")]
     pub request_type_url: ::prost::alloc::string::String,
     /// If true, the request is streamed.
-    #[prost(bool, tag="3")]
+    #[prost(bool, tag="3", name="request_streaming")]
     pub request_streaming: bool,
     /// The URL of the output message type.
-    #[prost(string, tag="4")]
+    #[prost(string, tag="4", name="response_type_url")]
     pub response_type_url: ::prost::alloc::string::String,
     /// If true, the response is streamed.
-    #[prost(bool, tag="5")]
+    #[prost(bool, tag="5", name="response_streaming")]
     pub response_streaming: bool,
     /// Any metadata attached to the method.
-    #[prost(message, repeated, tag="6")]
+    #[prost(message, repeated, tag="6", name="options")]
     pub options: ::prost::alloc::vec::Vec<Option>,
     /// The source syntax of this method.
-    #[prost(enumeration="Syntax", tag="7")]
+    #[prost(enumeration="Syntax", tag="7", name="syntax")]
     pub syntax: i32,
 }
+impl ::prost::Name for Method {
+    const NAME: &'static str = "Method";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Declares an API Interface to be included in this interface. The including
 /// interface must redeclare all the methods from the included interface, but
 /// documentation and options are inherited as follows:
@@ -1541,16 +1806,21 @@ pub struct Method {
 ///    ...
 ///  }
 /// ```
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Mixin")]
 pub struct Mixin {
     /// The fully qualified name of the interface which is included.
-    #[prost(string, tag="1")]
+    #[prost(string, tag="1", name="name")]
     pub name: ::prost::alloc::string::String,
     /// If non-empty specifies a path under which inherited HTTP paths
     /// are rooted.
-    #[prost(string, tag="2")]
+    #[prost(string, tag="2", name="root")]
     pub root: ::prost::alloc::string::String,
 }
+impl ::prost::Name for Mixin {
+    const NAME: &'static str = "Mixin";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// A Duration represents a signed, fixed-length span of time represented
 /// as a count of seconds and fractions of seconds at nanosecond
 /// resolution. It is independent of any calendar and concepts like "day"
@@ -1615,12 +1885,13 @@ pub struct Mixin {
 /// encoded in JSON format as "3s", while 3 seconds and 1 nanosecond should
 /// be expressed in JSON format as "3.000000001s", and 3 seconds and 1
 /// microsecond should be expressed in JSON format as "3.000001s".
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Duration")]
 pub struct Duration {
     /// Signed seconds of the span of time. Must be from -315,576,000,000
     /// to +315,576,000,000 inclusive. Note: these bounds are computed from:
     /// 60 sec/min * 60 min/hr * 24 hr/day * 365.25 days/year * 10000 years
-    #[prost(int64, tag="1")]
+    #[prost(int64, tag="1", name="seconds")]
     pub seconds: i64,
     /// Signed fractions of a second at nanosecond resolution of the span
     /// of time. Durations less than one second are represented with a 0
@@ -1628,9 +1899,13 @@ pub struct Duration {
     /// of one second or more, a non-zero value for the `nanos` field must be
     /// of the same sign as the `seconds` field. Must be from -999,999,999
     /// to +999,999,999 inclusive.
-    #[prost(int32, tag="2")]
+    #[prost(int32, tag="2", name="nanos")]
     pub nanos: i32,
 }
+impl ::prost::Name for Duration {
+    const NAME: &'static str = "Duration";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// `FieldMask` represents a set of symbolic field paths, for example:
 ///
 /// ```text
@@ -1853,12 +2128,17 @@ pub struct Duration {
 /// The implementation of any API method which has a FieldMask type field in the
 /// request should verify the included field paths, and return an
 /// `INVALID_ARGUMENT` error if any path is unmappable.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.FieldMask")]
 pub struct FieldMask {
     /// The set of field mask paths.
-    #[prost(string, repeated, tag="1")]
+    #[prost(string, repeated, tag="1", name="paths")]
     pub paths: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
+impl ::prost::Name for FieldMask {
+    const NAME: &'static str = "FieldMask";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// `Struct` represents a structured data value, consisting of fields
 /// which map to dynamically typed values. In some languages, `Struct`
 /// might be supported by a native representation. For example, in
@@ -1867,66 +2147,83 @@ pub struct FieldMask {
 /// with the proto support for the language.
 ///
 /// The JSON representation for `Struct` is JSON object.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Struct")]
 pub struct Struct {
     /// Unordered map of dynamically typed values.
-    #[prost(btree_map="string, message", tag="1")]
+    #[prost(btree_map="string, message", tag="1", name="fields")]
     pub fields: ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, Value>,
 }
+impl ::prost::Name for Struct {
+    const NAME: &'static str = "Struct";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// `Value` represents a dynamically typed value which can be either
 /// null, a number, a string, a boolean, a recursive struct value, or a
 /// list of values. A producer of value is expected to set one of these
 /// variants. Absence of any variant indicates an error.
 ///
 /// The JSON representation for `Value` is JSON value.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Value")]
 pub struct Value {
     /// The kind of value.
-    #[prost(oneof="value::Kind", tags="1, 2, 3, 4, 5, 6")]
+    #[prost(oneof="value::Kind", tags="1, 2, 3, 4, 5, 6", name="kind")]
     pub kind: ::core::option::Option<value::Kind>,
 }
+impl ::prost::Name for Value {
+    const NAME: &'static str = "Value";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// Nested message and enum types in `Value`.
 pub mod value {
     /// The kind of value.
-    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    #[derive(Clone, PartialEq, ::prost::Oneof, ::prost::ReflectMessage, ::prost::DiffMessage)]
     pub enum Kind {
         /// Represents a null value.
-        #[prost(enumeration="super::NullValue", tag="1")]
+        #[prost(enumeration="super::NullValue", tag="1", name="null_value")]
         NullValue(i32),
         /// Represents a double value.
-        #[prost(double, tag="2")]
+        #[prost(double, tag="2", name="number_value")]
         NumberValue(f64),
         /// Represents a string value.
-        #[prost(string, tag="3")]
+        #[prost(string, tag="3", name="string_value")]
         StringValue(::prost::alloc::string::String),
         /// Represents a boolean value.
-        #[prost(bool, tag="4")]
+        #[prost(bool, tag="4", name="bool_value")]
         BoolValue(bool),
         /// Represents a structured value.
-        #[prost(message, tag="5")]
+        #[prost(message, tag="5", name="struct_value")]
         StructValue(super::Struct),
         /// Represents a repeated `Value`.
-        #[prost(message, tag="6")]
+        #[prost(message, tag="6", name="list_value")]
         ListValue(super::ListValue),
     }
 }
 /// `ListValue` is a wrapper around a repeated field of values.
 ///
 /// The JSON representation for `ListValue` is JSON array.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.ListValue")]
 pub struct ListValue {
     /// Repeated field of dynamically typed values.
-    #[prost(message, repeated, tag="1")]
+    #[prost(message, repeated, tag="1", name="values")]
     pub values: ::prost::alloc::vec::Vec<Value>,
 }
+impl ::prost::Name for ListValue {
+    const NAME: &'static str = "ListValue";
+    const PACKAGE: &'static str = "google.protobuf";
+}
 /// `NullValue` is a singleton enumeration to represent the null value for the
 /// `Value` type union.
 ///
 /// The JSON representation for `NullValue` is JSON `null`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+#[prost(reflect, full_name="google.protobuf.NullValue")]
 pub enum NullValue {
     /// Null value.
+    #[prost(name="NULL_VALUE")]
     NullValue = 0,
 }
 impl NullValue {
@@ -2039,17 +2336,22 @@ impl NullValue {
 /// \[`strftime`\](<https://docs.python.org/2/library/time.html#time.strftime>) with
 /// the time format spec '%Y-%m-%dT%H:%M:%S.%fZ'. Likewise, in Java, one can use
 /// the Joda Time's \[`ISODateTimeFormat.dateTime()`\](<http://www.joda.org/joda-time/apidocs/org/joda/time/format/ISODateTimeFormat.html#dateTime%2D%2D>) to obtain a formatter capable of generating timestamps in this format.
-#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage, ::prost::DiffMessage)]
+#[prost(full_name="google.protobuf.Timestamp")]
 pub struct Timestamp {
     /// Represents seconds of UTC time since Unix epoch
     /// 1970-01-01T00:00:00Z. Must be from 0001-01-01T00:00:00Z to
     /// 9999-12-31T23:59:59Z inclusive.
-    #[prost(int64, tag="1")]
+    #[prost(int64, tag="1", name="seconds")]
     pub seconds: i64,
     /// Non-negative fractions of a second at nanosecond resolution. Negative
     /// second values with fractions must still have non-negative nanos values
     /// that count forward in time. Must be from 0 to 999,999,999
     /// inclusive.
-    #[prost(int32, tag="2")]
+    #[prost(int32, tag="2", name="nanos")]
     pub nanos: i32,
 }
+impl ::prost::Name for Timestamp {
+    const NAME: &'static str = "Timestamp";
+    const PACKAGE: &'static str = "google.protobuf";
+}
@@ -0,0 +1,506 @@
+//! Schema-less decoding of the Protobuf wire format.
+//!
+//! [`decode_raw`] decodes a message without knowing its type, like `protoc --decode_raw`, into a
+//! tree of fields, each with its field number and value. Length-delimited values are ambiguous on
+//! the wire, so they are classified heuristically: printable UTF-8 is decoded as a string, data
+//! which can be decoded as a message as a nested message, and data which can be decoded as a
+//! sequence of varints as a packed field. Anything else is kept as bytes.
+//!
+//! [`decode_raw_with_descriptor`] uses a message descriptor, e.g. from a `FileDescriptorSet`, to
+//! label fields with their names and to classify the values of known fields by their declared
+//! type. Unknown fields are classified heuristically.
+//!
+//! [`RawMessage`] is printed in the same form as `protoc --decode_raw`:
+//!
+//! ```text
+//! 1: 150
+//! 2 {
+//!   1: "nested"
+//! }
+//! 3: [1, 2, 3]
+//! 4: 0x3ff0000000000000
+//! ```
+
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
+use core::str;
+
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::encoding::{decode_key, decode_varint, encoded_len_varint, WireType};
+use prost::{DecodeError, DecodeErrorKind};
+
+use crate::dynamic::{FieldDescriptor, Kind, MessageDescriptor};
+use crate::text_format::write_escaped;
+
+/// The maximum depth of nested messages and groups, matching the recursion limit of message
+/// decoding.
+const RECURSION_LIMIT: u32 = 100;
+
+/// A message decoded without its type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RawMessage {
+    /// The fields of the message, in the order they were encoded.
+    pub fields: Vec<RawField>,
+}
+
+/// A field of a [`RawMessage`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawField {
+    /// The field number.
+    pub number: u32,
+    /// The name of the field, if it was decoded with a descriptor which declares it.
+    pub name: Option<String>,
+    /// The value of the field.
+    pub value: RawValue,
+}
+
+/// The value of a [`RawField`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawValue {
+    /// A varint.
+    Varint(u64),
+    /// A 64-bit fixed-width value.
+    Fixed64(u64),
+    /// A 32-bit fixed-width value.
+    Fixed32(u32),
+    /// A group of fields.
+    Group(RawMessage),
+    /// A length-delimited value decoded as a nested message.
+    Message(RawMessage),
+    /// A length-delimited value decoded as a UTF-8 string.
+    String(String),
+    /// A length-delimited value decoded as packed varints.
+    PackedVarint(Vec<u64>),
+    /// A length-delimited value decoded as packed 32-bit fixed-width values.
+    PackedFixed32(Vec<u32>),
+    /// A length-delimited value decoded as packed 64-bit fixed-width values.
+    PackedFixed64(Vec<u64>),
+    /// A length-delimited value which isn't classified as any of the other kinds.
+    Bytes(Vec<u8>),
+}
+
+impl RawValue {
+    /// Returns the wire type of the value.
+    pub fn wire_type(&self) -> WireType {
+        match *self {
+            RawValue::Varint(..) => WireType::Varint,
+            RawValue::Fixed64(..) => WireType::SixtyFourBit,
+            RawValue::Fixed32(..) => WireType::ThirtyTwoBit,
+            RawValue::Group(..) => WireType::StartGroup,
+            _ => WireType::LengthDelimited,
+        }
+    }
+}
+
+/// Decodes a message without its type.
+pub fn decode_raw(buf: &[u8]) -> Result<RawMessage, DecodeError> {
+    Decoder { buf }.decode_message(None, RECURSION_LIMIT)
+}
+
+/// Decodes a message, labelling its fields with the names declared by `desc`.
+pub fn decode_raw_with_descriptor(
+    buf: &[u8],
+    desc: &MessageDescriptor,
+) -> Result<RawMessage, DecodeError> {
+    Decoder { buf }.decode_message(Some(desc), RECURSION_LIMIT)
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Decodes fields until the end of the buffer.
+    fn decode_message(
+        &mut self,
+        desc: Option<&MessageDescriptor>,
+        depth: u32,
+    ) -> Result<RawMessage, DecodeError> {
+        self.decode_fields(desc, None, depth)
+    }
+
+    /// Decodes fields until the end of the buffer, or until the end group key of the group with
+    /// the field number `group`.
+    fn decode_fields(
+        &mut self,
+        desc: Option<&MessageDescriptor>,
+        group: Option<u32>,
+        depth: u32,
+    ) -> Result<RawMessage, DecodeError> {
+        let mut message = RawMessage::default();
+        while !self.buf.is_empty() {
+            let (number, wire_type) = decode_key(&mut self.buf)?;
+            if wire_type == WireType::EndGroup {
+                if group == Some(number) {
+                    return Ok(message);
+                }
+                return Err(DecodeError::with_kind(
+                    DecodeErrorKind::UnexpectedEndGroup,
+                    "unexpected end group tag",
+                ));
+            }
+            let field = desc.and_then(|desc| desc.get_field(number));
+            let value = self.decode_value(number, wire_type, field.as_ref(), depth)?;
+            message.fields.push(RawField {
+                number,
+                name: field.map(|field| field.name().into()),
+                value,
+            });
+        }
+        if group.is_some() {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "missing end group tag",
+            ));
+        }
+        Ok(message)
+    }
+
+    fn decode_value(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        field: Option<&FieldDescriptor>,
+        depth: u32,
+    ) -> Result<RawValue, DecodeError> {
+        let value = match wire_type {
+            WireType::Varint => RawValue::Varint(decode_varint(&mut self.buf)?),
+            WireType::SixtyFourBit => RawValue::Fixed64(u64::from_le_bytes(self.take_array()?)),
+            WireType::ThirtyTwoBit => RawValue::Fixed32(u32::from_le_bytes(self.take_array()?)),
+            WireType::StartGroup => {
+                let depth = enter(depth)?;
+                let desc = field.and_then(|field| match field.kind() {
+                    Kind::Message(desc) => Some(desc),
+                    _ => None,
+                });
+                RawValue::Group(self.decode_fields(desc.as_ref(), Some(number), depth)?)
+            }
+            WireType::LengthDelimited => {
+                let len = decode_varint(&mut self.buf)?;
+                if len > self.buf.len() as u64 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::BufferUnderflow,
+                        "buffer underflow",
+                    ));
+                }
+                let (data, rest) = self.buf.split_at(len as usize);
+                self.buf = rest;
+                match field {
+                    Some(field) => classify_known(data, field.kind(), field.is_list(), depth)?,
+                    None => classify(data, depth),
+                }
+            }
+            WireType::EndGroup => unreachable!(),
+        };
+        Ok(value)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        if self.buf.len() < N {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "buffer underflow",
+            ));
+        }
+        let (array, rest) = self.buf.split_at(N);
+        self.buf = rest;
+        Ok(<[u8; N]>::try_from(array).unwrap())
+    }
+}
+
+fn enter(depth: u32) -> Result<u32, DecodeError> {
+    match depth.checked_sub(1) {
+        Some(depth) => Ok(depth),
+        None => Err(DecodeError::with_kind(
+            DecodeErrorKind::RecursionLimitReached,
+            "recursion limit reached",
+        )),
+    }
+}
+
+/// Classifies a length-delimited value of a field declared in the message descriptor.
+///
+/// Values which don't match the declared type are classified heuristically.
+fn classify_known(
+    data: &[u8],
+    kind: Kind,
+    is_list: bool,
+    depth: u32,
+) -> Result<RawValue, DecodeError> {
+    let value = match kind {
+        Kind::Message(desc) => {
+            let depth = enter(depth)?;
+            Decoder { buf: data }
+                .decode_message(Some(&desc), depth)
+                .ok()
+                .map(RawValue::Message)
+        }
+        Kind::String => str::from_utf8(data)
+            .ok()
+            .map(|value| RawValue::String(value.into())),
+        Kind::Bytes => Some(RawValue::Bytes(data.to_vec())),
+        Kind::Fixed32 | Kind::Sfixed32 | Kind::Float if is_list => {
+            decode_packed(data).map(RawValue::PackedFixed32)
+        }
+        Kind::Fixed64 | Kind::Sfixed64 | Kind::Double if is_list => {
+            decode_packed(data).map(RawValue::PackedFixed64)
+        }
+        _ if is_list => decode_packed_varints(data).map(RawValue::PackedVarint),
+        _ => None,
+    };
+    Ok(value.unwrap_or_else(|| classify(data, depth)))
+}
+
+/// Classifies a length-delimited value of an unknown field.
+fn classify(data: &[u8], depth: u32) -> RawValue {
+    if data.is_empty() {
+        return RawValue::String(String::new());
+    }
+    if let Ok(value) = str::from_utf8(data) {
+        if value
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        {
+            return RawValue::String(value.into());
+        }
+    }
+    if let Ok(depth) = enter(depth) {
+        if let Ok(message) = (Decoder { buf: data }).decode_message(None, depth) {
+            return RawValue::Message(message);
+        }
+    }
+    if let Some(values) = decode_packed_varints(data) {
+        return RawValue::PackedVarint(values);
+    }
+    RawValue::Bytes(data.to_vec())
+}
+
+/// Decodes a sequence of minimally encoded varints.
+fn decode_packed_varints(mut data: &[u8]) -> Option<Vec<u64>> {
+    let mut values = Vec::new();
+    while !data.is_empty() {
+        let len = data.len();
+        let value = decode_varint(&mut data).ok()?;
+        // Varints encoded with redundant continuation bytes are unlikely to have been written by
+        // a Protobuf encoder.
+        if len - data.len() != encoded_len_varint(value) {
+            return None;
+        }
+        values.push(value);
+    }
+    Some(values)
+}
+
+/// Decodes a sequence of little-endian fixed-width values.
+fn decode_packed<T>(data: &[u8]) -> Option<Vec<T>>
+where
+    T: FixedWidth,
+{
+    let chunks = data.chunks_exact(T::LEN);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    Some(chunks.map(T::from_le_bytes).collect())
+}
+
+trait FixedWidth: Sized {
+    const LEN: usize;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+}
+
+impl FixedWidth for u32 {
+    const LEN: usize = 4;
+
+    fn from_le_bytes(bytes: &[u8]) -> u32 {
+        u32::from_le_bytes(<[u8; 4]>::try_from(bytes).unwrap())
+    }
+}
+
+impl FixedWidth for u64 {
+    const LEN: usize = 8;
+
+    fn from_le_bytes(bytes: &[u8]) -> u64 {
+        u64::from_le_bytes(<[u8; 8]>::try_from(bytes).unwrap())
+    }
+}
+
+impl fmt::Display for RawMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_message(&mut out, self, 0);
+        f.write_str(&out)
+    }
+}
+
+fn write_message(out: &mut String, message: &RawMessage, indent: usize) {
+    for field in &message.fields {
+        for _ in 0..indent {
+            out.push_str("  ");
+        }
+        match field.name {
+            Some(ref name) => write!(out, "{} ({})", name, field.number).unwrap(),
+            None => write!(out, "{}", field.number).unwrap(),
+        }
+        match field.value {
+            RawValue::Varint(value) => write!(out, ": {}", value).unwrap(),
+            RawValue::Fixed64(value) => write!(out, ": 0x{:016x}", value).unwrap(),
+            RawValue::Fixed32(value) => write!(out, ": 0x{:08x}", value).unwrap(),
+            RawValue::Group(ref message) | RawValue::Message(ref message) => {
+                out.push_str(" {\n");
+                write_message(out, message, indent + 1);
+                for _ in 0..indent {
+                    out.push_str("  ");
+                }
+                out.push('}');
+            }
+            RawValue::String(ref value) => write_literal(out, value.as_bytes()),
+            RawValue::Bytes(ref value) => write_literal(out, value),
+            RawValue::PackedVarint(ref values) => {
+                write_list(out, values, |out, value| write!(out, "{}", value).unwrap())
+            }
+            RawValue::PackedFixed32(ref values) => write_list(out, values, |out, value| {
+                write!(out, "0x{:08x}", value).unwrap()
+            }),
+            RawValue::PackedFixed64(ref values) => write_list(out, values, |out, value| {
+                write!(out, "0x{:016x}", value).unwrap()
+            }),
+        }
+        out.push('\n');
+    }
+}
+
+fn write_literal(out: &mut String, bytes: &[u8]) {
+    out.push_str(": \"");
+    for &b in bytes {
+        write_escaped(out, b);
+    }
+    out.push('"');
+}
+
+fn write_list<T, F>(out: &mut String, values: &[T], write_value: F)
+where
+    F: Fn(&mut String, &T),
+{
+    out.push_str(": [");
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_value(out, value);
+    }
+    out.push(']');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+
+    fn field(number: u32, value: RawValue) -> RawField {
+        RawField {
+            number,
+            name: None,
+            value,
+        }
+    }
+
+    #[test]
+    fn decode_scalars() {
+        let buf = [
+            0x08, 0x96, 0x01, // 1: 150
+            0x11, 1, 0, 0, 0, 0, 0, 0, 0, // 2: fixed64
+            0x1D, 2, 0, 0, 0, // 3: fixed32
+            0x22, 0x02, b'h', b'i', // 4: "hi"
+        ];
+        let message = decode_raw(&buf).unwrap();
+        assert_eq!(
+            message.fields,
+            vec![
+                field(1, RawValue::Varint(150)),
+                field(2, RawValue::Fixed64(1)),
+                field(3, RawValue::Fixed32(2)),
+                field(4, RawValue::String("hi".into())),
+            ]
+        );
+        assert_eq!(
+            message.to_string(),
+            "1: 150\n2: 0x0000000000000001\n3: 0x00000002\n4: \"hi\"\n"
+        );
+    }
+
+    #[test]
+    fn classify_length_delimited() {
+        // A nested message, a packed field and bytes which are neither.
+        let buf = [
+            0x0A, 0x04, 0x08, 0x01, 0x10, 0x02, // 1 { 1: 1 2: 2 }
+            0x12, 0x03, 0x01, 0x96, 0x01, // 2: [1, 150]
+            0x1A, 0x02, 0xFF, 0xFF, // 3: "\377\377"
+            0x22, 0x00, // 4: ""
+        ];
+        let message = decode_raw(&buf).unwrap();
+        assert_eq!(
+            message.fields,
+            vec![
+                field(
+                    1,
+                    RawValue::Message(RawMessage {
+                        fields: vec![field(1, RawValue::Varint(1)), field(2, RawValue::Varint(2))],
+                    })
+                ),
+                field(2, RawValue::PackedVarint(vec![1, 150])),
+                field(3, RawValue::Bytes(vec![0xFF, 0xFF])),
+                field(4, RawValue::String(String::new())),
+            ]
+        );
+        assert_eq!(
+            message.to_string(),
+            "1 {\n  1: 1\n  2: 2\n}\n2: [1, 150]\n3: \"\\377\\377\"\n4: \"\"\n"
+        );
+    }
+
+    #[test]
+    fn decode_groups() {
+        // 1 { 2: 3 }, followed by 4: 5.
+        let buf = [0x0B, 0x10, 0x03, 0x0C, 0x20, 0x05];
+        let message = decode_raw(&buf).unwrap();
+        assert_eq!(
+            message.fields,
+            vec![
+                field(
+                    1,
+                    RawValue::Group(RawMessage {
+                        fields: vec![field(2, RawValue::Varint(3))],
+                    })
+                ),
+                field(4, RawValue::Varint(5)),
+            ]
+        );
+        assert_eq!(message.fields[0].value.wire_type(), WireType::StartGroup);
+
+        // An end group key without a start group key, and a group without an end.
+        assert!(decode_raw(&[0x0C]).is_err());
+        assert!(decode_raw(&[0x0B, 0x10, 0x03]).is_err());
+    }
+
+    #[test]
+    fn invalid_data() {
+        for buf in [&[0x08][..], &[0x0A, 0x05, 0x01], &[0x0F], &[0x11, 0x00]] {
+            assert!(decode_raw(buf).is_err(), "{:?}", buf);
+        }
+
+        // Deeply nested length-delimited values are kept as bytes once the recursion limit is
+        // reached, rather than failing.
+        let mut buf = vec![0x08, 0x01];
+        for _ in 0..200 {
+            let mut outer = vec![0x0A];
+            prost::encoding::encode_varint(buf.len() as u64, &mut outer);
+            outer.extend_from_slice(&buf);
+            buf = outer;
+        }
+        assert!(decode_raw(&buf).is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+//! The Protobuf [text format][1] for reflected messages.
+//!
+//! The text format is the human-readable form printed by `protoc --decode` and by the `DebugString`
+//! methods of the other Protobuf implementations. Fields are written by name, nested messages as
+//! `{ ... }` blocks, enums by the name of their value and strings and bytes as C-escaped literals:
+//!
+//! ```text
+//! id: 42
+//! status: ACTIVE
+//! address {
+//!   street: "1 Main St"
+//! }
+//! tags: "a\001b"
+//! ```
+//!
+//! `google.protobuf.Any` fields can be written in their expanded `[type.googleapis.com/pkg.Msg] {
+//! ... }` form, for message types registered with [`PrintOptions::message_type`] and
+//! [`ParseOptions::message_type`]. Extensions are not supported, unknown fields are not printed
+//! and fields which the message type doesn't have are rejected by the parser.
+//!
+//! [1]: https://developers.google.com/protocol-buffers/docs/text-format-spec
+
+mod parser;
+mod printer;
+
+pub(crate) use self::printer::write_escaped;
+
+use core::fmt;
+
+use prost::alloc::boxed::Box;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::reflect::MessageDescriptor;
+use prost::ReflectMessage;
+
+/// Prints a message in the text format, with one field per line.
+pub fn to_string(message: &dyn ReflectMessage) -> String {
+    to_string_with_options(message, &PrintOptions::default())
+}
+
+/// Prints a message in the text format, using the given options.
+pub fn to_string_with_options(message: &dyn ReflectMessage, options: &PrintOptions) -> String {
+    printer::print(message, options)
+}
+
+/// Parses a message of type `M` from the text format.
+pub fn from_str<M>(s: &str) -> Result<M, TextFormatError>
+where
+    M: ReflectMessage,
+{
+    from_str_with_options(s, &ParseOptions::default())
+}
+
+/// Parses a message of type `M` from the text format, using the given options.
+pub fn from_str_with_options<M>(s: &str, options: &ParseOptions) -> Result<M, TextFormatError>
+where
+    M: ReflectMessage,
+{
+    let message = from_str_with_descriptor(M::type_descriptor(), s, options)?;
+    M::from_boxed(message).map_err(|message| TextFormatError {
+        description: format!(
+            "parsed a {} rather than a {}",
+            message.descriptor().full_name(),
+            M::type_descriptor().full_name()
+        ),
+        // The mismatch isn't caused by the input, so the error points at its start.
+        line: 1,
+        column: 1,
+    })
+}
+
+/// Parses a message of the type described by `desc` from the text format.
+pub fn from_str_with_descriptor(
+    desc: &'static MessageDescriptor,
+    s: &str,
+    options: &ParseOptions,
+) -> Result<Box<dyn ReflectMessage>, TextFormatError> {
+    parser::parse(desc, s, options)
+}
+
+/// Options for printing messages in the text format.
+#[derive(Clone, Debug, Default)]
+pub struct PrintOptions {
+    single_line: bool,
+    message_types: Vec<&'static MessageDescriptor>,
+}
+
+impl PrintOptions {
+    /// Creates the default print options.
+    pub fn new() -> PrintOptions {
+        PrintOptions::default()
+    }
+
+    /// Prints the whole message on a single line, rather than one field per line with nested
+    /// messages indented.
+    pub fn single_line(mut self, enabled: bool) -> PrintOptions {
+        self.single_line = enabled;
+        self
+    }
+
+    /// Registers a message type which `google.protobuf.Any` values may be expanded to.
+    pub fn message_type(mut self, desc: &'static MessageDescriptor) -> PrintOptions {
+        self.message_types.push(desc);
+        self
+    }
+}
+
+/// Options for parsing messages from the text format.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    message_types: Vec<&'static MessageDescriptor>,
+}
+
+impl ParseOptions {
+    /// Creates the default parse options.
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Registers a message type which expanded `google.protobuf.Any` values may contain.
+    pub fn message_type(mut self, desc: &'static MessageDescriptor) -> ParseOptions {
+        self.message_types.push(desc);
+        self
+    }
+}
+
+/// An error returned when a message can't be parsed from the text format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextFormatError {
+    description: String,
+    line: usize,
+    column: usize,
+}
+
+impl TextFormatError {
+    /// Returns the line of the input at which the error occurred, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column of the input at which the error occurred, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for TextFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid text format at {}:{}: {}",
+            self.line, self.column, self.description
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TextFormatError {}
@@ -0,0 +1,741 @@
+//! Parsing of reflected messages from the text format.
+
+use core::convert::TryFrom;
+
+use prost::alloc::boxed::Box;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::reflect::{Cardinality, FieldDescriptor, Kind, MessageDescriptor, Value};
+use prost::ReflectMessage;
+
+use super::{ParseOptions, TextFormatError};
+use crate::type_registry::resolve_type_url;
+
+/// The maximum depth of nested messages.
+const RECURSION_LIMIT: u32 = 100;
+
+pub(super) fn parse(
+    desc: &'static MessageDescriptor,
+    s: &str,
+    options: &ParseOptions,
+) -> Result<Box<dyn ReflectMessage>, TextFormatError> {
+    let mut parser = Parser {
+        tokens: tokenize(s)?,
+        pos: 0,
+        options,
+    };
+    let mut message = desc.new_message();
+    parser.parse_fields(&mut *message, None, RECURSION_LIMIT)?;
+    Ok(message)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    /// A number, without its sign, in its textual form.
+    Number(String),
+    /// A string literal, with escapes resolved.
+    String(Vec<u8>),
+    Symbol(u8),
+    End,
+}
+
+/// A token, along with the line and column at which it starts.
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Spanned>, TextFormatError> {
+    let bytes = s.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    loop {
+        // Skip whitespace and comments.
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'\n' => {
+                    pos += 1;
+                    line += 1;
+                    line_start = pos;
+                }
+                b' ' | b'\t' | b'\r' | b'\x0B' | b'\x0C' => pos += 1,
+                b'#' => {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let column = pos - line_start + 1;
+        let error = |description: String| TextFormatError {
+            description,
+            line,
+            column,
+        };
+
+        let token = match bytes.get(pos) {
+            None => Token::End,
+            Some(c) if c.is_ascii_alphabetic() || *c == b'_' => {
+                let start = pos;
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                Token::Ident(s[start..pos].into())
+            }
+            Some(c)
+                if c.is_ascii_digit()
+                    || (*c == b'.'
+                        && matches!(bytes.get(pos + 1), Some(c) if c.is_ascii_digit())) =>
+            {
+                let start = pos;
+                let is_hex = bytes[pos..].starts_with(b"0x") || bytes[pos..].starts_with(b"0X");
+                while pos < bytes.len() {
+                    let c = bytes[pos];
+                    let is_exponent_sign = (c == b'+' || c == b'-')
+                        && !is_hex
+                        && matches!(bytes[pos - 1], b'e' | b'E');
+                    if c.is_ascii_alphanumeric() || c == b'.' || is_exponent_sign {
+                        pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Number(s[start..pos].into())
+            }
+            Some(&quote @ (b'"' | b'\'')) => {
+                let (value, end) = parse_string_literal(bytes, pos + 1, quote).map_err(error)?;
+                pos = end;
+                Token::String(value)
+            }
+            Some(
+                &c @ (b'{' | b'}' | b'<' | b'>' | b'[' | b']' | b':' | b',' | b';' | b'/' | b'-'
+                | b'.'),
+            ) => {
+                pos += 1;
+                Token::Symbol(c)
+            }
+            Some(_) => return Err(error("unexpected character".into())),
+        };
+
+        let end = token == Token::End;
+        tokens.push(Spanned {
+            token,
+            line,
+            column,
+        });
+        if end {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Parses the contents of a string literal starting at `pos`, after the opening quote. Returns
+/// the value and the position after the closing quote.
+fn parse_string_literal(
+    bytes: &[u8],
+    mut pos: usize,
+    quote: u8,
+) -> Result<(Vec<u8>, usize), String> {
+    let mut value = Vec::new();
+    loop {
+        let c = match bytes.get(pos) {
+            None | Some(b'\n') => return Err("unterminated string".into()),
+            Some(&c) => c,
+        };
+        pos += 1;
+        if c == quote {
+            return Ok((value, pos));
+        } else if c != b'\\' {
+            value.push(c);
+            continue;
+        }
+
+        let escape = *bytes.get(pos).ok_or("unterminated string")?;
+        pos += 1;
+        match escape {
+            b'a' => value.push(0x07),
+            b'b' => value.push(0x08),
+            b'f' => value.push(0x0C),
+            b'n' => value.push(b'\n'),
+            b'r' => value.push(b'\r'),
+            b't' => value.push(b'\t'),
+            b'v' => value.push(0x0B),
+            b'\\' | b'\'' | b'"' | b'?' => value.push(escape),
+            b'0'..=b'7' => {
+                let mut code = u32::from(escape - b'0');
+                let mut digits = 1;
+                while digits < 3 {
+                    match bytes.get(pos) {
+                        Some(&d @ b'0'..=b'7') => {
+                            code = code * 8 + u32::from(d - b'0');
+                            pos += 1;
+                            digits += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                let byte = u8::try_from(code).map_err(|_| "octal escape out of range")?;
+                value.push(byte);
+            }
+            b'x' | b'X' => {
+                let (code, len) = parse_hex(&bytes[pos..], 2);
+                if len == 0 {
+                    return Err("invalid hex escape".into());
+                }
+                pos += len;
+                value.push(code as u8);
+            }
+            b'u' | b'U' => {
+                let digits = if escape == b'u' { 4 } else { 8 };
+                let (code, len) = parse_hex(&bytes[pos..], digits);
+                if len != digits {
+                    return Err("invalid unicode escape".into());
+                }
+                pos += len;
+                let c = char::from_u32(code).ok_or("invalid unicode escape")?;
+                let mut buf = [0; 4];
+                value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => return Err("invalid escape sequence".into()),
+        }
+    }
+}
+
+/// Parses up to `max` hex digits, returning the value and the number of digits.
+fn parse_hex(bytes: &[u8], max: usize) -> (u32, usize) {
+    let mut code = 0;
+    let mut len = 0;
+    while len < max {
+        match bytes.get(len).and_then(|&c| char::from(c).to_digit(16)) {
+            Some(digit) => {
+                code = code * 16 + digit;
+                len += 1;
+            }
+            None => break,
+        }
+    }
+    (code, len)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    options: &'a ParseOptions,
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, index: usize, description: String) -> TextFormatError {
+        let token = &self.tokens[index];
+        TextFormatError {
+            description,
+            line: token.line,
+            column: token.column,
+        }
+    }
+
+    fn error(&self, description: String) -> TextFormatError {
+        self.error_at(self.pos, description)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn consume_symbol(&mut self, symbol: u8) -> bool {
+        if *self.peek() == Token::Symbol(symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: u8) -> Result<(), TextFormatError> {
+        if self.consume_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", char::from(symbol))))
+        }
+    }
+
+    /// Parses fields into `message` until the `close` symbol, or the end of input.
+    fn parse_fields(
+        &mut self,
+        message: &mut dyn ReflectMessage,
+        close: Option<u8>,
+        depth: u32,
+    ) -> Result<(), TextFormatError> {
+        let desc = message.descriptor();
+        let mut lists: Vec<(u32, Vec<Value>)> = Vec::new();
+        let mut maps: Vec<(u32, Vec<(Value, Value)>)> = Vec::new();
+        let mut seen_fields = Vec::new();
+        let mut seen_oneofs = Vec::new();
+
+        loop {
+            match close {
+                Some(close) if self.consume_symbol(close) => break,
+                None if *self.peek() == Token::End => break,
+                _ if *self.peek() == Token::End => {
+                    return Err(self.error("unexpected end of input".into()))
+                }
+                _ => (),
+            }
+
+            let index = self.pos;
+            if self.consume_symbol(b'[') {
+                if seen_fields.contains(&1) || seen_fields.contains(&2) {
+                    return Err(self.error_at(index, "Any is specified multiple times".into()));
+                }
+                seen_fields.extend_from_slice(&[1, 2]);
+                self.parse_any(message, index, depth)?;
+                self.skip_separator();
+                continue;
+            }
+
+            let name = match self.next() {
+                Token::Ident(name) => name,
+                _ => return Err(self.error_at(index, "expected a field name".into())),
+            };
+            let field = desc.field_by_name(&name).or_else(|| {
+                // Groups are named by their type, as in the .proto file.
+                desc.fields().find(|field| {
+                    field.kind() == Kind::Group
+                        && matches!(field.message_type(), Some(group) if group.name() == name)
+                })
+            });
+            let field = match field {
+                Some(field) => field,
+                None => {
+                    return Err(self.error_at(
+                        index,
+                        format!("unknown field {} in {}", name, desc.full_name()),
+                    ))
+                }
+            };
+            let number = field.number();
+
+            match field.kind() {
+                Kind::Map(key_kind, value_kind) => {
+                    self.consume_symbol(b':');
+                    let mut entries = Vec::new();
+                    let is_list = self.parse_list(|parser| {
+                        entries.push(parser.parse_map_entry(
+                            field,
+                            *key_kind,
+                            *value_kind,
+                            depth,
+                        )?);
+                        Ok(())
+                    })?;
+                    if !is_list {
+                        entries.push(self.parse_map_entry(field, *key_kind, *value_kind, depth)?);
+                    }
+                    match maps.iter_mut().find(|(n, _)| *n == number) {
+                        Some((_, existing)) => existing.append(&mut entries),
+                        None => maps.push((number, entries)),
+                    }
+                }
+                kind if field.cardinality() == Cardinality::Repeated => {
+                    self.parse_colon(kind)?;
+                    let mut values = Vec::new();
+                    let is_list = self.parse_list(|parser| {
+                        values.push(parser.parse_value(field, kind, depth)?);
+                        Ok(())
+                    })?;
+                    if !is_list {
+                        values.push(self.parse_value(field, kind, depth)?);
+                    }
+                    match lists.iter_mut().find(|(n, _)| *n == number) {
+                        Some((_, existing)) => existing.append(&mut values),
+                        None => lists.push((number, values)),
+                    }
+                }
+                kind => {
+                    if seen_fields.contains(&number) {
+                        return Err(self.error_at(
+                            index,
+                            format!("non-repeated field {} is specified multiple times", name),
+                        ));
+                    }
+                    seen_fields.push(number);
+                    if let Some(oneof) = desc.oneof_of(number) {
+                        if seen_oneofs.contains(&oneof.name()) {
+                            return Err(self.error_at(
+                                index,
+                                format!("multiple fields of oneof {} are specified", oneof.name()),
+                            ));
+                        }
+                        seen_oneofs.push(oneof.name());
+                    }
+                    self.parse_colon(kind)?;
+                    let value = self.parse_value(field, kind, depth)?;
+                    self.set_field(message, index, number, value)?;
+                }
+            }
+            self.skip_separator();
+        }
+
+        for (number, values) in lists {
+            self.set_field(message, self.pos, number, Value::List(values))?;
+        }
+        for (number, entries) in maps {
+            self.set_field(message, self.pos, number, Value::Map(entries))?;
+        }
+        Ok(())
+    }
+
+    fn set_field(
+        &self,
+        message: &mut dyn ReflectMessage,
+        index: usize,
+        number: u32,
+        value: Value,
+    ) -> Result<(), TextFormatError> {
+        message
+            .set_field(number, value)
+            .map_err(|error| self.error_at(index, format!("{}", error)))
+    }
+
+    /// The colon between a field name and its value is optional for messages.
+    fn parse_colon(&mut self, kind: Kind) -> Result<(), TextFormatError> {
+        match kind {
+            Kind::Message | Kind::Group => {
+                self.consume_symbol(b':');
+                Ok(())
+            }
+            _ => self.expect_symbol(b':'),
+        }
+    }
+
+    fn skip_separator(&mut self) {
+        if !self.consume_symbol(b',') {
+            self.consume_symbol(b';');
+        }
+    }
+
+    /// Parses a `[a, b, ...]` list of values if one follows, returning `false` otherwise.
+    fn parse_list<F>(&mut self, mut parse_element: F) -> Result<bool, TextFormatError>
+    where
+        F: FnMut(&mut Self) -> Result<(), TextFormatError>,
+    {
+        if !self.consume_symbol(b'[') {
+            return Ok(false);
+        }
+        if self.consume_symbol(b']') {
+            return Ok(true);
+        }
+        loop {
+            parse_element(self)?;
+            if self.consume_symbol(b']') {
+                return Ok(true);
+            }
+            self.expect_symbol(b',')?;
+        }
+    }
+
+    /// Parses an expanded `Any`, after the opening `[`.
+    fn parse_any(
+        &mut self,
+        message: &mut dyn ReflectMessage,
+        index: usize,
+        depth: u32,
+    ) -> Result<(), TextFormatError> {
+        let mut type_url = String::new();
+        loop {
+            match self.next() {
+                Token::Symbol(b']') => break,
+                Token::Ident(s) | Token::Number(s) => type_url.push_str(&s),
+                Token::Symbol(c @ (b'.' | b'/' | b'-')) => type_url.push(char::from(c)),
+                _ => return Err(self.error_at(index, "invalid type URL".into())),
+            }
+        }
+
+        if message.descriptor().full_name() != "google.protobuf.Any" || !type_url.contains('/') {
+            return Err(self.error_at(
+                index,
+                format!("extensions are not supported: [{}]", type_url),
+            ));
+        }
+        let desc = match resolve_type_url(&self.options.message_types, &type_url) {
+            Some(desc) => desc,
+            None => return Err(self.error_at(index, format!("unknown message type {}", type_url))),
+        };
+
+        self.consume_symbol(b':');
+        let mut expanded = desc.new_message();
+        self.parse_block(&mut *expanded, depth)?;
+        let mut value = Vec::new();
+        expanded.encode_dyn(&mut value);
+        self.set_field(message, index, 1, Value::String(type_url))?;
+        self.set_field(message, index, 2, Value::Bytes(value))
+    }
+
+    /// Parses a nested message delimited by `{}` or `<>`.
+    fn parse_block(
+        &mut self,
+        message: &mut dyn ReflectMessage,
+        depth: u32,
+    ) -> Result<(), TextFormatError> {
+        let depth = self.enter(depth)?;
+        let close = if self.consume_symbol(b'{') {
+            b'}'
+        } else if self.consume_symbol(b'<') {
+            b'>'
+        } else {
+            return Err(self.error("expected '{' or '<'".into()));
+        };
+        self.parse_fields(message, Some(close), depth)
+    }
+
+    fn enter(&self, depth: u32) -> Result<u32, TextFormatError> {
+        depth
+            .checked_sub(1)
+            .ok_or_else(|| self.error("recursion limit reached".into()))
+    }
+
+    fn parse_map_entry(
+        &mut self,
+        field: &'static FieldDescriptor,
+        key_kind: Kind,
+        value_kind: Kind,
+        depth: u32,
+    ) -> Result<(Value, Value), TextFormatError> {
+        let depth = self.enter(depth)?;
+        let close = if self.consume_symbol(b'{') {
+            b'}'
+        } else if self.consume_symbol(b'<') {
+            b'>'
+        } else {
+            return Err(self.error("expected '{' or '<'".into()));
+        };
+
+        let mut key = None;
+        let mut value = None;
+        while !self.consume_symbol(close) {
+            let index = self.pos;
+            let (slot, kind) = match self.next() {
+                Token::Ident(ref name) if name == "key" => (&mut key, key_kind),
+                Token::Ident(ref name) if name == "value" => (&mut value, value_kind),
+                _ => return Err(self.error_at(index, "expected a map key or value".into())),
+            };
+            if slot.is_some() {
+                return Err(
+                    self.error_at(index, "map entry field is specified multiple times".into())
+                );
+            }
+            self.parse_colon(kind)?;
+            *slot = Some(self.parse_value(field, kind, depth)?);
+            self.skip_separator();
+        }
+
+        let key = match key {
+            Some(key) => key,
+            None => self.default_value(field, key_kind)?,
+        };
+        let value = match value {
+            Some(value) => value,
+            None => self.default_value(field, value_kind)?,
+        };
+        Ok((key, value))
+    }
+
+    fn default_value(
+        &self,
+        field: &'static FieldDescriptor,
+        kind: Kind,
+    ) -> Result<Value, TextFormatError> {
+        let value = match kind {
+            Kind::Double => Value::F64(0.0),
+            Kind::Float => Value::F32(0.0),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(0),
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(0),
+            Kind::Uint32 | Kind::Fixed32 => Value::U32(0),
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(0),
+            Kind::Bool => Value::Bool(false),
+            Kind::String => Value::String(String::new()),
+            Kind::Bytes => Value::Bytes(Vec::new()),
+            Kind::Enum => Value::EnumNumber(0),
+            Kind::Message | Kind::Group | Kind::Map(..) => match field.message_type() {
+                Some(desc) => Value::Message(desc.new_message()),
+                None => return Err(self.error(format!("unknown type of field {}", field.name()))),
+            },
+        };
+        Ok(value)
+    }
+
+    /// Parses a singular value, or an element of a repeated or map field.
+    fn parse_value(
+        &mut self,
+        field: &'static FieldDescriptor,
+        kind: Kind,
+        depth: u32,
+    ) -> Result<Value, TextFormatError> {
+        let index = self.pos;
+        let out_of_range = |parser: &Self| {
+            parser.error_at(
+                index,
+                format!("value out of range for field {}", field.name()),
+            )
+        };
+        let value = match kind {
+            Kind::Double => Value::F64(self.parse_float()?),
+            Kind::Float => Value::F32(self.parse_float()? as f32),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                let value = self.parse_signed()?;
+                Value::I32(i32::try_from(value).map_err(|_| out_of_range(self))?)
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(self.parse_signed()?),
+            Kind::Uint32 | Kind::Fixed32 => {
+                let value = self.parse_unsigned()?;
+                Value::U32(u32::try_from(value).map_err(|_| out_of_range(self))?)
+            }
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(self.parse_unsigned()?),
+            Kind::Bool => Value::Bool(self.parse_bool()?),
+            Kind::String => {
+                let value = self.parse_bytes()?;
+                Value::String(
+                    String::from_utf8(value)
+                        .map_err(|_| self.error_at(index, "invalid UTF-8 in string".into()))?,
+                )
+            }
+            Kind::Bytes => Value::Bytes(self.parse_bytes()?),
+            Kind::Enum => match self.peek().clone() {
+                Token::Ident(name) => {
+                    self.pos += 1;
+                    match field.enum_type().and_then(|desc| desc.value_number(&name)) {
+                        Some(number) => Value::EnumNumber(number),
+                        None => {
+                            return Err(self.error_at(
+                                index,
+                                format!("unknown enum value {} for field {}", name, field.name()),
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    let value = self.parse_signed()?;
+                    Value::EnumNumber(i32::try_from(value).map_err(|_| out_of_range(self))?)
+                }
+            },
+            Kind::Message | Kind::Group | Kind::Map(..) => {
+                let mut message = match self.default_value(field, kind)? {
+                    Value::Message(message) => message,
+                    _ => unreachable!(),
+                };
+                self.parse_block(&mut *message, depth)?;
+                Value::Message(message)
+            }
+        };
+        Ok(value)
+    }
+
+    fn parse_signed(&mut self) -> Result<i64, TextFormatError> {
+        let index = self.pos;
+        let negative = self.consume_symbol(b'-');
+        let magnitude = match self.next() {
+            Token::Number(text) => parse_integer(&text),
+            _ => None,
+        };
+        let magnitude =
+            magnitude.ok_or_else(|| self.error_at(index, "expected an integer".into()))?;
+        if negative {
+            if magnitude > 1 << 63 {
+                return Err(self.error_at(index, "integer out of range".into()));
+            }
+            Ok((magnitude as i64).wrapping_neg())
+        } else {
+            i64::try_from(magnitude)
+                .map_err(|_| self.error_at(index, "integer out of range".into()))
+        }
+    }
+
+    fn parse_unsigned(&mut self) -> Result<u64, TextFormatError> {
+        let index = self.pos;
+        match self.next() {
+            Token::Number(text) => parse_integer(&text),
+            _ => None,
+        }
+        .ok_or_else(|| self.error_at(index, "expected an unsigned integer".into()))
+    }
+
+    fn parse_float(&mut self) -> Result<f64, TextFormatError> {
+        let index = self.pos;
+        let negative = self.consume_symbol(b'-');
+        let value = match self.next() {
+            Token::Ident(name) => match &*name.to_ascii_lowercase() {
+                "inf" | "infinity" => Some(f64::INFINITY),
+                "nan" => Some(f64::NAN),
+                _ => None,
+            },
+            Token::Number(text) => {
+                if text.starts_with("0x") || text.starts_with("0X") {
+                    parse_integer(&text).map(|value| value as f64)
+                } else {
+                    text.trim_end_matches(&['f', 'F'][..]).parse::<f64>().ok()
+                }
+            }
+            _ => None,
+        };
+        let value = value.ok_or_else(|| self.error_at(index, "expected a number".into()))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    fn parse_bool(&mut self) -> Result<bool, TextFormatError> {
+        let index = self.pos;
+        match self.next() {
+            Token::Ident(ref name) if name == "true" || name == "True" || name == "t" => Ok(true),
+            Token::Ident(ref name) if name == "false" || name == "False" || name == "f" => {
+                Ok(false)
+            }
+            Token::Number(ref text) if text == "1" => Ok(true),
+            Token::Number(ref text) if text == "0" => Ok(false),
+            _ => Err(self.error_at(index, "expected a boolean".into())),
+        }
+    }
+
+    /// Parses one or more adjacent string literals.
+    fn parse_bytes(&mut self) -> Result<Vec<u8>, TextFormatError> {
+        let mut value = match self.next() {
+            Token::String(value) => value,
+            _ => return Err(self.error_at(self.pos - 1, "expected a string".into())),
+        };
+        while let Token::String(next) = self.peek() {
+            value.extend_from_slice(next);
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Parses a decimal, hexadecimal (`0x`) or octal (leading `0`) integer.
+fn parse_integer(text: &str) -> Option<u64> {
+    let (digits, radix) = if text.starts_with("0x") || text.starts_with("0X") {
+        (&text[2..], 16)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (&text[1..], 8)
+    } else {
+        (text, 10)
+    };
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
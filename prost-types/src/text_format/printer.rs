@@ -0,0 +1,202 @@
+//! Printing of reflected messages in the text format.
+
+use core::fmt::{self, Write};
+
+use prost::alloc::string::String;
+use prost::reflect::{FieldDescriptor, Kind, Value};
+use prost::ReflectMessage;
+
+use super::PrintOptions;
+use crate::json::write_finite_float;
+use crate::type_registry::resolve_type_url;
+
+pub(super) fn print(message: &dyn ReflectMessage, options: &PrintOptions) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        options,
+        indent: 0,
+    };
+    printer.write_message(message);
+    if options.single_line && printer.out.ends_with(' ') {
+        printer.out.pop();
+    }
+    printer.out
+}
+
+struct Printer<'a> {
+    out: String,
+    options: &'a PrintOptions,
+    indent: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn write_message(&mut self, message: &dyn ReflectMessage) {
+        let desc = message.descriptor();
+        if desc.full_name() == "google.protobuf.Any" && self.write_any(message) {
+            return;
+        }
+
+        for field in desc.fields() {
+            if !message.has_field(field.number()) {
+                continue;
+            }
+            let name = match (field.kind(), field.message_type()) {
+                // Groups are written using the name of their type, as in the .proto file.
+                (Kind::Group, Some(group)) => group.name(),
+                _ => field.name(),
+            };
+            match message.get_field(field.number()) {
+                Some(Value::List(values)) => {
+                    for value in &values {
+                        self.write_field(name, field, value);
+                    }
+                }
+                Some(Value::Map(entries)) => {
+                    for (key, value) in &entries {
+                        self.start_field(name);
+                        self.open_block();
+                        self.write_field("key", field, key);
+                        self.write_field("value", field, value);
+                        self.close_block();
+                    }
+                }
+                Some(value) => self.write_field(name, field, &value),
+                None => (),
+            }
+        }
+    }
+
+    /// Writes an `Any` in its expanded form, if its type is registered and its value decodes.
+    fn write_any(&mut self, message: &dyn ReflectMessage) -> bool {
+        let (type_url, value) = match (message.get_field(1), message.get_field(2)) {
+            (Some(Value::String(type_url)), Some(Value::Bytes(value))) => (type_url, value),
+            _ => return false,
+        };
+        let desc = match resolve_type_url(&self.options.message_types, &type_url) {
+            Some(desc) => desc,
+            None => return false,
+        };
+        let mut expanded = desc.new_message();
+        if expanded.merge_dyn(&value).is_err() {
+            return false;
+        }
+
+        self.indent();
+        write!(self.out, "[{}]", type_url).unwrap();
+        self.open_block();
+        self.write_message(&*expanded);
+        self.close_block();
+        true
+    }
+
+    fn write_field(&mut self, name: &str, field: &FieldDescriptor, value: &Value) {
+        self.start_field(name);
+        match value {
+            Value::Message(message) => {
+                self.open_block();
+                self.write_message(&**message);
+                self.close_block();
+                return;
+            }
+            Value::Bool(value) => write!(self.out, ": {}", value).unwrap(),
+            Value::I32(value) => write!(self.out, ": {}", value).unwrap(),
+            Value::I64(value) => write!(self.out, ": {}", value).unwrap(),
+            Value::U32(value) => write!(self.out, ": {}", value).unwrap(),
+            Value::U64(value) => write!(self.out, ": {}", value).unwrap(),
+            Value::F32(value) => write_float(&mut self.out, *value, f64::from(*value)),
+            Value::F64(value) => write_float(&mut self.out, *value, *value),
+            Value::String(value) => {
+                self.out.push_str(": \"");
+                for c in value.chars() {
+                    if c.is_ascii() {
+                        write_escaped(&mut self.out, c as u8);
+                    } else {
+                        self.out.push(c);
+                    }
+                }
+                self.out.push('"');
+            }
+            Value::Bytes(value) => {
+                self.out.push_str(": \"");
+                for &b in value {
+                    write_escaped(&mut self.out, b);
+                }
+                self.out.push('"');
+            }
+            Value::EnumNumber(number) => {
+                match field.enum_type().and_then(|desc| desc.value_name(*number)) {
+                    Some(name) => write!(self.out, ": {}", name).unwrap(),
+                    None => write!(self.out, ": {}", number).unwrap(),
+                }
+            }
+            // Nested lists and maps can't occur in well-formed messages.
+            Value::List(_) | Value::Map(_) => self.out.push_str(": []"),
+        }
+        self.end_line();
+    }
+
+    fn start_field(&mut self, name: &str) {
+        self.indent();
+        self.out.push_str(name);
+    }
+
+    fn open_block(&mut self) {
+        self.out.push_str(" {");
+        self.end_line();
+        self.indent += 1;
+    }
+
+    fn close_block(&mut self) {
+        self.indent -= 1;
+        self.indent();
+        self.out.push('}');
+        self.end_line();
+    }
+
+    fn indent(&mut self) {
+        if !self.options.single_line {
+            for _ in 0..self.indent {
+                self.out.push_str("  ");
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.out
+            .push(if self.options.single_line { ' ' } else { '\n' });
+    }
+}
+
+/// Writes a floating point number, using the text format names for the non-finite values.
+///
+/// Finite values are written as in JSON, which uses exponent notation for very large and very
+/// small magnitudes.
+fn write_float<F>(out: &mut String, value: F, wide: f64)
+where
+    F: fmt::Display + fmt::LowerExp,
+{
+    if wide.is_nan() {
+        out.push_str(": nan");
+    } else if wide == f64::INFINITY {
+        out.push_str(": inf");
+    } else if wide == f64::NEG_INFINITY {
+        out.push_str(": -inf");
+    } else {
+        out.push_str(": ");
+        write_finite_float(out, value, wide);
+    }
+}
+
+/// Writes a byte of a string literal, escaping it if it's not printable ASCII.
+pub(crate) fn write_escaped(out: &mut String, b: u8) {
+    match b {
+        b'\n' => out.push_str("\\n"),
+        b'\r' => out.push_str("\\r"),
+        b'\t' => out.push_str("\\t"),
+        b'"' => out.push_str("\\\""),
+        b'\'' => out.push_str("\\'"),
+        b'\\' => out.push_str("\\\\"),
+        0x20..=0x7E => out.push(char::from(b)),
+        _ => write!(out, "\\{:03o}", b).unwrap(),
+    }
+}
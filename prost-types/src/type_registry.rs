@@ -0,0 +1,165 @@
+use core::fmt;
+
+use prost::alloc::boxed::Box;
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::string::String;
+use prost::reflect::MessageDescriptor;
+use prost::{Message, Name, ReflectMessage};
+
+use crate::{Any, AnyError};
+
+/// A function which decodes the value of an [`Any`] as a particular message type.
+pub type DecodeFn = fn(&[u8]) -> Result<Box<dyn Message>, prost::DecodeError>;
+
+/// A registry of message types, used to decode [`Any`] values whose message type is only known at
+/// runtime.
+///
+/// Message types are registered by their fully qualified names, and looked up by the type name of
+/// the `Any`, which is the part of its type URL following the last `/`. `prost-build` can emit a
+/// `register_types` function for each package, which registers all of the package's messages, with
+/// `Config::type_registry`.
+///
+/// The registry can also hold the reflection descriptors of message types, which the [JSON
+/// mapping][crate::json] uses to resolve the message types of `Any` values.
+///
+/// ```rust
+/// use prost_types::{Any, Duration, Timestamp, TypeRegistry};
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<Duration>().register::<Timestamp>();
+///
+/// let any = Any::from_msg(&Duration { seconds: 1, nanos: 0 });
+/// let message = registry.decode(&any).unwrap();
+/// assert_eq!(format!("{:?}", message), "Duration { seconds: 1, nanos: 0 }");
+/// ```
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    types: BTreeMap<String, DecodeFn>,
+    descriptors: BTreeMap<&'static str, &'static MessageDescriptor>,
+}
+
+impl TypeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> TypeRegistry {
+        TypeRegistry::default()
+    }
+
+    /// Registers the message type `M`, replacing any type previously registered with the same
+    /// name.
+    pub fn register<M>(&mut self) -> &mut Self
+    where
+        M: Name + Default + 'static,
+    {
+        self.register_fn(M::full_name(), |buf| {
+            M::decode(buf).map(|message| Box::new(message) as Box<dyn Message>)
+        })
+    }
+
+    /// Registers a decode function for the message type with the fully qualified name
+    /// `full_name`, replacing any type previously registered with the same name.
+    pub fn register_fn<S>(&mut self, full_name: S, decode: DecodeFn) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.types.insert(full_name.into(), decode);
+        self
+    }
+
+    /// Registers the message type `M` along with its reflection descriptor.
+    pub fn register_reflect<M>(&mut self) -> &mut Self
+    where
+        M: ReflectMessage + Name + Default,
+    {
+        self.register::<M>()
+            .register_descriptor(M::type_descriptor())
+    }
+
+    /// Registers the reflection descriptor of a message type, replacing any descriptor previously
+    /// registered with the same name.
+    pub fn register_descriptor(&mut self, desc: &'static MessageDescriptor) -> &mut Self {
+        self.descriptors.insert(desc.full_name(), desc);
+        self
+    }
+
+    /// Registers the reflection descriptors of the well-known types, such as
+    /// `google.protobuf.Timestamp` and the wrapper types.
+    pub fn register_well_known_types(&mut self) -> &mut Self {
+        let descriptors = [
+            crate::Any::type_descriptor(),
+            crate::Duration::type_descriptor(),
+            crate::FieldMask::type_descriptor(),
+            crate::ListValue::type_descriptor(),
+            crate::Struct::type_descriptor(),
+            crate::Timestamp::type_descriptor(),
+            crate::Value::type_descriptor(),
+            <()>::type_descriptor(),
+            bool::type_descriptor(),
+            i32::type_descriptor(),
+            i64::type_descriptor(),
+            u32::type_descriptor(),
+            u64::type_descriptor(),
+            f32::type_descriptor(),
+            f64::type_descriptor(),
+            String::type_descriptor(),
+            <prost::alloc::vec::Vec<u8>>::type_descriptor(),
+        ];
+        for desc in descriptors {
+            self.register_descriptor(desc);
+        }
+        self
+    }
+
+    /// Returns the reflection descriptor of the message type with the fully qualified name, if
+    /// it's registered.
+    pub fn descriptor(&self, full_name: &str) -> Option<&'static MessageDescriptor> {
+        self.descriptors.get(full_name).copied()
+    }
+
+    /// Returns an iterator over the registered reflection descriptors, in order of their names.
+    pub fn descriptors(&self) -> impl Iterator<Item = &'static MessageDescriptor> + '_ {
+        self.descriptors.values().copied()
+    }
+
+    /// Returns `true` if a message type with the fully qualified name is registered.
+    pub fn contains(&self, full_name: &str) -> bool {
+        self.types.contains_key(full_name)
+    }
+
+    /// Returns an iterator over the fully qualified names of the registered message types, in
+    /// order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.types.keys().map(String::as_str)
+    }
+
+    /// Decodes the message contained in the `Any`.
+    ///
+    /// An error is returned if the message type is not registered, or if the value can't be
+    /// decoded.
+    pub fn decode(&self, any: &Any) -> Result<Box<dyn Message>, AnyError> {
+        let decode = self
+            .types
+            .get(any.type_name())
+            .ok_or_else(|| AnyError::UnknownType(any.type_url.clone()))?;
+        decode(&any.value).map_err(AnyError::Decode)
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.types.keys()).finish()
+    }
+}
+
+/// Finds the message type named by the type URL of an `Any`, i.e. the part following the last
+/// `/`.
+pub(crate) fn resolve_type_url(
+    message_types: &[&'static MessageDescriptor],
+    type_url: &str,
+) -> Option<&'static MessageDescriptor> {
+    let slash = type_url.rfind('/')?;
+    let full_name = &type_url[slash + 1..];
+    message_types
+        .iter()
+        .copied()
+        .find(|desc| desc.full_name() == full_name)
+}
@@ -0,0 +1,340 @@
+//! A `protoc` plugin which generates Rust code with `prost-build`.
+//!
+//! The plugin reads a `CodeGeneratorRequest` from stdin, and writes a `CodeGeneratorResponse`
+//! containing one file for each Protobuf package to stdout. The plugin parameters, which are
+//! described in the README, map onto `prost_build::Config` options.
+
+use std::io::{self, Read, Write};
+
+use prost::Message;
+use prost_build::{Config, Module};
+use prost_types::compiler::code_generator_response::{Feature, File};
+use prost_types::compiler::{CodeGeneratorRequest, CodeGeneratorResponse};
+
+fn main() -> io::Result<()> {
+    let mut buf = Vec::new();
+    io::stdin().read_to_end(&mut buf)?;
+    let request = CodeGeneratorRequest::decode(&*buf)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    let response = generate(request);
+
+    let mut stdout = io::stdout();
+    stdout.write_all(&response.encode_to_vec())?;
+    stdout.flush()
+}
+
+/// Generates the files for `request`. Errors are reported in the response, since `protoc`
+/// expects the plugin to exit successfully unless the request itself is invalid.
+fn generate(request: CodeGeneratorRequest) -> CodeGeneratorResponse {
+    let (file, error) = match try_generate(request) {
+        Ok(files) => (files, None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+    CodeGeneratorResponse {
+        error,
+        supported_features: Some(Feature::Proto3Optional as u64),
+        file,
+    }
+}
+
+fn try_generate(request: CodeGeneratorRequest) -> Result<Vec<File>, String> {
+    let mut options = Options::parse(request.parameter())?;
+
+    let CodeGeneratorRequest {
+        file_to_generate,
+        proto_file,
+        ..
+    } = request;
+    let requests = proto_file
+        .into_iter()
+        .filter(|file| file_to_generate.iter().any(|name| name == file.name()))
+        .map(|file| (Module::from_protobuf_package_name(file.package()), file))
+        .collect();
+    let mut modules: Vec<(Module, String)> = options
+        .config
+        .generate(requests)
+        .map_err(|error| error.to_string())?
+        .into_iter()
+        .collect();
+    modules.sort();
+
+    let mut files = Vec::new();
+    if let Some(include_file) = options.include_file {
+        let mut content = Vec::new();
+        let modules: Vec<&Module> = modules.iter().map(|(module, _)| module).collect();
+        options
+            .config
+            .write_include_file(&modules, &mut content)
+            .map_err(|error| error.to_string())?;
+        files.push(File {
+            name: Some(include_file),
+            content: Some(String::from_utf8(content).expect("include file is not UTF-8")),
+            ..File::default()
+        });
+    }
+    for (module, content) in modules {
+        files.push(File {
+            name: Some(module.to_file_name_or(&options.default_package_filename)),
+            content: Some(content),
+            ..File::default()
+        });
+    }
+    Ok(files)
+}
+
+/// The options set by the plugin parameters.
+struct Options {
+    config: Config,
+    default_package_filename: String,
+    include_file: Option<String>,
+}
+
+impl Options {
+    /// Parses the comma-separated plugin parameters.
+    fn parse(parameters: &str) -> Result<Options, String> {
+        let mut options = Options {
+            config: Config::new(),
+            default_package_filename: "_".to_string(),
+            include_file: None,
+        };
+        // The path options replace the paths set by earlier calls, so the paths of repeated
+        // parameters are collected and applied once.
+        let mut btree_map = Vec::new();
+        let mut bytes = Vec::new();
+        let mut disable_comments = Vec::new();
+        for parameter in split_parameters(parameters) {
+            let (key, value) = match parameter.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (&*parameter, None),
+            };
+            let config = &mut options.config;
+            match (key, value) {
+                ("btree_map", Some(path)) => btree_map.push(path.to_string()),
+                ("bytes", Some(path)) => bytes.push(path.to_string()),
+                ("disable_comments", Some(path)) => disable_comments.push(path.to_string()),
+                ("type_attribute", Some(value)) => {
+                    let (path, attribute) = split_pair(key, value)?;
+                    config.type_attribute(path, attribute);
+                }
+                ("field_attribute", Some(value)) => {
+                    let (path, attribute) = split_pair(key, value)?;
+                    config.field_attribute(path, attribute);
+                }
+                ("extern_path", Some(value)) => {
+                    let (proto_path, rust_path) = split_pair(key, value)?;
+                    config.extern_path(proto_path, rust_path);
+                }
+                ("default_package_filename", Some(name)) => {
+                    config.default_package_filename(name);
+                    options.default_package_filename = name.to_string();
+                }
+                ("include_file", Some(name)) => options.include_file = Some(name.to_string()),
+                ("compile_well_known_types", None) => {
+                    config.compile_well_known_types();
+                }
+                ("retain_enum_prefix", None) => {
+                    config.retain_enum_prefix();
+                }
+                (
+                    "btree_map"
+                    | "bytes"
+                    | "disable_comments"
+                    | "type_attribute"
+                    | "field_attribute"
+                    | "extern_path"
+                    | "default_package_filename"
+                    | "include_file",
+                    None,
+                ) => return Err(format!("parameter {} requires a value", key)),
+                ("compile_well_known_types" | "retain_enum_prefix", Some(_)) => {
+                    return Err(format!("parameter {} does not take a value", key))
+                }
+                _ => return Err(format!("unknown parameter: {}", key)),
+            }
+        }
+        options
+            .config
+            .btree_map(btree_map)
+            .bytes(bytes)
+            .disable_comments(disable_comments);
+        Ok(options)
+    }
+}
+
+/// Splits parameters on the commas which aren't escaped with a backslash.
+fn split_parameters(parameters: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut chars = parameters.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.as_str().starts_with(',') => {
+                current.push(',');
+                chars.next();
+            }
+            ',' => split.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    split.push(current);
+    split.retain(|parameter| !parameter.is_empty());
+    split
+}
+
+/// Splits a `<path>=<value>` parameter value.
+fn split_pair<'a>(key: &str, value: &'a str) -> Result<(&'a str, &'a str), String> {
+    value.split_once('=').ok_or_else(|| {
+        format!(
+            "parameter {} must be of the form {}=<path>=<value>",
+            key, key
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    use prost_types::FileDescriptorSet;
+
+    use super::*;
+
+    /// Returns a request to generate `files` from the `prost-build` test protos.
+    fn request(files: &[&str], parameter: &str) -> CodeGeneratorRequest {
+        request_in(Path::new("../prost-build/src"), files, parameter)
+    }
+
+    /// Returns a request to generate `files` from the protos in `include`.
+    fn request_in(include: &Path, files: &[&str], parameter: &str) -> CodeGeneratorRequest {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("descriptor-set");
+        let output = Command::new(prost_build::protoc())
+            .arg("--include_imports")
+            .arg("--include_source_info")
+            .arg("-o")
+            .arg(&path)
+            .arg("-I")
+            .arg(include)
+            .args(files)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let set = FileDescriptorSet::decode(&*fs::read(path).unwrap()).unwrap();
+        CodeGeneratorRequest {
+            file_to_generate: files.iter().map(|file| file.to_string()).collect(),
+            parameter: Some(parameter.to_string()),
+            proto_file: set.file,
+            compiler_version: None,
+        }
+    }
+
+    fn file<'a>(response: &'a CodeGeneratorResponse, name: &str) -> &'a str {
+        response
+            .file
+            .iter()
+            .find(|file| file.name() == name)
+            .unwrap_or_else(|| panic!("missing {}", name))
+            .content()
+    }
+
+    #[test]
+    fn generate_files() {
+        let response = generate(request(
+            &["hello.proto", "smoke_test.proto"],
+            "include_file=mod.rs",
+        ));
+        assert_eq!(response.error, None);
+        assert_eq!(
+            response.supported_features,
+            Some(Feature::Proto3Optional as u64)
+        );
+        let names: Vec<&str> = response.file.iter().map(|file| file.name()).collect();
+        assert_eq!(names, ["mod.rs", "helloworld.rs", "smoke_test.rs"]);
+
+        // Only the requested files are generated, so `types.proto` is left out.
+        assert!(!file(&response, "helloworld.rs").contains("pub struct Message"));
+        assert_eq!(
+            file(&response, "mod.rs"),
+            "pub mod helloworld {\n    include!(\"helloworld.rs\");\n}\n\
+             pub mod smoke_test {\n    include!(\"smoke_test.rs\");\n}\n"
+        );
+
+        let response = generate(request(
+            &["types.proto"],
+            "type_attribute=.helloworld.Message=#[derive(Eq\\, Hash)],\
+             field_attribute=.helloworld.Response.say=#[doc(hidden)]",
+        ));
+        let types = file(&response, "helloworld.rs");
+        assert!(types.contains(
+            "#[derive(Eq, Hash)]\n\
+             #[derive(Clone, PartialEq, ::prost::Message)]\n\
+             pub struct Message"
+        ));
+        assert!(types.contains("#[doc(hidden)]\n    pub say"));
+    }
+
+    #[test]
+    fn repeated_path_parameters() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join("maps.proto"),
+            "syntax = \"proto3\";\n\
+             package maps;\n\
+             message A { map<int32, int32> a = 1; }\n\
+             message B { map<int32, int32> b = 1; }\n\
+             message C { map<int32, int32> c = 1; }\n",
+        )
+        .unwrap();
+
+        let response = generate(request_in(
+            tmp.path(),
+            &["maps.proto"],
+            "btree_map=.maps.A,btree_map=.maps.B",
+        ));
+        assert_eq!(response.error, None);
+        let maps = file(&response, "maps.rs");
+        assert!(maps.contains("pub a: ::prost::alloc::collections::BTreeMap<i32, i32>"));
+        assert!(maps.contains("pub b: ::prost::alloc::collections::BTreeMap<i32, i32>"));
+        assert!(maps.contains("pub c: ::std::collections::HashMap<i32, i32>"));
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(
+            split_parameters("a=1,,b=x\\,y\\z,c"),
+            ["a=1", "b=x,y\\z", "c"]
+        );
+        assert!(split_parameters("").is_empty());
+
+        let errors = [
+            ("unknown=1", "unknown parameter: unknown"),
+            ("bytes", "parameter bytes requires a value"),
+            (
+                "type_attribute=.foo",
+                "parameter type_attribute must be of the form type_attribute=<path>=<value>",
+            ),
+            (
+                "retain_enum_prefix=true",
+                "parameter retain_enum_prefix does not take a value",
+            ),
+        ];
+        for (parameter, error) in errors.iter() {
+            let response = generate(request(&["types.proto"], parameter));
+            assert_eq!(response.error.as_deref(), Some(*error));
+            assert!(response.file.is_empty());
+        }
+
+        let response = generate(request(
+            &["types.proto"],
+            "default_package_filename=x,extern_path=.foo=::foo,retain_enum_prefix",
+        ));
+        assert_eq!(response.error, None);
+    }
+}
@@ -0,0 +1,252 @@
+//! A [`tokio-util`][1] codec for length-delimited messages.
+//!
+//! [`MessageCodec`] frames messages with the same varint length delimiter as
+//! [`Message::encode_length_delimited`], and can be used with `FramedRead`, `FramedWrite` and
+//! `Framed` to send and receive messages over an async byte stream:
+//!
+//! ```rust,ignore
+//! use futures::{SinkExt, StreamExt};
+//! use tokio_util::codec::Framed;
+//!
+//! let mut framed = Framed::new(socket, MessageCodec::<Request>::new());
+//! framed.send(response).await?;
+//! let request = framed.next().await.transpose()?;
+//! ```
+//!
+//! This module requires the `codec` feature.
+//!
+//! [1]: https://docs.rs/tokio-util
+
+use std::io;
+use std::marker::PhantomData;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{decode_length_delimiter, length_delimiter_len, Message};
+
+/// The default maximum length of a message, 8 MiB.
+const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// A codec which decodes length-delimited messages of type `M`, and encodes length-delimited
+/// messages of any type.
+///
+/// Messages longer than the maximum frame length are rejected with an error of kind
+/// [`io::ErrorKind::InvalidData`] when decoding, and [`io::ErrorKind::InvalidInput`] when
+/// encoding. A stream which ends inside a message results in an error of kind
+/// [`io::ErrorKind::UnexpectedEof`].
+#[derive(Debug)]
+pub struct MessageCodec<M> {
+    max_frame_len: usize,
+    /// The length of the message being decoded, once its length delimiter has been read.
+    frame_len: Option<usize>,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<M> MessageCodec<M> {
+    /// Creates a new codec, with a maximum frame length of 8 MiB.
+    pub fn new() -> MessageCodec<M> {
+        MessageCodec::with_max_frame_len(DEFAULT_MAX_FRAME_LEN)
+    }
+
+    /// Creates a new codec with the given maximum frame length.
+    ///
+    /// The length excludes the length delimiter.
+    pub fn with_max_frame_len(max_frame_len: usize) -> MessageCodec<M> {
+        MessageCodec {
+            max_frame_len,
+            frame_len: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the maximum frame length.
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+
+    /// Decodes the length delimiter at the start of `src`, returning `None` if it is incomplete.
+    fn decode_frame_len(&self, src: &mut BytesMut) -> io::Result<Option<usize>> {
+        // A varint is complete at its first byte without the continuation bit, and is invalid if
+        // it has none in its first 10 bytes.
+        let complete = src.iter().take(10).any(|&byte| byte < 0x80);
+        if !complete && src.len() < 10 {
+            return Ok(None);
+        }
+
+        let frame_len = decode_length_delimiter(&src[..])?;
+        if frame_len > self.max_frame_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "message of {} bytes exceeds the maximum frame length of {} bytes",
+                    frame_len, self.max_frame_len
+                ),
+            ));
+        }
+        src.advance(length_delimiter_len(frame_len));
+        Ok(Some(frame_len))
+    }
+}
+
+impl<M> Default for MessageCodec<M> {
+    fn default() -> MessageCodec<M> {
+        MessageCodec::new()
+    }
+}
+
+impl<M> Clone for MessageCodec<M> {
+    fn clone(&self) -> MessageCodec<M> {
+        MessageCodec::with_max_frame_len(self.max_frame_len)
+    }
+}
+
+impl<M> Decoder for MessageCodec<M>
+where
+    M: Message + Default,
+{
+    type Item = M;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<M>> {
+        let frame_len = match self.frame_len {
+            Some(frame_len) => frame_len,
+            None => match self.decode_frame_len(src)? {
+                Some(frame_len) => {
+                    self.frame_len = Some(frame_len);
+                    frame_len
+                }
+                None => return Ok(None),
+            },
+        };
+
+        if src.len() < frame_len {
+            // Make room for the rest of the message, so that it is read with as few reads as
+            // possible.
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+
+        self.frame_len = None;
+        let frame = src.split_to(frame_len).freeze();
+        Ok(Some(M::decode(frame)?))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<M>> {
+        match self.decode(src)? {
+            Some(message) => Ok(Some(message)),
+            None if src.is_empty() && self.frame_len.is_none() => Ok(None),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended inside a length-delimited message",
+            )),
+        }
+    }
+}
+
+impl<M, T> Encoder<T> for MessageCodec<M>
+where
+    T: Message,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> io::Result<()> {
+        let frame_len = item.encoded_len();
+        if frame_len > self.max_frame_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "message of {} bytes exceeds the maximum frame length of {} bytes",
+                    frame_len, self.max_frame_len
+                ),
+            ));
+        }
+        dst.reserve(length_delimiter_len(frame_len) + frame_len);
+        item.encode_length_delimited(dst)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    fn message(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn partial_frames() {
+        let mut codec = MessageCodec::<Vec<u8>>::new();
+        let mut encoded = BytesMut::new();
+        codec.encode(message(300), &mut encoded).unwrap();
+        codec.encode(message(1), &mut encoded).unwrap();
+
+        // Feed the encoded messages one byte at a time, including the two byte length delimiter of
+        // the first message.
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for &byte in encoded.iter() {
+            src.extend_from_slice(&[byte]);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                decoded.push(message);
+            }
+        }
+        assert_eq!(decoded, [message(300), message(1)]);
+        assert!(src.is_empty());
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+    }
+
+    #[test]
+    fn max_frame_len() {
+        let mut codec = MessageCodec::<Vec<u8>>::with_max_frame_len(10);
+        let mut dst = BytesMut::new();
+        let error = codec.encode(message(9), &mut dst).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(dst.is_empty());
+
+        let mut src = BytesMut::from(&[0x0B][..]);
+        let error = codec.decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut src = BytesMut::from(&[0xFF; 10][..]);
+        let error = codec.decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_frames() {
+        for src in [&[0x80][..], &[0x03, 0x0A]] {
+            let mut codec = MessageCodec::<Vec<u8>>::new();
+            let mut src = BytesMut::from(src);
+            let error = codec.decode_eof(&mut src).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[tokio::test]
+    async fn duplex() {
+        let (client, server) = tokio::io::duplex(64);
+        let messages = vec![message(0), message(100), message(1000)];
+
+        let sent = messages.clone();
+        let writer = tokio::spawn(async move {
+            let mut framed = FramedWrite::new(client, MessageCodec::<Vec<u8>>::new());
+            for message in sent {
+                framed.send(message).await.unwrap();
+            }
+            framed.into_inner().shutdown().await.unwrap();
+        });
+
+        let received = FramedRead::new(server, MessageCodec::<Vec<u8>>::new())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        writer.await.unwrap();
+        assert_eq!(received, messages);
+    }
+}
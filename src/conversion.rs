@@ -0,0 +1,86 @@
+use crate::DecodeError;
+
+/// A conversion between the Rust type of a field and the type of its wire representation.
+///
+/// Fields declared with a `convert` attribute, e.g. `#[prost(uint64, tag = "1", convert =
+/// "UserIdConversion")]`, hold a value of the [`Value`][Self::Value] type, which is converted to
+/// the [`Wire`][Self::Wire] type when the message is encoded, and converted back when the message
+/// is decoded. The wire type is the type the field would have without the attribute, e.g. `u64`,
+/// `Vec<u8>` or a message type. Optional and repeated fields hold an `Option` or a `Vec` of the
+/// value type.
+///
+/// The conversion is usually implemented by a marker type, which allows converting types defined
+/// in other crates, such as `uuid::Uuid` or `chrono::DateTime<Utc>`.
+///
+/// # Cost
+///
+/// Values are converted each time the message is encoded and each time its encoded length is
+/// computed, so [`Message::encode_to_vec`][1] converts every value twice. Conversions should be
+/// cheap, or the value should hold its wire representation and return it from
+/// [`to_wire_ref`][Self::to_wire_ref], which lets the field be encoded without converting.
+/// Packed repeated fields are the exception: they're always converted into a temporary `Vec`,
+/// since their length is encoded before the values.
+///
+/// When decoding, each occurrence of a converted message or group field converts the existing
+/// value to its wire representation, merges the decoded message into it and converts the result
+/// back. Decoded scalar values replace the existing value without converting it.
+///
+/// [1]: crate::Message::encode_to_vec
+///
+/// # Examples
+///
+/// ```rust
+/// use prost::{Conversion, DecodeError, Message};
+///
+/// #[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// pub struct UserId(u64);
+///
+/// pub struct UserIdConversion;
+///
+/// impl Conversion for UserIdConversion {
+///     type Value = UserId;
+///     type Wire = u64;
+///
+///     fn to_wire(value: &UserId) -> u64 {
+///         value.0
+///     }
+///
+///     fn from_wire(wire: u64) -> Result<UserId, DecodeError> {
+///         Ok(UserId(wire))
+///     }
+/// }
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// pub struct User {
+///     #[prost(uint64, tag = "1", convert = "UserIdConversion")]
+///     pub id: UserId,
+/// }
+///
+/// let user = User { id: UserId(42) };
+/// assert_eq!(User::decode(&*user.encode_to_vec()).unwrap(), user);
+/// ```
+pub trait Conversion {
+    /// The Rust type of the field.
+    type Value;
+
+    /// The type of the field's wire representation.
+    type Wire;
+
+    /// Converts a value to its wire representation.
+    fn to_wire(value: &Self::Value) -> Self::Wire;
+
+    /// Returns a reference to the wire representation of a value, if the value holds it.
+    ///
+    /// Values for which this returns `Some` are encoded without calling
+    /// [`to_wire`][Self::to_wire]. The default implementation returns `None`.
+    fn to_wire_ref(value: &Self::Value) -> Option<&Self::Wire> {
+        let _ = value;
+        None
+    }
+
+    /// Converts a decoded wire representation to a value.
+    ///
+    /// Values which can't be represented by the Rust type should result in an error, which fails
+    /// decoding of the message.
+    fn from_wire(wire: Self::Wire) -> Result<Self::Value, DecodeError>;
+}
@@ -0,0 +1,282 @@
+//! Field-by-field comparison of Protobuf messages.
+//!
+//! Messages which implement [`DiffMessage`] report each of their fields, along with the same
+//! field of another instance of the message, to a [`Differ`]. The values are passed as
+//! [`ValueRef`]s, which borrow the strings, bytes and messages of the fields, and nested messages
+//! are passed as `&dyn DiffMessage` so that the differ can compare their fields in turn.
+//! `prost_types::diff` uses this to report the fields which differ between two messages.
+//!
+//! `DiffMessage` is typically derived alongside `Message`, and `prost-build` emits the derive for
+//! the messages selected with `Config::diff`. Unlike [`ReflectMessage`][crate::ReflectMessage],
+//! it doesn't need descriptors: fields are named by their `name` attribute, or by their Rust
+//! identifier.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::any::Any;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::Conversion;
+
+/// A Protobuf message which can be compared field by field with another instance of its type.
+pub trait DiffMessage: fmt::Debug + Send + Sync + Any {
+    /// Reports each field of the message to `differ`, along with the same field of `other`, in
+    /// the order the fields are declared.
+    ///
+    /// The value of a field is `None` if it isn't set: fields without presence are set if they
+    /// differ from their default value, and repeated and map fields are set if they're not empty.
+    /// Each field of a `oneof` is reported separately. Nothing is reported if `other` is a
+    /// message of a different type.
+    fn diff_fields(&self, other: &dyn DiffMessage, differ: &mut dyn Differ);
+
+    /// Returns a boxed copy of the message.
+    fn clone_boxed(&self) -> Box<dyn DiffMessage>;
+
+    /// Returns `true` if `other` is a message of the same type with equal field values.
+    fn eq_dyn(&self, other: &dyn DiffMessage) -> bool;
+
+    /// Converts the message to `&dyn Any`.
+    ///
+    /// Meant to be used only by `DiffMessage` implementations; use `downcast_ref` instead.
+    #[doc(hidden)]
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl dyn DiffMessage {
+    /// Returns a reference to the message if it is of type `M`.
+    pub fn downcast_ref<M: DiffMessage>(&self) -> Option<&M> {
+        self.as_any().downcast_ref()
+    }
+}
+
+impl<M> DiffMessage for Box<M>
+where
+    M: DiffMessage,
+{
+    fn diff_fields(&self, other: &dyn DiffMessage, differ: &mut dyn Differ) {
+        (**self).diff_fields(other, differ)
+    }
+    fn clone_boxed(&self) -> Box<dyn DiffMessage> {
+        (**self).clone_boxed()
+    }
+    fn eq_dyn(&self, other: &dyn DiffMessage) -> bool {
+        (**self).eq_dyn(other)
+    }
+    fn as_any(&self) -> &dyn Any {
+        (**self).as_any()
+    }
+}
+
+/// Compares the fields reported by [`DiffMessage::diff_fields`].
+pub trait Differ {
+    /// Compares the values of the field `name` in the old and the new message, which are `None`
+    /// if the field isn't set in that message.
+    fn diff_field(
+        &mut self,
+        name: &'static str,
+        old: Option<ValueRef<'_>>,
+        new: Option<ValueRef<'_>>,
+    );
+}
+
+/// The oneof half of the `DiffMessage` derive.
+///
+/// Meant to be used only by `DiffMessage` implementations.
+#[doc(hidden)]
+pub trait DiffOneof {
+    /// Reports each field of the oneof to `differ`, with its value in `old` and in `new`.
+    fn diff_oneof(old: Option<&Self>, new: Option<&Self>, differ: &mut dyn Differ);
+}
+
+/// The wire representation of a value of a converted field, which is borrowed from the value if
+/// the conversion supports it.
+///
+/// Meant to be used only by `DiffMessage` implementations.
+#[doc(hidden)]
+pub enum Wire<'a, T> {
+    Borrowed(&'a T),
+    Converted(T),
+}
+
+impl<'a, T> Wire<'a, T> {
+    pub fn new<C>(value: &'a C::Value) -> Wire<'a, T>
+    where
+        C: Conversion<Wire = T>,
+    {
+        match C::to_wire_ref(value) {
+            Some(wire) => Wire::Borrowed(wire),
+            None => Wire::Converted(C::to_wire(value)),
+        }
+    }
+}
+
+impl<T> Deref for Wire<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match *self {
+            Wire::Borrowed(wire) => wire,
+            Wire::Converted(ref wire) => wire,
+        }
+    }
+}
+
+/// A field value reported by [`DiffMessage::diff_fields`], copied out of its message.
+///
+/// The variants match those of [`reflect::Value`][crate::reflect::Value], except that messages
+/// only need to implement [`DiffMessage`].
+#[derive(Debug)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    EnumNumber(i32),
+    Message(Box<dyn DiffMessage>),
+    /// The values of a repeated field.
+    List(Vec<Value>),
+    /// The entries of a map field.
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    /// Returns a boxed copy of the message as a value.
+    pub fn message<M>(message: &M) -> Value
+    where
+        M: DiffMessage,
+    {
+        Value::Message(message.clone_boxed())
+    }
+
+    /// Returns the message if the value holds a message of type `M`.
+    pub fn as_message<M>(&self) -> Option<&M>
+    where
+        M: DiffMessage,
+    {
+        match *self {
+            Value::Message(ref message) => message.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Value {
+        match *self {
+            Value::Bool(value) => Value::Bool(value),
+            Value::I32(value) => Value::I32(value),
+            Value::I64(value) => Value::I64(value),
+            Value::U32(value) => Value::U32(value),
+            Value::U64(value) => Value::U64(value),
+            Value::F32(value) => Value::F32(value),
+            Value::F64(value) => Value::F64(value),
+            Value::String(ref value) => Value::String(value.clone()),
+            Value::Bytes(ref value) => Value::Bytes(value.clone()),
+            Value::EnumNumber(value) => Value::EnumNumber(value),
+            Value::Message(ref value) => Value::Message(value.clone_boxed()),
+            Value::List(ref value) => Value::List(value.clone()),
+            Value::Map(ref value) => Value::Map(value.clone()),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::U32(a), Value::U32(b)) => a == b,
+            (Value::U64(a), Value::U64(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a == b,
+            (Value::F64(a), Value::F64(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::EnumNumber(a), Value::EnumNumber(b)) => a == b,
+            (Value::Message(a), Value::Message(b)) => a.eq_dyn(&**b),
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// A borrowed view of a field value, passed to [`Differ::diff_field`].
+///
+/// The variants match those of [`Value`], except that strings, bytes and messages are borrowed
+/// from the message which holds the field.
+#[derive(Clone, Debug)]
+pub enum ValueRef<'a> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    EnumNumber(i32),
+    Message(&'a dyn DiffMessage),
+    /// The values of a repeated field.
+    List(Vec<ValueRef<'a>>),
+    /// The entries of a map field.
+    Map(Vec<(ValueRef<'a>, ValueRef<'a>)>),
+}
+
+impl ValueRef<'_> {
+    /// Returns an owned copy of the value.
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::Bool(value) => Value::Bool(value),
+            ValueRef::I32(value) => Value::I32(value),
+            ValueRef::I64(value) => Value::I64(value),
+            ValueRef::U32(value) => Value::U32(value),
+            ValueRef::U64(value) => Value::U64(value),
+            ValueRef::F32(value) => Value::F32(value),
+            ValueRef::F64(value) => Value::F64(value),
+            ValueRef::String(value) => Value::String(value.into()),
+            ValueRef::Bytes(value) => Value::Bytes(value.to_vec()),
+            ValueRef::EnumNumber(value) => Value::EnumNumber(value),
+            ValueRef::Message(value) => Value::Message(value.clone_boxed()),
+            ValueRef::List(ref values) => {
+                Value::List(values.iter().map(ValueRef::to_value).collect())
+            }
+            ValueRef::Map(ref entries) => Value::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_value(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PartialEq for ValueRef<'_> {
+    fn eq(&self, other: &ValueRef<'_>) -> bool {
+        match (self, other) {
+            (ValueRef::Bool(a), ValueRef::Bool(b)) => a == b,
+            (ValueRef::I32(a), ValueRef::I32(b)) => a == b,
+            (ValueRef::I64(a), ValueRef::I64(b)) => a == b,
+            (ValueRef::U32(a), ValueRef::U32(b)) => a == b,
+            (ValueRef::U64(a), ValueRef::U64(b)) => a == b,
+            (ValueRef::F32(a), ValueRef::F32(b)) => a == b,
+            (ValueRef::F64(a), ValueRef::F64(b)) => a == b,
+            (ValueRef::String(a), ValueRef::String(b)) => a == b,
+            (ValueRef::Bytes(a), ValueRef::Bytes(b)) => a == b,
+            (ValueRef::EnumNumber(a), ValueRef::EnumNumber(b)) => a == b,
+            (ValueRef::Message(a), ValueRef::Message(b)) => a.eq_dyn(*b),
+            (ValueRef::List(a), ValueRef::List(b)) => a == b,
+            (ValueRef::Map(a), ValueRef::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
@@ -4,6 +4,7 @@
 
 #![allow(clippy::implicit_hasher, clippy::ptr_arg)]
 
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::String;
@@ -15,10 +16,11 @@ use core::str;
 use core::u32;
 use core::usize;
 
-use ::bytes::{Buf, BufMut, Bytes};
+use ::bytes::{Buf, BufMut, Bytes, BytesMut};
 
-use crate::DecodeError;
+use crate::projection::Projection;
 use crate::Message;
+use crate::{DecodeError, DecodeErrorKind};
 
 /// Encodes an integer value into LEB128 variable length format, and writes it to the buffer.
 /// The buffer must have enough remaining space (maximum 10 bytes).
@@ -47,7 +49,10 @@ where
     let bytes = buf.chunk();
     let len = bytes.len();
     if len == 0 {
-        return Err(DecodeError::new("invalid varint"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidVarint,
+            "invalid varint",
+        ));
     }
 
     let byte = bytes[0];
@@ -152,7 +157,10 @@ fn decode_varint_slice(bytes: &[u8]) -> Result<(u64, usize), DecodeError> {
 
     // We have overrun the maximum size of a varint (10 bytes) or the final byte caused an overflow.
     // Assume the data is corrupt.
-    Err(DecodeError::new("invalid varint"))
+    Err(DecodeError::with_kind(
+        DecodeErrorKind::InvalidVarint,
+        "invalid varint",
+    ))
 }
 
 /// Decodes a LEB128-encoded variable length integer from the buffer, advancing the buffer as
@@ -175,14 +183,119 @@ where
             // Check for u64::MAX overflow. See [`ConsumeVarint`][1] for details.
             // [1]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
             if count == 9 && byte >= 0x02 {
-                return Err(DecodeError::new("invalid varint"));
+                return Err(DecodeError::with_kind(
+                    DecodeErrorKind::InvalidVarint,
+                    "invalid varint",
+                ));
             } else {
                 return Ok(value);
             }
         }
     }
 
-    Err(DecodeError::new("invalid varint"))
+    Err(DecodeError::with_kind(
+        DecodeErrorKind::InvalidVarint,
+        "invalid varint",
+    ))
+}
+
+/// Limits and checks applied while decoding a message.
+///
+/// The default options apply a recursion limit of 100 nested messages (unless the Prost crate is
+/// built with the `no-recursion-limit` feature), and no other limits or checks. Options are passed
+/// to [`Message::decode_with_options`] or [`Message::merge_with_options`], and are applied to every
+/// nested message, string, bytes, repeated and map field in the decoded message.
+///
+/// ```rust
+/// # use prost::{DecodeOptions, Message};
+/// let options = DecodeOptions::new()
+///     .recursion_limit(16)
+///     .max_message_size(64 * 1024)
+///     .max_length(4096)
+///     .max_repeated_len(1024)
+///     .check_required_fields(true);
+/// # let buf: &[u8] = &[];
+/// let value = <()>::decode_with_options(buf, options).unwrap();
+/// ```
+///
+/// [`Message::decode_with_options`]: crate::Message::decode_with_options
+/// [`Message::merge_with_options`]: crate::Message::merge_with_options
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeOptions {
+    recursion_limit: u32,
+    max_message_size: usize,
+    max_length: usize,
+    max_repeated_len: usize,
+    check_required_fields: bool,
+}
+
+impl Default for DecodeOptions {
+    #[inline]
+    fn default() -> DecodeOptions {
+        DecodeOptions {
+            #[cfg(not(feature = "no-recursion-limit"))]
+            recursion_limit: crate::RECURSION_LIMIT,
+            #[cfg(feature = "no-recursion-limit")]
+            recursion_limit: u32::MAX,
+            max_message_size: usize::MAX,
+            max_length: usize::MAX,
+            max_repeated_len: usize::MAX,
+            check_required_fields: false,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Creates the default decode options.
+    pub fn new() -> DecodeOptions {
+        DecodeOptions::default()
+    }
+
+    /// Sets the maximum number of nested messages, groups and map entries which may be decoded.
+    ///
+    /// This limit has no effect when the Prost crate is built with the `no-recursion-limit`
+    /// feature.
+    pub fn recursion_limit(mut self, limit: u32) -> DecodeOptions {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of the encoded message being decoded.
+    pub fn max_message_size(mut self, size: usize) -> DecodeOptions {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of any single `string` or `bytes` field.
+    pub fn max_length(mut self, len: usize) -> DecodeOptions {
+        self.max_length = len;
+        self
+    }
+
+    /// Sets the maximum number of elements in any single repeated or map field.
+    pub fn max_repeated_len(mut self, len: usize) -> DecodeOptions {
+        self.max_repeated_len = len;
+        self
+    }
+
+    /// Sets whether proto2 `required` fields must be present in the encoded message.
+    ///
+    /// When enabled, decoding fails if the encoded data of the message, or of any nested message,
+    /// group or map entry value, doesn't contain one of its required fields. The error names the
+    /// missing field, and the fields of the enclosing messages which lead to it.
+    ///
+    /// Required fields are decoded into plain values, so a missing field is otherwise decoded as
+    /// its default value. For the same reason, a message in memory always holds its required
+    /// fields, so there's nothing to check outside of decoding.
+    ///
+    /// The fields of each message are checked once the whole message has been decoded, so a
+    /// nested message may be split across several occurrences of its field. When merging into an
+    /// existing message, the fields of the message, and of the nested messages it already held,
+    /// are present as well.
+    pub fn check_required_fields(mut self, check: bool) -> DecodeOptions {
+        self.check_required_fields = check;
+        self
+    }
 }
 
 /// Additional information passed to every decode/merge function.
@@ -190,29 +303,44 @@ where
 /// The context should be passed by value and can be freely cloned. When passing
 /// to a function which is decoding a nested object, then use `enter_recursion`.
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "no-recursion-limit", derive(Default))]
 pub struct DecodeContext {
     /// How many times we can recurse in the current decode stack before we hit
     /// the recursion limit.
     ///
-    /// The recursion limit is defined by `RECURSION_LIMIT` and cannot be
-    /// customized. The recursion limit can be ignored by building the Prost
-    /// crate with the `no-recursion-limit` feature.
+    /// The recursion limit is defined by `DecodeOptions::recursion_limit`, and
+    /// defaults to `RECURSION_LIMIT`. The recursion limit can be ignored by
+    /// building the Prost crate with the `no-recursion-limit` feature.
     #[cfg(not(feature = "no-recursion-limit"))]
     recurse_count: u32,
+
+    /// The limits which apply to the current decode.
+    options: DecodeOptions,
 }
 
-#[cfg(not(feature = "no-recursion-limit"))]
 impl Default for DecodeContext {
     #[inline]
     fn default() -> DecodeContext {
-        DecodeContext {
-            recurse_count: crate::RECURSION_LIMIT,
-        }
+        DecodeContext::new(DecodeOptions::default())
     }
 }
 
 impl DecodeContext {
+    /// Creates a new context which applies the provided decode options.
+    #[inline]
+    pub fn new(options: DecodeOptions) -> DecodeContext {
+        DecodeContext {
+            #[cfg(not(feature = "no-recursion-limit"))]
+            recurse_count: options.recursion_limit,
+            options,
+        }
+    }
+
+    /// Returns the decode options which apply to this context.
+    #[inline]
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
     /// Call this function before recursively decoding.
     ///
     /// There is no `exit` function since this function creates a new `DecodeContext`
@@ -223,13 +351,14 @@ impl DecodeContext {
     pub(crate) fn enter_recursion(&self) -> DecodeContext {
         DecodeContext {
             recurse_count: self.recurse_count - 1,
+            options: self.options,
         }
     }
 
     #[cfg(feature = "no-recursion-limit")]
     #[inline]
     pub(crate) fn enter_recursion(&self) -> DecodeContext {
-        DecodeContext {}
+        self.clone()
     }
 
     /// Checks whether the recursion limit has been reached in the stack of
@@ -241,7 +370,10 @@ impl DecodeContext {
     #[inline]
     pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
         if self.recurse_count == 0 {
-            Err(DecodeError::new("recursion limit reached"))
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::RecursionLimitReached,
+                "recursion limit reached",
+            ))
         } else {
             Ok(())
         }
@@ -253,6 +385,141 @@ impl DecodeContext {
     pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
         Ok(())
     }
+
+    /// Checks that an encoded message of `size` bytes is within the message size limit.
+    #[inline]
+    pub(crate) fn check_message_size(&self, size: usize) -> Result<(), DecodeError> {
+        if size > self.options.max_message_size {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::LimitExceeded,
+                "message size limit exceeded",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that a `string` or `bytes` value of `len` bytes is within the length limit.
+    #[inline]
+    pub(crate) fn check_length(&self, len: u64) -> Result<(), DecodeError> {
+        if len > self.options.max_length as u64 {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::LimitExceeded,
+                "string or bytes length limit exceeded",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that a repeated or map field with `len` elements is within the element count limit.
+    #[inline]
+    pub(crate) fn check_repeated_len(&self, len: usize) -> Result<(), DecodeError> {
+        if len > self.options.max_repeated_len {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::LimitExceeded,
+                "repeated field length limit exceeded",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The fields decoded for a message and for the singular message fields nested in it, which are
+/// recorded when the decode options require required fields to be checked.
+///
+/// Presence is tracked across every occurrence of a message in the encoded data, so that its
+/// required fields are checked once, after the whole message has been decoded.
+///
+/// Meant to be used only by `Message` implementations.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct FieldPresence {
+    /// Whether the message held its fields before the decode, rather than being created by it.
+    existing: bool,
+    /// The numbers of the decoded fields, in ascending order.
+    numbers: Vec<u32>,
+    /// The presence of the decoded singular message fields, by field number.
+    nested: Vec<(u32, FieldPresence)>,
+}
+
+impl FieldPresence {
+    /// Creates the presence of a message which has no decoded fields yet.
+    #[inline]
+    pub fn new(existing: bool) -> FieldPresence {
+        FieldPresence {
+            existing,
+            numbers: Vec::new(),
+            nested: Vec::new(),
+        }
+    }
+
+    /// Returns the presence to track for a message, if the decode options require its required
+    /// fields to be checked.
+    #[inline]
+    pub(crate) fn track(ctx: &DecodeContext, existing: bool) -> Option<FieldPresence> {
+        if ctx.options.check_required_fields {
+            Some(FieldPresence::new(existing))
+        } else {
+            None
+        }
+    }
+
+    /// Records that the field with the given number was decoded.
+    #[inline]
+    pub fn record(&mut self, number: u32) {
+        if let Err(index) = self.numbers.binary_search(&number) {
+            self.numbers.insert(index, number);
+        }
+    }
+
+    /// Returns the presence of a singular message field, to record the fields decoded for it.
+    ///
+    /// `set` is whether the field held a message before this occurrence was decoded. The recorded
+    /// presence is reset if it didn't, since the message was created by this occurrence.
+    pub fn nested_mut(&mut self, number: u32, set: bool) -> &mut FieldPresence {
+        let existing = self.existing && set;
+        let index = match self.nested.iter().position(|&(n, _)| n == number) {
+            Some(index) => {
+                if !set {
+                    self.nested[index].1 = FieldPresence::new(existing);
+                }
+                index
+            }
+            None => {
+                self.nested.push((number, FieldPresence::new(existing)));
+                self.nested.len() - 1
+            }
+        };
+        &mut self.nested[index].1
+    }
+
+    /// Checks that a required field is present: the field is present if it was decoded, or if
+    /// the message held its fields before the decode.
+    #[inline]
+    pub fn require(presence: &FieldPresence, number: u32) -> Result<(), DecodeError> {
+        if presence.existing || presence.numbers.binary_search(&number).is_ok() {
+            Ok(())
+        } else {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::MissingRequiredField,
+                "missing required field",
+            ))
+        }
+    }
+
+    /// Returns the recorded presence of a singular message field, or `None` if no message was
+    /// decoded for it. The required fields of a message which wasn't decoded aren't checked,
+    /// since a message in memory always holds them.
+    #[inline]
+    pub fn nested(presence: &FieldPresence, number: u32) -> Option<&FieldPresence> {
+        presence
+            .nested
+            .iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, nested)| nested)
+    }
 }
 
 /// Returns the encoded length of the value in LEB128 variable length format.
@@ -290,10 +557,10 @@ impl TryFrom<u64> for WireType {
             3 => Ok(WireType::StartGroup),
             4 => Ok(WireType::EndGroup),
             5 => Ok(WireType::ThirtyTwoBit),
-            _ => Err(DecodeError::new(format!(
-                "invalid wire type value: {}",
-                value
-            ))),
+            _ => Err(DecodeError::with_kind(
+                DecodeErrorKind::InvalidWireType,
+                format!("invalid wire type value: {}", value),
+            )),
         }
     }
 }
@@ -319,13 +586,19 @@ where
 {
     let key = decode_varint(buf)?;
     if key > u64::from(u32::MAX) {
-        return Err(DecodeError::new(format!("invalid key value: {}", key)));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidTag,
+            format!("invalid key value: {}", key),
+        ));
     }
     let wire_type = WireType::try_from(key & 0x07)?;
     let tag = key as u32 >> 3;
 
     if tag < MIN_TAG {
-        return Err(DecodeError::new("invalid tag value: 0"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidTag,
+            "invalid tag value: 0",
+        ));
     }
 
     Ok((tag, wire_type))
@@ -343,10 +616,10 @@ pub fn key_len(tag: u32) -> usize {
 #[inline]
 pub fn check_wire_type(expected: WireType, actual: WireType) -> Result<(), DecodeError> {
     if expected != actual {
-        return Err(DecodeError::new(format!(
-            "invalid wire type: {:?} (expected {:?})",
-            actual, expected
-        )));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidWireType,
+            format!("invalid wire type: {:?} (expected {:?})", actual, expected),
+        ));
     }
     Ok(())
 }
@@ -366,7 +639,10 @@ where
     let len = decode_varint(buf)?;
     let remaining = buf.remaining();
     if len > remaining as u64 {
-        return Err(DecodeError::new("buffer underflow"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::BufferUnderflow,
+            "buffer underflow",
+        ));
     }
 
     let limit = remaining - len as usize;
@@ -375,7 +651,10 @@ where
     }
 
     if buf.remaining() != limit {
-        return Err(DecodeError::new("delimited length exceeded"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
+            "delimited length exceeded",
+        ));
     }
     Ok(())
 }
@@ -400,18 +679,29 @@ where
             match inner_wire_type {
                 WireType::EndGroup => {
                     if inner_tag != tag {
-                        return Err(DecodeError::new("unexpected end group tag"));
+                        return Err(DecodeError::with_kind(
+                            DecodeErrorKind::UnexpectedEndGroup,
+                            "unexpected end group tag",
+                        ));
                     }
                     break 0;
                 }
                 _ => skip_field(inner_wire_type, inner_tag, buf, ctx.enter_recursion())?,
             }
         },
-        WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+        WireType::EndGroup => {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::UnexpectedEndGroup,
+                "unexpected end group tag",
+            ))
+        }
     };
 
     if len > buf.remaining() as u64 {
-        return Err(DecodeError::new("buffer underflow"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::BufferUnderflow,
+            "buffer underflow",
+        ));
     }
 
     buf.advance(len as usize);
@@ -450,6 +740,7 @@ macro_rules! merge_repeated_numeric {
             if wire_type == WireType::LengthDelimited {
                 // Packed.
                 merge_loop(values, buf, ctx, |values, buf, ctx| {
+                    ctx.check_repeated_len(values.len() + 1)?;
                     let mut value = Default::default();
                     $merge($wire_type, &mut value, buf, ctx)?;
                     values.push(value);
@@ -458,6 +749,7 @@ macro_rules! merge_repeated_numeric {
             } else {
                 // Unpacked.
                 check_wire_type($wire_type, wire_type)?;
+                ctx.check_repeated_len(values.len() + 1)?;
                 let mut value = Default::default();
                 $merge(wire_type, &mut value, buf, ctx)?;
                 values.push(value);
@@ -628,7 +920,10 @@ macro_rules! fixed_width {
             {
                 check_wire_type($wire_type, wire_type)?;
                 if buf.remaining() < $width {
-                    return Err(DecodeError::new("buffer underflow"));
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::BufferUnderflow,
+                        "buffer underflow",
+                    ));
                 }
                 *value = buf.$get();
                 Ok(())
@@ -769,6 +1064,7 @@ macro_rules! length_delimited {
             B: Buf,
         {
             check_wire_type(WireType::LengthDelimited, wire_type)?;
+            ctx.check_repeated_len(values.len() + 1)?;
             let mut value = Default::default();
             merge(wire_type, &mut value, buf, ctx)?;
             values.push(value);
@@ -791,29 +1087,81 @@ macro_rules! length_delimited {
     };
 }
 
-pub mod string {
-    use super::*;
+/// A type which can be used for Protobuf `string` fields.
+///
+/// `StringAdapter` is implemented for `String` and `Box<str>`. Other string types, such as
+/// `Arc<str>` or a string type from another crate, can be used by implementing it for a newtype
+/// wrapper.
+pub trait StringAdapter: Default + AsRef<str> + Sized + 'static {
+    /// Replaces the contents of this string with `value`.
+    fn replace_with(&mut self, value: &str);
+
+    /// Returns the length of this string, in bytes.
+    fn len(&self) -> usize {
+        self.as_ref().len()
+    }
+
+    /// Returns `true` if this string is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-    pub fn encode<B>(tag: u32, value: &String, buf: &mut B)
+    /// Replaces the contents of this string with the next `len` bytes of `buf`, which hold at
+    /// least `len` bytes, returning an error if they aren't valid UTF-8.
+    ///
+    /// Implementations must consume exactly `len` bytes of `buf`, and leave the string empty if
+    /// the bytes aren't valid UTF-8. The default implementation checks the bytes in place if
+    /// they're contiguous in `buf`, and otherwise copies them out first, and then calls
+    /// [`replace_with`][Self::replace_with]. It can be overridden to decode directly into the
+    /// string's own storage.
+    fn merge_from<B>(&mut self, len: usize, buf: &mut B) -> Result<(), DecodeError>
     where
-        B: BufMut,
+        B: Buf,
     {
-        encode_key(tag, WireType::LengthDelimited, buf);
-        encode_varint(value.len() as u64, buf);
-        buf.put_slice(value.as_bytes());
+        let chunk = buf.chunk();
+        if chunk.len() >= len {
+            let result = replace_with_utf8(self, &chunk[..len]);
+            buf.advance(len);
+            result
+        } else {
+            replace_with_utf8(self, &buf.copy_to_bytes(len))
+        }
     }
-    pub fn merge<B>(
-        wire_type: WireType,
-        value: &mut String,
-        buf: &mut B,
-        ctx: DecodeContext,
-    ) -> Result<(), DecodeError>
+}
+
+/// Replaces the contents of a string with `bytes`, or clears it if they aren't valid UTF-8.
+fn replace_with_utf8<S>(value: &mut S, bytes: &[u8]) -> Result<(), DecodeError>
+where
+    S: StringAdapter,
+{
+    match str::from_utf8(bytes) {
+        Ok(s) => {
+            value.replace_with(s);
+            Ok(())
+        }
+        Err(_) => {
+            value.replace_with("");
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::InvalidUtf8,
+                "invalid string value: data is not UTF-8 encoded",
+            ))
+        }
+    }
+}
+
+impl StringAdapter for String {
+    fn replace_with(&mut self, value: &str) {
+        self.clear();
+        self.push_str(value);
+    }
+
+    fn merge_from<B>(&mut self, len: usize, buf: &mut B) -> Result<(), DecodeError>
     where
         B: Buf,
     {
         // ## Unsafety
         //
-        // `string::merge` reuses `bytes::merge`, with an additional check of utf-8
+        // The bytes are copied directly into the string's buffer, followed by a check of utf-8
         // well-formedness. If the utf-8 is not well-formed, or if any other error occurs, then the
         // string is cleared, so as to avoid leaking a string field with invalid data.
         //
@@ -822,8 +1170,8 @@ pub mod string {
         // in up to 10% better performance on the protobuf message decoding benchmarks.
         //
         // It's required when using `String::as_mut_vec` that invalid utf-8 data not be leaked into
-        // the backing `String`. To enforce this, even in the event of a panic in `bytes::merge` or
-        // in the buf implementation, a drop guard is used.
+        // the backing `String`. To enforce this, even in the event of a panic in the buf
+        // implementation, a drop guard is used.
         unsafe {
             struct DropGuard<'a>(&'a mut Vec<u8>);
             impl<'a> Drop for DropGuard<'a> {
@@ -833,22 +1181,65 @@ pub mod string {
                 }
             }
 
-            let drop_guard = DropGuard(value.as_mut_vec());
-            bytes::merge_one_copy(wire_type, drop_guard.0, buf, ctx)?;
+            let drop_guard = DropGuard(self.as_mut_vec());
+            BytesAdapter::replace_with(drop_guard.0, buf.take(len));
             match str::from_utf8(drop_guard.0) {
                 Ok(_) => {
                     // Success; do not clear the bytes.
                     mem::forget(drop_guard);
                     Ok(())
                 }
-                Err(_) => Err(DecodeError::new(
+                Err(_) => Err(DecodeError::with_kind(
+                    DecodeErrorKind::InvalidUtf8,
                     "invalid string value: data is not UTF-8 encoded",
                 )),
             }
         }
     }
+}
+
+impl StringAdapter for Box<str> {
+    fn replace_with(&mut self, value: &str) {
+        *self = value.into();
+    }
+}
+
+pub mod string {
+    use super::*;
+
+    pub fn encode<S, B>(tag: u32, value: &S, buf: &mut B)
+    where
+        S: StringAdapter,
+        B: BufMut,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(value.len() as u64, buf);
+        buf.put_slice(value.as_ref().as_bytes());
+    }
+
+    pub fn merge<S, B>(
+        wire_type: WireType,
+        value: &mut S,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        S: StringAdapter,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = decode_varint(buf)?;
+        if len > buf.remaining() as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "buffer underflow",
+            ));
+        }
+        ctx.check_length(len)?;
+        value.merge_from(len as usize, buf)
+    }
 
-    length_delimited!(String);
+    length_delimited!(impl StringAdapter);
 
     #[cfg(test)]
     mod test {
@@ -860,8 +1251,14 @@ pub mod string {
         proptest! {
             #[test]
             fn check(value: String, tag in MIN_TAG..=MAX_TAG) {
-                super::test::check_type(value, tag, WireType::LengthDelimited,
-                                        encode, merge, encoded_len)?;
+                super::test::check_type::<String, String>(value, tag, WireType::LengthDelimited,
+                                                          encode, merge, encoded_len)?;
+            }
+            #[test]
+            fn check_boxed(value: String, tag in MIN_TAG..=MAX_TAG) {
+                let value = value.into_boxed_str();
+                super::test::check_type::<Box<str>, Box<str>>(value, tag, WireType::LengthDelimited,
+                                                              encode, merge, encoded_len)?;
             }
             #[test]
             fn check_repeated(value: Vec<String>, tag in MIN_TAG..=MAX_TAG) {
@@ -873,37 +1270,37 @@ pub mod string {
     }
 }
 
-pub trait BytesAdapter: sealed::BytesAdapter {}
-
-mod sealed {
-    use super::{Buf, BufMut};
-
-    pub trait BytesAdapter: Default + Sized + 'static {
-        fn len(&self) -> usize;
-
-        /// Replace contents of this buffer with the contents of another buffer.
-        fn replace_with<B>(&mut self, buf: B)
-        where
-            B: Buf;
-
-        /// Appends this buffer to the (contents of) other buffer.
-        fn append_to<B>(&self, buf: &mut B)
-        where
-            B: BufMut;
+/// A type which can be used for Protobuf `bytes` fields.
+///
+/// `BytesAdapter` is implemented for `Vec<u8>`, `Bytes` and `Box<[u8]>`. Other byte buffer types,
+/// such as `Arc<[u8]>`, a small vector or a pooled buffer, can be used by implementing it for a
+/// newtype wrapper.
+pub trait BytesAdapter: Default + AsRef<[u8]> + Sized + 'static {
+    /// Replaces the contents of this buffer with the contents of another buffer.
+    fn replace_with<B>(&mut self, buf: B)
+    where
+        B: Buf;
 
-        fn is_empty(&self) -> bool {
-            self.len() == 0
-        }
+    /// Appends this buffer to the (contents of) other buffer.
+    fn append_to<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        buf.put_slice(self.as_ref())
     }
-}
-
-impl BytesAdapter for Bytes {}
 
-impl sealed::BytesAdapter for Bytes {
+    /// Returns the length of this buffer.
     fn len(&self) -> usize {
-        Buf::remaining(self)
+        self.as_ref().len()
+    }
+
+    /// Returns `true` if this buffer is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
     }
+}
 
+impl BytesAdapter for Bytes {
     fn replace_with<B>(&mut self, mut buf: B)
     where
         B: Buf,
@@ -917,15 +1314,13 @@ impl sealed::BytesAdapter for Bytes {
     {
         buf.put(self.clone())
     }
-}
-
-impl BytesAdapter for Vec<u8> {}
 
-impl sealed::BytesAdapter for Vec<u8> {
     fn len(&self) -> usize {
-        Vec::len(self)
+        Buf::remaining(self)
     }
+}
 
+impl BytesAdapter for Vec<u8> {
     fn replace_with<B>(&mut self, buf: B)
     where
         B: Buf,
@@ -935,11 +1330,19 @@ impl sealed::BytesAdapter for Vec<u8> {
         self.put(buf);
     }
 
-    fn append_to<B>(&self, buf: &mut B)
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+impl BytesAdapter for Box<[u8]> {
+    fn replace_with<B>(&mut self, buf: B)
     where
-        B: BufMut,
+        B: Buf,
     {
-        buf.put(self.as_slice())
+        let mut vec = Vec::with_capacity(buf.remaining());
+        vec.put(buf);
+        *self = vec.into_boxed_slice();
     }
 }
 
@@ -960,7 +1363,7 @@ pub mod bytes {
         wire_type: WireType,
         value: &mut A,
         buf: &mut B,
-        _ctx: DecodeContext,
+        ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
         A: BytesAdapter,
@@ -969,8 +1372,12 @@ pub mod bytes {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
         let len = decode_varint(buf)?;
         if len > buf.remaining() as u64 {
-            return Err(DecodeError::new("buffer underflow"));
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "buffer underflow",
+            ));
         }
+        ctx.check_length(len)?;
         let len = len as usize;
 
         // Clear the existing value. This follows from the following rule in the encoding guide[1]:
@@ -983,34 +1390,11 @@ pub mod bytes {
         // [1]: https://developers.google.com/protocol-buffers/docs/encoding#optional
         //
         // This is intended for A and B both being Bytes so it is zero-copy.
-        // Some combinations of A and B types may cause a double-copy,
-        // in which case merge_one_copy() should be used instead.
+        // Some combinations of A and B types may cause a double-copy.
         value.replace_with(buf.copy_to_bytes(len));
         Ok(())
     }
 
-    pub(super) fn merge_one_copy<A, B>(
-        wire_type: WireType,
-        value: &mut A,
-        buf: &mut B,
-        _ctx: DecodeContext,
-    ) -> Result<(), DecodeError>
-    where
-        A: BytesAdapter,
-        B: Buf,
-    {
-        check_wire_type(WireType::LengthDelimited, wire_type)?;
-        let len = decode_varint(buf)?;
-        if len > buf.remaining() as u64 {
-            return Err(DecodeError::new("buffer underflow"));
-        }
-        let len = len as usize;
-
-        // If we must copy, make sure to copy only once.
-        value.replace_with(buf.take(len));
-        Ok(())
-    }
-
     length_delimited!(impl BytesAdapter);
 
     #[cfg(test)]
@@ -1034,6 +1418,13 @@ pub mod bytes {
                                                         encode, merge, encoded_len)?;
             }
 
+            #[test]
+            fn check_boxed(value: Vec<u8>, tag in MIN_TAG..=MAX_TAG) {
+                let value = value.into_boxed_slice();
+                super::test::check_type::<Box<[u8]>, Box<[u8]>>(value, tag, WireType::LengthDelimited,
+                                                                encode, merge, encoded_len)?;
+            }
+
             #[test]
             fn check_repeated_vec(value: Vec<Vec<u8>>, tag in MIN_TAG..=MAX_TAG) {
                 super::test::check_collection_type(value, tag, WireType::LengthDelimited,
@@ -1065,6 +1456,17 @@ pub mod message {
         msg.encode_raw(buf);
     }
 
+    /// Encodes a message field, with the entries of its map fields in key order.
+    pub fn encode_deterministic<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: Message,
+        B: BufMut,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(msg.encoded_len() as u64, buf);
+        msg.encode_raw_deterministic(buf);
+    }
+
     pub fn merge<M, B>(
         wire_type: WireType,
         msg: &mut M,
@@ -1075,6 +1477,11 @@ pub mod message {
         M: Message,
         B: Buf,
     {
+        if let Some(mut presence) = FieldPresence::track(&ctx, false) {
+            merge_tracked(wire_type, msg, buf, ctx, &mut presence)?;
+            return msg.check_required_fields(&presence);
+        }
+
         check_wire_type(WireType::LengthDelimited, wire_type)?;
         ctx.limit_reached()?;
         merge_loop(
@@ -1088,31 +1495,105 @@ pub mod message {
         )
     }
 
-    pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
+    /// Merges an occurrence of a singular message field, recording its decoded fields in
+    /// `presence` so that its required fields can be checked once the enclosing message has been
+    /// decoded.
+    pub fn merge_tracked<M, B>(
+        wire_type: WireType,
+        msg: &mut M,
+        buf: &mut B,
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
     where
         M: Message,
-        B: BufMut,
+        B: Buf,
     {
-        for msg in messages {
-            encode(tag, msg, buf);
-        }
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+        merge_loop(
+            msg,
+            buf,
+            ctx.enter_recursion(),
+            |msg: &mut M, buf: &mut B, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                presence.record(tag);
+                msg.merge_field_tracked(tag, wire_type, buf, ctx, presence)
+            },
+        )
     }
 
-    pub fn merge_repeated<M, B>(
+    /// Merges the fields of a message field which are selected by `projection`.
+    pub fn merge_projected<M, B>(
         wire_type: WireType,
-        messages: &mut Vec<M>,
+        msg: &mut M,
         buf: &mut B,
         ctx: DecodeContext,
+        projection: &Projection,
     ) -> Result<(), DecodeError>
     where
-        M: Message + Default,
+        M: Message,
         B: Buf,
     {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
-        let mut msg = M::default();
-        merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
-        messages.push(msg);
-        Ok(())
+        ctx.limit_reached()?;
+        merge_loop(
+            msg,
+            buf,
+            ctx.enter_recursion(),
+            |msg: &mut M, buf: &mut B, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                msg.merge_field_projected(tag, wire_type, buf, ctx, projection)
+            },
+        )
+    }
+
+    pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
+    where
+        M: Message,
+        B: BufMut,
+    {
+        for msg in messages {
+            encode(tag, msg, buf);
+        }
+    }
+
+    pub fn merge_repeated<M, B>(
+        wire_type: WireType,
+        messages: &mut Vec<M>,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message + Default,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(messages.len() + 1)?;
+        let mut msg = M::default();
+        merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
+        messages.push(msg);
+        Ok(())
+    }
+
+    /// Merges the fields of a repeated message field element which are selected by `projection`.
+    pub fn merge_repeated_projected<M, B>(
+        wire_type: WireType,
+        messages: &mut Vec<M>,
+        buf: &mut B,
+        ctx: DecodeContext,
+        projection: &Projection,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message + Default,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(messages.len() + 1)?;
+        let mut msg = M::default();
+        merge_projected(WireType::LengthDelimited, &mut msg, buf, ctx, projection)?;
+        messages.push(msg);
+        Ok(())
     }
 
     #[inline]
@@ -1138,6 +1619,400 @@ pub mod message {
     }
 }
 
+/// Encoding functions for message fields which are decoded on first access.
+///
+/// Until the message is decoded, the encoded bytes are merged and encoded as they are.
+pub mod lazy {
+    use super::*;
+
+    use crate::Lazy;
+
+    pub fn encode<M, B>(tag: u32, lazy: &Lazy<M>, buf: &mut B)
+    where
+        M: Message + Default,
+        B: BufMut,
+    {
+        match lazy.state() {
+            Ok(msg) => message::encode(tag, msg, buf),
+            Err(bytes) => encode_bytes(tag, bytes, buf),
+        }
+    }
+
+    /// Encodes a lazy field, with the entries of its map fields in key order if it has been
+    /// decoded. The bytes of a field which hasn't been decoded are encoded unchanged, since the
+    /// encoded length of the containing messages is computed from them.
+    pub fn encode_deterministic<M, B>(tag: u32, lazy: &Lazy<M>, buf: &mut B)
+    where
+        M: Message + Default,
+        B: BufMut,
+    {
+        match lazy.state() {
+            Ok(msg) => message::encode_deterministic(tag, msg, buf),
+            Err(bytes) => encode_bytes(tag, bytes, buf),
+        }
+    }
+
+    fn encode_bytes<B>(tag: u32, bytes: &Bytes, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(bytes.len() as u64, buf);
+        buf.put_slice(bytes);
+    }
+
+    pub fn merge<M, B>(
+        wire_type: WireType,
+        lazy: &mut Lazy<M>,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message + Default,
+        B: Buf,
+    {
+        let (bytes, options) = match lazy.state_mut() {
+            Ok(msg) => return message::merge(wire_type, msg, buf, ctx),
+            Err(encoded) => encoded,
+        };
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+        let len = decode_varint(buf)?;
+        if len > buf.remaining() as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "buffer underflow",
+            ));
+        }
+        let merged = buf.copy_to_bytes(len as usize);
+        // Merging a message is equivalent to decoding the concatenation of the encoded messages.
+        *bytes = if bytes.is_empty() {
+            merged
+        } else {
+            let mut concatenated = BytesMut::with_capacity(bytes.len() + merged.len());
+            concatenated.put_slice(bytes);
+            concatenated.put_slice(&merged);
+            concatenated.freeze()
+        };
+        *options = *ctx.options();
+        Ok(())
+    }
+
+    pub fn encode_repeated<M, B>(tag: u32, messages: &[Lazy<M>], buf: &mut B)
+    where
+        M: Message + Default,
+        B: BufMut,
+    {
+        for msg in messages {
+            encode(tag, msg, buf);
+        }
+    }
+
+    pub fn merge_repeated<M, B>(
+        wire_type: WireType,
+        messages: &mut Vec<Lazy<M>>,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message + Default,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(messages.len() + 1)?;
+        let mut msg = Lazy::default();
+        merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
+        messages.push(msg);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn encoded_len<M>(tag: u32, lazy: &Lazy<M>) -> usize
+    where
+        M: Message + Default,
+    {
+        let len = lazy.encoded_len();
+        key_len(tag) + encoded_len_varint(len as u64) + len
+    }
+
+    #[inline]
+    pub fn encoded_len_repeated<M>(tag: u32, messages: &[Lazy<M>]) -> usize
+    where
+        M: Message + Default,
+    {
+        key_len(tag) * messages.len()
+            + messages
+                .iter()
+                .map(Lazy::encoded_len)
+                .map(|len| len + encoded_len_varint(len as u64))
+                .sum::<usize>()
+    }
+}
+
+/// Encoding functions for fields which borrow from the decoded buffer.
+///
+/// Values are decoded from a `&'a [u8]` buffer without copying, so these functions are only
+/// usable from `BorrowedMessage` implementations.
+pub mod borrowed {
+    use super::*;
+
+    use crate::BorrowedMessage;
+
+    /// Decodes a length-delimited value, returning the slice of the buffer which contains it.
+    fn merge_slice<'a>(
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: &DecodeContext,
+    ) -> Result<&'a [u8], DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = decode_varint(buf)?;
+        if len > buf.len() as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::BufferUnderflow,
+                "buffer underflow",
+            ));
+        }
+        ctx.check_length(len)?;
+        let (value, rest) = buf.split_at(len as usize);
+        *buf = rest;
+        Ok(value)
+    }
+
+    pub mod string {
+        use super::*;
+
+        pub fn encode<B>(tag: u32, value: &&str, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value.as_bytes());
+        }
+
+        pub fn merge<'a>(
+            wire_type: WireType,
+            value: &mut &'a str,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            let bytes = merge_slice(wire_type, buf, &ctx)?;
+            *value = str::from_utf8(bytes).map_err(|_| {
+                DecodeError::with_kind(
+                    DecodeErrorKind::InvalidUtf8,
+                    "invalid string value: data is not UTF-8 encoded",
+                )
+            })?;
+            Ok(())
+        }
+
+        pub fn encode_repeated<B>(tag: u32, values: &[&str], buf: &mut B)
+        where
+            B: BufMut,
+        {
+            for value in values {
+                encode(tag, value, buf);
+            }
+        }
+
+        pub fn merge_repeated<'a>(
+            wire_type: WireType,
+            values: &mut Vec<&'a str>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            ctx.check_repeated_len(values.len() + 1)?;
+            let mut value = "";
+            merge(wire_type, &mut value, buf, ctx)?;
+            values.push(value);
+            Ok(())
+        }
+
+        #[inline]
+        pub fn encoded_len(tag: u32, value: &&str) -> usize {
+            key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+        }
+
+        #[inline]
+        pub fn encoded_len_repeated(tag: u32, values: &[&str]) -> usize {
+            values.iter().map(|value| encoded_len(tag, value)).sum()
+        }
+    }
+
+    pub mod bytes {
+        use super::*;
+
+        pub fn encode<B>(tag: u32, value: &&[u8], buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value);
+        }
+
+        pub fn merge<'a>(
+            wire_type: WireType,
+            value: &mut &'a [u8],
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            *value = merge_slice(wire_type, buf, &ctx)?;
+            Ok(())
+        }
+
+        pub fn encode_repeated<B>(tag: u32, values: &[&[u8]], buf: &mut B)
+        where
+            B: BufMut,
+        {
+            for value in values {
+                encode(tag, value, buf);
+            }
+        }
+
+        pub fn merge_repeated<'a>(
+            wire_type: WireType,
+            values: &mut Vec<&'a [u8]>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            ctx.check_repeated_len(values.len() + 1)?;
+            let mut value: &[u8] = &[];
+            merge(wire_type, &mut value, buf, ctx)?;
+            values.push(value);
+            Ok(())
+        }
+
+        #[inline]
+        pub fn encoded_len(tag: u32, value: &&[u8]) -> usize {
+            key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+        }
+
+        #[inline]
+        pub fn encoded_len_repeated(tag: u32, values: &[&[u8]]) -> usize {
+            values.iter().map(|value| encoded_len(tag, value)).sum()
+        }
+    }
+
+    pub mod message {
+        use super::*;
+
+        pub fn encode<'a, M, B>(tag: u32, msg: &M, buf: &mut B)
+        where
+            M: BorrowedMessage<'a>,
+            B: BufMut,
+        {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(msg.encoded_len() as u64, buf);
+            msg.encode_raw(buf);
+        }
+
+        pub fn merge<'a, M>(
+            wire_type: WireType,
+            msg: &mut M,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            M: BorrowedMessage<'a>,
+        {
+            if let Some(mut presence) = FieldPresence::track(&ctx, false) {
+                merge_tracked(wire_type, msg, buf, ctx, &mut presence)?;
+                return msg.check_required_fields(&presence);
+            }
+
+            check_wire_type(WireType::LengthDelimited, wire_type)?;
+            ctx.limit_reached()?;
+            merge_loop(
+                msg,
+                buf,
+                ctx.enter_recursion(),
+                |msg: &mut M, buf: &mut &'a [u8], ctx| {
+                    let (tag, wire_type) = decode_key(buf)?;
+                    msg.merge_field(tag, wire_type, buf, ctx)
+                },
+            )
+        }
+
+        /// Merges an occurrence of a singular message field, recording its decoded fields in
+        /// `presence`.
+        ///
+        /// See `message::merge_tracked`.
+        pub fn merge_tracked<'a, M>(
+            wire_type: WireType,
+            msg: &mut M,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+            presence: &mut FieldPresence,
+        ) -> Result<(), DecodeError>
+        where
+            M: BorrowedMessage<'a>,
+        {
+            check_wire_type(WireType::LengthDelimited, wire_type)?;
+            ctx.limit_reached()?;
+            merge_loop(
+                msg,
+                buf,
+                ctx.enter_recursion(),
+                |msg: &mut M, buf: &mut &'a [u8], ctx| {
+                    let (tag, wire_type) = decode_key(buf)?;
+                    presence.record(tag);
+                    msg.merge_field_tracked(tag, wire_type, buf, ctx, presence)
+                },
+            )
+        }
+
+        pub fn encode_repeated<'a, M, B>(tag: u32, messages: &[M], buf: &mut B)
+        where
+            M: BorrowedMessage<'a>,
+            B: BufMut,
+        {
+            for msg in messages {
+                encode(tag, msg, buf);
+            }
+        }
+
+        pub fn merge_repeated<'a, M>(
+            wire_type: WireType,
+            messages: &mut Vec<M>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            M: BorrowedMessage<'a> + Default,
+        {
+            check_wire_type(WireType::LengthDelimited, wire_type)?;
+            ctx.check_repeated_len(messages.len() + 1)?;
+            let mut msg = M::default();
+            merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
+            messages.push(msg);
+            Ok(())
+        }
+
+        #[inline]
+        pub fn encoded_len<'a, M>(tag: u32, msg: &M) -> usize
+        where
+            M: BorrowedMessage<'a>,
+        {
+            let len = msg.encoded_len();
+            key_len(tag) + encoded_len_varint(len as u64) + len
+        }
+
+        #[inline]
+        pub fn encoded_len_repeated<'a, M>(tag: u32, messages: &[M]) -> usize
+        where
+            M: BorrowedMessage<'a>,
+        {
+            key_len(tag) * messages.len()
+                + messages
+                    .iter()
+                    .map(BorrowedMessage::encoded_len)
+                    .map(|len| len + encoded_len_varint(len as u64))
+                    .sum::<usize>()
+        }
+    }
+}
+
 pub mod group {
     use super::*;
 
@@ -1151,6 +2026,17 @@ pub mod group {
         encode_key(tag, WireType::EndGroup, buf);
     }
 
+    /// Encodes a group field, with the entries of its map fields in key order.
+    pub fn encode_deterministic<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: Message,
+        B: BufMut,
+    {
+        encode_key(tag, WireType::StartGroup, buf);
+        msg.encode_raw_deterministic(buf);
+        encode_key(tag, WireType::EndGroup, buf);
+    }
+
     pub fn merge<M, B>(
         tag: u32,
         wire_type: WireType,
@@ -1162,6 +2048,11 @@ pub mod group {
         M: Message,
         B: Buf,
     {
+        if let Some(mut presence) = FieldPresence::track(&ctx, false) {
+            merge_tracked(tag, wire_type, msg, buf, ctx, &mut presence)?;
+            return msg.check_required_fields(&presence);
+        }
+
         check_wire_type(WireType::StartGroup, wire_type)?;
 
         ctx.limit_reached()?;
@@ -1169,7 +2060,10 @@ pub mod group {
             let (field_tag, field_wire_type) = decode_key(buf)?;
             if field_wire_type == WireType::EndGroup {
                 if field_tag != tag {
-                    return Err(DecodeError::new("unexpected end group tag"));
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::UnexpectedEndGroup,
+                        "unexpected end group tag",
+                    ));
                 }
                 return Ok(());
             }
@@ -1178,6 +2072,43 @@ pub mod group {
         }
     }
 
+    /// Merges an occurrence of a singular group field, recording its decoded fields in
+    /// `presence`.
+    ///
+    /// See `message::merge_tracked`.
+    pub fn merge_tracked<M, B>(
+        tag: u32,
+        wire_type: WireType,
+        msg: &mut M,
+        buf: &mut B,
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message,
+        B: Buf,
+    {
+        check_wire_type(WireType::StartGroup, wire_type)?;
+
+        ctx.limit_reached()?;
+        loop {
+            let (field_tag, field_wire_type) = decode_key(buf)?;
+            if field_wire_type == WireType::EndGroup {
+                if field_tag != tag {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::UnexpectedEndGroup,
+                        "unexpected end group tag",
+                    ));
+                }
+                return Ok(());
+            }
+
+            presence.record(field_tag);
+            let ctx = ctx.enter_recursion();
+            msg.merge_field_tracked(field_tag, field_wire_type, buf, ctx, presence)?;
+        }
+    }
+
     pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
     where
         M: Message,
@@ -1200,6 +2131,7 @@ pub mod group {
         B: Buf,
     {
         check_wire_type(WireType::StartGroup, wire_type)?;
+        ctx.check_repeated_len(messages.len() + 1)?;
         let mut msg = M::default();
         merge(tag, WireType::StartGroup, &mut msg, buf, ctx)?;
         messages.push(msg);
@@ -1316,7 +2248,100 @@ macro_rules! map {
             VE: Fn(u32, &V, &mut B),
             VL: Fn(u32, &V) -> usize,
         {
-            for (key, val) in values.iter() {
+            encode_entries(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                val_default,
+                tag,
+                values.iter(),
+                buf,
+            )
+        }
+
+        /// Generic protobuf map encode function which encodes the entries in key order.
+        pub fn encode_sorted<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: Default + PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            encode_sorted_with_default(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                &V::default(),
+                tag,
+                values,
+                buf,
+            )
+        }
+
+        /// Generic protobuf map encode function which encodes the entries in key order, with an
+        /// overridden value default.
+        pub fn encode_sorted_with_default<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            val_default: &V,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            encode_entries(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                val_default,
+                tag,
+                sorted_entries(values),
+                buf,
+            )
+        }
+
+        fn encode_entries<'a, K, V, I, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            val_default: &V,
+            tag: u32,
+            entries: I,
+            buf: &mut B,
+        ) where
+            K: Default + PartialEq + 'a,
+            V: PartialEq + 'a,
+            I: IntoIterator<Item = (&'a K, &'a V)>,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            for (key, val) in entries {
                 let skip_key = key == &K::default();
                 let skip_val = val == val_default;
 
@@ -1369,6 +2394,7 @@ macro_rules! map {
                 },
             )?;
             values.insert(key, val);
+            ctx.check_repeated_len(values.len())?;
 
             Ok(())
         }
@@ -1414,10 +2440,27 @@ macro_rules! map {
 pub mod hash_map {
     use std::collections::HashMap;
     map!(HashMap);
+
+    /// Returns the entries of the map, sorted by key.
+    fn sorted_entries<K, V>(values: &HashMap<K, V>) -> Vec<(&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut entries = values.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|&(key, _)| key);
+        entries
+    }
 }
 
 pub mod btree_map {
     map!(BTreeMap);
+
+    /// Returns the entries of the map, which are already sorted by key.
+    fn sorted_entries<K, V>(
+        values: &BTreeMap<K, V>,
+    ) -> alloc::collections::btree_map::Iter<'_, K, V> {
+        values.iter()
+    }
 }
 
 #[cfg(test)]
@@ -1600,6 +2643,44 @@ mod test {
         assert!(s.is_empty());
     }
 
+    #[test]
+    fn string_merge_from_chunks() {
+        // The default `merge_from` reads contiguous and split strings, and leaves the rest of the
+        // buffer in place.
+        let mut s: Box<str> = "old".into();
+        let mut buf = &b"\x03abcd"[..];
+        string::merge(
+            WireType::LengthDelimited,
+            &mut s,
+            &mut buf,
+            DecodeContext::default(),
+        )
+        .unwrap();
+        assert_eq!(&*s, "abc");
+        assert_eq!(buf, b"d");
+
+        let mut buf = (&b"\x03a"[..]).chain(&b"bcd"[..]);
+        string::merge(
+            WireType::LengthDelimited,
+            &mut s,
+            &mut buf,
+            DecodeContext::default(),
+        )
+        .unwrap();
+        assert_eq!(&*s, "abc");
+        assert_eq!(buf.chunk(), b"d");
+
+        let mut buf = (&b"\x02\x80"[..]).chain(&b"\x80"[..]);
+        string::merge(
+            WireType::LengthDelimited,
+            &mut s,
+            &mut buf,
+            DecodeContext::default(),
+        )
+        .expect_err("must be an error");
+        assert!(s.is_empty());
+    }
+
     #[test]
     fn varint() {
         fn check(value: u64, mut encoded: &[u8]) {
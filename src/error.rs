@@ -18,12 +18,46 @@ pub struct DecodeError {
 
 #[derive(Clone, PartialEq, Eq)]
 struct Inner {
+    /// The category of the error.
+    kind: DecodeErrorKind,
     /// A 'best effort' root cause description.
     description: Cow<'static, str>,
     /// A stack of (message, field) name pairs, which identify the specific
     /// message type and field where decoding failed. The stack contains an
     /// entry per level of nesting.
     stack: Vec<(&'static str, &'static str)>,
+    /// The number of bytes of the buffer which had been consumed when decoding failed.
+    offset: Option<usize>,
+}
+
+/// The category of a [`DecodeError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeErrorKind {
+    /// A varint is longer than 10 bytes, overflows a `u64`, or is truncated.
+    InvalidVarint,
+    /// The buffer ended before the end of a value.
+    BufferUnderflow,
+    /// A field key has an invalid wire type, or a wire type which doesn't match the field.
+    InvalidWireType,
+    /// A field key is out of range, or has a field number of 0.
+    InvalidTag,
+    /// A group end tag doesn't match the group start tag, or appears outside of a group.
+    UnexpectedEndGroup,
+    /// A length delimiter doesn't fit in a `usize`, or the fields of a length-delimited value
+    /// extend past its length.
+    LengthOverflow,
+    /// A `string` value is not valid UTF-8.
+    InvalidUtf8,
+    /// Messages are nested more deeply than the recursion limit.
+    RecursionLimitReached,
+    /// A message, `string` or `bytes` value, or repeated field exceeds a limit set in the
+    /// decode options.
+    LimitExceeded,
+    /// A `required` field is missing.
+    MissingRequiredField,
+    /// Any other error, such as a value which is invalid for its field.
+    Other,
 }
 
 impl DecodeError {
@@ -33,14 +67,43 @@ impl DecodeError {
     #[doc(hidden)]
     #[cold]
     pub fn new(description: impl Into<Cow<'static, str>>) -> DecodeError {
+        DecodeError::with_kind(DecodeErrorKind::Other, description)
+    }
+
+    /// Creates a new `DecodeError` of the given kind, with a 'best effort' root cause
+    /// description.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    #[cold]
+    pub fn with_kind(
+        kind: DecodeErrorKind,
+        description: impl Into<Cow<'static, str>>,
+    ) -> DecodeError {
         DecodeError {
             inner: Box::new(Inner {
+                kind,
                 description: description.into(),
                 stack: Vec::new(),
+                offset: None,
             }),
         }
     }
 
+    /// Returns the category of the error.
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.inner.kind
+    }
+
+    /// Returns the offset in the decoded buffer at which decoding failed.
+    ///
+    /// The offset is the number of bytes of the buffer passed to the decode or merge method which
+    /// had been consumed when the error was detected, so it points at or just past the invalid
+    /// data. It is `None` for errors which weren't returned by one of those methods.
+    pub fn offset(&self) -> Option<usize> {
+        self.inner.offset
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
@@ -48,13 +111,20 @@ impl DecodeError {
     pub fn push(&mut self, message: &'static str, field: &'static str) {
         self.inner.stack.push((message, field));
     }
+
+    /// Sets the offset at which decoding failed.
+    pub(crate) fn set_offset(&mut self, offset: usize) {
+        self.inner.offset = Some(offset);
+    }
 }
 
 impl fmt::Debug for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DecodeError")
+            .field("kind", &self.inner.kind)
             .field("description", &self.inner.description)
             .field("stack", &self.inner.stack)
+            .field("offset", &self.inner.offset)
             .finish()
     }
 }
@@ -0,0 +1,613 @@
+//! Proto2 extensions.
+//!
+//! A message which declares extension ranges stores the fields in those ranges in an
+//! [`ExtensionSet`], and implements [`ExtendableMessage`]. Each field declared in an `extend`
+//! block is described by a typed [`Extension`], which `prost-build` emits as a constant, and which
+//! is used to read and write the field:
+//!
+//! ```rust,ignore
+//! use prost::ExtendableMessage;
+//!
+//! let mut message = Extendable::default();
+//! message.set_extension(&MY_EXTENSION, 42);
+//! assert_eq!(message.get_extension(&MY_EXTENSION), Ok(Some(42)));
+//! ```
+//!
+//! Extension fields are kept in their encoded form, so that messages can be decoded and re-encoded
+//! without knowing which extensions exist. They are decoded each time they are read.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use bytes::{Buf, BufMut};
+
+use crate::encoding::{self, decode_key, DecodeContext, WireType};
+use crate::{DecodeError, UnknownFields};
+
+/// A message with extension ranges.
+///
+/// This is implemented by `#[derive(Message)]` for messages with a field annotated with
+/// `#[prost(extensions = "...")]`.
+pub trait ExtendableMessage: Sized {
+    /// The extension ranges of the message, as `(start, end)` pairs with an exclusive end.
+    const EXTENSION_RANGES: &'static [(u32, u32)];
+
+    /// Returns the extension fields of the message.
+    fn extensions(&self) -> &ExtensionSet;
+
+    /// Returns the extension fields of the message mutably.
+    fn extensions_mut(&mut self) -> &mut ExtensionSet;
+
+    /// Returns `true` if the extension is set.
+    fn has_extension<T>(&self, extension: &Extension<Self, T>) -> bool {
+        self.extensions().has(extension)
+    }
+
+    /// Returns the value of the extension, or `None` if it is not set.
+    ///
+    /// An error is returned if the encoded extension field is not valid for the extension's type.
+    fn get_extension<T>(
+        &self,
+        extension: &Extension<Self, T>,
+    ) -> Result<Option<T::Value>, DecodeError>
+    where
+        T: ExtensionType,
+    {
+        self.extensions().get(extension)
+    }
+
+    /// Sets the value of the extension, replacing any previous value.
+    fn set_extension<T>(&mut self, extension: &Extension<Self, T>, value: T::Value)
+    where
+        T: ExtensionType,
+    {
+        self.extensions_mut().set(extension, value)
+    }
+
+    /// Clears the extension.
+    fn clear_extension<T>(&mut self, extension: &Extension<Self, T>) {
+        self.extensions_mut().clear_extension(extension)
+    }
+}
+
+/// A field which extends the message type `M`, with values encoded as `T`.
+///
+/// `T` is one of the types in this module, such as [`Int32`] or [`Message`][struct@Message], and
+/// determines the Rust type of the extension's values.
+pub struct Extension<M, T> {
+    full_name: &'static str,
+    number: u32,
+    _marker: PhantomData<(M, T)>,
+}
+
+impl<M, T> Extension<M, T> {
+    /// Creates a new extension.
+    ///
+    /// `prost-build` emits a constant for each extension declared in the compiled `.proto` files.
+    /// Extensions declared elsewhere, such as custom options defined in `.proto` files which are
+    /// not compiled to Rust, can be described by hand:
+    ///
+    /// ```rust,ignore
+    /// const SCOPE: Extension<prost_types::MethodOptions, prost::extension::String> =
+    ///     Extension::new("auth.scope", 50000);
+    /// ```
+    pub const fn new(full_name: &'static str, number: u32) -> Extension<M, T> {
+        Extension {
+            full_name,
+            number,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the fully qualified name of the extension, e.g. `my.package.my_extension`.
+    pub fn full_name(&self) -> &'static str {
+        self.full_name
+    }
+
+    /// Returns the field number of the extension.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+}
+
+impl<M, T> fmt::Debug for Extension<M, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extension")
+            .field("full_name", &self.full_name)
+            .field("number", &self.number)
+            .finish()
+    }
+}
+
+/// The encoded extension fields of a message.
+///
+/// Messages opt in to extensions by including a field of this type annotated with
+/// `#[prost(extensions = "...")]`, listing the extension ranges of the message. Fields with tags in
+/// the extension ranges are stored here during decoding, and are re-emitted, in field number
+/// order, after the known fields when the message is encoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtensionSet {
+    fields: BTreeMap<u32, Vec<u8>>,
+}
+
+impl ExtensionSet {
+    /// Creates an empty extension set.
+    pub fn new() -> ExtensionSet {
+        ExtensionSet::default()
+    }
+
+    /// Returns the number of extensions which are set.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if no extensions are set.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns an iterator over the field numbers of the extensions which are set, in order.
+    pub fn numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.fields.keys().copied()
+    }
+
+    /// Returns the encoded extension field with the field number, or `None` if it is not set.
+    ///
+    /// The field is returned as it appears on the wire, including its key. If the field occurs
+    /// more than once, the occurrences are concatenated.
+    pub fn get_raw(&self, number: u32) -> Option<&[u8]> {
+        self.fields.get(&number).map(Vec::as_slice)
+    }
+
+    /// Returns `true` if the extension is set.
+    pub fn has<M, T>(&self, extension: &Extension<M, T>) -> bool {
+        self.fields.contains_key(&extension.number)
+    }
+
+    /// Returns the value of the extension, or `None` if it is not set.
+    ///
+    /// If the extension field occurs more than once, the occurrences are merged, as they would be
+    /// for a regular field.
+    pub fn get<M, T>(&self, extension: &Extension<M, T>) -> Result<Option<T::Value>, DecodeError>
+    where
+        T: ExtensionType,
+    {
+        let mut buf = match self.fields.get(&extension.number) {
+            Some(buf) => &buf[..],
+            None => return Ok(None),
+        };
+        let mut value = T::Value::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            T::merge(
+                tag,
+                wire_type,
+                &mut value,
+                &mut buf,
+                DecodeContext::default(),
+            )?;
+        }
+        Ok(Some(value))
+    }
+
+    /// Sets the value of the extension, replacing any previous value.
+    pub fn set<M, T>(&mut self, extension: &Extension<M, T>, value: T::Value)
+    where
+        T: ExtensionType,
+    {
+        let mut buf = Vec::new();
+        T::encode(extension.number, &value, &mut buf);
+        self.fields.insert(extension.number, buf);
+    }
+
+    /// Clears the extension.
+    pub fn clear_extension<M, T>(&mut self, extension: &Extension<M, T>) {
+        self.fields.remove(&extension.number);
+    }
+
+    /// Clears all extensions.
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    /// Decodes an extension field from the buffer, and adds it to the set.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    pub fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        // The field is checked and stored as an unknown field, and kept in its encoded form.
+        let mut field = UnknownFields::new();
+        field.merge_field(tag, wire_type, buf, ctx)?;
+        field.encode_raw(self.fields.entry(tag).or_default());
+        Ok(())
+    }
+
+    /// Encodes the extension fields to the buffer.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    pub fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for field in self.fields.values() {
+            buf.put_slice(field);
+        }
+    }
+
+    /// Returns the encoded length of the extension fields.
+    pub fn encoded_len(&self) -> usize {
+        self.fields.values().map(Vec::len).sum()
+    }
+}
+
+/// The encoding of an extension's values.
+///
+/// This is implemented by the types in this module, which correspond to the Protobuf field types.
+pub trait ExtensionType {
+    /// The Rust type of the extension's values.
+    type Value: Default;
+
+    /// Encodes the value as the field with the given number.
+    ///
+    /// Meant to be used only by `ExtensionSet`.
+    #[doc(hidden)]
+    fn encode(number: u32, value: &Self::Value, buf: &mut Vec<u8>);
+
+    /// Decodes a field and merges it into the value.
+    ///
+    /// Meant to be used only by `ExtensionSet`.
+    #[doc(hidden)]
+    fn merge(
+        number: u32,
+        wire_type: WireType,
+        value: &mut Self::Value,
+        buf: &mut &[u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>;
+}
+
+/// A repeated extension of type `T`, which is not packed.
+pub struct Repeated<T>(PhantomData<T>);
+
+/// A packed repeated extension of scalar type `T`.
+pub struct Packed<T>(PhantomData<T>);
+
+/// Declares the marker type of a scalar extension type, along with its repeated forms.
+macro_rules! scalar {
+    ($(#[$meta:meta])* $name:ident, $ty:ty, $module:ident) => {
+        $(#[$meta])*
+        pub struct $name;
+
+        impl ExtensionType for $name {
+            type Value = $ty;
+
+            fn encode(number: u32, value: &$ty, buf: &mut Vec<u8>) {
+                encoding::$module::encode(number, value, buf);
+            }
+
+            fn merge(
+                _number: u32,
+                wire_type: WireType,
+                value: &mut $ty,
+                buf: &mut &[u8],
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                encoding::$module::merge(wire_type, value, buf, ctx)
+            }
+        }
+
+        impl ExtensionType for Repeated<$name> {
+            type Value = Vec<$ty>;
+
+            fn encode(number: u32, values: &Vec<$ty>, buf: &mut Vec<u8>) {
+                encoding::$module::encode_repeated(number, values, buf);
+            }
+
+            fn merge(
+                _number: u32,
+                wire_type: WireType,
+                values: &mut Vec<$ty>,
+                buf: &mut &[u8],
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                encoding::$module::merge_repeated(wire_type, values, buf, ctx)
+            }
+        }
+    };
+}
+
+/// Declares the marker type of a numeric extension type, which may also be packed.
+macro_rules! numeric {
+    ($(#[$meta:meta])* $name:ident, $ty:ty, $module:ident) => {
+        scalar!($(#[$meta])* $name, $ty, $module);
+
+        impl ExtensionType for Packed<$name> {
+            type Value = Vec<$ty>;
+
+            fn encode(number: u32, values: &Vec<$ty>, buf: &mut Vec<u8>) {
+                encoding::$module::encode_packed(number, values, buf);
+            }
+
+            fn merge(
+                _number: u32,
+                wire_type: WireType,
+                values: &mut Vec<$ty>,
+                buf: &mut &[u8],
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                encoding::$module::merge_repeated(wire_type, values, buf, ctx)
+            }
+        }
+    };
+}
+
+numeric!(
+    /// A `double` extension.
+    Double,
+    f64,
+    double
+);
+numeric!(
+    /// A `float` extension.
+    Float,
+    f32,
+    float
+);
+numeric!(
+    /// An `int32` extension.
+    Int32,
+    i32,
+    int32
+);
+numeric!(
+    /// An `int64` extension.
+    Int64,
+    i64,
+    int64
+);
+numeric!(
+    /// A `uint32` extension.
+    Uint32,
+    u32,
+    uint32
+);
+numeric!(
+    /// A `uint64` extension.
+    Uint64,
+    u64,
+    uint64
+);
+numeric!(
+    /// A `sint32` extension.
+    Sint32,
+    i32,
+    sint32
+);
+numeric!(
+    /// A `sint64` extension.
+    Sint64,
+    i64,
+    sint64
+);
+numeric!(
+    /// A `fixed32` extension.
+    Fixed32,
+    u32,
+    fixed32
+);
+numeric!(
+    /// A `fixed64` extension.
+    Fixed64,
+    u64,
+    fixed64
+);
+numeric!(
+    /// An `sfixed32` extension.
+    Sfixed32,
+    i32,
+    sfixed32
+);
+numeric!(
+    /// An `sfixed64` extension.
+    Sfixed64,
+    i64,
+    sfixed64
+);
+numeric!(
+    /// A `bool` extension.
+    Bool,
+    bool,
+    bool
+);
+numeric!(
+    /// An enum extension. Like enum fields, the values are represented by their number.
+    Enumeration,
+    i32,
+    int32
+);
+scalar!(
+    /// A `string` extension.
+    String,
+    alloc::string::String,
+    string
+);
+scalar!(
+    /// A `bytes` extension.
+    Bytes,
+    Vec<u8>,
+    bytes
+);
+
+/// A message extension, with values of message type `M`.
+pub struct Message<M>(PhantomData<M>);
+
+impl<M> ExtensionType for Message<M>
+where
+    M: crate::Message + Default,
+{
+    type Value = M;
+
+    fn encode(number: u32, value: &M, buf: &mut Vec<u8>) {
+        encoding::message::encode(number, value, buf);
+    }
+
+    fn merge(
+        _number: u32,
+        wire_type: WireType,
+        value: &mut M,
+        buf: &mut &[u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        encoding::message::merge(wire_type, value, buf, ctx)
+    }
+}
+
+impl<M> ExtensionType for Repeated<Message<M>>
+where
+    M: crate::Message + Default,
+{
+    type Value = Vec<M>;
+
+    fn encode(number: u32, values: &Vec<M>, buf: &mut Vec<u8>) {
+        encoding::message::encode_repeated(number, values, buf);
+    }
+
+    fn merge(
+        _number: u32,
+        wire_type: WireType,
+        values: &mut Vec<M>,
+        buf: &mut &[u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        encoding::message::merge_repeated(wire_type, values, buf, ctx)
+    }
+}
+
+/// A group extension, with values of message type `M`.
+pub struct Group<M>(PhantomData<M>);
+
+impl<M> ExtensionType for Group<M>
+where
+    M: crate::Message + Default,
+{
+    type Value = M;
+
+    fn encode(number: u32, value: &M, buf: &mut Vec<u8>) {
+        encoding::group::encode(number, value, buf);
+    }
+
+    fn merge(
+        number: u32,
+        wire_type: WireType,
+        value: &mut M,
+        buf: &mut &[u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        encoding::group::merge(number, wire_type, value, buf, ctx)
+    }
+}
+
+impl<M> ExtensionType for Repeated<Group<M>>
+where
+    M: crate::Message + Default,
+{
+    type Value = Vec<M>;
+
+    fn encode(number: u32, values: &Vec<M>, buf: &mut Vec<u8>) {
+        encoding::group::encode_repeated(number, values, buf);
+    }
+
+    fn merge(
+        number: u32,
+        wire_type: WireType,
+        values: &mut Vec<M>,
+        buf: &mut &[u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        encoding::group::merge_repeated(number, wire_type, values, buf, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Extendable;
+
+    const INT32: Extension<Extendable, Int32> = Extension::new("test.int32", 100);
+    const STRINGS: Extension<Extendable, Repeated<String>> = Extension::new("test.strings", 101);
+    const PACKED: Extension<Extendable, Packed<Sint64>> = Extension::new("test.packed", 102);
+
+    #[test]
+    fn set_and_get() {
+        let mut extensions = ExtensionSet::new();
+        assert_eq!(extensions.get(&INT32), Ok(None));
+
+        extensions.set(&STRINGS, vec!["a".into(), "b".into()]);
+        extensions.set(&INT32, -1);
+        extensions.set(&PACKED, vec![1, -2, 3]);
+        assert_eq!(extensions.numbers().collect::<Vec<_>>(), [100, 101, 102]);
+        assert_eq!(extensions.get(&INT32), Ok(Some(-1)));
+        assert_eq!(
+            extensions.get(&STRINGS),
+            Ok(Some(vec!["a".into(), "b".into()]))
+        );
+        assert_eq!(extensions.get(&PACKED), Ok(Some(vec![1, -2, 3])));
+
+        extensions.clear_extension(&INT32);
+        assert!(!extensions.has(&INT32));
+        assert_eq!(extensions.len(), 2);
+    }
+
+    #[test]
+    fn merge_and_encode() {
+        let data: &[u8] = &[
+            0xA0, 0x06, 0x01, // tag 100, varint 1
+            0xAA, 0x06, 0x01, b'a', // tag 101, "a"
+            0xA0, 0x06, 0x02, // tag 100, varint 2
+        ];
+        let mut extensions = ExtensionSet::new();
+        let mut buf = data;
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf).unwrap();
+            extensions
+                .merge_field(tag, wire_type, &mut buf, DecodeContext::default())
+                .unwrap();
+        }
+
+        // The last occurrence of a scalar wins.
+        assert_eq!(extensions.get(&INT32), Ok(Some(2)));
+        assert_eq!(extensions.get(&STRINGS), Ok(Some(vec!["a".into()])));
+        assert_eq!(
+            extensions.get_raw(100),
+            Some(&[0xA0, 0x06, 0x01, 0xA0, 0x06, 0x02][..])
+        );
+        assert_eq!(extensions.get_raw(102), None);
+
+        // Fields are encoded in field number order.
+        let mut encoded = Vec::new();
+        extensions.encode_raw(&mut encoded);
+        assert_eq!(
+            encoded,
+            [0xA0, 0x06, 0x01, 0xA0, 0x06, 0x02, 0xAA, 0x06, 0x01, b'a']
+        );
+        assert_eq!(extensions.encoded_len(), encoded.len());
+
+        // Reading an extension with the wrong wire type is an error.
+        const WRONG: Extension<Extendable, String> = Extension::new("test.wrong", 100);
+        assert!(extensions.get(&WRONG).is_err());
+    }
+}
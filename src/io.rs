@@ -0,0 +1,270 @@
+//! Reading and writing length-delimited messages from `std::io` streams.
+//!
+//! A stream of messages is written as a sequence of records, each of which is a message encoded
+//! with [`Message::encode_length_delimited`]: a varint length delimiter, followed by that many
+//! bytes of message. [`MessageWriter`] writes such a stream to an [`io::Write`], and
+//! [`MessageReader`] iterates over the messages of a stream read from an [`io::Read`].
+//!
+//! ```rust
+//! use prost::io::{MessageReader, MessageWriter};
+//!
+//! let mut writer = MessageWriter::new(Vec::new());
+//! writer.write(&"first".to_string()).unwrap();
+//! writer.write(&"second".to_string()).unwrap();
+//! let buf = writer.into_inner();
+//!
+//! let messages = MessageReader::<_, String>::new(&buf[..])
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(messages, ["first", "second"]);
+//! ```
+//!
+//! A stream which ends cleanly between two records ends the iteration, while a stream which ends
+//! inside a record results in an error of kind [`io::ErrorKind::UnexpectedEof`]. Messages which
+//! can't be decoded result in an error of kind [`io::ErrorKind::InvalidData`].
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use crate::{decode_length_delimiter, Message};
+
+/// Reads a length-delimited message from the reader.
+///
+/// Returns `None` if the reader is at the end of the stream. Exactly the bytes of the record are
+/// consumed from the reader, so the reader should be buffered if it is expensive to read from.
+pub fn read_length_delimited<M, R>(reader: &mut R) -> io::Result<Option<M>>
+where
+    M: Message + Default,
+    R: Read + ?Sized,
+{
+    let len = match read_length_delimiter(reader)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+
+    // The buffer grows as the record is read, so that a corrupt length delimiter doesn't result
+    // in a huge allocation.
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stream ended inside a length-delimited message",
+        ));
+    }
+    Ok(Some(M::decode(&*buf)?))
+}
+
+/// Reads a length delimiter from the reader, one byte at a time.
+///
+/// Returns `None` if the reader is at the end of the stream.
+fn read_length_delimiter<R>(reader: &mut R) -> io::Result<Option<usize>>
+where
+    R: Read + ?Sized,
+{
+    let mut buf = [0u8; 10];
+    for i in 0..buf.len() {
+        if !read_byte(reader, &mut buf[i])? {
+            if i == 0 {
+                return Ok(None);
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended inside a length delimiter",
+            ));
+        }
+        if buf[i] < 0x80 {
+            return Ok(Some(decode_length_delimiter(&buf[..=i])?));
+        }
+    }
+    // Let `decode_length_delimiter` report the invalid varint.
+    Ok(Some(decode_length_delimiter(&buf[..])?))
+}
+
+/// Reads a single byte from the reader, returning `false` at the end of the stream.
+fn read_byte<R>(reader: &mut R, byte: &mut u8) -> io::Result<bool>
+where
+    R: Read + ?Sized,
+{
+    loop {
+        match reader.read(std::slice::from_mut(byte)) {
+            Ok(0) => return Ok(false),
+            Ok(_) => return Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// An iterator over the length-delimited messages read from a stream.
+///
+/// The iterator ends at the end of the stream, or after the first error.
+#[derive(Debug)]
+pub struct MessageReader<R, M> {
+    reader: R,
+    done: bool,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<R, M> MessageReader<R, M>
+where
+    R: Read,
+    M: Message + Default,
+{
+    /// Creates a new reader of messages of type `M`.
+    pub fn new(reader: R) -> MessageReader<R, M> {
+        MessageReader {
+            reader,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, M> Iterator for MessageReader<R, M>
+where
+    R: Read,
+    M: Message + Default,
+{
+    type Item = io::Result<M>;
+
+    fn next(&mut self) -> Option<io::Result<M>> {
+        if self.done {
+            return None;
+        }
+        let result = read_length_delimited(&mut self.reader).transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Writes length-delimited messages to a stream.
+///
+/// Each message is encoded to an internal buffer, which is reused between messages, and written
+/// to the stream with a single `write_all` call.
+#[derive(Debug)]
+pub struct MessageWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W> MessageWriter<W>
+where
+    W: Write,
+{
+    /// Creates a new writer of messages.
+    pub fn new(writer: W) -> MessageWriter<W> {
+        MessageWriter {
+            writer,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Writes a length-delimited message to the stream.
+    pub fn write<M>(&mut self, message: &M) -> io::Result<()>
+    where
+        M: Message,
+    {
+        self.buf.clear();
+        message.encode_length_delimited(&mut self.buf)?;
+        self.writer.write_all(&self.buf)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(messages: &[&str]) -> Vec<u8> {
+        let mut writer = MessageWriter::new(Vec::new());
+        for message in messages {
+            writer.write(&message.to_string()).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    #[test]
+    fn read_records() {
+        let buf = records(&["a", "", "bc"]);
+        let mut reader = &buf[..];
+        assert_eq!(
+            read_length_delimited::<String, _>(&mut reader).unwrap(),
+            Some("a".to_string())
+        );
+
+        let messages = MessageReader::<_, String>::new(reader)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(messages, ["", "bc"]);
+
+        let mut empty: &[u8] = &[];
+        assert!(read_length_delimited::<String, _>(&mut empty)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn truncated_records() {
+        let buf = records(&["a", "long enough"]);
+        let mut reader = MessageReader::<_, String>::new(&buf[..buf.len() - 1]);
+        assert_eq!(reader.next().unwrap().unwrap(), "a");
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+
+        // A length delimiter with the continuation bit set, followed by the end of the stream.
+        let mut reader: &[u8] = &[0x80];
+        let error = read_length_delimited::<String, _>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn invalid_records() {
+        // An invalid length delimiter.
+        let mut reader: &[u8] = &[0xFF; 11];
+        let error = read_length_delimited::<String, _>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // A string which isn't valid UTF-8.
+        let mut reader: &[u8] = &[0x03, 0x0A, 0x01, 0xFF];
+        let error = read_length_delimited::<String, _>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,262 @@
+use alloc::boxed::Box;
+
+use core::any::Any;
+use core::fmt;
+
+use bytes::Bytes;
+
+use crate::diff::{DiffMessage, Differ};
+use crate::encoding::DecodeOptions;
+use crate::{DecodeError, Message};
+
+/// A message field which is decoded on first access.
+///
+/// Fields declared with the `lazy` attribute, e.g. `#[prost(message, optional, lazy, tag =
+/// "1")]`, hold a `Lazy<M>`. Decoding the containing message only copies the encoded bytes of the
+/// field, which are decoded when the field is first accessed with [`get_mut`][Self::get_mut] or
+/// [`into_inner`][Self::into_inner]. Until then, encoding the containing message writes the
+/// original bytes back unchanged. Decoding the bytes applies the decode options of the containing
+/// message, although the recursion limit starts again at the lazy field.
+///
+/// Since the field isn't decoded with its containing message, errors in its encoding are only
+/// reported when it's accessed.
+///
+/// [`Message::encode_deterministic`] also writes the original bytes of a field which hasn't been
+/// decoded, so it only encodes the entries of the field's map fields in key order once the field
+/// has been decoded.
+///
+/// # Examples
+///
+/// ```rust
+/// use prost::{Lazy, Message};
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// pub struct Payload {
+///     #[prost(string, tag = "1")]
+///     pub data: String,
+/// }
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// pub struct Envelope {
+///     #[prost(uint64, tag = "1")]
+///     pub id: u64,
+///     #[prost(message, optional, lazy, tag = "2")]
+///     pub payload: Option<Lazy<Payload>>,
+/// }
+///
+/// let envelope = Envelope {
+///     id: 1,
+///     payload: Some(Lazy::new(Payload { data: "hello".into() })),
+/// };
+/// let buf = envelope.encode_to_vec();
+///
+/// let mut envelope = Envelope::decode(&*buf).unwrap();
+/// let payload = envelope.payload.as_mut().unwrap();
+/// assert!(!payload.is_decoded());
+/// assert_eq!(payload.get_mut().unwrap().data, "hello");
+/// ```
+pub struct Lazy<M> {
+    state: State<M>,
+}
+
+#[derive(Clone)]
+enum State<M> {
+    /// The encoded bytes of the message, and the options to decode them with.
+    Encoded(Bytes, DecodeOptions),
+    /// The decoded message, which is boxed so that messages can contain lazy fields of their own
+    /// type.
+    Decoded(Box<M>),
+}
+
+impl<M> Lazy<M> {
+    /// Creates a lazy field holding a decoded message.
+    pub fn new(msg: M) -> Lazy<M> {
+        Lazy {
+            state: State::Decoded(Box::new(msg)),
+        }
+    }
+
+    /// Returns `true` if the message has been decoded, or the field was created from a message.
+    pub fn is_decoded(&self) -> bool {
+        matches!(self.state, State::Decoded(..))
+    }
+
+    /// Returns the encoded bytes of the message, if it hasn't been decoded.
+    pub fn encoded(&self) -> Option<&[u8]> {
+        match self.state {
+            State::Encoded(ref bytes, _) => Some(bytes),
+            State::Decoded(..) => None,
+        }
+    }
+}
+
+impl<M> Lazy<M>
+where
+    M: Message + Default,
+{
+    /// Returns a mutable reference to the message, decoding it if it hasn't been decoded.
+    ///
+    /// Once decoded, the field is encoded from the message rather than the original bytes. If
+    /// decoding fails, the field keeps the original bytes.
+    pub fn get_mut(&mut self) -> Result<&mut M, DecodeError> {
+        if let State::Encoded(ref bytes, options) = self.state {
+            let msg = M::decode_with_options(bytes.clone(), options)?;
+            self.state = State::Decoded(Box::new(msg));
+        }
+        match self.state {
+            State::Decoded(ref mut msg) => Ok(msg),
+            State::Encoded(..) => unreachable!(),
+        }
+    }
+
+    /// Returns a copy of the message, decoding it without storing the result if it hasn't been
+    /// decoded.
+    pub fn decode(&self) -> Result<M, DecodeError>
+    where
+        M: Clone,
+    {
+        self.with_decoded(M::clone)
+    }
+
+    /// Returns the message, decoding it if it hasn't been decoded.
+    pub fn into_inner(self) -> Result<M, DecodeError> {
+        match self.state {
+            State::Encoded(bytes, options) => M::decode_with_options(bytes, options),
+            State::Decoded(msg) => Ok(*msg),
+        }
+    }
+
+    /// Calls `f` with the message, decoding a temporary copy if it hasn't been decoded.
+    fn with_decoded<F, R>(&self, f: F) -> Result<R, DecodeError>
+    where
+        F: FnOnce(&M) -> R,
+    {
+        match self.state {
+            State::Encoded(ref bytes, options) => {
+                M::decode_with_options(bytes.clone(), options).map(|msg| f(&msg))
+            }
+            State::Decoded(ref msg) => Ok(f(msg)),
+        }
+    }
+
+    /// Returns the encoded length of the message, without a length delimiter.
+    pub(crate) fn encoded_len(&self) -> usize {
+        match self.state {
+            State::Encoded(ref bytes, _) => bytes.len(),
+            State::Decoded(ref msg) => msg.encoded_len(),
+        }
+    }
+
+    /// Returns the decoded message, or the encoded bytes if it hasn't been decoded.
+    pub(crate) fn state(&self) -> Result<&M, &Bytes> {
+        match self.state {
+            State::Encoded(ref bytes, _) => Err(bytes),
+            State::Decoded(ref msg) => Ok(msg),
+        }
+    }
+
+    /// Returns the decoded message, or the encoded bytes and decode options if it hasn't been
+    /// decoded.
+    pub(crate) fn state_mut(&mut self) -> Result<&mut M, (&mut Bytes, &mut DecodeOptions)> {
+        match self.state {
+            State::Encoded(ref mut bytes, ref mut options) => Err((bytes, options)),
+            State::Decoded(ref mut msg) => Ok(msg),
+        }
+    }
+}
+
+impl<M> Default for Lazy<M> {
+    fn default() -> Lazy<M> {
+        Lazy {
+            state: State::Encoded(Bytes::new(), DecodeOptions::default()),
+        }
+    }
+}
+
+impl<M> Clone for Lazy<M>
+where
+    M: Clone,
+{
+    fn clone(&self) -> Lazy<M> {
+        Lazy {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<M> From<M> for Lazy<M> {
+    fn from(msg: M) -> Lazy<M> {
+        Lazy::new(msg)
+    }
+}
+
+/// Lazy fields are equal if their messages are equal, so different encodings of equal messages,
+/// such as encodings with the fields in a different order, are equal.
+///
+/// Fields which both hold the same encoded bytes are equal without decoding them. Otherwise,
+/// comparing a field which hasn't been decoded decodes a temporary copy of the message. If it
+/// fails to decode, the fields aren't equal.
+impl<M> PartialEq for Lazy<M>
+where
+    M: Message + Default + PartialEq,
+{
+    fn eq(&self, other: &Lazy<M>) -> bool {
+        if let (State::Encoded(ref a, _), State::Encoded(ref b, _)) = (&self.state, &other.state) {
+            if a == b {
+                return true;
+            }
+        }
+        self.with_decoded(|a| other.with_decoded(|b| a == b).unwrap_or(false))
+            .unwrap_or(false)
+    }
+}
+
+/// Lazy fields are compared by the fields of their messages, except that fields which both hold
+/// the same encoded bytes are equal without decoding them. Messages which fail to decode are
+/// compared as empty messages, and are copied as empty messages by
+/// [`clone_boxed`][DiffMessage::clone_boxed].
+impl<M> DiffMessage for Lazy<M>
+where
+    M: DiffMessage + Message + Default + Clone + PartialEq,
+{
+    fn diff_fields(&self, other: &dyn DiffMessage, differ: &mut dyn Differ) {
+        let other = match other.downcast_ref::<Lazy<M>>() {
+            Some(other) => other,
+            None => return,
+        };
+        if let (Some(a), Some(b)) = (self.encoded(), other.encoded()) {
+            if a == b {
+                return;
+            }
+        }
+        let old = self.decode().unwrap_or_default();
+        let new = other.decode().unwrap_or_default();
+        old.diff_fields(&new, differ)
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DiffMessage> {
+        Box::new(self.decode().unwrap_or_default())
+    }
+
+    fn eq_dyn(&self, other: &dyn DiffMessage) -> bool {
+        other.downcast_ref::<Lazy<M>>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Formats the message if it has been decoded, and the encoded bytes otherwise, without decoding
+/// them.
+impl<M> fmt::Debug for Lazy<M>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state {
+            State::Encoded(ref bytes, _) => f.debug_tuple("Lazy").field(&&bytes[..]).finish(),
+            State::Decoded(ref msg) => msg.fmt(f),
+        }
+    }
+}
@@ -9,15 +9,36 @@ pub extern crate alloc;
 #[doc(hidden)]
 pub use bytes;
 
+mod conversion;
 mod error;
+mod lazy;
 mod message;
+mod name;
 mod types;
+mod unknown;
 
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod diff;
 #[doc(hidden)]
 pub mod encoding;
+pub mod extension;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod projection;
+pub mod reflect;
 
-pub use crate::error::{DecodeError, EncodeError};
-pub use crate::message::Message;
+pub use crate::conversion::Conversion;
+pub use crate::diff::DiffMessage;
+pub use crate::encoding::{BytesAdapter, DecodeOptions, StringAdapter};
+pub use crate::error::{DecodeError, DecodeErrorKind, EncodeError};
+pub use crate::extension::{ExtendableMessage, Extension, ExtensionSet};
+pub use crate::lazy::Lazy;
+pub use crate::message::{BorrowedMessage, Message};
+pub use crate::name::Name;
+pub use crate::projection::Projection;
+pub use crate::reflect::ReflectMessage;
+pub use crate::unknown::{UnknownField, UnknownFieldValue, UnknownFields};
 
 use bytes::{Buf, BufMut};
 
@@ -73,7 +94,8 @@ where
 {
     let length = decode_varint(&mut buf)?;
     if length > usize::max_value() as u64 {
-        return Err(DecodeError::new(
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
             "length delimiter exceeds maximum usize value",
         ));
     }
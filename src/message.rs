@@ -7,8 +7,10 @@ use core::usize;
 use bytes::{Buf, BufMut};
 
 use crate::encoding::{
-    decode_key, encode_varint, encoded_len_varint, message, DecodeContext, WireType,
+    decode_key, encode_varint, encoded_len_varint, message, skip_field, DecodeContext,
+    DecodeOptions, FieldPresence, WireType,
 };
+use crate::projection::Projection;
 use crate::DecodeError;
 use crate::EncodeError;
 
@@ -25,6 +27,20 @@ pub trait Message: Debug + Send + Sync {
         B: BufMut,
         Self: Sized;
 
+    /// Encodes the message to a buffer, with the entries of map fields in key order.
+    ///
+    /// This method will panic if the buffer has insufficient capacity.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn encode_raw_deterministic<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        self.encode_raw(buf)
+    }
+
     /// Decodes a field from a buffer, and merges it into `self`.
     ///
     /// Meant to be used only by `Message` implementations.
@@ -40,6 +56,60 @@ pub trait Message: Debug + Send + Sync {
         B: Buf,
         Self: Sized;
 
+    /// Decodes a field from a buffer if it's selected by `projection`, and merges it into `self`,
+    /// or skips it otherwise.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn merge_field_projected<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        projection: &Projection,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        match projection.get(tag) {
+            Some(_) => self.merge_field(tag, wire_type, buf, ctx),
+            None => skip_field(wire_type, tag, buf, ctx),
+        }
+    }
+
+    /// Decodes a field from a buffer, and merges it into `self`, recording the fields decoded for
+    /// its singular message fields in `presence`.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn merge_field_tracked<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        let _ = presence;
+        self.merge_field(tag, wire_type, buf, ctx)
+    }
+
+    /// Checks that the required fields of the message, and of the messages decoded into it, are
+    /// present among the decoded fields recorded in `presence`.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn check_required_fields(&self, presence: &FieldPresence) -> Result<(), DecodeError> {
+        let _ = presence;
+        Ok(())
+    }
+
     /// Returns the encoded length of the message without a length delimiter.
     fn encoded_len(&self) -> usize;
 
@@ -72,6 +142,48 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    /// Encodes the message to a buffer deterministically.
+    ///
+    /// [`Message::encode`] encodes the entries of `HashMap` fields in iteration order, which
+    /// differs between maps with the same contents. This method encodes the entries of all map
+    /// fields, including those of nested messages, in key order instead, so that equal messages
+    /// are always encoded to the same bytes by the same version of the code. Unknown fields and
+    /// extensions are encoded as they were decoded.
+    ///
+    /// Likewise, [`Lazy`][crate::Lazy] fields which haven't been decoded are encoded from their
+    /// original bytes, whose map entries may be in any order, so equal messages with such fields
+    /// may be encoded to different bytes. Decode them with [`Lazy::get_mut`][crate::Lazy::get_mut]
+    /// first to encode them deterministically.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_deterministic<B>(&self, buf: &mut B) -> Result<(), EncodeError>
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        let required = self.encoded_len();
+        let remaining = buf.remaining_mut();
+        if required > remaining {
+            return Err(EncodeError::new(required, remaining));
+        }
+
+        self.encode_raw_deterministic(buf);
+        Ok(())
+    }
+
+    /// Encodes the message deterministically to a newly allocated buffer.
+    ///
+    /// See [`Message::encode_deterministic`].
+    fn encode_deterministic_to_vec(&self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+
+        self.encode_raw_deterministic(&mut buf);
+        buf
+    }
+
     /// Encodes the message with a length-delimiter to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
@@ -116,6 +228,37 @@ pub trait Message: Debug + Send + Sync {
         Self::merge(&mut message, &mut buf).map(|_| message)
     }
 
+    /// Decodes an instance of the message from a buffer, applying the provided decode options.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_options<B>(mut buf: B, options: DecodeOptions) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+        Self: Default,
+    {
+        let mut message = Self::default();
+        let len = buf.remaining();
+        merge_fields(&mut message, &mut buf, DecodeContext::new(options), false)
+            .map(|()| message)
+            .map_err(|mut error| {
+                error.set_offset(len - buf.remaining());
+                error
+            })
+    }
+
+    /// Decodes the fields selected by `projection` of an instance of the message from a buffer.
+    ///
+    /// Fields which aren't selected are skipped, and keep their default values. Required fields
+    /// are not checked.
+    fn decode_projected<B>(mut buf: B, projection: &Projection) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+        Self: Default,
+    {
+        let mut message = Self::default();
+        Self::merge_projected(&mut message, &mut buf, projection).map(|_| message)
+    }
+
     /// Decodes a length-delimited instance of the message from the buffer.
     fn decode_length_delimited<B>(buf: B) -> Result<Self, DecodeError>
     where
@@ -130,17 +273,56 @@ pub trait Message: Debug + Send + Sync {
     /// Decodes an instance of the message from a buffer, and merges it into `self`.
     ///
     /// The entire buffer will be consumed.
-    fn merge<B>(&mut self, mut buf: B) -> Result<(), DecodeError>
+    fn merge<B>(&mut self, buf: B) -> Result<(), DecodeError>
     where
         B: Buf,
         Self: Sized,
     {
+        self.merge_with_options(buf, DecodeOptions::default())
+    }
+
+    /// Decodes an instance of the message from a buffer, applying the provided decode options,
+    /// and merges it into `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_with_options<B>(
+        &mut self,
+        mut buf: B,
+        options: DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        let len = buf.remaining();
+        merge_fields(self, &mut buf, DecodeContext::new(options), true).map_err(|mut error| {
+            error.set_offset(len - buf.remaining());
+            error
+        })
+    }
+
+    /// Decodes the fields selected by `projection` of an instance of the message from a buffer,
+    /// and merges them into `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_projected<B>(&mut self, mut buf: B, projection: &Projection) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        let len = buf.remaining();
         let ctx = DecodeContext::default();
-        while buf.has_remaining() {
-            let (tag, wire_type) = decode_key(&mut buf)?;
-            self.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
-        }
-        Ok(())
+        let result = ctx.check_message_size(len).and_then(|()| {
+            while buf.has_remaining() {
+                let (tag, wire_type) = decode_key(&mut buf)?;
+                self.merge_field_projected(tag, wire_type, &mut buf, ctx.clone(), projection)?;
+            }
+            Ok(())
+        });
+        result.map_err(|mut error| {
+            error.set_offset(len - buf.remaining());
+            error
+        })
     }
 
     /// Decodes a length-delimited instance of the message from buffer, and
@@ -150,18 +332,55 @@ pub trait Message: Debug + Send + Sync {
         B: Buf,
         Self: Sized,
     {
+        let len = buf.remaining();
         message::merge(
             WireType::LengthDelimited,
             self,
             &mut buf,
             DecodeContext::default(),
         )
+        .map_err(|mut error| {
+            error.set_offset(len - buf.remaining());
+            error
+        })
     }
 
     /// Clears the message, resetting all fields to their default.
     fn clear(&mut self);
 }
 
+/// Decodes the fields of a message from the buffer, and merges them into the message.
+///
+/// If the decode options require it, the required fields are checked once all the fields have been
+/// decoded. `existing` is whether the message held its fields before the decode.
+fn merge_fields<M, B>(
+    msg: &mut M,
+    buf: &mut B,
+    ctx: DecodeContext,
+    existing: bool,
+) -> Result<(), DecodeError>
+where
+    M: Message,
+    B: Buf,
+{
+    ctx.check_message_size(buf.remaining())?;
+    let mut presence = FieldPresence::track(&ctx, existing);
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(buf)?;
+        match presence {
+            Some(ref mut presence) => {
+                presence.record(tag);
+                msg.merge_field_tracked(tag, wire_type, buf, ctx.clone(), presence)?;
+            }
+            None => msg.merge_field(tag, wire_type, buf, ctx.clone())?,
+        }
+    }
+    match presence {
+        Some(ref presence) => msg.check_required_fields(presence),
+        None => Ok(()),
+    }
+}
+
 impl<M> Message for Box<M>
 where
     M: Message,
@@ -172,6 +391,12 @@ where
     {
         (**self).encode_raw(buf)
     }
+    fn encode_raw_deterministic<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        (**self).encode_raw_deterministic(buf)
+    }
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -184,6 +409,35 @@ where
     {
         (**self).merge_field(tag, wire_type, buf, ctx)
     }
+    fn merge_field_projected<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        projection: &Projection,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        (**self).merge_field_projected(tag, wire_type, buf, ctx, projection)
+    }
+    fn merge_field_tracked<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        (**self).merge_field_tracked(tag, wire_type, buf, ctx, presence)
+    }
+    fn check_required_fields(&self, presence: &FieldPresence) -> Result<(), DecodeError> {
+        (**self).check_required_fields(presence)
+    }
     fn encoded_len(&self) -> usize {
         (**self).encoded_len()
     }
@@ -192,9 +446,246 @@ where
     }
 }
 
+/// A Protocol Buffers message which borrows string and bytes fields from the buffer it is decoded
+/// from.
+///
+/// Borrowed messages are decoded from a `&'a [u8]` slice without copying the contents of their
+/// `&'a str` and `&'a [u8]` fields. They are derived for message structs with a lifetime
+/// parameter, and can be generated by `prost-build` with `Config::borrowed`.
+pub trait BorrowedMessage<'a>: Debug + Send + Sync {
+    /// Encodes the message to a buffer.
+    ///
+    /// This method will panic if the buffer has insufficient capacity.
+    ///
+    /// Meant to be used only by `BorrowedMessage` implementations.
+    #[doc(hidden)]
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+        Self: Sized;
+
+    /// Decodes a field from a buffer, and merges it into `self`.
+    ///
+    /// Meant to be used only by `BorrowedMessage` implementations.
+    #[doc(hidden)]
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        Self: Sized;
+
+    /// Decodes a field from a buffer, and merges it into `self`, recording the fields decoded for
+    /// its singular message fields in `presence`.
+    ///
+    /// Meant to be used only by `BorrowedMessage` implementations.
+    #[doc(hidden)]
+    fn merge_field_tracked(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: DecodeContext,
+        presence: &mut FieldPresence,
+    ) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        let _ = presence;
+        self.merge_field(tag, wire_type, buf, ctx)
+    }
+
+    /// Checks that the required fields of the message, and of the messages decoded into it, are
+    /// present among the decoded fields recorded in `presence`.
+    ///
+    /// Meant to be used only by `BorrowedMessage` implementations.
+    #[doc(hidden)]
+    fn check_required_fields(&self, presence: &FieldPresence) -> Result<(), DecodeError> {
+        let _ = presence;
+        Ok(())
+    }
+
+    /// Returns the encoded length of the message without a length delimiter.
+    fn encoded_len(&self) -> usize;
+
+    /// Encodes the message to a buffer.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode<B>(&self, buf: &mut B) -> Result<(), EncodeError>
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        let required = self.encoded_len();
+        let remaining = buf.remaining_mut();
+        if required > buf.remaining_mut() {
+            return Err(EncodeError::new(required, remaining));
+        }
+
+        self.encode_raw(buf);
+        Ok(())
+    }
+
+    /// Encodes the message to a newly allocated buffer.
+    fn encode_to_vec(&self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+
+        self.encode_raw(&mut buf);
+        buf
+    }
+
+    /// Decodes an instance of the message from a slice, borrowing from it.
+    ///
+    /// The entire slice will be consumed.
+    fn decode_borrowed(buf: &'a [u8]) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        Self::decode_borrowed_with_options(buf, DecodeOptions::default())
+    }
+
+    /// Decodes an instance of the message from a slice, borrowing from it and applying the
+    /// provided decode options.
+    ///
+    /// The entire slice will be consumed.
+    fn decode_borrowed_with_options(
+        mut buf: &'a [u8],
+        options: DecodeOptions,
+    ) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        let mut message = Self::default();
+        let len = buf.len();
+        merge_borrowed_fields(&mut message, &mut buf, DecodeContext::new(options), false)
+            .map(|()| message)
+            .map_err(|mut error| {
+                error.set_offset(len - buf.len());
+                error
+            })
+    }
+
+    /// Decodes an instance of the message from a slice, borrowing from it, and merges it into
+    /// `self`.
+    ///
+    /// The entire slice will be consumed.
+    fn merge_borrowed(&mut self, buf: &'a [u8]) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        self.merge_borrowed_with_options(buf, DecodeOptions::default())
+    }
+
+    /// Decodes an instance of the message from a slice, borrowing from it and applying the
+    /// provided decode options, and merges it into `self`.
+    ///
+    /// The entire slice will be consumed.
+    fn merge_borrowed_with_options(
+        &mut self,
+        mut buf: &'a [u8],
+        options: DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        let len = buf.len();
+        let ctx = DecodeContext::new(options);
+        merge_borrowed_fields(self, &mut buf, ctx, true).map_err(|mut error| {
+            error.set_offset(len - buf.len());
+            error
+        })
+    }
+
+    /// Clears the message, resetting all fields to their default.
+    fn clear(&mut self);
+}
+
+/// Decodes the fields of a borrowed message from the slice, and merges them into the message.
+///
+/// See `merge_fields`.
+fn merge_borrowed_fields<'a, M>(
+    msg: &mut M,
+    buf: &mut &'a [u8],
+    ctx: DecodeContext,
+    existing: bool,
+) -> Result<(), DecodeError>
+where
+    M: BorrowedMessage<'a>,
+{
+    ctx.check_message_size(buf.len())?;
+    let mut presence = FieldPresence::track(&ctx, existing);
+    while !buf.is_empty() {
+        let (tag, wire_type) = decode_key(buf)?;
+        match presence {
+            Some(ref mut presence) => {
+                presence.record(tag);
+                msg.merge_field_tracked(tag, wire_type, buf, ctx.clone(), presence)?;
+            }
+            None => msg.merge_field(tag, wire_type, buf, ctx.clone())?,
+        }
+    }
+    match presence {
+        Some(ref presence) => msg.check_required_fields(presence),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const _MESSAGE_IS_OBJECT_SAFE: Option<&dyn Message> = None;
+    const _BORROWED_MESSAGE_IS_OBJECT_SAFE: Option<&dyn BorrowedMessage<'static>> = None;
+
+    use alloc::string::{String, ToString};
+
+    use crate::DecodeErrorKind;
+
+    #[test]
+    fn decode_error_kind_and_offset() {
+        let cases: &[(&[u8], DecodeErrorKind, usize)] = &[
+            // A string field whose length extends past the end of the buffer.
+            (
+                &[0x0A, 0x01, b'a', 0x0A, 0x05, b'b'],
+                DecodeErrorKind::BufferUnderflow,
+                5,
+            ),
+            (&[0x0A, 0x01, 0xFF], DecodeErrorKind::InvalidUtf8, 3),
+            (&[0x08, 0x01], DecodeErrorKind::InvalidWireType, 1),
+            (&[0x00], DecodeErrorKind::InvalidTag, 1),
+            (&[0x14], DecodeErrorKind::UnexpectedEndGroup, 1),
+        ];
+        for &(buf, kind, offset) in cases {
+            let error = String::decode(buf).unwrap_err();
+            assert_eq!(error.kind(), kind, "{:?}", buf);
+            assert_eq!(error.offset(), Some(offset), "{:?}", buf);
+        }
+
+        let error = String::decode(&[0xFF; 11][..]).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::InvalidVarint);
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: invalid varint"
+        );
+
+        // The offset of a length-delimited message includes its length delimiter.
+        let error = String::decode_length_delimited(&[0x03, 0x0A, 0x01, 0xFF][..]).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::InvalidUtf8);
+        assert_eq!(error.offset(), Some(4));
+
+        let error = String::decode_length_delimited(&[0x04, 0x0A, 0x01][..]).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::BufferUnderflow);
+
+        assert_eq!(
+            crate::DecodeError::new("other").kind(),
+            DecodeErrorKind::Other
+        );
+        assert_eq!(crate::DecodeError::new("other").offset(), None);
+    }
 }
@@ -0,0 +1,33 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::Message;
+
+/// Associates a message type with its fully qualified Protobuf name.
+///
+/// `prost-build` implements this trait for every generated message, which allows messages to be
+/// packed into, and unpacked from, a `google.protobuf.Any` without spelling out type URLs.
+pub trait Name: Message {
+    /// The name of the message, relative to its package. Nested messages are qualified by the
+    /// messages which contain them, e.g. `Outer.Inner`.
+    const NAME: &'static str;
+
+    /// The Protobuf package of the message, e.g. `google.protobuf`. Empty for messages defined
+    /// without a package.
+    const PACKAGE: &'static str;
+
+    /// Returns the fully qualified name of the message, e.g. `google.protobuf.Duration`.
+    fn full_name() -> String {
+        if Self::PACKAGE.is_empty() {
+            Self::NAME.into()
+        } else {
+            format!("{}.{}", Self::PACKAGE, Self::NAME)
+        }
+    }
+
+    /// Returns the type URL of the message, as used by `google.protobuf.Any`, e.g.
+    /// `type.googleapis.com/google.protobuf.Duration`.
+    fn type_url() -> String {
+        format!("type.googleapis.com/{}", Self::full_name())
+    }
+}
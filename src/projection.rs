@@ -0,0 +1,216 @@
+//! Decoding a subset of the fields of a message.
+//!
+//! A [`Projection`] selects fields of a message by number, and optionally selects fields of
+//! nested messages. [`Message::decode_projected`] and [`Message::merge_projected`] skip the
+//! fields which aren't selected without decoding them, which is much cheaper than decoding every
+//! field when only a few are needed. Fields which aren't selected keep their default values.
+//!
+//! Nested projections apply to singular and repeated message fields of derived messages. Other
+//! selected fields, such as map fields and message fields in a `oneof`, are decoded in full.
+//!
+//! [`Message::decode_projected`]: crate::Message::decode_projected
+//! [`Message::merge_projected`]: crate::Message::merge_projected
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use crate::reflect::MessageDescriptor;
+
+/// A selection of the fields of a message, and of the fields of nested messages.
+///
+/// # Examples
+///
+/// ```rust
+/// use prost::projection::Projection;
+///
+/// // Select field 1, and field 2 of the message in field 4.
+/// let mut projection = Projection::new();
+/// projection.select(&[1]).select(&[4, 2]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Projection {
+    /// The selected fields, with the projection of the nested message if only some of its fields
+    /// are selected.
+    fields: BTreeMap<u32, Option<Projection>>,
+}
+
+/// How a field is selected by a [`Projection`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection<'a> {
+    /// The whole field is selected.
+    Field,
+    /// Some of the fields of the nested message are selected.
+    Nested(&'a Projection),
+}
+
+impl<'a> Selection<'a> {
+    fn new(nested: &'a Option<Projection>) -> Selection<'a> {
+        match *nested {
+            Some(ref nested) => Selection::Nested(nested),
+            None => Selection::Field,
+        }
+    }
+}
+
+impl Projection {
+    /// Creates a projection which selects no fields.
+    pub fn new() -> Projection {
+        Projection::default()
+    }
+
+    /// Creates a projection from field paths of dotted field names, such as `"address.city"`,
+    /// which are resolved using the reflection descriptor of the message type.
+    pub fn from_paths<I, S>(desc: &MessageDescriptor, paths: I) -> Result<Projection, PathError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut projection = Projection::new();
+        for path in paths {
+            projection.select_path(desc, path.as_ref())?;
+        }
+        Ok(projection)
+    }
+
+    /// Selects the field with the path of field numbers `path`, i.e. a field of the message
+    /// followed by the fields of nested messages. Selecting a field selects all of its nested
+    /// fields, and an empty path selects nothing.
+    pub fn select(&mut self, path: &[u32]) -> &mut Projection {
+        let (&number, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return self,
+        };
+        if rest.is_empty() {
+            self.fields.insert(number, None);
+        } else if let Some(nested) = self
+            .fields
+            .entry(number)
+            .or_insert_with(|| Some(Projection::new()))
+        {
+            nested.select(rest);
+        }
+        self
+    }
+
+    /// Selects the field with the path of dotted field names `path`, such as `"address.city"`,
+    /// which is resolved using the reflection descriptor of the message type.
+    pub fn select_path(
+        &mut self,
+        desc: &MessageDescriptor,
+        path: &str,
+    ) -> Result<&mut Projection, PathError> {
+        let numbers = resolve_path(desc, path, true)?;
+        Ok(self.select(&numbers))
+    }
+
+    /// Selects the field with the path of dotted field names `path`, like
+    /// [`select_path`][Self::select_path], except that only fields of singular message fields may
+    /// follow the first field of the path.
+    ///
+    /// These are the paths which may be used in a `google.protobuf.FieldMask`, in which repeated
+    /// fields may only be the last field of a path.
+    pub fn select_singular_path(
+        &mut self,
+        desc: &MessageDescriptor,
+        path: &str,
+    ) -> Result<&mut Projection, PathError> {
+        let numbers = resolve_path(desc, path, false)?;
+        Ok(self.select(&numbers))
+    }
+
+    /// Returns how the field `number` is selected, or `None` if it isn't selected.
+    pub fn get(&self, number: u32) -> Option<Selection<'_>> {
+        self.fields.get(&number).map(Selection::new)
+    }
+
+    /// Returns the selected fields in the order of their numbers, with how they're selected.
+    pub fn fields(&self) -> impl Iterator<Item = (u32, Selection<'_>)> + '_ {
+        self.fields
+            .iter()
+            .map(|(&number, nested)| (number, Selection::new(nested)))
+    }
+
+    /// Returns `true` if no fields are selected.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// Resolves a path of dotted field names to field numbers. Map fields may only be the last field
+/// of the path, and so may repeated message fields unless `repeated` is `true`.
+fn resolve_path(
+    desc: &MessageDescriptor,
+    path: &str,
+    repeated: bool,
+) -> Result<Vec<u32>, PathError> {
+    let mut numbers = Vec::new();
+    let mut desc = Some(desc);
+    for name in path.split('.') {
+        let field = desc
+            .and_then(|desc| desc.field_by_name(name))
+            .ok_or_else(|| PathError::new(path))?;
+        numbers.push(field.number());
+        desc = if field.is_map() || (field.is_list() && !repeated) {
+            None
+        } else {
+            field.message_type()
+        };
+    }
+    Ok(numbers)
+}
+
+/// An error returned when a field path doesn't match the fields of a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathError {
+    path: String,
+}
+
+impl PathError {
+    /// Creates an error for the invalid path `path`.
+    pub fn new(path: impl Into<String>) -> PathError {
+        PathError { path: path.into() }
+    }
+
+    /// Returns the invalid path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid field path: {}", self.path)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select() {
+        let mut projection = Projection::new();
+        assert!(projection.is_empty());
+        projection.select(&[]).select(&[4, 2]).select(&[4, 3, 1]);
+        assert_eq!(projection.get(1), None);
+        let nested = match projection.get(4) {
+            Some(Selection::Nested(nested)) => nested,
+            selection => panic!("unexpected selection: {:?}", selection),
+        };
+        assert_eq!(nested.get(2), Some(Selection::Field));
+        assert!(matches!(nested.get(3), Some(Selection::Nested(..))));
+        let numbers: Vec<u32> = nested.fields().map(|(number, _)| number).collect();
+        assert_eq!(numbers, [2, 3]);
+
+        // Selecting a whole field replaces the nested projection, and the nested projection of a
+        // whole field is ignored.
+        projection.select(&[4]).select(&[4, 5]);
+        assert_eq!(projection.get(4), Some(Selection::Field));
+    }
+}
@@ -0,0 +1,825 @@
+//! Runtime reflection over Protobuf messages.
+//!
+//! Messages which implement [`ReflectMessage`] expose a static [`MessageDescriptor`] describing
+//! their fields, and allow fields to be read, written and cleared by field number using the
+//! dynamically typed [`Value`] enum, or read without copying them as a [`ValueRef`].
+//! `ReflectMessage` is object safe, so generic tooling can work with a `&dyn ReflectMessage`
+//! without knowing the concrete message type.
+//!
+//! `ReflectMessage` is typically derived alongside `Message`, and `prost-build` can be configured
+//! to emit the derive with `Config::reflect`.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::fmt;
+use core::slice;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::{DecodeError, Message};
+
+/// A Protobuf message which supports runtime reflection.
+pub trait ReflectMessage: Message + Any {
+    /// Returns the descriptor of the message type.
+    fn descriptor(&self) -> &'static MessageDescriptor;
+
+    /// Returns the descriptor of the message type, without requiring an instance.
+    fn type_descriptor() -> &'static MessageDescriptor
+    where
+        Self: Sized;
+
+    /// Returns the value of the field with the given number.
+    ///
+    /// Returns `None` if the message has no such field, or if the field tracks presence and is
+    /// not set. Fields without presence always return a value, which may be the default.
+    fn get_field(&self, number: u32) -> Option<Value>;
+
+    /// Returns a borrowed view of the value of the field with the given number, which refers to
+    /// the strings, bytes and messages of the field rather than copying them.
+    ///
+    /// Returns `None` in the same cases as [`get_field`][Self::get_field].
+    fn get_field_ref(&self, number: u32) -> Option<ValueRef<'_>>;
+
+    /// Returns a mutable reference to the singular message or group field with the given number,
+    /// which is set to an empty message first if it isn't set.
+    ///
+    /// Returns `None` if the message has no such field, or if it's not a singular message or group
+    /// field.
+    fn get_message_mut(&mut self, number: u32) -> Option<&mut dyn ReflectMessage>;
+
+    /// Returns `true` if the field with the given number is set.
+    ///
+    /// Fields without presence are considered set if they differ from their default value.
+    /// Repeated and map fields are considered set if they are not empty.
+    fn has_field(&self, number: u32) -> bool;
+
+    /// Sets the field with the given number.
+    ///
+    /// An error is returned if the message has no such field, or if the value does not match the
+    /// type of the field, in which case the message is not modified.
+    fn set_field(&mut self, number: u32, value: Value) -> Result<(), ReflectError>;
+
+    /// Clears the field with the given number, resetting it to its default value.
+    ///
+    /// Returns `false` if the message has no such field.
+    fn clear_field(&mut self, number: u32) -> bool;
+
+    /// Returns a boxed copy of the message.
+    fn clone_boxed(&self) -> Box<dyn ReflectMessage>;
+
+    /// Returns `true` if `other` is a message of the same type with equal field values.
+    fn eq_dyn(&self, other: &dyn ReflectMessage) -> bool;
+
+    /// Encodes the message to the end of `buf`.
+    ///
+    /// This is [`Message::encode_raw`] for trait objects.
+    fn encode_dyn(&self, buf: &mut Vec<u8>);
+
+    /// Decodes fields from `buf` and merges them into the message.
+    ///
+    /// This is [`Message::merge`] for trait objects.
+    fn merge_dyn(&mut self, buf: &[u8]) -> Result<(), DecodeError>;
+
+    /// Converts the message to `&dyn Any`.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations; use `downcast_ref` instead.
+    #[doc(hidden)]
+    fn as_any(&self) -> &dyn Any;
+
+    /// Converts the boxed message to `Box<dyn Any>`.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations; use `downcast` instead.
+    #[doc(hidden)]
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Converts a boxed message of unknown type to `Self`, returning the box if the types differ.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    fn from_boxed(message: Box<dyn ReflectMessage>) -> Result<Self, Box<dyn ReflectMessage>>
+    where
+        Self: Sized,
+    {
+        message.downcast().map(|message| *message)
+    }
+
+    /// Returns the value of the field with the given name.
+    ///
+    /// See [`ReflectMessage::get_field`].
+    fn get_field_by_name(&self, name: &str) -> Option<Value> {
+        let number = self.descriptor().field_by_name(name)?.number();
+        self.get_field(number)
+    }
+
+    /// Sets the field with the given name.
+    ///
+    /// See [`ReflectMessage::set_field`].
+    fn set_field_by_name(&mut self, name: &str, value: Value) -> Result<(), ReflectError> {
+        let number = self
+            .descriptor()
+            .field_by_name(name)
+            .ok_or(ReflectError::UnknownFieldName)?
+            .number();
+        self.set_field(number, value)
+    }
+}
+
+impl dyn ReflectMessage {
+    /// Returns a reference to the message if it is of type `M`.
+    pub fn downcast_ref<M: ReflectMessage>(&self) -> Option<&M> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns the boxed message as `Box<M>` if it is of type `M`, or the original box otherwise.
+    pub fn downcast<M: ReflectMessage>(self: Box<Self>) -> Result<Box<M>, Box<Self>> {
+        if self.as_any().is::<M>() {
+            Ok(self.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<M> ReflectMessage for Box<M>
+where
+    M: ReflectMessage,
+{
+    fn descriptor(&self) -> &'static MessageDescriptor {
+        (**self).descriptor()
+    }
+    fn type_descriptor() -> &'static MessageDescriptor {
+        M::type_descriptor()
+    }
+    fn get_field(&self, number: u32) -> Option<Value> {
+        (**self).get_field(number)
+    }
+    fn get_field_ref(&self, number: u32) -> Option<ValueRef<'_>> {
+        (**self).get_field_ref(number)
+    }
+    fn get_message_mut(&mut self, number: u32) -> Option<&mut dyn ReflectMessage> {
+        (**self).get_message_mut(number)
+    }
+    fn has_field(&self, number: u32) -> bool {
+        (**self).has_field(number)
+    }
+    fn set_field(&mut self, number: u32, value: Value) -> Result<(), ReflectError> {
+        (**self).set_field(number, value)
+    }
+    fn clear_field(&mut self, number: u32) -> bool {
+        (**self).clear_field(number)
+    }
+    fn clone_boxed(&self) -> Box<dyn ReflectMessage> {
+        (**self).clone_boxed()
+    }
+    fn eq_dyn(&self, other: &dyn ReflectMessage) -> bool {
+        (**self).eq_dyn(other)
+    }
+    fn encode_dyn(&self, buf: &mut Vec<u8>) {
+        (**self).encode_dyn(buf)
+    }
+    fn merge_dyn(&mut self, buf: &[u8]) -> Result<(), DecodeError> {
+        (**self).merge_dyn(buf)
+    }
+    fn as_any(&self) -> &dyn Any {
+        (**self).as_any()
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        (*self).into_any()
+    }
+    fn from_boxed(message: Box<dyn ReflectMessage>) -> Result<Self, Box<dyn ReflectMessage>> {
+        M::from_boxed(message).map(Box::new)
+    }
+}
+
+/// The oneof half of the `ReflectMessage` derive.
+///
+/// Meant to be used only by `ReflectMessage` implementations.
+#[doc(hidden)]
+pub trait ReflectOneof: Sized {
+    /// The descriptors of the oneof's fields.
+    const FIELDS: &'static [FieldDescriptor];
+
+    /// Returns the field number of the set variant.
+    fn number(&self) -> u32;
+
+    /// Returns the value of the set variant.
+    fn get(&self) -> Value;
+
+    /// Returns a borrowed view of the value of the set variant.
+    fn get_ref(&self) -> ValueRef<'_>;
+
+    /// Returns a mutable reference to the message of the set variant, if it's a message or group
+    /// variant.
+    fn get_message_mut(&mut self) -> Option<&mut dyn ReflectMessage>;
+
+    /// Creates the variant with the given field number from a value.
+    fn from_value(number: u32, value: Value) -> Result<Self, ReflectError>;
+}
+
+/// A Protobuf enumeration which supports runtime reflection.
+///
+/// This is implemented by `#[derive(Enumeration)]` for enums annotated with `#[prost(reflect)]`.
+pub trait ReflectEnum {
+    /// Returns the descriptor of the enum type.
+    fn enum_descriptor() -> &'static EnumDescriptor;
+}
+
+/// Maps the Rust type of a message, group or map field to the type of its messages.
+///
+/// Meant to be used only by `ReflectMessage` implementations.
+#[doc(hidden)]
+pub trait MessageField {
+    type Message: ReflectMessage;
+}
+
+impl<M> MessageField for Option<M>
+where
+    M: ReflectMessage,
+{
+    type Message = M;
+}
+
+impl<M> MessageField for Vec<M>
+where
+    M: ReflectMessage,
+{
+    type Message = M;
+}
+
+impl<K, M> MessageField for BTreeMap<K, M>
+where
+    M: ReflectMessage,
+{
+    type Message = M;
+}
+
+#[cfg(feature = "std")]
+impl<K, M, S> MessageField for HashMap<K, M, S>
+where
+    M: ReflectMessage,
+{
+    type Message = M;
+}
+
+/// Describes a message type.
+pub struct MessageDescriptor {
+    full_name: &'static str,
+    fields: &'static [FieldDescriptor],
+    oneofs: &'static [OneofDescriptor],
+    new_message: fn() -> Box<dyn ReflectMessage>,
+}
+
+impl MessageDescriptor {
+    /// Creates a new message descriptor.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    pub const fn new(
+        full_name: &'static str,
+        fields: &'static [FieldDescriptor],
+        oneofs: &'static [OneofDescriptor],
+        new_message: fn() -> Box<dyn ReflectMessage>,
+    ) -> MessageDescriptor {
+        MessageDescriptor {
+            full_name,
+            fields,
+            oneofs,
+            new_message,
+        }
+    }
+
+    /// Returns the fully qualified name of the message type, e.g. `google.protobuf.Timestamp`.
+    pub fn full_name(&self) -> &'static str {
+        self.full_name
+    }
+
+    /// Returns the unqualified name of the message type, e.g. `Timestamp`.
+    pub fn name(&self) -> &'static str {
+        match self.full_name.rfind('.') {
+            Some(index) => &self.full_name[index + 1..],
+            None => self.full_name,
+        }
+    }
+
+    /// Returns an iterator over all fields of the message, including the fields of oneofs.
+    pub fn fields(&self) -> Fields {
+        Fields {
+            fields: self.fields.iter(),
+            oneofs: self.oneofs.iter(),
+        }
+    }
+
+    /// Returns the oneofs of the message.
+    pub fn oneofs(&self) -> &'static [OneofDescriptor] {
+        self.oneofs
+    }
+
+    /// Returns the field with the given number.
+    pub fn field(&self, number: u32) -> Option<&'static FieldDescriptor> {
+        self.fields().find(|field| field.number == number)
+    }
+
+    /// Returns the field with the given name.
+    pub fn field_by_name(&self, name: &str) -> Option<&'static FieldDescriptor> {
+        self.fields().find(|field| field.name == name)
+    }
+
+    /// Returns the field with the given JSON name.
+    pub fn field_by_json_name(&self, json_name: &str) -> Option<&'static FieldDescriptor> {
+        self.fields().find(|field| field.json_name == json_name)
+    }
+
+    /// Returns a new message of this type, with all fields set to their default values.
+    pub fn new_message(&self) -> Box<dyn ReflectMessage> {
+        (self.new_message)()
+    }
+
+    /// Returns the oneof containing the field with the given number.
+    pub fn oneof_of(&self, number: u32) -> Option<&'static OneofDescriptor> {
+        self.oneofs
+            .iter()
+            .find(|oneof| oneof.fields.iter().any(|field| field.number == number))
+    }
+}
+
+impl fmt::Debug for MessageDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageDescriptor")
+            .field("full_name", &self.full_name)
+            .field("fields", &self.fields)
+            .field("oneofs", &self.oneofs)
+            .finish()
+    }
+}
+
+/// An iterator over the fields of a message. See [`MessageDescriptor::fields`].
+#[derive(Clone, Debug)]
+pub struct Fields {
+    fields: slice::Iter<'static, FieldDescriptor>,
+    oneofs: slice::Iter<'static, OneofDescriptor>,
+}
+
+impl Iterator for Fields {
+    type Item = &'static FieldDescriptor;
+
+    fn next(&mut self) -> Option<&'static FieldDescriptor> {
+        loop {
+            if let Some(field) = self.fields.next() {
+                return Some(field);
+            }
+            self.fields = self.oneofs.next()?.fields.iter();
+        }
+    }
+}
+
+/// Describes a oneof.
+#[derive(Debug)]
+pub struct OneofDescriptor {
+    name: &'static str,
+    fields: &'static [FieldDescriptor],
+}
+
+impl OneofDescriptor {
+    /// Creates a new oneof descriptor.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, fields: &'static [FieldDescriptor]) -> OneofDescriptor {
+        OneofDescriptor { name, fields }
+    }
+
+    /// Returns the name of the oneof.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the fields of the oneof.
+    pub fn fields(&self) -> &'static [FieldDescriptor] {
+        self.fields
+    }
+}
+
+/// Describes a field of a message.
+pub struct FieldDescriptor {
+    name: &'static str,
+    json_name: &'static str,
+    number: u32,
+    kind: Kind,
+    cardinality: Cardinality,
+    message_type: Option<fn() -> &'static MessageDescriptor>,
+    enum_type: Option<fn() -> &'static EnumDescriptor>,
+}
+
+impl FieldDescriptor {
+    /// Creates a new field descriptor.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    pub const fn new(
+        name: &'static str,
+        json_name: &'static str,
+        number: u32,
+        kind: Kind,
+        cardinality: Cardinality,
+    ) -> FieldDescriptor {
+        FieldDescriptor {
+            name,
+            json_name,
+            number,
+            kind,
+            cardinality,
+            message_type: None,
+            enum_type: None,
+        }
+    }
+
+    /// Sets the message type of a message, group or map field with message values.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    pub const fn with_message_type(
+        mut self,
+        message_type: fn() -> &'static MessageDescriptor,
+    ) -> FieldDescriptor {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    /// Sets the enum type of an enum field or a map field with enum values.
+    ///
+    /// Meant to be used only by `ReflectMessage` implementations.
+    #[doc(hidden)]
+    pub const fn with_enum_type(
+        mut self,
+        enum_type: fn() -> &'static EnumDescriptor,
+    ) -> FieldDescriptor {
+        self.enum_type = Some(enum_type);
+        self
+    }
+
+    /// Returns the name of the field, as declared in the `.proto` file.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the name of the field in the JSON mapping, which is the lowerCamelCase form of
+    /// the `.proto` name unless overridden with the `json_name` option.
+    pub fn json_name(&self) -> &'static str {
+        self.json_name
+    }
+
+    /// Returns the field number.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Returns the type of the field's values. For map fields, this is the type of the map
+    /// entries.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns the cardinality of the field.
+    pub fn cardinality(&self) -> Cardinality {
+        self.cardinality
+    }
+
+    /// Returns `true` if the field is a repeated or map field.
+    pub fn is_list(&self) -> bool {
+        matches!(self.cardinality, Cardinality::Repeated) && !matches!(self.kind, Kind::Map(..))
+    }
+
+    /// Returns `true` if the field is a map field.
+    pub fn is_map(&self) -> bool {
+        matches!(self.kind, Kind::Map(..))
+    }
+
+    /// Returns the message type of a message or group field. For map fields, this is the type of
+    /// the map values, if they are messages.
+    pub fn message_type(&self) -> Option<&'static MessageDescriptor> {
+        self.message_type.map(|message_type| message_type())
+    }
+
+    /// Returns the enum type of an enum field. For map fields, this is the type of the map values,
+    /// if they are enums.
+    pub fn enum_type(&self) -> Option<&'static EnumDescriptor> {
+        self.enum_type.map(|enum_type| enum_type())
+    }
+}
+
+impl fmt::Debug for FieldDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldDescriptor")
+            .field("name", &self.name)
+            .field("json_name", &self.json_name)
+            .field("number", &self.number)
+            .field("kind", &self.kind)
+            .field("cardinality", &self.cardinality)
+            .finish()
+    }
+}
+
+impl PartialEq for FieldDescriptor {
+    fn eq(&self, other: &FieldDescriptor) -> bool {
+        self.name == other.name
+            && self.json_name == other.json_name
+            && self.number == other.number
+            && self.kind == other.kind
+            && self.cardinality == other.cardinality
+            && self.message_type().map(MessageDescriptor::full_name)
+                == other.message_type().map(MessageDescriptor::full_name)
+            && self.enum_type().map(EnumDescriptor::full_name)
+                == other.enum_type().map(EnumDescriptor::full_name)
+    }
+}
+
+impl Eq for FieldDescriptor {}
+
+/// Describes an enum type.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnumDescriptor {
+    full_name: &'static str,
+    values: &'static [(&'static str, i32)],
+}
+
+impl EnumDescriptor {
+    /// Creates a new enum descriptor.
+    ///
+    /// Meant to be used only by `ReflectEnum` implementations.
+    #[doc(hidden)]
+    pub const fn new(
+        full_name: &'static str,
+        values: &'static [(&'static str, i32)],
+    ) -> EnumDescriptor {
+        EnumDescriptor { full_name, values }
+    }
+
+    /// Returns the fully qualified name of the enum type, e.g. `google.protobuf.NullValue`.
+    pub fn full_name(&self) -> &'static str {
+        self.full_name
+    }
+
+    /// Returns the unqualified name of the enum type, e.g. `NullValue`.
+    pub fn name(&self) -> &'static str {
+        match self.full_name.rfind('.') {
+            Some(index) => &self.full_name[index + 1..],
+            None => self.full_name,
+        }
+    }
+
+    /// Returns the names and numbers of the enum's values, in declaration order.
+    pub fn values(&self) -> &'static [(&'static str, i32)] {
+        self.values
+    }
+
+    /// Returns the name of the value with the given number.
+    pub fn value_name(&self, number: i32) -> Option<&'static str> {
+        self.values
+            .iter()
+            .find(|&&(_, value)| value == number)
+            .map(|&(name, _)| name)
+    }
+
+    /// Returns the number of the value with the given name.
+    pub fn value_number(&self, name: &str) -> Option<i32> {
+        self.values
+            .iter()
+            .find(|&&(value, _)| value == name)
+            .map(|&(_, number)| number)
+    }
+}
+
+/// The type of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    Enum,
+    Message,
+    Group,
+    /// A map, with the given key and value types.
+    Map(&'static Kind, &'static Kind),
+}
+
+/// The cardinality of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cardinality {
+    /// A singular field without presence, such as a proto3 scalar field.
+    Singular,
+    /// A singular field which tracks presence.
+    Optional,
+    /// A proto2 required field.
+    Required,
+    /// A repeated or map field.
+    Repeated,
+}
+
+/// A dynamically typed field value.
+///
+/// Values of 32-bit integer fields (`int32`, `sint32`, `sfixed32`) are represented as `I32`, and
+/// likewise for the other integer widths. Enum fields are represented by their number, since
+/// prost stores open enums as `i32`.
+#[derive(Debug)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    EnumNumber(i32),
+    Message(Box<dyn ReflectMessage>),
+    /// The values of a repeated field.
+    List(Vec<Value>),
+    /// The entries of a map field.
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    /// Returns a boxed copy of the message as a value.
+    pub fn message<M>(message: &M) -> Value
+    where
+        M: ReflectMessage,
+    {
+        Value::Message(message.clone_boxed())
+    }
+
+    /// Returns the message if the value holds a message of type `M`.
+    pub fn as_message<M>(&self) -> Option<&M>
+    where
+        M: ReflectMessage,
+    {
+        match *self {
+            Value::Message(ref message) => message.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Value {
+        match *self {
+            Value::Bool(value) => Value::Bool(value),
+            Value::I32(value) => Value::I32(value),
+            Value::I64(value) => Value::I64(value),
+            Value::U32(value) => Value::U32(value),
+            Value::U64(value) => Value::U64(value),
+            Value::F32(value) => Value::F32(value),
+            Value::F64(value) => Value::F64(value),
+            Value::String(ref value) => Value::String(value.clone()),
+            Value::Bytes(ref value) => Value::Bytes(value.clone()),
+            Value::EnumNumber(value) => Value::EnumNumber(value),
+            Value::Message(ref value) => Value::Message(value.clone_boxed()),
+            Value::List(ref value) => Value::List(value.clone()),
+            Value::Map(ref value) => Value::Map(value.clone()),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::U32(a), Value::U32(b)) => a == b,
+            (Value::U64(a), Value::U64(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a == b,
+            (Value::F64(a), Value::F64(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::EnumNumber(a), Value::EnumNumber(b)) => a == b,
+            (Value::Message(a), Value::Message(b)) => a.eq_dyn(&**b),
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// A borrowed view of a field value, returned by [`ReflectMessage::get_field_ref`].
+///
+/// The variants match those of [`Value`], except that strings, bytes and messages are borrowed
+/// from the message which holds the field.
+#[derive(Clone, Debug)]
+pub enum ValueRef<'a> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    EnumNumber(i32),
+    Message(&'a dyn ReflectMessage),
+    /// The values of a repeated field.
+    List(Vec<ValueRef<'a>>),
+    /// The entries of a map field.
+    Map(Vec<(ValueRef<'a>, ValueRef<'a>)>),
+}
+
+impl ValueRef<'_> {
+    /// Returns an owned copy of the value.
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::Bool(value) => Value::Bool(value),
+            ValueRef::I32(value) => Value::I32(value),
+            ValueRef::I64(value) => Value::I64(value),
+            ValueRef::U32(value) => Value::U32(value),
+            ValueRef::U64(value) => Value::U64(value),
+            ValueRef::F32(value) => Value::F32(value),
+            ValueRef::F64(value) => Value::F64(value),
+            ValueRef::String(value) => Value::String(value.into()),
+            ValueRef::Bytes(value) => Value::Bytes(value.to_vec()),
+            ValueRef::EnumNumber(value) => Value::EnumNumber(value),
+            ValueRef::Message(value) => Value::Message(value.clone_boxed()),
+            ValueRef::List(ref values) => {
+                Value::List(values.iter().map(ValueRef::to_value).collect())
+            }
+            ValueRef::Map(ref entries) => Value::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_value(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PartialEq for ValueRef<'_> {
+    fn eq(&self, other: &ValueRef<'_>) -> bool {
+        match (self, other) {
+            (ValueRef::Bool(a), ValueRef::Bool(b)) => a == b,
+            (ValueRef::I32(a), ValueRef::I32(b)) => a == b,
+            (ValueRef::I64(a), ValueRef::I64(b)) => a == b,
+            (ValueRef::U32(a), ValueRef::U32(b)) => a == b,
+            (ValueRef::U64(a), ValueRef::U64(b)) => a == b,
+            (ValueRef::F32(a), ValueRef::F32(b)) => a == b,
+            (ValueRef::F64(a), ValueRef::F64(b)) => a == b,
+            (ValueRef::String(a), ValueRef::String(b)) => a == b,
+            (ValueRef::Bytes(a), ValueRef::Bytes(b)) => a == b,
+            (ValueRef::EnumNumber(a), ValueRef::EnumNumber(b)) => a == b,
+            (ValueRef::Message(a), ValueRef::Message(b)) => a.eq_dyn(*b),
+            (ValueRef::List(a), ValueRef::List(b)) => a == b,
+            (ValueRef::Map(a), ValueRef::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// An error returned when a field can not be set through reflection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReflectError {
+    /// The message has no field with the given number.
+    UnknownField(u32),
+    /// The message has no field with the given name.
+    UnknownFieldName,
+    /// The value does not match the type of the field.
+    TypeMismatch {
+        /// The name of the field.
+        field: &'static str,
+        /// The type of the field.
+        expected: Kind,
+    },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ReflectError::UnknownField(number) => write!(f, "unknown field number {}", number),
+            ReflectError::UnknownFieldName => f.write_str("unknown field name"),
+            ReflectError::TypeMismatch { field, expected } => {
+                write!(
+                    f,
+                    "invalid value for field {}: expected {:?}",
+                    field, expected
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReflectError {}
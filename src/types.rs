@@ -1,21 +1,27 @@
 //! Protocol Buffers well-known wrapper types.
 //!
-//! This module provides implementations of `Message` for Rust standard library types which
-//! correspond to a Protobuf well-known wrapper type. The remaining well-known types are defined in
-//! the `prost-types` crate in order to avoid a cyclic dependency between `prost` and
-//! `prost-build`.
+//! This module provides implementations of `Message`, `ReflectMessage` and `DiffMessage` for Rust
+//! standard library types which correspond to a Protobuf well-known wrapper type. The remaining
+//! well-known types are defined in the `prost-types` crate in order to avoid a cyclic dependency
+//! between `prost` and `prost-build`.
 
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::any::Any;
 
 use ::bytes::{Buf, BufMut, Bytes};
 
 use crate::{
+    diff::{self, DiffMessage, Differ},
     encoding::{
         bool, bytes, double, float, int32, int64, skip_field, string, uint32, uint64,
         DecodeContext, WireType,
     },
-    DecodeError, Message,
+    reflect::{
+        Cardinality, FieldDescriptor, Kind, MessageDescriptor, ReflectError, Value, ValueRef,
+    },
+    DecodeError, Message, ReflectMessage,
 };
 
 /// `google.protobuf.BoolValue`
@@ -422,3 +428,284 @@ impl Message for () {
     }
     fn clear(&mut self) {}
 }
+
+/// Implements `ReflectMessage` for a wrapper type, which has a single `value` field.
+macro_rules! reflect_wrapper {
+    (
+        $ty:ty,
+        $full_name:expr,
+        $kind:ident,
+        |$value:ident| $to_value:expr,
+        $to_value_ref:expr,
+        $variant:ident($from:ident) => $from_value:expr
+    ) => {
+        impl ReflectMessage for $ty {
+            fn descriptor(&self) -> &'static MessageDescriptor {
+                <$ty as ReflectMessage>::type_descriptor()
+            }
+
+            fn type_descriptor() -> &'static MessageDescriptor {
+                fn new_message() -> Box<dyn ReflectMessage> {
+                    Box::new(<$ty>::default())
+                }
+                static DESCRIPTOR: MessageDescriptor = MessageDescriptor::new(
+                    $full_name,
+                    &[FieldDescriptor::new(
+                        "value",
+                        "value",
+                        1,
+                        Kind::$kind,
+                        Cardinality::Singular,
+                    )],
+                    &[],
+                    new_message,
+                );
+                &DESCRIPTOR
+            }
+
+            fn get_field(&self, number: u32) -> Option<Value> {
+                let $value = self;
+                match number {
+                    1 => Some(Value::$variant($to_value)),
+                    _ => None,
+                }
+            }
+
+            fn get_field_ref(&self, number: u32) -> Option<ValueRef<'_>> {
+                let $value = self;
+                match number {
+                    1 => Some(ValueRef::$variant($to_value_ref)),
+                    _ => None,
+                }
+            }
+
+            fn get_message_mut(&mut self, _number: u32) -> Option<&mut dyn ReflectMessage> {
+                None
+            }
+
+            fn has_field(&self, number: u32) -> bool {
+                number == 1 && *self != <$ty>::default()
+            }
+
+            fn set_field(&mut self, number: u32, value: Value) -> Result<(), ReflectError> {
+                match (number, value) {
+                    (1, Value::$variant($from)) => {
+                        *self = $from_value;
+                        Ok(())
+                    }
+                    (1, _) => Err(ReflectError::TypeMismatch {
+                        field: "value",
+                        expected: Kind::$kind,
+                    }),
+                    _ => Err(ReflectError::UnknownField(number)),
+                }
+            }
+
+            fn clear_field(&mut self, number: u32) -> bool {
+                if number == 1 {
+                    *self = <$ty>::default();
+                }
+                number == 1
+            }
+
+            fn clone_boxed(&self) -> Box<dyn ReflectMessage> {
+                Box::new(self.clone())
+            }
+
+            fn eq_dyn(&self, other: &dyn ReflectMessage) -> bool {
+                other.downcast_ref::<$ty>() == Some(self)
+            }
+
+            fn encode_dyn(&self, buf: &mut Vec<u8>) {
+                Message::encode_raw(self, buf)
+            }
+
+            fn merge_dyn(&mut self, buf: &[u8]) -> Result<(), DecodeError> {
+                Message::merge(self, buf)
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn into_any(self: Box<Self>) -> Box<dyn Any> {
+                self
+            }
+        }
+    };
+}
+
+reflect_wrapper!(
+    bool, "google.protobuf.BoolValue", Bool,
+    |value| *value, *value,
+    Bool(value) => value
+);
+reflect_wrapper!(
+    u32, "google.protobuf.UInt32Value", Uint32,
+    |value| *value, *value,
+    U32(value) => value
+);
+reflect_wrapper!(
+    u64, "google.protobuf.UInt64Value", Uint64,
+    |value| *value, *value,
+    U64(value) => value
+);
+reflect_wrapper!(
+    i32, "google.protobuf.Int32Value", Int32,
+    |value| *value, *value,
+    I32(value) => value
+);
+reflect_wrapper!(
+    i64, "google.protobuf.Int64Value", Int64,
+    |value| *value, *value,
+    I64(value) => value
+);
+reflect_wrapper!(
+    f32, "google.protobuf.FloatValue", Float,
+    |value| *value, *value,
+    F32(value) => value
+);
+reflect_wrapper!(
+    f64, "google.protobuf.DoubleValue", Double,
+    |value| *value, *value,
+    F64(value) => value
+);
+reflect_wrapper!(
+    String, "google.protobuf.StringValue", String,
+    |value| value.clone(), value,
+    String(value) => value
+);
+reflect_wrapper!(
+    Vec<u8>, "google.protobuf.BytesValue", Bytes,
+    |value| value.clone(), value,
+    Bytes(value) => value
+);
+reflect_wrapper!(
+    Bytes, "google.protobuf.BytesValue", Bytes,
+    |value| value.to_vec(), value,
+    Bytes(value) => Bytes::from(value)
+);
+
+/// `google.protobuf.Empty`
+impl ReflectMessage for () {
+    fn descriptor(&self) -> &'static MessageDescriptor {
+        <() as ReflectMessage>::type_descriptor()
+    }
+
+    fn type_descriptor() -> &'static MessageDescriptor {
+        fn new_message() -> Box<dyn ReflectMessage> {
+            Box::new(())
+        }
+        static DESCRIPTOR: MessageDescriptor =
+            MessageDescriptor::new("google.protobuf.Empty", &[], &[], new_message);
+        &DESCRIPTOR
+    }
+
+    fn get_field(&self, _number: u32) -> Option<Value> {
+        None
+    }
+
+    fn get_field_ref(&self, _number: u32) -> Option<ValueRef<'_>> {
+        None
+    }
+
+    fn get_message_mut(&mut self, _number: u32) -> Option<&mut dyn ReflectMessage> {
+        None
+    }
+
+    fn has_field(&self, _number: u32) -> bool {
+        false
+    }
+
+    fn set_field(&mut self, number: u32, _value: Value) -> Result<(), ReflectError> {
+        Err(ReflectError::UnknownField(number))
+    }
+
+    fn clear_field(&mut self, _number: u32) -> bool {
+        false
+    }
+
+    fn clone_boxed(&self) -> Box<dyn ReflectMessage> {
+        Box::new(())
+    }
+
+    fn eq_dyn(&self, other: &dyn ReflectMessage) -> bool {
+        other.downcast_ref::<()>().is_some()
+    }
+
+    fn encode_dyn(&self, buf: &mut Vec<u8>) {
+        Message::encode_raw(self, buf)
+    }
+
+    fn merge_dyn(&mut self, buf: &[u8]) -> Result<(), DecodeError> {
+        Message::merge(self, buf)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Implements `DiffMessage` for a wrapper type, which has a single `value` field.
+macro_rules! diff_wrapper {
+    ($ty:ty, |$value:ident| $to_value_ref:expr) => {
+        impl DiffMessage for $ty {
+            fn diff_fields(&self, other: &dyn DiffMessage, differ: &mut dyn Differ) {
+                fn to_value_ref($value: &$ty) -> Option<diff::ValueRef<'_>> {
+                    if *$value != <$ty>::default() {
+                        Some($to_value_ref)
+                    } else {
+                        None
+                    }
+                }
+                if let Some(other) = other.downcast_ref::<$ty>() {
+                    differ.diff_field("value", to_value_ref(self), to_value_ref(other));
+                }
+            }
+
+            fn clone_boxed(&self) -> Box<dyn DiffMessage> {
+                Box::new(self.clone())
+            }
+
+            fn eq_dyn(&self, other: &dyn DiffMessage) -> bool {
+                other.downcast_ref::<$ty>() == Some(self)
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+    };
+}
+
+diff_wrapper!(bool, |value| diff::ValueRef::Bool(*value));
+diff_wrapper!(u32, |value| diff::ValueRef::U32(*value));
+diff_wrapper!(u64, |value| diff::ValueRef::U64(*value));
+diff_wrapper!(i32, |value| diff::ValueRef::I32(*value));
+diff_wrapper!(i64, |value| diff::ValueRef::I64(*value));
+diff_wrapper!(f32, |value| diff::ValueRef::F32(*value));
+diff_wrapper!(f64, |value| diff::ValueRef::F64(*value));
+diff_wrapper!(String, |value| diff::ValueRef::String(value));
+diff_wrapper!(Vec<u8>, |value| diff::ValueRef::Bytes(value));
+diff_wrapper!(Bytes, |value| diff::ValueRef::Bytes(value));
+
+/// `google.protobuf.Empty`
+impl DiffMessage for () {
+    fn diff_fields(&self, _other: &dyn DiffMessage, _differ: &mut dyn Differ) {}
+
+    fn clone_boxed(&self) -> Box<dyn DiffMessage> {
+        Box::new(())
+    }
+
+    fn eq_dyn(&self, other: &dyn DiffMessage) -> bool {
+        other.downcast_ref::<()>().is_some()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
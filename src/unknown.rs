@@ -0,0 +1,283 @@
+//! Storage for fields which are not recognized by a message's schema.
+
+use alloc::vec::Vec;
+
+use core::slice;
+
+use bytes::{Buf, BufMut};
+
+use crate::encoding::{
+    decode_key, decode_varint, encode_key, encode_varint, encoded_len_varint, key_len,
+    DecodeContext, WireType,
+};
+use crate::{DecodeError, DecodeErrorKind};
+
+/// A set of fields which were not recognized while decoding a message.
+///
+/// Messages opt in to preserving unknown fields by including a field of this type annotated with
+/// `#[prost(unknown_fields)]`. Any field with an unrecognized tag is stored here during decoding,
+/// and is re-emitted, in the order it was decoded, after the known fields when the message is
+/// encoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnknownFields {
+    fields: Vec<UnknownField>,
+}
+
+/// A single field which was not recognized while decoding a message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnknownField {
+    tag: u32,
+    value: UnknownFieldValue,
+}
+
+/// The wire-format payload of an unknown field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnknownFieldValue {
+    /// A varint-encoded value.
+    Varint(u64),
+    /// A 64-bit fixed-width value.
+    SixtyFourBit(u64),
+    /// A length-delimited value.
+    LengthDelimited(Vec<u8>),
+    /// A group of nested fields.
+    Group(UnknownFields),
+    /// A 32-bit fixed-width value.
+    ThirtyTwoBit(u32),
+}
+
+impl UnknownFields {
+    /// Creates an empty set of unknown fields.
+    pub fn new() -> UnknownFields {
+        UnknownFields::default()
+    }
+
+    /// Returns the number of unknown fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if there are no unknown fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns an iterator over the unknown fields, in the order they were decoded.
+    pub fn iter(&self) -> slice::Iter<'_, UnknownField> {
+        self.fields.iter()
+    }
+
+    /// Appends an unknown field.
+    pub fn push(&mut self, tag: u32, value: UnknownFieldValue) {
+        self.fields.push(UnknownField { tag, value });
+    }
+
+    /// Removes all unknown fields.
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    /// Decodes a field with an unrecognized tag from the buffer, and appends it to the set.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    pub fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        ctx.limit_reached()?;
+        let value = match wire_type {
+            WireType::Varint => UnknownFieldValue::Varint(decode_varint(buf)?),
+            WireType::SixtyFourBit => {
+                if buf.remaining() < 8 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::BufferUnderflow,
+                        "buffer underflow",
+                    ));
+                }
+                UnknownFieldValue::SixtyFourBit(buf.get_u64_le())
+            }
+            WireType::ThirtyTwoBit => {
+                if buf.remaining() < 4 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::BufferUnderflow,
+                        "buffer underflow",
+                    ));
+                }
+                UnknownFieldValue::ThirtyTwoBit(buf.get_u32_le())
+            }
+            WireType::LengthDelimited => {
+                let len = decode_varint(buf)?;
+                if len > buf.remaining() as u64 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::BufferUnderflow,
+                        "buffer underflow",
+                    ));
+                }
+                ctx.check_length(len)?;
+                let mut value = Vec::with_capacity(len as usize);
+                value.put(buf.take(len as usize));
+                UnknownFieldValue::LengthDelimited(value)
+            }
+            WireType::StartGroup => {
+                let mut group = UnknownFields::new();
+                loop {
+                    let (inner_tag, inner_wire_type) = decode_key(buf)?;
+                    if inner_wire_type == WireType::EndGroup {
+                        if inner_tag != tag {
+                            return Err(DecodeError::with_kind(
+                                DecodeErrorKind::UnexpectedEndGroup,
+                                "unexpected end group tag",
+                            ));
+                        }
+                        break;
+                    }
+                    group.merge_field(inner_tag, inner_wire_type, buf, ctx.enter_recursion())?;
+                }
+                UnknownFieldValue::Group(group)
+            }
+            WireType::EndGroup => {
+                return Err(DecodeError::with_kind(
+                    DecodeErrorKind::UnexpectedEndGroup,
+                    "unexpected end group tag",
+                ))
+            }
+        };
+        self.push(tag, value);
+        Ok(())
+    }
+
+    /// Encodes the unknown fields to the buffer.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    pub fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for field in &self.fields {
+            field.encode(buf);
+        }
+    }
+
+    /// Returns the encoded length of the unknown fields.
+    pub fn encoded_len(&self) -> usize {
+        self.fields.iter().map(UnknownField::encoded_len).sum()
+    }
+}
+
+impl<'a> IntoIterator for &'a UnknownFields {
+    type Item = &'a UnknownField;
+    type IntoIter = slice::Iter<'a, UnknownField>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl UnknownField {
+    /// Returns the field tag.
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Returns the field payload.
+    pub fn value(&self) -> &UnknownFieldValue {
+        &self.value
+    }
+
+    fn encode<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        match self.value {
+            UnknownFieldValue::Varint(value) => {
+                encode_key(self.tag, WireType::Varint, buf);
+                encode_varint(value, buf);
+            }
+            UnknownFieldValue::SixtyFourBit(value) => {
+                encode_key(self.tag, WireType::SixtyFourBit, buf);
+                buf.put_u64_le(value);
+            }
+            UnknownFieldValue::LengthDelimited(ref value) => {
+                encode_key(self.tag, WireType::LengthDelimited, buf);
+                encode_varint(value.len() as u64, buf);
+                buf.put_slice(value);
+            }
+            UnknownFieldValue::Group(ref group) => {
+                encode_key(self.tag, WireType::StartGroup, buf);
+                group.encode_raw(buf);
+                encode_key(self.tag, WireType::EndGroup, buf);
+            }
+            UnknownFieldValue::ThirtyTwoBit(value) => {
+                encode_key(self.tag, WireType::ThirtyTwoBit, buf);
+                buf.put_u32_le(value);
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self.value {
+            UnknownFieldValue::Varint(value) => key_len(self.tag) + encoded_len_varint(value),
+            UnknownFieldValue::SixtyFourBit(_) => key_len(self.tag) + 8,
+            UnknownFieldValue::LengthDelimited(ref value) => {
+                key_len(self.tag) + encoded_len_varint(value.len() as u64) + value.len()
+            }
+            UnknownFieldValue::Group(ref group) => 2 * key_len(self.tag) + group.encoded_len(),
+            UnknownFieldValue::ThirtyTwoBit(_) => key_len(self.tag) + 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(data: &[u8]) -> UnknownFields {
+        let mut fields = UnknownFields::new();
+        let mut buf = data;
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf).unwrap();
+            fields
+                .merge_field(tag, wire_type, &mut buf, DecodeContext::default())
+                .unwrap();
+        }
+
+        let mut encoded = Vec::new();
+        fields.encode_raw(&mut encoded);
+        assert_eq!(data, &encoded[..]);
+        assert_eq!(data.len(), fields.encoded_len());
+        fields
+    }
+
+    #[test]
+    fn roundtrip_all_wire_types() {
+        let fields = roundtrip(&[
+            0x08, 0x96, 0x01, // tag 1, varint 150
+            0x11, 1, 2, 3, 4, 5, 6, 7, 8, // tag 2, fixed64
+            0x1A, 0x03, b'f', b'o', b'o', // tag 3, length delimited
+            0x23, 0x08, 0x01, 0x24, // tag 4, group containing tag 1 varint
+            0x2D, 1, 2, 3, 4, // tag 5, fixed32
+        ]);
+
+        let tags = fields.iter().map(UnknownField::tag).collect::<Vec<_>>();
+        assert_eq!(tags, [1, 2, 3, 4, 5]);
+        assert_eq!(
+            fields.iter().next().unwrap().value(),
+            &UnknownFieldValue::Varint(150)
+        );
+    }
+
+    #[test]
+    fn mismatched_end_group() {
+        let mut fields = UnknownFields::new();
+        let mut buf: &[u8] = &[0x2C];
+        let err = fields.merge_field(4, WireType::StartGroup, &mut buf, DecodeContext::default());
+        assert!(err.is_err());
+    }
+}
@@ -7,3 +7,7 @@ pub struct OutdirRequest {
     #[prost(int32, tag="3")]
     pub result_per_page: i32,
 }
+impl ::prost::Name for OutdirRequest {
+    const NAME: &'static str = "OutdirRequest";
+    const PACKAGE: &'static str = "outdir";
+}
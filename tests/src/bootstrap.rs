@@ -23,6 +23,8 @@ fn bootstrap() {
     prost_build::Config::new()
         .compile_well_known_types()
         .btree_map(&["."])
+        .reflect(&["."])
+        .diff(&["."])
         .out_dir(tempdir.path())
         .compile_protos(
             &[
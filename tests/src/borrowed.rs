@@ -0,0 +1,119 @@
+use alloc::borrow::ToOwned;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{BorrowedMessage, DecodeOptions, Message};
+
+mod borrowed {
+    include!(concat!(env!("OUT_DIR"), "/borrowed.rs"));
+}
+
+use self::borrowed::{log_entry, owned_log_entry, Level, LogEntry, OwnedLogEntry, OwnedSource};
+
+fn owned_entry() -> OwnedLogEntry {
+    OwnedLogEntry {
+        timestamp: 1_650_000_000,
+        message: "request served".to_string(),
+        payload: vec![0xDE, 0xAD, 0xBE, 0xEF],
+        tags: vec!["http".to_string(), "ingress".to_string()],
+        chunks: vec![vec![1, 2], vec![], vec![3]],
+        host: Some("example.com".to_string()),
+        source: Some(OwnedSource {
+            file: "server.rs".to_string(),
+            line: 42,
+        }),
+        frames: vec![
+            OwnedSource {
+                file: "main.rs".to_string(),
+                line: 7,
+            },
+            OwnedSource::default(),
+        ],
+        level: Some(Level { severity: 3 }),
+        attributes: vec![("method".to_owned(), "GET".to_owned())]
+            .into_iter()
+            .collect(),
+        id: Some(owned_log_entry::Id::Name("worker".to_string())),
+    }
+}
+
+#[test]
+fn test_decode_borrowed() {
+    let owned = owned_entry();
+    let buf = owned.encode_to_vec();
+
+    let entry = LogEntry::decode_borrowed(&buf).unwrap();
+    assert_eq!(entry.timestamp, owned.timestamp);
+    assert_eq!(entry.message, "request served");
+    assert_eq!(entry.payload, &[0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(entry.tags, ["http", "ingress"]);
+    assert_eq!(entry.chunks, [&[1, 2][..], &[], &[3]]);
+    assert_eq!(entry.host, Some("example.com"));
+    assert_eq!(entry.host(), "example.com");
+    assert_eq!(entry.source.as_ref().unwrap().file, "server.rs");
+    assert_eq!(entry.source.as_ref().unwrap().line, 42);
+    assert_eq!(entry.frames.len(), 2);
+    assert_eq!(entry.frames[0].file, "main.rs");
+    assert_eq!(entry.level, Some(Level { severity: 3 }));
+    assert_eq!(entry.attributes, owned.attributes);
+    assert_eq!(entry.id, Some(log_entry::Id::Name("worker".to_string())));
+
+    // The borrowed fields point into the decoded buffer.
+    let range = buf.as_ptr_range();
+    assert!(range.contains(&entry.message.as_ptr()));
+    assert!(range.contains(&entry.payload.as_ptr()));
+    assert!(range.contains(&entry.tags[0].as_ptr()));
+    assert!(range.contains(&entry.source.as_ref().unwrap().file.as_ptr()));
+}
+
+#[test]
+fn test_borrowed_roundtrip() {
+    let owned = owned_entry();
+    let buf = owned.encode_to_vec();
+
+    let entry = LogEntry::decode_borrowed(&buf).unwrap();
+    assert_eq!(entry.encoded_len(), buf.len());
+    assert_eq!(entry.encode_to_vec(), buf);
+
+    let mut encoded = Vec::new();
+    entry.encode(&mut encoded).unwrap();
+    assert_eq!(OwnedLogEntry::decode(&*encoded).unwrap(), owned);
+}
+
+#[test]
+fn test_borrowed_default_and_clear() {
+    assert_eq!(LogEntry::decode_borrowed(&[]).unwrap(), LogEntry::default());
+    assert_eq!(LogEntry::default().encoded_len(), 0);
+
+    let buf = owned_entry().encode_to_vec();
+    let mut entry = LogEntry::decode_borrowed(&buf).unwrap();
+    entry.clear();
+    assert_eq!(entry, LogEntry::default());
+}
+
+#[test]
+fn test_borrowed_invalid_utf8() {
+    let buf = OwnedLogEntry {
+        payload: vec![0xFF],
+        ..OwnedLogEntry::default()
+    }
+    .encode_to_vec();
+    // Re-tag the `payload` bytes field as the `message` string field.
+    let buf = [&[0x12][..], &buf[1..]].concat();
+    assert!(LogEntry::decode_borrowed(&buf).is_err());
+}
+
+#[test]
+fn test_borrowed_decode_options() {
+    let buf = owned_entry().encode_to_vec();
+
+    let options = DecodeOptions::new().max_length(8);
+    assert!(LogEntry::decode_borrowed_with_options(&buf, options).is_err());
+
+    let options = DecodeOptions::new().max_repeated_len(2);
+    assert!(LogEntry::decode_borrowed_with_options(&buf, options).is_err());
+
+    let options = DecodeOptions::new().max_repeated_len(3).max_length(64);
+    assert!(LogEntry::decode_borrowed_with_options(&buf, options).is_ok());
+}
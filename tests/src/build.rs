@@ -44,6 +44,7 @@ fn main() {
     config.field_attribute("Foo.Custom.Attrs.AnotherEnum.D", "/// The D docs");
     config.field_attribute("Foo.Custom.Attrs.Msg.field.a", "/// Oneof A docs");
     config.field_attribute("Foo.Custom.Attrs.Msg.field.b", "/// Oneof B docs");
+    config.diff(&[".Foo.Bar_Baz"]);
 
     config.file_descriptor_set_path(
         PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR environment variable not set"))
@@ -54,7 +55,10 @@ fn main() {
         .compile_protos(&[src.join("ident_conversion.proto")], includes)
         .unwrap();
 
-    config
+    // Check that the native parser can be used in place of protoc.
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .native_parser()
         .compile_protos(&[src.join("nesting.proto")], includes)
         .unwrap();
 
@@ -90,11 +94,118 @@ fn main() {
         .compile_protos(&[src.join("default_string_escape.proto")], includes)
         .unwrap();
 
+    config
+        .compile_protos(&[src.join("extensions.proto")], includes)
+        .unwrap();
+
     prost_build::Config::new()
         .protoc_arg("--experimental_allow_proto3_optional")
         .compile_protos(&[src.join("proto3_presence.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .preserve_unknown_fields(&[".unknown_fields.Old"])
+        .compile_protos(&[src.join("unknown_fields.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .field_type(
+            ".conversion.User.id",
+            "crate::conversion::UserId",
+            "crate::conversion::UserId",
+        )
+        .field_type(
+            ".conversion.User.friends",
+            "crate::conversion::UserId",
+            "crate::conversion::UserId",
+        )
+        .field_type(
+            ".conversion.User.token",
+            "[u8; 4]",
+            "crate::conversion::Token",
+        )
+        .field_type(
+            ".conversion.User.tokens",
+            "[u8; 4]",
+            "crate::conversion::Token",
+        )
+        .field_type(
+            ".conversion.User.created_at",
+            "::core::time::Duration",
+            "crate::conversion::SinceEpoch",
+        )
+        .field_type(
+            ".conversion.User.logins",
+            "::core::time::Duration",
+            "crate::conversion::SinceEpoch",
+        )
+        .field_type(
+            ".conversion.User.name",
+            "crate::conversion::Name",
+            "crate::conversion::Name",
+        )
+        .diff(&[".conversion"])
+        .compile_protos(&[src.join("conversion.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .lazy(&[".lazy.Envelope"])
+        .diff(&[".lazy"])
+        .compile_protos(&[src.join("lazy.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .string_type(".custom_types", "::prost::alloc::boxed::Box<str>")
+        .string_type(
+            ".custom_types.Custom.name",
+            "crate::custom_types::SharedStr",
+        )
+        .bytes_type(".custom_types", "crate::custom_types::SharedBytes")
+        .compile_protos(&[src.join("custom_types.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .borrowed(&[".borrowed.LogEntry", ".borrowed.Source", ".borrowed.Level"])
+        .compile_protos(&[src.join("borrowed.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .reflect(&[".reflect"])
+        .protoc_arg("--experimental_allow_proto3_optional")
+        .compile_protos(&[src.join("reflect.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .reflect(&[".projection"])
+        .diff(&[".projection"])
+        .compile_protos(&[src.join("projection.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .reflect(&[".json"])
+        .protoc_arg("--experimental_allow_proto3_optional")
+        .compile_protos(&[src.join("json.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .type_registry(&["."])
+        .compile_protos(&[src.join("type_registry.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .file_descriptor_set_path(
+            PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR environment variable not set"))
+                .join("dynamic_descriptor_set.bin"),
+        )
+        .compile_protos(&[src.join("dynamic.proto")], includes)
+        .unwrap();
+
     {
         let mut config = prost_build::Config::new();
         config.disable_comments(&["."]);
@@ -0,0 +1,198 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::time::Duration;
+
+use prost::diff::Value;
+use prost::{Conversion, DecodeError, Message};
+use prost_types::diff::{diff, Change};
+use prost_types::Timestamp;
+
+use crate::check_message;
+
+mod conversion {
+    include!(concat!(env!("OUT_DIR"), "/conversion.rs"));
+}
+
+use self::conversion::User;
+
+/// A user ID, which is encoded as a `uint64`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UserId(u64);
+
+impl Conversion for UserId {
+    type Value = UserId;
+    type Wire = u64;
+
+    fn to_wire(value: &UserId) -> u64 {
+        value.0
+    }
+
+    fn from_wire(wire: u64) -> Result<UserId, DecodeError> {
+        Ok(UserId(wire))
+    }
+}
+
+/// A display name, which holds its wire representation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Name(String);
+
+impl Conversion for Name {
+    type Value = Name;
+    type Wire = String;
+
+    fn to_wire(_: &Name) -> String {
+        unreachable!("names are encoded through to_wire_ref")
+    }
+
+    fn to_wire_ref(value: &Name) -> Option<&String> {
+        Some(&value.0)
+    }
+
+    fn from_wire(wire: String) -> Result<Name, DecodeError> {
+        Ok(Name(wire))
+    }
+}
+
+/// Converts a four byte token to and from `bytes`.
+pub struct Token;
+
+impl Conversion for Token {
+    type Value = [u8; 4];
+    type Wire = Vec<u8>;
+
+    fn to_wire(value: &[u8; 4]) -> Vec<u8> {
+        value.to_vec()
+    }
+
+    fn from_wire(wire: Vec<u8>) -> Result<[u8; 4], DecodeError> {
+        <[u8; 4]>::try_from(&wire[..]).map_err(|_| DecodeError::new("invalid token length"))
+    }
+}
+
+/// Converts a duration since the Unix epoch to and from a `google.protobuf.Timestamp`.
+pub struct SinceEpoch;
+
+impl Conversion for SinceEpoch {
+    type Value = Duration;
+    type Wire = Timestamp;
+
+    fn to_wire(value: &Duration) -> Timestamp {
+        Timestamp {
+            seconds: value.as_secs() as i64,
+            nanos: value.subsec_nanos() as i32,
+        }
+    }
+
+    fn from_wire(wire: Timestamp) -> Result<Duration, DecodeError> {
+        if wire.seconds < 0 || wire.nanos < 0 {
+            return Err(DecodeError::new("timestamp before the Unix epoch"));
+        }
+        Ok(Duration::new(wire.seconds as u64, wire.nanos as u32))
+    }
+}
+
+fn user() -> User {
+    User {
+        id: UserId(42),
+        token: *b"abcd",
+        created_at: Some(Duration::new(1_600_000_000, 500)),
+        friends: vec![UserId(1), UserId(2), UserId(3)],
+        tokens: vec![*b"efgh", [0; 4]],
+        name: Name("name".to_string()),
+        logins: vec![Duration::new(1_700_000_000, 0), Duration::default()],
+    }
+}
+
+#[test]
+fn test_conversion_roundtrip() {
+    check_message(&user());
+    check_message(&User::default());
+
+    // Fields are encoded unless their wire representation is the default value, so the default
+    // token, which converts to four zero bytes, is encoded.
+    assert_eq!(User::default().encoded_len(), 6);
+
+    // The encoding is the same as the encoding of the wire representation.
+    let mut wire = Vec::new();
+    prost::encoding::uint64::encode(1, &42, &mut wire);
+    prost::encoding::bytes::encode(2, &b"abcd".to_vec(), &mut wire);
+    let msg = User {
+        id: UserId(42),
+        token: *b"abcd",
+        ..User::default()
+    };
+    assert_eq!(msg.encode_to_vec(), wire);
+
+    let mut cleared = user();
+    cleared.clear();
+    assert_eq!(cleared, User::default());
+}
+
+#[test]
+fn test_conversion_merge() {
+    // Converted message fields are merged through their wire representation.
+    let mut buf = Vec::new();
+    prost::encoding::message::encode(
+        3,
+        &Timestamp {
+            seconds: 10,
+            nanos: 0,
+        },
+        &mut buf,
+    );
+    prost::encoding::message::encode(
+        3,
+        &Timestamp {
+            seconds: 0,
+            nanos: 20,
+        },
+        &mut buf,
+    );
+    let msg = User::decode(&*buf).unwrap();
+    assert_eq!(msg.created_at, Some(Duration::new(10, 20)));
+
+    // Repeated fields are appended to.
+    let mut msg = user();
+    msg.merge(&*user().encode_to_vec()).unwrap();
+    assert_eq!(msg.friends.len(), 6);
+    assert_eq!(msg.tokens.len(), 4);
+}
+
+#[test]
+fn test_conversion_error() {
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(2, &b"abc".to_vec(), &mut buf);
+    let error = User::decode(&*buf).unwrap_err();
+    assert!(error.to_string().contains("invalid token length"));
+    assert!(error.to_string().contains("User.token"));
+}
+
+#[test]
+fn test_conversion_diff() {
+    // Converted fields are compared by their wire representation, which is borrowed from names
+    // rather than converted.
+    let mut new = user();
+    new.id = UserId(7);
+    new.created_at = None;
+    new.friends.push(UserId(4));
+    new.name = Name("other".to_string());
+
+    let diff = diff(&user(), &new);
+    assert_eq!(
+        diff.to_string(),
+        "~ id\n- created_at\n+ friends[3]\n~ name\n"
+    );
+    assert_eq!(
+        diff.changes[0].change,
+        Change::Changed(Value::U64(42), Value::U64(7))
+    );
+    assert_eq!(
+        diff.changes[3].change,
+        Change::Changed(
+            Value::String("name".to_string()),
+            Value::String("other".to_string())
+        )
+    );
+}
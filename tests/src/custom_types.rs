@@ -0,0 +1,142 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::bytes::{Buf, BufMut};
+use prost::{BytesAdapter, DecodeErrorKind, Message, StringAdapter};
+
+use crate::check_message;
+
+mod custom_types {
+    include!(concat!(env!("OUT_DIR"), "/custom_types.rs"));
+}
+
+use self::custom_types::{custom, Custom};
+
+/// A reference counted string, which can be cloned without copying it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedStr(Arc<str>);
+
+impl Default for SharedStr {
+    fn default() -> SharedStr {
+        SharedStr(Arc::from(""))
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl StringAdapter for SharedStr {
+    fn replace_with(&mut self, value: &str) {
+        self.0 = Arc::from(value);
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(value: &str) -> SharedStr {
+        SharedStr(Arc::from(value))
+    }
+}
+
+/// A reference counted byte buffer, which can be cloned without copying it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedBytes(Arc<[u8]>);
+
+impl Default for SharedBytes {
+    fn default() -> SharedBytes {
+        SharedBytes(Arc::from(&[][..]))
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl BytesAdapter for SharedBytes {
+    fn replace_with<B>(&mut self, buf: B)
+    where
+        B: Buf,
+    {
+        let mut vec = Vec::with_capacity(buf.remaining());
+        vec.put(buf);
+        self.0 = vec.into();
+    }
+}
+
+impl From<&[u8]> for SharedBytes {
+    fn from(value: &[u8]) -> SharedBytes {
+        SharedBytes(Arc::from(value))
+    }
+}
+
+/// A message with custom types, and a non-empty default value on a field without presence.
+#[derive(Clone, PartialEq, Message)]
+pub struct Derived {
+    #[prost(string = "SharedStr", tag = "1", default = "abc")]
+    pub name: SharedStr,
+    #[prost(bytes = "Box<[u8]>", tag = "2")]
+    pub data: Box<[u8]>,
+    #[prost(string = "Box<str>", optional, tag = "3")]
+    pub nickname: Option<Box<str>>,
+    #[prost(bytes = "SharedBytes", repeated, tag = "4")]
+    pub chunks: Vec<SharedBytes>,
+}
+
+#[test]
+fn test_custom_types_generated() {
+    let msg = Custom {
+        name: "name".into(),
+        payload: Some(b"payload"[..].into()),
+        greeting: None,
+        tags: vec!["a".into(), "".into(), "c".into()],
+        chunks: vec![b"chunk"[..].into(), SharedBytes::default()],
+        attributes: BTreeMap::new(),
+        value: Some(custom::Value::Text("text".into())),
+    };
+    check_message(&msg);
+    check_message(&Custom {
+        value: Some(custom::Value::Data(b"data"[..].into())),
+        ..msg.clone()
+    });
+
+    // Getters return the default value of unset fields.
+    assert_eq!(msg.payload(), b"payload");
+    assert_eq!(msg.greeting(), "hello");
+    let empty = Custom::default();
+    assert_eq!(empty.payload(), b"\x01\x02");
+    assert_eq!(empty.name.as_ref(), "");
+
+    // Decoding a string which isn't valid UTF-8 into a `Box<str>` fails.
+    let invalid = [0x0A, 0x00, 0x22, 0x01, 0xFF];
+    let error = Custom::decode(&invalid[..]).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::InvalidUtf8);
+}
+
+#[test]
+fn test_custom_types_derived() {
+    let msg = Derived::default();
+    assert_eq!(msg.name.as_ref(), "abc");
+    assert_eq!(msg.encoded_len(), 0);
+    assert_eq!(msg.nickname(), "");
+
+    // The empty string differs from the default value, so it is encoded.
+    let msg = Derived {
+        name: SharedStr::default(),
+        data: Box::from(&b"data"[..]),
+        nickname: Some("nickname".into()),
+        chunks: vec![b"chunk"[..].into()],
+    };
+    assert_eq!(msg.nickname(), "nickname");
+    check_message(&msg);
+
+    let mut cleared = msg;
+    cleared.clear();
+    assert!(cleared == Derived::default());
+}
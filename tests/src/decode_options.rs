@@ -0,0 +1,371 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{BorrowedMessage, DecodeErrorKind, DecodeOptions, Message};
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Limited {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: Vec<u8>,
+    #[prost(int32, repeated, tag = "3")]
+    pub packed: Vec<i32>,
+    #[prost(string, repeated, tag = "4")]
+    pub names: Vec<String>,
+    #[prost(message, repeated, tag = "5")]
+    pub children: Vec<Limited>,
+    #[prost(btree_map = "int32, string", tag = "6")]
+    pub map: BTreeMap<i32, String>,
+}
+
+fn limited() -> Limited {
+    Limited {
+        name: "four".to_string(),
+        data: vec![1, 2, 3, 4],
+        packed: vec![1, 2, 3],
+        names: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        children: vec![Limited::default(), Limited::default(), Limited::default()],
+        map: (0..3).map(|i| (i, i.to_string())).collect(),
+    }
+}
+
+#[test]
+fn test_default_options() {
+    let msg = limited();
+    let buf = msg.encode_to_vec();
+    assert_eq!(
+        Limited::decode_with_options(&*buf, DecodeOptions::default()),
+        Ok(msg)
+    );
+}
+
+#[test]
+fn test_max_message_size() {
+    let msg = limited();
+    let buf = msg.encode_to_vec();
+
+    let options = DecodeOptions::new().max_message_size(buf.len());
+    assert_eq!(Limited::decode_with_options(&*buf, options), Ok(msg));
+
+    let options = DecodeOptions::new().max_message_size(buf.len() - 1);
+    let err = Limited::decode_with_options(&*buf, options).unwrap_err();
+    assert_eq!(err.kind(), DecodeErrorKind::LimitExceeded);
+    assert_eq!(err.offset(), Some(0));
+}
+
+#[test]
+fn test_max_length() {
+    let msg = limited();
+    let buf = msg.encode_to_vec();
+
+    let options = DecodeOptions::new().max_length(4);
+    assert_eq!(Limited::decode_with_options(&*buf, options), Ok(msg));
+
+    for msg in [
+        Limited {
+            name: "five!".to_string(),
+            ..Limited::default()
+        },
+        Limited {
+            data: vec![0; 5],
+            ..Limited::default()
+        },
+    ] {
+        let buf = msg.encode_to_vec();
+        assert!(Limited::decode_with_options(&*buf, options).is_err());
+    }
+}
+
+#[test]
+fn test_max_repeated_len() {
+    let msg = limited();
+    let buf = msg.encode_to_vec();
+
+    let options = DecodeOptions::new().max_repeated_len(3);
+    assert_eq!(
+        Limited::decode_with_options(&*buf, options),
+        Ok(msg.clone())
+    );
+
+    let options = DecodeOptions::new().max_repeated_len(2);
+    for msg in [
+        Limited {
+            packed: msg.packed.clone(),
+            ..Limited::default()
+        },
+        Limited {
+            names: msg.names.clone(),
+            ..Limited::default()
+        },
+        Limited {
+            children: msg.children.clone(),
+            ..Limited::default()
+        },
+        Limited {
+            map: msg.map.clone(),
+            ..Limited::default()
+        },
+    ] {
+        let buf = msg.encode_to_vec();
+        assert!(Limited::decode_with_options(&*buf, options).is_err());
+    }
+}
+
+#[test]
+fn test_recursion_limit() {
+    let mut msg = Limited::default();
+    for _ in 0..10 {
+        msg = Limited {
+            children: vec![msg],
+            ..Limited::default()
+        };
+    }
+    let buf = msg.encode_to_vec();
+
+    let options = DecodeOptions::new().recursion_limit(10);
+    assert_eq!(Limited::decode_with_options(&*buf, options), Ok(msg));
+
+    let options = DecodeOptions::new().recursion_limit(9);
+    let err = Limited::decode_with_options(&*buf, options).unwrap_err();
+    assert_eq!(err.kind(), DecodeErrorKind::RecursionLimitReached);
+}
+
+#[test]
+fn test_merge_with_options() {
+    let msg = limited();
+    let buf = msg.encode_to_vec();
+
+    let mut merged = Limited::default();
+    merged
+        .merge_with_options(&*buf, DecodeOptions::new().max_repeated_len(6))
+        .unwrap();
+    merged
+        .merge_with_options(&*buf, DecodeOptions::new().max_repeated_len(6))
+        .unwrap();
+    assert_eq!(merged.names.len(), 6);
+
+    let err = merged.merge_with_options(&*buf, DecodeOptions::new().max_repeated_len(6));
+    assert!(err.is_err());
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Required {
+    #[prost(int32, required, tag = "1")]
+    pub id: i32,
+    #[prost(message, required, tag = "2")]
+    pub child: RequiredChild,
+    #[prost(group, required, tag = "3")]
+    pub group: RequiredChild,
+    #[prost(message, repeated, tag = "4")]
+    pub children: Vec<RequiredChild>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct RequiredChild {
+    #[prost(string, required, tag = "5")]
+    pub name: String,
+}
+
+/// The same message as `Required`, with optional fields.
+#[derive(Clone, PartialEq, Message)]
+pub struct Partial {
+    #[prost(int32, optional, tag = "1")]
+    pub id: Option<i32>,
+    #[prost(message, optional, tag = "2")]
+    pub child: Option<PartialChild>,
+    #[prost(group, optional, tag = "3")]
+    pub group: Option<PartialChild>,
+    #[prost(message, repeated, tag = "4")]
+    pub children: Vec<PartialChild>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PartialChild {
+    #[prost(string, optional, tag = "5")]
+    pub name: Option<String>,
+}
+
+fn partial_child(name: Option<&str>) -> PartialChild {
+    PartialChild {
+        name: name.map(ToString::to_string),
+    }
+}
+
+fn partial() -> Partial {
+    Partial {
+        id: Some(1),
+        child: Some(partial_child(Some("child"))),
+        group: Some(partial_child(Some("group"))),
+        children: vec![partial_child(Some("a")), partial_child(Some("b"))],
+    }
+}
+
+#[test]
+fn test_check_required_fields() {
+    let options = DecodeOptions::new().check_required_fields(true);
+    let check = |msg: Partial| {
+        Required::decode_with_options(&*msg.encode_to_vec(), options).map_err(|e| e.to_string())
+    };
+
+    let required = check(partial()).unwrap();
+    assert_eq!(
+        Required::decode(&*required.encode_to_vec()),
+        Ok(required.clone())
+    );
+
+    let missing = Partial {
+        id: None,
+        ..partial()
+    };
+    assert_eq!(
+        check(missing.clone()),
+        Err("failed to decode Protobuf message: Required.id: missing required field".to_string())
+    );
+    // Without the check, the missing field is decoded as its default value.
+    assert_eq!(Required::decode(&*missing.encode_to_vec()).unwrap().id, 0);
+
+    let missing = Partial {
+        child: None,
+        ..partial()
+    };
+    assert_eq!(
+        check(missing),
+        Err(
+            "failed to decode Protobuf message: Required.child: missing required field".to_string()
+        )
+    );
+
+    let missing = Partial {
+        group: Some(partial_child(None)),
+        ..partial()
+    };
+    assert_eq!(
+        check(missing),
+        Err(
+            "failed to decode Protobuf message: RequiredChild.name: Required.group: \
+             missing required field"
+                .to_string()
+        )
+    );
+
+    let missing = Partial {
+        children: vec![partial_child(Some("a")), partial_child(None)],
+        ..partial()
+    };
+    assert_eq!(
+        check(missing),
+        Err(
+            "failed to decode Protobuf message: RequiredChild.name: Required.children: \
+             missing required field"
+                .to_string()
+        )
+    );
+
+    // A nested message is checked once all of its occurrences have been merged.
+    let mut split = Partial {
+        child: Some(partial_child(None)),
+        ..partial()
+    }
+    .encode_to_vec();
+    Partial {
+        child: Some(partial_child(Some("child"))),
+        ..Partial::default()
+    }
+    .encode(&mut split)
+    .unwrap();
+    assert_eq!(
+        Required::decode_with_options(&*split, options),
+        Ok(required.clone())
+    );
+
+    // The required fields of the merged message, and of the messages it held, are present, even
+    // if they hold their default values.
+    let update = Partial {
+        child: Some(partial_child(None)),
+        ..Partial::default()
+    };
+    for existing in &[required.clone(), Required::default()] {
+        let mut merged = existing.clone();
+        merged
+            .merge_with_options(&*update.encode_to_vec(), options)
+            .unwrap();
+        assert_eq!(&merged, existing);
+    }
+
+    // Messages created by the merge are checked.
+    let update = Partial {
+        children: vec![partial_child(None)],
+        ..Partial::default()
+    };
+    let err = Required::default()
+        .merge_with_options(&*update.encode_to_vec(), options)
+        .unwrap_err();
+    assert_eq!(err.kind(), DecodeErrorKind::MissingRequiredField);
+}
+
+#[test]
+fn test_check_required_default_values() {
+    // Required fields are always encoded, so fields holding their default values are present.
+    let options = DecodeOptions::new().check_required_fields(true);
+    let required = Required {
+        children: vec![RequiredChild::default()],
+        ..Required::default()
+    };
+    assert_eq!(
+        Required::decode_with_options(&*required.encode_to_vec(), options),
+        Ok(required)
+    );
+}
+
+/// The same message as `Required`, borrowing from the decoded buffer.
+#[derive(Clone, PartialEq, Message)]
+pub struct BorrowedRequired<'a> {
+    #[prost(int32, required, tag = "1")]
+    pub id: i32,
+    #[prost(message, required, borrowed, tag = "2")]
+    pub child: BorrowedRequiredChild<'a>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BorrowedRequiredChild<'a> {
+    #[prost(string = "str", required, tag = "5")]
+    pub name: &'a str,
+}
+
+#[test]
+fn test_check_required_fields_borrowed() {
+    let options = DecodeOptions::new().check_required_fields(true);
+
+    let buf = partial().encode_to_vec();
+    let required = BorrowedRequired::decode_borrowed_with_options(&buf, options).unwrap();
+    assert_eq!(required.child.name, "child");
+
+    let buf = Partial {
+        child: Some(partial_child(None)),
+        ..partial()
+    }
+    .encode_to_vec();
+    assert_eq!(
+        BorrowedRequired::decode_borrowed_with_options(&buf, options).map_err(|e| e.to_string()),
+        Err(
+            "failed to decode Protobuf message: BorrowedRequiredChild.name: \
+             BorrowedRequired.child: missing required field"
+                .to_string()
+        )
+    );
+
+    let mut split = buf;
+    Partial {
+        child: Some(partial_child(Some("child"))),
+        ..Partial::default()
+    }
+    .encode(&mut split)
+    .unwrap();
+    assert_eq!(
+        BorrowedRequired::decode_borrowed_with_options(&split, options),
+        Ok(required)
+    );
+}
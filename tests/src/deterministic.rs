@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, HashMap};
+
+use prost::{Message, Oneof};
+
+/// A message with `HashMap` fields, at the top level and nested in map values, repeated messages,
+/// groups and oneofs.
+#[derive(Clone, PartialEq, Message)]
+pub struct Hashed {
+    #[prost(hash_map = "string, int32", tag = "1")]
+    pub counts: HashMap<String, i32>,
+    #[prost(hash_map = "int64, message", tag = "2")]
+    pub children: HashMap<i64, HashedChild>,
+    #[prost(message, repeated, tag = "3")]
+    pub repeated: Vec<HashedChild>,
+    #[prost(group, optional, tag = "4")]
+    pub group: Option<HashedChild>,
+    #[prost(oneof = "HashedOneof", tags = "5, 6")]
+    pub oneof: Option<HashedOneof>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct HashedChild {
+    #[prost(hash_map = "uint32, string", tag = "1")]
+    pub names: HashMap<u32, String>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum HashedOneof {
+    #[prost(message, tag = "5")]
+    Child(HashedChild),
+    #[prost(string, tag = "6")]
+    Name(String),
+}
+
+/// The same message as `Hashed`, with `BTreeMap` fields.
+#[derive(Clone, PartialEq, Message)]
+pub struct Sorted {
+    #[prost(btree_map = "string, int32", tag = "1")]
+    pub counts: BTreeMap<String, i32>,
+    #[prost(btree_map = "int64, message", tag = "2")]
+    pub children: BTreeMap<i64, SortedChild>,
+    #[prost(message, repeated, tag = "3")]
+    pub repeated: Vec<SortedChild>,
+    #[prost(group, optional, tag = "4")]
+    pub group: Option<SortedChild>,
+    #[prost(oneof = "SortedOneof", tags = "5, 6")]
+    pub oneof: Option<SortedOneof>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SortedChild {
+    #[prost(btree_map = "uint32, string", tag = "1")]
+    pub names: BTreeMap<u32, String>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum SortedOneof {
+    #[prost(message, tag = "5")]
+    Child(SortedChild),
+    #[prost(string, tag = "6")]
+    Name(String),
+}
+
+fn hashed_child(len: u32) -> HashedChild {
+    HashedChild {
+        names: (0..len).map(|i| (i, i.to_string())).collect(),
+    }
+}
+
+fn hashed() -> Hashed {
+    Hashed {
+        counts: (0..50).map(|i| (format!("key{}", i), i)).collect(),
+        children: (-10..10).map(|i| (i, hashed_child(i as u32 % 7))).collect(),
+        repeated: vec![hashed_child(20), hashed_child(30)],
+        group: Some(hashed_child(40)),
+        oneof: Some(HashedOneof::Child(hashed_child(50))),
+    }
+}
+
+#[test]
+fn test_deterministic_encoding() {
+    let hashed = hashed();
+    let encoded = hashed.encode_deterministic_to_vec();
+    assert_eq!(encoded.len(), hashed.encoded_len());
+
+    // The deterministic encoding of a message with `HashMap` fields is the same as the encoding of
+    // the equivalent message with `BTreeMap` fields, whose entries are always in key order.
+    let sorted = Sorted::decode(&*encoded).unwrap();
+    assert_eq!(encoded, sorted.encode_to_vec());
+    assert_eq!(encoded, sorted.encode_deterministic_to_vec());
+    assert_eq!(Hashed::decode(&*encoded).unwrap(), hashed);
+
+    // Maps with the same entries, inserted in a different order, are encoded to the same bytes.
+    let mut reordered = hashed.clone();
+    reordered.counts = HashMap::with_capacity(1000);
+    reordered
+        .counts
+        .extend((0..50).rev().map(|i| (format!("key{}", i), i)));
+    assert_eq!(reordered.encode_deterministic_to_vec(), encoded);
+
+    let mut buf = Vec::new();
+    hashed.encode_deterministic(&mut buf).unwrap();
+    assert_eq!(buf, encoded);
+
+    let mut buf = [0u8; 8];
+    assert!(hashed.encode_deterministic(&mut &mut buf[..]).is_err());
+}
+
+#[test]
+fn test_deterministic_encoding_boxed() {
+    let boxed = Box::new(hashed());
+    assert_eq!(
+        boxed.encode_deterministic_to_vec(),
+        hashed().encode_deterministic_to_vec()
+    );
+}
@@ -0,0 +1,227 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use prost::diff::Value;
+use prost_types::diff::{diff, Change, PathSegment};
+
+use crate::fixtures::projection::{person, Person};
+use crate::fixtures::{address, person};
+use crate::foo::bar_baz::foo_bar_baz::FuzzBuster;
+use crate::foo::bar_baz::FooBarBaz;
+
+#[test]
+fn test_diff() {
+    let mut old = person();
+    old.contacts
+        .insert("work".to_string(), address("5 Mill St", "Leeds"));
+    old.contact = Some(person::Contact::Email("ada@example.com".to_string()));
+    assert!(diff(&old, &old).is_empty());
+
+    let mut new = old.clone();
+    new.address.as_mut().unwrap().city = "Cambridge".to_string();
+    new.previous[1].street.clear();
+    new.previous.push(address("6 New St", "Derby"));
+    new.contacts.remove("home");
+    new.contacts.get_mut("work").unwrap().city = "Bradford".to_string();
+    new.contacts
+        .insert("club".to_string(), address("7 Park St", "Ely"));
+    new.scores.truncate(2);
+    new.manager = None;
+    new.contact = Some(person::Contact::Mail(address("8 Post St", "Bath")));
+
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "~ address.city\n\
+         - previous[1].street\n\
+         + previous[2]\n\
+         + contacts[\"club\"]\n\
+         - contacts[\"home\"]\n\
+         ~ contacts[\"work\"].city\n\
+         - scores[2]\n\
+         - manager\n\
+         - email\n\
+         + mail\n"
+    );
+
+    let change = &diff.changes[0];
+    assert_eq!(
+        change.path,
+        [PathSegment::Field("address"), PathSegment::Field("city")]
+    );
+    assert_eq!(
+        change.change,
+        Change::Changed(
+            Value::String("London".to_string()),
+            Value::String("Cambridge".to_string())
+        )
+    );
+    assert_eq!(
+        diff.changes[2].change,
+        Change::Added(Value::message(&new.previous[2]))
+    );
+    assert_eq!(diff.changes[6].change, Change::Removed(Value::I32(3)));
+
+    // Changes to elements and entries are covered by the mask of the whole field.
+    let mask = diff.field_mask();
+    assert_eq!(
+        mask.paths,
+        [
+            "address.city",
+            "contacts",
+            "email",
+            "mail",
+            "manager",
+            "previous",
+            "scores"
+        ]
+    );
+    let mut updated = old.clone();
+    mask.copy(&new, &mut updated).unwrap();
+    assert_eq!(updated, new);
+    assert!(prost_types::diff::diff(&updated, &new).is_empty());
+}
+
+#[test]
+fn test_diff_nested_oneof() {
+    let mut old = person();
+    old.contact = Some(person::Contact::Mail(address("8 Post St", "Bath")));
+    let mut new = old.clone();
+    new.contact = Some(person::Contact::Mail(address("8 Post St", "Wells")));
+    new.manager.as_mut().unwrap().scores.push(4);
+    new.name.clear();
+
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "- name\n+ manager.scores[1]\n~ mail.city\n"
+    );
+    assert_eq!(
+        diff.field_mask().paths,
+        ["mail.city", "manager.scores", "name"]
+    );
+}
+
+#[test]
+fn test_diff_oneof_switch() {
+    let old = person();
+    let mut new = person();
+    new.contact = Some(person::Contact::Email("ada@example.com".to_string()));
+
+    // Switching the field of a oneof removes the old field and adds the new one.
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.changes
+            .iter()
+            .map(|c| c.change.clone())
+            .collect::<Vec<_>>(),
+        [
+            Change::Added(Value::String("ada@example.com".to_string())),
+            Change::Removed(Value::message(&address("5 Post St", "Leeds"))),
+        ]
+    );
+    assert_eq!(diff.field_mask().paths, ["email", "mail"]);
+
+    // Clearing the oneof removes its field.
+    new.contact = None;
+    assert_eq!(prost_types::diff::diff(&old, &new).to_string(), "- mail\n");
+}
+
+#[test]
+fn test_diff_repeated() {
+    let old = person();
+
+    // Elements are compared by index, so removing an element changes the following ones.
+    let mut new = person();
+    new.scores.remove(1);
+    new.scores.push(4);
+    new.scores.push(5);
+    assert_eq!(
+        diff(&old, &new).to_string(),
+        "~ scores[1]\n~ scores[2]\n+ scores[3]\n"
+    );
+
+    // An empty repeated field isn't set, so clearing it removes the whole field.
+    let mut new = person();
+    new.previous.clear();
+    let diff = diff(&old, &new);
+    assert_eq!(diff.to_string(), "- previous\n");
+    assert_eq!(
+        diff.changes[0].change,
+        Change::Removed(Value::List(
+            old.previous.iter().map(Value::message).collect()
+        ))
+    );
+}
+
+#[test]
+fn test_diff_field_mask_nested() {
+    let old = person();
+    let mut new = person();
+    let manager = new.manager.as_mut().unwrap();
+    manager.address = Some(address("9 Bay St", "Hull"));
+    manager.manager = Some(Box::new(Person {
+        name: "Alan".to_string(),
+        ..Person::default()
+    }));
+    manager.previous.push(address("10 Dock St", "Leeds"));
+    new.previous[0].city = "Cambridge".to_string();
+    new.address.as_mut().unwrap().street.clear();
+
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "- address.street\n\
+         ~ previous[0].city\n\
+         + manager.address\n\
+         + manager.previous\n\
+         + manager.manager\n"
+    );
+    // Paths of nested messages are kept, and elements of repeated fields are covered by the
+    // path of the field.
+    let mask = diff.field_mask();
+    assert_eq!(
+        mask.paths,
+        [
+            "address.street",
+            "manager.address",
+            "manager.manager",
+            "manager.previous",
+            "previous"
+        ]
+    );
+    let mut updated = old.clone();
+    mask.copy(&new, &mut updated).unwrap();
+    assert_eq!(updated, new);
+}
+
+#[test]
+fn test_diff_without_reflection() {
+    // Messages generated without reflection are diffed by their `.proto` field names rather than
+    // their Rust identifiers.
+    let old = FooBarBaz {
+        fuzz_busters: vec![FuzzBuster::default()],
+        ..FooBarBaz::default()
+    };
+    let mut new = old.clone();
+    new.foo_bar_baz = 1;
+    new.r#type = 2;
+    new.self_ = 3;
+    new.fuzz_busters[0].t.insert(4, FooBarBaz::default());
+    new.fuzz_busters[0].nested_self = Some(Box::new(FuzzBuster::default()));
+
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "+ fooBar_baz\n\
+         + fuzz_busters[0].t\n\
+         + fuzz_busters[0].NestedSelf\n\
+         + type\n\
+         + self\n"
+    );
+    assert_eq!(
+        diff.changes[2].change,
+        Change::Added(Value::message(&FuzzBuster::default()))
+    );
+}
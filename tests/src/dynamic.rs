@@ -0,0 +1,472 @@
+use alloc::borrow::ToOwned;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{DecodeOptions, Message};
+use prost_types::dynamic::{DescriptorPool, DynamicMessage, Kind, MapKey, SetFieldError, Value};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::raw::{self, RawValue};
+use prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet, MessageOptions,
+};
+
+#[allow(clippy::wrong_self_convention)]
+mod dynamic {
+    include!(concat!(env!("OUT_DIR"), "/dynamic.rs"));
+}
+
+use self::dynamic::{order, Order, Status};
+
+const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/dynamic_descriptor_set.bin"));
+
+fn pool() -> DescriptorPool {
+    DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap()
+}
+
+fn order() -> Order {
+    Order {
+        id: 42,
+        customer: Some("Ada".to_string()),
+        status: Some(Status::Suspended as i32),
+        lines: vec![
+            order::Line {
+                sku: "widget".to_string(),
+                quantity: Some(3),
+                price_cents: Some(-150),
+            },
+            order::Line {
+                sku: "gizmo".to_string(),
+                quantity: None,
+                price_cents: None,
+            },
+        ],
+        tags: vec![1, -2, 300],
+        codes: vec![7, 8],
+        discounts: vec![("loyalty".to_string(), 0.5), ("promo".to_string(), 1.25)]
+            .into_iter()
+            .collect(),
+        placed: Some(prost_types::Timestamp {
+            seconds: 1_650_000_000,
+            nanos: 5,
+        }),
+        token: Some(vec![0xFF, 0x00]),
+        shipping: Some(order::Shipping {
+            address: Some("1 Main St".to_string()),
+            weight: Some(2.5),
+        }),
+        payment: Some(order::Payment::Card("4111".to_string())),
+    }
+}
+
+#[test]
+fn descriptors() {
+    let pool = pool();
+    assert!(pool
+        .files()
+        .iter()
+        .any(|file| file.name() == "dynamic.proto"));
+    assert!(pool
+        .files()
+        .iter()
+        .any(|file| file.name() == "google/protobuf/timestamp.proto"));
+    assert!(pool.get_message_by_name("dynamic.Status").is_none());
+    assert!(pool.get_enum_by_name("dynamic.Status").is_some());
+
+    let desc = pool.get_message_by_name(".dynamic.Order").unwrap();
+    assert_eq!(desc.full_name(), "dynamic.Order");
+    assert_eq!(desc.name(), "Order");
+    assert_eq!(desc.fields().len(), 12);
+
+    let lines = desc.get_field_by_name("lines").unwrap();
+    assert!(lines.is_list());
+    assert_eq!(lines.cardinality(), Label::Repeated);
+    match lines.kind() {
+        Kind::Message(line) => assert_eq!(line.full_name(), "dynamic.Order.Line"),
+        kind => panic!("unexpected kind: {:?}", kind),
+    }
+
+    let discounts = desc.get_field(7).unwrap();
+    assert!(discounts.is_map());
+    assert!(!discounts.is_list());
+
+    let placed = desc.get_field(8).unwrap();
+    assert_eq!(
+        placed.kind(),
+        Kind::Message(
+            pool.get_message_by_name("google.protobuf.Timestamp")
+                .unwrap()
+        )
+    );
+
+    assert!(desc.get_field(5).unwrap().is_packed());
+    assert!(!desc.get_field(6).unwrap().is_packed());
+    assert!(desc.get_field(10).unwrap().is_group());
+    assert_eq!(
+        desc.get_field(14).unwrap().containing_oneof(),
+        Some("payment")
+    );
+    assert_eq!(desc.get_field(2).unwrap().containing_oneof(), None);
+
+    // Explicit proto2 defaults.
+    assert_eq!(
+        desc.get_field(2).unwrap().default_value(),
+        Value::String("anonymous".to_string())
+    );
+    assert_eq!(
+        desc.get_field(3).unwrap().default_value(),
+        Value::EnumNumber(Status::Active as i32)
+    );
+    assert_eq!(
+        desc.get_field(9).unwrap().default_value(),
+        Value::Bytes(b"\x01\x02abc".to_vec())
+    );
+}
+
+#[test]
+fn decode_and_reencode() {
+    let order = order();
+    let bytes = order.encode_to_vec();
+
+    let desc = pool().get_message_by_name("dynamic.Order").unwrap();
+    let message = DynamicMessage::decode(desc, bytes.as_slice()).unwrap();
+
+    assert_eq!(message.get_field(1).unwrap().as_u64(), Some(42));
+    assert_eq!(
+        message.get_field_by_name("customer").unwrap().as_str(),
+        Some("Ada")
+    );
+    assert_eq!(
+        message.get_field(3).unwrap().as_enum_number(),
+        Some(Status::Suspended as i32)
+    );
+    assert_eq!(
+        *message.get_field(5).unwrap(),
+        Value::List(vec![Value::I32(1), Value::I32(-2), Value::I32(300)])
+    );
+
+    let lines = message.get_field(4).unwrap();
+    let lines = lines.as_list().unwrap();
+    assert_eq!(lines.len(), 2);
+    let second = lines[1].as_message().unwrap();
+    assert_eq!(second.get_field(1).unwrap().as_str(), Some("gizmo"));
+    assert!(!second.has_field(2));
+    assert_eq!(second.get_field(2).unwrap().as_u32(), Some(1));
+
+    let discounts = message.get_field(7).unwrap();
+    assert_eq!(
+        discounts.as_map().unwrap()[&MapKey::String("promo".to_string())],
+        Value::F64(1.25)
+    );
+
+    let placed = message.get_field(8).unwrap();
+    assert_eq!(
+        placed.as_message().unwrap().get_field(2).unwrap().as_i32(),
+        Some(5)
+    );
+    let shipping = message.get_field(10).unwrap();
+    assert_eq!(
+        shipping
+            .as_message()
+            .unwrap()
+            .get_field_by_name("weight")
+            .unwrap()
+            .as_f32(),
+        Some(2.5)
+    );
+    assert!(message.has_field(13));
+    assert!(!message.has_field(14));
+
+    assert_eq!(message.encoded_len(), bytes.len());
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn modify() {
+    let desc = pool().get_message_by_name("dynamic.Order").unwrap();
+    let mut message = DynamicMessage::decode(desc, order().encode_to_vec().as_slice()).unwrap();
+
+    message
+        .set_field_by_name("customer", Value::String("Grace".to_string()))
+        .unwrap();
+    message.clear_field_by_name("placed");
+    message.push_field(5, Value::I32(4)).unwrap();
+    message
+        .insert_map_entry(7, MapKey::String("bulk".to_string()), Value::F64(2.0))
+        .unwrap();
+    message
+        .get_message_mut_by_name("shipping")
+        .unwrap()
+        .set_field_by_name("weight", Value::F32(2.5))
+        .unwrap();
+
+    // Setting one oneof field clears the others.
+    let line = message.get_field(4).unwrap().as_list().unwrap()[0].clone();
+    message.set_field(14, line).unwrap();
+    assert!(!message.has_field(13));
+
+    // Fields which are not part of the schema are preserved.
+    let mut bytes = message.encode_to_vec();
+    prost::encoding::uint32::encode(99, &7, &mut bytes);
+    let message = DynamicMessage::decode(message.descriptor().clone(), bytes.as_slice()).unwrap();
+    assert_eq!(message.unknown_fields().len(), 1);
+
+    let order = Order::decode(message.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(order.customer(), "Grace");
+    assert_eq!(order.placed, None);
+    assert_eq!(order.tags, [1, -2, 300, 4]);
+    assert_eq!(order.discounts["bulk"], 2.0);
+    assert_eq!(order.shipping.unwrap().weight, Some(2.5));
+    assert_eq!(
+        order.payment,
+        Some(order::Payment::Voucher(order::Line {
+            sku: "widget".to_string(),
+            quantity: Some(3),
+            price_cents: Some(-150),
+        }))
+    );
+}
+
+#[test]
+fn set_field_errors() {
+    let pool = pool();
+    let desc = pool.get_message_by_name("dynamic.Order").unwrap();
+    let mut message = DynamicMessage::new(desc);
+
+    assert_eq!(
+        message.set_field(1, Value::I64(1)),
+        Err(SetFieldError::InvalidValue {
+            field: "id".to_string()
+        })
+    );
+    assert_eq!(
+        message.set_field(99, Value::I64(1)),
+        Err(SetFieldError::UnknownField(99))
+    );
+    assert_eq!(
+        message.set_field_by_name("nope", Value::I64(1)),
+        Err(SetFieldError::UnknownFieldName("nope".to_string()))
+    );
+    assert!(message
+        .set_field(5, Value::List(vec![Value::U32(1)]))
+        .is_err());
+
+    // Values added to repeated and map fields are checked as well.
+    assert_eq!(
+        message.push_field(5, Value::String("1".to_string())),
+        Err(SetFieldError::InvalidValue {
+            field: "tags".to_string()
+        })
+    );
+    assert!(message
+        .push_field(2, Value::String("1".to_string()))
+        .is_err());
+    assert!(message
+        .insert_map_entry(7, MapKey::I32(1), Value::F64(1.0))
+        .is_err());
+    assert!(message
+        .insert_map_entry_by_name(
+            "discounts",
+            MapKey::String("a".to_string()),
+            Value::F32(1.0)
+        )
+        .is_err());
+    assert!(message.get_message_mut(5).is_none());
+
+    // Messages must have the field's exact type.
+    let timestamp = pool
+        .get_message_by_name("google.protobuf.Timestamp")
+        .unwrap();
+    assert!(message
+        .set_field(14, Value::Message(DynamicMessage::new(timestamp)))
+        .is_err());
+    assert!(message.fields().next().is_none());
+}
+
+#[test]
+fn pool_errors() {
+    let set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+    let files = set
+        .file
+        .into_iter()
+        .filter(|file| file.name() == "dynamic.proto")
+        .collect::<Vec<_>>();
+
+    let error = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: files.clone(),
+    })
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid descriptor: file dynamic.proto imports google/protobuf/timestamp.proto, \
+         which is not in the pool"
+    );
+
+    // Adding the same file twice is allowed, but conflicting definitions are not.
+    let mut pool = pool();
+    pool.add_file_descriptor_set(FileDescriptorSet { file: files })
+        .unwrap();
+    let conflicting = FileDescriptorProto {
+        name: Some("conflicting.proto".to_owned()),
+        package: Some("dynamic".to_owned()),
+        enum_type: vec![prost_types::EnumDescriptorProto {
+            name: Some("Status".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(pool.add_file_descriptor_proto(conflicting).is_err());
+    assert!(pool
+        .files()
+        .iter()
+        .all(|file| file.name() != "conflicting.proto"));
+}
+
+#[test]
+fn map_entry_errors() {
+    fn field(name: &str, number: i32, ty: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(ty as i32),
+            ..Default::default()
+        }
+    }
+
+    fn pool_with_entry(entry_fields: Vec<FieldDescriptorProto>) -> Result<(), String> {
+        let file = FileDescriptorProto {
+            name: Some("map.proto".to_owned()),
+            package: Some("map".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("Map".to_owned()),
+                field: vec![FieldDescriptorProto {
+                    label: Some(Label::Repeated as i32),
+                    type_name: Some(".map.Map.EntriesEntry".to_owned()),
+                    ..field("entries", 1, Type::Message)
+                }],
+                nested_type: vec![DescriptorProto {
+                    name: Some("EntriesEntry".to_owned()),
+                    field: entry_fields,
+                    options: Some(MessageOptions {
+                        map_entry: Some(true),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] })
+            .map(drop)
+            .map_err(|error| error.to_string())
+    }
+
+    pool_with_entry(vec![
+        field("key", 1, Type::String),
+        field("value", 2, Type::Int32),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        pool_with_entry(vec![field("key", 1, Type::String)]).unwrap_err(),
+        "invalid descriptor: map entry map.Map.EntriesEntry must have a singular key field 1 \
+         and value field 2"
+    );
+    assert_eq!(
+        pool_with_entry(vec![
+            field("key", 1, Type::Float),
+            field("value", 2, Type::Int32),
+        ])
+        .unwrap_err(),
+        "invalid descriptor: map entry map.Map.EntriesEntry has a key type that can't be used \
+         as a map key"
+    );
+    assert_eq!(
+        pool_with_entry(vec![
+            field("key", 1, Type::String),
+            field("value", 0, Type::Int32),
+        ])
+        .unwrap_err(),
+        "invalid descriptor: field map.Map.EntriesEntry.value has invalid number 0"
+    );
+}
+
+#[test]
+fn check_required_fields() {
+    let desc = pool().get_message_by_name("dynamic.Order").unwrap();
+    let options = DecodeOptions::new().check_required_fields(true);
+
+    let buf = order().encode_to_vec();
+    let mut message = DynamicMessage::new(desc.clone());
+    message.merge_with_options(&*buf, options).unwrap();
+
+    let buf = Order::default().encode_to_vec();
+    let mut message = DynamicMessage::new(desc.clone());
+    message.merge_with_options(&*buf, options).unwrap();
+
+    // The required `id` field is missing from an empty message, and the required `sku` field is
+    // missing from the empty line.
+    let mut message = DynamicMessage::new(desc.clone());
+    let err = message.merge_with_options(&[][..], options).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("missing required field dynamic.Order.id"));
+
+    let buf = [0x08, 0x01, 0x22, 0x00];
+    let mut message = DynamicMessage::new(desc);
+    let err = message.merge_with_options(&buf[..], options).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("missing required field dynamic.Order.Line.sku"));
+}
+
+#[test]
+fn decode_raw_with_descriptor() {
+    let desc = pool().get_message_by_name("dynamic.Order").unwrap();
+    let buf = Order {
+        id: 42,
+        lines: vec![order::Line {
+            sku: "widget".to_string(),
+            quantity: Some(3),
+            price_cents: None,
+        }],
+        tags: vec![1, 300],
+        token: Some(vec![b'o', b'k']),
+        shipping: Some(order::Shipping {
+            address: None,
+            weight: Some(1.0),
+        }),
+        ..Order::default()
+    }
+    .encode_to_vec();
+
+    // Without the descriptor, the token looks like a string.
+    let message = raw::decode_raw(&buf).unwrap();
+    assert_eq!(
+        message.to_string(),
+        "1: 42\n\
+         4 {\n  1: \"widget\"\n  2: 3\n}\n\
+         5: [1, 300]\n\
+         9: \"ok\"\n\
+         10 {\n  12: 0x3f800000\n}\n"
+    );
+
+    let message = raw::decode_raw_with_descriptor(&buf, &desc).unwrap();
+    assert_eq!(message.fields[3].value, RawValue::Bytes(b"ok".to_vec()));
+    assert_eq!(
+        message.to_string(),
+        "id (1): 42\n\
+         lines (4) {\n  sku (1): \"widget\"\n  quantity (2): 3\n}\n\
+         tags (5): [1, 300]\n\
+         token (9): \"ok\"\n\
+         shipping (10) {\n  weight (12): 0x3f800000\n}\n"
+    );
+
+    // Fields which don't match the descriptor are classified without it.
+    let message = raw::decode_raw_with_descriptor(&[0x22, 0x02, 0xFF, 0xFF], &desc).unwrap();
+    assert_eq!(message.fields[0].name.as_deref(), Some("lines"));
+    assert_eq!(message.fields[0].value, RawValue::Bytes(vec![0xFF, 0xFF]));
+}
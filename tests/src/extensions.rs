@@ -0,0 +1,179 @@
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{ExtendableMessage, Message};
+
+use crate::check_message;
+
+#[allow(clippy::wrong_self_convention)]
+mod extensions {
+    include!(concat!(env!("OUT_DIR"), "/extensions.rs"));
+}
+
+use self::extensions::*;
+
+fn nested(name: &str, values: &[i32]) -> Nested {
+    Nested {
+        name: Some(name.to_string()),
+        values: values.to_vec(),
+    }
+}
+
+fn extendable() -> Extendable {
+    let mut message = Extendable {
+        id: Some(7),
+        ..Default::default()
+    };
+    message.set_extension(&INT32_EXT, -42);
+    message.set_extension(&SINT64_EXT, -1_000_000_000_000);
+    message.set_extension(&STRING_EXT, "string".to_string());
+    message.set_extension(&BYTES_EXT, b"bytes".to_vec());
+    message.set_extension(&ENUM_EXT, Color::Green as i32);
+    message.set_extension(&MESSAGE_EXT, nested("message", &[1]));
+    message.set_extension(&REPEATED_EXT, vec![1, 2, 3]);
+    message.set_extension(&PACKED_EXT, vec![4, 5, 6]);
+    message.set_extension(
+        &REPEATED_MESSAGE_EXT,
+        vec![nested("a", &[]), nested("b", &[2, 3])],
+    );
+    message.set_extension(&GROUPEXT, GroupExt { a: Some(9) });
+    message.set_extension(&scope::SCOPED_EXT, true);
+    message
+}
+
+fn plain() -> Plain {
+    Plain {
+        id: Some(7),
+        int32_ext: Some(-42),
+        sint64_ext: Some(-1_000_000_000_000),
+        string_ext: Some("string".to_string()),
+        bytes_ext: Some(b"bytes".to_vec()),
+        enum_ext: Some(Color::Green as i32),
+        message_ext: Some(nested("message", &[1])),
+        repeated_ext: vec![1, 2, 3],
+        packed_ext: vec![4, 5, 6],
+        repeated_message_ext: vec![nested("a", &[]), nested("b", &[2, 3])],
+        groupext: Some(plain::GroupExt { a: Some(9) }),
+        scoped_ext: Some(true),
+        unknown: None,
+    }
+}
+
+#[test]
+fn test_extensions_roundtrip() {
+    let message = extendable();
+    check_message(&message);
+    assert_eq!(Plain::decode(&*message.encode_to_vec()).unwrap(), plain());
+
+    let decoded = Extendable::decode(&*plain().encode_to_vec()).unwrap();
+    assert_eq!(decoded.id, Some(7));
+    assert_eq!(decoded.get_extension(&INT32_EXT), Ok(Some(-42)));
+    assert_eq!(
+        decoded.get_extension(&SINT64_EXT),
+        Ok(Some(-1_000_000_000_000))
+    );
+    assert_eq!(
+        decoded.get_extension(&STRING_EXT),
+        Ok(Some("string".to_string()))
+    );
+    assert_eq!(
+        decoded.get_extension(&BYTES_EXT),
+        Ok(Some(b"bytes".to_vec()))
+    );
+    assert_eq!(
+        decoded.get_extension(&ENUM_EXT),
+        Ok(Some(Color::Green as i32))
+    );
+    assert_eq!(
+        decoded.get_extension(&MESSAGE_EXT),
+        Ok(Some(nested("message", &[1])))
+    );
+    assert_eq!(
+        decoded.get_extension(&REPEATED_EXT),
+        Ok(Some(vec![1, 2, 3]))
+    );
+    assert_eq!(decoded.get_extension(&PACKED_EXT), Ok(Some(vec![4, 5, 6])));
+    assert_eq!(
+        decoded.get_extension(&REPEATED_MESSAGE_EXT),
+        Ok(Some(vec![nested("a", &[]), nested("b", &[2, 3])]))
+    );
+    assert_eq!(
+        decoded.get_extension(&GROUPEXT),
+        Ok(Some(GroupExt { a: Some(9) }))
+    );
+    assert_eq!(decoded.get_extension(&scope::SCOPED_EXT), Ok(Some(true)));
+    assert_eq!(decoded, extendable());
+}
+
+#[test]
+fn test_extension_ranges() {
+    assert_eq!(
+        Extendable::EXTENSION_RANGES,
+        &[(100, 200), (1000, 536_870_912)]
+    );
+    assert_eq!(INT32_EXT.number(), 100);
+    assert_eq!(INT32_EXT.full_name(), "extensions.int32_ext");
+    assert_eq!(scope::SCOPED_EXT.full_name(), "extensions.Scope.scoped_ext");
+    check_message(&Scope {});
+
+    // Fields outside of the extension ranges are not retained.
+    let message = Plain {
+        unknown: Some("unknown".to_string()),
+        ..plain()
+    };
+    let decoded = Extendable::decode(&*message.encode_to_vec()).unwrap();
+    assert_eq!(
+        decoded._extensions.numbers().collect::<Vec<_>>(),
+        [100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 1000]
+    );
+    assert_eq!(decoded, extendable());
+}
+
+#[test]
+fn test_extension_merge() {
+    let mut buf = Plain {
+        message_ext: Some(nested("first", &[1])),
+        repeated_ext: vec![1],
+        int32_ext: Some(1),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    Plain {
+        message_ext: Some(nested("second", &[2])),
+        repeated_ext: vec![2],
+        int32_ext: Some(2),
+        ..Default::default()
+    }
+    .encode(&mut buf)
+    .unwrap();
+
+    let decoded = Extendable::decode(&*buf).unwrap();
+    assert_eq!(
+        decoded.get_extension(&MESSAGE_EXT),
+        Ok(Some(nested("second", &[1, 2])))
+    );
+    assert_eq!(decoded.get_extension(&REPEATED_EXT), Ok(Some(vec![1, 2])));
+    assert_eq!(decoded.get_extension(&INT32_EXT), Ok(Some(2)));
+}
+
+#[test]
+fn test_extension_clear() {
+    let mut message = extendable();
+    assert!(message.has_extension(&STRING_EXT));
+    message.clear_extension(&STRING_EXT);
+    assert!(!message.has_extension(&STRING_EXT));
+    assert_eq!(message.get_extension(&STRING_EXT), Ok(None));
+
+    message.clear();
+    assert!(message._extensions.is_empty());
+    assert_eq!(message, Extendable::default());
+}
+
+#[test]
+fn test_extension_type_mismatch() {
+    // A varint with the field number of `string_ext`.
+    let message = Extendable::decode(&[0xB0, 0x06, 0x01][..]).unwrap();
+    assert!(message.has_extension(&STRING_EXT));
+    assert!(message.get_extension(&STRING_EXT).is_err());
+}
@@ -0,0 +1,185 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+
+use prost::ReflectMessage;
+use prost_types::FieldMask;
+
+use crate::fixtures::projection::{person, Person};
+use crate::fixtures::{address, person};
+
+#[test]
+fn test_field_mask_validate() {
+    let desc = Person::type_descriptor();
+    let mask = FieldMask::new(["name", "address.city", "manager.manager.scores", "contacts"]);
+    assert_eq!(mask.validate(desc), Ok(()));
+
+    // Repeated and map fields may only be the last field of a path.
+    for path in [
+        "",
+        "nickname",
+        "name.first",
+        "previous.city",
+        "contacts.street",
+        "address.",
+    ] {
+        let error = FieldMask::new(["name", path]).validate(desc).unwrap_err();
+        assert_eq!(error.path(), path);
+    }
+}
+
+#[test]
+fn test_field_mask_copy() {
+    let source = person();
+    let mut destination = Person {
+        name: "Grace".to_string(),
+        address: Some(address("6 Dock St", "Hull")),
+        scores: vec![9],
+        contact: Some(person::Contact::Email("grace@example.com".to_string())),
+        ..Person::default()
+    };
+
+    let mask = FieldMask::new(["address.city", "manager.name", "previous", "email"]);
+    mask.copy(&source, &mut destination).unwrap();
+    assert_eq!(
+        destination,
+        Person {
+            name: "Grace".to_string(),
+            address: Some(address("6 Dock St", "London")),
+            previous: vec![address("2 High St", "Oxford"), address("3 Low St", "Bath")],
+            scores: vec![9],
+            manager: Some(Box::new(Person {
+                name: "Charles".to_string(),
+                ..Person::default()
+            })),
+            // The email isn't set in the source, so it's cleared.
+            contact: None,
+            ..Person::default()
+        }
+    );
+
+    // Fields which aren't set in the source are cleared, and nested messages are only created if
+    // they're set in the source.
+    let mut copy = person();
+    FieldMask::new(["name", "scores", "manager.scores", "address.street"])
+        .copy(&Person::default(), &mut copy)
+        .unwrap();
+    assert_eq!(copy.name, "");
+    assert!(copy.scores.is_empty());
+    assert_eq!(copy.manager.unwrap().name, "Charles");
+    assert_eq!(copy.address, Some(address("", "London")));
+
+    // Invalid paths leave the destination unchanged.
+    let mut unchanged = person();
+    let error = FieldMask::new(["name", "nickname"])
+        .copy(&Person::default(), &mut unchanged)
+        .unwrap_err();
+    assert_eq!(error.path(), "nickname");
+    assert_eq!(unchanged, person());
+}
+
+#[test]
+fn test_field_mask_merge() {
+    let source = person();
+    let mut destination = Person {
+        name: "Grace".to_string(),
+        address: Some(address("6 Dock St", "Hull")),
+        scores: vec![9],
+        manager: Some(Box::new(Person {
+            name: "Alan".to_string(),
+            scores: vec![5],
+            ..Person::default()
+        })),
+        ..Person::default()
+    };
+
+    let mask = FieldMask::new(["scores", "address", "manager.scores", "email"]);
+    mask.merge(&source, &mut destination).unwrap();
+    assert_eq!(
+        destination,
+        Person {
+            name: "Grace".to_string(),
+            address: Some(address("1 Main St", "London")),
+            scores: vec![9, 1, 2, 3],
+            manager: Some(Box::new(Person {
+                name: "Alan".to_string(),
+                scores: vec![5, 4],
+                ..Person::default()
+            })),
+            ..Person::default()
+        }
+    );
+
+    // Fields which aren't set in the source are left unchanged.
+    let mut merged = person();
+    mask.merge(&Person::default(), &mut merged).unwrap();
+    assert_eq!(merged, person());
+
+    // Map entries are replaced by key, and whole message fields, including the message fields of
+    // a oneof, are merged field by field.
+    let mut destination = Person {
+        contacts: vec![
+            ("home".to_string(), address("7 Old St", "Bath")),
+            ("work".to_string(), address("8 Mill St", "Derby")),
+        ]
+        .into_iter()
+        .collect(),
+        manager: Some(Box::new(Person {
+            name: "Alan".to_string(),
+            scores: vec![5],
+            ..Person::default()
+        })),
+        contact: Some(person::Contact::Mail(address("9 Bay St", "Bath"))),
+        ..Person::default()
+    };
+    let source = Person {
+        contact: Some(person::Contact::Mail(address("", "Leeds"))),
+        ..person()
+    };
+    FieldMask::new(["contacts", "manager", "mail"])
+        .merge(&source, &mut destination)
+        .unwrap();
+    assert_eq!(
+        destination,
+        Person {
+            contacts: vec![
+                ("home".to_string(), address("4 Side St", "York")),
+                ("work".to_string(), address("8 Mill St", "Derby")),
+            ]
+            .into_iter()
+            .collect(),
+            manager: Some(Box::new(Person {
+                name: "Charles".to_string(),
+                scores: vec![5, 4],
+                ..Person::default()
+            })),
+            contact: Some(person::Contact::Mail(address("9 Bay St", "Leeds"))),
+            ..Person::default()
+        }
+    );
+}
+
+#[test]
+fn test_field_mask_trim() {
+    let mut trimmed = person();
+    FieldMask::new(["address.city", "contacts", "mail", "manager.scores"])
+        .trim(&mut trimmed)
+        .unwrap();
+    assert_eq!(
+        trimmed,
+        Person {
+            address: Some(address("", "London")),
+            contacts: person().contacts,
+            manager: Some(Box::new(Person {
+                scores: vec![4],
+                ..Person::default()
+            })),
+            contact: person().contact,
+            ..Person::default()
+        }
+    );
+
+    let mut trimmed = person();
+    FieldMask::default().trim(&mut trimmed).unwrap();
+    assert_eq!(trimmed, Person::default());
+}
@@ -0,0 +1,68 @@
+//! Messages and sample values shared by several test modules.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+
+#[allow(clippy::wrong_self_convention)]
+pub(crate) mod json_proto {
+    include!(concat!(env!("OUT_DIR"), "/json.rs"));
+}
+
+pub(crate) mod projection {
+    include!(concat!(env!("OUT_DIR"), "/projection.rs"));
+}
+
+use self::json_proto::{scalars, Color, Scalars};
+use self::projection::{person, Address, Person};
+
+pub(crate) fn address(street: &str, city: &str) -> Address {
+    Address {
+        street: street.to_string(),
+        city: city.to_string(),
+    }
+}
+
+/// A person with every field of `projection.Person` set.
+pub(crate) fn person() -> Person {
+    Person {
+        name: "Ada".to_string(),
+        address: Some(address("1 Main St", "London")),
+        previous: vec![address("2 High St", "Oxford"), address("3 Low St", "Bath")],
+        contacts: vec![("home".to_string(), address("4 Side St", "York"))]
+            .into_iter()
+            .collect(),
+        scores: vec![1, 2, 3],
+        manager: Some(Box::new(Person {
+            name: "Charles".to_string(),
+            scores: vec![4],
+            ..Person::default()
+        })),
+        contact: Some(person::Contact::Mail(address("5 Post St", "Leeds"))),
+    }
+}
+
+/// A message with every scalar field of `json.Scalars` set.
+pub(crate) fn scalars() -> Scalars {
+    Scalars {
+        int32_value: -5,
+        int64_value: -9_007_199_254_740_993,
+        uint32_value: 7,
+        uint64_value: u64::MAX,
+        float_value: 1.5,
+        double_value: f64::NEG_INFINITY,
+        bool_value: true,
+        string_value: "a \"quoted\"\n\u{1}".to_string(),
+        bytes_value: vec![0xFB, 0xFF, 0x01],
+        color: Color::Red as i32,
+        renamed: "x".to_string(),
+        optional_int32: Some(0),
+        int64_list: vec![1, -2],
+        colors: vec![Color::Green as i32, 42],
+        int_map: vec![(-1, "neg".to_string()), (2, "two".to_string())]
+            .into_iter()
+            .collect(),
+        bool_map: vec![(true, Color::Green as i32)].into_iter().collect(),
+        choice: Some(scalars::Choice::Text(String::new())),
+    }
+}
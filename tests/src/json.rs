@@ -0,0 +1,374 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{Message, ReflectMessage};
+use prost_types::json::{self, ParseOptions, SerializeOptions};
+use prost_types::{value, Any, Duration, FieldMask, ListValue, Struct, Timestamp, TypeRegistry};
+
+use crate::fixtures::json_proto::{scalars, Color, Scalars, WellKnown};
+use crate::fixtures::scalars;
+
+fn value(kind: value::Kind) -> prost_types::Value {
+    prost_types::Value { kind: Some(kind) }
+}
+
+fn well_known() -> WellKnown {
+    WellKnown {
+        timestamp: Some(Timestamp {
+            seconds: 1_650_000_000,
+            nanos: 5_000_000,
+        }),
+        duration: Some(Duration {
+            seconds: 0,
+            nanos: -500_000_000,
+        }),
+        field_mask: Some(FieldMask {
+            paths: vec!["user.display_name".to_string(), "photo".to_string()],
+        }),
+        struct_value: Some(Struct {
+            fields: vec![
+                ("a".to_string(), value(value::Kind::NumberValue(1.0))),
+                (
+                    "b".to_string(),
+                    value(value::Kind::ListValue(ListValue {
+                        values: vec![
+                            value(value::Kind::NullValue(0)),
+                            value(value::Kind::BoolValue(true)),
+                            value(value::Kind::StringValue("s".to_string())),
+                        ],
+                    })),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }),
+        value: Some(value(value::Kind::StringValue("v".to_string()))),
+        list_value: Some(ListValue::default()),
+        int64_wrapper: Some(-3),
+        string_wrapper: Some(String::new()),
+        bool_wrapper: None,
+        empty: Some(()),
+        values: vec![value(value::Kind::NullValue(0))],
+        null_value: 0,
+    }
+}
+
+#[test]
+fn serialize() {
+    assert_eq!(
+        json::to_string(&scalars()).unwrap(),
+        r#"{"int32Value":-5,"int64Value":"-9007199254740993","uint32Value":7,"#.to_string()
+            + r#""uint64Value":"18446744073709551615","floatValue":1.5,"#
+            + r#""doubleValue":"-Infinity","boolValue":true,"#
+            + r#""stringValue":"a \"quoted\"\n\u0001","bytesValue":"+/8B","color":"COLOR_RED","#
+            + r#""customName":"x","optionalInt32":0,"int64List":["1","-2"],"#
+            + r#""colors":["COLOR_GREEN",42],"intMap":{"-1":"neg","2":"two"},"#
+            + r#""boolMap":{"true":"COLOR_GREEN"},"text":""}"#
+    );
+    assert_eq!(json::to_string(&Scalars::default()).unwrap(), "{}");
+}
+
+#[test]
+fn parse() {
+    // Proto field names, quoted and exponent integers, enum numbers and unpadded URL-safe base64
+    // are all accepted. A null oneof field is ignored.
+    let parsed = json::from_str::<Scalars>(
+        r#"{
+            "int32_value": "-5",
+            "int64Value": -9007199254740993,
+            "uint32Value": 7e0,
+            "uint64_value": "18446744073709551615",
+            "floatValue": "1.5",
+            "doubleValue": "-Infinity",
+            "boolValue": true,
+            "stringValue": "a \"quoted\"\n\u0001",
+            "bytesValue": "-_8B",
+            "color": 1,
+            "customName": "x",
+            "optionalInt32": 0,
+            "int64List": ["1", -2],
+            "colors": ["COLOR_GREEN", 42],
+            "intMap": {"-1": "neg", "2": "two"},
+            "boolMap": {"true": "COLOR_GREEN"},
+            "text": "",
+            "nested": null
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(parsed, scalars());
+
+    let nested = Scalars {
+        choice: Some(scalars::Choice::Nested(Box::new(scalars()))),
+        ..Scalars::default()
+    };
+    let json = json::to_string(&nested).unwrap();
+    assert_eq!(json::from_str::<Scalars>(&json).unwrap(), nested);
+}
+
+#[test]
+fn well_known_types() {
+    let json = json::to_string(&well_known()).unwrap();
+    assert_eq!(
+        json,
+        r#"{"timestamp":"2022-04-15T05:20:00.005Z","duration":"-0.500s","#.to_string()
+            + r#""fieldMask":"user.displayName,photo","structValue":{"a":1,"b":[null,true,"s"]},"#
+            + r#""value":"v","listValue":[],"int64Wrapper":"-3","stringWrapper":"","empty":{},"#
+            + r#""values":[null]}"#
+    );
+    assert_eq!(json::from_str::<WellKnown>(&json).unwrap(), well_known());
+
+    // Well-known types are also mapped at the top level.
+    assert_eq!(
+        json::to_string(&Timestamp {
+            seconds: 0,
+            nanos: 0
+        })
+        .unwrap(),
+        r#""1970-01-01T00:00:00Z""#
+    );
+    assert_eq!(
+        json::from_str::<Duration>(r#""1.000000010s""#).unwrap(),
+        Duration {
+            seconds: 1,
+            nanos: 10
+        }
+    );
+    assert_eq!(
+        json::from_str::<Timestamp>(r#""1970-01-01T01:00:00+01:00""#).unwrap(),
+        Timestamp {
+            seconds: 0,
+            nanos: 0
+        }
+    );
+    let null = json::from_str::<WellKnown>(r#"{"value":null,"nullValue":null}"#).unwrap();
+    assert_eq!(null.value, Some(value(value::Kind::NullValue(0))));
+}
+
+#[test]
+fn options() {
+    let message = Scalars {
+        int32_value: 1,
+        renamed: "x".to_string(),
+        ..Scalars::default()
+    };
+    let options = SerializeOptions::new().use_proto_field_name(true);
+    assert_eq!(
+        json::to_string_with_options(&message, &options).unwrap(),
+        r#"{"int32_value":1,"renamed":"x"}"#
+    );
+
+    // Fields with presence are not emitted when unset.
+    let options = SerializeOptions::new().emit_unpopulated_fields(true);
+    let json = json::to_string_with_options(&Scalars::default(), &options).unwrap();
+    assert!(json.starts_with(r#"{"int32Value":0,"int64Value":"0","#));
+    assert!(json.contains(r#""color":"COLOR_UNSPECIFIED","customName":"","int64List":[]"#));
+    assert!(json.ends_with(r#""intMap":{},"boolMap":{}}"#));
+    assert!(!json.contains("optionalInt32"));
+    assert!(!json.contains("text"));
+
+    let input = r#"{"unknown":{"a":[1]},"color":"COLOR_BLUE","colors":["COLOR_RED","COLOR_BLUE"]}"#;
+    assert!(json::from_str::<Scalars>(input).is_err());
+    let options = ParseOptions::new().ignore_unknown_fields(true);
+    assert_eq!(
+        json::from_str_with_options::<Scalars>(input, &options).unwrap(),
+        Scalars {
+            colors: vec![Color::Red as i32],
+            ..Scalars::default()
+        }
+    );
+}
+
+#[test]
+fn any() {
+    let mut registry = TypeRegistry::new();
+    registry
+        .register_well_known_types()
+        .register_descriptor(Scalars::type_descriptor());
+    let serialize = SerializeOptions::new().type_registry(&registry);
+    let parse = ParseOptions::new().type_registry(&registry);
+    let roundtrip = |any: &Any, expected: &str| {
+        let json = json::to_string_with_options(any, &serialize).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(
+            json::from_str_with_options::<Any>(&json, &parse).unwrap(),
+            *any
+        );
+    };
+
+    // Messages are written with their fields, and `@type` may follow them.
+    let scalars = Any {
+        type_url: "type.googleapis.com/json.Scalars".to_string(),
+        value: Scalars {
+            int32_value: 5,
+            ..Scalars::default()
+        }
+        .encode_to_vec(),
+    };
+    roundtrip(
+        &scalars,
+        r#"{"@type":"type.googleapis.com/json.Scalars","int32Value":5}"#,
+    );
+    assert_eq!(
+        json::from_str_with_options::<Any>(
+            r#"{"int32Value":5,"@type":"type.googleapis.com/json.Scalars"}"#,
+            &parse
+        )
+        .unwrap(),
+        scalars
+    );
+
+    // Well-known types with a special form are written in a `value` key.
+    let duration = Any {
+        type_url: "type.googleapis.com/google.protobuf.Duration".to_string(),
+        value: Duration {
+            seconds: 1,
+            nanos: 0,
+        }
+        .encode_to_vec(),
+    };
+    roundtrip(
+        &duration,
+        r#"{"@type":"type.googleapis.com/google.protobuf.Duration","value":"1s"}"#,
+    );
+    roundtrip(
+        &Any {
+            type_url: "type.googleapis.com/google.protobuf.Any".to_string(),
+            value: duration.encode_to_vec(),
+        },
+        r#"{"@type":"type.googleapis.com/google.protobuf.Any","value":{"@type":"type.googleapis.com/google.protobuf.Duration","value":"1s"}}"#,
+    );
+    roundtrip(
+        &Any {
+            type_url: "type.googleapis.com/google.protobuf.Int32Value".to_string(),
+            value: 12345i32.encode_to_vec(),
+        },
+        r#"{"@type":"type.googleapis.com/google.protobuf.Int32Value","value":12345}"#,
+    );
+    roundtrip(
+        &Any {
+            type_url: "type.googleapis.com/google.protobuf.Empty".to_string(),
+            value: Vec::new(),
+        },
+        r#"{"@type":"type.googleapis.com/google.protobuf.Empty"}"#,
+    );
+    roundtrip(&Any::default(), "{}");
+
+    // The message type must be registered.
+    assert!(json::to_string(&scalars).is_err());
+    assert!(json::from_str::<Any>(r#"{"@type":"type.googleapis.com/json.Scalars"}"#).is_err());
+    let invalid: Vec<&str> = vec![
+        r#"{"int32Value":5}"#,
+        r#"{"@type":"json.Scalars"}"#,
+        r#"{"@type":1}"#,
+        r#"{"@type":"type.googleapis.com/json.Scalars","unknown":1}"#,
+        r#"{"@type":"type.googleapis.com/google.protobuf.Duration","value":"1s","x":1}"#,
+    ];
+    for input in invalid {
+        assert!(
+            json::from_str_with_options::<Any>(input, &parse).is_err(),
+            "parsed {}",
+            input
+        );
+    }
+}
+
+#[test]
+fn parse_errors() {
+    let invalid: Vec<&str> = vec![
+        r#"{"int32Value":1.5}"#,
+        r#"{"int32Value":2147483648}"#,
+        r#"{"int32Value":" 1"}"#,
+        r#"{"uint32Value":-1}"#,
+        r#"{"int64Value":"9223372036854775808"}"#,
+        r#"{"floatValue":1e39}"#,
+        r#"{"doubleValue":"nan"}"#,
+        r#"{"boolValue":"true"}"#,
+        r#"{"bytesValue":"!!"}"#,
+        r#"{"color":"COLOR_BLUE"}"#,
+        r#"{"int32Value":1,"int32_value":2}"#,
+        r#"{"text":"a","nested":{}}"#,
+        r#"{"int64List":[null]}"#,
+        r#"{"intMap":{"a":"b"}}"#,
+        r#"{"int32Value":1,}"#,
+        r#"{"stringValue":"\ud800"}"#,
+        r#"{"stringValue":"a"} x"#,
+        r#"[]"#,
+    ];
+    for input in invalid {
+        assert!(
+            json::from_str::<Scalars>(input).is_err(),
+            "parsed {}",
+            input
+        );
+    }
+
+    let invalid: Vec<&str> = vec![
+        r#"{"timestamp":"2022-04-15t05:20:00Z"}"#,
+        r#"{"timestamp":"2022-04-15T05:20:00"}"#,
+        r#"{"timestamp":"0000-12-31T23:59:59Z"}"#,
+        r#"{"duration":"315576000001s"}"#,
+        r#"{"duration":"1"}"#,
+        r#"{"fieldMask":"display_name"}"#,
+        r#"{"int64Wrapper":true}"#,
+        r#"{"values":[1e400]}"#,
+    ];
+    for input in invalid {
+        assert!(
+            json::from_str::<WellKnown>(input).is_err(),
+            "parsed {}",
+            input
+        );
+    }
+
+    assert_eq!(
+        json::from_str::<Scalars>(r#"{"unknown":1}"#)
+            .unwrap_err()
+            .to_string(),
+        r#"invalid JSON: unknown field "unknown" in json.Scalars"#
+    );
+
+    let mut nested = String::new();
+    for _ in 0..200 {
+        nested.push_str(r#"{"nested":"#);
+    }
+    assert!(json::from_str::<Scalars>(&nested).is_err());
+}
+
+#[test]
+fn serialize_errors() {
+    let invalid = vec![
+        WellKnown {
+            timestamp: Some(Timestamp {
+                seconds: i64::MAX,
+                nanos: 0,
+            }),
+            ..WellKnown::default()
+        },
+        WellKnown {
+            duration: Some(Duration {
+                seconds: 1,
+                nanos: -1,
+            }),
+            ..WellKnown::default()
+        },
+        WellKnown {
+            field_mask: Some(FieldMask {
+                paths: vec!["fooBar".to_string()],
+            }),
+            ..WellKnown::default()
+        },
+        WellKnown {
+            value: Some(value(value::Kind::NumberValue(f64::NAN))),
+            ..WellKnown::default()
+        },
+    ];
+    for message in invalid {
+        assert!(
+            json::to_string(&message).is_err(),
+            "serialized {:?}",
+            message
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{Lazy, Message};
+
+mod lazy {
+    include!(concat!(env!("OUT_DIR"), "/lazy.rs"));
+}
+
+use self::lazy::{EagerEnvelope, Envelope, Payload};
+
+fn payload(data: &str) -> Payload {
+    Payload {
+        data: data.to_string(),
+        values: vec![1, 2, 3],
+    }
+}
+
+#[test]
+fn test_lazy_roundtrip() {
+    let envelope = Envelope {
+        id: 7,
+        payload: Some(Lazy::new(payload("a"))),
+        attachments: vec![Lazy::new(payload("b")), Lazy::new(payload("c"))],
+        next: Some(Lazy::new(Envelope {
+            id: 8,
+            ..Envelope::default()
+        })),
+    };
+    let eager = EagerEnvelope {
+        id: 7,
+        payload: Some(payload("a")),
+        attachments: vec![payload("b"), payload("c")],
+        next: Some(Box::new(EagerEnvelope {
+            id: 8,
+            ..EagerEnvelope::default()
+        })),
+    };
+
+    // Lazy fields are encoded in the same way as eager fields.
+    let buf = envelope.encode_to_vec();
+    assert_eq!(buf, eager.encode_to_vec());
+    assert_eq!(envelope.encoded_len(), buf.len());
+
+    let mut decoded = Envelope::decode(&*buf).unwrap();
+    assert!(!decoded.payload.as_ref().unwrap().is_decoded());
+    assert!(decoded.attachments.iter().all(|lazy| !lazy.is_decoded()));
+    assert_eq!(decoded, envelope);
+    assert_eq!(decoded.encode_to_vec(), buf);
+
+    let next = decoded.next.take().unwrap().into_inner().unwrap();
+    assert_eq!(next.id, 8);
+    assert_eq!(decoded.attachments[1].decode().unwrap(), payload("c"));
+    assert!(!decoded.attachments[1].is_decoded());
+
+    decoded.clear();
+    assert_eq!(decoded, Envelope::default());
+}
+
+#[test]
+fn test_lazy_unchanged() {
+    // The fields of the payload are encoded out of order, so re-encoding the payload after it has
+    // been decoded changes its bytes.
+    let mut payload_buf = Vec::new();
+    prost::encoding::int32::encode(2, &5, &mut payload_buf);
+    prost::encoding::string::encode(1, &"x".to_string(), &mut payload_buf);
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(2, &payload_buf, &mut buf);
+
+    let mut envelope = Envelope::decode(&*buf).unwrap();
+    assert_eq!(
+        envelope.payload.as_ref().unwrap().encoded(),
+        Some(&payload_buf[..])
+    );
+    assert_eq!(envelope.encode_to_vec(), buf);
+
+    let payload = envelope.payload.as_mut().unwrap().get_mut().unwrap();
+    assert_eq!(payload.values, [5]);
+    payload.data.push('y');
+    assert_eq!(
+        envelope.encode_to_vec(),
+        EagerEnvelope {
+            payload: Some(Payload {
+                data: "xy".to_string(),
+                values: vec![5],
+            }),
+            ..EagerEnvelope::default()
+        }
+        .encode_to_vec()
+    );
+}
+
+#[test]
+fn test_lazy_merge() {
+    // Merged lazy fields are decoded as if the messages had been merged eagerly.
+    let first = EagerEnvelope {
+        payload: Some(payload("a")),
+        ..EagerEnvelope::default()
+    };
+    let second = EagerEnvelope {
+        payload: Some(payload("b")),
+        ..EagerEnvelope::default()
+    };
+    let mut buf = first.encode_to_vec();
+    buf.extend(second.encode_to_vec());
+
+    let expected = EagerEnvelope::decode(&*buf).unwrap().payload.unwrap();
+    let mut envelope = Envelope::decode(&*buf).unwrap();
+    assert_eq!(
+        envelope.payload.as_ref().unwrap().decode().unwrap(),
+        expected
+    );
+
+    // Once decoded, fields are merged into the decoded message.
+    envelope.payload.as_mut().unwrap().get_mut().unwrap();
+    envelope.merge(&*first.encode_to_vec()).unwrap();
+    let payload = envelope.payload.unwrap().into_inner().unwrap();
+    assert_eq!(payload.data, "a");
+    assert_eq!(payload.values, [1, 2, 3, 1, 2, 3, 1, 2, 3]);
+}
+
+#[test]
+fn test_lazy_decode_error() {
+    // Errors in lazy fields are reported when the field is accessed.
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(2, &vec![0x0A, 0x05], &mut buf);
+
+    let mut envelope = Envelope::decode(&*buf).unwrap();
+    let lazy = envelope.payload.as_mut().unwrap();
+    assert!(lazy.get_mut().is_err());
+    assert!(!lazy.is_decoded());
+    assert_eq!(envelope.encode_to_vec(), buf);
+
+    assert!(EagerEnvelope::decode(&*buf).is_err());
+
+    // Fields which haven't been decoded are formatted as their encoded bytes.
+    let lazy = envelope.payload.as_ref().unwrap();
+    assert_eq!(format!("{:?}", lazy), "Lazy([10, 5])");
+    assert!(!lazy.is_decoded());
+}
+
+#[test]
+fn test_lazy_eq() {
+    let mut reordered = Vec::new();
+    prost::encoding::int32::encode(2, &5, &mut reordered);
+    prost::encoding::string::encode(1, &"x".to_string(), &mut reordered);
+    let decoded = Payload {
+        data: "x".to_string(),
+        values: vec![5],
+    };
+    let lazy = |buf: &[u8]| {
+        let mut envelope_buf = Vec::new();
+        prost::encoding::bytes::encode(2, &buf.to_vec(), &mut envelope_buf);
+        Envelope::decode(&*envelope_buf).unwrap().payload.unwrap()
+    };
+
+    // Fields are compared by their messages, whether or not they have been decoded.
+    assert_eq!(lazy(&reordered), lazy(&reordered));
+    assert_eq!(lazy(&reordered), lazy(&decoded.encode_to_vec()));
+    assert_eq!(lazy(&reordered), Lazy::new(decoded.clone()));
+    assert_eq!(Lazy::new(decoded.clone()), lazy(&decoded.encode_to_vec()));
+    assert_ne!(lazy(&reordered), lazy(&[0x10, 0x06]));
+
+    // Identical encoded bytes are equal without being decoded, so fields which fail to decode are
+    // only equal to the same bytes.
+    assert_eq!(lazy(&[0x0A, 0x05]), lazy(&[0x0A, 0x05]));
+    assert_ne!(lazy(&[0x0A, 0x05]), lazy(&[0x0A, 0x06]));
+    assert_ne!(Lazy::new(decoded), lazy(&[0x0A, 0x05]));
+}
+
+#[test]
+fn test_lazy_diff() {
+    use prost::diff::Value;
+    use prost_types::diff::{diff, Change};
+
+    let envelope = Envelope {
+        id: 7,
+        payload: Some(Lazy::new(payload("a"))),
+        ..Envelope::default()
+    };
+    let buf = envelope.encode_to_vec();
+    let old = Envelope::decode(&*buf).unwrap();
+
+    // Lazy fields are compared by their messages, which are decoded if they differ.
+    let mut new = Envelope::decode(&*buf).unwrap();
+    assert!(diff(&old, &new).is_empty());
+    let new_payload = new.payload.as_mut().unwrap().get_mut().unwrap();
+    new_payload.values.push(4);
+    new.attachments.push(Lazy::new(payload("b")));
+    let diff = diff(&old, &new);
+    assert_eq!(diff.to_string(), "+ payload.values[3]\n+ attachments\n");
+    assert!(!old.payload.as_ref().unwrap().is_decoded());
+
+    // Changed values hold the decoded messages.
+    assert_eq!(
+        diff.changes[1].change,
+        Change::Added(Value::List(vec![Value::message(&payload("b"))]))
+    );
+}
@@ -33,16 +33,50 @@ pub mod unittest;
 #[cfg(test)]
 mod bootstrap;
 #[cfg(test)]
+mod borrowed;
+#[cfg(test)]
+mod conversion;
+#[cfg(test)]
+mod custom_types;
+#[cfg(test)]
 mod debug;
 #[cfg(test)]
+mod decode_options;
+#[cfg(test)]
 mod deprecated_field;
+#[cfg(all(test, feature = "std"))]
+mod deterministic;
+#[cfg(test)]
+mod diff;
+#[cfg(test)]
+mod dynamic;
+#[cfg(test)]
+mod extensions;
+#[cfg(test)]
+mod field_mask;
+#[cfg(test)]
+mod fixtures;
 #[cfg(test)]
 mod generic_derive;
 #[cfg(test)]
+mod json;
+#[cfg(test)]
+mod lazy;
+#[cfg(test)]
 mod message_encoding;
 #[cfg(test)]
 mod no_unused_results;
 #[cfg(test)]
+mod projection;
+#[cfg(test)]
+mod reflect;
+#[cfg(test)]
+mod text_format;
+#[cfg(test)]
+mod type_registry;
+#[cfg(test)]
+mod unknown_fields;
+#[cfg(test)]
 mod well_known_types;
 
 pub mod foo {
@@ -44,3 +44,12 @@ fn test() {
     widget_factory.gizmo_inner = Some(gizmo::gizmo::Inner {});
     assert_eq!(14, widget_factory.encoded_len());
 }
+
+#[test]
+fn test_names() {
+    use prost::Name;
+
+    assert_eq!(Root::PACKAGE, "");
+    assert_eq!(Root::full_name(), "Root");
+    assert_eq!(root::Inner::type_url(), "type.googleapis.com/Root.Inner");
+}
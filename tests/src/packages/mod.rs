@@ -50,3 +50,20 @@ fn test() {
     widget_factory.gizmo_inner = Some(gizmo::gizmo::Inner {});
     assert_eq!(14, widget_factory.encoded_len());
 }
+
+#[test]
+fn test_names() {
+    use prost::Name;
+
+    assert_eq!(Root::NAME, "Root");
+    assert_eq!(Root::PACKAGE, "packages");
+    assert_eq!(root::Inner::full_name(), "packages.Root.Inner");
+    assert_eq!(
+        widget::factory::widget_factory::Inner::full_name(),
+        "packages.widget.factory.WidgetFactory.Inner"
+    );
+    assert_eq!(
+        gizmo::Gizmo::type_url(),
+        "type.googleapis.com/packages.gizmo.Gizmo"
+    );
+}
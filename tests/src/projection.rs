@@ -0,0 +1,112 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::projection::Projection;
+use prost::{Message, ReflectMessage};
+use prost_types::FieldMask;
+
+use crate::fixtures::projection::Person;
+use crate::fixtures::{address, person};
+
+#[test]
+fn test_projection_numbers() {
+    let buf = person().encode_to_vec();
+
+    let mut projection = Projection::new();
+    projection
+        .select(&[1])
+        .select(&[2, 2])
+        .select(&[3, 1])
+        .select(&[6, 1]);
+    let projected = Person::decode_projected(&*buf, &projection).unwrap();
+    assert_eq!(
+        projected,
+        Person {
+            name: "Ada".to_string(),
+            address: Some(address("", "London")),
+            previous: vec![address("2 High St", ""), address("3 Low St", "")],
+            manager: Some(Box::new(Person {
+                name: "Charles".to_string(),
+                ..Person::default()
+            })),
+            ..Person::default()
+        }
+    );
+
+    // Map and oneof fields are decoded in full, even with a nested projection.
+    let mut projection = Projection::new();
+    projection.select(&[4, 1]).select(&[8, 2]);
+    let projected = Person::decode_projected(&*buf, &projection).unwrap();
+    assert_eq!(projected.contacts, person().contacts);
+    assert_eq!(projected.contact, person().contact);
+    assert_eq!(projected.name, "");
+
+    // An empty projection skips every field.
+    assert_eq!(
+        Person::decode_projected(&*buf, &Projection::new()).unwrap(),
+        Person::default()
+    );
+}
+
+#[test]
+fn test_projection_paths() {
+    let buf = person().encode_to_vec();
+    let desc = Person::type_descriptor();
+
+    let mask = FieldMask {
+        paths: vec![
+            "address.city".to_string(),
+            "scores".to_string(),
+            "manager.scores".to_string(),
+        ],
+    };
+    let projection = mask.projection(desc).unwrap();
+    assert_eq!(
+        projection,
+        Projection::from_paths(desc, ["address.city", "scores", "manager.scores"]).unwrap()
+    );
+
+    let mut projected = Person::default();
+    projected.merge_projected(&*buf, &projection).unwrap();
+    assert_eq!(
+        projected,
+        Person {
+            address: Some(address("", "London")),
+            scores: vec![1, 2, 3],
+            manager: Some(Box::new(Person {
+                scores: vec![4],
+                ..Person::default()
+            })),
+            ..Person::default()
+        }
+    );
+
+    // Paths must name fields, and may only continue into message fields which aren't maps.
+    for path in ["nickname", "name.first", "contacts.street", "address."] {
+        let error = Projection::from_paths(desc, [path]).unwrap_err();
+        assert_eq!(error.path(), path);
+    }
+}
+
+#[test]
+fn test_projection_errors() {
+    // Fields which aren't selected are skipped without being decoded, but must still be
+    // well-formed.
+    let mut buf = person().encode_to_vec();
+    let mut projection = Projection::new();
+    projection.select(&[1]);
+    buf.truncate(buf.len() - 1);
+    assert!(Person::decode_projected(&*buf, &projection).is_err());
+
+    // Invalid values of fields which aren't selected are not detected.
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(1, &vec![0xFF], &mut buf);
+    prost::encoding::int32::encode(5, &7, &mut buf);
+    assert!(Person::decode(&*buf).is_err());
+    let mut projection = Projection::new();
+    projection.select(&[5]);
+    let projected = Person::decode_projected(&*buf, &projection).unwrap();
+    assert_eq!(projected.scores, [7]);
+}
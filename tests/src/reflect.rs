@@ -0,0 +1,372 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::reflect::{Cardinality, Kind, ReflectError, Value, ValueRef};
+use prost::ReflectMessage;
+
+#[allow(clippy::wrong_self_convention)]
+mod reflect {
+    include!(concat!(env!("OUT_DIR"), "/reflect.rs"));
+}
+
+use self::reflect::{account, Account, Profile, Role};
+
+fn account() -> Account {
+    Account {
+        id: 7,
+        display_name: "Ada".to_string(),
+        email: Some("ada@example.com".to_string()),
+        password_hash: vec![0xAB, 0xCD],
+        role: Role::Admin as i32,
+        groups: vec!["eng".to_string(), "ops".to_string()],
+        quotas: vec![("cpu".to_string(), 4), ("disk".to_string(), 100)]
+            .into_iter()
+            .collect(),
+        profile: Some(Profile {
+            name: "ada".to_string(),
+            age: 36,
+        }),
+        previous_profiles: Vec::new(),
+        created: Some(prost_types::Timestamp {
+            seconds: 1_650_000_000,
+            nanos: 0,
+        }),
+        delegate: None,
+        credential: Some(account::Credential::ApiKey("secret".to_string())),
+    }
+}
+
+#[test]
+fn descriptor() {
+    let account = Account::default();
+    let descriptor = account.descriptor();
+    assert_eq!(descriptor.full_name(), "reflect.Account");
+    assert_eq!(descriptor.name(), "Account");
+
+    let numbers = descriptor.fields().map(|f| f.number()).collect::<Vec<_>>();
+    assert_eq!(numbers, (1..=13).collect::<Vec<_>>());
+
+    let display_name = descriptor.field(2).unwrap();
+    assert_eq!(display_name.name(), "displayName");
+    assert_eq!(display_name.kind(), Kind::String);
+    assert_eq!(display_name.cardinality(), Cardinality::Singular);
+
+    let email = descriptor.field_by_name("email").unwrap();
+    assert_eq!(email.number(), 3);
+    assert_eq!(email.cardinality(), Cardinality::Optional);
+
+    let quotas = descriptor.field_by_name("quotas").unwrap();
+    assert!(quotas.is_map());
+    assert!(!quotas.is_list());
+    assert_eq!(quotas.kind(), Kind::Map(&Kind::String, &Kind::Int32));
+
+    let groups = descriptor.field(6).unwrap();
+    assert!(groups.is_list());
+    assert_eq!(groups.cardinality(), Cardinality::Repeated);
+
+    assert_eq!(descriptor.field(5).unwrap().kind(), Kind::Enum);
+    assert_eq!(descriptor.field(10).unwrap().kind(), Kind::Message);
+    assert!(descriptor.field(14).is_none());
+
+    let oneof = descriptor.oneof_of(13).unwrap();
+    assert_eq!(oneof.name(), "credential");
+    let names = oneof.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+    assert_eq!(names, ["api_key", "service_profile"]);
+    assert!(descriptor.oneof_of(1).is_none());
+}
+
+#[test]
+fn get_field() {
+    let account = account();
+    assert_eq!(account.get_field(1), Some(Value::U64(7)));
+    assert_eq!(
+        account.get_field_by_name("displayName"),
+        Some(Value::String("Ada".to_string()))
+    );
+    assert_eq!(account.get_field(4), Some(Value::Bytes(vec![0xAB, 0xCD])));
+    assert_eq!(account.get_field(5), Some(Value::EnumNumber(1)));
+    assert_eq!(
+        account.get_field(6),
+        Some(Value::List(vec![
+            Value::String("eng".to_string()),
+            Value::String("ops".to_string()),
+        ]))
+    );
+    assert_eq!(
+        account.get_field(7),
+        Some(Value::Map(vec![
+            (Value::String("cpu".to_string()), Value::I32(4)),
+            (Value::String("disk".to_string()), Value::I32(100)),
+        ]))
+    );
+
+    let profile = account.get_field(8).unwrap();
+    assert_eq!(profile.as_message::<Profile>(), account.profile.as_ref());
+    let created = account.get_field(10).unwrap();
+    assert_eq!(
+        created
+            .as_message::<prost_types::Timestamp>()
+            .unwrap()
+            .seconds,
+        1_650_000_000
+    );
+
+    // Unset fields with presence, and unknown fields, have no value.
+    assert_eq!(account.get_field(11), None);
+    assert_eq!(account.get_field(13), None);
+    assert_eq!(account.get_field(99), None);
+    assert_eq!(
+        account.get_field(12),
+        Some(Value::String("secret".to_string()))
+    );
+
+    // Fields without presence always have a value.
+    let empty = Account::default();
+    assert_eq!(empty.get_field(1), Some(Value::U64(0)));
+    assert_eq!(empty.get_field(3), None);
+    assert!(!empty.has_field(1));
+    assert!(account.has_field(1));
+    assert!(account.has_field(7));
+    assert!(account.has_field(12));
+    assert!(!account.has_field(13));
+}
+
+#[test]
+fn get_field_ref() {
+    let account = account();
+    assert_eq!(account.get_field_ref(2), Some(ValueRef::String("Ada")));
+    assert_eq!(
+        account.get_field_ref(4),
+        Some(ValueRef::Bytes(&[0xAB, 0xCD]))
+    );
+    match account.get_field_ref(8) {
+        Some(ValueRef::Message(profile)) => {
+            assert_eq!(profile.downcast_ref(), account.profile.as_ref())
+        }
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    // Borrowed values match the values returned by `get_field`.
+    for number in 1..=14 {
+        assert_eq!(
+            account.get_field_ref(number).map(|value| value.to_value()),
+            account.get_field(number)
+        );
+    }
+    assert_eq!(
+        "wrapped".to_string().get_field_ref(1),
+        Some(ValueRef::String("wrapped"))
+    );
+    assert_eq!(().get_field_ref(1), None);
+}
+
+#[test]
+fn get_message_mut() {
+    let mut account = account();
+    account
+        .get_message_mut(8)
+        .unwrap()
+        .set_field(1, Value::String("lovelace".to_string()))
+        .unwrap();
+    assert_eq!(account.profile.as_ref().unwrap().name, "lovelace");
+
+    // Unset message fields, including boxed fields, are set to an empty message.
+    let delegate = account.get_message_mut(11).unwrap();
+    assert_eq!(delegate.get_field(1), Some(Value::U64(0)));
+    delegate.set_field(1, Value::U64(8)).unwrap();
+    assert_eq!(account.delegate.as_ref().unwrap().id, 8);
+
+    // Accessing a oneof message variant replaces the set variant.
+    assert!(account.get_message_mut(13).is_some());
+    assert_eq!(
+        account.credential,
+        Some(account::Credential::ServiceProfile(Profile::default()))
+    );
+
+    // Scalar, repeated and unknown fields have no message to return.
+    assert!(account.get_message_mut(12).is_none());
+    assert!(account.get_message_mut(2).is_none());
+    assert!(account.get_message_mut(9).is_none());
+    assert!(account.get_message_mut(100).is_none());
+    assert_eq!(
+        account.credential,
+        Some(account::Credential::ServiceProfile(Profile::default()))
+    );
+    assert!("wrapped".to_string().get_message_mut(1).is_none());
+}
+
+#[test]
+fn set_field() {
+    let mut account = Account::default();
+    account.set_field(1, Value::U64(42)).unwrap();
+    account
+        .set_field_by_name("displayName", Value::String("Grace".to_string()))
+        .unwrap();
+    account
+        .set_field(3, Value::String("grace@example.com".to_string()))
+        .unwrap();
+    account.set_field(4, Value::Bytes(vec![1, 2, 3])).unwrap();
+    account.set_field(5, Value::EnumNumber(1)).unwrap();
+    account
+        .set_field(6, Value::List(vec![Value::String("eng".to_string())]))
+        .unwrap();
+    account
+        .set_field(
+            7,
+            Value::Map(vec![(Value::String("cpu".to_string()), Value::I32(8))]),
+        )
+        .unwrap();
+    let profile = Profile {
+        name: "grace".to_string(),
+        age: 85,
+    };
+    account.set_field(8, Value::message(&profile)).unwrap();
+    account
+        .set_field(9, Value::List(vec![Value::message(&profile)]))
+        .unwrap();
+    account
+        .set_field(11, Value::message(&account.clone()))
+        .unwrap();
+    account.set_field(13, Value::message(&profile)).unwrap();
+
+    assert_eq!(account.id, 42);
+    assert_eq!(account.display_name, "Grace");
+    assert_eq!(account.email.as_deref(), Some("grace@example.com"));
+    assert_eq!(account.password_hash, [1, 2, 3]);
+    assert_eq!(account.role(), Role::Admin);
+    assert_eq!(account.groups, ["eng"]);
+    assert_eq!(account.quotas["cpu"], 8);
+    assert_eq!(account.profile.as_ref(), Some(&profile));
+    assert_eq!(account.previous_profiles.len(), 1);
+    assert_eq!(account.previous_profiles[0], profile);
+    assert_eq!(account.delegate.as_ref().unwrap().id, 42);
+    assert_eq!(
+        account.credential,
+        Some(account::Credential::ServiceProfile(profile))
+    );
+}
+
+#[test]
+fn set_field_errors() {
+    let mut account = account();
+    let expected = account.clone();
+
+    assert_eq!(
+        account.set_field(1, Value::I32(1)),
+        Err(ReflectError::TypeMismatch {
+            field: "id",
+            expected: Kind::Uint64,
+        })
+    );
+    assert!(account
+        .set_field(6, Value::String("eng".to_string()))
+        .is_err());
+    assert!(account
+        .set_field(6, Value::List(vec![Value::Bool(true)]))
+        .is_err());
+    assert!(account
+        .set_field(8, Value::message(&prost_types::Timestamp::default()))
+        .is_err());
+    assert!(account.set_field(12, Value::U64(1)).is_err());
+    assert_eq!(
+        account.set_field(99, Value::U64(1)),
+        Err(ReflectError::UnknownField(99))
+    );
+    assert_eq!(
+        account.set_field_by_name("nope", Value::U64(1)),
+        Err(ReflectError::UnknownFieldName)
+    );
+    assert_eq!(account, expected);
+}
+
+#[test]
+fn clear_field() {
+    let mut account = account();
+    assert!(account.clear_field(2));
+    assert!(account.clear_field(3));
+    assert!(account.clear_field(7));
+    assert!(account.clear_field(8));
+    assert!(!account.clear_field(99));
+    assert_eq!(account.display_name, "");
+    assert_eq!(account.email, None);
+    assert!(account.quotas.is_empty());
+    assert_eq!(account.profile, None);
+
+    // Clearing an unset oneof variant leaves the set variant in place.
+    assert!(account.clear_field(13));
+    assert!(account.credential.is_some());
+    assert!(account.clear_field(12));
+    assert_eq!(account.credential, None);
+}
+
+/// Blanks out every string field of a message, recursing into nested messages.
+fn redact(message: &mut dyn ReflectMessage) {
+    for field in message.descriptor().fields() {
+        let number = field.number();
+        match field.kind() {
+            Kind::String if !field.is_list() && message.has_field(number) => {
+                message
+                    .set_field(number, Value::String("<redacted>".to_string()))
+                    .unwrap();
+            }
+            Kind::Message if !field.is_list() => {
+                if let Some(Value::Message(mut nested)) = message.get_field(number) {
+                    redact(&mut *nested);
+                    message.set_field(number, Value::Message(nested)).unwrap();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn dynamic_access() {
+    let mut account = account();
+    account.delegate = Some(Box::new(account.clone()));
+    redact(&mut account);
+
+    assert_eq!(account.display_name, "<redacted>");
+    assert_eq!(account.email.as_deref(), Some("<redacted>"));
+    assert_eq!(account.profile.as_ref().unwrap().name, "<redacted>");
+    assert_eq!(
+        account.delegate.as_ref().unwrap().display_name,
+        "<redacted>"
+    );
+    assert_eq!(
+        account.credential,
+        Some(account::Credential::ApiKey("<redacted>".to_string()))
+    );
+
+    let boxed: Box<dyn ReflectMessage> = Box::new(account.clone());
+    assert_eq!(boxed.descriptor().full_name(), "reflect.Account");
+    assert!(boxed.downcast_ref::<Profile>().is_none());
+    let unboxed = boxed.downcast::<Account>().unwrap();
+    assert_eq!(*unboxed, account);
+
+    let names = account
+        .descriptor()
+        .fields()
+        .filter(|field| account.has_field(field.number()))
+        .map(|field| field.name())
+        .collect::<Vec<_>>();
+    let expected: Vec<String> = vec![
+        "id",
+        "displayName",
+        "email",
+        "password_hash",
+        "role",
+        "groups",
+        "quotas",
+        "profile",
+        "created",
+        "delegate",
+        "api_key",
+    ]
+    .into_iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(names, expected);
+}
@@ -0,0 +1,240 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{Message, ReflectMessage};
+use prost_types::text_format::{self, ParseOptions, PrintOptions};
+use prost_types::{Any, Timestamp};
+
+use crate::fixtures::json_proto::{scalars, Color, Scalars, WellKnown};
+use crate::fixtures::scalars;
+
+fn escaped() -> Scalars {
+    Scalars {
+        int64_value: i64::MIN,
+        string_value: "\u{1}é".to_string(),
+        bytes_value: vec![0xFB, b'\''],
+        ..Scalars::default()
+    }
+}
+
+#[test]
+fn print() {
+    assert_eq!(
+        text_format::to_string(&scalars()),
+        r#"int32_value: -5
+int64_value: -9007199254740993
+uint32_value: 7
+uint64_value: 18446744073709551615
+float_value: 1.5
+double_value: -inf
+bool_value: true
+string_value: "a \"quoted\"\n\001"
+bytes_value: "\373\377\001"
+color: COLOR_RED
+renamed: "x"
+optional_int32: 0
+int64_list: 1
+int64_list: -2
+colors: COLOR_GREEN
+colors: 42
+int_map {
+  key: -1
+  value: "neg"
+}
+int_map {
+  key: 2
+  value: "two"
+}
+bool_map {
+  key: true
+  value: COLOR_GREEN
+}
+text: ""
+"#
+    );
+    assert_eq!(text_format::to_string(&Scalars::default()), "");
+
+    // Non-ASCII characters are printed as is in strings, and single quotes are escaped in bytes.
+    assert_eq!(
+        text_format::to_string(&escaped()),
+        r#"int64_value: -9223372036854775808
+string_value: "\001é"
+bytes_value: "\373\'"
+"#
+    );
+
+    // Very large and very small magnitudes are written in exponent notation.
+    let message = Scalars {
+        float_value: 1e-10,
+        double_value: 1e300,
+        ..Scalars::default()
+    };
+    let text = text_format::to_string(&message);
+    assert_eq!(text, "float_value: 1e-10\ndouble_value: 1e300\n");
+    assert_eq!(text_format::from_str::<Scalars>(&text).unwrap(), message);
+    let message = Scalars {
+        double_value: f64::MIN_POSITIVE,
+        ..Scalars::default()
+    };
+    assert_eq!(
+        text_format::to_string(&message),
+        "double_value: 2.2250738585072014e-308\n"
+    );
+
+    let options = PrintOptions::new().single_line(true);
+    let message = Scalars {
+        int32_value: 1,
+        choice: Some(scalars::Choice::Nested(Box::new(Scalars {
+            colors: vec![Color::Green as i32],
+            ..Scalars::default()
+        }))),
+        ..Scalars::default()
+    };
+    assert_eq!(
+        text_format::to_string_with_options(&message, &options),
+        "int32_value: 1 nested { colors: COLOR_GREEN }"
+    );
+}
+
+#[test]
+fn parse() {
+    let text = text_format::to_string(&scalars());
+    assert_eq!(text_format::from_str::<Scalars>(&text).unwrap(), scalars());
+    let options = PrintOptions::new().single_line(true);
+    let text = text_format::to_string_with_options(&scalars(), &options);
+    assert_eq!(text_format::from_str::<Scalars>(&text).unwrap(), scalars());
+    let text = text_format::to_string(&escaped());
+    assert_eq!(text_format::from_str::<Scalars>(&text).unwrap(), escaped());
+
+    // Comments, `<>` blocks, list syntax, separators, alternative number and bool forms, enum
+    // numbers and concatenated literals with escapes are all accepted.
+    let parsed = text_format::from_str::<Scalars>(
+        r#"
+        # A comment.
+        int32_value: 0x10, uint32_value: 010; float_value: 2f
+        double_value: -Infinity bool_value: t
+        string_value: 'a\x41' "\u00e9\101"
+        bytes_value: "\0\xff"
+        color: 2
+        int64_list: [1, -2] int64_list: 3
+        colors: []
+        int_map <key: 1>
+        bool_map: [{ value: COLOR_RED }, { key: true value: COLOR_GREEN }]
+        nested < text: "" >
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed,
+        Scalars {
+            int32_value: 16,
+            uint32_value: 8,
+            float_value: 2.0,
+            double_value: f64::NEG_INFINITY,
+            bool_value: true,
+            string_value: "aAéA".to_string(),
+            bytes_value: vec![0, 0xFF],
+            color: Color::Green as i32,
+            int64_list: vec![1, -2, 3],
+            int_map: vec![(1, String::new())].into_iter().collect(),
+            bool_map: vec![(false, Color::Red as i32), (true, Color::Green as i32)]
+                .into_iter()
+                .collect(),
+            choice: Some(scalars::Choice::Nested(Box::new(Scalars {
+                choice: Some(scalars::Choice::Text(String::new())),
+                ..Scalars::default()
+            }))),
+            ..Scalars::default()
+        }
+    );
+
+    let parsed = text_format::from_str::<Scalars>("double_value: nan").unwrap();
+    assert!(parsed.double_value.is_nan());
+}
+
+#[test]
+fn any() {
+    let message = WellKnown {
+        timestamp: Some(Timestamp {
+            seconds: 1,
+            nanos: 0,
+        }),
+        ..WellKnown::default()
+    };
+    let any = Any {
+        type_url: "type.googleapis.com/json.WellKnown".to_string(),
+        value: message.encode_to_vec(),
+    };
+
+    // Without the type registered, the Any is printed as its fields.
+    assert_eq!(
+        text_format::to_string(&any),
+        "type_url: \"type.googleapis.com/json.WellKnown\"\nvalue: \"\\n\\002\\010\\001\"\n"
+    );
+
+    let options = PrintOptions::new()
+        .single_line(true)
+        .message_type(WellKnown::type_descriptor());
+    let text = text_format::to_string_with_options(&any, &options);
+    assert_eq!(
+        text,
+        "[type.googleapis.com/json.WellKnown] { timestamp { seconds: 1 } }"
+    );
+
+    let options = ParseOptions::new().message_type(WellKnown::type_descriptor());
+    assert_eq!(
+        text_format::from_str_with_options::<Any>(&text, &options).unwrap(),
+        any
+    );
+    assert!(text_format::from_str::<Any>(&text).is_err());
+}
+
+#[test]
+fn parse_errors() {
+    let invalid: Vec<&str> = vec![
+        "int32_value: 2147483648",
+        "int32_value: -2147483649",
+        "int32_value: 1.5",
+        "int64_value: 9223372036854775808",
+        "uint32_value: -1",
+        "uint64_value: 18446744073709551616",
+        "int32_value 1",
+        "int32_value: 1 int32_value: 2",
+        "text: \"a\" nested {}",
+        "color: COLOR_PURPLE",
+        "bool_value: yes",
+        "string_value: \"\\xff\"",
+        "string_value: \"unterminated",
+        "string_value: \"\\q\"",
+        "bytes_value: \"\\400\"",
+        "nested { int32_value: 1",
+        "nested { int32_value: 1 >",
+        "int_map { key: 1 key: 2 }",
+        "int_map { other: 1 }",
+        "[ext.field]: 1",
+        "int32_value: 1 }",
+        "@",
+    ];
+    for input in invalid {
+        assert!(
+            text_format::from_str::<Scalars>(input).is_err(),
+            "parsed {}",
+            input
+        );
+    }
+
+    let error = text_format::from_str::<Scalars>("int32_value: 1\n  unknown: 2").unwrap_err();
+    assert_eq!((error.line(), error.column()), (2, 3));
+    assert_eq!(
+        error.to_string(),
+        "invalid text format at 2:3: unknown field unknown in json.Scalars"
+    );
+
+    let mut nested = String::new();
+    for _ in 0..200 {
+        nested.push_str("nested {");
+    }
+    assert!(text_format::from_str::<Scalars>(&nested).is_err());
+}
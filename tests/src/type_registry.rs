@@ -0,0 +1,60 @@
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost_types::{Any, AnyError, Duration, TypeRegistry};
+
+mod type_registry {
+    include!(concat!(env!("OUT_DIR"), "/type_registry.rs"));
+}
+
+use self::type_registry::*;
+
+fn registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::new();
+    register_types(&mut registry);
+    registry
+}
+
+#[test]
+fn test_register_types() {
+    assert_eq!(
+        registry().names().collect::<Vec<_>>(),
+        [
+            "type_registry.Event",
+            "type_registry.Event.Payload",
+            "type_registry.Log"
+        ]
+    );
+}
+
+#[test]
+fn test_decode_any() {
+    let payload = event::Payload {
+        data: "data".to_string(),
+    };
+    let event = Event {
+        name: "event".to_string(),
+        payload: Some(payload.clone()),
+    };
+    let log = Log {
+        entries: vec![Any::from_msg(&event), Any::from_msg(&payload)],
+    };
+
+    let registry = registry();
+    let decoded = log
+        .entries
+        .iter()
+        .map(|entry| format!("{:?}", registry.decode(entry).unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, [format!("{:?}", event), format!("{:?}", payload)]);
+    crate::check_message(&log);
+
+    let any = Any::from_msg(&Duration::default());
+    assert!(!registry.contains(any.type_name()));
+    assert_eq!(
+        registry.decode(&any).unwrap_err(),
+        AnyError::UnknownType("type.googleapis.com/google.protobuf.Duration".to_string())
+    );
+}
@@ -0,0 +1,86 @@
+use alloc::string::ToString;
+use alloc::vec;
+
+use prost::{Message, UnknownFieldValue};
+
+use crate::check_message;
+
+mod unknown_fields {
+    include!(concat!(env!("OUT_DIR"), "/unknown_fields.rs"));
+}
+
+use self::unknown_fields::{new, old, New, Old};
+
+fn new_message() -> New {
+    New {
+        id: 42,
+        nested: Some(new::NewNested {
+            name: "nested".to_string(),
+            flag: true,
+        }),
+        label: "label".to_string(),
+        timestamp: 1_234_567_890,
+        samples: vec![-1, 0, 1],
+        ratio: 0.5,
+    }
+}
+
+#[test]
+fn test_unknown_fields_roundtrip() {
+    let new = new_message();
+    let old = Old::decode(&*new.encode_to_vec()).unwrap();
+
+    assert_eq!(old.id, 42);
+    assert_eq!(old._unknown_fields.len(), 4);
+    assert_eq!(
+        old.nested.as_ref().unwrap()._unknown_fields.len(),
+        1,
+        "nested messages preserve unknown fields"
+    );
+
+    let reencoded = old.encode_to_vec();
+    assert_eq!(reencoded.len(), old.encoded_len());
+    assert_eq!(New::decode(&*reencoded).unwrap(), new);
+}
+
+#[test]
+fn test_unknown_fields_contents() {
+    let old = Old::decode(&*new_message().encode_to_vec()).unwrap();
+    let fields = old
+        ._unknown_fields
+        .iter()
+        .map(|field| (field.tag(), field.value().clone()))
+        .collect::<alloc::vec::Vec<_>>();
+    assert_eq!(
+        fields,
+        vec![
+            (3, UnknownFieldValue::LengthDelimited(b"label".to_vec())),
+            (4, UnknownFieldValue::SixtyFourBit(1_234_567_890)),
+            (
+                5,
+                UnknownFieldValue::LengthDelimited(vec![0x01, 0x00, 0x02])
+            ),
+            (6, UnknownFieldValue::ThirtyTwoBit(0.5f32.to_bits())),
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_fields_clear() {
+    let mut old = Old::decode(&*new_message().encode_to_vec()).unwrap();
+    old.clear();
+    assert!(old._unknown_fields.is_empty());
+    assert_eq!(old, Old::default());
+}
+
+#[test]
+fn test_unknown_fields_known_only() {
+    check_message(&Old {
+        id: 7,
+        nested: Some(old::Nested {
+            name: "foo".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+}
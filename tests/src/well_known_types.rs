@@ -22,6 +22,42 @@ fn test_well_known_types() {
     crate::check_message(&msg);
 }
 
+#[test]
+fn test_any() {
+    use prost::Name;
+    use prost_types::{Any, AnyError, Duration};
+
+    let msg = Foo {
+        string: Some("value".into()),
+        ..Default::default()
+    };
+    let any = Any::from_msg(&msg);
+    assert_eq!(any.type_url, "type.googleapis.com/well_known_types.Foo");
+    assert_eq!(any.type_name(), "well_known_types.Foo");
+    assert_eq!(any.to_msg::<Foo>(), Ok(msg.clone()));
+
+    // Only the type name is compared.
+    let any = Any {
+        type_url: "example.com/types/well_known_types.Foo".into(),
+        ..any
+    };
+    assert_eq!(any.to_msg::<Foo>(), Ok(msg));
+
+    assert_eq!(
+        any.to_msg::<Duration>(),
+        Err(AnyError::TypeMismatch {
+            expected: "google.protobuf.Duration".into(),
+            actual: "example.com/types/well_known_types.Foo".into(),
+        })
+    );
+
+    let any = Any {
+        type_url: Foo::type_url(),
+        value: b"\x0A".to_vec(),
+    };
+    assert!(matches!(any.to_msg::<Foo>(), Err(AnyError::Decode(_))));
+}
+
 #[cfg(feature = "std")]
 #[test]
 fn test_timestamp() {